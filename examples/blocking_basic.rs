@@ -118,6 +118,9 @@ fn main() -> Result<()> {
         shared_memory_name: None,
         message_queue_name: None,
         internal_latency_file: None,
+
+        // Remaining options (e.g. subcommands) keep their defaults
+        ..Default::default()
     };
 
     println!("Configuration:");
@@ -296,6 +296,7 @@ fn create_args(
         shared_memory_name: None,
         message_queue_name: None,
         internal_latency_file: None,
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,400 @@
+//! Verification that pinned threads stayed on their cores.
+//!
+//! `--client-affinity` and `--server-affinity` can fail without much noise:
+//! a cgroup cpuset that excludes the core rejects the request with a logged
+//! error, and threads created before the pin, such as a runtime's workers,
+//! are not pinned at all. Results then carry the affinity that was asked
+//! for, not the one in effect.
+//!
+//! While a single-client test runs, an [`AffinityMonitor`] reads the CPU
+//! each pinned task last ran on (field 39 of `/proc/<pid>/task/<tid>/stat`)
+//! every [`SAMPLE_INTERVAL`]: the client thread that pinned itself, and
+//! every thread of the server process. Samples off the assigned CPU and
+//! changes of CPU between samples are counted per side, along with the
+//! `Cpus_allowed_list` in effect. Migrations are those seen between
+//! samples, so they are a lower bound.
+//!
+//! Linux only; remote servers are not checked.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often pinned tasks are sampled
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Which side of the test a pinned task belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AffinityRole {
+    Client,
+    Server,
+}
+
+impl fmt::Display for AffinityRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AffinityRole::Client => write!(f, "client"),
+            AffinityRole::Server => write!(f, "server"),
+        }
+    }
+}
+
+/// Where one side's threads ran during a test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AffinityCheck {
+    /// Side that was pinned
+    pub role: AffinityRole,
+
+    /// CPU the side was pinned to
+    pub cpu: usize,
+
+    /// `Cpus_allowed_list` of the side's first thread when sampling began
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_cpus: Option<String>,
+
+    /// Threads sampled
+    pub threads: usize,
+
+    /// Samples taken, across all threads
+    pub samples: usize,
+
+    /// Samples that found a thread on another CPU
+    pub off_core_samples: usize,
+
+    /// Changes of CPU seen between consecutive samples of a thread
+    pub migrations: usize,
+
+    /// Every CPU a thread was seen on, ascending
+    pub cpus_seen: Vec<usize>,
+}
+
+impl AffinityCheck {
+    /// Whether every sample found the side on its CPU
+    pub fn held(&self) -> bool {
+        self.off_core_samples == 0
+    }
+}
+
+impl fmt::Display for AffinityCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pinned to CPU {}: ", self.role, self.cpu)?;
+        if self.held() {
+            write!(f, "held over {} samples", self.samples)?;
+        } else {
+            let cpus: Vec<String> = self.cpus_seen.iter().map(|c| c.to_string()).collect();
+            write!(
+                f,
+                "{} of {} samples off core (CPUs {})",
+                self.off_core_samples,
+                self.samples,
+                cpus.join(",")
+            )?;
+        }
+        write!(f, ", {} migrations", self.migrations)?;
+        if let Some(allowed) = &self.allowed_cpus {
+            write!(f, ", allowed CPUs {}", allowed)?;
+        }
+        Ok(())
+    }
+}
+
+/// The tasks of one pinned side
+#[derive(Debug, Clone, Copy)]
+pub enum Tasks {
+    /// A single thread of a process
+    Thread { pid: u32, tid: u32 },
+
+    /// Every thread of a process, re-listed at each sample
+    Process { pid: u32 },
+}
+
+/// A pinned side to watch
+#[derive(Debug, Clone, Copy)]
+pub struct AffinityTarget {
+    pub role: AffinityRole,
+    pub cpu: usize,
+    pub tasks: Tasks,
+}
+
+impl AffinityTarget {
+    /// The calling thread, pinned to the core at `core_index`
+    pub fn current_thread(role: AffinityRole, core_index: usize) -> Option<Self> {
+        Some(Self {
+            role,
+            cpu: cpu_for_core_index(core_index)?,
+            tasks: Tasks::Thread {
+                pid: std::process::id(),
+                tid: current_tid()?,
+            },
+        })
+    }
+
+    /// Every thread of process `pid`, pinned to the core at `core_index`
+    pub fn process(role: AffinityRole, pid: u32, core_index: usize) -> Option<Self> {
+        Some(Self {
+            role,
+            cpu: cpu_for_core_index(core_index)?,
+            tasks: Tasks::Process { pid },
+        })
+    }
+}
+
+/// Running tally of one target
+struct Tally {
+    target: AffinityTarget,
+    allowed_cpus: Option<String>,
+    last_cpu: Vec<(u32, usize)>,
+    samples: usize,
+    off_core_samples: usize,
+    migrations: usize,
+    cpus_seen: Vec<usize>,
+}
+
+impl Tally {
+    fn new(target: AffinityTarget) -> Self {
+        let allowed_cpus = task_ids(target.tasks)
+            .first()
+            .and_then(|&(pid, tid)| allowed_cpus(pid, tid));
+        Self {
+            target,
+            allowed_cpus,
+            last_cpu: Vec::new(),
+            samples: 0,
+            off_core_samples: 0,
+            migrations: 0,
+            cpus_seen: Vec::new(),
+        }
+    }
+
+    fn sample(&mut self) {
+        for (pid, tid) in task_ids(self.target.tasks) {
+            let Some(cpu) = last_cpu(pid, tid) else {
+                continue;
+            };
+            self.record(tid, cpu);
+        }
+    }
+
+    fn record(&mut self, tid: u32, cpu: usize) {
+        self.samples += 1;
+        if cpu != self.target.cpu {
+            self.off_core_samples += 1;
+        }
+        if let Err(index) = self.cpus_seen.binary_search(&cpu) {
+            self.cpus_seen.insert(index, cpu);
+        }
+        match self.last_cpu.iter_mut().find(|(t, _)| *t == tid) {
+            Some((_, last)) => {
+                if *last != cpu {
+                    self.migrations += 1;
+                    *last = cpu;
+                }
+            }
+            None => self.last_cpu.push((tid, cpu)),
+        }
+    }
+
+    fn finish(self) -> Option<AffinityCheck> {
+        (self.samples > 0).then_some(AffinityCheck {
+            role: self.target.role,
+            cpu: self.target.cpu,
+            allowed_cpus: self.allowed_cpus,
+            threads: self.last_cpu.len(),
+            samples: self.samples,
+            off_core_samples: self.off_core_samples,
+            migrations: self.migrations,
+            cpus_seen: self.cpus_seen,
+        })
+    }
+}
+
+/// Samples pinned tasks on a background thread.
+///
+/// Dropping the monitor without [`finish`](Self::finish) stops the thread
+/// and discards the samples.
+pub struct AffinityMonitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Vec<AffinityCheck>>>,
+}
+
+impl AffinityMonitor {
+    /// Start watching `targets`, if there are any and this is Linux.
+    pub fn start(targets: Vec<AffinityTarget>) -> Option<Self> {
+        if targets.is_empty() || !cfg!(target_os = "linux") {
+            return None;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            let mut tallies: Vec<Tally> = targets.into_iter().map(Tally::new).collect();
+            while !stop_flag.load(Ordering::Relaxed) {
+                for tally in &mut tallies {
+                    tally.sample();
+                }
+                std::thread::park_timeout(SAMPLE_INTERVAL);
+            }
+            tallies.into_iter().filter_map(Tally::finish).collect()
+        });
+        Some(Self {
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Watch a test's pinned sides: the calling thread, pinned to the core
+    /// at `client_core`, and the server process `pid` pinned to `core`.
+    pub fn for_test(client_core: Option<usize>, server: Option<(u32, usize)>) -> Option<Self> {
+        let client =
+            client_core.and_then(|core| AffinityTarget::current_thread(AffinityRole::Client, core));
+        let server =
+            server.and_then(|(pid, core)| AffinityTarget::process(AffinityRole::Server, pid, core));
+        Self::start(client.into_iter().chain(server).collect())
+    }
+
+    /// Stop sampling and report each side, warning about those that did not
+    /// stay on their CPU.
+    pub fn finish(mut self) -> Vec<AffinityCheck> {
+        let Some(handle) = self.handle.take() else {
+            return Vec::new();
+        };
+        self.stop.store(true, Ordering::Relaxed);
+        handle.thread().unpark();
+        let checks = handle.join().unwrap_or_default();
+        for check in checks.iter().filter(|check| !check.held()) {
+            tracing::warn!("Affinity not held: {}", check);
+        }
+        checks
+    }
+}
+
+impl Drop for AffinityMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = &self.handle {
+            handle.thread().unpark();
+        }
+    }
+}
+
+/// CPU number of the core at `index` in `core_affinity`'s list, the index
+/// the affinity options take
+fn cpu_for_core_index(index: usize) -> Option<usize> {
+    core_affinity::get_core_ids()?
+        .get(index)
+        .map(|core| core.id)
+}
+
+#[cfg(target_os = "linux")]
+fn current_tid() -> Option<u32> {
+    Some(unsafe { libc::syscall(libc::SYS_gettid) } as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_tid() -> Option<u32> {
+    None
+}
+
+fn task_ids(tasks: Tasks) -> Vec<(u32, u32)> {
+    match tasks {
+        Tasks::Thread { pid, tid } => vec![(pid, tid)],
+        Tasks::Process { pid } => std::fs::read_dir(format!("/proc/{}/task", pid))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                    .map(|tid| (pid, tid))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn last_cpu(pid: u32, tid: u32) -> Option<usize> {
+    parse_last_cpu(&std::fs::read_to_string(format!("/proc/{}/task/{}/stat", pid, tid)).ok()?)
+}
+
+fn allowed_cpus(pid: u32, tid: u32) -> Option<String> {
+    let status = std::fs::read_to_string(format!("/proc/{}/task/{}/status", pid, tid)).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .map(|list| list.trim().to_string())
+}
+
+/// The `processor` field (39) of a `stat` line: the CPU the task last ran on.
+fn parse_last_cpu(stat: &str) -> Option<usize> {
+    // The command may contain spaces; fields resume after its last ')'
+    let rest = stat.get(stat.rfind(')')? + 1..)?;
+    // Field 3 (state) is the first after the command
+    rest.split_whitespace().nth(36)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_last_cpu() {
+        let stat = "1234 (a b) S 1 1234 1234 0 -1 4194560 100 0 0 0 5 2 0 0 20 0 1 0 \
+                    4567 12345678 345 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 3 0 0 0 0 0";
+        assert_eq!(parse_last_cpu(stat), Some(3));
+        assert_eq!(parse_last_cpu("1234 (short) S 1"), None);
+    }
+
+    #[test]
+    fn test_tally_counts_off_core_and_migrations() {
+        let target = AffinityTarget {
+            role: AffinityRole::Server,
+            cpu: 2,
+            tasks: Tasks::Process { pid: 0 },
+        };
+        let mut tally = Tally {
+            target,
+            allowed_cpus: Some("0-3".to_string()),
+            last_cpu: Vec::new(),
+            samples: 0,
+            off_core_samples: 0,
+            migrations: 0,
+            cpus_seen: Vec::new(),
+        };
+        for (tid, cpu) in [(10, 2), (11, 0), (10, 2), (11, 2), (10, 3), (11, 2)] {
+            tally.record(tid, cpu);
+        }
+        let check = tally.finish().unwrap();
+        assert_eq!(check.threads, 2);
+        assert_eq!(check.samples, 6);
+        assert_eq!(check.off_core_samples, 2);
+        assert_eq!(check.migrations, 2);
+        assert_eq!(check.cpus_seen, vec![0, 2, 3]);
+        assert!(!check.held());
+        assert_eq!(
+            check.to_string(),
+            "server pinned to CPU 2: 2 of 6 samples off core (CPUs 0,2,3), 2 migrations, \
+             allowed CPUs 0-3"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_monitor_sees_this_thread() {
+        let target = AffinityTarget {
+            role: AffinityRole::Client,
+            cpu: usize::MAX,
+            tasks: Tasks::Thread {
+                pid: std::process::id(),
+                tid: current_tid().unwrap(),
+            },
+        };
+        let monitor = AffinityMonitor::start(vec![target]).unwrap();
+        std::thread::sleep(SAMPLE_INTERVAL * 3);
+        let checks = monitor.finish();
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].samples > 0);
+        // Never on a CPU that does not exist
+        assert_eq!(checks[0].off_core_samples, checks[0].samples);
+        assert!(checks[0].allowed_cpus.is_some());
+    }
+}
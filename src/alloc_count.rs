@@ -0,0 +1,132 @@
+//! Heap allocation counting for `--no-alloc-hot-path`.
+//!
+//! [`CountingAllocator`] wraps the system allocator and counts, per thread,
+//! the allocations and reallocations made. The benchmark binary installs it
+//! as its global allocator when built with the `alloc-count` feature; a
+//! send loop reads [`thread_allocations`] before and after the measured
+//! phase to learn how often it allocated.
+//!
+//! Counting is one thread-local increment per allocation, paid by every
+//! allocation of the process, which is why the feature is off by default.
+//! Builds and library users without the allocator get `None` from
+//! [`thread_allocations`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Set by the first counted allocation
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// The system allocator, counting allocations per thread
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn count() {
+        // Threads being torn down have no counter left; skip them
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Allocations the calling thread has made so far, or `None` when
+/// [`CountingAllocator`] is not the global allocator.
+pub fn thread_allocations() -> Option<u64> {
+    INSTALLED
+        .load(Ordering::Relaxed)
+        .then(|| ALLOCATIONS.with(Cell::get))
+}
+
+/// Allocations of the calling thread from just after the first message a
+/// send loop sends; the first send fills the payload pool and the
+/// transport's buffers.
+#[derive(Debug, Default)]
+pub struct HotPathAllocations {
+    start: Option<u64>,
+}
+
+impl HotPathAllocations {
+    /// Note a message sent; counting starts after the first.
+    pub fn sent(&mut self) {
+        if self.start.is_none() {
+            self.start = thread_allocations();
+        }
+    }
+
+    /// Allocations since the first message, or `None` when none was sent
+    /// or allocations are not counted.
+    pub fn finish(self) -> Option<u64> {
+        Some(thread_allocations()?.saturating_sub(self.start?))
+    }
+}
+
+/// Fail when the `mechanism` send loop that sent `sent` messages made any
+/// of the `allocations` counted after its first.
+pub fn check_hot_path(
+    mechanism: impl std::fmt::Display,
+    allocations: Option<u64>,
+    sent: usize,
+) -> anyhow::Result<()> {
+    match allocations {
+        Some(0) => Ok(()),
+        Some(count) => anyhow::bail!(
+            "--no-alloc-hot-path: the {} send loop made {} heap allocations after its first \
+             message ({:.2} per message)",
+            mechanism,
+            count,
+            count as f64 / sent.saturating_sub(1).max(1) as f64
+        ),
+        None if sent > 0 && thread_allocations().is_none() => anyhow::bail!(
+            "--no-alloc-hot-path needs the counting allocator ipc-benchmark installs when built \
+             with `--features alloc-count`"
+        ),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_hot_path() {
+        assert!(check_hot_path("UDS", Some(0), 100).is_ok());
+        let error = check_hot_path("UDS", Some(198), 100).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "--no-alloc-hot-path: the UDS send loop made 198 heap allocations after its first \
+             message (2.00 per message)"
+        );
+        // The library's tests run without the counting allocator
+        assert_eq!(thread_allocations(), None);
+        assert!(check_hot_path("UDS", None, 100).is_err());
+        assert!(check_hot_path("UDS", None, 0).is_ok());
+    }
+}
@@ -0,0 +1,846 @@
+//! # Streaming Output Analysis Module
+//!
+//! This module implements the `analyze` subcommand, which post-processes
+//! per-message streaming files (`--streaming-output-csv` or
+//! `--streaming-output-json`) produced by earlier benchmark runs. It lets
+//! users recompute statistics with different settings without re-running
+//! the benchmark.
+//!
+//! ## Analysis Products
+//!
+//! For every (mechanism, message size, latency type) series found in the file:
+//! - **Percentiles**: Recomputed with the same HDR histogram configuration
+//!   used during live runs, so numbers are directly comparable, each with a
+//!   bootstrap confidence interval from every sample in the file
+//! - **Time-windowed series**: Per-window count, mean, P50, P99 and max,
+//!   useful for spotting drift or periodic interference
+//! - **Outliers**: The slowest messages above a configurable percentile,
+//!   with their message IDs and timestamps for correlation with other data
+//! - **Heatmap** (`--heatmap`): A latency histogram per window, for plots
+//!   with time on X, latency on Y and count as intensity. A single
+//!   aggregate histogram hides when the tail happened.
+//!
+//! ## Input Formats
+//!
+//! Both streaming formats are accepted. The format is detected from the
+//! file contents rather than the extension. JSON files from interrupted
+//! runs (missing the closing brackets) are tolerated.
+//!
+//! CSV files written with a `--csv-columns` subset need only `timestamp_ns`
+//! and one of the latency columns. Without `mechanism` or `message_size`
+//! every row belongs to one series, named [`UNKNOWN_MECHANISM`]; without
+//! `message_id` outliers are reported with ID 0.
+
+use crate::bootstrap;
+use crate::cli::{AnalyzeArgs, IpcMechanism};
+use crate::metrics::{utils::format_latency, LatencyCollector, LatencyMetrics, LatencyType};
+use crate::results::MessageLatencyRecord;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Mechanism of the samples of a file without a `mechanism` column
+pub const UNKNOWN_MECHANISM: &str = "unknown";
+
+/// A single row recovered from a streaming output file.
+///
+/// The mechanism is kept as its display name because CSV files store the
+/// human-readable form, while JSON files store the enum variant name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSample {
+    /// Unix timestamp in nanoseconds when the message was sent
+    pub timestamp_ns: u64,
+    /// Message identifier within the test
+    pub message_id: u64,
+    /// Display name of the mechanism (e.g. "Unix Domain Socket")
+    pub mechanism: String,
+    /// Payload size in bytes
+    pub message_size: usize,
+    /// One-way latency in nanoseconds, if recorded
+    pub one_way_latency_ns: Option<u64>,
+    /// Round-trip latency in nanoseconds, if recorded
+    pub round_trip_latency_ns: Option<u64>,
+}
+
+/// Settings controlling how samples are analyzed.
+#[derive(Debug, Clone)]
+pub struct AnalysisOptions {
+    /// Percentiles to report for each series
+    pub percentiles: Vec<f64>,
+    /// Width of each time window in the windowed series
+    pub window: Duration,
+    /// Samples above this percentile are reported as outliers
+    pub outlier_percentile: f64,
+    /// Maximum number of outliers listed per series
+    pub max_outliers: usize,
+    /// Compute a latency histogram per window
+    pub heatmap: bool,
+}
+
+impl From<&AnalyzeArgs> for AnalysisOptions {
+    fn from(args: &AnalyzeArgs) -> Self {
+        Self {
+            percentiles: args.percentiles.clone(),
+            window: args.window,
+            outlier_percentile: args.outlier_percentile,
+            max_outliers: args.max_outliers,
+            heatmap: args.heatmap.is_some(),
+        }
+    }
+}
+
+/// Statistics for a single time window.
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowStats {
+    /// Window start, relative to the first sample of the series
+    pub start_offset_ns: u64,
+    /// Number of samples in the window
+    pub count: usize,
+    /// Mean latency in nanoseconds
+    pub mean_ns: f64,
+    /// Median latency in nanoseconds
+    pub p50_ns: u64,
+    /// 99th percentile latency in nanoseconds
+    pub p99_ns: u64,
+    /// Maximum latency in nanoseconds
+    pub max_ns: u64,
+}
+
+/// Samples of one window that fell in one latency bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeatmapCell {
+    /// Window start, relative to the first sample of the series
+    pub start_offset_ns: u64,
+    /// Lower bound of the latency bucket in nanoseconds
+    pub bucket_ns: u64,
+    /// Number of samples in the window and bucket
+    pub count: usize,
+}
+
+/// A message whose latency exceeded the outlier threshold.
+#[derive(Debug, Clone, Serialize)]
+pub struct Outlier {
+    /// Message identifier
+    pub message_id: u64,
+    /// Unix timestamp in nanoseconds when the message was sent
+    pub timestamp_ns: u64,
+    /// Observed latency in nanoseconds
+    pub latency_ns: u64,
+}
+
+/// Analysis of one latency series.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesAnalysis {
+    /// Display name of the mechanism
+    pub mechanism: String,
+    /// Payload size in bytes
+    pub message_size: usize,
+    /// Recomputed summary statistics and percentiles
+    pub latency: LatencyMetrics,
+    /// Time-windowed series, in chronological order
+    pub windows: Vec<WindowStats>,
+    /// Latency above which a sample counts as an outlier
+    pub outlier_threshold_ns: u64,
+    /// Total number of samples above the threshold
+    pub outlier_count: usize,
+    /// The slowest outliers, sorted by descending latency
+    pub outliers: Vec<Outlier>,
+    /// Non-empty (window, bucket) cells, with `--heatmap`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub heatmap: Vec<HeatmapCell>,
+}
+
+/// Complete analysis of a streaming file.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisReport {
+    /// Number of rows read from the input
+    pub total_records: usize,
+    /// Width of each time window in nanoseconds
+    pub window_ns: u64,
+    /// One entry per (mechanism, message size, latency type)
+    pub series: Vec<SeriesAnalysis>,
+}
+
+/// Entry point for the `analyze` subcommand.
+pub fn run_analyze(args: &AnalyzeArgs) -> Result<()> {
+    let samples = load_samples(&args.input)?;
+    let report = analyze(&samples, &AnalysisOptions::from(args))?;
+
+    if let Some(path) = &args.heatmap {
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create heatmap file {}", path.display()))?;
+        let mut out = io::BufWriter::new(file);
+        write_heatmap(&report, &mut out)?;
+        out.flush()?;
+    }
+
+    if let Some(path) = &args.output_json {
+        let json = serde_json::to_string_pretty(&report)?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write analysis to {}", path.display()))?;
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report, &args.input)?;
+    }
+    Ok(())
+}
+
+/// Load all samples from a CSV or JSON streaming file.
+pub fn load_samples(path: &Path) -> Result<Vec<StreamSample>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read streaming file {}", path.display()))?;
+
+    if content.trim_start().starts_with('{') {
+        parse_json(&content)
+    } else {
+        parse_csv(&content)
+    }
+    .with_context(|| format!("Failed to parse streaming file {}", path.display()))
+}
+
+/// Maps each expected heading to its column position in the input, if the
+/// input has that column.
+struct ColumnMap {
+    indices: [Option<usize>; 6],
+}
+
+impl ColumnMap {
+    fn new<S: AsRef<str>>(headings: &[S]) -> Result<Self> {
+        let mut indices = [None; 6];
+        for (slot, expected) in indices.iter_mut().zip(MessageLatencyRecord::HEADINGS) {
+            *slot = headings.iter().position(|h| h.as_ref().trim() == *expected);
+        }
+        if indices[0].is_none() {
+            bail!("Missing column 'timestamp_ns'");
+        }
+        if indices[4].is_none() && indices[5].is_none() {
+            bail!("Missing column 'one_way_latency_ns' or 'round_trip_latency_ns'");
+        }
+        Ok(Self { indices })
+    }
+}
+
+fn parse_csv(content: &str) -> Result<Vec<StreamSample>> {
+    let mut lines = content.lines().enumerate();
+    let header = lines
+        .next()
+        .map(|(_, line)| line)
+        .ok_or_else(|| anyhow!("File is empty"))?;
+    let columns = ColumnMap::new(&header.split(',').collect::<Vec<_>>())?;
+
+    let mut samples = Vec::new();
+    for (line_no, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').collect();
+        let field = |i: usize| -> Result<Option<&str>> {
+            columns.indices[i]
+                .map(|index| {
+                    fields
+                        .get(index)
+                        .map(|f| f.trim())
+                        .ok_or_else(|| anyhow!("Line {}: too few columns", line_no + 1))
+                })
+                .transpose()
+        };
+        let number = |i: usize| -> Result<u64> {
+            field(i)?.map_or(Ok(0), |value| {
+                value
+                    .parse()
+                    .with_context(|| format!("Line {}: invalid number", line_no + 1))
+            })
+        };
+        let optional = |i: usize| -> Result<Option<u64>> {
+            match field(i)? {
+                None | Some("") => Ok(None),
+                Some(value) => {
+                    Ok(Some(value.parse().with_context(|| {
+                        format!("Line {}: invalid latency", line_no + 1)
+                    })?))
+                }
+            }
+        };
+
+        samples.push(StreamSample {
+            timestamp_ns: number(0)?,
+            message_id: number(1)?,
+            mechanism: field(2)?.unwrap_or(UNKNOWN_MECHANISM).to_string(),
+            message_size: number(3)? as usize,
+            one_way_latency_ns: optional(4)?,
+            round_trip_latency_ns: optional(5)?,
+        });
+    }
+    Ok(samples)
+}
+
+fn parse_json(content: &str) -> Result<Vec<StreamSample>> {
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        // An interrupted run leaves the data array and object unterminated.
+        Err(_) => {
+            let repaired = format!("{}]}}", content.trim_end().trim_end_matches(','));
+            serde_json::from_str(&repaired)?
+        }
+    };
+
+    let headings: Vec<String> = serde_json::from_value(
+        value
+            .get("headings")
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing 'headings' field"))?,
+    )?;
+    let columns = ColumnMap::new(&headings)?;
+    let rows = value
+        .get("data")
+        .and_then(|d| d.as_array())
+        .ok_or_else(|| anyhow!("Missing 'data' array"))?;
+
+    let mut samples = Vec::with_capacity(rows.len());
+    for (row_no, row) in rows.iter().enumerate() {
+        let cell = |i: usize| -> Result<Option<&serde_json::Value>> {
+            columns.indices[i]
+                .map(|index| {
+                    row.get(index)
+                        .ok_or_else(|| anyhow!("Row {}: too few columns", row_no))
+                })
+                .transpose()
+        };
+        let number = |i: usize| -> Result<u64> {
+            cell(i)?.map_or(Ok(0), |value| {
+                value
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Row {}: invalid number", row_no))
+            })
+        };
+        let mechanism = match cell(2)? {
+            None => UNKNOWN_MECHANISM.to_string(),
+            Some(value) => match serde_json::from_value::<IpcMechanism>(value.clone()) {
+                Ok(mechanism) => mechanism.to_string(),
+                Err(_) => value.as_str().unwrap_or_default().to_string(),
+            },
+        };
+
+        samples.push(StreamSample {
+            timestamp_ns: number(0)?,
+            message_id: number(1)?,
+            mechanism,
+            message_size: number(3)? as usize,
+            one_way_latency_ns: cell(4)?.and_then(serde_json::Value::as_u64),
+            round_trip_latency_ns: cell(5)?.and_then(serde_json::Value::as_u64),
+        });
+    }
+    Ok(samples)
+}
+
+/// `(timestamp_ns, message_id, latency_ns)` for one sample of a series.
+type Point = (u64, u64, u64);
+
+/// `(mechanism, message_size, latency_type)` identifying one series.
+type SeriesKey<'a> = (&'a str, usize, LatencyType);
+
+/// Recompute percentiles, windowed statistics and outliers for each series.
+pub fn analyze(samples: &[StreamSample], options: &AnalysisOptions) -> Result<AnalysisReport> {
+    if options.window.is_zero() {
+        bail!("Analysis window must be greater than zero");
+    }
+
+    // Group by (mechanism, size, type), preserving the order of first appearance.
+    let mut groups: Vec<(SeriesKey, Vec<Point>)> = Vec::new();
+    for sample in samples.iter().filter(|s| s.message_id != u64::MAX) {
+        for (latency_type, latency) in [
+            (LatencyType::OneWay, sample.one_way_latency_ns),
+            (LatencyType::RoundTrip, sample.round_trip_latency_ns),
+        ] {
+            let Some(latency) = latency else { continue };
+            let key = (sample.mechanism.as_str(), sample.message_size, latency_type);
+            let entry = (sample.timestamp_ns, sample.message_id, latency);
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, values)) => values.push(entry),
+                None => groups.push((key, vec![entry])),
+            }
+        }
+    }
+
+    let window_ns = options.window.as_nanos() as u64;
+    let mut series = Vec::with_capacity(groups.len());
+    for ((mechanism, message_size, latency_type), mut values) in groups {
+        values.sort_by_key(|&(timestamp, _, _)| timestamp);
+
+        let mut collector = LatencyCollector::new(latency_type)?;
+        for &(_, _, latency) in &values {
+            collector.record(Duration::from_nanos(latency))?;
+        }
+        let mut latency = collector.get_metrics(&options.percentiles);
+        latency.histogram_data.clear();
+        latency.buckets.clear();
+        let latencies: Vec<u64> = values.iter().map(|&(_, _, l)| l).collect();
+        latency.percentile_intervals =
+            bootstrap::percentile_intervals(&latencies, &options.percentiles);
+
+        let outlier_threshold_ns = collector
+            .get_metrics(&[options.outlier_percentile])
+            .percentiles
+            .first()
+            .map(|p| p.value_ns)
+            .unwrap_or(u64::MAX);
+        let mut outliers: Vec<Outlier> = values
+            .iter()
+            .filter(|&&(_, _, latency)| latency > outlier_threshold_ns)
+            .map(|&(timestamp_ns, message_id, latency_ns)| Outlier {
+                message_id,
+                timestamp_ns,
+                latency_ns,
+            })
+            .collect();
+        let outlier_count = outliers.len();
+        outliers.sort_by_key(|o| std::cmp::Reverse(o.latency_ns));
+        outliers.truncate(options.max_outliers);
+
+        series.push(SeriesAnalysis {
+            mechanism: mechanism.to_string(),
+            message_size,
+            latency,
+            windows: windowed_stats(&values, window_ns),
+            outlier_threshold_ns,
+            outlier_count,
+            outliers,
+            heatmap: if options.heatmap {
+                heatmap(&values, window_ns)
+            } else {
+                Vec::new()
+            },
+        });
+    }
+
+    Ok(AnalysisReport {
+        total_records: samples.len(),
+        window_ns,
+        series,
+    })
+}
+
+/// Split timestamp-sorted samples into fixed-width windows, returning each
+/// non-empty window's start offset and samples.
+fn split_windows(values: &[Point], window_ns: u64) -> Vec<(u64, &[Point])> {
+    let Some(&(first_timestamp, _, _)) = values.first() else {
+        return Vec::new();
+    };
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < values.len() {
+        let index = (values[start].0 - first_timestamp) / window_ns;
+        let end = start
+            + values[start..]
+                .iter()
+                .take_while(|&&(timestamp, _, _)| {
+                    (timestamp - first_timestamp) / window_ns == index
+                })
+                .count();
+        windows.push((index * window_ns, &values[start..end]));
+        start = end;
+    }
+    windows
+}
+
+/// Statistics of each fixed-width window.
+///
+/// Empty windows are omitted so gaps in the data stay visible as jumps in
+/// `start_offset_ns` rather than rows of zeros.
+fn windowed_stats(values: &[Point], window_ns: u64) -> Vec<WindowStats> {
+    split_windows(values, window_ns)
+        .into_iter()
+        .map(|(start_offset_ns, window)| {
+            let mut latencies: Vec<u64> = window.iter().map(|&(_, _, l)| l).collect();
+            latencies.sort_unstable();
+            let sum: u128 = latencies.iter().map(|&l| l as u128).sum();
+            WindowStats {
+                start_offset_ns,
+                count: latencies.len(),
+                mean_ns: sum as f64 / latencies.len() as f64,
+                p50_ns: nearest_rank(&latencies, 50.0),
+                p99_ns: nearest_rank(&latencies, 99.0),
+                max_ns: *latencies.last().unwrap_or(&0),
+            }
+        })
+        .collect()
+}
+
+/// Count timestamp-sorted samples per window and latency bucket.
+///
+/// Cells are ordered by window, then bucket; empty cells are omitted so the
+/// output stays small for long runs.
+fn heatmap(values: &[Point], window_ns: u64) -> Vec<HeatmapCell> {
+    let mut cells: Vec<HeatmapCell> = Vec::new();
+    for (start_offset_ns, window) in split_windows(values, window_ns) {
+        let mut buckets: Vec<u64> = window.iter().map(|&(_, _, l)| heatmap_bucket(l)).collect();
+        buckets.sort_unstable();
+        for bucket_ns in buckets {
+            match cells.last_mut() {
+                Some(cell)
+                    if cell.start_offset_ns == start_offset_ns && cell.bucket_ns == bucket_ns =>
+                {
+                    cell.count += 1
+                }
+                _ => cells.push(HeatmapCell {
+                    start_offset_ns,
+                    bucket_ns,
+                    count: 1,
+                }),
+            }
+        }
+    }
+    cells
+}
+
+/// Lower bound of the heatmap bucket holding `latency_ns`.
+///
+/// Buckets split each power of two into four, so they keep roughly 20%
+/// resolution from nanoseconds to seconds on a log-scaled axis.
+fn heatmap_bucket(latency_ns: u64) -> u64 {
+    if latency_ns < 4 {
+        return latency_ns;
+    }
+    let shift = 63 - latency_ns.leading_zeros() - 2;
+    (latency_ns >> shift) << shift
+}
+
+/// Write the heatmap cells of every series as CSV, one row per cell.
+pub fn write_heatmap<W: Write>(report: &AnalysisReport, out: &mut W) -> Result<()> {
+    writeln!(
+        out,
+        "mechanism,message_size,latency_type,start_offset_ns,bucket_ns,count"
+    )?;
+    for series in &report.series {
+        let latency_type = match series.latency.latency_type {
+            LatencyType::OneWay => "one_way",
+            LatencyType::RoundTrip => "round_trip",
+            LatencyType::InterArrival => "inter_arrival",
+        };
+        for cell in &series.heatmap {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                series.mechanism,
+                series.message_size,
+                latency_type,
+                cell.start_offset_ns,
+                cell.bucket_ns,
+                cell.count
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Nearest-rank percentile over a sorted, non-empty slice.
+fn nearest_rank(sorted: &[u64], percentile: f64) -> u64 {
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn print_report(report: &AnalysisReport, input: &Path) -> Result<()> {
+    println!("\nStreaming Analysis: {}", input.display());
+    println!("-----------------------------------------------------------------");
+    println!("  Records: {}", report.total_records);
+    println!("  Window:  {}", format_latency(report.window_ns));
+    println!("-----------------------------------------------------------------");
+
+    if report.series.is_empty() {
+        println!("No latency samples found.");
+    }
+
+    for series in &report.series {
+        let title = match series.latency.latency_type {
+            LatencyType::OneWay => "One-Way Latency",
+            LatencyType::RoundTrip => "Round-Trip Latency",
+            LatencyType::InterArrival => "Inter-Arrival Gaps",
+        };
+        println!("Mechanism: {}", series.mechanism);
+        println!("  Message Size: {} bytes", series.message_size);
+        println!("  {} ({} samples):", title, series.latency.total_samples);
+        println!(
+            "    Mean: {}, Min: {}, Max: {}",
+            format_latency(series.latency.mean_ns as u64),
+            format_latency(series.latency.min_ns),
+            format_latency(series.latency.max_ns)
+        );
+        for p in &series.latency.percentiles {
+            let interval = crate::results::percentile_interval(&series.latency, p.percentile)
+                .map(|i| {
+                    format!(
+                        " ({}% CI {}-{})",
+                        bootstrap::CONFIDENCE_LEVEL,
+                        format_latency(i.lower_ns),
+                        format_latency(i.upper_ns)
+                    )
+                })
+                .unwrap_or_default();
+            println!(
+                "    P{:<6} {}{}",
+                p.percentile,
+                format_latency(p.value_ns),
+                interval
+            );
+        }
+
+        println!("  Windows:");
+        println!(
+            "    {:>12} {:>10} {:>12} {:>12} {:>12} {:>12}",
+            "Offset", "Count", "Mean", "P50", "P99", "Max"
+        );
+        for w in &series.windows {
+            println!(
+                "    {:>12} {:>10} {:>12} {:>12} {:>12} {:>12}",
+                format_latency(w.start_offset_ns),
+                w.count,
+                format_latency(w.mean_ns as u64),
+                format_latency(w.p50_ns),
+                format_latency(w.p99_ns),
+                format_latency(w.max_ns)
+            );
+        }
+
+        println!(
+            "  Outliers (> {}): {} total",
+            format_latency(series.outlier_threshold_ns),
+            series.outlier_count
+        );
+        for o in &series.outliers {
+            println!(
+                "    message {:>10} at {} ns: {}",
+                o.message_id,
+                o.timestamp_ns,
+                format_latency(o.latency_ns)
+            );
+        }
+        println!("-----------------------------------------------------------------");
+    }
+
+    io::stdout().flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    fn options() -> AnalysisOptions {
+        AnalysisOptions {
+            percentiles: vec![50.0, 99.0],
+            window: Duration::from_nanos(1_000),
+            outlier_percentile: 90.0,
+            max_outliers: 2,
+            heatmap: false,
+        }
+    }
+
+    fn write_temp(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parse_csv_with_empty_latency_columns() {
+        let csv = "timestamp_ns,message_id,mechanism,message_size,one_way_latency_ns,round_trip_latency_ns\n\
+                   100,0,Unix Domain Socket,64,1500,\n\
+                   200,1,Unix Domain Socket,64,,3000\n";
+        let samples = load_samples(write_temp(csv).path()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].mechanism, "Unix Domain Socket");
+        assert_eq!(samples[0].one_way_latency_ns, Some(1500));
+        assert_eq!(samples[0].round_trip_latency_ns, None);
+        assert_eq!(samples[1].round_trip_latency_ns, Some(3000));
+    }
+
+    #[test]
+    fn test_parse_json_normalizes_mechanism_and_tolerates_truncation() {
+        let json = "{\n  \"headings\": [\"timestamp_ns\",\"message_id\",\"mechanism\",\
+                    \"message_size\",\"one_way_latency_ns\",\"round_trip_latency_ns\"],\n  \
+                    \"data\": [\n    [100,0,\"TcpSocket\",128,null,2500],\n    \
+                    [200,1,\"TcpSocket\",128,null,2600]";
+        let samples = load_samples(write_temp(json).path()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].mechanism, IpcMechanism::TcpSocket.to_string());
+        assert_eq!(samples[1].round_trip_latency_ns, Some(2600));
+    }
+
+    #[test]
+    fn test_parse_csv_missing_column_is_error() {
+        let csv = "timestamp_ns,message_id,mechanism\n1,2,x\n";
+        assert!(load_samples(write_temp(csv).path()).is_err());
+        let csv = "message_id,round_trip_latency_ns\n1,2\n";
+        assert!(load_samples(write_temp(csv).path()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_csv_written_with_column_subset() {
+        use crate::csv_schema::{parse_columns, CsvLayout};
+        use crate::metrics::LatencyType;
+
+        let layout =
+            CsvLayout::new(parse_columns("phase,round_trip_latency_ns,timestamp_ns").unwrap());
+        let mut csv = layout.header();
+        for i in 0..20u64 {
+            let mut record = MessageLatencyRecord::new(
+                i,
+                IpcMechanism::TcpSocket,
+                64,
+                LatencyType::RoundTrip,
+                Duration::from_nanos(1000 + i),
+                0,
+            );
+            record.timestamp_ns = 10_000 + i * 100;
+            csv.push('\n');
+            csv.push_str(&layout.row(&record));
+        }
+
+        let samples = load_samples(write_temp(&csv).path()).unwrap();
+        assert_eq!(samples.len(), 20);
+        assert_eq!(samples[3].timestamp_ns, 10_300);
+        assert_eq!(samples[3].round_trip_latency_ns, Some(1003));
+        assert_eq!(samples[3].mechanism, UNKNOWN_MECHANISM);
+
+        let report = analyze(&samples, &options()).unwrap();
+        assert_eq!(report.series.len(), 1);
+        assert_eq!(report.series[0].latency.total_samples, 20);
+        assert_eq!(report.series[0].windows.len(), 2);
+    }
+
+    #[test]
+    fn test_analyze_windows_and_outliers() {
+        let samples: Vec<StreamSample> = (0..20u64)
+            .map(|i| StreamSample {
+                timestamp_ns: 10_000 + i * 100,
+                message_id: i,
+                mechanism: "Shared Memory".to_string(),
+                message_size: 64,
+                one_way_latency_ns: Some(if i == 7 { 50_000 } else { 1_000 + i }),
+                round_trip_latency_ns: None,
+            })
+            .collect();
+
+        let report = analyze(&samples, &options()).unwrap();
+        assert_eq!(report.series.len(), 1);
+        let series = &report.series[0];
+        assert_eq!(series.latency.total_samples, 20);
+        assert_eq!(series.latency.max_ns, 50_000);
+        let p50 = &series.latency.percentile_intervals[0];
+        assert!(p50.lower_ns >= 1_000 && p50.upper_ns < 1_020);
+
+        // 20 samples 100ns apart in 1us windows -> two windows of ten.
+        assert_eq!(series.windows.len(), 2);
+        assert_eq!(series.windows[0].count, 10);
+        assert_eq!(series.windows[1].start_offset_ns, 1_000);
+        assert_eq!(series.windows[0].max_ns, 50_000);
+
+        assert_eq!(series.outliers.len(), 2);
+        assert_eq!(series.outliers[0].message_id, 7);
+        assert!(series.outlier_count >= series.outliers.len());
+    }
+
+    #[test]
+    fn test_analyze_splits_series_and_skips_canary() {
+        let mut samples = vec![
+            StreamSample {
+                timestamp_ns: 1,
+                message_id: u64::MAX,
+                mechanism: "TCP Socket".to_string(),
+                message_size: 64,
+                one_way_latency_ns: Some(999_999),
+                round_trip_latency_ns: None,
+            },
+            StreamSample {
+                timestamp_ns: 2,
+                message_id: 0,
+                mechanism: "TCP Socket".to_string(),
+                message_size: 64,
+                one_way_latency_ns: Some(1_000),
+                round_trip_latency_ns: Some(2_000),
+            },
+        ];
+        samples.push(StreamSample {
+            message_size: 128,
+            ..samples[1].clone()
+        });
+
+        let report = analyze(&samples, &options()).unwrap();
+        assert_eq!(report.series.len(), 4);
+        assert!(report.series.iter().all(|s| s.latency.max_ns < 999_999));
+    }
+
+    #[test]
+    fn test_analyze_rejects_zero_window() {
+        let opts = AnalysisOptions {
+            window: Duration::ZERO,
+            ..options()
+        };
+        assert!(analyze(&[], &opts).is_err());
+    }
+
+    #[test]
+    fn test_heatmap_counts_per_window_and_bucket() {
+        let samples: Vec<StreamSample> = (0..20u64)
+            .map(|i| StreamSample {
+                timestamp_ns: i * 100,
+                message_id: i,
+                mechanism: "TCP Socket".to_string(),
+                message_size: 64,
+                one_way_latency_ns: Some(if i == 15 { 5_000 } else { 1_000 }),
+                round_trip_latency_ns: None,
+            })
+            .collect();
+        let opts = AnalysisOptions {
+            heatmap: true,
+            ..options()
+        };
+
+        let report = analyze(&samples, &opts).unwrap();
+        let cell = |start_offset_ns, bucket_ns, count| HeatmapCell {
+            start_offset_ns,
+            bucket_ns,
+            count,
+        };
+        assert_eq!(
+            report.series[0].heatmap,
+            vec![cell(0, 896, 10), cell(1_000, 896, 9), cell(1_000, 4_096, 1)]
+        );
+
+        let mut csv = Vec::new();
+        write_heatmap(&report, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(3),
+            Some("TCP Socket,64,one_way,1000,4096,1")
+        );
+    }
+
+    #[test]
+    fn test_heatmap_bucket() {
+        assert_eq!(heatmap_bucket(0), 0);
+        assert_eq!(heatmap_bucket(3), 3);
+        assert_eq!(heatmap_bucket(4), 4);
+        assert_eq!(heatmap_bucket(7), 7);
+        assert_eq!(heatmap_bucket(9), 8);
+        assert_eq!(heatmap_bucket(1_000), 896);
+        assert_eq!(heatmap_bucket(1_024), 1_024);
+        assert_eq!(heatmap_bucket(1_300), 1_280);
+    }
+
+    #[test]
+    fn test_nearest_rank() {
+        let sorted = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(nearest_rank(&sorted, 50.0), 5);
+        assert_eq!(nearest_rank(&sorted, 99.0), 10);
+        assert_eq!(nearest_rank(&sorted, 0.0), 1);
+    }
+}
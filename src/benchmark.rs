@@ -36,23 +36,38 @@
 //! - Comprehensive error handling with graceful degradation
 
 use crate::{
-    cli::{Args, IpcMechanism},
-    ipc::{Message, MessageType, TransportConfig, TransportFactory},
+    affinity_check::AffinityMonitor,
+    cli::{Args, IpcMechanism, ResponseMode},
+    confidence,
+    cpu_frequency::FrequencyMonitor,
+    first_message::FirstMessage,
+    hook::{self, MessageHook, SimulatedWork},
+    ipc::{
+        handshake::{self, Handshake},
+        limits::TransportLimits,
+        receive_response, segmentation, Message, MessageType, TransportConfig, TransportFactory,
+        TransportStatsTotals,
+    },
     metrics::{LatencyType, MetricsCollector, PerformanceMetrics},
-    results::BenchmarkResults,
+    pacing::Pacer,
+    payload::{MessageSource, PayloadGenerator, ZeroPayload},
+    profile::{self, ProfileSettings},
+    queue_depth::QueueDepthSampler,
+    reproduce::Reproduction,
+    results::{BenchmarkResults, CacheControlReport},
+    runtime::RuntimeConfig,
+    scheduler_noise::NoiseProbe,
+    sequence::{IdSequence, IdSpace},
+    server_handle::{self, ServerHandle},
+    setup_retry::SetupRetry,
+    shutdown,
+    smt::SharedCore,
     utils::get_temp_dir,
+    warmup::{self, WarmupCapture, WarmupLength, WarmupReport},
 };
 use anyhow::{Context, Result};
-use clap::ValueEnum;
-use os_pipe::PipeReader;
-#[cfg(unix)]
-use std::os::unix::io::FromRawFd;
-#[cfg(windows)]
-use std::os::windows::io::{FromRawHandle, IntoRawHandle};
-use std::process::Command;
 use std::{
-    io::Read,
-    process::Stdio,
+    sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::time::sleep;
@@ -102,6 +117,22 @@ impl<'a> std::fmt::Display for BenchmarkConfigDisplay<'a> {
             writeln!(f, "  Send Delay:         {:?}", delay)?;
         }
 
+        if let Some(size) = self.transport_config.segment_size {
+            writeln!(
+                f,
+                "  Segmentation:       {} segments/message of up to {} bytes",
+                segmentation::segment_count(self.config.message_size, size),
+                size
+            )?;
+        }
+        if self.mechanism == IpcMechanism::TcpSocket {
+            if let Some(device) = &self.transport_config.bind_device {
+                writeln!(f, "  Bind Device:        {}", device)?;
+            }
+            if let Some(ip) = self.transport_config.source_addr {
+                writeln!(f, "  Source Address:     {}", ip)?;
+            }
+        }
         #[cfg(target_os = "linux")]
         if self.mechanism == IpcMechanism::PosixMessageQueue {
             writeln!(f, "  PMQ Priority:       {}", self.config.pmq_priority)?;
@@ -264,6 +295,122 @@ pub fn parse_latency_file_line(line: &str) -> anyhow::Result<(u64, u64)> {
     Ok((wall_send_ns, latency_ns))
 }
 
+/// Check protocol compatibility with the server on a freshly connected
+/// client transport, proposing `terms` (nothing to check if `None`).
+async fn handshake_with_server(
+    transport: &mut dyn crate::ipc::IpcTransport,
+    terms: Option<Handshake>,
+) -> Result<()> {
+    if let Some(terms) = terms {
+        let agreed = handshake::client(transport, &terms)
+            .await
+            .context("Handshake with the server failed")?;
+        debug!("Handshake agreed: {:?}", agreed);
+    }
+    Ok(())
+}
+
+/// Path of the first-byte latency file that accompanies a latency file.
+///
+/// The server writes first-byte latencies here when the client asks for
+/// them with `--first-byte-latency`, in the same format as the latency file.
+pub(crate) fn first_byte_file_for(latency_file_path: &str) -> String {
+    format!("{}.first_byte", latency_file_path)
+}
+
+/// Path of the file where the server reports how many one-way messages it
+/// dropped for arriving past their `--deadline`.
+pub(crate) fn expired_file_for(latency_file_path: &str) -> String {
+    format!("{}.expired", latency_file_path)
+}
+
+/// Read the server's expired-message count, then remove the file. A missing
+/// or unreadable file counts as none.
+pub(crate) fn read_expired_count(path: &str) -> usize {
+    let count = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(0);
+    let _ = std::fs::remove_file(path);
+    count
+}
+
+/// Path of the file where the server lists the priority of each message in
+/// the latency file, when the client sends `--priorities`.
+pub(crate) fn priority_file_for(latency_file_path: &str) -> String {
+    format!("{}.priority", latency_file_path)
+}
+
+/// Path of the file where the server lists the producer of each message
+/// in the latency file, when the client runs `--producers`.
+pub(crate) fn producer_file_for(latency_file_path: &str) -> String {
+    format!("{}.producer", latency_file_path)
+}
+
+/// Read the server's per-message priorities or producers, then remove the
+/// file. A missing file yields none, and unparsable lines count as 0.
+pub(crate) fn read_message_tags(path: &str) -> Vec<u8> {
+    let tags = std::fs::read_to_string(path)
+        .map(|text| {
+            text.lines()
+                .map(|line| line.trim().parse().unwrap_or(0))
+                .collect()
+        })
+        .unwrap_or_default();
+    let _ = std::fs::remove_file(path);
+    tags
+}
+
+/// Read a first-byte latency file written by the server into a
+/// distribution, then remove the file.
+pub(crate) fn read_first_byte_latencies(
+    path: &str,
+    message_size: usize,
+    percentiles: &[f64],
+) -> Result<Option<crate::metrics::LatencyMetrics>> {
+    use std::io::BufRead;
+
+    let file = std::fs::File::open(path).context("Failed to open first-byte latency file")?;
+    let mut collector = MetricsCollector::new(Some(LatencyType::OneWay), percentiles.to_vec())?;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line.context("Failed to read line from first-byte latency file")?;
+        let (_, latency_ns) = parse_latency_file_line(&line)?;
+        collector.record_message(message_size, Some(Duration::from_nanos(latency_ns)))?;
+    }
+    let _ = std::fs::remove_file(path);
+    Ok(collector.get_metrics().latency)
+}
+
+/// Segment size for a test, or `None` to send messages whole.
+///
+/// An explicit `--segment-size` always wins. Otherwise the client segments
+/// only when `message_size` exceeds the mechanism's single-message limit;
+/// the spawned server never decides for itself and uses what the client
+/// forwards, so both ends frame messages the same way.
+pub(crate) fn resolve_segment_size(
+    args: &Args,
+    mechanism: &IpcMechanism,
+    message_size: usize,
+    buffer_size: usize,
+) -> Option<usize> {
+    if args.segment_size.is_some() || args.internal_run_as_server {
+        return args.segment_size;
+    }
+    let max_payload = segmentation::max_payload_for(mechanism, buffer_size, args.shm_direct);
+    let segment_size = segmentation::auto_segment_size(message_size, max_payload);
+    if let Some(size) = segment_size {
+        info!(
+            "{} carries at most {} bytes per message; sending {}-byte messages as {} segments of {} bytes",
+            mechanism,
+            max_payload,
+            message_size,
+            segmentation::segment_count(message_size, size),
+            size
+        );
+    }
+    segment_size
+}
+
 impl BenchmarkConfig {
     /// Create benchmark configuration from CLI arguments
     ///
@@ -322,7 +469,7 @@ impl BenchmarkConfig {
             port: args.port,
             send_delay: args.send_delay,
             pmq_priority: args.pmq_priority,
-            include_first_message: args.include_first_message,
+            include_first_message: args.first_message_mode() == FirstMessage::Include,
             server_affinity: args.server_affinity,
             client_affinity: args.client_affinity,
         })
@@ -385,6 +532,7 @@ impl BenchmarkConfig {
 /// #     message_queue_name: None,
 /// #     internal_latency_file: None,
 /// #     shm_direct: false,
+/// #     ..Default::default()
 /// # };
 /// let config = BenchmarkConfig::from_args(&args)?;
 /// #[cfg(unix)]
@@ -407,6 +555,27 @@ pub struct BenchmarkRunner {
 
     /// Available CPU cores (cached at startup to avoid affinity-dependent detection)
     available_cores: Option<Vec<core_affinity::CoreId>>,
+
+    /// Description of the runtime the benchmark runs on, when set by the caller
+    runtime: Option<RuntimeConfig>,
+
+    /// Retry policy for transport setup, counting the retries made
+    setup_retry: SetupRetry,
+
+    /// Builds the messages the client sends
+    messages: MessageSource,
+
+    /// Processing the client does for each message (`--client-work`)
+    client_hook: Option<Arc<dyn MessageHook>>,
+
+    /// What `--profile` set for this mechanism
+    profile: Option<ProfileSettings>,
+
+    /// How long this mechanism warms up
+    warmup: WarmupLength,
+
+    /// Counters of the client transports of the measured tests
+    transport_stats: TransportStatsTotals,
 }
 
 impl BenchmarkRunner {
@@ -459,18 +628,98 @@ impl BenchmarkRunner {
     ///
     /// ## Returns
     /// Configured benchmark runner ready for execution
-    pub fn new(config: BenchmarkConfig, mechanism: IpcMechanism, args: Args) -> Self {
+    pub fn new(mut config: BenchmarkConfig, mechanism: IpcMechanism, mut args: Args) -> Self {
+        let profile = profile::apply(&mut args, &mut config, mechanism);
+        if let Some(profile) = &profile {
+            info!("Profile for {}: {}", mechanism, profile);
+        }
+        let warmup = warmup::length_for(&args, mechanism, config.warmup_iterations);
+
         // Cache available cores at construction time to avoid affinity-dependent detection
         let available_cores = core_affinity::get_core_ids();
 
+        let setup_retry = SetupRetry::from_args(&args);
+        // --id-width and --id-start were validated before any runner is made
+        let ids = IdSpace::from_args(&args)
+            .ok()
+            .flatten()
+            .map(IdSequence::new);
+        let messages = MessageSource::new(
+            Arc::new(ZeroPayload::new(config.message_size)),
+            config.message_size,
+        )
+        .with_id_sequence(ids);
+        let client_hook = args
+            .client_work
+            .map(|work| Arc::new(SimulatedWork::new(work)) as Arc<dyn MessageHook>);
         Self {
             config,
             mechanism,
             args,
             available_cores,
+            runtime: None,
+            setup_retry,
+            messages,
+            client_hook,
+            profile,
+            warmup,
+            transport_stats: TransportStatsTotals::default(),
         }
     }
 
+    /// Describe the Tokio runtime the benchmark runs on, for the results.
+    ///
+    /// Without this, the runtime requested by the arguments is recorded, or
+    /// just the flavor of the current runtime when it doesn't match them (as
+    /// when a library caller built its own).
+    pub fn with_runtime_config(mut self, runtime: RuntimeConfig) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Send messages made by `generator` instead of zeroed payloads of the
+    /// configured message size, which its payloads must not exceed.
+    pub fn with_payload_generator(mut self, generator: Arc<dyn PayloadGenerator>) -> Self {
+        self.messages = MessageSource::new(generator, self.config.message_size)
+            .with_id_sequence(self.messages.id_sequence().cloned());
+        self
+    }
+
+    /// Run `hook` on each message the client sends one-way, before sending
+    /// it, and on each round-trip request, once its reply has arrived. Its
+    /// cost is reported apart from the transport latency; see
+    /// [`crate::hook`].
+    pub fn with_client_hook(mut self, hook: Arc<dyn MessageHook>) -> Self {
+        self.client_hook = Some(hook);
+        self
+    }
+
+    /// Run the benchmark to completion on the runtime behind `handle`.
+    ///
+    /// For callers that own a Tokio runtime and are not in async code
+    /// themselves. Must not be called from within a runtime.
+    pub fn run_on(
+        &self,
+        handle: &tokio::runtime::Handle,
+        results_manager: Option<&mut crate::results::ResultsManager>,
+    ) -> Result<BenchmarkResults> {
+        handle.block_on(self.run(results_manager))
+    }
+
+    /// The runtime to record for this run's results.
+    fn runtime_config(&self) -> Option<RuntimeConfig> {
+        if self.runtime.is_some() {
+            return self.runtime;
+        }
+        let requested = RuntimeConfig::from_args(&self.args);
+        let current = RuntimeConfig::of_handle(&tokio::runtime::Handle::try_current().ok()?);
+        Some(if current.flavor == requested.flavor {
+            requested
+        } else {
+            current
+        })
+    }
+
     /// Run the benchmark and return comprehensive results
     ///
     /// This is the main entry point for benchmark execution. It orchestrates
@@ -516,28 +765,104 @@ impl BenchmarkRunner {
                 transport_config: &transport_config,
             }
         );
+        let runtime = self.runtime_config();
+        if let Some(runtime) = &runtime {
+            info!("Tokio runtime: {}", runtime);
+        }
+        if let Some(target) = &self.args.server_remote {
+            info!(
+                "Server runs on {} over SSH; client connects to {}",
+                target, transport_config.host
+            );
+        }
 
         // Initialize results structure with test configuration
         let mut results = BenchmarkResults::new(
             self.mechanism,
             self.config.message_size,
             transport_config.buffer_size,
-            self.config.concurrency,
+            // Shared memory runs a single client whatever was requested
+            if self.mechanism == IpcMechanism::SharedMemory {
+                1
+            } else {
+                self.config.concurrency
+            },
             self.config.msg_count,
             self.config.duration,
             self.config.warmup_iterations,
             self.config.one_way,
             self.config.round_trip,
         );
+        results.test_config.server_batch =
+            (self.args.server_batch > 1).then_some(self.args.server_batch);
+        results.test_config.profile = self.profile.clone();
+        if self.args.server_remote.is_none() {
+            results.test_config.smt_siblings = self
+                .config
+                .client_affinity
+                .zip(self.config.server_affinity)
+                .and_then(|(client, server)| SharedCore::between(client, server));
+        }
+        results.reproduction = Some(Reproduction::of_test(
+            &self.args,
+            self.mechanism,
+            self.config.client_affinity,
+            self.config.server_affinity,
+        ));
+        results.test_config.server_rate = self.args.server_rate;
+        results.test_config.client_work = self.args.client_work;
+        results.test_config.server_work = self.args.server_work;
+        results.test_config.server_spin = self.args.server_spin;
+        results.test_config.response_mode = Some(self.args.response_mode);
+        results.test_config.id_width = self.args.id_width;
+        #[cfg(unix)]
+        if self.mechanism == IpcMechanism::UnixDomainSocket
+            && self.args.uds_credentials != crate::cli::UdsCredentialMode::Off
+        {
+            results.test_config.uds_credentials = Some(self.args.uds_credentials);
+        }
+        results.test_config.idle_gap = self.args.idle_gap;
+        results.test_config.send_delay = self.config.send_delay;
+        results.test_config.deadline = self.args.deadline;
+        results.test_config.runtime = runtime;
+        if self.mechanism == IpcMechanism::Http {
+            results.test_config.http_version = Some(transport_config.http_version);
+        }
+        if self.mechanism == IpcMechanism::TcpSocket {
+            results.test_config.tcp_keepalive = transport_config.tcp_keepalive;
+            results.test_config.bind_device = transport_config.bind_device.clone();
+            results.test_config.source_addr = transport_config.source_addr;
+            results.test_config.server_remote = self.args.server_remote.clone();
+        }
+        if let Some(size) = transport_config.segment_size {
+            results.test_config.segment_size = Some(size);
+            results.test_config.segments_per_message =
+                Some(segmentation::segment_count(self.config.message_size, size));
+        }
+        let limits = TransportLimits::discover(&self.mechanism);
+        results.test_config.transport_limits = (!limits.is_empty()).then_some(limits);
 
         // Run warmup if configured
-        if self.config.warmup_iterations > 0 {
-            info!(
-                "Running warmup with {} iterations",
-                self.config.warmup_iterations
-            );
-            self.run_warmup(&transport_config).await?;
+        if !self.warmup.is_empty() {
+            info!("Running warmup of {}", self.warmup);
+            let (sent, report) = self.run_warmup(&transport_config).await?;
+            results.test_config.warmup_iterations = sent;
+            results.warmup = report;
         }
+        results.test_config.warmup_duration = self.warmup.duration();
+
+        let mut cache_control =
+            (self.args.drop_caches || self.args.fresh_resources).then(|| CacheControlReport {
+                drop_caches: self.args.drop_caches,
+                fresh_resources: self.args.fresh_resources,
+                ..Default::default()
+            });
+
+        let noise_floor_ns = confidence::measure_noise_floor();
+
+        // Watch for throttling across the measured phases
+        let frequency_monitor = FrequencyMonitor::start(self.args.frequency_sample_interval);
+        let noise_probe = NoiseProbe::start(self.args.scheduler_noise_interval);
 
         // Check if we need to run in combined mode for streaming
         let results_manager_ref = results_manager.as_deref_mut();
@@ -545,10 +870,21 @@ impl BenchmarkRunner {
             .map(|rm| rm.is_combined_streaming_enabled())
             .unwrap_or(false);
 
-        if combined_streaming && self.config.one_way && self.config.round_trip {
+        // The shared memory ring carries messages one way only: a client
+        // reading it for replies would get its own requests back
+        let round_trip = self.config.round_trip && self.mechanism != IpcMechanism::SharedMemory;
+        if self.config.round_trip && !round_trip {
+            warn!(
+                "Shared memory does not support bidirectional communication. \
+                Skipping round-trip test."
+            );
+        }
+
+        if combined_streaming && self.config.one_way && round_trip {
             info!("Running combined one-way and round-trip test for streaming");
+            let phase_config = self.prepare_test_phase(&transport_config, &mut cache_control)?;
             let combined_results = self
-                .run_combined_test(&transport_config, results_manager.as_deref_mut())
+                .run_combined_test(&phase_config, results_manager.as_deref_mut())
                 .await?;
             results.add_one_way_results(combined_results.0);
             results.add_round_trip_results(combined_results.1);
@@ -556,26 +892,88 @@ impl BenchmarkRunner {
             // Run one-way latency test if enabled
             if self.config.one_way {
                 info!("Running one-way latency test");
-                let one_way_results = self
-                    .run_one_way_test(&transport_config, results_manager.as_deref_mut())
+                let phase_config =
+                    self.prepare_test_phase(&transport_config, &mut cache_control)?;
+                let (one_way_results, first_byte) = self
+                    .run_one_way_test(&phase_config, results_manager.as_deref_mut())
                     .await?;
                 results.add_one_way_results(one_way_results);
+                results.first_byte_latency = first_byte;
             }
 
             // Run round-trip latency test if enabled
-            if self.config.round_trip {
+            if round_trip && !shutdown::requested() {
                 info!("Running round-trip latency test");
+                let phase_config =
+                    self.prepare_test_phase(&transport_config, &mut cache_control)?;
                 let round_trip_results = self
-                    .run_round_trip_test(&transport_config, results_manager)
+                    .run_round_trip_test(&phase_config, results_manager)
                     .await?;
                 results.add_round_trip_results(round_trip_results);
             }
         }
 
+        results.cpu_frequency = frequency_monitor
+            .and_then(|monitor| monitor.finish(self.args.frequency_drop_threshold));
+        results.scheduler_noise = noise_probe.and_then(NoiseProbe::finish);
+        if let Some(report) = results.cpu_frequency.as_ref().filter(|r| r.throttled) {
+            warn!(
+                "CPU frequency dropped during the {} test: {}. Results may reflect throttling",
+                self.mechanism, report
+            );
+        }
+        results.annotate_confidence(noise_floor_ns);
+        results.annotate_goodput(self.args.goodput_budget);
+        results.annotate_sla(&self.args.sla_thresholds);
+        results.cache_control = cache_control;
+        results.setup_retries = self.setup_retry.count();
+        results.transport_stats = self.transport_stats.get();
+
+        if shutdown::requested() {
+            warn!(
+                "{} test interrupted; recording partial results",
+                self.mechanism
+            );
+            results.set_aborted();
+        }
+
         info!("Benchmark completed for {} mechanism", self.mechanism);
         Ok(results)
     }
 
+    /// Apply the cold-cache controls requested on the command line before a
+    /// measured test phase.
+    ///
+    /// Returns the transport configuration the phase should use: a newly
+    /// generated one under `--fresh-resources`, otherwise a copy of `current`.
+    /// A failed cache drop is logged and recorded but does not abort the test.
+    fn prepare_test_phase(
+        &self,
+        current: &TransportConfig,
+        report: &mut Option<CacheControlReport>,
+    ) -> Result<TransportConfig> {
+        // Every test's IDs start at --id-start, as its server expects
+        self.messages.restart_ids();
+
+        let config = if self.args.fresh_resources {
+            self.create_transport_config_internal(&self.args)?
+        } else {
+            current.clone()
+        };
+
+        if self.args.drop_caches {
+            let outcome = crate::utils::drop_page_caches();
+            if let Err(e) = &outcome {
+                warn!("Could not drop page caches, this phase is not cold: {}", e);
+            }
+            if let Some(report) = report.as_mut() {
+                report.record_drop_attempt(&outcome);
+            }
+        }
+
+        Ok(config)
+    }
+
     /// Run warmup iterations to stabilize performance
     ///
     /// Warmup is critical for accurate performance measurement as it allows
@@ -594,25 +992,52 @@ impl BenchmarkRunner {
     /// The function uses a Tokio `oneshot` channel to ensure the server task has successfully
     /// initialized the transport and is ready to accept connections before the client
     /// proceeds. This prevents race conditions and ensures startup errors are propagated immediately.
-    async fn run_warmup(&self, transport_config: &TransportConfig) -> Result<()> {
-        let mut client_transport = TransportFactory::create(&self.mechanism)?;
+    ///
+    /// With `--capture-warmup` the warmup messages are round trips, returned
+    /// as a [`WarmupReport`].
+    async fn run_warmup(
+        &self,
+        transport_config: &TransportConfig,
+    ) -> Result<(usize, Option<WarmupReport>)> {
+        let mut client_transport = segmentation::wrap(
+            TransportFactory::create(&self.mechanism)?,
+            transport_config.segment_size,
+        );
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self.spawn_server_process(transport_config)?;
+        let mut server = self.spawn_server_process(transport_config)?;
 
         // Wait for the server to signal that it's ready.
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe for warmup")?;
         debug!("Client received server ready signal for warmup");
 
         // --- Client Logic ---
-        client_transport.start_client(transport_config).await?;
+        self.setup_retry
+            .start_client(client_transport.as_mut(), transport_config)
+            .await?;
+        handshake_with_server(
+            client_transport.as_mut(),
+            self.handshake_terms(transport_config),
+        )
+        .await?;
 
-        let payload = vec![0u8; self.config.message_size];
-        for i in 0..self.config.warmup_iterations {
-            let message = Message::new(i as u64, payload.clone(), MessageType::OneWay);
+        let mut capture = self
+            .captures_warmup()
+            .then(|| WarmupCapture::with_capacity(self.warmup.capacity()));
+        let message_type = if capture.is_some() {
+            MessageType::Request
+        } else {
+            MessageType::OneWay
+        };
+        let mut errors = 0;
+        let started = Instant::now();
+        let mut sent = 0;
+        while self.warmup.continues(sent, started) {
+            let message = self.messages.message(sent as u64, message_type, None, 0)?;
+            sent += 1;
+            let send_time = Instant::now();
             client_transport
                 .send(&message)
                 .await
@@ -620,16 +1045,42 @@ impl BenchmarkRunner {
             if let Some(delay) = self.config.send_delay {
                 sleep(delay).await;
             }
+            if let Some(capture) = capture.as_mut() {
+                if receive_response(client_transport.as_mut(), message.id, &mut errors).await? {
+                    capture.record(send_time.elapsed());
+                }
+            }
+        }
+        if errors > 0 {
+            warn!("{} warmup replies did not match their request", errors);
         }
 
         // --- Cleanup ---
         client_transport.close().await?;
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error during warmup")?;
 
         debug!("Warmup completed");
-        Ok(())
+        let report = match capture {
+            Some(capture) => capture.finish(&self.config.percentiles)?,
+            None => None,
+        };
+        Ok((sent, report))
+    }
+
+    /// Whether warmup round trips are captured: with `--capture-warmup`,
+    /// unless the mechanism has no replies to time.
+    fn captures_warmup(&self) -> bool {
+        if !self.args.capture_warmup {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        if self.mechanism == IpcMechanism::UdpMulticast {
+            warn!("-m udp-multicast has no replies; --capture-warmup ignored");
+            return false;
+        }
+        true
     }
 
     /// Spawns the server process for a benchmark run.
@@ -645,13 +1096,10 @@ impl BenchmarkRunner {
     ///
     /// ## Returns
     ///
-    /// - `Ok((Child, PipeReader))`: A tuple containing the handle to the spawned
-    ///   child process and the reader end of the signaling pipe.
+    /// - `Ok(ServerHandle)`: The spawned server; call `wait_ready` before
+    ///   connecting to it.
     /// - `Err(anyhow::Error)`: An error if the pipe creation or process spawning fails.
-    pub fn spawn_server_process(
-        &self,
-        transport_config: &TransportConfig,
-    ) -> Result<(std::process::Child, PipeReader)> {
+    pub fn spawn_server_process(&self, transport_config: &TransportConfig) -> Result<ServerHandle> {
         self.spawn_server_process_with_latency_file(transport_config, None)
     }
 
@@ -667,129 +1115,114 @@ impl BenchmarkRunner {
     ///
     /// ## Returns
     ///
-    /// - `Ok((Child, PipeReader))`: Spawned process and ready signal pipe
+    /// - `Ok(ServerHandle)`: The spawned server
     /// - `Err(anyhow::Error)`: Spawn failure
     pub fn spawn_server_process_with_latency_file(
         &self,
         transport_config: &TransportConfig,
         latency_file_path: Option<&str>,
-    ) -> Result<(std::process::Child, PipeReader)> {
-        let (reader, writer) =
-            os_pipe::pipe().context("Failed to create OS pipe for server signaling")?;
-
-        let current_exe =
-            std::env::current_exe().context("Failed to get current executable path")?;
-
-        // When running tests, `current_exe()` points to the test runner binary,
-        // not the main application binary. We need to find the actual application
-        // binary to spawn the server.
-        // Resolve the path to the main binary robustly at runtime. Integration tests
-        // compile the library without cfg(test), so we cannot rely on cfg!(test).
-        // Strategy:
-        // 1) If current_exe filename matches our binary name, use it.
-        // 2) If CARGO_BIN_EXE_* env var is set, use it.
-        // 3) Fallback to target/debug/<name>[.exe] from manifest dir.
-        let exe_name = "ipc-benchmark";
-        #[cfg(windows)]
-        let exe_name_win = "ipc-benchmark.exe";
-        let mut exe_path = None;
+    ) -> Result<ServerHandle> {
+        let mut cmd = match &self.args.server_remote {
+            Some(_) => ServerHandle::command_at(
+                self.args
+                    .remote_binary
+                    .as_deref()
+                    .unwrap_or("ipc-benchmark"),
+            ),
+            None => ServerHandle::command()?,
+        };
 
-        {
-            let current_name = current_exe.file_name().and_then(|n| n.to_str());
-            let matches_unix = current_name == Some(exe_name);
-            #[cfg(windows)]
-            let matches_win = current_name == Some(exe_name_win);
-            #[cfg(not(windows))]
-            let matches_win = false;
-            if matches_unix || matches_win {
-                exe_path = Some(current_exe.clone());
-            }
-        }
+        // --- Pass all relevant arguments to the server process ---
+        cmd.arg("-m").arg(self.mechanism.name());
+        cmd.arg("-s").arg(self.config.message_size.to_string());
 
-        if exe_path.is_none() {
-            if let Ok(p) = std::env::var("CARGO_BIN_EXE_ipc-benchmark") {
-                let pbuf = std::path::PathBuf::from(p);
-                if pbuf.exists() && pbuf.file_name().and_then(|n| n.to_str()) == Some(exe_name) {
-                    exe_path = Some(pbuf);
-                }
-            } else if let Ok(p) = std::env::var("CARGO_BIN_EXE_ipc_benchmark") {
-                // Some Cargo setups may use underscores in the env var name
-                let pbuf = std::path::PathBuf::from(p);
-                if pbuf.exists() {
-                    exe_path = Some(pbuf);
-                }
-            }
+        if let Some(duration) = self.config.duration {
+            cmd.arg("-d").arg(format!("{}s", duration.as_secs_f64()));
+        } else if let Some(count) = self.config.msg_count {
+            cmd.arg("-i").arg(count.to_string());
         }
 
-        if exe_path.is_none() {
-            let root = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-            #[cfg(windows)]
-            let p = root
-                .join("target")
-                .join("debug")
-                .join(format!("{}{}", exe_name, ".exe"));
-            #[cfg(not(windows))]
-            let p = root.join("target").join("debug").join(exe_name);
-            if p.exists() {
-                exe_path = Some(p);
-            }
+        if let Some(affinity) = self.config.server_affinity {
+            cmd.arg("--server-affinity").arg(affinity.to_string());
         }
 
-        let exe_path = exe_path.ok_or_else(|| {
-            anyhow::anyhow!(
-                "Could not resolve '{}' binary for server mode. Build it with \
-                 `cargo build --bin {}` or run full `cargo test` first.",
-                "ipc-benchmark",
-                "ipc-benchmark"
-            )
-        })?;
+        if self.args.server_batch > 1 {
+            cmd.arg("--server-batch")
+                .arg(self.args.server_batch.to_string());
+        }
 
-        debug!("Spawning server binary: {}", exe_path.display());
-        let mut cmd = Command::new(&exe_path);
+        if let Some(rate) = self.args.server_rate {
+            cmd.arg("--server-rate").arg(rate.to_string());
+        }
 
-        // Connect the writer end of the pipe to the child's stdout so the child
-        // can write a single ready byte which the parent will read from the pipe.
-        cmd.stdin(Stdio::null());
-        #[cfg(unix)]
-        {
-            use std::os::unix::io::IntoRawFd;
-            cmd.stdout(unsafe { Stdio::from_raw_fd(writer.into_raw_fd()) });
+        if let Some(work) = self.args.server_work {
+            cmd.arg("--server-work")
+                .arg(format!("{}us", work.as_micros()));
         }
-        #[cfg(windows)]
-        {
-            cmd.stdout(unsafe { Stdio::from_raw_handle(writer.into_raw_handle()) });
+
+        if let Some(iterations) = self.args.server_spin {
+            cmd.arg("--server-spin").arg(iterations.to_string());
         }
-        cmd.stderr(Stdio::inherit());
 
-        // --- Pass all relevant arguments to the server process ---
-        cmd.arg("--internal-run-as-server");
-        cmd.arg("-m")
-            .arg(self.mechanism.to_possible_value().unwrap().get_name());
-        cmd.arg("-s").arg(self.config.message_size.to_string());
+        if self.args.response_mode != ResponseMode::Ack {
+            cmd.arg("--response-mode")
+                .arg(self.args.response_mode.to_string());
+        }
 
-        if let Some(duration) = self.config.duration {
-            cmd.arg("-d").arg(format!("{}s", duration.as_secs_f64()));
-        } else if let Some(count) = self.config.msg_count {
-            cmd.arg("-i").arg(count.to_string());
+        if let Some(width) = self.args.id_width {
+            cmd.arg("--id-width").arg(width.to_string());
         }
 
-        if let Some(affinity) = self.config.server_affinity {
-            cmd.arg("--server-affinity").arg(affinity.to_string());
+        // The server logs under the same run ID as this process
+        cmd.arg("--run-id").arg(crate::run_id::get());
+
+        // The server reports its receive-side latency at the same percentiles
+        for percentile in &self.config.percentiles {
+            cmd.arg("--percentiles").arg(percentile.to_string());
         }
 
+        cmd.arg("--log-rate-limit")
+            .arg(self.args.log_rate_limit.to_string());
+        // The server retries its own setup under the same policy
+        cmd.arg("--setup-retries")
+            .arg(self.args.setup_retries.to_string());
+        cmd.arg("--setup-backoff")
+            .arg(format!("{}us", self.args.setup_backoff.as_micros()));
+
+        // The server's async runtime matches the client's
+        cmd.args(RuntimeConfig::from_args(&self.args).to_cli_args());
+
         // Pass transport-specific details
         match self.mechanism {
             #[cfg(unix)]
             IpcMechanism::UnixDomainSocket => {
                 cmd.arg("--socket-path").arg(&transport_config.socket_path);
+                if transport_config.uds_credentials != crate::cli::UdsCredentialMode::Off {
+                    cmd.arg("--uds-credentials")
+                        .arg(transport_config.uds_credentials.to_string());
+                }
                 debug!(
                     "Server args: --socket-path {}",
                     transport_config.socket_path
                 );
             }
+            #[cfg(windows)]
+            IpcMechanism::NamedPipe => {
+                cmd.arg("--socket-path").arg(&transport_config.socket_path);
+            }
             IpcMechanism::SharedMemory => {
                 cmd.arg("--shared-memory-name")
                     .arg(&transport_config.shared_memory_name);
+                // The server creates the segment, so it decides the ring layout.
+                if let Some(align) = self.args.shm_align {
+                    cmd.arg("--shm-align").arg(align.to_string());
+                }
+                if self.args.shm_pad_indices {
+                    cmd.arg("--shm-pad-indices").arg("on");
+                }
+                if let Some(messages) = self.args.shm_capacity_messages {
+                    cmd.arg("--shm-capacity-messages").arg(messages.to_string());
+                }
                 debug!(
                     "Server args: --shared-memory-name {}",
                     transport_config.shared_memory_name
@@ -797,7 +1230,18 @@ impl BenchmarkRunner {
             }
             IpcMechanism::TcpSocket => {
                 cmd.arg("--port").arg(transport_config.port.to_string());
-                cmd.arg("--host").arg(&transport_config.host);
+                if self.args.server_remote.is_some() {
+                    cmd.arg("--host").arg(server_handle::REMOTE_LISTEN_HOST);
+                } else {
+                    cmd.arg("--host").arg(&transport_config.host);
+                }
+                if let Some(idle) = transport_config.tcp_keepalive {
+                    cmd.arg("--tcp-keepalive")
+                        .arg(format!("{}us", idle.as_micros()));
+                }
+                if let Some(device) = &transport_config.bind_device {
+                    cmd.arg("--bind-device").arg(device);
+                }
                 debug!(
                     "Server args: --host {} --port {}",
                     transport_config.host, transport_config.port
@@ -812,17 +1256,56 @@ impl BenchmarkRunner {
                     transport_config.message_queue_name
                 );
             }
+            IpcMechanism::Http | IpcMechanism::Grpc => {
+                cmd.arg("--port").arg(transport_config.port.to_string());
+                if self.args.server_remote.is_some() {
+                    cmd.arg("--host").arg(server_handle::REMOTE_LISTEN_HOST);
+                } else {
+                    cmd.arg("--host").arg(&transport_config.host);
+                }
+                if self.mechanism == IpcMechanism::Http {
+                    cmd.arg("--http-version")
+                        .arg(transport_config.http_version.to_string());
+                }
+            }
+            // Blocking-only; main() switches to the blocking runner
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Futex | IpcMechanism::RtSignal | IpcMechanism::Inotify => {}
+            #[cfg(unix)]
+            IpcMechanism::Socketpair => {}
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => {}
             IpcMechanism::All => {} // 'All' is expanded in the main process
         }
 
+        if let Some(size) = transport_config.segment_size {
+            cmd.arg("--segment-size").arg(size.to_string());
+        }
+        if let Some(size) = transport_config.fragment_writes {
+            cmd.arg("--fragment-writes").arg(size.to_string());
+        }
+
         // Add latency file path if provided (for true IPC measurement)
         if let Some(path) = latency_file_path {
             cmd.arg("--internal-latency-file").arg(path);
+            if self.args.first_byte_latency {
+                cmd.arg("--internal-first-byte-file")
+                    .arg(first_byte_file_for(path));
+            }
+            if self.args.deadline.is_some() {
+                cmd.arg("--internal-expired-file")
+                    .arg(expired_file_for(path));
+            }
         }
 
-        let child = cmd.spawn().context("Failed to spawn server process")?;
+        // ssh hands the command line to the remote shell as one string
+        if let Some(target) = &self.args.server_remote {
+            cmd = ServerHandle::remote_command(target, &cmd)?;
+        }
 
-        Ok((child, reader))
+        Ok(ServerHandle::spawn(cmd)?
+            .with_heartbeat_timeout(self.args.heartbeat_timeout)
+            .with_retry_counter(self.setup_retry.counter()))
     }
 
     #[cfg(test)]
@@ -886,15 +1369,19 @@ impl BenchmarkRunner {
     /// - Other mechanisms: Supports both single and multi-threaded execution
     ///
     /// ## Returns
-    /// - `Ok(PerformanceMetrics)`: Comprehensive latency and throughput metrics
+    /// - `Ok((PerformanceMetrics, first_byte))`: Comprehensive latency and
+    ///   throughput metrics, plus the first-byte latency distribution when
+    ///   `--first-byte-latency` is set
     /// - `Err(anyhow::Error)`: Test execution failure
     async fn run_one_way_test(
         &self,
         transport_config: &TransportConfig,
         results_manager: Option<&mut crate::results::ResultsManager>,
-    ) -> Result<PerformanceMetrics> {
+    ) -> Result<(PerformanceMetrics, Option<crate::metrics::LatencyMetrics>)> {
         let mut metrics_collector =
             MetricsCollector::new(Some(LatencyType::OneWay), self.config.percentiles.clone())?;
+        metrics_collector.set_reservoir(self.args.latency_reservoir);
+        let mut first_byte = None;
 
         // Check for problematic configurations and adapt automatically
         // Shared memory currently has race conditions with concurrency > 1
@@ -904,19 +1391,21 @@ impl BenchmarkRunner {
                 "Shared memory with concurrency > 1 has race conditions. Forcing concurrency = 1."
             );
             // Run single-threaded instead
-            self.run_single_threaded_one_way(
-                transport_config,
-                &mut metrics_collector,
-                results_manager,
-            )
-            .await?;
+            first_byte = self
+                .run_single_threaded_one_way(
+                    transport_config,
+                    &mut metrics_collector,
+                    results_manager,
+                )
+                .await?;
         } else if self.config.concurrency == 1 {
-            self.run_single_threaded_one_way(
-                transport_config,
-                &mut metrics_collector,
-                results_manager,
-            )
-            .await?;
+            first_byte = self
+                .run_single_threaded_one_way(
+                    transport_config,
+                    &mut metrics_collector,
+                    results_manager,
+                )
+                .await?;
         } else {
             self.run_multi_threaded_one_way(
                 transport_config,
@@ -926,7 +1415,7 @@ impl BenchmarkRunner {
             .await?;
         }
 
-        Ok(metrics_collector.get_metrics())
+        Ok((metrics_collector.get_metrics(), first_byte))
     }
 
     /// Run round-trip latency test
@@ -959,6 +1448,7 @@ impl BenchmarkRunner {
             Some(LatencyType::RoundTrip),
             self.config.percentiles.clone(),
         )?;
+        metrics_collector.set_reservoir(self.args.latency_reservoir);
 
         // Check for problematic configurations and adapt automatically
         if self.mechanism == IpcMechanism::SharedMemory && self.config.concurrency > 1 {
@@ -1028,8 +1518,11 @@ impl BenchmarkRunner {
         transport_config: &TransportConfig,
         metrics_collector: &mut MetricsCollector,
         mut results_manager: Option<&mut crate::results::ResultsManager>,
-    ) -> Result<()> {
-        let mut client_transport = TransportFactory::create(&self.mechanism)?;
+    ) -> Result<Option<crate::metrics::LatencyMetrics>> {
+        let mut client_transport = segmentation::wrap(
+            TransportFactory::create(&self.mechanism)?,
+            transport_config.segment_size,
+        );
 
         // Create a temporary file for server to write latencies
         let latency_file_path = std::env::temp_dir()
@@ -1041,13 +1534,12 @@ impl BenchmarkRunner {
             .to_string();
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self
+        let mut server = self
             .spawn_server_process_with_latency_file(transport_config, Some(&latency_file_path))?;
 
-        // Wait for the server to signal that it's ready by reading one byte from the pipe.
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        // Wait for the server to signal that it's ready and report its port.
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe")?;
         debug!("Client received server ready signal for one-way test");
 
@@ -1056,9 +1548,22 @@ impl BenchmarkRunner {
         let transport_config_clone = transport_config.clone();
 
         let mechanism_for_err = self.mechanism;
+        let idle_gap = self.args.idle_gap;
+        let deadline = self.args.deadline;
+        let queue_depth_interval = self.args.queue_depth_interval;
+        let pacing = self.args.pacing;
+        let client_affinity = self.config.client_affinity;
+        let pinned_server = self.pinned_server(&server);
+        let liveness = server.liveness();
+        let setup_retry = self.setup_retry.clone();
+        let transport_stats = self.transport_stats.clone();
+        let messages = self.messages.clone();
+        let client_hook = self.client_hook.clone();
+        let terms = self.handshake_terms(transport_config);
         let client_future = async move {
-            client_transport
-                .start_client(&transport_config_clone)
+            let mut client_work = Vec::new();
+            setup_retry
+                .start_client(client_transport.as_mut(), &transport_config_clone)
                 .await
                 .with_context(|| {
                     format!(
@@ -1070,8 +1575,17 @@ port={}",
                         transport_config_clone.port
                     )
                 })?;
+            handshake_with_server(client_transport.as_mut(), terms).await?;
+
+            // Watch the queue fill while the test runs
+            let queue_sampler = QueueDepthSampler::start(
+                client_transport.queue_depth_probe(),
+                queue_depth_interval,
+            );
 
             let payload = vec![0u8; client_config.message_size];
+            let mut pacer = Pacer::for_delay(pacing, client_config.send_delay);
+            let affinity = AffinityMonitor::for_test(client_affinity, pinned_server);
             let start_time = Instant::now();
 
             // Client just sends messages - server measures and records latencies
@@ -1081,8 +1595,15 @@ port={}",
                     let canary = Message::new(u64::MAX, payload.clone(), MessageType::OneWay);
                     let _ = client_transport.send(&canary).await;
                 }
-                while start_time.elapsed() < duration {
-                    let message = Message::new(i, payload.clone(), MessageType::OneWay);
+                while start_time.elapsed() < duration && !shutdown::requested() {
+                    if let Some(gap) = idle_gap {
+                        sleep(gap).await;
+                    }
+                    let mut message = messages.message(i, MessageType::OneWay, deadline, 0)?;
+                    if let Some(hook) = &client_hook {
+                        client_work.push(hook::run_timed(hook.as_ref(), &message));
+                        message.set_timestamp_now();
+                    }
                     match tokio::time::timeout(
                         Duration::from_millis(50),
                         client_transport.send(&message),
@@ -1090,13 +1611,19 @@ port={}",
                     .await
                     {
                         Ok(Ok(_)) => {
+                            messages.recycle(message);
                             i += 1;
-                            if let Some(delay) = client_config.send_delay {
-                                sleep(delay).await;
+                            if let Some(pacer) = pacer.as_mut() {
+                                pacer.pace().await;
                             }
                         }
                         Ok(Err(_)) => break,
                         Err(_) => {
+                            // A send that keeps timing out may be waiting
+                            // on a server that is gone
+                            if let Some(liveness) = &liveness {
+                                liveness.check()?;
+                            }
                             sleep(Duration::from_millis(1)).await;
                             continue;
                         }
@@ -1112,24 +1639,48 @@ port={}",
                 }
 
                 for i in 0..msg_count {
-                    let message = Message::new(i as u64, payload.clone(), MessageType::OneWay);
+                    if shutdown::requested() {
+                        break;
+                    }
+                    if let Some(gap) = idle_gap {
+                        sleep(gap).await;
+                    }
+                    let mut message =
+                        messages.message(i as u64, MessageType::OneWay, deadline, 0)?;
+                    if let Some(hook) = &client_hook {
+                        client_work.push(hook::run_timed(hook.as_ref(), &message));
+                        message.set_timestamp_now();
+                    }
                     let _ = client_transport.send(&message).await?;
-                    if let Some(delay) = client_config.send_delay {
-                        sleep(delay).await;
+                    messages.recycle(message);
+                    if let Some(pacer) = pacer.as_mut() {
+                        pacer.pace().await;
                     }
                 }
             }
+            let queue_depth = queue_sampler.and_then(QueueDepthSampler::finish);
+            transport_stats.add(client_transport.stats());
             client_transport.close().await?;
-            Ok::<(), anyhow::Error>(())
+            Ok::<_, anyhow::Error>((
+                queue_depth,
+                pacer.and_then(Pacer::finish),
+                affinity.map(AffinityMonitor::finish).unwrap_or_default(),
+                client_work,
+            ))
         };
 
         // Execute client work with proper affinity using spawn_with_affinity
-        crate::utils::spawn_with_affinity(client_future, self.config.client_affinity).await?;
+        let (queue_depth, pacing, affinity, client_work) =
+            crate::utils::spawn_with_affinity(client_future, self.config.client_affinity).await?;
+        metrics_collector.set_queue_depth(queue_depth);
+        metrics_collector.set_pacing(pacing);
+        metrics_collector.set_affinity(affinity);
 
         // --- Cleanup ---
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error")?;
+        metrics_collector.set_server(server.take_report());
 
         // --- Read server-measured latencies from file ---
         debug!(
@@ -1156,6 +1707,9 @@ port={}",
             let latency = Duration::from_nanos(latency_ns);
 
             metrics_collector.record_message(self.config.message_size, Some(latency))?;
+            if let Some(&work) = client_work.get(line_num as usize) {
+                metrics_collector.record_processing(work, Some(latency))?;
+            }
 
             if let Some(ref mut manager) = results_manager {
                 let record = crate::results::MessageLatencyRecord::new(
@@ -1176,7 +1730,19 @@ port={}",
         // Clean up temporary latency file
         let _ = tokio::fs::remove_file(&latency_file_path).await;
 
-        Ok(())
+        if self.args.deadline.is_some() {
+            metrics_collector.expired_count +=
+                read_expired_count(&expired_file_for(&latency_file_path));
+        }
+
+        if !self.args.first_byte_latency {
+            return Ok(None);
+        }
+        read_first_byte_latencies(
+            &first_byte_file_for(&latency_file_path),
+            self.config.message_size,
+            &self.config.percentiles,
+        )
     }
 
     /// Run single-threaded round-trip test
@@ -1188,29 +1754,32 @@ port={}",
     /// ## Round-trip Protocol
     ///
     /// 1. **Client sends request**: Message with Request type
-    /// 2. **Server processes and responds**: Increments message ID and sends Response
-    /// 3. **Client receives response**: Completes timing measurement
+    /// 2. **Server processes and responds**: Echoes the message ID in a Response
+    /// 3. **Client receives response**: Checks the ID and completes timing
     ///
     /// ## Latency Measurement
     ///
     /// Round-trip latency is measured from the start of the send operation
     /// to the completion of the receive operation, capturing the complete
-    /// communication cycle including any processing delays.
+    /// communication cycle including any processing delays. Replies that do
+    /// not answer the outstanding request are counted as errors, not timed.
     async fn run_single_threaded_round_trip(
         &self,
         transport_config: &TransportConfig,
         metrics_collector: &mut MetricsCollector,
         mut results_manager: Option<&mut crate::results::ResultsManager>,
     ) -> Result<()> {
-        let mut client_transport = TransportFactory::create(&self.mechanism)?;
+        let mut client_transport = segmentation::wrap(
+            TransportFactory::create(&self.mechanism)?,
+            transport_config.segment_size,
+        );
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self.spawn_server_process(transport_config)?;
+        let mut server = self.spawn_server_process(transport_config)?;
 
         // Wait for the server to signal that it's ready.
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe")?;
         debug!("Client received server ready signal for round-trip test");
 
@@ -1218,13 +1787,33 @@ port={}",
         let client_config = self.config.clone();
         let transport_config_clone = transport_config.clone();
 
+        let idle_gap = self.args.idle_gap;
+        let deadline = self.args.deadline;
+        let queue_depth_interval = self.args.queue_depth_interval;
+        let pacing = self.args.pacing;
+        let client_affinity = self.config.client_affinity;
+        let pinned_server = self.pinned_server(&server);
+        let liveness = server.liveness();
+        let setup_retry = self.setup_retry.clone();
+        let transport_stats = self.transport_stats.clone();
+        let messages = self.messages.clone();
+        let client_hook = self.client_hook.clone();
+        let terms = self.handshake_terms(transport_config);
         let client_future = async move {
-            let mut latencies: Vec<(Duration, u64)> = Vec::new();
-            client_transport
-                .start_client(&transport_config_clone)
+            let mut latencies: Vec<(Duration, u64, Option<Duration>)> = Vec::new();
+            let mut errors = 0usize;
+            setup_retry
+                .start_client(client_transport.as_mut(), &transport_config_clone)
                 .await?;
+            handshake_with_server(client_transport.as_mut(), terms).await?;
+            let queue_sampler = QueueDepthSampler::start(
+                client_transport.queue_depth_probe(),
+                queue_depth_interval,
+            );
 
             let payload = vec![0u8; client_config.message_size];
+            let mut pacer = Pacer::for_delay(pacing, client_config.send_delay);
+            let affinity = AffinityMonitor::for_test(client_affinity, pinned_server);
             let start_time = Instant::now();
 
             if let Some(duration) = client_config.duration {
@@ -1236,10 +1825,13 @@ port={}",
                     }
                 }
 
-                while start_time.elapsed() < duration {
+                while start_time.elapsed() < duration && !shutdown::requested() {
+                    if let Some(gap) = idle_gap {
+                        sleep(gap).await;
+                    }
                     let wall_ts = crate::results::MessageLatencyRecord::current_timestamp_ns();
                     let send_time = Instant::now();
-                    let message = Message::new(i, payload.clone(), MessageType::Request);
+                    let message = messages.message(i, MessageType::Request, deadline, 0)?;
 
                     match tokio::time::timeout(
                         Duration::from_millis(50),
@@ -1249,55 +1841,118 @@ port={}",
                     {
                         Ok(Ok(_)) => {
                             i += 1;
-                            if let Some(delay) = client_config.send_delay {
-                                sleep(delay).await;
+                            if let Some(pacer) = pacer.as_mut() {
+                                pacer.pace().await;
                             }
-                            if tokio::time::timeout(
+                            // A reply that misses the timeout is drained as
+                            // stale while awaiting a later request
+                            let matched = tokio::time::timeout(
                                 Duration::from_millis(50),
-                                client_transport.receive(),
+                                receive_response(
+                                    client_transport.as_mut(),
+                                    message.id,
+                                    &mut errors,
+                                ),
                             )
-                            .await
-                            .is_ok()
-                            {
-                                latencies.push((send_time.elapsed(), wall_ts));
+                            .await;
+                            match matched {
+                                Ok(Ok(true)) => {
+                                    let latency = send_time.elapsed();
+                                    let work = client_hook
+                                        .as_ref()
+                                        .map(|hook| hook::run_timed(hook.as_ref(), &message));
+                                    latencies.push((latency, wall_ts, work));
+                                }
+                                // No reply in time; the server may be gone
+                                Err(_) => {
+                                    if let Some(liveness) = &liveness {
+                                        liveness.check()?;
+                                    }
+                                }
+                                _ => {}
                             }
+                            messages.recycle(message);
                         }
                         _ => {
+                            if let Some(liveness) = &liveness {
+                                liveness.check()?;
+                            }
                             sleep(Duration::from_millis(1)).await;
                         }
                     }
                 }
             } else {
                 let msg_count = client_config.msg_count.unwrap_or_default();
-                let iterations = if client_config.include_first_message {
-                    msg_count
-                } else {
-                    msg_count + 1
-                };
-                for i in 0..iterations {
+                if !client_config.include_first_message {
+                    let canary = Message::new(u64::MAX, payload.clone(), MessageType::Request);
+                    if client_transport.send(&canary).await.is_ok() {
+                        let _ = client_transport.receive().await;
+                    }
+                }
+                for i in 0..msg_count {
+                    if shutdown::requested() {
+                        break;
+                    }
+                    if let Some(gap) = idle_gap {
+                        sleep(gap).await;
+                    }
                     let wall_ts = crate::results::MessageLatencyRecord::current_timestamp_ns();
                     let send_time = Instant::now();
-                    let message = Message::new(i as u64, payload.clone(), MessageType::Request);
+                    let message = messages.message(i as u64, MessageType::Request, deadline, 0)?;
                     client_transport.send(&message).await?;
-                    if let Some(delay) = client_config.send_delay {
-                        sleep(delay).await;
+                    if let Some(pacer) = pacer.as_mut() {
+                        pacer.pace().await;
                     }
-                    client_transport.receive().await?;
-                    if i > 0 || client_config.include_first_message {
-                        latencies.push((send_time.elapsed(), wall_ts));
+                    let matched =
+                        receive_response(client_transport.as_mut(), message.id, &mut errors)
+                            .await?;
+                    if matched {
+                        let latency = send_time.elapsed();
+                        let work = client_hook
+                            .as_ref()
+                            .map(|hook| hook::run_timed(hook.as_ref(), &message));
+                        latencies.push((latency, wall_ts, work));
                     }
+                    messages.recycle(message);
                 }
             }
+            let queue_depth = queue_sampler.and_then(QueueDepthSampler::finish);
+            transport_stats.add(client_transport.stats());
             client_transport.close().await?;
-            Ok::<Vec<(Duration, u64)>, anyhow::Error>(latencies)
+            Ok::<_, anyhow::Error>((
+                latencies,
+                errors,
+                queue_depth,
+                pacer.and_then(Pacer::finish),
+                affinity.map(AffinityMonitor::finish).unwrap_or_default(),
+            ))
         };
 
         // Execute client work with proper affinity using spawn_with_affinity
-        let latencies =
+        let (latencies, errors, queue_depth, pacing, affinity) =
             crate::utils::spawn_with_affinity(client_future, self.config.client_affinity).await?;
+        metrics_collector.error_count += errors;
+        metrics_collector.set_queue_depth(queue_depth);
+        metrics_collector.set_pacing(pacing);
+        metrics_collector.set_affinity(affinity);
+        if errors > 0 {
+            warn!("{} round-trip replies did not match their request", errors);
+        }
 
-        for (i, (latency, wall_ts)) in latencies.iter().enumerate() {
+        for (i, (latency, wall_ts, work)) in latencies.iter().enumerate() {
+            // A reply past the request's deadline is dropped
+            if self
+                .args
+                .deadline
+                .is_some_and(|deadline| *latency > deadline)
+            {
+                metrics_collector.expired_count += 1;
+                continue;
+            }
             metrics_collector.record_message(self.config.message_size, Some(*latency))?;
+            if let Some(work) = *work {
+                metrics_collector.record_processing(work, Some(*latency))?;
+            }
             if let Some(ref mut manager) = results_manager {
                 let record = crate::results::MessageLatencyRecord::new(
                     i as u64,
@@ -1312,9 +1967,10 @@ port={}",
         }
 
         // --- Cleanup ---
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error")?;
+        metrics_collector.set_server(server.take_report());
         Ok(())
     }
 
@@ -1361,6 +2017,7 @@ port={}",
 
             let mut worker_metrics =
                 MetricsCollector::new(Some(LatencyType::OneWay), self.config.percentiles.clone())?;
+            worker_metrics.set_reservoir(self.args.latency_reservoir);
 
             // Run single-threaded test for this worker
             // Note: Passing None for results_manager to avoid duplicate streaming in simulated multi-threading
@@ -1428,6 +2085,7 @@ port={}",
                 Some(LatencyType::RoundTrip),
                 self.config.percentiles.clone(),
             )?;
+            worker_metrics.set_reservoir(self.args.latency_reservoir);
 
             // Run single-threaded test for this worker
             // Note: Passing None for results_manager to avoid duplicate streaming in simulated multi-threading
@@ -1485,6 +2143,8 @@ port={}",
             Some(LatencyType::RoundTrip),
             self.config.percentiles.clone(),
         )?;
+        one_way_metrics.set_reservoir(self.args.latency_reservoir);
+        round_trip_metrics.set_reservoir(self.args.latency_reservoir);
 
         // Check for problematic configurations and adapt automatically
         if self.mechanism == IpcMechanism::SharedMemory && self.config.concurrency > 1 {
@@ -1519,15 +2179,17 @@ port={}",
         round_trip_metrics: &mut MetricsCollector,
         mut results_manager: Option<&mut crate::results::ResultsManager>,
     ) -> Result<()> {
-        let mut client_transport = TransportFactory::create(&self.mechanism)?;
+        let mut client_transport = segmentation::wrap(
+            TransportFactory::create(&self.mechanism)?,
+            transport_config.segment_size,
+        );
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self.spawn_server_process(transport_config)?;
+        let mut server = self.spawn_server_process(transport_config)?;
 
         // Wait for the server to signal that it's ready.
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe")?;
         debug!("Client received server ready signal for combined test");
 
@@ -1535,32 +2197,52 @@ port={}",
         let client_config = self.config.clone();
         let transport_config_clone = transport_config.clone();
 
+        let idle_gap = self.args.idle_gap;
+        let deadline = self.args.deadline;
+        let setup_retry = self.setup_retry.clone();
+        let transport_stats = self.transport_stats.clone();
+        let messages = self.messages.clone();
+        let client_hook = self.client_hook.clone();
+        let terms = self.handshake_terms(transport_config);
         let client_future = async move {
             let mut one_way_latencies: Vec<(Duration, u64)> = Vec::new();
             let mut round_trip_latencies: Vec<Duration> = Vec::new();
-            client_transport
-                .start_client(&transport_config_clone)
+            let mut client_work: Vec<Duration> = Vec::new();
+            let mut errors = 0usize;
+            setup_retry
+                .start_client(client_transport.as_mut(), &transport_config_clone)
                 .await?;
+            handshake_with_server(client_transport.as_mut(), terms).await?;
 
-            let payload = vec![0u8; client_config.message_size];
             let start_time = Instant::now();
 
             if let Some(duration) = client_config.duration {
                 let mut i = 0u64;
-                while start_time.elapsed() < duration {
+                while start_time.elapsed() < duration && !shutdown::requested() {
+                    if let Some(gap) = idle_gap {
+                        sleep(gap).await;
+                    }
                     let wall_ts = crate::results::MessageLatencyRecord::current_timestamp_ns();
                     let send_start = Instant::now();
-                    let message = Message::new(i, payload.clone(), MessageType::Request);
+                    let message = messages.message(i, MessageType::Request, deadline, 0)?;
 
                     if client_transport.send(&message).await.is_ok() {
                         let one_way_latency = send_start.elapsed();
-                        if client_transport.receive().await.is_ok() {
-                            let round_trip_latency = send_start.elapsed();
-                            one_way_latencies.push((one_way_latency, wall_ts));
-                            round_trip_latencies.push(round_trip_latency);
-                            i += 1;
-                        } else {
-                            break;
+                        match receive_response(client_transport.as_mut(), message.id, &mut errors)
+                            .await
+                        {
+                            Ok(matched) => {
+                                let round_trip_latency = send_start.elapsed();
+                                if matched {
+                                    one_way_latencies.push((one_way_latency, wall_ts));
+                                    round_trip_latencies.push(round_trip_latency);
+                                    if let Some(hook) = &client_hook {
+                                        client_work.push(hook::run_timed(hook.as_ref(), &message));
+                                    }
+                                }
+                                i += 1;
+                            }
+                            Err(_) => break,
                         }
                     } else {
                         break;
@@ -1569,27 +2251,42 @@ port={}",
             } else {
                 let msg_count = client_config.msg_count.unwrap_or_default();
                 for i in 0..msg_count {
+                    if shutdown::requested() {
+                        break;
+                    }
+                    if let Some(gap) = idle_gap {
+                        sleep(gap).await;
+                    }
                     let wall_ts = crate::results::MessageLatencyRecord::current_timestamp_ns();
                     let send_start = Instant::now();
-                    let message = Message::new(i as u64, payload.clone(), MessageType::Request);
+                    let message = messages.message(i as u64, MessageType::Request, deadline, 0)?;
                     client_transport.send(&message).await?;
                     let one_way_latency = send_start.elapsed();
-                    client_transport.receive().await?;
+                    let matched =
+                        receive_response(client_transport.as_mut(), message.id, &mut errors)
+                            .await?;
                     let round_trip_latency = send_start.elapsed();
-                    one_way_latencies.push((one_way_latency, wall_ts));
-                    round_trip_latencies.push(round_trip_latency);
+                    if matched {
+                        one_way_latencies.push((one_way_latency, wall_ts));
+                        round_trip_latencies.push(round_trip_latency);
+                        if let Some(hook) = &client_hook {
+                            client_work.push(hook::run_timed(hook.as_ref(), &message));
+                        }
+                    }
                 }
             }
+            transport_stats.add(client_transport.stats());
             client_transport.close().await?;
-            Ok::<(Vec<(Duration, u64)>, Vec<Duration>), anyhow::Error>((
-                one_way_latencies,
-                round_trip_latencies,
-            ))
+            Ok::<_, anyhow::Error>((one_way_latencies, round_trip_latencies, client_work, errors))
         };
 
         // Execute client work with proper affinity
-        let (one_way_latencies, round_trip_latencies) =
+        let (one_way_latencies, round_trip_latencies, client_work, errors) =
             crate::utils::spawn_with_affinity(client_future, self.config.client_affinity).await?;
+        round_trip_metrics.error_count += errors;
+        if errors > 0 {
+            warn!("{} round-trip replies did not match their request", errors);
+        }
 
         for (i, (one_way_latency, wall_ts)) in one_way_latencies.iter().enumerate() {
             one_way_metrics.record_message(self.config.message_size, Some(*one_way_latency))?;
@@ -1606,18 +2303,39 @@ port={}",
             }
         }
 
-        for round_trip_latency in &round_trip_latencies {
+        for (i, round_trip_latency) in round_trip_latencies.iter().enumerate() {
+            // A reply past the request's deadline is dropped
+            if self
+                .args
+                .deadline
+                .is_some_and(|deadline| *round_trip_latency > deadline)
+            {
+                round_trip_metrics.expired_count += 1;
+                continue;
+            }
             round_trip_metrics
                 .record_message(self.config.message_size, Some(*round_trip_latency))?;
+            if let Some(&work) = client_work.get(i) {
+                round_trip_metrics.record_processing(work, Some(*round_trip_latency))?;
+            }
         }
 
         // --- Cleanup ---
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error")?;
+        // The server answered every request, so its report goes with the
+        // round trips
+        round_trip_metrics.set_server(server.take_report());
         Ok(())
     }
 
+    /// The transport configuration this runner's tests will use, with its
+    /// profile and the host's transport limits applied.
+    pub fn resolved_transport_config(&self) -> Result<TransportConfig> {
+        self.create_transport_config_internal(&self.args)
+    }
+
     /// Create transport configuration with intelligent parameter adaptation
     ///
     /// This function creates a transport configuration that is optimized for
@@ -1628,7 +2346,7 @@ port={}",
     ///
     /// - **Unique Identifiers**: Uses UUIDs to prevent resource conflicts
     /// - **Adaptive Buffer Sizing**: Adjusts buffer sizes based on test parameters
-    /// - **Port Uniqueness**: Ensures unique ports for TCP to avoid conflicts
+    /// - **Ephemeral Ports**: TCP servers bind port 0 so tests never collide on a port
     /// - **Mechanism-Specific Tuning**: Applies optimizations for each transport type
     ///
     /// ## Buffer Size Calculation
@@ -1639,21 +2357,18 @@ port={}",
     ///   2× message size when a single message exceeds 32 KB).
     ///   This enables streaming where the writer blocks when the
     ///   buffer is full, rather than dumping all data at once.
-    /// - If in duration mode and mechanism is TCP/UDS, use 1 GB
-    ///   to avoid backpressure during timed runs.
+    ///   With `--shm-capacity-messages N`, size the ring to hold
+    ///   exactly N messages instead.
+    /// - If in duration mode and mechanism is TCP/UDS, use the same
+    ///   bounded streaming buffer so timed runs see real backpressure.
     /// - Otherwise (TCP/UDS msg-count mode), size the buffer to
     ///   fit all messages: `msg_count × (msg_size + 64)`.
     pub fn create_transport_config_internal(&self, args: &Args) -> Result<TransportConfig> {
-        const DURATION_MODE_BUFFER_SIZE: usize = 1_073_741_824; // 1 GB
         const PMQ_SAFE_DEFAULT_BUFFER_SIZE: usize = 8192;
 
         let unique_id = Uuid::new_v4();
         // Use shortened UUID for socket paths to stay within macOS SUN_LEN limit (104 bytes)
         let short_id = &unique_id.to_string()[..8];
-        let port_offset = (unique_id.as_u128() % 1000) as u32;
-        // Keep computed ports within valid TCP range [1, 65535] without overflow.
-        let base_port = u32::from(self.config.port.max(1));
-        let unique_port = ((base_port - 1 + port_offset) % 65_535 + 1) as u16;
 
         // Determine if the current mechanism is PMQ
         let is_pmq = {
@@ -1667,6 +2382,8 @@ port={}",
             }
         };
 
+        let limits = TransportLimits::discover(&self.mechanism);
+
         // Buffer size logic:
         // 1. If user provides --buffer-size, use it directly.
         // 2. If the mechanism is PMQ, always use a safe, small default.
@@ -1674,25 +2391,38 @@ port={}",
         //    to enable proper streaming behavior. Previously, sizing to fit all messages
         //    caused the writer to dump everything instantly while the reader slowly drained,
         //    leading to huge accumulated latencies.
-        // 4. If in duration mode, use a large fixed size to avoid backpressure.
+        //    With --shm-capacity-messages, size the ring to hold exactly that many messages.
+        // 4. If in duration mode, use the same bounded streaming buffer so that senders
+        //    see realistic backpressure instead of an effectively unbounded buffer.
         // 5. Otherwise, calculate based on message count (for UDS/TCP which handle backpressure well).
         let is_shm = self.mechanism == IpcMechanism::SharedMemory;
-        const SHM_DEFAULT_BUFFER_SIZE: usize = 65536; // 64KB - matches H2C behavior
-                                                      // Per-message overhead for buffer sizing: 8 (id) + 8
-                                                      // (timestamp) + 8 (bincode vec length) + 1 (message
-                                                      // type) + 4 (ring buffer length prefix) = 29 bytes,
-                                                      // rounded up to 64 for alignment and safety margin.
+        const STREAMING_BUFFER_SIZE: usize = 65536; // 64KB - matches H2C behavior
+                                                    // Per-message overhead for buffer sizing: 8 (id) + 8
+                                                    // (timestamp) + 8 (bincode vec length) + 1 (message
+                                                    // type) + 12 (ring buffer record header) = 37 bytes,
+                                                    // rounded up to 64 for alignment and safety margin.
         const MESSAGE_OVERHEAD: usize = 64;
 
+        let streaming_buffer_size = std::cmp::max(
+            STREAMING_BUFFER_SIZE,
+            (self.config.message_size + MESSAGE_OVERHEAD) * 2,
+        );
         let buffer_size = self.config.buffer_size.unwrap_or_else(|| {
             if is_pmq {
                 PMQ_SAFE_DEFAULT_BUFFER_SIZE
             } else if is_shm {
                 // Use fixed buffer for SHM to enable streaming, not batching
-                let min_for_message = (self.config.message_size + MESSAGE_OVERHEAD) * 2;
-                std::cmp::max(SHM_DEFAULT_BUFFER_SIZE, min_for_message)
+                match args.shm_capacity_messages {
+                    #[cfg(feature = "shm")]
+                    Some(messages) => crate::ipc::shared_memory::ring_bytes_for_messages(
+                        messages,
+                        self.config.message_size + MESSAGE_OVERHEAD,
+                        args.shm_align.unwrap_or(1),
+                    ),
+                    _ => streaming_buffer_size,
+                }
             } else if self.config.duration.is_some() {
-                DURATION_MODE_BUFFER_SIZE
+                streaming_buffer_size
             } else {
                 // For message-count mode with UDS/TCP, size buffer to fit all messages.
                 // These mechanisms handle backpressure via kernel buffers.
@@ -1703,7 +2433,7 @@ port={}",
         // Add a specific validation for PMQ, as it's often limited by the OS.
         // This check is important regardless of how the buffer size was determined.
         #[cfg(target_os = "linux")]
-        if self.mechanism == IpcMechanism::PosixMessageQueue {
+        if self.mechanism == IpcMechanism::PosixMessageQueue && limits.pmq_msgsize_max.is_none() {
             // PMQ has small system limits, so warn if the buffer is large.
             if buffer_size > PMQ_SAFE_DEFAULT_BUFFER_SIZE {
                 warn!(
@@ -1760,11 +2490,31 @@ port={}",
             }
         };
 
+        // Fit the configuration to the host's limits before anything is created
+        let (buffer_size, adaptive_queue_depth) = limits.apply(
+            &self.mechanism,
+            buffer_size,
+            self.config.buffer_size.is_some(),
+            adaptive_queue_depth,
+        )?;
+
         Ok(TransportConfig {
             buffer_size,
-            host: self.config.host.clone(),
-            port: unique_port,
+            host: match &args.server_remote {
+                Some(target) => server_handle::remote_connect_host(target, &self.config.host),
+                None => self.config.host.clone(),
+            },
+            // The spawned server binds an ephemeral port and reports it back
+            // through the readiness pipe; see `read_server_ready`.
+            port: 0,
             socket_path: args.socket_path.clone().unwrap_or_else(|| {
+                #[cfg(windows)]
+                if self.mechanism == IpcMechanism::NamedPipe {
+                    return crate::ipc::named_pipe_blocking::pipe_path(&format!(
+                        "ipc_{}",
+                        short_id
+                    ));
+                }
                 get_temp_dir()
                     .join(format!("ipc_{}.sock", short_id))
                     .to_string_lossy()
@@ -1781,6 +2531,53 @@ port={}",
                 .clone()
                 .unwrap_or_else(|| format!("/ipc_benchmark_pmq_{}", unique_id)),
             pmq_priority: self.config.pmq_priority,
+            shm_slot_align: args.shm_align.unwrap_or(1),
+            shm_pad_indices: args.shm_pad_indices,
+            shm_capacity_messages: args.shm_capacity_messages,
+            shm_priority_lanes: !args.priorities.is_empty(),
+            shm_zero_copy: args.zero_copy,
+            shm_wait: args.shm_wait,
+            shm_hugetlbfs: args.shm_hugetlbfs.clone(),
+            http_version: args.http_version,
+            uds_credentials: args.uds_credentials,
+            tcp_keepalive: args.tcp_keepalive,
+            bind_device: args.bind_device.clone(),
+            source_addr: args.source_addr,
+            inherited_fd: None,
+            multicast_group: args
+                .multicast_group
+                .unwrap_or(crate::defaults::MULTICAST_GROUP),
+            first_byte_timestamps: args.internal_first_byte_file.is_some(),
+            fragment_writes: args.fragment_writes,
+            verify_frames: args.verify_frames,
+            segment_size: resolve_segment_size(
+                args,
+                &self.mechanism,
+                self.config.message_size,
+                buffer_size,
+            ),
+        })
+    }
+
+    /// The spawned server's process and the core it pinned itself to, for
+    /// the affinity check; `None` when unpinned or remote.
+    fn pinned_server(&self, server: &ServerHandle) -> Option<(u32, usize)> {
+        let core = self.config.server_affinity?;
+        self.args
+            .server_remote
+            .is_none()
+            .then(|| (server.id(), core))
+    }
+
+    /// Terms this client proposes in its handshake with the server, or
+    /// `None` if the mechanism has no reply path to handshake over.
+    fn handshake_terms(&self, transport_config: &TransportConfig) -> Option<Handshake> {
+        handshake::has_reply_path(&self.mechanism).then(|| {
+            Handshake::new(
+                self.config.message_size as u64,
+                transport_config.segment_size.is_some(),
+            )
+            .with_frame_checksums(transport_config.verify_frames)
         })
     }
 
@@ -1855,13 +2652,8 @@ mod tests {
     fn candidate_includes_fallback_target_debug() {
         let current = PathBuf::from("/not/matching/name");
         let cands = BenchmarkRunner::candidate_server_binaries_for_test(&current, |_| None);
-        let cand_strs: Vec<_> = cands
-            .iter()
-            .map(|p| p.to_string_lossy().to_string())
-            .collect();
         #[cfg(windows)]
         {
-            use std::path::Path;
             assert!(cands.iter().any(|p| {
                 let file_ok = p
                     .file_name()
@@ -1886,6 +2678,10 @@ mod tests {
         }
         #[cfg(not(windows))]
         {
+            let cand_strs: Vec<_> = cands
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect();
             assert!(cand_strs
                 .iter()
                 .any(|s| s.ends_with("target/debug/ipc-benchmark")));
@@ -1924,6 +2720,24 @@ mod tests {
             message_queue_depth: 10,
             message_queue_name: "/pmq-x".into(),
             pmq_priority: 0,
+            shm_slot_align: 1,
+            shm_pad_indices: false,
+            shm_capacity_messages: None,
+            shm_priority_lanes: false,
+            shm_zero_copy: false,
+            shm_wait: crate::cli::ShmWait::Condvar,
+            shm_hugetlbfs: None,
+            http_version: crate::cli::HttpVersion::H1,
+            uds_credentials: crate::cli::UdsCredentialMode::Off,
+            tcp_keepalive: None,
+            first_byte_timestamps: false,
+            segment_size: None,
+            fragment_writes: None,
+            verify_frames: false,
+            bind_device: None,
+            source_addr: None,
+            inherited_fd: None,
+            multicast_group: crate::defaults::MULTICAST_GROUP,
         };
         let display = format!(
             "{}",
@@ -2120,9 +2934,9 @@ mod tests {
         let config = BenchmarkConfig::from_args(&args).unwrap();
         let runner = BenchmarkRunner::new(config, IpcMechanism::TcpSocket, args.clone());
         let transport_config = runner.create_transport_config_internal(&args).unwrap();
-        let (mut child, _reader) = runner.spawn_server_process(&transport_config).unwrap();
+        let mut server = runner.spawn_server_process(&transport_config).unwrap();
         tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-        let _ = child.kill();
+        let _ = server.kill();
     }
 
     /// Spawn server for Shared Memory wiring
@@ -2141,9 +2955,9 @@ mod tests {
         let config = BenchmarkConfig::from_args(&args).unwrap();
         let runner = BenchmarkRunner::new(config, IpcMechanism::SharedMemory, args.clone());
         let transport_config = runner.create_transport_config_internal(&args).unwrap();
-        let (mut child, _reader) = runner.spawn_server_process(&transport_config).unwrap();
+        let mut server = runner.spawn_server_process(&transport_config).unwrap();
         tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-        let _ = child.kill();
+        let _ = server.kill();
     }
 
     /// Simulated multi-threaded path (Tcp) and SHM fallback to single-thread
@@ -2264,12 +3078,12 @@ mod tests {
     /// Test the buffer size logic in `create_transport_config` is platform-aware.
     #[test]
     fn test_transport_config_buffer_size_logic() {
-        const DURATION_MODE_BUFFER_SIZE: usize = 1_073_741_824; // 1 GB
         #[cfg(target_os = "linux")]
         const PMQ_SAFE_DEFAULT_BUFFER_SIZE: usize = 8192;
 
         // Helper to get only the mechanisms available on the current platform.
         fn get_platform_mechanisms() -> Vec<IpcMechanism> {
+            #[allow(unused_mut)]
             let mut mechanisms = vec![IpcMechanism::SharedMemory, IpcMechanism::TcpSocket];
             #[cfg(unix)]
             mechanisms.push(IpcMechanism::UnixDomainSocket);
@@ -2304,6 +3118,12 @@ mod tests {
         base_config.buffer_size = Some(user_size);
         for mechanism in get_platform_mechanisms() {
             let runner = BenchmarkRunner::new(base_config.clone(), mechanism, args.clone());
+            // An explicit size the host cannot support is rejected rather than clamped.
+            let limits = crate::ipc::limits::TransportLimits::discover(&mechanism);
+            if limits.pmq_msgsize_max.is_some_and(|max| user_size > max) {
+                assert!(runner.create_transport_config_internal(&args).is_err());
+                continue;
+            }
             let transport_config = runner.create_transport_config_internal(&args).unwrap();
             assert_eq!(
                 transport_config.buffer_size, user_size,
@@ -2331,6 +3151,7 @@ mod tests {
 
         // Scenario 2b: TCP/UDS still size buffer for all messages.
         let expected_msg_count_auto_size = 10000 * (1024 + 64);
+        #[allow(unused_mut)]
         let mut auto_sized_mechanisms = vec![IpcMechanism::TcpSocket];
         #[cfg(unix)]
         auto_sized_mechanisms.push(IpcMechanism::UnixDomainSocket);
@@ -2345,15 +3166,16 @@ mod tests {
             );
         }
 
-        // Scenario 3: Automatic buffer size for duration mode (non-PMQ).
+        // Scenario 3: Duration mode (non-PMQ) uses the bounded streaming buffer.
         base_config.duration = Some(Duration::from_secs(1));
         base_config.msg_count = None;
         for mechanism in &auto_sized_mechanisms {
             let runner = BenchmarkRunner::new(base_config.clone(), *mechanism, args.clone());
             let transport_config = runner.create_transport_config_internal(&args).unwrap();
             assert_eq!(
-                transport_config.buffer_size, DURATION_MODE_BUFFER_SIZE,
-                "Automatic buffer size should be the large default for duration mode on {:?}",
+                transport_config.buffer_size,
+                std::cmp::max(65536, (base_config.message_size + 64) * 2),
+                "Duration mode should use the bounded streaming buffer on {:?}",
                 mechanism
             );
         }
@@ -2437,8 +3259,8 @@ mod tests {
         );
     }
 
-    /// Verify SHM in duration mode still gets the fixed 64 KB
-    /// buffer, not the 1 GB duration default used by TCP/UDS.
+    /// Verify SHM in duration mode gets the fixed 64 KB buffer
+    /// rather than anything sized from the run length.
     #[test]
     fn test_shm_duration_mode_uses_fixed_buffer() {
         let config = BenchmarkConfig {
@@ -2467,11 +3289,57 @@ mod tests {
         let expected_shm = std::cmp::max(65536, (1024 + 64) * 2);
         assert_eq!(
             tc.buffer_size, expected_shm,
-            "SHM in duration mode should use fixed 64KB buffer, \
-             not the 1GB duration default"
+            "SHM in duration mode should use fixed 64KB buffer"
         );
     }
 
+    /// `--fresh-resources` must hand each test phase a new SHM segment
+    /// name, while the default reuses the one created for the mechanism.
+    #[test]
+    fn test_prepare_test_phase_fresh_resources() {
+        let config = BenchmarkConfig {
+            mechanism: IpcMechanism::SharedMemory,
+            message_size: 64,
+            msg_count: Some(10),
+            duration: None,
+            concurrency: 1,
+            one_way: true,
+            round_trip: false,
+            warmup_iterations: 0,
+            percentiles: vec![],
+            buffer_size: None,
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            server_affinity: None,
+            client_affinity: None,
+            send_delay: None,
+            pmq_priority: 0,
+            include_first_message: false,
+        };
+
+        let args = Args::default();
+        let runner = BenchmarkRunner::new(config.clone(), IpcMechanism::SharedMemory, args.clone());
+        let tc = runner.create_transport_config_internal(&args).unwrap();
+        let mut report = None;
+        let phase = runner.prepare_test_phase(&tc, &mut report).unwrap();
+        assert_eq!(phase.shared_memory_name, tc.shared_memory_name);
+        assert!(report.is_none());
+
+        let args = Args {
+            fresh_resources: true,
+            ..Default::default()
+        };
+        let runner = BenchmarkRunner::new(config, IpcMechanism::SharedMemory, args.clone());
+        let tc = runner.create_transport_config_internal(&args).unwrap();
+        let mut report = Some(CacheControlReport {
+            fresh_resources: true,
+            ..Default::default()
+        });
+        let phase = runner.prepare_test_phase(&tc, &mut report).unwrap();
+        assert_ne!(phase.shared_memory_name, tc.shared_memory_name);
+        assert_eq!(report.unwrap().drop_caches_attempts, 0);
+    }
+
     /// Verify that a user-provided `--buffer-size` overrides
     /// SHM's automatic 64 KB default. Without the override the
     /// runner would pick 64 KB; with it, the exact user value
@@ -2510,6 +3378,49 @@ mod tests {
         );
     }
 
+    /// Verify --shm-capacity-messages sizes the SHM ring for exactly that
+    /// many messages and is passed through to the transport.
+    #[test]
+    #[cfg(feature = "shm")]
+    fn test_shm_capacity_messages_sizes_ring() {
+        let config = BenchmarkConfig {
+            mechanism: IpcMechanism::SharedMemory,
+            message_size: 1024,
+            msg_count: Some(500),
+            duration: None,
+            concurrency: 1,
+            one_way: true,
+            round_trip: false,
+            warmup_iterations: 0,
+            percentiles: vec![],
+            buffer_size: None,
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            server_affinity: None,
+            client_affinity: None,
+            send_delay: None,
+            pmq_priority: 0,
+            include_first_message: false,
+        };
+        let args = Args {
+            shm_capacity_messages: Some(8),
+            shm_align: Some(64),
+            ..Default::default()
+        };
+        let runner = BenchmarkRunner::new(config, IpcMechanism::SharedMemory, args.clone());
+        let tc = runner.create_transport_config_internal(&args).unwrap();
+
+        assert_eq!(tc.shm_capacity_messages, Some(8));
+        assert_eq!(
+            tc.buffer_size,
+            crate::ipc::shared_memory::ring_bytes_for_messages(8, 1024 + 64, 64)
+        );
+        assert!(
+            tc.buffer_size < 65536,
+            "ring should no longer use the 64KB default"
+        );
+    }
+
     /// Verify SHM buffer sizing at the exact 32 KB boundary
     /// where `2 * (msg_size + 64)` transitions from below
     /// to above the 64 KB default.
@@ -2658,6 +3569,102 @@ mod tests {
         }
     }
 
+    /// Test that --idle-gap pauses before every measured message, whether
+    /// or not an unmeasured canary goes first.
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_idle_gap_is_applied() {
+        let msg_count = 4;
+        let idle_gap = Duration::from_millis(25);
+
+        for include_first_message in [true, false] {
+            let args = Args {
+                mechanisms: vec![IpcMechanism::UnixDomainSocket],
+                message_size: 64,
+                msg_count,
+                concurrency: 1,
+                round_trip: true,
+                warmup_iterations: 0,
+                idle_gap: Some(idle_gap),
+                include_first_message,
+                ..Default::default()
+            };
+
+            let config = BenchmarkConfig::from_args(&args).unwrap();
+            let runner = BenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
+            let transport_config = runner.create_transport_config_internal(&args).unwrap();
+            let mut metrics_collector =
+                MetricsCollector::new(Some(LatencyType::RoundTrip), vec![]).unwrap();
+
+            let start_time = Instant::now();
+            runner
+                .run_single_threaded_round_trip(&transport_config, &mut metrics_collector, None)
+                .await
+                .unwrap();
+
+            // One idle period precedes each of the measured messages, and the
+            // idle time itself is not part of any measured latency.
+            assert!(start_time.elapsed() >= idle_gap * msg_count as u32);
+            let metrics = metrics_collector.get_metrics();
+            let latency = metrics.latency.expect("round-trip latency recorded");
+            assert_eq!(latency.total_samples, msg_count);
+            assert!(latency.max_ns < idle_gap.as_nanos() as u64);
+        }
+    }
+
+    #[test]
+    fn test_read_first_byte_latencies_removes_file() {
+        let latency_path = std::env::temp_dir()
+            .join(format!("ipc_benchmark_first_byte_{}.txt", Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let path = first_byte_file_for(&latency_path);
+        assert!(path.starts_with(&latency_path) && path != latency_path);
+        std::fs::write(&path, "1000,200\n2000,400\n3000,600\n").unwrap();
+
+        let latency = read_first_byte_latencies(&path, 1024, &[50.0])
+            .unwrap()
+            .expect("latency distribution");
+        assert_eq!(latency.total_samples, 3);
+        assert_eq!(latency.min_ns, 200);
+        assert_eq!(latency.max_ns, 600);
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[test]
+    fn test_resolve_segment_size() {
+        let args = Args::default();
+        // Small messages on SHM fit in the ring: no segmentation.
+        assert_eq!(
+            resolve_segment_size(&args, &IpcMechanism::SharedMemory, 1024, 65536),
+            None
+        );
+        // A message larger than the ring is split into segments that fit.
+        let size = resolve_segment_size(&args, &IpcMechanism::SharedMemory, 1 << 20, 65536)
+            .expect("segmentation");
+        assert!(size + segmentation::SEGMENT_HEADER_LEN <= 65536 - 1024);
+
+        // An explicit segment size always wins.
+        let forced = Args {
+            segment_size: Some(4096),
+            ..Args::default()
+        };
+        assert_eq!(
+            resolve_segment_size(&forced, &IpcMechanism::TcpSocket, 64, 65536),
+            Some(4096)
+        );
+
+        // The spawned server only uses what the client forwarded.
+        let server = Args {
+            internal_run_as_server: true,
+            ..Args::default()
+        };
+        assert_eq!(
+            resolve_segment_size(&server, &IpcMechanism::SharedMemory, 1 << 20, 65536),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_latency_file_line_valid() {
         let (wall, lat) = super::parse_latency_file_line("1700000000000000000,42000").unwrap();
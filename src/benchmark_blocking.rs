@@ -56,29 +56,53 @@
 //! ```
 
 use crate::{
-    benchmark::BenchmarkConfig,
-    cli::{Args, IpcMechanism},
-    ipc::{BlockingTransportFactory, Message, MessageType, TransportConfig},
-    metrics::{LatencyType, MetricsCollector, PerformanceMetrics},
-    results::BenchmarkResults,
-    utils::get_temp_dir,
+    affinity_check::AffinityMonitor,
+    alloc_count::{self, HotPathAllocations},
+    benchmark::{
+        expired_file_for, first_byte_file_for, priority_file_for, producer_file_for,
+        read_expired_count, read_first_byte_latencies, read_message_tags, resolve_segment_size,
+        BenchmarkConfig,
+    },
+    cli::{Args, IpcMechanism, ResponseMode},
+    confidence,
+    cpu_frequency::FrequencyMonitor,
+    hook::{self, MessageHook, SimulatedWork},
+    ipc::{
+        get_monotonic_time_ns, get_thread_cpu_time_ns,
+        handshake::{self, Handshake},
+        limits::TransportLimits,
+        receive_blocking_with, receive_reply_blocking, segmentation, BlockingTransport,
+        BlockingTransportFactory, Message, MessageType, TransportConfig, TransportStatsTotals,
+    },
+    metrics::{
+        ConnectionReuse, LatencyMetrics, LatencyType, MetricsCollector, PerformanceMetrics,
+        RoundTripPhase,
+    },
+    pacing::Pacer,
+    pair,
+    payload::{MessageSource, PayloadGenerator, ZeroPayload},
+    priority_inversion::{self, InversionReport},
+    producers::{self, ProducerTally},
+    profile::{self, ProfileSettings},
+    queue_depth::QueueDepthSampler,
+    reproduce::Reproduction,
+    results::{BenchmarkResults, CacheControlReport, CpuUsage},
+    scheduler_noise::NoiseProbe,
+    sequence::{IdSequence, IdSpace},
+    serialization::SerializationCost,
+    server_handle::{self, ServerHandle},
+    setup_retry::SetupRetry,
+    shutdown,
+    smt::SharedCore,
+    warmup::{self, WarmupCapture, WarmupLength, WarmupReport},
 };
 use anyhow::{Context, Result};
-use clap::ValueEnum;
-use os_pipe::PipeReader;
-use std::{
-    io::Read,
-    process::{Command, Stdio},
-    time::Instant,
-};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-#[cfg(unix)]
-use std::os::unix::io::FromRawFd;
-#[cfg(windows)]
-use std::os::windows::io::{FromRawHandle, IntoRawHandle};
-
 /// A helper struct to provide consistent display of benchmark configuration.
 ///
 /// This mirrors the async version's BenchmarkConfigDisplay to ensure
@@ -124,6 +148,22 @@ impl<'a> std::fmt::Display for BenchmarkConfigDisplay<'a> {
             writeln!(f, "  Send Delay:         {:?}", delay)?;
         }
 
+        if let Some(size) = self.transport_config.segment_size {
+            writeln!(
+                f,
+                "  Segmentation:       {} segments/message of up to {} bytes",
+                segmentation::segment_count(self.config.message_size, size),
+                size
+            )?;
+        }
+        if self.mechanism == IpcMechanism::TcpSocket {
+            if let Some(device) = &self.transport_config.bind_device {
+                writeln!(f, "  Bind Device:        {}", device)?;
+            }
+            if let Some(ip) = self.transport_config.source_addr {
+                writeln!(f, "  Source Address:     {}", ip)?;
+            }
+        }
         #[cfg(target_os = "linux")]
         if self.mechanism == IpcMechanism::PosixMessageQueue {
             writeln!(f, "  PMQ Priority:       {}", self.config.pmq_priority)?;
@@ -188,6 +228,7 @@ impl<'a> std::fmt::Display for BenchmarkConfigDisplay<'a> {
 /// # Ok(())
 /// # }
 /// ```
+#[derive(Clone)]
 pub struct BlockingBenchmarkRunner {
     /// Benchmark configuration parameters
     config: BenchmarkConfig,
@@ -200,6 +241,24 @@ pub struct BlockingBenchmarkRunner {
 
     /// Available CPU cores (cached at startup to avoid affinity-dependent detection)
     available_cores: Option<Vec<core_affinity::CoreId>>,
+
+    /// Retry policy for transport setup, counting the retries made
+    setup_retry: SetupRetry,
+
+    /// Builds the messages the client sends
+    messages: MessageSource,
+
+    /// Processing the client does for each message (`--client-work`)
+    client_hook: Option<Arc<dyn MessageHook>>,
+
+    /// What `--profile` set for this mechanism
+    profile: Option<ProfileSettings>,
+
+    /// How long this mechanism warms up
+    warmup: WarmupLength,
+
+    /// Counters of the client transports of the measured tests
+    transport_stats: TransportStatsTotals,
 }
 
 impl BlockingBenchmarkRunner {
@@ -231,19 +290,62 @@ impl BlockingBenchmarkRunner {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new(config: BenchmarkConfig, mechanism: IpcMechanism, args: Args) -> Self {
+    pub fn new(mut config: BenchmarkConfig, mechanism: IpcMechanism, mut args: Args) -> Self {
+        let profile = profile::apply(&mut args, &mut config, mechanism);
+        if let Some(profile) = &profile {
+            info!("Profile for {}: {}", mechanism, profile);
+        }
+        let warmup = warmup::length_for(&args, mechanism, config.warmup_iterations);
+
         // Cache available cores at construction time to avoid affinity-dependent
         // detection issues
         let available_cores = core_affinity::get_core_ids();
 
+        let setup_retry = SetupRetry::from_args(&args);
+        // --id-width and --id-start were validated before any runner is made
+        let ids = IdSpace::from_args(&args)
+            .ok()
+            .flatten()
+            .map(IdSequence::new);
+        let messages = MessageSource::new(
+            Arc::new(ZeroPayload::new(config.message_size)),
+            config.message_size,
+        )
+        .with_id_sequence(ids);
+        let client_hook = args
+            .client_work
+            .map(|work| Arc::new(SimulatedWork::new(work)) as Arc<dyn MessageHook>);
         Self {
             config,
             mechanism,
             args,
             available_cores,
+            setup_retry,
+            messages,
+            client_hook,
+            profile,
+            warmup,
+            transport_stats: TransportStatsTotals::default(),
         }
     }
 
+    /// Send messages made by `generator` instead of zeroed payloads of the
+    /// configured message size, which its payloads must not exceed.
+    pub fn with_payload_generator(mut self, generator: Arc<dyn PayloadGenerator>) -> Self {
+        self.messages = MessageSource::new(generator, self.config.message_size)
+            .with_id_sequence(self.messages.id_sequence().cloned());
+        self
+    }
+
+    /// Run `hook` on each message the client sends one-way, before sending
+    /// it, and on each round-trip request, once its reply has arrived. Its
+    /// cost is reported apart from the transport latency; see
+    /// [`crate::hook`].
+    pub fn with_client_hook(mut self, hook: Arc<dyn MessageHook>) -> Self {
+        self.client_hook = Some(hook);
+        self
+    }
+
     /// Validate CPU core availability at startup
     ///
     /// This validates that the requested cores are available using cached
@@ -317,13 +419,10 @@ impl BlockingBenchmarkRunner {
     ///
     /// ## Returns
     ///
-    /// - `Ok((Child, PipeReader))`: A tuple containing the handle to the spawned
-    ///   child process and the reader end of the signaling pipe.
+    /// - `Ok(ServerHandle)`: The spawned server; call `wait_ready` before
+    ///   connecting to it.
     /// - `Err(anyhow::Error)`: An error if the pipe creation or process spawning fails.
-    pub fn spawn_server_process(
-        &self,
-        transport_config: &TransportConfig,
-    ) -> Result<(std::process::Child, PipeReader)> {
+    pub fn spawn_server_process(&self, transport_config: &TransportConfig) -> Result<ServerHandle> {
         self.spawn_server_process_with_latency_file(transport_config, None)
     }
 
@@ -339,92 +438,49 @@ impl BlockingBenchmarkRunner {
     ///
     /// ## Returns
     ///
-    /// - `Ok((Child, PipeReader))`: Spawned process and ready signal pipe
+    /// - `Ok(ServerHandle)`: The spawned server
     /// - `Err(anyhow::Error)`: Spawn failure
     pub fn spawn_server_process_with_latency_file(
         &self,
         transport_config: &TransportConfig,
         latency_file_path: Option<&str>,
-    ) -> Result<(std::process::Child, PipeReader)> {
-        let (reader, writer) =
-            os_pipe::pipe().context("Failed to create OS pipe for server signaling")?;
-
-        let current_exe =
-            std::env::current_exe().context("Failed to get current executable path")?;
-
-        // Resolve the path to the main binary robustly at runtime
-        let exe_name = "ipc-benchmark";
-        #[cfg(windows)]
-        let exe_name_win = "ipc-benchmark.exe";
-        let mut exe_path = None;
-
-        {
-            let current_name = current_exe.file_name().and_then(|n| n.to_str());
-            let matches_unix = current_name == Some(exe_name);
-            #[cfg(windows)]
-            let matches_win = current_name == Some(exe_name_win);
-            #[cfg(not(windows))]
-            let matches_win = false;
-            if matches_unix || matches_win {
-                exe_path = Some(current_exe.clone());
-            }
-        }
-
-        if exe_path.is_none() {
-            if let Ok(p) = std::env::var("CARGO_BIN_EXE_ipc-benchmark") {
-                let pbuf = std::path::PathBuf::from(p);
-                if pbuf.exists() && pbuf.file_name().and_then(|n| n.to_str()) == Some(exe_name) {
-                    exe_path = Some(pbuf);
-                }
-            } else if let Ok(p) = std::env::var("CARGO_BIN_EXE_ipc_benchmark") {
-                let pbuf = std::path::PathBuf::from(p);
-                if pbuf.exists() {
-                    exe_path = Some(pbuf);
-                }
-            }
-        }
-
-        if exe_path.is_none() {
-            let root = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-            #[cfg(windows)]
-            let p = root
-                .join("target")
-                .join("debug")
-                .join(format!("{}{}", exe_name, ".exe"));
-            #[cfg(not(windows))]
-            let p = root.join("target").join("debug").join(exe_name);
-            if p.exists() {
-                exe_path = Some(p);
-            }
-        }
-
-        let exe_path = exe_path.ok_or_else(|| {
-            anyhow::anyhow!(
-                "Could not resolve '{}' binary for server mode. Build it with \
-                 `cargo build --bin {}` or run full `cargo test` first.",
-                "ipc-benchmark",
-                "ipc-benchmark"
-            )
-        })?;
-
-        debug!("Spawning blocking server binary: {}", exe_path.display());
-        let mut cmd = Command::new(&exe_path);
+    ) -> Result<ServerHandle> {
+        let cmd = self.server_command(transport_config, latency_file_path)?;
+        self.spawn_server(cmd)
+    }
 
-        // Connect the writer end of the pipe to the child's stdout
-        cmd.stdin(Stdio::null());
+    /// Spawn the server running `cmd`, pre-connected to the client through
+    /// a socketpair for `-m socketpair`.
+    fn spawn_server(&self, cmd: Command) -> Result<ServerHandle> {
         #[cfg(unix)]
-        {
-            use std::os::unix::io::IntoRawFd;
-            cmd.stdout(unsafe { Stdio::from_raw_fd(writer.into_raw_fd()) });
-        }
-        #[cfg(windows)]
-        {
-            cmd.stdout(unsafe { Stdio::from_raw_handle(writer.into_raw_handle()) });
-        }
-        cmd.stderr(Stdio::inherit());
+        let server = if self.mechanism == IpcMechanism::Socketpair {
+            ServerHandle::spawn_with_socketpair(cmd)
+        } else {
+            ServerHandle::spawn(cmd)
+        };
+        #[cfg(not(unix))]
+        let server = ServerHandle::spawn(cmd);
+        Ok(server
+            .context("Failed to spawn server process in blocking mode")?
+            .with_heartbeat_timeout(self.args.heartbeat_timeout)
+            .with_retry_counter(self.setup_retry.counter()))
+    }
 
-        // Add --internal-run-as-server flag
-        cmd.arg("--internal-run-as-server");
+    /// Build the command line that runs the server for this test.
+    fn server_command(
+        &self,
+        transport_config: &TransportConfig,
+        latency_file_path: Option<&str>,
+    ) -> Result<Command> {
+        let mut cmd = match &self.args.server_remote {
+            Some(_) => ServerHandle::command_at(
+                self.args
+                    .remote_binary
+                    .as_deref()
+                    .unwrap_or("ipc-benchmark"),
+            ),
+            None => ServerHandle::command()?,
+        };
 
         // IMPORTANT: Add --blocking flag so the server runs in blocking mode
         cmd.arg("--blocking");
@@ -436,8 +492,7 @@ impl BlockingBenchmarkRunner {
 
         // Add mechanism-specific arguments
         // Use possible_value name (e.g., "tcp") not Display name (e.g., "TCP Socket")
-        cmd.arg("-m")
-            .arg(self.mechanism.to_possible_value().unwrap().get_name());
+        cmd.arg("-m").arg(self.mechanism.name());
 
         // Add message size and count
         cmd.arg("--message-size")
@@ -452,13 +507,45 @@ impl BlockingBenchmarkRunner {
             cmd.arg("--shared-memory-name")
                 .arg(&transport_config.shared_memory_name);
         }
+        // The server creates the SHM segment, so it decides the ring layout.
+        if let Some(align) = self.args.shm_align {
+            cmd.arg("--shm-align").arg(align.to_string());
+        }
+        if self.args.shm_pad_indices {
+            cmd.arg("--shm-pad-indices").arg("on");
+        }
+        if let Some(messages) = self.args.shm_capacity_messages {
+            cmd.arg("--shm-capacity-messages").arg(messages.to_string());
+        }
+        if self.args.shm_wait != crate::cli::ShmWait::Condvar {
+            cmd.arg("--shm-wait").arg(self.args.shm_wait.to_string());
+        }
+        if let Some(dir) = &self.args.shm_hugetlbfs {
+            cmd.arg("--shm-hugetlbfs").arg(dir);
+        }
+        if self.args.zero_copy {
+            cmd.arg("--zero-copy");
+        }
+        #[cfg(target_os = "linux")]
+        if self.mechanism == IpcMechanism::UdpMulticast {
+            cmd.arg("--multicast-group")
+                .arg(transport_config.multicast_group.to_string());
+        }
+        if self.mechanism == IpcMechanism::Http {
+            cmd.arg("--http-version")
+                .arg(transport_config.http_version.to_string());
+        }
         if !transport_config.message_queue_name.is_empty() {
             cmd.arg("--message-queue-name")
                 .arg(&transport_config.message_queue_name);
         }
 
         // Add network configuration
-        cmd.arg("--host").arg(&transport_config.host);
+        if self.args.server_remote.is_some() {
+            cmd.arg("--host").arg(server_handle::REMOTE_LISTEN_HOST);
+        } else {
+            cmd.arg("--host").arg(&transport_config.host);
+        }
         cmd.arg("--port").arg(transport_config.port.to_string());
 
         // Add buffer size
@@ -470,6 +557,65 @@ impl BlockingBenchmarkRunner {
             cmd.arg("--server-affinity").arg(core_id.to_string());
         }
 
+        // The receiving side of the SHM ring keeps the priority lanes
+        if !self.args.priorities.is_empty() {
+            let priorities: Vec<String> = self.args.priorities.iter().map(u8::to_string).collect();
+            cmd.arg("--priorities").arg(priorities.join(","));
+        }
+
+        // Add receiver batching if requested
+        if self.args.server_batch > 1 {
+            cmd.arg("--server-batch")
+                .arg(self.args.server_batch.to_string());
+        }
+
+        if let Some(rate) = self.args.server_rate {
+            cmd.arg("--server-rate").arg(rate.to_string());
+        }
+
+        if let Some(work) = self.args.server_work {
+            cmd.arg("--server-work")
+                .arg(format!("{}us", work.as_micros()));
+        }
+
+        if let Some(iterations) = self.args.server_spin {
+            cmd.arg("--server-spin").arg(iterations.to_string());
+        }
+
+        if self.args.response_mode != ResponseMode::Ack {
+            cmd.arg("--response-mode")
+                .arg(self.args.response_mode.to_string());
+        }
+
+        if let Some(width) = self.args.id_width {
+            cmd.arg("--id-width").arg(width.to_string());
+        }
+
+        // The server logs under the same run ID as this process
+        cmd.arg("--run-id").arg(crate::run_id::get());
+
+        // The server reports its receive-side latency at the same percentiles
+        for percentile in &self.config.percentiles {
+            cmd.arg("--percentiles").arg(percentile.to_string());
+        }
+
+        cmd.arg("--log-rate-limit")
+            .arg(self.args.log_rate_limit.to_string());
+        // The server retries its own setup under the same policy
+        cmd.arg("--setup-retries")
+            .arg(self.args.setup_retries.to_string());
+        cmd.arg("--setup-backoff")
+            .arg(format!("{}us", self.args.setup_backoff.as_micros()));
+
+        // Add UDS credential checks if applicable
+        #[cfg(unix)]
+        if self.mechanism == IpcMechanism::UnixDomainSocket
+            && self.args.uds_credentials != crate::cli::UdsCredentialMode::Off
+        {
+            cmd.arg("--uds-credentials")
+                .arg(self.args.uds_credentials.to_string());
+        }
+
         // Add PMQ priority if applicable
         #[cfg(target_os = "linux")]
         if self.mechanism == IpcMechanism::PosixMessageQueue {
@@ -477,6 +623,25 @@ impl BlockingBenchmarkRunner {
                 .arg(self.config.pmq_priority.to_string());
         }
 
+        // Add TCP keepalive and interface binding if applicable
+        if self.mechanism == IpcMechanism::TcpSocket {
+            if let Some(idle) = transport_config.tcp_keepalive {
+                cmd.arg("--tcp-keepalive")
+                    .arg(format!("{}us", idle.as_micros()));
+            }
+            if let Some(device) = &transport_config.bind_device {
+                cmd.arg("--bind-device").arg(device);
+            }
+        }
+
+        // Add segmentation so the server reassembles what the client splits
+        if let Some(size) = transport_config.segment_size {
+            cmd.arg("--segment-size").arg(size.to_string());
+        }
+        if let Some(size) = transport_config.fragment_writes {
+            cmd.arg("--fragment-writes").arg(size.to_string());
+        }
+
         // Forward send-delay to server so SHM-direct can enable precise
         // (inside-mutex) timestamps for latency-focused benchmarks.
         if let Some(delay) = self.config.send_delay {
@@ -487,6 +652,22 @@ impl BlockingBenchmarkRunner {
         // Add latency file path if provided (for true IPC measurement)
         if let Some(path) = latency_file_path {
             cmd.arg("--internal-latency-file").arg(path);
+            if self.args.first_byte_latency {
+                cmd.arg("--internal-first-byte-file")
+                    .arg(first_byte_file_for(path));
+            }
+            if self.args.deadline.is_some() {
+                cmd.arg("--internal-expired-file")
+                    .arg(expired_file_for(path));
+            }
+            if !self.args.priorities.is_empty() {
+                cmd.arg("--internal-priority-file")
+                    .arg(priority_file_for(path));
+            }
+            if self.args.producers > 1 {
+                cmd.arg("--internal-producer-file")
+                    .arg(producer_file_for(path));
+            }
         }
 
         // Forward verbose flags to the server for debugging
@@ -495,13 +676,18 @@ impl BlockingBenchmarkRunner {
             cmd.arg("-v");
         }
 
-        debug!("Spawning blocking server process with command: {:?}", cmd);
+        // ssh hands the command line to the remote shell as one string
+        if let Some(target) = &self.args.server_remote {
+            cmd = ServerHandle::remote_command(target, &cmd)?;
+        }
 
-        let child = cmd
-            .spawn()
-            .context("Failed to spawn server process in blocking mode")?;
+        Ok(cmd)
+    }
 
-        Ok((child, reader))
+    /// The transport configuration this runner's tests will use, with its
+    /// profile and the host's transport limits applied.
+    pub fn resolved_transport_config(&self) -> Result<TransportConfig> {
+        self.create_transport_config_internal(&self.args)
     }
 
     /// Create transport configuration with intelligent parameter adaptation
@@ -515,19 +701,25 @@ impl BlockingBenchmarkRunner {
     /// - **Adaptive Buffer Sizing**: Per-mechanism buffer sizing —
     ///   SHM uses a fixed 64 KB buffer (or 2× message size for
     ///   large messages), PMQ uses a safe 8 KB default, and
-    ///   TCP/UDS sizes to fit all messages or 1 GB in duration mode
-    /// - **Port Uniqueness**: Ensures unique ports for TCP to avoid conflicts
+    ///   TCP/UDS sizes to fit all messages, or uses the bounded
+    ///   streaming buffer in duration mode
+    /// - **Ephemeral Ports**: TCP servers bind port 0 so tests never collide on a port
     /// - **Mechanism-Specific Tuning**: Applies optimizations for each transport type
     ///
     /// ## Returns
     /// - `Ok(TransportConfig)`: Configured transport settings
     /// - `Err(anyhow::Error)`: Configuration validation failure
     pub fn create_transport_config_internal(&self, args: &Args) -> Result<TransportConfig> {
-        const DURATION_MODE_BUFFER_SIZE: usize = 1_073_741_824; // 1 GB
         const PMQ_SAFE_DEFAULT_BUFFER_SIZE: usize = 8192;
 
-        // Validate port for TCP-based mechanisms
-        if matches!(self.mechanism, IpcMechanism::TcpSocket) && self.config.port == 0 {
+        // Validate port for TCP-based mechanisms. The spawned server is
+        // deliberately handed port 0 so that it binds an ephemeral port.
+        if matches!(
+            self.mechanism,
+            IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc
+        ) && self.config.port == 0
+            && !args.internal_run_as_server
+        {
             return Err(anyhow::anyhow!(
                 "Invalid port number: 0. Port must be between 1 and 65535 for TCP connections."
             ));
@@ -536,10 +728,6 @@ impl BlockingBenchmarkRunner {
         let unique_id = Uuid::new_v4();
         // Use shortened UUID for socket paths to stay within macOS SUN_LEN limit (104 bytes)
         let short_id = &unique_id.to_string()[..8];
-        let port_offset = (unique_id.as_u128() % 1000) as u32;
-        // Keep computed ports within valid TCP range [1, 65535] without overflow.
-        let base_port = u32::from(self.config.port.max(1));
-        let unique_port = ((base_port - 1 + port_offset) % 65_535 + 1) as u16;
 
         // Determine if the current mechanism is PMQ
         let is_pmq = {
@@ -553,6 +741,8 @@ impl BlockingBenchmarkRunner {
             }
         };
 
+        let limits = self.transport_limits();
+
         // Buffer size logic:
         // 1. If user provides --buffer-size, use it directly.
         // 2. If the mechanism is PMQ, always use a safe, small default.
@@ -560,25 +750,69 @@ impl BlockingBenchmarkRunner {
         //    to enable proper streaming behavior. Previously, sizing to fit all messages
         //    caused the writer to dump everything instantly while the reader slowly drained,
         //    leading to huge accumulated latencies.
-        // 4. If in duration mode, use a large fixed size to avoid backpressure.
-        // 5. Otherwise, calculate based on message count (for UDS/TCP which handle backpressure well).
+        //    With --shm-capacity-messages, size the ring to hold exactly that many messages.
+        // 4. If the mechanism is a futex or RT signal one, size each slot for one message.
+        // 5. If the mechanism is UDP multicast, which has no backpressure, make room
+        //    for every message (or a fixed number in duration mode) as the kernel
+        //    accounts for queued datagrams.
+        // 6. If in duration mode, use the same bounded streaming buffer so that senders
+        //    see realistic backpressure instead of an effectively unbounded buffer.
+        // 7. Otherwise, calculate based on message count (for UDS/TCP which handle backpressure well).
         let is_shm = self.mechanism == IpcMechanism::SharedMemory;
-        const SHM_DEFAULT_BUFFER_SIZE: usize = 65536; // 64KB - matches H2C behavior
-                                                      // Per-message overhead for buffer sizing: 8 (id) + 8
-                                                      // (timestamp) + 8 (bincode vec length) + 1 (message
-                                                      // type) + 4 (ring buffer length prefix) = 29 bytes,
-                                                      // rounded up to 64 for alignment and safety margin.
+        // A futex or RT signal slot holds exactly one message
+        #[cfg(target_os = "linux")]
+        let is_slot = matches!(self.mechanism, IpcMechanism::Futex | IpcMechanism::RtSignal);
+        #[cfg(not(target_os = "linux"))]
+        let is_slot = false;
+        // The inotify spool lives in /dev/shm under the same naming
+        #[cfg(target_os = "linux")]
+        let is_spool = self.mechanism == IpcMechanism::Inotify;
+        #[cfg(not(target_os = "linux"))]
+        let is_spool = false;
+        #[cfg(target_os = "linux")]
+        let is_datagram = self.mechanism == IpcMechanism::UdpMulticast;
+        #[cfg(not(target_os = "linux"))]
+        let is_datagram = false;
+        // The kernel charges each queued datagram its whole allocation
+        // against the receive buffer, about 1KB beyond the data itself
+        const DATAGRAM_KERNEL_OVERHEAD: usize = 1024;
+        const DATAGRAM_STREAMING_MESSAGES: usize = 1024;
+        const STREAMING_BUFFER_SIZE: usize = 65536; // 64KB - matches H2C behavior
+                                                    // Per-message overhead for buffer sizing: 8 (id) + 8
+                                                    // (timestamp) + 8 (bincode vec length) + 1 (message
+                                                    // type) + 12 (ring buffer record header) = 37 bytes,
+                                                    // rounded up to 64 for alignment and safety margin.
         const MESSAGE_OVERHEAD: usize = 64;
 
+        let streaming_buffer_size = std::cmp::max(
+            STREAMING_BUFFER_SIZE,
+            (self.config.message_size + MESSAGE_OVERHEAD) * 2,
+        );
         let buffer_size = self.config.buffer_size.unwrap_or_else(|| {
             if is_pmq {
                 PMQ_SAFE_DEFAULT_BUFFER_SIZE
             } else if is_shm {
                 // Use fixed buffer for SHM to enable streaming, not batching
-                let min_for_message = (self.config.message_size + MESSAGE_OVERHEAD) * 2;
-                std::cmp::max(SHM_DEFAULT_BUFFER_SIZE, min_for_message)
+                match args.shm_capacity_messages {
+                    #[cfg(feature = "shm")]
+                    Some(messages) => crate::ipc::shared_memory::ring_bytes_for_messages(
+                        messages,
+                        self.config.message_size + MESSAGE_OVERHEAD,
+                        args.shm_align.unwrap_or(1),
+                    ),
+                    _ => streaming_buffer_size,
+                }
+            } else if is_slot {
+                self.config.message_size + MESSAGE_OVERHEAD
+            } else if is_datagram {
+                let messages = if self.config.duration.is_some() {
+                    DATAGRAM_STREAMING_MESSAGES
+                } else {
+                    self.get_msg_count()
+                };
+                messages * (self.config.message_size + MESSAGE_OVERHEAD + DATAGRAM_KERNEL_OVERHEAD)
             } else if self.config.duration.is_some() {
-                DURATION_MODE_BUFFER_SIZE
+                streaming_buffer_size
             } else {
                 // For message-count mode with UDS/TCP, size buffer to fit all messages.
                 // These mechanisms handle backpressure via kernel buffers.
@@ -590,6 +824,7 @@ impl BlockingBenchmarkRunner {
         #[cfg(target_os = "linux")]
         if self.mechanism == IpcMechanism::PosixMessageQueue
             && buffer_size > PMQ_SAFE_DEFAULT_BUFFER_SIZE
+            && limits.pmq_msgsize_max.is_none()
         {
             warn!(
                 "The specified buffer size ({} bytes) exceeds the typical system limit of 8192 bytes for POSIX Message Queues. The benchmark may fail if the system is not configured for larger message sizes.",
@@ -642,13 +877,21 @@ impl BlockingBenchmarkRunner {
             }
         };
 
+        // Fit the configuration to the host's limits before anything is created
+        let (buffer_size, adaptive_queue_depth) = limits.apply(
+            &self.mechanism,
+            buffer_size,
+            self.config.buffer_size.is_some(),
+            adaptive_queue_depth,
+        )?;
+
         Ok(TransportConfig {
             socket_path: {
                 #[cfg(unix)]
                 {
                     if self.mechanism == IpcMechanism::UnixDomainSocket {
                         args.socket_path.clone().unwrap_or_else(|| {
-                            get_temp_dir()
+                            crate::utils::get_temp_dir()
                                 .join(format!("ipc_{}.sock", short_id))
                                 .to_string_lossy()
                                 .into_owned()
@@ -657,14 +900,29 @@ impl BlockingBenchmarkRunner {
                         String::new()
                     }
                 }
-                #[cfg(not(unix))]
+                #[cfg(windows)]
+                {
+                    if self.mechanism == IpcMechanism::NamedPipe {
+                        args.socket_path.clone().unwrap_or_else(|| {
+                            crate::ipc::named_pipe_blocking::pipe_path(&format!("ipc_{}", short_id))
+                        })
+                    } else {
+                        String::new()
+                    }
+                }
+                #[cfg(not(any(unix, windows)))]
                 {
                     String::new()
                 }
             },
-            host: self.config.host.clone(),
-            port: unique_port,
-            shared_memory_name: if self.mechanism == IpcMechanism::SharedMemory {
+            host: match &args.server_remote {
+                Some(target) => server_handle::remote_connect_host(target, &self.config.host),
+                None => self.config.host.clone(),
+            },
+            // The spawned server binds an ephemeral port and reports it back
+            // through the readiness pipe; see `read_server_ready`.
+            port: 0,
+            shared_memory_name: if is_shm || is_slot || is_spool {
                 args.shared_memory_name
                     .clone()
                     .unwrap_or_else(|| format!("ipc_benchmark_{}", unique_id))
@@ -691,6 +949,31 @@ impl BlockingBenchmarkRunner {
             max_connections: 1,
             message_queue_depth: adaptive_queue_depth,
             pmq_priority: self.config.pmq_priority,
+            shm_slot_align: args.shm_align.unwrap_or(1),
+            shm_pad_indices: args.shm_pad_indices,
+            shm_capacity_messages: args.shm_capacity_messages,
+            shm_priority_lanes: !args.priorities.is_empty(),
+            shm_zero_copy: args.zero_copy,
+            shm_wait: args.shm_wait,
+            shm_hugetlbfs: args.shm_hugetlbfs.clone(),
+            http_version: args.http_version,
+            uds_credentials: args.uds_credentials,
+            tcp_keepalive: args.tcp_keepalive,
+            bind_device: args.bind_device.clone(),
+            source_addr: args.source_addr,
+            inherited_fd: None,
+            multicast_group: args
+                .multicast_group
+                .unwrap_or(crate::defaults::MULTICAST_GROUP),
+            first_byte_timestamps: args.internal_first_byte_file.is_some(),
+            fragment_writes: args.fragment_writes,
+            verify_frames: args.verify_frames,
+            segment_size: resolve_segment_size(
+                args,
+                &self.mechanism,
+                self.config.message_size,
+                buffer_size,
+            ),
         })
     }
 
@@ -742,40 +1025,145 @@ impl BlockingBenchmarkRunner {
                 transport_config: &transport_config,
             }
         );
+        if let Some(target) = &self.args.server_remote {
+            info!(
+                "Server runs on {} over SSH; client connects to {}",
+                target, transport_config.host
+            );
+        }
 
         // Initialize results structure with test configuration
         let mut results = BenchmarkResults::new(
             self.mechanism,
             self.config.message_size,
             transport_config.buffer_size,
-            self.config.concurrency,
+            // The blocking runner always drives a single client
+            1,
             self.config.msg_count,
             self.config.duration,
             self.config.warmup_iterations,
             self.config.one_way,
             self.config.round_trip,
         );
+        results.test_config.server_batch =
+            (self.args.server_batch > 1).then_some(self.args.server_batch);
+        results.test_config.profile = self.profile.clone();
+        if self.args.server_remote.is_none() {
+            results.test_config.smt_siblings = self
+                .config
+                .client_affinity
+                .zip(self.config.server_affinity)
+                .and_then(|(client, server)| SharedCore::between(client, server));
+        }
+        results.reproduction = Some(Reproduction::of_test(
+            &self.args,
+            self.mechanism,
+            self.config.client_affinity,
+            self.config.server_affinity,
+        ));
+        results.test_config.server_rate = self.args.server_rate;
+        results.test_config.client_work = self.args.client_work;
+        results.test_config.server_work = self.args.server_work;
+        results.test_config.server_spin = self.args.server_spin;
+        results.test_config.response_mode = Some(self.args.response_mode);
+        results.test_config.id_width = self.args.id_width;
+        #[cfg(unix)]
+        if self.mechanism == IpcMechanism::UnixDomainSocket
+            && self.args.uds_credentials != crate::cli::UdsCredentialMode::Off
+        {
+            results.test_config.uds_credentials = Some(self.args.uds_credentials);
+        }
+        results.test_config.idle_gap = self.args.idle_gap;
+        results.test_config.send_delay = self.config.send_delay;
+        results.test_config.deadline = self.args.deadline;
+        results.test_config.priorities = self.args.priorities.clone();
+        results.test_config.producers = (self.args.producers > 1).then_some(self.args.producers);
+        results.test_config.subscribers =
+            (self.args.subscribers > 1).then_some(self.args.subscribers);
+        if self.mechanism == IpcMechanism::SharedMemory
+            && !self.args.shm_direct
+            && transport_config.shm_wait != crate::cli::ShmWait::Condvar
+        {
+            results.test_config.shm_wait = Some(transport_config.shm_wait);
+        }
+        if self.mechanism == IpcMechanism::SharedMemory && !self.args.shm_direct {
+            results.test_config.shm_hugetlbfs = transport_config.shm_hugetlbfs.clone();
+        }
+        if self.mechanism == IpcMechanism::Http {
+            results.test_config.http_version = Some(transport_config.http_version);
+        }
+        if self.mechanism == IpcMechanism::TcpSocket {
+            results.test_config.tcp_keepalive = transport_config.tcp_keepalive;
+            results.test_config.bind_device = transport_config.bind_device.clone();
+            results.test_config.source_addr = transport_config.source_addr;
+            results.test_config.server_remote = self.args.server_remote.clone();
+        }
+        if let Some(size) = transport_config.segment_size {
+            results.test_config.segment_size = Some(size);
+            results.test_config.segments_per_message =
+                Some(segmentation::segment_count(self.config.message_size, size));
+        }
+        let limits = self.transport_limits();
+        results.test_config.transport_limits = (!limits.is_empty()).then_some(limits);
 
         // Run warmup if configured
-        if self.config.warmup_iterations > 0 {
-            info!(
-                "Running warmup with {} iterations",
-                self.config.warmup_iterations
-            );
-            self.run_warmup(&transport_config)?;
+        if !self.warmup.is_empty() {
+            info!("Running warmup of {}", self.warmup);
+            let (sent, report) = self.run_warmup(&transport_config)?;
+            results.test_config.warmup_iterations = sent;
+            results.warmup = report;
         }
+        results.test_config.warmup_duration = self.warmup.duration();
+
+        let mut cache_control =
+            (self.args.drop_caches || self.args.fresh_resources).then(|| CacheControlReport {
+                drop_caches: self.args.drop_caches,
+                fresh_resources: self.args.fresh_resources,
+                ..Default::default()
+            });
+
+        let noise_floor_ns = confidence::measure_noise_floor();
+
+        // Watch for throttling across the measured phases
+        let frequency_monitor = FrequencyMonitor::start(self.args.frequency_sample_interval);
+        let noise_probe = NoiseProbe::start(self.args.scheduler_noise_interval);
 
         // Run one-way latency test if enabled
         if self.config.one_way {
             info!("Running one-way latency test");
-            let one_way_results =
-                self.run_one_way_test(&transport_config, results_manager.as_deref_mut())?;
+            let inversion_baseline = match self.args.priority_inversion {
+                Some(bulk_per_critical) if !shutdown::requested() => {
+                    info!("Running priority inversion baseline: critical messages alone");
+                    let baseline = self.inversion_baseline(bulk_per_critical);
+                    let phase_config =
+                        baseline.prepare_test_phase(&transport_config, &mut cache_control)?;
+                    Some(baseline.run_one_way_test(&phase_config, None)?.0)
+                }
+                _ => None,
+            };
+            let phase_config = self.prepare_test_phase(&transport_config, &mut cache_control)?;
+            let (one_way_results, first_byte) =
+                self.run_one_way_test(&phase_config, results_manager.as_deref_mut())?;
+            if let (Some(bulk_per_critical), Some(baseline)) =
+                (self.args.priority_inversion, &inversion_baseline)
+            {
+                results.priority_inversion = InversionReport::new(
+                    bulk_per_critical,
+                    self.args.server_rate,
+                    baseline,
+                    &one_way_results,
+                );
+                if let Some(report) = &results.priority_inversion {
+                    info!("Priority inversion for {}: {}", self.mechanism, report);
+                }
+            }
             results.add_one_way_results(one_way_results);
+            results.first_byte_latency = first_byte;
         }
 
         // Run round-trip latency test if enabled
         // Note: Shared memory in blocking mode doesn't support bidirectional communication
-        if self.config.round_trip {
+        if self.config.round_trip && !shutdown::requested() {
             if self.mechanism == IpcMechanism::SharedMemory {
                 warn!(
                     "Shared memory in blocking mode does not support bidirectional \
@@ -783,12 +1171,43 @@ impl BlockingBenchmarkRunner {
                 );
             } else {
                 info!("Running round-trip latency test");
-                let round_trip_results =
-                    self.run_round_trip_test(&transport_config, results_manager)?;
+                let phase_config =
+                    self.prepare_test_phase(&transport_config, &mut cache_control)?;
+                let (round_trip_results, client_cpu) =
+                    self.run_round_trip_test(&phase_config, results_manager)?;
                 results.add_round_trip_results(round_trip_results);
+                results.client_cpu = client_cpu;
+                results.test_config.spin_wait = self.args.spin_wait;
             }
         }
 
+        results.cpu_frequency = frequency_monitor
+            .and_then(|monitor| monitor.finish(self.args.frequency_drop_threshold));
+        results.scheduler_noise = noise_probe.and_then(NoiseProbe::finish);
+        if self.mechanism == IpcMechanism::SharedMemory && self.args.zero_copy {
+            results.serialization = Some(SerializationCost::measure(self.config.message_size)?);
+        }
+        if let Some(report) = results.cpu_frequency.as_ref().filter(|r| r.throttled) {
+            warn!(
+                "CPU frequency dropped during the {} test: {}. Results may reflect throttling",
+                self.mechanism, report
+            );
+        }
+        results.annotate_confidence(noise_floor_ns);
+        results.annotate_goodput(self.args.goodput_budget);
+        results.annotate_sla(&self.args.sla_thresholds);
+        results.cache_control = cache_control;
+        results.setup_retries = self.setup_retry.count();
+        results.transport_stats = self.transport_stats.get();
+
+        if shutdown::requested() {
+            warn!(
+                "{} test interrupted; recording partial results",
+                self.mechanism
+            );
+            results.set_aborted();
+        }
+
         // Set total benchmark duration
         results.test_duration = total_start.elapsed();
 
@@ -796,6 +1215,213 @@ impl BlockingBenchmarkRunner {
         Ok(results)
     }
 
+    /// Whether a round trip that took `latency` missed the `--deadline`.
+    fn past_deadline(&self, latency: std::time::Duration) -> bool {
+        self.args
+            .deadline
+            .is_some_and(|deadline| latency > deadline)
+    }
+
+    /// Record the phases of a round trip sent at `send_ns` and answered by
+    /// `reply` at `receive_ns`, when the server stamped the reply. A remote
+    /// server's clock is unrelated to ours, so its stamps are not used.
+    fn record_round_trip_phases(
+        &self,
+        metrics_collector: &mut MetricsCollector,
+        send_ns: u64,
+        reply: &Message,
+        receive_ns: u64,
+    ) -> Result<()> {
+        if self.args.server_remote.is_some() {
+            return Ok(());
+        }
+        match RoundTripPhase::split(
+            send_ns,
+            reply.server_receive_ns,
+            reply.timestamp,
+            receive_ns,
+        ) {
+            Some(phases) => metrics_collector.record_phase_latency(phases),
+            None => Ok(()),
+        }
+    }
+
+    /// The client thread's CPU time now, if `--cpu-split` asks for it.
+    fn cpu_mark(&self) -> Option<u64> {
+        if self.args.cpu_split {
+            get_thread_cpu_time_ns()
+        } else {
+            None
+        }
+    }
+
+    /// CPU time the client thread has run since `mark`.
+    fn cpu_since(mark: Option<u64>) -> Option<std::time::Duration> {
+        mark.zip(get_thread_cpu_time_ns())
+            .map(|(start, end)| std::time::Duration::from_nanos(end.saturating_sub(start)))
+    }
+
+    /// Run the client hook, if any, on a `request` whose reply arrived
+    /// after `latency`, and record its cost.
+    fn run_client_hook(
+        &self,
+        metrics_collector: &mut MetricsCollector,
+        request: &Message,
+        latency: std::time::Duration,
+    ) -> Result<()> {
+        match &self.client_hook {
+            Some(hook) => {
+                let work = hook::run_timed(hook.as_ref(), request);
+                metrics_collector.record_processing(work, Some(latency))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Host limits for this mechanism. A ring on hugetlbfs does not use
+    /// /dev/shm; its huge pages are checked when the segment is mapped.
+    fn transport_limits(&self) -> TransportLimits {
+        let mut limits = TransportLimits::discover(&self.mechanism);
+        if self.args.shm_hugetlbfs.is_some() && !self.args.shm_direct {
+            limits.shm_free_bytes = None;
+        }
+        limits
+    }
+
+    /// Whether round trips alternate warm and cold connections: the test
+    /// asked for it and the mechanism has connections.
+    fn compares_pooling(&self) -> bool {
+        #[cfg(unix)]
+        let connected = matches!(
+            self.mechanism,
+            IpcMechanism::TcpSocket | IpcMechanism::UnixDomainSocket
+        );
+        #[cfg(not(unix))]
+        let connected = self.mechanism == IpcMechanism::TcpSocket;
+        self.args.compare_pooling && connected
+    }
+
+    /// Whether the one-way test also measures server to client: the test
+    /// asked for it and the mechanism's server can send to its client.
+    fn pairs_directions(&self) -> bool {
+        self.args.pair && pair::supports(&self.mechanism)
+    }
+
+    /// Connect a fresh client transport for a single cold request.
+    fn connect_fresh(
+        &self,
+        transport_config: &TransportConfig,
+    ) -> Result<Box<dyn BlockingTransport>> {
+        let mut transport = segmentation::wrap_blocking(
+            BlockingTransportFactory::create(
+                &self.mechanism,
+                self.args.shm_direct,
+                self.config.send_delay,
+            )?,
+            transport_config.segment_size,
+        );
+        self.setup_retry
+            .start_client_blocking(transport.as_mut(), transport_config)
+            .context("Failed to open a fresh connection")?;
+        Ok(transport)
+    }
+
+    /// Check protocol compatibility with the server on a freshly connected
+    /// client transport, unless the mechanism has no reply path.
+    fn handshake(
+        &self,
+        transport: &mut dyn BlockingTransport,
+        transport_config: &TransportConfig,
+    ) -> Result<()> {
+        if !handshake::has_reply_path(&self.mechanism) {
+            return Ok(());
+        }
+        let terms = Handshake::new(
+            self.config.message_size as u64,
+            transport_config.segment_size.is_some(),
+        )
+        .with_frame_checksums(transport_config.verify_frames);
+        let agreed = handshake::client_blocking(transport, &terms)
+            .context("Handshake with the server failed")?;
+        debug!("Handshake agreed: {:?}", agreed);
+        Ok(())
+    }
+
+    /// The spawned server's process and the core it pinned itself to, for
+    /// the affinity check; `None` when unpinned or remote.
+    fn pinned_server(&self, server: &ServerHandle) -> Option<(u32, usize)> {
+        let core = self.config.server_affinity?;
+        self.args
+            .server_remote
+            .is_none()
+            .then(|| (server.id(), core))
+    }
+
+    /// This runner for the baseline of `--priority-inversion`: only the
+    /// critical messages, as many and, with `--send-delay`, as far apart as
+    /// in the contended run.
+    fn inversion_baseline(&self, bulk_per_critical: u32) -> Self {
+        let per_critical = bulk_per_critical as usize + 1;
+        let mut baseline = self.clone();
+        baseline.args.priorities = vec![priority_inversion::CRITICAL_PRIORITY];
+        baseline.config.msg_count = self
+            .config
+            .msg_count
+            .map(|count| (count / per_critical).max(1));
+        baseline.config.send_delay = self
+            .config
+            .send_delay
+            .map(|delay| delay * per_critical as u32);
+        baseline
+    }
+
+    /// Priority of measured message `id`: the `--priorities` in turn, or 0.
+    /// Under `--producers` this client is producer 0 and keeps to its own.
+    fn message_priority(&self, id: u64) -> u8 {
+        let priorities = &self.args.priorities;
+        if self.args.producers > 1 {
+            return producers::producer_priority(priorities, 0);
+        }
+        if priorities.is_empty() {
+            0
+        } else {
+            priorities[(id % priorities.len() as u64) as usize]
+        }
+    }
+
+    /// Apply the cold-cache controls requested on the command line before a
+    /// measured test phase.
+    ///
+    /// Returns the transport configuration the phase should use: a newly
+    /// generated one under `--fresh-resources`, otherwise a copy of `current`.
+    /// A failed cache drop is logged and recorded but does not abort the test.
+    fn prepare_test_phase(
+        &self,
+        current: &TransportConfig,
+        report: &mut Option<CacheControlReport>,
+    ) -> Result<TransportConfig> {
+        // Every test's IDs start at --id-start, as its server expects
+        self.messages.restart_ids();
+
+        let config = if self.args.fresh_resources {
+            self.create_transport_config_internal(&self.args)?
+        } else {
+            current.clone()
+        };
+
+        if self.args.drop_caches {
+            let outcome = crate::utils::drop_page_caches();
+            if let Err(e) = &outcome {
+                warn!("Could not drop page caches, this phase is not cold: {}", e);
+            }
+            if let Some(report) = report.as_mut() {
+                report.record_drop_attempt(&outcome);
+            }
+        }
+
+        Ok(config)
+    }
+
     /// Run warmup iterations to stabilize performance (blocking version)
     ///
     /// Warmup is critical for accurate performance measurement as it allows
@@ -819,35 +1445,71 @@ impl BlockingBenchmarkRunner {
     /// ## Returns
     /// - `Ok(())`: Warmup completed successfully
     /// - `Err(anyhow::Error)`: Warmup failed
-    fn run_warmup(&self, transport_config: &TransportConfig) -> Result<()> {
-        let mut client_transport = BlockingTransportFactory::create(
-            &self.mechanism,
-            self.args.shm_direct,
-            self.config.send_delay,
-        )?;
+    ///
+    /// With `--capture-warmup` the warmup messages are round trips, returned
+    /// as a [`WarmupReport`].
+    fn run_warmup(
+        &self,
+        transport_config: &TransportConfig,
+    ) -> Result<(usize, Option<WarmupReport>)> {
+        let mut client_transport = segmentation::wrap_blocking(
+            BlockingTransportFactory::create(
+                &self.mechanism,
+                self.args.shm_direct,
+                self.config.send_delay,
+            )?,
+            transport_config.segment_size,
+        );
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self.spawn_server_process(transport_config)?;
+        let mut server = self.spawn_server_process(transport_config)?;
 
         // Wait for the server to signal that it's ready.
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe for warmup")?;
         debug!("Client received server ready signal for warmup");
 
         // --- Client Logic ---
-        client_transport.start_client_blocking(transport_config)?;
-
-        let payload = vec![0u8; self.config.message_size];
-        for i in 0..self.config.warmup_iterations {
-            let message = Message::new(i as u64, payload.clone(), MessageType::OneWay);
+        self.setup_retry
+            .start_client_blocking(client_transport.as_mut(), transport_config)?;
+        self.handshake(client_transport.as_mut(), transport_config)?;
+
+        let mut capture = self
+            .captures_warmup()
+            .then(|| WarmupCapture::with_capacity(self.warmup.capacity()));
+        let message_type = if capture.is_some() {
+            MessageType::Request
+        } else {
+            MessageType::OneWay
+        };
+        let mut errors = 0;
+        let started = Instant::now();
+        let mut sent = 0;
+        while self.warmup.continues(sent, started) {
+            let message = self.messages.message(sent as u64, message_type, None, 0)?;
+            sent += 1;
+            let send_time = Instant::now();
             client_transport
                 .send_blocking(&message)
                 .context("Failed to send warmup message")?;
             if let Some(delay) = self.config.send_delay {
                 std::thread::sleep(delay);
             }
+            if let Some(capture) = capture.as_mut() {
+                let reply = receive_reply_blocking(
+                    client_transport.as_mut(),
+                    message.id,
+                    self.args.spin_wait,
+                    &mut errors,
+                )?;
+                if reply.is_some() {
+                    capture.record(send_time.elapsed());
+                }
+            }
+        }
+        if errors > 0 {
+            warn!("{} warmup replies did not match their request", errors);
         }
 
         // --- Cleanup ---
@@ -868,12 +1530,30 @@ impl BlockingBenchmarkRunner {
         }
 
         client_transport.close_blocking()?;
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error during warmup")?;
 
         debug!("Warmup completed");
-        Ok(())
+        let report = match capture {
+            Some(capture) => capture.finish(&self.config.percentiles)?,
+            None => None,
+        };
+        Ok((sent, report))
+    }
+
+    /// Whether warmup round trips are captured: with `--capture-warmup`,
+    /// unless the mechanism has no replies to time.
+    fn captures_warmup(&self) -> bool {
+        if !self.args.capture_warmup {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        if self.mechanism == IpcMechanism::UdpMulticast {
+            warn!("-m udp-multicast has no replies; --capture-warmup ignored");
+            return false;
+        }
+        true
     }
 
     /// Run one-way latency test (blocking version)
@@ -896,15 +1576,18 @@ impl BlockingBenchmarkRunner {
     ///   not implemented in blocking mode yet)
     ///
     /// ## Returns
-    /// - `Ok(PerformanceMetrics)`: Comprehensive latency and throughput metrics
+    /// - `Ok((PerformanceMetrics, first_byte))`: Comprehensive latency and
+    ///   throughput metrics, plus the first-byte latency distribution when
+    ///   `--first-byte-latency` is set
     /// - `Err(anyhow::Error)`: Test execution failure
     fn run_one_way_test(
         &self,
         transport_config: &TransportConfig,
         results_manager: Option<&mut crate::results_blocking::BlockingResultsManager>,
-    ) -> Result<PerformanceMetrics> {
+    ) -> Result<(PerformanceMetrics, Option<LatencyMetrics>)> {
         let mut metrics_collector =
             MetricsCollector::new(Some(LatencyType::OneWay), self.config.percentiles.clone())?;
+        metrics_collector.set_reservoir(self.args.latency_reservoir);
 
         // Check for problematic configurations and adapt automatically
         if self.mechanism == IpcMechanism::SharedMemory && self.config.concurrency > 1 {
@@ -913,15 +1596,22 @@ impl BlockingBenchmarkRunner {
             );
         }
 
+        if self.args.pair && !self.pairs_directions() {
+            warn!(
+                "{} cannot send from server to client; --pair ignored",
+                self.mechanism
+            );
+        }
+
         // For blocking mode, we only implement single-threaded execution
         // Multi-threaded execution can be added in future if needed
-        self.run_single_threaded_one_way(
+        let first_byte = self.run_single_threaded_one_way(
             transport_config,
             &mut metrics_collector,
             results_manager,
         )?;
 
-        Ok(metrics_collector.get_metrics())
+        Ok((metrics_collector.get_metrics(), first_byte))
     }
 
     /// Run round-trip latency test (blocking version)
@@ -943,17 +1633,19 @@ impl BlockingBenchmarkRunner {
     /// a response with a modified message ID to verify proper round-trip completion.
     ///
     /// ## Returns
-    /// - `Ok(PerformanceMetrics)`: Round-trip latency and throughput metrics
+    /// - `Ok((PerformanceMetrics, Option<CpuUsage>))`: Round-trip latency and
+    ///   throughput metrics, and the client thread's CPU usage where measurable
     /// - `Err(anyhow::Error)`: Test execution failure
     fn run_round_trip_test(
         &self,
         transport_config: &TransportConfig,
         results_manager: Option<&mut crate::results_blocking::BlockingResultsManager>,
-    ) -> Result<PerformanceMetrics> {
+    ) -> Result<(PerformanceMetrics, Option<CpuUsage>)> {
         let mut metrics_collector = MetricsCollector::new(
             Some(LatencyType::RoundTrip),
             self.config.percentiles.clone(),
         )?;
+        metrics_collector.set_reservoir(self.args.latency_reservoir);
 
         // Check for problematic configurations and adapt automatically
         if self.mechanism == IpcMechanism::SharedMemory && self.config.concurrency > 1 {
@@ -961,15 +1653,27 @@ impl BlockingBenchmarkRunner {
                 "Shared memory with concurrency > 1 has race conditions. Forcing concurrency = 1."
             );
         }
+        if self.args.compare_pooling && !self.compares_pooling() {
+            warn!(
+                "{} has no connections to pool; --compare-pooling ignored",
+                self.mechanism
+            );
+        }
 
         // For blocking mode, we only implement single-threaded execution
-        self.run_single_threaded_round_trip(
+        let client_cpu = self.run_single_threaded_round_trip(
             transport_config,
             &mut metrics_collector,
             results_manager,
         )?;
+        if metrics_collector.error_count > 0 {
+            warn!(
+                "{} round-trip replies did not match their request",
+                metrics_collector.error_count
+            );
+        }
 
-        Ok(metrics_collector.get_metrics())
+        Ok((metrics_collector.get_metrics(), client_cpu))
     }
 
     /// Run single-threaded one-way test (blocking version)
@@ -1009,12 +1713,15 @@ impl BlockingBenchmarkRunner {
         transport_config: &TransportConfig,
         metrics_collector: &mut MetricsCollector,
         mut results_manager: Option<&mut crate::results_blocking::BlockingResultsManager>,
-    ) -> Result<()> {
-        let mut client_transport = BlockingTransportFactory::create(
-            &self.mechanism,
-            self.args.shm_direct,
-            self.config.send_delay,
-        )?;
+    ) -> Result<Option<LatencyMetrics>> {
+        let mut client_transport = segmentation::wrap_blocking(
+            BlockingTransportFactory::create(
+                &self.mechanism,
+                self.args.shm_direct,
+                self.config.send_delay,
+            )?,
+            transport_config.segment_size,
+        );
 
         // Create a temporary file for server to write latencies
         let latency_file_path = std::env::temp_dir()
@@ -1023,15 +1730,34 @@ impl BlockingBenchmarkRunner {
             .to_string();
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self
-            .spawn_server_process_with_latency_file(transport_config, Some(&latency_file_path))?;
+        let pairs = self.pairs_directions();
+        let mut command = self.server_command(transport_config, Some(&latency_file_path))?;
+        if pairs {
+            command.arg("--pair");
+        }
+        let mut server = self.spawn_server(command)?;
 
         // Wait for the server to signal that it's ready
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe")?;
 
+        // Further multicast subscribers join the group port the first one
+        // bound, each with a latency file of its own
+        let mut extra_subscribers: Vec<(ServerHandle, String)> = Vec::new();
+        #[cfg(target_os = "linux")]
+        if self.mechanism == IpcMechanism::UdpMulticast {
+            for subscriber in 1..self.args.subscribers {
+                let path = format!("{}.subscriber{}", latency_file_path, subscriber);
+                let mut subscriber_server =
+                    self.spawn_server_process_with_latency_file(transport_config, Some(&path))?;
+                subscriber_server
+                    .wait_ready(transport_config)
+                    .with_context(|| format!("Subscriber {} failed to start", subscriber))?;
+                extra_subscribers.push((subscriber_server, path));
+            }
+        }
+
         // --- Client Logic ---
         // Apply client affinity if specified
         if let Some(client_core_id) = self.config.client_affinity {
@@ -1047,8 +1773,8 @@ impl BlockingBenchmarkRunner {
             }
         }
 
-        client_transport
-            .start_client_blocking(transport_config)
+        self.setup_retry
+            .start_client_blocking(client_transport.as_mut(), transport_config)
             .with_context(|| {
                 format!(
                     "start_client_blocking failed: mechanism={:?}, socket_path={}, host={}, port={}",
@@ -1058,9 +1784,41 @@ impl BlockingBenchmarkRunner {
                     transport_config.port
                 )
             })?;
+        self.handshake(client_transport.as_mut(), transport_config)?;
+
+        // Extra producers open the queue now and start along with this client
+        #[cfg(target_os = "linux")]
+        let mut extra_producers = if self.args.producers > 1 {
+            Some(producers::ProducerProcesses::spawn(
+                &self.args,
+                &self.config,
+                transport_config,
+            )?)
+        } else {
+            None
+        };
 
+        // Watch the queue fill while the test runs
+        let queue_sampler = QueueDepthSampler::start(
+            client_transport.queue_depth_probe(),
+            self.args.queue_depth_interval,
+        );
+
+        #[cfg(target_os = "linux")]
+        if let Some(extra_producers) = extra_producers.as_mut() {
+            extra_producers.start()?;
+        }
         let payload = vec![0u8; self.config.message_size];
+        let mut pacer = Pacer::for_delay(self.args.pacing, self.config.send_delay);
+        let affinity =
+            AffinityMonitor::for_test(self.config.client_affinity, self.pinned_server(&server));
         let start_time = Instant::now();
+        let mut sent = 0usize;
+        let mut client_work = Vec::new();
+        let mut allocations = self
+            .args
+            .no_alloc_hot_path
+            .then(HotPathAllocations::default);
 
         // Client just sends messages - server measures and records latencies
         if let Some(duration) = self.config.duration {
@@ -1073,14 +1831,36 @@ impl BlockingBenchmarkRunner {
                 let _ = client_transport.send_blocking(&canary);
             }
 
-            while start_time.elapsed() < duration {
-                let message = Message::new(i, payload.clone(), MessageType::OneWay);
+            while start_time.elapsed() < duration && !shutdown::requested() {
+                if let Some(gap) = self.args.idle_gap {
+                    std::thread::sleep(gap);
+                }
+                let mut message = self.messages.message(
+                    i,
+                    MessageType::OneWay,
+                    self.args.deadline,
+                    self.message_priority(i),
+                )?;
+                if let Some(hook) = &self.client_hook {
+                    client_work.push(hook::run_timed(hook.as_ref(), &message));
+                    message.set_timestamp_now();
+                }
 
+                let cpu_start = self.cpu_mark();
+                let call_start = cpu_start.map(|_| Instant::now());
                 match client_transport.send_blocking(&message) {
                     Ok(_) => {
+                        if let Some((cpu, start)) = Self::cpu_since(cpu_start).zip(call_start) {
+                            metrics_collector.record_cpu_split(cpu, start.elapsed())?;
+                        }
+                        self.messages.recycle(message);
                         i += 1;
-                        if let Some(delay) = self.config.send_delay {
-                            std::thread::sleep(delay);
+                        sent += 1;
+                        if let Some(allocations) = allocations.as_mut() {
+                            allocations.sent();
+                        }
+                        if let Some(pacer) = pacer.as_mut() {
+                            pacer.pace_blocking();
                         }
                     }
                     Err(_) => break,
@@ -1097,15 +1877,61 @@ impl BlockingBenchmarkRunner {
             }
 
             for i in 0..msg_count {
-                let message = Message::new(i as u64, payload.clone(), MessageType::OneWay);
+                if shutdown::requested() {
+                    break;
+                }
+                if let Some(gap) = self.args.idle_gap {
+                    std::thread::sleep(gap);
+                }
+                let mut message = self.messages.message(
+                    i as u64,
+                    MessageType::OneWay,
+                    self.args.deadline,
+                    self.message_priority(i as u64),
+                )?;
+                if let Some(hook) = &self.client_hook {
+                    client_work.push(hook::run_timed(hook.as_ref(), &message));
+                    message.set_timestamp_now();
+                }
+                let cpu_start = self.cpu_mark();
+                let call_start = cpu_start.map(|_| Instant::now());
                 client_transport.send_blocking(&message)?;
+                if let Some((cpu, start)) = Self::cpu_since(cpu_start).zip(call_start) {
+                    metrics_collector.record_cpu_split(cpu, start.elapsed())?;
+                }
+                self.messages.recycle(message);
+                sent += 1;
+                if let Some(allocations) = allocations.as_mut() {
+                    allocations.sent();
+                }
 
-                if let Some(delay) = self.config.send_delay {
-                    std::thread::sleep(delay);
+                if let Some(pacer) = pacer.as_mut() {
+                    pacer.pace_blocking();
                 }
             }
         }
 
+        #[cfg(target_os = "linux")]
+        if let Some(extra_producers) = extra_producers {
+            extra_producers.finish()?;
+        }
+        metrics_collector.set_queue_depth(queue_sampler.and_then(QueueDepthSampler::finish));
+        metrics_collector.set_pacing(pacer.and_then(Pacer::finish));
+        metrics_collector.set_affinity(affinity.map(AffinityMonitor::finish).unwrap_or_default());
+        let send_allocations = allocations.and_then(HotPathAllocations::finish);
+        metrics_collector.set_send_allocations(send_allocations);
+
+        // --pair: the server streams as many messages back
+        if pairs && !shutdown::requested() {
+            info!("Running server-to-client phase of the pair test");
+            metrics_collector.set_pair(Some(pair::receive_reverse_blocking(
+                client_transport.as_mut(),
+                sent,
+                !self.config.include_first_message,
+                &self.config.percentiles,
+            )?));
+        }
+
         // --- Cleanup ---
         // For PMQ and SHM, send a shutdown message to signal the server to exit
         // (These mechanisms don't have a connection to close like sockets)
@@ -1121,10 +1947,20 @@ impl BlockingBenchmarkRunner {
             std::thread::sleep(std::time::Duration::from_millis(50));
         }
 
+        self.transport_stats.add(client_transport.stats());
         client_transport.close_blocking()?;
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error")?;
+        metrics_collector.set_server(server.take_report());
+        for (subscriber, (subscriber_server, _)) in (1..).zip(extra_subscribers.iter_mut()) {
+            subscriber_server
+                .shutdown()
+                .with_context(|| format!("Subscriber {} exited with an error", subscriber))?;
+        }
+        if self.args.no_alloc_hot_path {
+            alloc_count::check_hot_path(self.mechanism, send_allocations, sent)?;
+        }
 
         // --- Read server-measured latencies from file ---
         debug!(
@@ -1135,6 +1971,25 @@ impl BlockingBenchmarkRunner {
         let file =
             std::fs::File::open(&latency_file_path).context("Failed to open latency file")?;
         let reader = BufReader::new(file);
+        let priorities = if self.args.priorities.is_empty() {
+            Vec::new()
+        } else {
+            read_message_tags(&priority_file_for(&latency_file_path))
+        };
+        let producer_tags = if self.args.producers > 1 {
+            read_message_tags(&producer_file_for(&latency_file_path))
+        } else {
+            Vec::new()
+        };
+        let mut producer_tally = ProducerTally::new(self.config.percentiles.clone());
+        let mut subscriber_tally: Option<crate::multicast::SubscriberTally> = match self.mechanism {
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => Some(crate::multicast::SubscriberTally::new(
+                self.args.subscribers.max(1),
+                self.config.percentiles.clone(),
+            )?),
+            _ => None,
+        };
 
         for (i, line) in reader.lines().enumerate() {
             let line = line.context("Failed to read line from latency file")?;
@@ -1145,6 +2000,18 @@ impl BlockingBenchmarkRunner {
             let latency = std::time::Duration::from_nanos(latency_ns);
 
             metrics_collector.record_message(self.config.message_size, Some(latency))?;
+            if let Some(&work) = client_work.get(i) {
+                metrics_collector.record_processing(work, Some(latency))?;
+            }
+            if let Some(&priority) = priorities.get(i) {
+                metrics_collector.record_priority_latency(priority, latency)?;
+            }
+            if let Some(&producer) = producer_tags.get(i) {
+                producer_tally.record(producer, wall_send_ns, latency)?;
+            }
+            if let Some(tally) = subscriber_tally.as_mut() {
+                tally.record(0, latency)?;
+            }
 
             if let Some(ref mut manager) = results_manager {
                 let record = crate::results::MessageLatencyRecord::new(
@@ -1159,12 +2026,53 @@ impl BlockingBenchmarkRunner {
             }
         }
 
+        // The other subscribers' latencies count toward the overall
+        // distribution too
+        for (subscriber, (_, path)) in (1..).zip(&extra_subscribers) {
+            let tally = subscriber_tally
+                .as_mut()
+                .context("Extra subscribers without a multicast test")?;
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read subscriber {} latencies", subscriber))?;
+            for line in contents.lines() {
+                let (_, latency_ns) = crate::benchmark::parse_latency_file_line(line)?;
+                let latency = std::time::Duration::from_nanos(latency_ns);
+                metrics_collector.record_message(self.config.message_size, Some(latency))?;
+                tally.record(subscriber, latency)?;
+            }
+            let _ = std::fs::remove_file(path);
+        }
+
         debug!("Successfully read and recorded server-measured latencies");
+        metrics_collector.set_producers(producer_tally.finish(&self.args.priorities));
+        if let Some(tally) = subscriber_tally {
+            let report = tally.finish(sent);
+            if report.worst_loss_percent() > 0.0 {
+                warn!(
+                    "Multicast subscribers lost up to {:.2}% of {} messages",
+                    report.worst_loss_percent(),
+                    sent
+                );
+            }
+            metrics_collector.set_subscribers(report);
+        }
 
         // Clean up temporary latency file
         let _ = std::fs::remove_file(&latency_file_path);
 
-        Ok(())
+        if self.args.deadline.is_some() {
+            metrics_collector.expired_count +=
+                read_expired_count(&expired_file_for(&latency_file_path));
+        }
+
+        if !self.args.first_byte_latency {
+            return Ok(None);
+        }
+        read_first_byte_latencies(
+            &first_byte_file_for(&latency_file_path),
+            self.config.message_size,
+            &self.config.percentiles,
+        )
     }
 
     /// Run single-threaded round-trip test (blocking version)
@@ -1183,30 +2091,41 @@ impl BlockingBenchmarkRunner {
     ///
     /// Round-trip latency is measured from the start of the send operation
     /// to the completion of the receive operation, capturing the complete
-    /// communication cycle including any processing delays.
+    /// communication cycle including any processing delays. With
+    /// `--spin-wait` the client polls for each reply instead of blocking in
+    /// the kernel, and the thread's CPU time over the loop is reported so the
+    /// latency gain can be weighed against the core it burns.
     ///
     /// ## Returns
-    /// - `Ok(())`: Test completed successfully, metrics updated
+    /// - `Ok(Option<CpuUsage>)`: Test completed successfully, metrics updated;
+    ///   client thread CPU usage where the platform can measure it
     /// - `Err(anyhow::Error)`: Test execution failure
     fn run_single_threaded_round_trip(
         &self,
         transport_config: &TransportConfig,
         metrics_collector: &mut MetricsCollector,
         mut results_manager: Option<&mut crate::results_blocking::BlockingResultsManager>,
-    ) -> Result<()> {
-        let mut client_transport = BlockingTransportFactory::create(
-            &self.mechanism,
-            self.args.shm_direct,
-            self.config.send_delay,
-        )?;
+    ) -> Result<Option<CpuUsage>> {
+        let mut client_transport = segmentation::wrap_blocking(
+            BlockingTransportFactory::create(
+                &self.mechanism,
+                self.args.shm_direct,
+                self.config.send_delay,
+            )?,
+            transport_config.segment_size,
+        );
 
         // --- Server Process Spawning ---
-        let (mut server_process, mut pipe_reader) = self.spawn_server_process(transport_config)?;
+        let pooling = self.compares_pooling();
+        let mut command = self.server_command(transport_config, None)?;
+        if pooling {
+            command.arg("--compare-pooling");
+        }
+        let mut server = self.spawn_server(command)?;
 
         // Wait for the server to signal that it's ready
-        let mut buf = [0; 1];
-        pipe_reader
-            .read_exact(&mut buf)
+        let transport_config = &server
+            .wait_ready(transport_config)
             .context("Failed to read server ready signal from pipe")?;
         debug!("Client received server ready signal for round-trip test");
 
@@ -1227,10 +2146,30 @@ impl BlockingBenchmarkRunner {
             }
         }
 
-        client_transport.start_client_blocking(transport_config)?;
+        self.setup_retry
+            .start_client_blocking(client_transport.as_mut(), transport_config)?;
+        self.handshake(client_transport.as_mut(), transport_config)?;
+
+        let spin = self.args.spin_wait;
+        if spin.is_some() && !client_transport.supports_try_receive() {
+            warn!(
+                "{} does not support non-blocking receives; --spin-wait ignored",
+                self.mechanism
+            );
+        }
+
+        // Watch the queue fill while the test runs
+        let queue_sampler = QueueDepthSampler::start(
+            client_transport.queue_depth_probe(),
+            self.args.queue_depth_interval,
+        );
 
         let payload = vec![0u8; self.config.message_size];
+        let mut pacer = Pacer::for_delay(self.args.pacing, self.config.send_delay);
+        let affinity =
+            AffinityMonitor::for_test(self.config.client_affinity, self.pinned_server(&server));
         let start_time = Instant::now();
+        let cpu_start = get_thread_cpu_time_ns();
 
         if let Some(duration) = self.config.duration {
             // Duration-based test
@@ -1240,25 +2179,55 @@ impl BlockingBenchmarkRunner {
             if !self.config.include_first_message {
                 let canary = Message::new(u64::MAX, payload.clone(), MessageType::Request);
                 if client_transport.send_blocking(&canary).is_ok() {
-                    let _ = client_transport.receive_blocking();
+                    let _ = receive_blocking_with(client_transport.as_mut(), spin);
                 }
             }
 
-            while start_time.elapsed() < duration {
+            while start_time.elapsed() < duration && !shutdown::requested() {
+                if let Some(gap) = self.args.idle_gap {
+                    std::thread::sleep(gap);
+                }
                 // Capture send timestamp for streaming record (wall clock)
                 let send_timestamp_ns =
                     crate::results::MessageLatencyRecord::current_timestamp_ns();
+                let cpu_start = self.cpu_mark();
                 let send_time = Instant::now();
-                let message = Message::new(i, payload.clone(), MessageType::Request);
-
-                match client_transport.send_blocking(&message) {
+                let send_ns = get_monotonic_time_ns();
+                let connection = connection_reuse(pooling, i);
+                let mut fresh = match connection {
+                    ConnectionReuse::Cold => Some(self.connect_fresh(transport_config)?),
+                    ConnectionReuse::Warm => None,
+                };
+                let transport = fresh.as_deref_mut().unwrap_or(client_transport.as_mut());
+                let message = self.messages.message(
+                    i,
+                    MessageType::Request,
+                    self.args.deadline,
+                    self.message_priority(i),
+                )?;
+
+                match transport.send_blocking(&message) {
                     Ok(_) => {
-                        if let Some(delay) = self.config.send_delay {
-                            std::thread::sleep(delay);
+                        if let Some(pacer) = pacer.as_mut() {
+                            pacer.pace_blocking();
                         }
-                        if client_transport.receive_blocking().is_ok() {
-                            let latency = send_time.elapsed();
-
+                        let reply = receive_reply_blocking(
+                            transport,
+                            message.id,
+                            spin,
+                            &mut metrics_collector.error_count,
+                        );
+                        let latency = send_time.elapsed();
+                        let cpu = Self::cpu_since(cpu_start);
+                        let receive_ns = get_monotonic_time_ns();
+                        if let Some(mut fresh) = fresh {
+                            self.transport_stats.add(fresh.stats());
+                            fresh.close_blocking()?;
+                        }
+                        let matched = matches!(reply, Ok(Some(_)));
+                        if matched && self.past_deadline(latency) {
+                            metrics_collector.expired_count += 1;
+                        } else if let Ok(Some(reply)) = &reply {
                             // Stream latency if enabled
                             if let Some(ref mut manager) = results_manager {
                                 let record = crate::results::MessageLatencyRecord::new(
@@ -1275,7 +2244,25 @@ impl BlockingBenchmarkRunner {
                             // Record in metrics collector
                             metrics_collector
                                 .record_message(self.config.message_size, Some(latency))?;
+                            if !self.args.priorities.is_empty() {
+                                metrics_collector
+                                    .record_priority_latency(self.message_priority(i), latency)?;
+                            }
+                            self.record_round_trip_phases(
+                                metrics_collector,
+                                send_ns,
+                                reply,
+                                receive_ns,
+                            )?;
+                            self.run_client_hook(metrics_collector, &message, latency)?;
+                            if let Some(cpu) = cpu {
+                                metrics_collector.record_cpu_split(cpu, latency)?;
+                            }
+                            if pooling {
+                                metrics_collector.record_connection_latency(connection, latency)?;
+                            }
                         }
+                        self.messages.recycle(message);
                         i += 1;
                     }
                     Err(_) => break,
@@ -1289,28 +2276,61 @@ impl BlockingBenchmarkRunner {
             if !self.config.include_first_message {
                 let canary = Message::new(u64::MAX, payload.clone(), MessageType::Request);
                 if client_transport.send_blocking(&canary).is_ok() {
-                    let _ = client_transport.receive_blocking();
+                    let _ = receive_blocking_with(client_transport.as_mut(), spin);
                 }
             }
 
             for i in 0..msg_count {
+                if shutdown::requested() {
+                    break;
+                }
+                if let Some(gap) = self.args.idle_gap {
+                    std::thread::sleep(gap);
+                }
                 // Capture send timestamp for streaming record (wall clock)
                 let send_timestamp_ns =
                     crate::results::MessageLatencyRecord::current_timestamp_ns();
+                let cpu_start = self.cpu_mark();
                 let send_time = Instant::now();
-                let message = Message::new(i as u64, payload.clone(), MessageType::Request);
-                client_transport.send_blocking(&message)?;
-
-                if let Some(delay) = self.config.send_delay {
-                    std::thread::sleep(delay);
+                let send_ns = get_monotonic_time_ns();
+                let connection = connection_reuse(pooling, i as u64);
+                let mut fresh = match connection {
+                    ConnectionReuse::Cold => Some(self.connect_fresh(transport_config)?),
+                    ConnectionReuse::Warm => None,
+                };
+                let transport = fresh.as_deref_mut().unwrap_or(client_transport.as_mut());
+                let message = self.messages.message(
+                    i as u64,
+                    MessageType::Request,
+                    self.args.deadline,
+                    self.message_priority(i as u64),
+                )?;
+                transport.send_blocking(&message)?;
+
+                if let Some(pacer) = pacer.as_mut() {
+                    pacer.pace_blocking();
                 }
 
-                client_transport.receive_blocking()?;
+                let reply = receive_reply_blocking(
+                    transport,
+                    message.id,
+                    spin,
+                    &mut metrics_collector.error_count,
+                )?;
 
                 let latency = send_time.elapsed();
+                let cpu = Self::cpu_since(cpu_start);
+                let receive_ns = get_monotonic_time_ns();
+                if let Some(mut fresh) = fresh {
+                    self.transport_stats.add(fresh.stats());
+                    fresh.close_blocking()?;
+                }
 
-                // Record latency for all measured messages
-                if true {
+                // Record latency only for replies that answer this request
+                // in time; a reply past the request's deadline is dropped
+                if reply.is_some() && self.past_deadline(latency) {
+                    metrics_collector.expired_count += 1;
+                } else if let Some(reply) = &reply {
                     // Stream latency if enabled
                     if let Some(ref mut manager) = results_manager {
                         let record = crate::results::MessageLatencyRecord::new(
@@ -1326,10 +2346,31 @@ impl BlockingBenchmarkRunner {
 
                     // Record in metrics collector
                     metrics_collector.record_message(self.config.message_size, Some(latency))?;
+                    if !self.args.priorities.is_empty() {
+                        metrics_collector
+                            .record_priority_latency(self.message_priority(i as u64), latency)?;
+                    }
+                    self.record_round_trip_phases(metrics_collector, send_ns, reply, receive_ns)?;
+                    self.run_client_hook(metrics_collector, &message, latency)?;
+                    if let Some(cpu) = cpu {
+                        metrics_collector.record_cpu_split(cpu, latency)?;
+                    }
+                    if pooling {
+                        metrics_collector.record_connection_latency(connection, latency)?;
+                    }
                 }
+                self.messages.recycle(message);
             }
         }
 
+        let wall_time_ns = start_time.elapsed().as_nanos() as u64;
+        let client_cpu = cpu_start
+            .zip(get_thread_cpu_time_ns())
+            .map(|(start, end)| CpuUsage::new(end.saturating_sub(start), wall_time_ns));
+        metrics_collector.set_queue_depth(queue_sampler.and_then(QueueDepthSampler::finish));
+        metrics_collector.set_pacing(pacer.and_then(Pacer::finish));
+        metrics_collector.set_affinity(affinity.map(AffinityMonitor::finish).unwrap_or_default());
+
         // --- Cleanup ---
         // For PMQ and SHM, send a shutdown message to signal the server to exit
         // (These mechanisms don't have a connection to close like sockets)
@@ -1347,12 +2388,24 @@ impl BlockingBenchmarkRunner {
             std::thread::sleep(std::time::Duration::from_millis(50));
         }
 
+        self.transport_stats.add(client_transport.stats());
         client_transport.close_blocking()?;
-        server_process
-            .wait()
+        server
+            .shutdown()
             .context("Server process exited with an error")?;
+        metrics_collector.set_server(server.take_report());
 
-        Ok(())
+        Ok(client_cpu)
+    }
+}
+
+/// Connection round trip `id` uses: with `pooling`, odd-numbered requests
+/// open a fresh connection and the rest reuse the persistent one.
+fn connection_reuse(pooling: bool, id: u64) -> ConnectionReuse {
+    if pooling && id % 2 == 1 {
+        ConnectionReuse::Cold
+    } else {
+        ConnectionReuse::Warm
     }
 }
 
@@ -1448,8 +2501,8 @@ mod tests {
 
         let transport_config = runner.create_transport_config_internal(&args).unwrap();
 
-        // Verify basic configuration
-        assert!(transport_config.port > 0);
+        // Verify basic configuration; the server picks an ephemeral port
+        assert_eq!(transport_config.port, 0);
         assert_eq!(transport_config.host, "127.0.0.1");
         assert!(transport_config.buffer_size > 0);
     }
@@ -1478,10 +2531,9 @@ mod tests {
         // We expect this to either succeed or fail with a clear error about the binary
         // The important thing is that the method signature is correct
         match result {
-            Ok((mut child, _reader)) => {
+            Ok(mut server) => {
                 // Clean up the spawned process
-                let _ = child.kill();
-                let _ = child.wait();
+                let _ = server.kill();
             }
             Err(e) => {
                 // Expected in some test environments
@@ -2154,7 +3206,7 @@ mod tests {
     /// its safe default, and TCP/UDS size to fit all messages.
     #[test]
     fn test_blocking_transport_config_buffer_size_logic() {
-        const DURATION_MODE_BUFFER_SIZE: usize = 1_073_741_824;
+        #[cfg(target_os = "linux")]
         const PMQ_SAFE_DEFAULT_BUFFER_SIZE: usize = 8192;
 
         let mut base_config = BenchmarkConfig {
@@ -2222,7 +3274,7 @@ mod tests {
             );
         }
 
-        // Duration mode uses 1 GB for TCP (not SHM).
+        // Duration mode uses the bounded streaming buffer for TCP too.
         {
             let mut dur_config = base_config.clone();
             dur_config.duration = Some(Duration::from_secs(1));
@@ -2231,8 +3283,9 @@ mod tests {
                 BlockingBenchmarkRunner::new(dur_config, IpcMechanism::TcpSocket, args.clone());
             let tc = runner.create_transport_config_internal(&args).unwrap();
             assert_eq!(
-                tc.buffer_size, DURATION_MODE_BUFFER_SIZE,
-                "TCP duration mode should use 1GB buffer"
+                tc.buffer_size,
+                std::cmp::max(65536, (1024 + 64) * 2),
+                "TCP duration mode should use the bounded streaming buffer"
             );
         }
 
@@ -2253,7 +3306,7 @@ mod tests {
     }
 
     /// Verify SHM in blocking duration mode still gets the
-    /// fixed 64 KB buffer, not the 1 GB default used by TCP/UDS.
+    /// fixed 64 KB buffer rather than anything sized from the run length.
     #[test]
     fn test_blocking_shm_duration_mode_uses_fixed_buffer() {
         let config = BenchmarkConfig {
@@ -2285,8 +3338,7 @@ mod tests {
         let expected = std::cmp::max(65536, (1024 + 64) * 2);
         assert_eq!(
             tc.buffer_size, expected,
-            "Blocking SHM duration mode should use fixed \
-             64KB buffer, not the 1GB TCP/UDS default"
+            "Blocking SHM duration mode should use fixed 64KB buffer"
         );
     }
 
@@ -0,0 +1,166 @@
+//! Bootstrap confidence intervals of latency percentiles.
+//!
+//! A percentile from one run is an estimate: run the same test again and
+//! P99 lands somewhere else, so a 5% difference between two mechanisms may
+//! be nothing but that spread. Where raw latencies are at hand (the
+//! `--latency-reservoir` samples, or every message of a streaming file in
+//! `analyze`), [`percentile_intervals`] resamples them with replacement
+//! [`RESAMPLES`] times, takes each requested percentile of every resample,
+//! and reports the range holding the middle [`CONFIDENCE_LEVEL`] percent of
+//! those estimates. Two mechanisms whose intervals do not overlap differ by
+//! more than the measurement can explain.
+//!
+//! A reservoir holds fewer latencies than the test measured, so intervals
+//! computed from it are wider than the full run would give: they err on
+//! the side of calling a difference noise.
+
+use crate::metrics::utils::format_latency;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Resamples drawn to estimate each interval
+pub const RESAMPLES: usize = 1000;
+
+/// Share of the resampled estimates each interval holds, in percent
+pub const CONFIDENCE_LEVEL: f64 = 95.0;
+
+/// Most latencies resampled; larger inputs are thinned evenly first, which
+/// widens the interval
+pub const MAX_SAMPLES: usize = 100_000;
+
+/// Fewest latencies an interval is computed from
+pub const MIN_SAMPLES: usize = 10;
+
+/// Seed of the resampling, so the same latencies give the same intervals
+const SEED: u64 = 0x5EED;
+
+/// Confidence interval of one percentile
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PercentileInterval {
+    /// Percentile level (0.0 to 100.0)
+    pub percentile: f64,
+
+    /// Lower bound in nanoseconds
+    pub lower_ns: u64,
+
+    /// Upper bound in nanoseconds
+    pub upper_ns: u64,
+}
+
+impl PercentileInterval {
+    /// Whether this interval and `other` share any latency, that is,
+    /// whether the difference between the two percentiles may be noise
+    pub fn overlaps(&self, other: &PercentileInterval) -> bool {
+        self.lower_ns <= other.upper_ns && other.lower_ns <= self.upper_ns
+    }
+}
+
+impl std::fmt::Display for PercentileInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "P{} {}-{}",
+            self.percentile,
+            format_latency(self.lower_ns),
+            format_latency(self.upper_ns)
+        )
+    }
+}
+
+/// Intervals of the requested `percentiles` (those above 0 and up to 100)
+/// of the latencies in `samples_ns`; empty with fewer than [`MIN_SAMPLES`].
+pub fn percentile_intervals(samples_ns: &[u64], percentiles: &[f64]) -> Vec<PercentileInterval> {
+    let percentiles: Vec<f64> = percentiles
+        .iter()
+        .copied()
+        .filter(|p| *p > 0.0 && *p <= 100.0)
+        .collect();
+    if samples_ns.len() < MIN_SAMPLES || percentiles.is_empty() {
+        return Vec::new();
+    }
+
+    let step = (samples_ns.len() + MAX_SAMPLES - 1) / MAX_SAMPLES;
+    let samples: Vec<u64> = samples_ns.iter().step_by(step).copied().collect();
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut resample = vec![0u64; samples.len()];
+    let mut estimates = vec![Vec::with_capacity(RESAMPLES); percentiles.len()];
+    for _ in 0..RESAMPLES {
+        for slot in resample.iter_mut() {
+            *slot = samples[rng.gen_range(0..samples.len())];
+        }
+        for (percentile, estimates) in percentiles.iter().zip(estimates.iter_mut()) {
+            let index = nearest_rank(resample.len(), *percentile);
+            estimates.push(*resample.select_nth_unstable(index).1);
+        }
+    }
+
+    let tail = (100.0 - CONFIDENCE_LEVEL) / 2.0;
+    percentiles
+        .into_iter()
+        .zip(estimates)
+        .map(|(percentile, mut estimates)| {
+            estimates.sort_unstable();
+            PercentileInterval {
+                percentile,
+                lower_ns: estimates[nearest_rank(estimates.len(), tail)],
+                upper_ns: estimates[nearest_rank(estimates.len(), 100.0 - tail)],
+            }
+        })
+        .collect()
+}
+
+/// Index of the nearest-rank `percentile` in a sorted list of `len` values
+fn nearest_rank(len: usize, percentile: f64) -> usize {
+    let rank = ((percentile / 100.0) * len as f64).ceil() as usize;
+    rank.clamp(1, len) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intervals_bracket_the_percentile() {
+        let samples: Vec<u64> = (1..=2_000).collect();
+        let intervals = percentile_intervals(&samples, &[0.0, 50.0, 99.0]);
+        assert_eq!(intervals.len(), 2);
+
+        let p50 = intervals[0];
+        assert_eq!(p50.percentile, 50.0);
+        assert!(p50.lower_ns < 1_000 && p50.upper_ns > 1_000);
+        // About 1.96 standard errors of the median either side
+        assert!(p50.upper_ns - p50.lower_ns < 150);
+
+        let p99 = intervals[1];
+        assert!(p99.lower_ns < 1_980 && p99.upper_ns > 1_980);
+        assert!(!p50.overlaps(&p99));
+
+        // Seeded: the same samples give the same intervals
+        assert_eq!(
+            percentile_intervals(&samples, &[0.0, 50.0, 99.0]),
+            intervals
+        );
+    }
+
+    #[test]
+    fn test_too_few_samples() {
+        assert!(percentile_intervals(&[1; MIN_SAMPLES - 1], &[50.0]).is_empty());
+        let constant = percentile_intervals(&[7; MIN_SAMPLES], &[50.0, 100.0]);
+        assert_eq!(constant.len(), 2);
+        assert!(constant.iter().all(|i| i.lower_ns == 7 && i.upper_ns == 7));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let interval = |lower_ns, upper_ns| PercentileInterval {
+            percentile: 50.0,
+            lower_ns,
+            upper_ns,
+        };
+        assert!(interval(10, 20).overlaps(&interval(20, 30)));
+        assert!(interval(10, 40).overlaps(&interval(20, 30)));
+        assert!(!interval(10, 19).overlaps(&interval(20, 30)));
+        assert_eq!(interval(1_000, 1_500).to_string(), "P50 1.00μs-1.50μs");
+    }
+}
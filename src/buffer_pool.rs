@@ -0,0 +1,107 @@
+//! Reusable payload buffers for the client's send loops.
+//!
+//! Allocating a fresh `Vec<u8>` for every message puts the allocator on
+//! the measured path, and at small message sizes its cost and variance show
+//! up in the latency. A [`BufferPool`] hands out buffers that come back
+//! once the message is sent, so a send loop that returns each message's
+//! payload allocates only until the pool holds as many buffers as it has
+//! messages in flight.
+//!
+//! The pool keeps at most [`BufferPool::MAX_FREE`] buffers; ones returned
+//! beyond that are dropped.
+
+use std::sync::Mutex;
+
+/// Free list of payload buffers, all with room for the largest payload
+#[derive(Debug)]
+pub struct BufferPool {
+    free: Mutex<Vec<Vec<u8>>>,
+    capacity: usize,
+}
+
+impl BufferPool {
+    /// Most free buffers kept
+    pub const MAX_FREE: usize = 64;
+
+    /// A pool of buffers that hold up to `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            // Reserved up front, so returning a buffer never allocates
+            free: Mutex::new(Vec::with_capacity(Self::MAX_FREE)),
+            capacity,
+        }
+    }
+
+    /// A buffer of `len` bytes, reused when one is free. A reused buffer
+    /// keeps the bytes of the message it last held; only bytes beyond that
+    /// message's length are zeroed, so reuse costs no pass over the payload.
+    pub fn take(&self, len: usize) -> Vec<u8> {
+        let reused = self.free.lock().ok().and_then(|mut free| free.pop());
+        match reused {
+            Some(mut buffer) => {
+                buffer.resize(len, 0);
+                buffer
+            }
+            None => {
+                let mut buffer = Vec::with_capacity(self.capacity.max(len));
+                buffer.resize(len, 0);
+                buffer
+            }
+        }
+    }
+
+    /// Return `buffer` for reuse. Buffers too small for the largest
+    /// payload, or beyond [`MAX_FREE`](Self::MAX_FREE), are dropped.
+    pub fn give(&self, buffer: Vec<u8>) {
+        if buffer.capacity() < self.capacity {
+            return;
+        }
+        if let Ok(mut free) = self.free.lock() {
+            if free.len() < Self::MAX_FREE {
+                free.push(buffer);
+            }
+        }
+    }
+
+    /// Number of free buffers
+    pub fn free(&self) -> usize {
+        self.free.lock().map_or(0, |free| free.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_are_reused_without_zeroing() {
+        let pool = BufferPool::new(16);
+        let mut buffer = pool.take(8);
+        assert_eq!(buffer, [0; 8]);
+        buffer.fill(0xAB);
+        let address = buffer.as_ptr();
+        pool.give(buffer);
+        assert_eq!(pool.free(), 1);
+
+        let again = pool.take(12);
+        assert_eq!(again.as_ptr(), address);
+        assert_eq!(
+            again,
+            [0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0, 0, 0, 0]
+        );
+        assert_eq!(pool.free(), 0);
+
+        // A buffer without room for the largest payload is not kept
+        pool.give(Vec::with_capacity(4));
+        assert_eq!(pool.free(), 0);
+    }
+
+    #[test]
+    fn test_free_list_is_bounded() {
+        let pool = BufferPool::new(1);
+        for _ in 0..BufferPool::MAX_FREE + 3 {
+            pool.give(vec![0; 1]);
+        }
+        assert_eq!(pool.free(), BufferPool::MAX_FREE);
+    }
+}
@@ -0,0 +1,333 @@
+//! Persistent campaign state for resumable runs.
+//!
+//! A long matrix run can be saved as it goes with `--manifest FILE`: each
+//! time a cell (a mechanism at one message size and concurrency) completes
+//! successfully, its results are added to the manifest, which is rewritten
+//! atomically. After an interruption, `--resume FILE` loads the manifest,
+//! skips the cells it already holds and carries their saved results into the
+//! final output, while the manifest keeps being updated with the new cells.
+//!
+//! Failed and aborted cells are not recorded, so a resumed run retries them.
+//! The cell key does not cover other options (message count, test types,
+//! transport settings), so the manifest also keeps the options the campaign
+//! was started with, and a run whose options differ is refused rather than
+//! merging results measured another way. The options that only pick cells
+//! (mechanisms, message size and concurrency) may change, so a campaign can
+//! be extended.
+
+use crate::benchmark::BenchmarkConfig;
+use crate::cli::{Args, IpcMechanism};
+use crate::results::{BenchmarkResults, BenchmarkStatus};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Options that name files, control logging or pick the cells to run, and
+/// so may differ between the runs of one campaign
+const UNCOMPARED_OPTIONS: [&str; 20] = [
+    "mechanisms",
+    "message_size",
+    "concurrency",
+    "config",
+    "quiet",
+    "verbose",
+    "dump_cli_schema",
+    "log_file",
+    "output_file",
+    "streaming_output_json",
+    "streaming_output_csv",
+    "machine_output",
+    "percentile_distribution",
+    "append_summary",
+    "manifest",
+    "resume",
+    "upload",
+    "upload_retries",
+    "upload_headers_file",
+    "run_id",
+];
+
+/// The options of `args` that decide what a campaign measures, in the
+/// order the command defines them
+pub fn canonical_args(args: &Args) -> Vec<String> {
+    args.explicit_tokens
+        .iter()
+        .filter(|(id, _)| !UNCOMPARED_OPTIONS.contains(&id.as_str()))
+        .flat_map(|(_, tokens)| tokens.iter().cloned())
+        .collect()
+}
+
+/// One cell of a campaign's test matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cell {
+    /// Mechanism tested
+    pub mechanism: IpcMechanism,
+
+    /// Message size in bytes
+    pub message_size: usize,
+
+    /// Requested concurrency
+    pub concurrency: usize,
+}
+
+impl Cell {
+    /// The cell `mechanism` runs as under `config`.
+    pub fn new(mechanism: IpcMechanism, config: &BenchmarkConfig) -> Self {
+        Self {
+            mechanism,
+            message_size: config.message_size,
+            concurrency: config.concurrency,
+        }
+    }
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}B concurrency {}",
+            self.mechanism, self.message_size, self.concurrency
+        )
+    }
+}
+
+/// A completed cell and the results it produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedCell {
+    /// The cell
+    pub cell: Cell,
+
+    /// Its results, as they appear in the final output
+    pub results: BenchmarkResults,
+}
+
+/// Cells completed so far by a campaign
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignManifest {
+    /// Identifier of the run that started the campaign
+    pub run_id: String,
+
+    /// When the campaign was started
+    pub started: chrono::DateTime<chrono::Utc>,
+
+    /// Options the campaign was started with (see [`canonical_args`])
+    pub args: Vec<String>,
+
+    /// Cells completed so far, in completion order
+    pub completed: Vec<CompletedCell>,
+}
+
+impl CampaignManifest {
+    /// An empty manifest for a campaign started with `args`.
+    pub fn new(args: Vec<String>) -> Self {
+        Self {
+            run_id: crate::run_id::get().to_string(),
+            started: chrono::Utc::now(),
+            args,
+            completed: Vec::new(),
+        }
+    }
+
+    /// Read a manifest written by an earlier run.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read campaign manifest {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid campaign manifest {}", path.display()))
+    }
+
+    /// Write the manifest, replacing `path` atomically so an interruption
+    /// never leaves a truncated file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp = path.with_extension("partial");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to write campaign manifest {}", path.display()))
+    }
+
+    /// Fail unless `args` are the options the campaign was started with.
+    pub fn check_args(&self, args: &[String]) -> Result<()> {
+        if self.args != args {
+            bail!(
+                "Campaign {} was started with options [{}], not [{}]; resume it with \
+                 the same options or start a new manifest",
+                self.run_id,
+                self.args.join(" "),
+                args.join(" ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Saved results of `cell`, if it has completed.
+    pub fn completed(&self, cell: &Cell) -> Option<&BenchmarkResults> {
+        self.completed
+            .iter()
+            .find(|entry| entry.cell == *cell)
+            .map(|entry| &entry.results)
+    }
+
+    /// Record the results of `cell`, replacing any earlier ones. Only
+    /// successful results complete a cell; others are ignored and return
+    /// `false`.
+    pub fn record(&mut self, cell: Cell, results: &BenchmarkResults) -> bool {
+        if results.status != BenchmarkStatus::Success {
+            return false;
+        }
+        self.completed.retain(|entry| entry.cell != cell);
+        self.completed.push(CompletedCell {
+            cell,
+            results: results.clone(),
+        });
+        true
+    }
+}
+
+/// A manifest being kept up to date on disk during a run
+#[derive(Debug)]
+pub struct Campaign {
+    path: PathBuf,
+    manifest: CampaignManifest,
+}
+
+impl Campaign {
+    /// The campaign requested with `--manifest` or `--resume`, if any.
+    ///
+    /// A resumed manifest is updated in place unless `--manifest` names
+    /// another file. Resuming with options other than those the campaign
+    /// was started with fails.
+    pub fn from_args(args: &Args) -> Result<Option<Self>> {
+        if let Some(resume) = &args.resume {
+            let manifest = CampaignManifest::load(resume)?;
+            manifest.check_args(&canonical_args(args))?;
+            info!(
+                "Resuming campaign {} from {}: {} cells completed",
+                manifest.run_id,
+                resume.display(),
+                manifest.completed.len()
+            );
+            let path = args.manifest.clone().unwrap_or_else(|| resume.clone());
+            return Ok(Some(Self { path, manifest }));
+        }
+        Ok(args.manifest.clone().map(|path| Self {
+            path,
+            manifest: CampaignManifest::new(canonical_args(args)),
+        }))
+    }
+
+    /// Saved results of `cell`, if an earlier run completed it.
+    pub fn completed(&self, cell: &Cell) -> Option<&BenchmarkResults> {
+        self.manifest.completed(cell)
+    }
+
+    /// Record the results of `cell` and save the manifest if they
+    /// completed it.
+    pub fn record(&mut self, cell: Cell, results: &BenchmarkResults) -> Result<()> {
+        if self.manifest.record(cell, results) {
+            self.manifest.save(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(mechanism: IpcMechanism) -> BenchmarkResults {
+        BenchmarkResults::new(mechanism, 64, 1024, 1, Some(10), None, 0, false, true)
+    }
+
+    #[test]
+    fn test_manifest_records_successful_cells() {
+        let mut manifest = CampaignManifest::new(Vec::new());
+        let tcp = Cell {
+            mechanism: IpcMechanism::TcpSocket,
+            message_size: 64,
+            concurrency: 1,
+        };
+        let shm = Cell {
+            mechanism: IpcMechanism::SharedMemory,
+            ..tcp
+        };
+
+        let mut failed = result(IpcMechanism::SharedMemory);
+        failed.set_failure("server did not start".to_string());
+        assert!(!manifest.record(shm, &failed));
+        let mut aborted = result(IpcMechanism::SharedMemory);
+        aborted.set_aborted();
+        assert!(!manifest.record(shm, &aborted));
+        // A skipped mechanism is probed again when the campaign resumes
+        let mut skipped = result(IpcMechanism::SharedMemory);
+        skipped.set_skipped("/dev/shm is read-only".to_string());
+        assert!(!manifest.record(shm, &skipped));
+        assert!(manifest.completed(&shm).is_none());
+
+        assert!(manifest.record(tcp, &result(IpcMechanism::TcpSocket)));
+        assert!(manifest.record(tcp, &result(IpcMechanism::TcpSocket)));
+        assert_eq!(manifest.completed.len(), 1);
+        assert!(manifest.completed(&tcp).is_some());
+        assert!(manifest
+            .completed(&Cell {
+                message_size: 128,
+                ..tcp
+            })
+            .is_none());
+        assert_eq!(tcp.to_string(), "TCP Socket 64B concurrency 1");
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("campaign.json");
+        let mut manifest = CampaignManifest::new(Vec::new());
+        let cell = Cell {
+            mechanism: IpcMechanism::TcpSocket,
+            message_size: 64,
+            concurrency: 2,
+        };
+        manifest.record(cell, &result(IpcMechanism::TcpSocket));
+        manifest.save(&path).unwrap();
+
+        let loaded = CampaignManifest::load(&path).unwrap();
+        assert_eq!(loaded.run_id, manifest.run_id);
+        assert_eq!(loaded.completed.len(), 1);
+        assert_eq!(loaded.completed[0].cell, cell);
+        assert!(CampaignManifest::load(&dir.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    fn test_resume_requires_the_same_options() {
+        let parse = |args: &[&str]| {
+            Args::try_parse_explicit_from(
+                std::iter::once("ipc-benchmark").chain(args.iter().copied()),
+            )
+            .unwrap()
+        };
+        let started = parse(&[
+            "-m",
+            "all",
+            "-i",
+            "1000",
+            "--manifest",
+            "c.json",
+            "-o",
+            "a.json",
+        ]);
+        let manifest = CampaignManifest::new(canonical_args(&started));
+        assert_eq!(manifest.args, ["--msg-count", "1000"]);
+
+        // Files, logging and the cells to run may change between runs
+        let resumed = parse(&["-i", "1000", "-m", "tcp", "--resume", "c.json", "-v"]);
+        assert!(manifest.check_args(&canonical_args(&resumed)).is_ok());
+
+        let changed = parse(&["-m", "all", "-i", "2000", "--resume", "c.json"]);
+        let err = manifest
+            .check_args(&canonical_args(&changed))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("[--msg-count 1000]"), "{}", err);
+    }
+}
@@ -38,13 +38,25 @@
 //! - **Advanced**: Buffer sizes, network settings, percentiles
 
 use clap::{
-    builder::styling::{AnsiColor, Styles},
-    Parser, ValueEnum,
+    builder::{
+        styling::{AnsiColor, Styles},
+        PossibleValue, TypedValueParser,
+    },
+    Parser, Subcommand, ValueEnum,
 };
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::first_message::FirstMessage;
+pub use crate::mechanism::IpcMechanism;
+use crate::metrics::utils::ByteUnits;
+pub use crate::options::{
+    HttpVersion, MachineOutput, Profile, ResponseMode, RuntimeFlavor, ShmWait, SpinWait,
+    UdsCredentialMode,
+};
+use crate::smt::SmtPolicy;
+
 /// IPC Benchmark Suite - A comprehensive tool for measuring IPC performance
 ///
 /// This application benchmarks various Inter-Process Communication mechanisms
@@ -135,6 +147,21 @@ pub struct Args {
     #[arg(short = 'd', long, value_parser = parse_duration, help_heading = TIMING)]
     pub duration: Option<Duration>,
 
+    /// Re-run the whole suite at this interval (e.g., "1h", "30m")
+    ///
+    /// The suite runs at the start of each interval until --until has
+    /// elapsed or the run is interrupted. With -o, the final results of each
+    /// repetition are appended, with its start and end times, as one JSON
+    /// line to the output file.
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_duration, help_heading = TIMING)]
+    pub repeat_every: Option<Duration>,
+
+    /// Stop starting repetitions once this long has passed (e.g., "24h")
+    ///
+    /// Without it, --repeat-every continues until interrupted.
+    #[arg(long, value_name = "WINDOW", value_parser = parse_duration, requires = "repeat_every", help_heading = TIMING)]
+    pub until: Option<Duration>,
+
     /// Delay between sending messages (e.g., "10ms", "50us")
     ///
     /// When specified, this introduces a fixed pause after each message is sent.
@@ -143,6 +170,38 @@ pub struct Args {
     #[arg(long, value_parser = parse_duration_micros, help_heading = TIMING)]
     pub send_delay: Option<Duration>,
 
+    /// How the client waits out --send-delay
+    ///
+    /// `sleep` sleeps for the delay after each send. `spin-sleep` releases
+    /// messages on absolute deadlines, sleeping until just before each and
+    /// spinning the rest; `timerfd` waits on a Linux timerfd armed with the
+    /// deadline. The deadline engines hold sub-millisecond intervals that
+    /// `sleep` overshoots. Achieved intervals are reported under "pacing".
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = crate::pacing::PacingMode::Sleep, requires = "send_delay", help_heading = TIMING)]
+    pub pacing: crate::pacing::PacingMode,
+
+    /// Idle period before each measured message (e.g., "500ms", "30s", "2m")
+    ///
+    /// The client sends one message, sits idle for this long, then sends and
+    /// measures the next one, repeating for every message. Latency after an
+    /// idle period exposes cache/TLB and CPU power-state decay and, with
+    /// --tcp-keepalive, keepalive effects that back-to-back sends hide.
+    /// Combine with a small -i, since each message costs one idle period.
+    /// The unmeasured first message of --first-message exclude goes out
+    /// without one, in async and blocking mode alike.
+    /// Applies to single-client tests.
+    #[arg(long, value_parser = parse_duration_micros, help_heading = TIMING)]
+    pub idle_gap: Option<Duration>,
+
+    /// Deadline for each measured message after its send (e.g., "100us", "2ms")
+    ///
+    /// Messages carry the deadline, and the receiver drops and counts those
+    /// that arrive late: the server for one-way messages, the client for
+    /// round-trip replies. Dropped messages are left out of the latency
+    /// distribution and reported as deadline misses for each test.
+    #[arg(long, value_parser = parse_duration_micros, help_heading = TIMING)]
+    pub deadline: Option<Duration>,
+
     /// Number of concurrent processes/threads
     ///
     /// Controls the level of parallelism during testing. Higher values can reveal
@@ -166,6 +225,24 @@ pub struct Args {
     #[arg(long, value_name = "CORE", help_heading = CONCURRENCY)]
     pub client_affinity: Option<usize>,
 
+    /// What to do when the client and server cores are SMT siblings
+    ///
+    /// Hardware threads of one physical core contend for its execution
+    /// units and caches. `warn` runs as pinned with a warning, `separate`
+    /// moves the server to another physical core, and `allow` runs as
+    /// pinned silently. Linux only.
+    #[arg(long, value_enum, default_value_t = SmtPolicy::Warn, value_name = "POLICY", help_heading = CONCURRENCY)]
+    pub smt_siblings: SmtPolicy,
+
+    /// Run up to N mechanisms at the same time
+    ///
+    /// Mechanisms run in groups of N. Each one gets its own pair of CPU
+    /// cores for its server and client, so they never share a CPU, but they
+    /// can still contend for caches, memory bandwidth and the kernel; results
+    /// are marked as collected concurrently. Implies --blocking.
+    #[arg(long, value_name = "N", default_value_t = 1, help_heading = CONCURRENCY)]
+    pub parallel_mechanisms: usize,
+
     /// Path to the final JSON output file. If used without a path, defaults to 'benchmark_results.json'.
     ///
     /// If the flag is not used, no final JSON file will be written, but a summary
@@ -199,6 +276,49 @@ pub struct Args {
     #[arg(short = 'w', long, default_value_t = crate::defaults::WARMUP_ITERATIONS, help_heading = TIMING)]
     pub warmup_iterations: usize,
 
+    /// Warm up for this long instead of a number of iterations
+    /// (e.g., "2s", "500ms").
+    ///
+    /// Each mechanism sends warmup messages until the time is up, so fast
+    /// mechanisms are not held to the message count slow ones need.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_micros, conflicts_with = "warmup_iterations", help_heading = TIMING)]
+    pub warmup: Option<Duration>,
+
+    /// Warm up one mechanism for LENGTH, a message count or a duration
+    /// (e.g., "pmq=5s", "shm=500"). Repeatable.
+    ///
+    /// Overrides -w and --warmup for that mechanism. The warmup runs once
+    /// per mechanism, before its one-way and round-trip tests.
+    #[arg(long = "warmup-for", value_name = "MECHANISM=LENGTH", value_parser = crate::warmup::parse_override, help_heading = TIMING)]
+    pub warmup_for: Vec<crate::warmup::WarmupOverride>,
+
+    /// Measure the warmup messages as round trips and report them
+    /// separately.
+    ///
+    /// The warmup distribution and its curve over ten segments appear
+    /// under `warmup` in the results, and never count towards the measured
+    /// latencies. Not available with -m udp-multicast, which has no replies.
+    #[arg(long, help_heading = TIMING)]
+    pub capture_warmup: bool,
+
+    /// Tune the run for latency, throughput, or a balance of both.
+    ///
+    /// Picks warmup, receiver batching, reply wait strategy and buffer
+    /// sizes for each mechanism; options given on the command line still
+    /// win. The choices are recorded with each result.
+    #[arg(long, value_enum, value_name = "PROFILE", help_heading = TIMING)]
+    pub profile: Option<Profile>,
+
+    /// Read options from a JSON file.
+    ///
+    /// The file holds one object keyed by long option name (`mechanisms`
+    /// for -m), as each test's `reproduction.config` in the results: `true`
+    /// for a flag, a count for -v, a string or number for one value and an
+    /// array for several.
+    /// Options given on the command line win.
+    #[arg(long, value_name = "FILE", help_heading = OUTPUT_AND_LOGGING)]
+    pub config: Option<PathBuf>,
+
     /// Continue running other benchmarks even if one fails
     ///
     /// By default, the suite stops on the first benchmark failure.
@@ -224,6 +344,14 @@ pub struct Args {
     #[arg(short, long, action = clap::ArgAction::Count, help_heading = OUTPUT_AND_LOGGING)]
     pub verbose: u8,
 
+    /// Print every option and subcommand as JSON and exit
+    ///
+    /// Describes flags, value counts, defaults, accepted values, conflicts
+    /// and help text of this exact version, for wrapper UIs and
+    /// orchestration tools that generate their forms from it.
+    #[arg(long, help_heading = OUTPUT_AND_LOGGING)]
+    pub dump_cli_schema: bool,
+
     /// Path to the output log file for detailed diagnostics, or 'stderr'.
     ///
     /// Specifies the file where detailed, structured logs will be written.
@@ -232,6 +360,15 @@ pub struct Args {
     #[arg(long, value_name = "PATH | stderr", help_heading = OUTPUT_AND_LOGGING)]
     pub log_file: Option<String>,
 
+    /// Most debug/trace events each log call site may emit per second.
+    ///
+    /// Per-message code logs at debug and trace level; past this rate its
+    /// events are dropped and counted, so -v can stay on while measuring.
+    /// The number suppressed is logged at the end of the run. 0 disables
+    /// the limit.
+    #[arg(long, value_name = "EVENTS", default_value_t = 100, help_heading = OUTPUT_AND_LOGGING)]
+    pub log_rate_limit: u32,
+
     /// JSON output file for streaming results. If used without a path, defaults to 'benchmark_streaming_output.json'.
     ///
     /// Writes partial results to this file in real-time during the benchmark.
@@ -246,6 +383,157 @@ pub struct Args {
     #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = Some("benchmark_streaming_output.csv"), help_heading = OUTPUT_AND_LOGGING)]
     pub streaming_output_csv: Option<PathBuf>,
 
+    /// Columns of the streaming CSV, separated by commas.
+    ///
+    /// Any of timestamp_ns, message_id, mechanism, message_size,
+    /// one_way_latency_ns, round_trip_latency_ns, run_id, worker_id,
+    /// cpu_core and phase, in the order given; `default` stands for the
+    /// first seven and `all` for every column. The columns are described in
+    /// a .schema.json file written next to the CSV.
+    #[arg(long, value_name = "COLUMNS", value_parser = crate::csv_schema::parse_columns, requires = "streaming_output_csv", help_heading = OUTPUT_AND_LOGGING)]
+    pub csv_columns: Option<crate::csv_schema::CsvColumns>,
+
+    /// Identifier recorded in logs, streaming records and results.
+    ///
+    /// Defaults to a random UUID. Pass the same value to a standalone
+    /// client and server to correlate their output. Letters, digits, '.',
+    /// '_' and '-' only, as it names uploaded files.
+    #[arg(long, value_name = "ID", value_parser = crate::run_id::parse, help_heading = OUTPUT_AND_LOGGING)]
+    pub run_id: Option<String>,
+
+    /// Print results to stdout as JSON instead of the human summary.
+    ///
+    /// `json` (the default when no format is given) prints the complete
+    /// results document once the run finishes; `jsonl` prints each
+    /// mechanism's results as one line as soon as it completes. Console
+    /// logging is turned off so stdout carries nothing else; detailed logs
+    /// still go to the log file.
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "json", help_heading = OUTPUT_AND_LOGGING)]
+    pub machine_output: Option<MachineOutput>,
+
+    /// Write the final JSON results on one line, without indentation.
+    ///
+    /// Applies to the results file and `--machine-output json`.
+    #[arg(long, help_heading = OUTPUT_AND_LOGGING)]
+    pub output_compact: bool,
+
+    /// Trim the final JSON results to the fields needed.
+    ///
+    /// A comma-separated list. `-NAME` drops every member called NAME, at
+    /// any depth (e.g. `-histogram_data`), and `-a.b` drops the member at
+    /// that path. A path like `results.one_way_results.latency` keeps that
+    /// member; once any path is kept, members on no kept path are dropped.
+    /// Paths step through arrays.
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',', allow_hyphen_values = true, help_heading = OUTPUT_AND_LOGGING)]
+    pub output_fields: Vec<String>,
+
+    /// Directory for per-test latency percentile distributions.
+    ///
+    /// Writes one `.hgrm` file per mechanism and latency type in
+    /// HdrHistogram's percentile distribution format, with values in
+    /// microseconds, ready for the HdrHistogram plotter.
+    #[arg(long, value_name = "DIR", help_heading = OUTPUT_AND_LOGGING)]
+    pub percentile_distribution: Option<PathBuf>,
+
+    /// Append one summary row per mechanism to this CSV file.
+    ///
+    /// Each row holds the test time, run ID, --summary-label labels,
+    /// P50/P99 latency and throughput. The file is created with a header
+    /// if missing and kept across runs, for trend plots.
+    #[arg(long, value_name = "FILE", help_heading = OUTPUT_AND_LOGGING)]
+    pub append_summary: Option<PathBuf>,
+
+    /// Label the --append-summary rows with KEY=VALUE (repeatable).
+    #[arg(long = "summary-label", value_name = "KEY=VALUE", value_parser = crate::history::parse_label, requires = "append_summary", help_heading = OUTPUT_AND_LOGGING)]
+    pub summary_labels: Vec<(String, String)>,
+
+    /// Keep up to this many raw latencies per test in the JSON results
+    /// (10000 if no number is given).
+    ///
+    /// The latencies are sampled uniformly from every one the test
+    /// measured, for statistical tests that need raw data, such as
+    /// Kolmogorov-Smirnov tests or bootstrapping, without the streaming
+    /// output files.
+    #[arg(long, value_name = "SAMPLES", num_args = 0..=1, default_missing_value = "10000", value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize), help_heading = OUTPUT_AND_LOGGING)]
+    pub latency_reservoir: Option<usize>,
+
+    /// Also report the throughput of the messages within this latency
+    /// budget (e.g., "100us", "2ms").
+    ///
+    /// Each test's goodput counts the messages whose latency stayed within
+    /// the budget and gives the message and byte rates they make up,
+    /// next to the raw throughput. It is recorded as `goodput` in each
+    /// test's JSON metrics.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_micros, help_heading = OUTPUT_AND_LOGGING)]
+    pub goodput_budget: Option<Duration>,
+
+    /// Report the percentile of each of these latency thresholds in each
+    /// test (e.g., "100us,1ms").
+    ///
+    /// Each answers "what share of messages took at most this long", the
+    /// form SLAs are usually written in. They are recorded as `sla` in each
+    /// test's JSON metrics.
+    #[arg(long, value_name = "DURATIONS", value_delimiter = ',', value_parser = parse_duration_micros, help_heading = OUTPUT_AND_LOGGING)]
+    pub sla_thresholds: Vec<Duration>,
+
+    /// Scale of byte sizes and rates in summaries and tables.
+    ///
+    /// Binary units are powers of 1024 (KiB, MiB, GiB); SI units are
+    /// powers of 1000 (kB, MB, GB). JSON and CSV output always carry raw
+    /// values in the unit their field names state.
+    #[arg(long, value_enum, default_value_t = ByteUnits::Binary, help_heading = OUTPUT_AND_LOGGING)]
+    pub units: ByteUnits,
+
+    /// Decimal places of scaled sizes, rates and latencies in summaries
+    /// and tables.
+    #[arg(long, value_name = "DIGITS", default_value_t = 2, value_parser = clap::value_parser!(u8).range(0..=9).map(usize::from), help_heading = OUTPUT_AND_LOGGING)]
+    pub precision: usize,
+
+    /// JSON file of per-scenario pass criteria.
+    ///
+    /// Each scenario selects tests by mechanism, message size and latency
+    /// type and bounds their P99 latency and throughput. The checks are
+    /// reported under `criteria` in the JSON results, and the run fails if
+    /// any bound is missed.
+    #[arg(long, value_name = "FILE", help_heading = OUTPUT_AND_LOGGING)]
+    pub criteria: Option<PathBuf>,
+
+    /// Save each completed mechanism to a campaign manifest.
+    ///
+    /// The manifest is rewritten as each (mechanism, message size,
+    /// concurrency) cell completes, so an interrupted run can be continued
+    /// with --resume.
+    #[arg(long, value_name = "FILE", help_heading = OUTPUT_AND_LOGGING)]
+    pub manifest: Option<PathBuf>,
+
+    /// Resume the campaign saved in a manifest.
+    ///
+    /// Cells the manifest already holds are skipped and their saved results
+    /// included in the output. The manifest keeps being updated, unless
+    /// --manifest names another file.
+    #[arg(long, value_name = "MANIFEST", help_heading = OUTPUT_AND_LOGGING)]
+    pub resume: Option<PathBuf>,
+
+    /// Upload the result files to s3://bucket/prefix or an http(s):// URL
+    /// once the run is finalized.
+    ///
+    /// The final JSON, streaming output and --append-summary files go to
+    /// DEST/<run ID>/, followed by a manifest.json listing them with their
+    /// sizes and checksums. S3 uploads use the AWS CLI; HTTP uploads a PUT
+    /// through curl.
+    #[arg(long, value_name = "DEST", value_parser = crate::upload::Destination::parse, conflicts_with = "server", help_heading = OUTPUT_AND_LOGGING)]
+    pub upload: Option<crate::upload::Destination>,
+
+    /// Retries of each failed upload, waiting twice as long each time.
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "upload", help_heading = OUTPUT_AND_LOGGING)]
+    pub upload_retries: u32,
+
+    /// File of extra HTTP headers for --upload, one per line, e.g.
+    /// "Authorization: Bearer ...". Read by curl, so secrets stay off the
+    /// command line.
+    #[arg(long, value_name = "FILE", requires = "upload", help_heading = OUTPUT_AND_LOGGING)]
+    pub upload_headers_file: Option<PathBuf>,
+
     /// Percentiles to calculate for latency metrics
     ///
     /// Specifies which percentile values to calculate and report in results.
@@ -259,9 +547,10 @@ pub struct Args {
     /// Controls the size of internal buffers used by IPC mechanisms.
     ///
     /// Larger buffers can improve throughput but increase memory usage.
-    /// If not specified, a smart default is calculated based on message count and size
-    /// to avoid backpressure, except for PMQ which uses a safe 8192-byte default
-    /// to stay within typical OS limits.
+    /// If not specified, a smart default is calculated: shared memory and
+    /// duration-mode sockets use a bounded 64KB streaming buffer (or 2x the
+    /// message size), message-count sockets are sized to the whole run, and
+    /// PMQ uses a safe 8192-byte default to stay within typical OS limits.
     #[arg(long, help_heading = ADVANCED)]
     pub buffer_size: Option<usize>,
 
@@ -275,9 +564,9 @@ pub struct Args {
 
     /// Port for TCP sockets
     ///
-    /// Specifies the TCP port number for socket communication.
-    /// The benchmark will automatically use unique ports for each test
-    /// to avoid conflicts when testing multiple mechanisms.
+    /// Specifies the TCP port number for standalone --server/--client
+    /// communication. Benchmarks that spawn their own server ignore it and
+    /// bind an OS-assigned ephemeral port, so tests never collide on a port.
     #[arg(long, default_value_t = 8080, help_heading = ADVANCED)]
     pub port: u16,
 
@@ -290,15 +579,404 @@ pub struct Args {
     #[arg(long, default_value_t = 0, help_heading = ADVANCED)]
     pub pmq_priority: u32,
 
-    /// Include the first message in the results.
+    /// Send measured messages at these priorities, in turn
+    ///
+    /// Message N is sent at the (N mod count)-th listed priority, and the
+    /// latency of each priority is reported separately. Priorities map onto
+    /// PMQ message priorities, SO_PRIORITY on TCP and UDS sockets (values
+    /// above 6 need CAP_NET_ADMIN), and per-priority receive lanes in the
+    /// SHM ring. Enables blocking mode.
+    #[arg(long, value_delimiter = ',', help_heading = ADVANCED)]
+    pub priorities: Vec<u8>,
+
+    /// Send one-way messages from this many client processes at once
+    ///
+    /// The benchmark's client and N-1 extra producer processes send into
+    /// the same POSIX message queue. With --priorities, producer N sends
+    /// all of its messages at the (N mod count)-th listed priority, so
+    /// producers compete at mixed priorities. Each producer's message
+    /// count, throughput and latency are reported along with Jain's
+    /// fairness index over their throughput. PMQ one-way tests only.
+    /// Enables blocking mode.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..), help_heading = ADVANCED)]
+    pub producers: u8,
+
+    /// Measure how much low-priority bulk traffic delays high-priority
+    /// messages, sending this many bulk messages after each critical one
+    ///
+    /// The one-way test runs twice: once with only the critical messages,
+    /// and once with each followed by the bulk messages at a lower priority.
+    /// The critical messages' P50, P99 and max of both runs are compared.
+    /// Combine with --server-rate so the consumer falls behind and messages
+    /// queue, and with --send-delay to pace the critical stream. PMQ and SHM
+    /// one-way tests only. Enables blocking mode.
+    #[arg(long, value_name = "BULK_PER_CRITICAL", value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "priorities", help_heading = ADVANCED)]
+    pub priority_inversion: Option<u32>,
+
+    /// Send message IDs of this many bits, wrapping to zero after the
+    /// largest (8 to 32)
+    ///
+    /// IDs normally count up in 64 bits and never wrap. With a narrow ID
+    /// space, each test's IDs start at --id-start and wrap as often as the
+    /// message count requires, and the server tracks them across the wrap
+    /// point, reporting lost, reordered and duplicate IDs and the number of
+    /// wraps. Applies to tests with a spawned server; not with --producers.
+    #[arg(long, value_name = "BITS", value_parser = clap::value_parser!(u8).range(crate::sequence::MIN_WIDTH as i64..=crate::sequence::MAX_WIDTH as i64), help_heading = ADVANCED)]
+    pub id_width: Option<u8>,
+
+    /// First message ID of each test with --id-width, e.g. just below the
+    /// wrap point
+    #[arg(long, value_name = "ID", default_value_t = 0, requires = "id_width", help_heading = ADVANCED)]
+    pub id_start: u64,
+
+    /// Receive -m udp-multicast messages in this many subscriber processes
+    ///
+    /// Each subscriber joins the multicast group and measures the latency
+    /// of every datagram it gets. Their latencies make up the one-way
+    /// distribution, and each subscriber's delivery and loss are reported
+    /// separately. One-way tests only.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..), help_heading = ADVANCED)]
+    pub subscribers: u16,
+
+    /// IPv4 multicast group -m udp-multicast publishes to.
+    ///
+    /// The group is joined and published on the interface whose address is
+    /// --host (loopback by default), with a TTL of 1. Defaults to
+    /// 239.255.0.1.
+    #[arg(long, value_name = "GROUP", help_heading = ADVANCED)]
+    pub multicast_group: Option<std::net::Ipv4Addr>,
+
+    /// Alternate round-trip requests between a persistent and a fresh
+    /// connection
+    ///
+    /// Even-numbered requests reuse the connection held open for the whole
+    /// test, as a pooled client would; odd-numbered ones connect, send,
+    /// receive the reply and disconnect, so their latency includes
+    /// connection setup. Both distributions are reported from the same
+    /// run. TCP and UDS only. Enables blocking mode.
+    #[arg(long, help_heading = ADVANCED)]
+    pub compare_pooling: bool,
+
+    /// Measure each direction of the one-way test's connection in turn
+    ///
+    /// After the client has sent its messages, the server streams as many
+    /// messages of the same size back over the same connection, and the
+    /// client times them. The server-to-client latency and its ratio to the
+    /// client-to-server latency are reported under `pair`. Needs a
+    /// mechanism whose server can send to its client and a local server.
+    /// Enables blocking mode.
+    #[arg(long, help_heading = ADVANCED)]
+    pub pair: bool,
+
+    /// Split each measured call into on-CPU and off-CPU time.
     ///
-    /// By default, the benchmark sends one message before starting measurements
-    /// to warm up caches and memory allocations. This first message is
-    /// discarded to prevent its typically higher latency from skewing the
-    /// results. Use this flag to include it in the final statistics.
+    /// Reads the client thread's CPU clock around each one-way send and
+    /// each round trip, and reports how much of the call the thread spent
+    /// running (copying, in syscalls) and how much blocked or descheduled.
+    /// The two extra clock reads add a little to every round trip.
+    /// Enables blocking mode.
     #[arg(long, help_heading = ADVANCED)]
+    pub cpu_split: bool,
+
+    /// How each test treats its first message.
+    ///
+    /// By default (`exclude`), the benchmark sends one message before
+    /// starting measurements to warm up caches and memory allocations, and
+    /// discards it to prevent its typically higher latency from skewing the
+    /// results. `include` counts the first message in the final statistics.
+    /// `both` runs each test twice, once each way, reports the run that
+    /// discarded it, and sets the first message of the other run against
+    /// it as the test's cold-start penalty.
+    #[arg(long, value_enum, default_value_t = FirstMessage::Exclude, help_heading = ADVANCED)]
+    pub first_message: FirstMessage,
+
+    /// Same as `--first-message include`, for existing scripts.
+    #[arg(long, hide = true, conflicts_with = "first_message")]
     pub include_first_message: bool,
 
+    /// Drop the kernel page cache before each measured test phase.
+    ///
+    /// Runs `sync` and writes `3` to `/proc/sys/vm/drop_caches` so cold-path
+    /// results are produced deliberately. Requires root (Linux only). A
+    /// failure is logged and recorded in the results metadata rather than
+    /// aborting the run.
+    #[arg(long, help_heading = ADVANCED)]
+    pub drop_caches: bool,
+
+    /// Recreate all transport resources for each measured test phase.
+    ///
+    /// By default the socket path, shared memory segment and queue name are
+    /// generated once per mechanism and reused by the warmup and every test
+    /// phase. With this flag each phase gets brand-new resources, so nothing
+    /// stays warm from the previous phase.
+    #[arg(long, help_heading = ADVANCED)]
+    pub fresh_resources: bool,
+
+    /// Tokio runtime flavor used in async mode.
+    ///
+    /// Runtime scheduling is part of what async mode measures: the
+    /// current-thread runtime avoids cross-thread wakeups, the multi-thread
+    /// runtime spreads tasks over a worker pool. Ignored with --blocking.
+    #[arg(long, value_enum, default_value_t = RuntimeFlavor::MultiThread, help_heading = ADVANCED)]
+    pub runtime: RuntimeFlavor,
+
+    /// Worker threads for the multi-thread runtime (default: one per CPU core)
+    #[arg(long, value_name = "N", help_heading = ADVANCED)]
+    pub worker_threads: Option<usize>,
+
+    /// Scheduler ticks between polls for I/O and timer events (Tokio default: 61)
+    ///
+    /// Lower values react to I/O sooner at the cost of more polling.
+    #[arg(long, value_name = "TICKS", help_heading = ADVANCED)]
+    pub event_interval: Option<u32>,
+
+    /// Also sample CPU frequency at this interval during each test (e.g., "1s")
+    ///
+    /// CPU frequency and thermal throttle counters are always read before
+    /// and after each test where cpufreq is available (Linux). Sampling
+    /// during the test catches a drop that recovers before it ends.
+    #[arg(long, value_parser = parse_duration_micros, help_heading = ADVANCED)]
+    pub frequency_sample_interval: Option<Duration>,
+
+    /// Flag a test as throttled when its sustained CPU frequency falls more
+    /// than this many percent below the frequency at its start.
+    #[arg(long, default_value_t = 10.0, value_name = "PERCENT", help_heading = ADVANCED)]
+    pub frequency_drop_threshold: f64,
+
+    /// Measure scheduler wakeup latency during each test by sleeping a
+    /// probe thread for this interval over and over (e.g., "1ms")
+    ///
+    /// Each wakeup's delay past the interval is recorded as
+    /// `scheduler_noise`, so tail latencies can be compared with the noise
+    /// the OS adds to any thread.
+    #[arg(long, value_parser = parse_duration_micros, value_name = "DURATION", help_heading = ADVANCED)]
+    pub scheduler_noise_interval: Option<Duration>,
+
+    /// Refuse to run when the machine is busy
+    ///
+    /// Load average, per-core utilization and other processes' CPU use are
+    /// always sampled before the run and recorded under system_info.load; a
+    /// busy machine is otherwise only warned about. Linux only.
+    #[arg(long, default_value_t = false, help_heading = ADVANCED)]
+    pub require_idle: bool,
+
+    /// Skip the resource footprint check before the run
+    ///
+    /// By default the shared memory, message queues, open files and
+    /// ephemeral ports the whole plan will hold are estimated up front, and
+    /// the run refuses to start if any exceeds the host's limit.
+    #[arg(long, default_value_t = false, help_heading = ADVANCED)]
+    pub skip_footprint_check: bool,
+
+    /// Fail a one-way test whose client send loop allocates heap memory
+    ///
+    /// Payload buffers come from a pool and the UDS, TCP, SHM and PMQ
+    /// transports reuse one serialization buffer, so their blocking one-way
+    /// send loops need no allocation once the first message is sent. The
+    /// allocations after it are recorded as `send_allocations`, and a test
+    /// with any fails. Round-trip tests are not checked: each reply is
+    /// received into a new message. Automatically enables --blocking.
+    /// Needs a build with `--features alloc-count`.
+    #[arg(long, help_heading = ADVANCED)]
+    pub no_alloc_hot_path: bool,
+
+    /// Sample the depth of the transport's queue at this interval during
+    /// each test (e.g., "1ms", "100us").
+    ///
+    /// Reads the current message count of a POSIX message queue
+    /// (mq_getattr) or the occupancy of a shared memory ring, to tell a
+    /// slow transport from a queue that stayed full. Each test reports the
+    /// mean and largest depth and how often the queue was full, and the
+    /// samples are written to the streaming output. Applies to single-client
+    /// tests over PMQ and SHM; other transports have no queue to sample.
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_duration_micros, help_heading = ADVANCED)]
+    pub queue_depth_interval: Option<Duration>,
+
+    /// Maximum number of messages the server drains per wakeup.
+    ///
+    /// After each blocking receive the server keeps reading messages that
+    /// are already queued, up to this many, before processing them and
+    /// sending any replies. This models receiver-side batching as used by
+    /// high-throughput consumers; compare latency and throughput against
+    /// the default of 1. Batching is supported by SHM (ring buffer), TCP,
+    /// UDS and, in blocking mode, PMQ; other transports receive one at a time.
+    /// Applies to the server process spawned by the benchmark.
+    #[arg(long, value_name = "K", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize), help_heading = ADVANCED)]
+    pub server_batch: usize,
+
+    /// Limit the rate at which the server consumes messages (messages/sec).
+    ///
+    /// The server waits between messages so it processes at most this many
+    /// per second, and messages queue up in the transport. Clients then
+    /// meet backpressure as they would with a slow consumer: round trips
+    /// wait for the server's pace, and one-way senders block once the
+    /// transport's buffer is full. After falling behind, the server catches
+    /// up by at most one message, so idle periods are not followed by a
+    /// burst. Applies to the server process spawned by the benchmark.
+    #[arg(long, value_name = "MSGS_PER_SEC", value_parser = clap::value_parser!(u64).range(1..), help_heading = ADVANCED)]
+    pub server_rate: Option<u64>,
+
+    /// Simulate this much processing on the client for each message.
+    ///
+    /// The client busy-waits this long per message, as application code
+    /// deserializing and acting on it would: before sending each one-way
+    /// message, and after each round trip's reply arrives. The time is kept
+    /// out of the transport latency and reported as client hook processing,
+    /// with transport latency plus that time as end-to-end latency. Applies
+    /// to single-client tests.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_micros, help_heading = ADVANCED)]
+    pub client_work: Option<Duration>,
+
+    /// Simulate this much processing on the server for each message.
+    ///
+    /// The server busy-waits this long for each message it receives,
+    /// before replying to requests, and reports the time it took. Round-trip
+    /// latency includes it. Applies to the server process spawned by the
+    /// benchmark.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_micros, help_heading = ADVANCED)]
+    pub server_work: Option<Duration>,
+
+    /// Spin this many iterations of a compute kernel on the server for each
+    /// message.
+    ///
+    /// Like --server-work, but a fixed amount of CPU work rather than a
+    /// fixed time, so the cost per message grows when the server's core is
+    /// slower or shared. The server runs it for each message it receives,
+    /// before replying to requests, and reports the time it took. Use it to
+    /// see how a consumer's CPU cost turns into backpressure on each
+    /// mechanism. Applies to the server process spawned by the benchmark.
+    #[arg(long, value_name = "ITERATIONS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "server_work", help_heading = ADVANCED)]
+    pub server_spin: Option<u64>,
+
+    /// What the server sends back for each request.
+    ///
+    /// "ack" (default) replies with an empty message carrying only the
+    /// request's ID, so round trips move the payload in one direction only.
+    /// "echo" replies with the request's payload, as a request/response
+    /// service returning data of the same size would. "none" sends no
+    /// replies at all and runs one-way tests only. Applies to the server
+    /// process spawned by the benchmark.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = ResponseMode::Ack, help_heading = ADVANCED)]
+    pub response_mode: ResponseMode,
+
+    /// Check Unix Domain Socket peer credentials.
+    ///
+    /// `connection` fetches and validates the peer's credentials
+    /// (SO_PEERCRED) once when each connection is established. `message`
+    /// additionally attaches SCM_CREDENTIALS to every message and validates
+    /// them on receipt, which requires Linux and implies --blocking. Use this
+    /// to measure the overhead of credential-checked IPC. Only affects -m uds.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = UdsCredentialMode::Off, help_heading = ADVANCED)]
+    pub uds_credentials: UdsCredentialMode,
+
+    /// Enable TCP keepalive with this idle time before the first probe.
+    ///
+    /// Sets SO_KEEPALIVE on both ends of TCP connections and starts probing
+    /// after the connection has been idle this long. Pair with --idle-gap
+    /// longer than this value to measure latency on connections that have
+    /// been kept alive by probes. Only affects -m tcp.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_micros, help_heading = ADVANCED)]
+    pub tcp_keepalive: Option<Duration>,
+
+    /// Restrict TCP sockets to this network interface (SO_BINDTODEVICE).
+    ///
+    /// Applied to both the server's listener and the client's connection,
+    /// so traffic uses this NIC or VLAN regardless of the routing table.
+    /// Requires Linux, and CAP_NET_RAW on kernels before 5.7. Only affects
+    /// -m tcp.
+    #[arg(long, value_name = "IFACE", help_heading = ADVANCED)]
+    pub bind_device: Option<String>,
+
+    /// Local IP address the TCP client connects from.
+    ///
+    /// Selects the source address, and so the outgoing NIC, on multi-homed
+    /// hosts. Must be the same address family as --host. The server picks
+    /// its address with --host. Only affects -m tcp.
+    #[arg(long, value_name = "IP", help_heading = ADVANCED)]
+    pub source_addr: Option<std::net::IpAddr>,
+
+    /// Launch the server on a remote machine over SSH (`[user@]host`).
+    ///
+    /// The server binary is started with `ssh -T` and signals readiness over
+    /// the SSH connection, then the benchmark runs across the real network.
+    /// The remote server listens on all interfaces and the client connects to
+    /// the SSH host, or to --host if it is set. Requires key-based SSH
+    /// authentication and -m tcp. Only round-trip latency is measured, since
+    /// one-way latency would compare two unsynchronized clocks.
+    #[arg(long, value_name = "[USER@]HOST", help_heading = ADVANCED)]
+    pub server_remote: Option<String>,
+
+    /// Path of the ipc-benchmark binary on the --server-remote host.
+    ///
+    /// Defaults to `ipc-benchmark` on the remote PATH. It must be the same
+    /// version as the local binary.
+    #[arg(long, value_name = "PATH", requires = "server_remote", help_heading = ADVANCED)]
+    pub remote_binary: Option<String>,
+
+    /// Retry a transport setup that failed for a transient reason this many times
+    ///
+    /// Covers failures that a moment's wait usually fixes: an address still
+    /// in use, a socket path or shared memory segment not created yet, a
+    /// refused connection, EAGAIN. Other setup failures are not retried. The
+    /// retries made by the client and its servers are recorded in the
+    /// results as `setup_retries`.
+    #[arg(long, value_name = "N", default_value_t = 3, help_heading = ADVANCED)]
+    pub setup_retries: u32,
+
+    /// Wait before the first setup retry; each later one waits twice as long
+    #[arg(long, value_name = "DURATION", default_value = "50ms", value_parser = parse_duration_micros, help_heading = ADVANCED)]
+    pub setup_backoff: Duration,
+
+    /// Fail a test whose server sends no heartbeat for this long (0 disables)
+    ///
+    /// Spawned servers send a heartbeat to the client every 250ms. One that
+    /// stops (stopped, hung, or stuck) is killed and its test fails with the
+    /// number of messages the server had received, instead of the client
+    /// waiting on it until the test's duration runs out.
+    #[arg(long, value_name = "DURATION", default_value = "5s", value_parser = parse_duration, help_heading = ADVANCED)]
+    pub heartbeat_timeout: Option<Duration>,
+
+    /// Also measure time-to-first-byte at the receiver in one-way tests.
+    ///
+    /// For stream transports (TCP, UDS) the server records when the first
+    /// bytes of each message arrive as well as when the whole message has
+    /// been read, and both are reported as separate latency distributions.
+    /// The gap grows with message size. SHM and PMQ deliver whole messages,
+    /// so their first-byte latency equals the full-message latency.
+    #[arg(long, help_heading = ADVANCED)]
+    pub first_byte_latency: bool,
+
+    /// Split each message into segments of at most this many payload bytes.
+    ///
+    /// By default messages are segmented automatically, and only when the
+    /// message size exceeds what the mechanism can carry in one message
+    /// (e.g. PMQ msgsize_max, the SHM ring size, or the 8 KB --shm-direct
+    /// slot). Each segment adds a 20-byte header; the receiver reassembles
+    /// the message before it is timed. Set this to force a segment size.
+    #[arg(long, value_name = "BYTES", value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize), help_heading = ADVANCED)]
+    pub segment_size: Option<usize>,
+
+    /// Write each TCP or UDS frame in pieces of at most this many bytes.
+    ///
+    /// A test mode for the stream transports: both ends write every frame
+    /// (its 4-byte length, then the message) in small pieces, one write
+    /// call each, so the receiver sees frames arrive in many partial reads.
+    /// With --verify-frames, frames that fail their checksum are dropped
+    /// and counted as torn. Adds latency; use it to check framing, not to
+    /// measure.
+    #[arg(long, value_name = "BYTES", value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize), help_heading = ADVANCED)]
+    pub fragment_writes: Option<usize>,
+
+    /// Checksum every TCP or UDS frame with a CRC-32.
+    ///
+    /// Off by default: frames are a 4-byte length and the message. With
+    /// this flag the client proposes checked frames in its handshake, and
+    /// once the server agrees both ends add a CRC-32 to each frame and
+    /// drop frames that fail it, counting them as torn. The checksum is
+    /// computed on the measured path, so it adds to the reported latency.
+    #[arg(long, help_heading = ADVANCED)]
+    pub verify_frames: bool,
+
     /// Use synchronous/blocking I/O instead of async I/O.
     ///
     /// When this flag is set, the benchmark will use pure standard library
@@ -307,6 +985,7 @@ pub struct Args {
     ///
     /// Default: false (uses async mode with Tokio runtime)
     /// Only one mode runs at a time - this flag switches from async to blocking
+    /// `--sync-io` is accepted as an alias.
     ///
     /// # Examples
     ///
@@ -317,9 +996,19 @@ pub struct Args {
     /// # Run in async mode (default)
     /// ipc-benchmark -m uds
     /// ```
-    #[arg(long, default_value_t = false, help_heading = ADVANCED)]
+    #[arg(long, visible_alias = "sync-io", default_value_t = false, help_heading = ADVANCED)]
     pub blocking: bool,
 
+    /// Busy-poll for round-trip replies instead of blocking in the kernel.
+    ///
+    /// The client polls non-blocking receives in a tight loop, `busy` (the
+    /// default when no mode is given) or with a `pause` spin-loop hint
+    /// between polls. This trades a fully used CPU core for the wakeup
+    /// latency of a blocking receive; the client's CPU use is reported next
+    /// to the latency. Supported by UDS, TCP and PMQ; enables --blocking.
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, default_missing_value = "busy", help_heading = ADVANCED)]
+    pub spin_wait: Option<SpinWait>,
+
     /// Use high-performance direct memory shared memory (auto-enables --blocking).
     ///
     /// This flag selects the direct memory implementation instead of the default
@@ -367,6 +1056,88 @@ pub struct Args {
     #[arg(long, default_value_t = false, help_heading = ADVANCED)]
     pub shm_direct: bool,
 
+    /// Align every shared memory ring record to 64 or 128 bytes.
+    ///
+    /// By default ring records are packed back to back, so consecutive small
+    /// messages share cache lines and the reader and writer can contend on
+    /// them (false sharing). Aligning records gives each one its own cache
+    /// line(s). Applies to the ring buffer implementation, not --shm-direct.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = clap::builder::PossibleValuesParser::new(["64", "128"])
+            .map(|s| s.parse::<usize>().unwrap()),
+        help_heading = ADVANCED
+    )]
+    pub shm_align: Option<usize>,
+
+    /// Place the shared memory ring's read and write indices on separate cache lines.
+    ///
+    /// With "off" (default) both indices share one cache line, so every
+    /// send and receive invalidates the line in the other core's cache.
+    /// Compare "on" and "off" to measure the cost of that false sharing.
+    /// Applies to the ring buffer implementation, not --shm-direct.
+    #[arg(
+        long,
+        value_name = "on|off",
+        default_value = "off",
+        action = clap::ArgAction::Set,
+        value_parser = clap::builder::BoolishValueParser::new(),
+        help_heading = ADVANCED
+    )]
+    pub shm_pad_indices: bool,
+
+    /// Bound the shared memory ring by message count instead of only by bytes.
+    ///
+    /// At most N messages can be unread in the ring; the sender blocks once
+    /// N are queued, like a bounded queue in a real system. Without
+    /// --buffer-size the ring is also sized in bytes to hold exactly N
+    /// messages of the configured size; with --buffer-size both limits
+    /// apply and whichever is reached first causes backpressure.
+    /// Applies to the ring buffer implementation, not --shm-direct.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize), help_heading = ADVANCED)]
+    pub shm_capacity_messages: Option<usize>,
+
+    /// How blocked shared memory ring senders and receivers wait.
+    ///
+    /// "condvar" (default) uses a process-shared pthread mutex and
+    /// condition variables. "semaphore" uses sem_post/sem_wait on a pair of
+    /// POSIX named semaphores, as classic producer/consumer code does, so
+    /// both can be compared on the same ring. Linux only; applies to the
+    /// ring buffer implementation, not --shm-direct, and enables --blocking.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = ShmWait::Condvar, help_heading = ADVANCED)]
+    pub shm_wait: ShmWait,
+
+    /// Back the shared memory ring with huge pages from this hugetlbfs mount.
+    ///
+    /// The ring is created as a file in DIR instead of a POSIX shared
+    /// memory object, rounded up to the mount's page size, so hosts that
+    /// reserve huge pages (e.g. a pagesize=1G mount for DPDK) can run with
+    /// their actual memory configuration. The file is removed once both
+    /// sides have mapped it. Linux only; applies to the ring buffer
+    /// implementation, not --shm-direct, and enables --blocking.
+    #[arg(long, value_name = "DIR", help_heading = ADVANCED)]
+    pub shm_hugetlbfs: Option<PathBuf>,
+
+    /// Carry shared memory ring messages without serde.
+    ///
+    /// Each record is a fixed 40-byte header (id, timestamp, type and the
+    /// other message fields) followed by the payload, written straight from
+    /// the message into the ring and taken back out without deserializing.
+    /// The results add what bincode costs per message next to the fixed
+    /// layout. Applies to the ring buffer implementation, not --shm-direct,
+    /// and enables --blocking.
+    #[arg(long, help_heading = ADVANCED)]
+    pub zero_copy: bool,
+
+    /// HTTP version spoken by -m http.
+    ///
+    /// "h1" (default) sends HTTP/1.1 requests over a persistent connection.
+    /// "h2" uses HTTP/2 in cleartext with prior knowledge, one stream per
+    /// request.
+    #[arg(long, value_enum, value_name = "VERSION", default_value_t = HttpVersion::H1, help_heading = ADVANCED)]
+    pub http_version: HttpVersion,
+
     /// Run in standalone server mode.
     ///
     /// Starts the process as a server that listens for incoming client
@@ -420,7 +1191,8 @@ pub struct Args {
     ///
     /// Specifies the filesystem path for the UDS socket. Both server
     /// and client must use the same path. If not specified, a default
-    /// path in the system temp directory is used.
+    /// path in the system temp directory is used. On Windows it names the
+    /// pipe for `-m pipe` instead, `\\.\pipe\ipc_benchmark` by default.
     #[arg(long, help_heading = STANDALONE)]
     pub socket_path: Option<String>,
 
@@ -456,159 +1228,678 @@ pub struct Args {
     /// each message.
     #[arg(long, hide = true)]
     pub internal_latency_file: Option<String>,
-}
 
-// Affinity parsing tests live in the tests module below
+    /// (Internal) File path for server to write first-byte latencies.
+    ///
+    /// Same format as --internal-latency-file, but each latency is measured
+    /// to the arrival of the message's first bytes rather than its last.
+    #[arg(long, hide = true)]
+    pub internal_first_byte_file: Option<String>,
 
-/// Available IPC mechanisms for benchmarking
-///
-/// This enumeration defines all supported Inter-Process Communication mechanisms.
-/// Each variant corresponds to a specific implementation with its own performance
-/// characteristics and use cases.
-///
-/// ## Performance Characteristics
-///
-/// - **UnixDomainSocket**: Excellent for local communication, supports multiple clients
-/// - **SharedMemory**: Highest throughput, lowest latency, but limited to single process pairs
-/// - **TcpSocket**: Network-capable, good performance, supports multiple clients
-/// - **PosixMessageQueue**: System-integrated, message boundaries preserved, limited throughput
-#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
-pub enum IpcMechanism {
-    /// Unix Domain Sockets
-    ///
-    /// High-performance local sockets that provide reliable, ordered communication
-    /// between processes on the same machine. Supports full-duplex communication
-    /// and multiple concurrent clients. Ideal for local service architectures.
-    #[cfg(unix)]
-    #[value(name = "uds")]
-    UnixDomainSocket,
+    /// (Internal) File path for server to write how many messages it
+    /// dropped for arriving past their deadline.
+    #[arg(long, hide = true)]
+    pub internal_expired_file: Option<String>,
 
-    /// Shared Memory
-    ///
-    /// Direct memory sharing between processes using a custom ring buffer implementation.
-    /// Provides the highest throughput and lowest latency but requires careful
-    /// synchronization. Limited to single client-server pairs in current implementation.
-    #[value(name = "shm")]
-    SharedMemory,
+    /// (Internal) File path for server to write the priority of each
+    /// message whose latency it wrote to --internal-latency-file, one per
+    /// line in the same order.
+    #[arg(long, hide = true)]
+    pub internal_priority_file: Option<String>,
 
-    /// TCP Sockets
-    ///
-    /// Standard network sockets that can work locally or across networks.
-    /// Provides good performance with broad compatibility and multi-client support.
-    /// Socket options are tuned for low latency (TCP_NODELAY, buffer sizes).
-    #[value(name = "tcp")]
-    TcpSocket,
+    /// (Internal) File path for server to write the producer of each
+    /// message whose latency it wrote to --internal-latency-file, one per
+    /// line in the same order.
+    #[arg(long, hide = true)]
+    pub internal_producer_file: Option<String>,
 
-    /// POSIX Message Queues
-    ///
-    /// System-level message queues that preserve message boundaries and support
-    /// priority-based delivery. Integrated with OS scheduling but limited by
-    /// system-imposed queue depth restrictions (typically 10 messages).
-    #[cfg(target_os = "linux")]
-    #[value(name = "pmq")]
-    PosixMessageQueue,
+    /// (Internal) Run the process as this extra producer of a --producers
+    /// test, sending into the queue of a server that is already running.
+    #[arg(long, hide = true)]
+    pub internal_run_as_producer: Option<u8>,
 
-    /// All available mechanisms
-    ///
-    /// Convenience option that expands to test all supported IPC mechanisms
-    /// sequentially. Useful for comprehensive performance comparisons across
-    /// all available transport types.
-    #[value(name = "all")]
-    All,
+    /// (Internal) Descriptor of the server's end of the `-m socketpair`
+    /// connection, inherited from the client process that spawned it.
+    #[arg(long, hide = true)]
+    pub internal_socketpair_fd: Option<i32>,
+
+    /// Optional subcommand; when absent the benchmark runs as usual.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// IDs of the options given on the command line, filled in by
+    /// [`Args::parse_explicit`], so presets leave them alone.
+    #[arg(skip)]
+    pub explicit_args: Vec<String>,
+
+    /// Each option given on the command line, by field name, with the
+    /// tokens that give it again; filled in by [`Args::parse_explicit`].
+    #[arg(skip)]
+    pub explicit_tokens: Vec<(String, Vec<String>)>,
 }
 
-impl std::fmt::Display for IpcMechanism {
-    /// Provide human-readable names for IPC mechanisms
-    ///
-    /// Used in output formatting and logging to present user-friendly
-    /// names rather than the internal enum variant names.
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            #[cfg(unix)]
-            IpcMechanism::UnixDomainSocket => write!(f, "Unix Domain Socket"),
-            IpcMechanism::SharedMemory => write!(f, "Shared Memory"),
-            IpcMechanism::TcpSocket => write!(f, "TCP Socket"),
-            #[cfg(target_os = "linux")]
-            IpcMechanism::PosixMessageQueue => write!(f, "POSIX Message Queue"),
-            IpcMechanism::All => write!(f, "All Mechanisms"),
-            #[allow(unreachable_patterns)]
-            _ => unreachable!(),
+impl Args {
+    /// How each test treats its first message, counting the hidden
+    /// `--include-first-message` as `--first-message include`.
+    pub fn first_message_mode(&self) -> FirstMessage {
+        if self.include_first_message {
+            FirstMessage::Include
+        } else {
+            self.first_message
         }
     }
+
+    /// Parse the process arguments, noting which options were given on
+    /// the command line rather than left at their defaults.
+    pub fn parse_explicit() -> Self {
+        Self::try_parse_explicit_from(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Like [`Args::parse_explicit`], from `args`.
+    pub fn try_parse_explicit_from<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        use clap::{parser::ValueSource, CommandFactory, FromArgMatches};
+
+        let mut command = Self::command();
+        let mut args: Vec<std::ffi::OsString> = args.into_iter().map(Into::into).collect();
+        let mut matches = command.clone().try_get_matches_from(&args)?;
+        if let Some(path) = matches.get_one::<PathBuf>("config") {
+            let given: Vec<String> = matches
+                .ids()
+                .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+                .map(|id| id.to_string())
+                .collect();
+            let tokens = config_tokens(&command, path, &given)
+                .map_err(|e| command.error(clap::error::ErrorKind::InvalidValue, e))?;
+            let at = args.len().min(1);
+            args.splice(at..at, tokens.into_iter().map(Into::into));
+            matches = command.clone().try_get_matches_from(&args)?;
+        }
+        let mut parsed = Self::from_arg_matches(&matches)?;
+        parsed.explicit_args = matches
+            .ids()
+            .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+            .map(|id| id.to_string())
+            .collect();
+        parsed.explicit_tokens = command
+            .get_arguments()
+            .filter(|arg| parsed.is_explicit(arg.get_id().as_str()))
+            .map(|arg| (arg.get_id().to_string(), option_tokens(arg, &matches)))
+            .collect();
+        Ok(parsed)
+    }
+
+    /// Whether the option with field name `id` was given on the command
+    /// line.
+    pub fn is_explicit(&self, id: &str) -> bool {
+        self.explicit_args.iter().any(|arg| arg == id)
+    }
 }
 
-impl IpcMechanism {
-    /// Expand the "All" variant to all available mechanisms
-    ///
-    /// This function handles the special "all" mechanism by expanding it to
-    /// the complete list of concrete IPC mechanisms. If "all" is present
-    /// anywhere in the input list, it returns all mechanisms; otherwise,
-    /// it returns the input list unchanged.
-    ///
-    /// ## Parameters
-    /// - `mechanisms`: Vector of mechanisms which may include the "All" variant
-    ///
-    /// ## Returns
-    /// Vector of concrete mechanisms with "All" expanded if present
-    ///
-    /// ## Example (Linux)
-    /// ```rust
-    /// # use ipc_benchmark::cli::IpcMechanism;
-    /// // On Linux, all mechanisms are available
-    /// #[cfg(target_os = "linux")]
-    /// {
-    ///     let input = vec![IpcMechanism::All];
-    ///     let expanded = IpcMechanism::expand_all(input);
-    ///     assert_eq!(expanded.len(), 4);
-    /// }
+/// Tokens that give `arg` the values it was given on the command line:
+/// the option name once per occurrence, followed by that occurrence's
+/// values.
+fn option_tokens(arg: &clap::Arg, matches: &clap::ArgMatches) -> Vec<String> {
+    use clap::ArgAction;
+
+    let id = arg.get_id().as_str();
+    let name = match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => format!("--{}", long),
+        (None, Some(short)) => format!("-{}", short),
+        (None, None) => String::new(),
+    };
+    match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse => vec![name],
+        ArgAction::Count => vec![name; usize::from(matches.get_count(id))],
+        _ => {
+            let mut tokens = Vec::new();
+            for occurrence in matches.get_raw_occurrences(id).into_iter().flatten() {
+                let values: Vec<String> = occurrence
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .collect();
+                if !name.is_empty() {
+                    tokens.push(name.clone());
+                }
+                match arg.get_value_delimiter() {
+                    Some(delimiter) if !values.is_empty() => {
+                        tokens.push(values.join(&delimiter.to_string()))
+                    }
+                    _ => tokens.extend(values),
+                }
+            }
+            tokens
+        }
+    }
+}
+
+/// Tokens that give the options of the `--config` file at `path`, leaving
+/// out the IDs in `given`.
+fn config_tokens(
+    command: &clap::Command,
+    path: &std::path::Path,
+    given: &[String],
+) -> std::result::Result<Vec<String>, String> {
+    use clap::ArgAction;
+    use serde_json::Value;
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+    let options: serde_json::Map<String, Value> = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+
+    let mut tokens = Vec::new();
+    for (long, value) in options {
+        let arg = command
+            .get_arguments()
+            .find(|arg| config_key(arg) == Some(long.clone()))
+            .ok_or_else(|| format!("Unknown option {:?} in {}", long, path.display()))?;
+        if given.iter().any(|id| id == arg.get_id().as_str()) {
+            continue;
+        }
+        let name = match (arg.get_long(), arg.get_short()) {
+            (None, Some(short)) => format!("-{}", short),
+            _ => format!("--{}", long),
+        };
+        let scalar = |value: &Value| match value {
+            Value::String(value) => Ok(value.clone()),
+            Value::Number(value) => Ok(value.to_string()),
+            _ => Err(format!(
+                "Invalid value for {:?} in {}",
+                long,
+                path.display()
+            )),
+        };
+        match (value, arg.get_action()) {
+            (Value::Bool(set), ArgAction::SetTrue | ArgAction::SetFalse) => {
+                if set {
+                    tokens.push(name);
+                }
+            }
+            (Value::Number(count), ArgAction::Count) => {
+                let count = count.as_u64().unwrap_or(0) as usize;
+                tokens.extend(std::iter::repeat(name).take(count));
+            }
+            (Value::Array(values), ArgAction::Append) => {
+                for value in &values {
+                    tokens.extend([name.clone(), scalar(value)?]);
+                }
+            }
+            (Value::Array(values), _) => {
+                tokens.push(name);
+                for value in &values {
+                    tokens.push(scalar(value)?);
+                }
+            }
+            (value, _) => tokens.extend([name, scalar(&value)?]),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Key of `arg` in a `--config` file: its long name, or its field name
+/// when it only has a short one (`mechanisms` for -m); None for positional
+/// arguments.
+fn config_key(arg: &clap::Arg) -> Option<String> {
+    match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => Some(long.to_string()),
+        (None, Some(_)) => Some(arg.get_id().to_string()),
+        (None, None) => None,
+    }
+}
+
+/// The `--config` file equivalent of `options`, given as field name and
+/// tokens like [`Args::explicit_tokens`].
+pub fn config_snippet(
+    options: &[(String, Vec<String>)],
+) -> serde_json::Map<String, serde_json::Value> {
+    use clap::{ArgAction, CommandFactory};
+    use serde_json::Value;
+
+    let command = Args::command();
+    let mut snippet = serde_json::Map::new();
+    for (id, tokens) in options {
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == id) else {
+            continue;
+        };
+        let Some(key) = config_key(arg) else {
+            continue;
+        };
+        let names = [
+            arg.get_long()
+                .map_or_else(String::new, |long| format!("--{}", long)),
+            arg.get_short()
+                .map_or_else(String::new, |short| format!("-{}", short)),
+        ];
+        let value = match arg.get_action() {
+            ArgAction::SetTrue | ArgAction::SetFalse => Value::Bool(true),
+            ArgAction::Count => Value::from(tokens.len()),
+            _ => {
+                let mut values: Vec<Value> = tokens
+                    .iter()
+                    .filter(|token| !names.contains(token))
+                    .map(|token| Value::String(token.clone()))
+                    .collect();
+                if values.len() == 1 {
+                    values.remove(0)
+                } else {
+                    Value::Array(values)
+                }
+            }
+        };
+        snippet.insert(key, value);
+    }
+    snippet
+}
+
+/// Subcommands that analyze previously captured data or coordinate runs
+/// instead of running a benchmark directly.
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Recompute statistics from a per-message streaming output file
+    ///
+    /// Reads a file written by --streaming-output-csv or
+    /// --streaming-output-json and reports percentiles, a time-windowed
+    /// series and the slowest outliers for each mechanism, message size and
+    /// latency type, without re-running the benchmark.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ipc-benchmark analyze stream.csv
+    /// ipc-benchmark analyze stream.json --window 100ms --outlier-percentile 99.99
+    /// ```
+    Analyze(AnalyzeArgs),
+
+    /// Wait for a conductor to run benchmarks on this host
+    ///
+    /// Listens on a TCP control port. A conductor connects, proves it
+    /// holds the shared token, measures the offset of this host's clock,
+    /// sends the benchmark arguments and a start time, and collects the
+    /// results once the run finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ipc-benchmark agent --listen 0.0.0.0:7171 --token-file ~/.ipc-benchmark-token
     /// ```
+    Agent(AgentArgs),
+
+    /// Start benchmarks on several agents at the same instant
     ///
-    /// ## Example (macOS/BSD)
-    /// ```rust
-    /// # use ipc_benchmark::cli::IpcMechanism;
-    /// // On non-Linux Unix, PMQ is not available
-    /// #[cfg(all(unix, not(target_os = "linux")))]
-    /// {
-    ///     let input = vec![IpcMechanism::All];
-    ///     let expanded = IpcMechanism::expand_all(input);
-    ///     assert_eq!(expanded.len(), 3);
-    /// }
+    /// Connects to each --agent, estimates its clock offset, has every
+    /// agent start its benchmark at the same moment and writes their
+    /// results to one file. Arguments after `--` are run by every agent
+    /// that does not name its own after `=`.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ipc-benchmark conduct --token-file ~/.ipc-benchmark-token --agent host-a:7171 --agent host-b:7171 -o run.json -- -m tcp -s 1024
+    /// ipc-benchmark conduct --token-file ~/.ipc-benchmark-token --agent "host-a:7171=--server -m tcp" --agent "host-b:7171=--client -m tcp --host host-a"
     /// ```
+    Conduct(ConductArgs),
+
+    /// Run benchmark jobs submitted over a local control API
     ///
-    /// ## Example (Windows)
-    /// ```rust
-    /// # use ipc_benchmark::cli::IpcMechanism;
-    /// // On Windows, only SHM and TCP are available
-    /// #[cfg(windows)]
-    /// {
-    ///     let input = vec![IpcMechanism::All];
-    ///     let expanded = IpcMechanism::expand_all(input);
-    ///     assert_eq!(expanded.len(), 2);
-    /// }
+    /// Listens on a Unix domain socket, reachable only by this user, for
+    /// JSON-RPC requests, one per line, to submit benchmark jobs, query
+    /// their progress and fetch their results. Jobs run one at a time in
+    /// submission order.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ipc-benchmark daemon --listen unix:/run/rusty-comms.sock
     /// ```
-    pub fn expand_all(mechanisms: Vec<IpcMechanism>) -> Vec<IpcMechanism> {
-        if mechanisms.contains(&IpcMechanism::All) {
-            // Start with a base list of mechanisms that work on all platforms,
-            // in a logical order.
-            let mut all = vec![
-                // SHM second (highest performance)
-                IpcMechanism::SharedMemory,
-                // TCP third (network-capable)
-                IpcMechanism::TcpSocket,
-            ];
-
-            // Conditionally add POSIX Message Queues on non-Windows platforms
-            // UDS first (most commonly used for local IPC)
+    Daemon(DaemonArgs),
+
+    /// Check that each mechanism works on this host
+    ///
+    /// Spawns a server for every mechanism and exchanges a few round trips,
+    /// matching each reply to its request, then reports pass or fail and
+    /// the latency seen. A quick smoke test of the host before launching a
+    /// long campaign; exits with an error when any mechanism failed.
+    /// Transport options given before `selftest` apply to every mechanism.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ipc-benchmark selftest
+    /// ipc-benchmark selftest -m shm pmq -i 100
+    /// ```
+    Selftest(SelftestArgs),
+
+    /// Print a shell completion script
+    ///
+    /// Writes the completion script for the given shell to stdout; install
+    /// it where the shell loads completions from.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ipc-benchmark completions bash > /etc/bash_completion.d/ipc-benchmark
+    /// ipc-benchmark completions zsh > "${fpath[1]}/_ipc-benchmark"
+    /// ipc-benchmark completions fish > ~/.config/fish/completions/ipc-benchmark.fish
+    /// ```
+    Completions(CompletionsArgs),
+}
+
+/// Arguments for the `analyze` subcommand.
+#[derive(clap::Args, Debug, Clone)]
+pub struct AnalyzeArgs {
+    /// Streaming output file (CSV or JSON) to analyze
+    #[arg(value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Width of each window in the time-windowed series (e.g., 500us, 100ms, 1s)
+    #[arg(long, default_value = "1s", value_parser = parse_duration_micros)]
+    pub window: Duration,
+
+    /// Percentiles to report for each series
+    #[arg(long, default_values_t = vec![50.0, 95.0, 99.0, 99.9])]
+    pub percentiles: Vec<f64>,
+
+    /// Samples above this percentile are listed as outliers
+    #[arg(long, default_value_t = 99.9)]
+    pub outlier_percentile: f64,
+
+    /// Maximum number of outliers listed per series
+    #[arg(long, default_value_t = 20)]
+    pub max_outliers: usize,
+
+    /// Print the analysis as JSON instead of a text summary
+    #[arg(long)]
+    pub json: bool,
+
+    /// Also write the analysis as JSON to this file
+    #[arg(long, value_name = "FILE")]
+    pub output_json: Option<PathBuf>,
+
+    /// Write a latency histogram per window to this CSV file, for heatmaps
+    #[arg(long, value_name = "FILE")]
+    pub heatmap: Option<PathBuf>,
+}
+
+/// Arguments for the `selftest` subcommand.
+#[derive(clap::Args, Debug, Clone)]
+pub struct SelftestArgs {
+    /// Mechanisms to test (space-separated, or all)
+    #[arg(short = 'm', value_enum, default_values_t = vec![IpcMechanism::All], num_args = 1..)]
+    pub mechanisms: Vec<IpcMechanism>,
+
+    /// Messages exchanged per mechanism
+    #[arg(short = 'i', long, default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize))]
+    pub msg_count: usize,
+
+    /// Message size in bytes
+    #[arg(short = 's', long, default_value_t = 64, value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize))]
+    pub message_size: usize,
+}
+
+/// Arguments for the `completions` subcommand.
+#[derive(clap::Args, Debug, Clone)]
+pub struct CompletionsArgs {
+    /// Shell to complete for
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
+
+/// Arguments for the `agent` subcommand.
+#[derive(clap::Args, Debug, Clone)]
+pub struct AgentArgs {
+    /// Address of the control port; give an external address to serve
+    /// conductors on other hosts
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7171")]
+    pub listen: String,
+
+    /// File holding the token conductors must prove they share; readable
+    /// only by its owner
+    #[arg(long, value_name = "FILE", required = true)]
+    pub token_file: PathBuf,
+
+    /// Exit after serving one conductor
+    #[arg(long)]
+    pub once: bool,
+}
+
+/// Arguments for the `daemon` subcommand.
+#[derive(clap::Args, Debug, Clone)]
+pub struct DaemonArgs {
+    /// Control socket, `unix:PATH`; created with mode 0600
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "unix:/run/rusty-comms.sock"
+    )]
+    pub listen: String,
+}
+
+/// Arguments for the `conduct` subcommand.
+#[derive(clap::Args, Debug, Clone)]
+pub struct ConductArgs {
+    /// Control address of an agent, optionally followed by `=` and the
+    /// whitespace-separated benchmark arguments of that agent
+    #[arg(long = "agent", value_name = "HOST:PORT[=ARGS]", required = true)]
+    pub agents: Vec<String>,
+
+    /// Time between the last agent being prepared and the common start
+    #[arg(long, default_value = "2s", value_parser = parse_duration)]
+    pub start_delay: Duration,
+
+    /// File holding the token the agents were started with
+    #[arg(long, value_name = "FILE", required = true)]
+    pub token_file: PathBuf,
+
+    /// Clock probes per agent; the fastest round trip sets the offset
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(usize))]
+    pub probes: usize,
+
+    /// Write the results of every agent to this JSON file
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output_file: Option<PathBuf>,
+
+    /// Benchmark arguments for agents without their own
+    #[arg(last = true, value_name = "ARGS")]
+    pub args: Vec<String>,
+}
+
+// Affinity parsing tests live in the tests module below
+
+/// `-m` values, by [`IpcMechanism::name`], with their `--help` lines.
+impl ValueEnum for IpcMechanism {
+    fn value_variants<'a>() -> &'a [Self] {
+        IpcMechanism::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let help = match self {
             #[cfg(unix)]
-            all.insert(0, IpcMechanism::UnixDomainSocket);
-            // PMQ last (most constrained), and only on Linux
+            IpcMechanism::UnixDomainSocket => "Unix Domain Sockets",
+            IpcMechanism::SharedMemory => "Shared Memory",
+            IpcMechanism::TcpSocket => "TCP Sockets",
             #[cfg(target_os = "linux")]
-            all.push(IpcMechanism::PosixMessageQueue);
+            IpcMechanism::PosixMessageQueue => "POSIX Message Queues",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Futex => "Futex wake/wait",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::RtSignal => "Real-time signals",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Inotify => "File + inotify",
+            IpcMechanism::Http => "HTTP loopback requests",
+            IpcMechanism::Grpc => "gRPC loopback calls",
+            #[cfg(unix)]
+            IpcMechanism::Socketpair => "Pre-connected socketpair",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => "UDP multicast fan-out",
+            #[cfg(windows)]
+            IpcMechanism::NamedPipe => "Windows named pipes",
+            IpcMechanism::All => "All available mechanisms",
+        };
+        Some(PossibleValue::new(self.name()).help(help))
+    }
+}
 
-            all
-        } else {
-            mechanisms
-        }
+/// `--uds-credentials` values with their `--help` lines.
+impl ValueEnum for UdsCredentialMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            UdsCredentialMode::Off,
+            UdsCredentialMode::Connection,
+            UdsCredentialMode::Message,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            UdsCredentialMode::Off => ("off", "No credential checks"),
+            UdsCredentialMode::Connection => (
+                "connection",
+                "Fetch and validate the peer's credentials once per connection",
+            ),
+            UdsCredentialMode::Message => (
+                "message",
+                "Validate per connection and attach SCM_CREDENTIALS to every message",
+            ),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--machine-output` formats with their `--help` lines.
+impl ValueEnum for MachineOutput {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[MachineOutput::Json, MachineOutput::Jsonl]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            MachineOutput::Json => (
+                "json",
+                "One JSON document with all results, printed when the run finishes",
+            ),
+            MachineOutput::Jsonl => (
+                "jsonl",
+                "One JSON line per mechanism, printed as each mechanism completes",
+            ),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--spin-wait` modes with their `--help` lines.
+impl ValueEnum for SpinWait {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[SpinWait::Busy, SpinWait::Pause]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            SpinWait::Busy => ("busy", "Poll again immediately"),
+            SpinWait::Pause => (
+                "pause",
+                "Execute a spin-loop hint (x86 `pause`, ARM `yield`) between polls, \
+                 which saves power and yields pipeline resources to an SMT sibling",
+            ),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--shm-wait` modes with their `--help` lines.
+impl ValueEnum for ShmWait {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ShmWait::Condvar, ShmWait::Semaphore]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            ShmWait::Condvar => (
+                "condvar",
+                "Process-shared pthread mutex and condition variables",
+            ),
+            ShmWait::Semaphore => ("semaphore", "POSIX named semaphores posted per record"),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--http-version` values with their `--help` lines.
+impl ValueEnum for HttpVersion {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[HttpVersion::H1, HttpVersion::H2]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            HttpVersion::H1 => ("h1", "HTTP/1.1 with keep-alive"),
+            HttpVersion::H2 => ("h2", "HTTP/2 over cleartext (h2c) with prior knowledge"),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--response-mode` values with their `--help` lines.
+impl ValueEnum for ResponseMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ResponseMode::Echo, ResponseMode::Ack, ResponseMode::None]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            ResponseMode::Echo => ("echo", "The request's payload"),
+            ResponseMode::Ack => ("ack", "An empty message carrying the request's ID"),
+            ResponseMode::None => ("none", "No reply; one-way tests only"),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--profile` goals with their `--help` lines.
+impl ValueEnum for Profile {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Profile::Latency, Profile::Throughput, Profile::Balanced]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            Profile::Latency => (
+                "latency",
+                "Lowest per-message latency: long warmup, no batching, busy-polled replies",
+            ),
+            Profile::Throughput => (
+                "throughput",
+                "Most messages per second: large buffers and deep receiver batches",
+            ),
+            Profile::Balanced => (
+                "balanced",
+                "Moderate batching and warmup, for runs that report both",
+            ),
+        };
+        Some(PossibleValue::new(name).help(help))
+    }
+}
+
+/// `--runtime` schedulers with their `--help` lines.
+impl ValueEnum for RuntimeFlavor {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[RuntimeFlavor::CurrentThread, RuntimeFlavor::MultiThread]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let (name, help) = match self {
+            RuntimeFlavor::CurrentThread => (
+                "current-thread",
+                "Everything runs on the thread that drives the benchmark",
+            ),
+            RuntimeFlavor::MultiThread => (
+                "multi-thread",
+                "Work-stealing scheduler with a pool of worker threads",
+            ),
+        };
+        Some(PossibleValue::new(name).help(help))
     }
 }
 
@@ -716,7 +2007,7 @@ impl From<&Args> for BenchmarkConfiguration {
             port: args.port,
             send_delay: args.send_delay,
             pmq_priority: args.pmq_priority,
-            include_first_message: args.include_first_message,
+            include_first_message: args.first_message_mode() == FirstMessage::Include,
         }
     }
 }
@@ -902,6 +2193,18 @@ mod tests {
             IpcMechanism::PosixMessageQueue.to_string(),
             "POSIX Message Queue"
         );
+        #[cfg(target_os = "linux")]
+        assert_eq!(IpcMechanism::Futex.to_string(), "Futex");
+        #[cfg(target_os = "linux")]
+        assert_eq!(IpcMechanism::RtSignal.to_string(), "RT Signal");
+        #[cfg(target_os = "linux")]
+        assert_eq!(IpcMechanism::Inotify.to_string(), "File + inotify");
+        assert_eq!(IpcMechanism::Http.to_string(), "HTTP");
+        assert_eq!(IpcMechanism::Grpc.to_string(), "gRPC");
+        #[cfg(unix)]
+        assert_eq!(IpcMechanism::Socketpair.to_string(), "Socketpair");
+        #[cfg(target_os = "linux")]
+        assert_eq!(IpcMechanism::UdpMulticast.to_string(), "UDP Multicast");
         assert_eq!(IpcMechanism::All.to_string(), "All Mechanisms");
     }
 
@@ -935,6 +2238,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_selftest_args() {
+        let args = Args::parse_from(["ipc-benchmark", "selftest"]);
+        let Some(Command::Selftest(selftest)) = args.command else {
+            panic!("expected the selftest subcommand");
+        };
+        assert_eq!(selftest.mechanisms, vec![IpcMechanism::All]);
+        assert_eq!((selftest.msg_count, selftest.message_size), (16, 64));
+
+        let args = Args::parse_from(["ipc-benchmark", "selftest", "-m", "tcp", "shm", "-i", "4"]);
+        let Some(Command::Selftest(selftest)) = args.command else {
+            panic!("expected the selftest subcommand");
+        };
+        assert_eq!(
+            selftest.mechanisms,
+            vec![IpcMechanism::TcpSocket, IpcMechanism::SharedMemory]
+        );
+        assert_eq!(selftest.msg_count, 4);
+        assert!(Args::try_parse_from(["ipc-benchmark", "selftest", "-i", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_completions_and_schema_args() {
+        let args = Args::parse_from(["ipc-benchmark", "completions", "zsh"]);
+        assert!(matches!(
+            args.command,
+            Some(Command::Completions(CompletionsArgs {
+                shell: clap_complete::Shell::Zsh
+            }))
+        ));
+        assert!(Args::try_parse_from(["ipc-benchmark", "completions", "tcsh"]).is_err());
+        assert!(!Args::parse_from(["ipc-benchmark"]).dump_cli_schema);
+        assert!(Args::parse_from(["ipc-benchmark", "--dump-cli-schema"]).dump_cli_schema);
+    }
+
+    /// Every mechanism parses back from the `-m` name it is spawned with
+    #[test]
+    fn test_ipc_mechanism_names_parse() {
+        for mechanism in IpcMechanism::VARIANTS {
+            assert_eq!(
+                IpcMechanism::from_str(mechanism.name(), false).unwrap(),
+                *mechanism
+            );
+        }
+        let args = Args::try_parse_from(["ipc-benchmark", "-m", "shm", "udp-multicast"]);
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            args.unwrap().mechanisms,
+            vec![IpcMechanism::SharedMemory, IpcMechanism::UdpMulticast]
+        );
+        #[cfg(not(target_os = "linux"))]
+        assert!(args.is_err());
+    }
+
     /// Test duration parsing with microsecond support
     #[test]
     fn test_parse_duration_micros() {
@@ -971,6 +2328,363 @@ mod tests {
         assert_eq!(args_custom.pmq_priority, 5);
     }
 
+    #[test]
+    fn test_priorities_arg() {
+        assert!(Args::parse_from(["ipc-benchmark"]).priorities.is_empty());
+        let args = Args::parse_from(["ipc-benchmark", "--priorities", "0,3,6"]);
+        assert_eq!(args.priorities, [0, 3, 6]);
+        assert!(Args::try_parse_from(["ipc-benchmark", "--priorities", "300"]).is_err());
+    }
+
+    #[test]
+    fn test_id_width_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!((args.id_width, args.id_start), (None, 0));
+        let args = Args::parse_from(["ipc-benchmark", "--id-width", "16", "--id-start", "65500"]);
+        assert_eq!((args.id_width, args.id_start), (Some(16), 65500));
+        assert!(Args::try_parse_from(["ipc-benchmark", "--id-width", "4"]).is_err());
+        assert!(Args::try_parse_from(["ipc-benchmark", "--id-width", "64"]).is_err());
+        assert!(Args::try_parse_from(["ipc-benchmark", "--id-start", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_priority_inversion_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).priority_inversion, None);
+        let args = Args::parse_from(["ipc-benchmark", "--priority-inversion", "8"]);
+        assert_eq!(args.priority_inversion, Some(8));
+        assert!(Args::try_parse_from(["ipc-benchmark", "--priority-inversion", "0"]).is_err());
+        assert!(Args::try_parse_from([
+            "ipc-benchmark",
+            "--priority-inversion",
+            "8",
+            "--priorities",
+            "0,6"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_criteria_arg() {
+        assert!(Args::parse_from(["ipc-benchmark"]).criteria.is_none());
+        let args = Args::parse_from(["ipc-benchmark", "--criteria", "plan.json"]);
+        assert_eq!(args.criteria, Some(PathBuf::from("plan.json")));
+    }
+
+    #[test]
+    fn test_parallel_mechanisms_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).parallel_mechanisms, 1);
+        let args = Args::parse_from(["ipc-benchmark", "--parallel-mechanisms", "3"]);
+        assert_eq!(args.parallel_mechanisms, 3);
+    }
+
+    #[test]
+    fn test_campaign_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert!(args.manifest.is_none() && args.resume.is_none());
+        let args = Args::parse_from(["ipc-benchmark", "--resume", "campaign.json"]);
+        assert_eq!(args.resume, Some(PathBuf::from("campaign.json")));
+    }
+
+    #[test]
+    fn test_pacing_arg() {
+        use crate::pacing::PacingMode;
+        assert_eq!(
+            Args::parse_from(["ipc-benchmark"]).pacing,
+            PacingMode::Sleep
+        );
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--send-delay",
+            "50us",
+            "--pacing",
+            "spin-sleep",
+        ]);
+        assert_eq!(args.pacing, PacingMode::SpinSleep);
+        assert!(Args::try_parse_from(["ipc-benchmark", "--pacing", "timerfd"]).is_err());
+    }
+
+    #[test]
+    fn test_server_rate_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).server_rate, None);
+        let args = Args::parse_from(["ipc-benchmark", "--server-rate", "500"]);
+        assert_eq!(args.server_rate, Some(500));
+        assert!(Args::try_parse_from(["ipc-benchmark", "--server-rate", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_work_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!((args.client_work, args.server_work), (None, None));
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--client-work",
+            "5us",
+            "--server-work",
+            "2ms",
+        ]);
+        assert_eq!(args.client_work, Some(Duration::from_micros(5)));
+        assert_eq!(args.server_work, Some(Duration::from_millis(2)));
+        assert_eq!(args.server_spin, None);
+
+        let args = Args::parse_from(["ipc-benchmark", "--server-spin", "5000"]);
+        assert_eq!(args.server_spin, Some(5000));
+        assert!(Args::try_parse_from(["ipc-benchmark", "--server-spin", "0"]).is_err());
+        assert!(Args::try_parse_from([
+            "ipc-benchmark",
+            "--server-spin",
+            "5000",
+            "--server-work",
+            "2ms"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_producers_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).producers, 1);
+        let args = Args::parse_from(["ipc-benchmark", "--producers", "4"]);
+        assert_eq!(args.producers, 4);
+        assert!(Args::try_parse_from(["ipc-benchmark", "--producers", "0"]).is_err());
+        assert!(Args::try_parse_from(["ipc-benchmark", "--producers", "256"]).is_err());
+    }
+
+    #[test]
+    fn test_repeat_args() {
+        let args = Args::parse_from(["ipc-benchmark", "--repeat-every", "1h", "--until", "24h"]);
+        assert_eq!(args.repeat_every, Some(Duration::from_secs(3600)));
+        assert_eq!(args.until, Some(Duration::from_secs(24 * 3600)));
+        // --until only bounds a repeated run
+        assert!(Args::try_parse_from(["ipc-benchmark", "--until", "24h"]).is_err());
+    }
+
+    #[test]
+    fn test_cpu_split_arg() {
+        assert!(!Args::parse_from(["ipc-benchmark"]).cpu_split);
+        assert!(Args::parse_from(["ipc-benchmark", "--cpu-split"]).cpu_split);
+    }
+
+    #[test]
+    fn test_csv_columns_arg() {
+        use crate::csv_schema::CsvColumn;
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--streaming-output-csv",
+            "s.csv",
+            "--csv-columns",
+            "message_id,phase",
+        ]);
+        assert_eq!(
+            args.csv_columns,
+            Some(vec![CsvColumn::MessageId, CsvColumn::Phase])
+        );
+        // Columns only apply to a streaming CSV
+        assert!(Args::try_parse_from(["ipc-benchmark", "--csv-columns", "phase"]).is_err());
+    }
+
+    #[test]
+    fn test_compare_pooling_arg() {
+        assert!(!Args::parse_from(["ipc-benchmark"]).compare_pooling);
+        assert!(Args::parse_from(["ipc-benchmark", "--compare-pooling"]).compare_pooling);
+    }
+
+    /// `--machine-output` defaults to a single JSON document when bare.
+    #[test]
+    fn test_machine_output_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).machine_output, None);
+        let args = Args::parse_from(["ipc-benchmark", "--machine-output"]);
+        assert_eq!(args.machine_output, Some(MachineOutput::Json));
+        let args = Args::parse_from(["ipc-benchmark", "--machine-output", "jsonl"]);
+        assert_eq!(args.machine_output, Some(MachineOutput::Jsonl));
+    }
+
+    #[test]
+    fn test_spin_wait_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).spin_wait, None);
+        let args = Args::parse_from(["ipc-benchmark", "--spin-wait"]);
+        assert_eq!(args.spin_wait, Some(SpinWait::Busy));
+        let args = Args::parse_from(["ipc-benchmark", "--spin-wait", "pause"]);
+        assert_eq!(args.spin_wait, Some(SpinWait::Pause));
+    }
+
+    #[test]
+    fn test_http_version_arg() {
+        assert_eq!(
+            Args::parse_from(["ipc-benchmark"]).http_version,
+            HttpVersion::H1
+        );
+        let args = Args::parse_from(["ipc-benchmark", "-m", "http", "--http-version", "h2"]);
+        assert_eq!(args.http_version, HttpVersion::H2);
+        assert_eq!(args.http_version.to_string(), "h2");
+    }
+
+    #[test]
+    fn test_response_mode_arg() {
+        assert_eq!(
+            Args::parse_from(["ipc-benchmark"]).response_mode,
+            ResponseMode::Ack
+        );
+        let args = Args::parse_from(["ipc-benchmark", "--response-mode", "echo"]);
+        assert_eq!(args.response_mode, ResponseMode::Echo);
+        assert_eq!(args.response_mode.to_string(), "echo");
+
+        assert_eq!(
+            ResponseMode::Echo.reply_payload(vec![1, 2]),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            ResponseMode::Ack.reply_payload(vec![1, 2]),
+            Some(Vec::new())
+        );
+        assert_eq!(ResponseMode::None.reply_payload(vec![1, 2]), None);
+    }
+
+    #[test]
+    fn test_shm_wait_arg() {
+        assert_eq!(
+            Args::parse_from(["ipc-benchmark"]).shm_wait,
+            ShmWait::Condvar
+        );
+        let args = Args::parse_from(["ipc-benchmark", "--shm-wait", "semaphore"]);
+        assert_eq!(args.shm_wait, ShmWait::Semaphore);
+        assert!(Args::try_parse_from(["ipc-benchmark", "--shm-wait", "futex"]).is_err());
+    }
+
+    #[test]
+    fn test_shm_hugetlbfs_arg() {
+        assert!(Args::parse_from(["ipc-benchmark"]).shm_hugetlbfs.is_none());
+        let args = Args::parse_from(["ipc-benchmark", "--shm-hugetlbfs", "/mnt/huge1g"]);
+        assert_eq!(args.shm_hugetlbfs, Some(PathBuf::from("/mnt/huge1g")));
+    }
+
+    #[test]
+    fn test_output_trimming_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert!(!args.output_compact);
+        assert!(args.output_fields.is_empty());
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--output-compact",
+            "--output-fields",
+            "-histogram_data,results.one_way_results",
+        ]);
+        assert!(args.output_compact);
+        assert_eq!(
+            args.output_fields,
+            ["-histogram_data", "results.one_way_results"]
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_timeout_arg() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!(args.heartbeat_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(args.setup_retries, 3);
+        assert_eq!(args.setup_backoff, Duration::from_millis(50));
+        let args = Args::parse_from(["ipc-benchmark", "--heartbeat-timeout", "0"]);
+        assert_eq!(args.heartbeat_timeout, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_multicast_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!(args.subscribers, 1);
+        assert_eq!(args.multicast_group, None);
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--subscribers",
+            "8",
+            "--multicast-group",
+            "239.1.2.3",
+        ]);
+        assert_eq!(args.subscribers, 8);
+        assert_eq!(
+            args.multicast_group,
+            Some(std::net::Ipv4Addr::new(239, 1, 2, 3))
+        );
+        assert!(Args::try_parse_from(["ipc-benchmark", "--subscribers", "0"]).is_err());
+        assert!(Args::try_parse_from(["ipc-benchmark", "--multicast-group", "::1"]).is_err());
+    }
+
+    #[test]
+    fn test_sync_io_alias() {
+        assert!(!Args::parse_from(["ipc-benchmark"]).blocking);
+        assert!(Args::parse_from(["ipc-benchmark", "--sync-io"]).blocking);
+    }
+
+    #[test]
+    fn test_frequency_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!(args.frequency_sample_interval, None);
+        assert_eq!(args.frequency_drop_threshold, 10.0);
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--frequency-sample-interval",
+            "500ms",
+            "--frequency-drop-threshold",
+            "5",
+        ]);
+        assert_eq!(
+            args.frequency_sample_interval,
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(args.frequency_drop_threshold, 5.0);
+    }
+
+    #[test]
+    fn test_queue_depth_interval_arg() {
+        assert_eq!(
+            Args::parse_from(["ipc-benchmark"]).queue_depth_interval,
+            None
+        );
+        let args = Args::parse_from(["ipc-benchmark", "--queue-depth-interval", "250us"]);
+        assert_eq!(args.queue_depth_interval, Some(Duration::from_micros(250)));
+    }
+
+    #[test]
+    fn test_latency_reservoir_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).latency_reservoir, None);
+        let args = Args::parse_from(["ipc-benchmark", "--latency-reservoir"]);
+        assert_eq!(args.latency_reservoir, Some(10_000));
+        let args = Args::parse_from(["ipc-benchmark", "--latency-reservoir", "500"]);
+        assert_eq!(args.latency_reservoir, Some(500));
+        assert!(Args::try_parse_from(["ipc-benchmark", "--latency-reservoir", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_goodput_budget_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).goodput_budget, None);
+        let args = Args::parse_from(["ipc-benchmark", "--goodput-budget", "100us"]);
+        assert_eq!(args.goodput_budget, Some(Duration::from_micros(100)));
+    }
+
+    #[test]
+    fn test_units_args() {
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!((args.units, args.precision), (ByteUnits::Binary, 2));
+        let args = Args::parse_from(["ipc-benchmark", "--units", "si", "--precision", "3"]);
+        assert_eq!((args.units, args.precision), (ByteUnits::Si, 3));
+        assert!(Args::try_parse_from(["ipc-benchmark", "--precision", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_sla_thresholds_arg() {
+        assert!(Args::parse_from(["ipc-benchmark"])
+            .sla_thresholds
+            .is_empty());
+        let args = Args::parse_from(["ipc-benchmark", "--sla-thresholds", "100us,1ms"]);
+        assert_eq!(
+            args.sla_thresholds,
+            [Duration::from_micros(100), Duration::from_millis(1)]
+        );
+    }
+
+    #[test]
+    fn test_deadline_arg() {
+        assert_eq!(Args::parse_from(["ipc-benchmark"]).deadline, None);
+        let args = Args::parse_from(["ipc-benchmark", "--deadline", "250us"]);
+        assert_eq!(args.deadline, Some(Duration::from_micros(250)));
+    }
+
     /// Verify mapping from `Args` to `BenchmarkConfiguration`
     #[test]
     fn test_benchmark_configuration_from_args_mapping() {
@@ -996,6 +2710,18 @@ mod tests {
         assert!(cfg.one_way && cfg.round_trip); // default when neither specified
         assert!(cfg.include_first_message);
 
+        // Running each test both ways reports the run behind a canary
+        let args_both = Args::parse_from(["ipc-benchmark", "--first-message", "both"]);
+        assert_eq!(args_both.first_message_mode(), FirstMessage::Both);
+        assert!(!BenchmarkConfiguration::from(&args_both).include_first_message);
+        assert!(Args::try_parse_from([
+            "ipc-benchmark",
+            "--first-message",
+            "both",
+            "--include-first-message"
+        ])
+        .is_err());
+
         // Duration precedence over msg_count
         let args_dur = Args::parse_from(["ipc-benchmark", "-d", "1s", "-i", "999"]);
         let cfg_dur: BenchmarkConfiguration = (&args_dur).into();
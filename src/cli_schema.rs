@@ -0,0 +1,243 @@
+//! Machine-readable description of the command line.
+//!
+//! `--dump-cli-schema` prints every option and subcommand of the installed
+//! binary as JSON, read from the same clap definition that parses the
+//! arguments, so wrapper UIs and orchestration tools can generate their
+//! forms and validation against the exact version they drive. Hidden
+//! internal options are left out.
+
+use crate::cli::Args;
+use clap::builder::ValueRange;
+use clap::{ArgAction, CommandFactory};
+use serde::{Deserialize, Serialize};
+
+/// Version of the schema layout, raised when fields change meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One option or positional argument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionSchema {
+    /// Identifier, the field name with underscores
+    pub id: String,
+
+    /// Long flag without the leading dashes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long: Option<String>,
+
+    /// Short flag without the leading dash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short: Option<char>,
+
+    /// Whether the argument is given by position rather than by flag
+    pub positional: bool,
+
+    /// What giving the option does: `set` stores its values, `append`
+    /// accumulates repeats, `flag` takes no value (including `--help` and
+    /// `--version`) and `count` counts repeats
+    pub kind: String,
+
+    /// Fewest values taken per occurrence
+    pub min_values: usize,
+
+    /// Most values taken per occurrence, when bounded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_values: Option<usize>,
+
+    /// Placeholder names of the values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_names: Vec<String>,
+
+    /// Values used when the option is not given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_values: Vec<String>,
+
+    /// The only values accepted, when restricted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub possible_values: Vec<String>,
+
+    /// Whether the option must be given
+    pub required: bool,
+
+    /// Identifiers of options that cannot be combined with this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts_with: Vec<String>,
+
+    /// `--help` section, when not the default one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+
+    /// One-line help
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+
+    /// Full help of `--help`, when longer than the one line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_help: Option<String>,
+}
+
+/// A command and its subcommands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandSchema {
+    /// Command name
+    pub name: String,
+
+    /// One-line description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+
+    /// Options and positional arguments, in `--help` order
+    pub options: Vec<OptionSchema>,
+
+    /// Subcommands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subcommands: Vec<CommandSchema>,
+}
+
+/// The whole command line of this binary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliSchema {
+    /// Layout version, [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+
+    /// Version of the binary described
+    pub version: String,
+
+    /// The top-level command
+    pub command: CommandSchema,
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a std::ffi::OsStr>) -> Vec<String> {
+    values.map(|v| v.to_string_lossy().into_owned()).collect()
+}
+
+fn describe_option(command: &clap::Command, arg: &clap::Arg) -> OptionSchema {
+    let kind = match arg.get_action() {
+        ArgAction::SetTrue
+        | ArgAction::SetFalse
+        | ArgAction::Help
+        | ArgAction::HelpShort
+        | ArgAction::HelpLong
+        | ArgAction::Version => "flag",
+        ArgAction::Count => "count",
+        ArgAction::Append => "append",
+        _ => "set",
+    };
+    let range = arg
+        .get_num_args()
+        .unwrap_or(if kind == "set" || kind == "append" {
+            ValueRange::SINGLE
+        } else {
+            ValueRange::EMPTY
+        });
+    OptionSchema {
+        id: arg.get_id().to_string(),
+        long: arg.get_long().map(str::to_string),
+        short: arg.get_short(),
+        positional: arg.is_positional(),
+        kind: kind.to_string(),
+        min_values: range.min_values(),
+        max_values: (range.max_values() != usize::MAX).then(|| range.max_values()),
+        value_names: arg
+            .get_value_names()
+            .map(|names| names.iter().map(|n| n.to_string()).collect())
+            .unwrap_or_default(),
+        default_values: strings(arg.get_default_values().iter().map(|v| v.as_os_str())),
+        possible_values: if kind == "flag" {
+            Vec::new()
+        } else {
+            arg.get_possible_values()
+                .iter()
+                .filter(|v| !v.is_hide_set())
+                .map(|v| v.get_name().to_string())
+                .collect()
+        },
+        required: arg.is_required_set(),
+        conflicts_with: command
+            .get_arg_conflicts_with(arg)
+            .iter()
+            .map(|other| other.get_id().to_string())
+            .collect(),
+        heading: arg.get_help_heading().map(str::to_string),
+        help: arg.get_help().map(|h| h.to_string()),
+        long_help: arg.get_long_help().map(|h| h.to_string()),
+    }
+}
+
+fn describe_command(command: &clap::Command) -> CommandSchema {
+    CommandSchema {
+        name: command.get_name().to_string(),
+        about: command.get_about().map(|a| a.to_string()),
+        options: command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set())
+            .map(|arg| describe_option(command, arg))
+            .collect(),
+        subcommands: command
+            .get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(describe_command)
+            .collect(),
+    }
+}
+
+/// The schema of this binary's command line.
+pub fn schema() -> CliSchema {
+    let mut command = Args::command();
+    command.build();
+    CliSchema {
+        schema_version: SCHEMA_VERSION,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        command: describe_command(&command),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option<'a>(command: &'a CommandSchema, id: &str) -> &'a OptionSchema {
+        command
+            .options
+            .iter()
+            .find(|o| o.id == id)
+            .unwrap_or_else(|| panic!("no option {}", id))
+    }
+
+    #[test]
+    fn test_schema_describes_options_and_subcommands() {
+        let schema = schema();
+        let root = &schema.command;
+
+        let mechanisms = option(root, "mechanisms");
+        assert_eq!(mechanisms.short, Some('m'));
+        assert_eq!(mechanisms.default_values, ["uds"]);
+        assert!(mechanisms.possible_values.iter().any(|v| v == "all"));
+        assert_eq!(mechanisms.max_values, None);
+
+        let blocking = option(root, "blocking");
+        assert_eq!(blocking.kind, "flag");
+        assert_eq!((blocking.min_values, blocking.max_values), (0, Some(0)));
+
+        let output = option(root, "output_file");
+        // A value is optional after the flag
+        assert_eq!((output.min_values, output.max_values), (0, Some(1)));
+        assert_eq!(output.heading.as_deref(), Some("Output and Logging"));
+
+        assert_eq!(option(root, "verbose").kind, "count");
+        assert!(option(root, "id_start").long.is_some());
+
+        // Internal options stay out of the schema
+        assert!(!root.options.iter().any(|o| o.id == "internal_latency_file"));
+
+        let selftest = root
+            .subcommands
+            .iter()
+            .find(|c| c.name == "selftest")
+            .unwrap();
+        assert_eq!(option(selftest, "msg_count").default_values, ["16"]);
+
+        // The schema round-trips through JSON
+        let json = serde_json::to_string(&schema).unwrap();
+        assert_eq!(serde_json::from_str::<CliSchema>(&json).unwrap(), schema);
+    }
+}
@@ -0,0 +1,220 @@
+//! Clock synchronization quality for distributed runs.
+//!
+//! Timestamps taken on different hosts can only be compared as far as their
+//! clocks agree. This module asks the local time daemon how far this
+//! host's clock may be from true time, so split runs record how much their
+//! wall-clock timestamps can be trusted across hosts. One-way latency uses
+//! the monotonic clock, which is not synchronized at all, so the standalone
+//! server only reports it for clients on its own host.
+//!
+//! ## Sources
+//!
+//! Queried in order, the first that answers wins:
+//!
+//! 1. **chrony**: `chronyc -c tracking`. The error bound is chrony's
+//!    `|offset| + root dispersion + root delay / 2`.
+//! 2. **PTP**: `pmc -u -b 0 'GET TIME_STATUS_NP'` from linuxptp. The error
+//!    bound is the magnitude of the offset from the grandmaster.
+//! 3. **kernel**: `adjtimex(2)`, which reports the maximum error whichever
+//!    daemon disciplines the clock (Linux only).
+
+use crate::metrics::utils::format_latency;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// How well this host's clock is synchronized, as reported by its time daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSync {
+    /// Where the figures came from: "chrony", "ptp" or "kernel"
+    pub source: String,
+
+    /// Whether the daemon considers the clock synchronized
+    pub synchronized: bool,
+
+    /// Current estimated offset from the reference, in nanoseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset_ns: Option<i64>,
+
+    /// Bound on the difference between this clock and true time, in nanoseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_bound_ns: Option<u64>,
+}
+
+impl ClockSync {
+    /// Query the local time daemons, returning `None` if none answers.
+    pub fn query() -> Option<Self> {
+        query_chrony().or_else(query_ptp).or_else(query_kernel)
+    }
+}
+
+impl std::fmt::Display for ClockSync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)?;
+        if !self.synchronized {
+            return write!(f, " (not synchronized)");
+        }
+        if let Some(offset) = self.offset_ns {
+            write!(
+                f,
+                ", offset {}{}",
+                if offset < 0 { '-' } else { '+' },
+                format_latency(offset.unsigned_abs())
+            )?;
+        }
+        if let Some(bound) = self.error_bound_ns {
+            write!(f, ", error bound ±{}", format_latency(bound))?;
+        }
+        Ok(())
+    }
+}
+
+fn command_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+fn query_chrony() -> Option<ClockSync> {
+    parse_chrony_tracking(&command_stdout("chronyc", &["-c", "tracking"])?)
+}
+
+fn query_ptp() -> Option<ClockSync> {
+    parse_pmc_time_status(&command_stdout(
+        "pmc",
+        &["-u", "-b", "0", "GET TIME_STATUS_NP"],
+    )?)
+}
+
+/// Parse the CSV form of `chronyc tracking`.
+///
+/// Fields: reference ID, reference name, stratum, reference time, system
+/// time offset, last offset, RMS offset, frequency, residual frequency,
+/// skew, root delay, root dispersion, update interval, leap status.
+/// Times are in seconds.
+fn parse_chrony_tracking(output: &str) -> Option<ClockSync> {
+    let fields: Vec<&str> = output.trim().split(',').collect();
+    if fields.len() < 14 {
+        return None;
+    }
+    let seconds = |i: usize| fields[i].trim().parse::<f64>().ok();
+    let offset = seconds(4)?;
+    let root_delay = seconds(10)?;
+    let root_dispersion = seconds(11)?;
+    let stratum: u32 = fields[2].trim().parse().ok()?;
+    let synchronized = stratum > 0 && fields[13].trim() != "Not synchronised";
+
+    Some(ClockSync {
+        source: "chrony".to_string(),
+        synchronized,
+        offset_ns: Some((offset * 1e9).round() as i64),
+        error_bound_ns: Some(((offset.abs() + root_dispersion + root_delay / 2.0) * 1e9) as u64),
+    })
+}
+
+/// Parse `pmc` output for the TIME_STATUS_NP management message.
+fn parse_pmc_time_status(output: &str) -> Option<ClockSync> {
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next() == Some(key)).then(|| parts.next()).flatten()
+        })
+    };
+    let offset: i64 = value("master_offset")?.parse().ok()?;
+    let synchronized = value("gmPresent") == Some("true");
+
+    Some(ClockSync {
+        source: "ptp".to_string(),
+        synchronized,
+        offset_ns: Some(offset),
+        error_bound_ns: Some(offset.unsigned_abs()),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn query_kernel() -> Option<ClockSync> {
+    let mut tx: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut tx) };
+    if state < 0 {
+        return None;
+    }
+    let synchronized = state != libc::TIME_ERROR && tx.status & libc::STA_UNSYNC == 0;
+
+    Some(ClockSync {
+        source: "kernel".to_string(),
+        synchronized,
+        offset_ns: None,
+        // maxerror is in microseconds
+        error_bound_ns: synchronized.then(|| tx.maxerror as u64 * 1000),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn query_kernel() -> Option<ClockSync> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chrony_tracking() {
+        let output = "A9FEA97B,169.254.169.123,4,1697462000.123456789,\
+                      -0.000001500,0.000002000,0.000003000,-12.345,-0.001,0.012,\
+                      0.000200000,0.000010000,64.1,Normal\n";
+        let sync = parse_chrony_tracking(output).unwrap();
+        assert_eq!(sync.source, "chrony");
+        assert!(sync.synchronized);
+        assert_eq!(sync.offset_ns, Some(-1500));
+        // |offset| + dispersion + delay / 2 = 1.5 + 10 + 100 us
+        assert_eq!(sync.error_bound_ns, Some(111_500));
+
+        let unsynced = output.replace("Normal", "Not synchronised");
+        assert!(!parse_chrony_tracking(&unsynced).unwrap().synchronized);
+        assert!(parse_chrony_tracking("506 Cannot talk to daemon").is_none());
+    }
+
+    #[test]
+    fn test_parse_pmc_time_status() {
+        let output = "sending: GET TIME_STATUS_NP\n\
+                      \t001122.fffe.334455-0 seq 0 RESPONSE MANAGEMENT TIME_STATUS_NP\n\
+                      \t\tmaster_offset              -42\n\
+                      \t\tingress_time               1697462000123456789\n\
+                      \t\tgmPresent                  true\n";
+        let sync = parse_pmc_time_status(output).unwrap();
+        assert_eq!(sync.source, "ptp");
+        assert!(sync.synchronized);
+        assert_eq!(sync.offset_ns, Some(-42));
+        assert_eq!(sync.error_bound_ns, Some(42));
+        assert!(parse_pmc_time_status("sending: GET TIME_STATUS_NP\n").is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_query_kernel() {
+        let sync = query_kernel().expect("adjtimex is always available on Linux");
+        assert_eq!(sync.source, "kernel");
+        assert_eq!(sync.error_bound_ns.is_some(), sync.synchronized);
+    }
+
+    #[test]
+    fn test_display() {
+        let sync = ClockSync {
+            source: "chrony".to_string(),
+            synchronized: true,
+            offset_ns: Some(-1500),
+            error_bound_ns: Some(111_500),
+        };
+        assert_eq!(
+            sync.to_string(),
+            "chrony, offset -1.50μs, error bound ±111.50μs"
+        );
+        let unsynced = ClockSync {
+            synchronized: false,
+            ..sync
+        };
+        assert_eq!(unsynced.to_string(), "chrony (not synchronized)");
+    }
+}
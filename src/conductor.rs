@@ -0,0 +1,1024 @@
+//! Coordinated runs across several hosts.
+//!
+//! `ipc-benchmark agent` waits on a TCP control port for a conductor.
+//! `ipc-benchmark conduct --agent HOST:PORT ...` connects to each agent,
+//! estimates the offset of its clock, hands it a benchmark command line and
+//! then has every agent start at the same instant, so cross-host tests (a
+//! `--server` on one machine and `--client`s on others, or independent
+//! suites contending for a shared network) overlap as intended. The
+//! conductor collects the final results of every agent into one document.
+//!
+//! ## Protocol
+//!
+//! One JSON object per line, tagged by `type`:
+//!
+//! 1. `hello` → `welcome`: protocol version, the agent's host name, its
+//!    clock synchronization status and a random challenge.
+//! 2. `auth` → `authenticated` or `error`: the HMAC-SHA256 of the
+//!    challenge keyed with the token both ends read from `--token-file`.
+//!    The agent answers nothing else until the conductor has proved it
+//!    holds the token, and the token itself never crosses the network.
+//!    Until the start, the agent waits at most [`CONTROL_TIMEOUT`] for each
+//!    line, and reads at most [`MAX_UNAUTHENTICATED_LINE`] bytes of one
+//!    before authentication.
+//! 3. `probe` → `probe_reply`, repeated: the conductor's send time and the
+//!    agent's clock when it answered. The round with the smallest round
+//!    trip gives the offset, as in NTP: the agent's reading minus the
+//!    midpoint of the round trip.
+//! 4. `prepare` → `ready` or `error`: the benchmark arguments, checked by
+//!    the agent's parser before anything starts. The conductor expands a
+//!    `--config` file into the options it holds, and the agent accepts only
+//!    the options in [`AGENT_OPTIONS`]: nothing that names a file on the
+//!    agent, reaches another host or changes the host itself.
+//! 5. `start` → `finished`: the start time on the agent's own clock. The
+//!    agent runs the benchmark as a child process at that time and answers
+//!    with its exit code and final results.
+//!
+//! The clocks are synchronized only logically: agents keep their own time
+//! and the conductor translates the start time into each of them. The start
+//! skew is within the round-trip time of the chosen probe.
+
+use crate::cli::{AgentArgs, Args, ConductArgs};
+use crate::clock_sync::ClockSync;
+use crate::metrics::utils::format_latency;
+use crate::{repeat, shutdown};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Version of the control protocol; both ends must agree
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Benchmark options an agent runs for a conductor, by field name.
+///
+/// Everything else is refused: output and log files, `--config`, uploads,
+/// repetition, `--server-remote` and `--remote-binary`, `--drop-caches`,
+/// and the other options that name a path on the agent.
+pub const AGENT_OPTIONS: [&str; 90] = [
+    "mechanisms",
+    "message_size",
+    "msg_count",
+    "duration",
+    "send_delay",
+    "pacing",
+    "idle_gap",
+    "deadline",
+    "concurrency",
+    "server_affinity",
+    "client_affinity",
+    "smt_siblings",
+    "parallel_mechanisms",
+    "one_way",
+    "round_trip",
+    "warmup_iterations",
+    "warmup",
+    "warmup_for",
+    "capture_warmup",
+    "profile",
+    "continue_on_error",
+    "quiet",
+    "verbose",
+    "log_rate_limit",
+    "csv_columns",
+    "run_id",
+    "output_compact",
+    "output_fields",
+    "latency_reservoir",
+    "goodput_budget",
+    "sla_thresholds",
+    "units",
+    "precision",
+    "percentiles",
+    "buffer_size",
+    "host",
+    "port",
+    "pmq_priority",
+    "priorities",
+    "producers",
+    "priority_inversion",
+    "id_width",
+    "id_start",
+    "subscribers",
+    "multicast_group",
+    "compare_pooling",
+    "pair",
+    "cpu_split",
+    "first_message",
+    "fresh_resources",
+    "runtime",
+    "worker_threads",
+    "event_interval",
+    "frequency_sample_interval",
+    "frequency_drop_threshold",
+    "scheduler_noise_interval",
+    "require_idle",
+    "skip_footprint_check",
+    "no_alloc_hot_path",
+    "queue_depth_interval",
+    "server_batch",
+    "server_rate",
+    "client_work",
+    "server_work",
+    "server_spin",
+    "response_mode",
+    "uds_credentials",
+    "tcp_keepalive",
+    "bind_device",
+    "source_addr",
+    "setup_retries",
+    "setup_backoff",
+    "heartbeat_timeout",
+    "first_byte_latency",
+    "segment_size",
+    "fragment_writes",
+    "verify_frames",
+    "blocking",
+    "spin_wait",
+    "shm_direct",
+    "shm_align",
+    "shm_pad_indices",
+    "shm_capacity_messages",
+    "shm_wait",
+    "zero_copy",
+    "http_version",
+    "server",
+    "client",
+    "shared_memory_name",
+    "message_queue_name",
+];
+
+/// How long either end waits for the other outside of the benchmark run
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest line an agent reads before the conductor has authenticated;
+/// the greeting and the proof fit in far less
+const MAX_UNAUTHENTICATED_LINE: u64 = 4096;
+
+/// A control message, sent as one JSON line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Conductor greeting
+    Hello { version: u32 },
+
+    /// Agent greeting, with the challenge the conductor must answer
+    Welcome {
+        version: u32,
+        hostname: Option<String>,
+        clock_sync: Option<ClockSync>,
+        challenge: String,
+    },
+
+    /// Answer to the challenge: its HMAC-SHA256 under the shared token
+    Auth { proof: String },
+
+    /// The conductor holds the token
+    Authenticated,
+
+    /// Clock probe, with the conductor's send time
+    Probe { sent_ns: u64 },
+
+    /// Answer to a probe, with the agent's clock
+    ProbeReply { sent_ns: u64, agent_ns: u64 },
+
+    /// Benchmark arguments for the agent to run
+    Prepare { args: Vec<String> },
+
+    /// The arguments are valid
+    Ready,
+
+    /// Start the benchmark at this time on the agent's clock
+    Start { at_ns: u64 },
+
+    /// The benchmark has finished
+    Finished {
+        started: DateTime<Utc>,
+        finished: DateTime<Utc>,
+        exit_code: Option<i32>,
+        results: Option<serde_json::Value>,
+    },
+
+    /// The request failed
+    Error { message: String },
+}
+
+/// One round of clock probing, all times in nanoseconds since the epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeSample {
+    /// Conductor's clock when the probe was sent
+    pub sent_ns: u64,
+
+    /// Agent's clock when it answered
+    pub agent_ns: u64,
+
+    /// Conductor's clock when the answer arrived
+    pub received_ns: u64,
+}
+
+/// Estimated offset of an agent's clock from the conductor's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockOffset {
+    /// Agent clock minus conductor clock, in nanoseconds
+    pub offset_ns: i64,
+
+    /// Round-trip time of the probe the estimate came from, in nanoseconds
+    pub rtt_ns: u64,
+}
+
+/// Estimate the offset from the probe with the smallest round trip, which
+/// spent the least time queued and so bounds the error most tightly.
+pub fn estimate_offset(samples: &[ProbeSample]) -> Option<ClockOffset> {
+    samples
+        .iter()
+        .filter(|sample| sample.received_ns >= sample.sent_ns)
+        .min_by_key(|sample| sample.received_ns - sample.sent_ns)
+        .map(|sample| {
+            let midpoint =
+                sample.sent_ns as i128 + (sample.received_ns as i128 - sample.sent_ns as i128) / 2;
+            ClockOffset {
+                offset_ns: (sample.agent_ns as i128 - midpoint) as i64,
+                rtt_ns: sample.received_ns - sample.sent_ns,
+            }
+        })
+}
+
+/// What one agent ran, in the conductor's output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentRun {
+    /// Control address of the agent
+    pub address: String,
+
+    /// Host name the agent reported
+    pub hostname: Option<String>,
+
+    /// Clock synchronization status the agent reported
+    pub clock_sync: Option<ClockSync>,
+
+    /// Estimated offset of the agent's clock
+    pub clock_offset: ClockOffset,
+
+    /// Benchmark arguments the agent ran
+    pub args: Vec<String>,
+
+    /// When the benchmark started, on the agent's clock
+    pub started: Option<DateTime<Utc>>,
+
+    /// When the benchmark finished, on the agent's clock
+    pub finished: Option<DateTime<Utc>>,
+
+    /// Difference between the actual and the scheduled start, corrected
+    /// for the clock offset, in nanoseconds
+    pub start_skew_ns: Option<i64>,
+
+    /// Exit code of the benchmark, if it exited normally
+    pub exit_code: Option<i32>,
+
+    /// Final results document of the benchmark, if any
+    pub results: Option<serde_json::Value>,
+
+    /// Why the agent did not run to completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Final document of a conducted run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConductedRun {
+    /// Scheduled start, on the conductor's clock
+    pub scheduled_start: DateTime<Utc>,
+
+    /// Every agent, in `--agent` order
+    pub agents: Vec<AgentRun>,
+}
+
+/// Nanoseconds since the epoch on this host's clock
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn datetime_ns(time: DateTime<Utc>) -> Option<u64> {
+    time.timestamp_nanos_opt().map(|ns| ns as u64)
+}
+
+/// Read the shared token from `path`, refusing an empty file or, on Unix,
+/// one that other users can read.
+fn read_token(path: &Path) -> Result<Vec<u8>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read the token file {}", path.display()))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            anyhow::bail!(
+                "The token file {} is accessible to other users; chmod 600 it",
+                path.display()
+            );
+        }
+    }
+    let mut token = std::fs::read(path)
+        .with_context(|| format!("Failed to read the token file {}", path.display()))?;
+    // A trailing newline from an editor or `echo` is not part of the token
+    let end = token
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |last| last + 1);
+    token.truncate(end);
+    if token.is_empty() {
+        anyhow::bail!("The token file {} is empty", path.display());
+    }
+    Ok(token)
+}
+
+/// HMAC-SHA256 of `challenge` keyed with `token`.
+fn challenge_mac(token: &[u8], challenge: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(token).expect("HMAC takes keys of any length");
+    mac.update(challenge.as_bytes());
+    mac
+}
+
+/// Proof that the conductor holds `token`: the hex HMAC of `challenge`.
+fn prove(token: &[u8], challenge: &str) -> String {
+    hex(&challenge_mac(token, challenge).finalize().into_bytes())
+}
+
+/// Whether `proof` answers `challenge` under `token`, compared in constant
+/// time.
+fn verify_proof(token: &[u8], challenge: &str, proof: &str) -> bool {
+    let Some(proof) = unhex(proof) else {
+        return false;
+    };
+    challenge_mac(token, challenge).verify_slice(&proof).is_ok()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Line-oriented JSON connection to the other end
+struct Channel {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Channel {
+    fn new(stream: TcpStream) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    fn send(&mut self, message: &Message) -> Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Message> {
+        self.receive_within(u64::MAX)
+    }
+
+    /// Receive a message whose line is at most `limit` bytes long.
+    fn receive_within(&mut self, limit: u64) -> Result<Message> {
+        let mut line = Vec::new();
+        let read = (&mut self.reader)
+            .take(limit)
+            .read_until(b'\n', &mut line)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+                    anyhow::anyhow!("No complete control message within the timeout")
+                }
+                _ => e.into(),
+            })?;
+        if read == 0 {
+            anyhow::bail!("Connection closed");
+        }
+        if read as u64 == limit && line.last() != Some(&b'\n') {
+            anyhow::bail!("Control message longer than {} bytes", limit);
+        }
+        serde_json::from_slice(&line).context("Invalid control message")
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        self.writer.set_read_timeout(timeout)?;
+        Ok(())
+    }
+}
+
+fn hostname() -> Option<String> {
+    #[cfg(unix)]
+    {
+        let mut buf = [0u8; 256];
+        if unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } != 0 {
+            return None;
+        }
+        let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        Some(String::from_utf8_lossy(&buf[..end]).into_owned())
+    }
+    #[cfg(not(unix))]
+    {
+        std::env::var("COMPUTERNAME").ok()
+    }
+}
+
+/// Parse benchmark arguments for an agent.
+fn parse_suite(args: &[String]) -> Result<Args> {
+    let parsed = Args::try_parse_explicit_from(
+        std::iter::once("ipc-benchmark").chain(args.iter().map(String::as_str)),
+    )
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    if parsed.command.is_some() {
+        anyhow::bail!("agents run benchmarks, not subcommands");
+    }
+    Ok(parsed)
+}
+
+/// Benchmark arguments as the conductor sends them: with any `--config`
+/// file, which exists only on the conductor's host, expanded into the
+/// options it holds.
+fn conductor_suite(args: &[String]) -> Result<Vec<String>> {
+    let parsed = parse_suite(args)?;
+    Ok(parsed
+        .explicit_tokens
+        .into_iter()
+        .filter(|(id, _)| id != "config")
+        .flat_map(|(_, tokens)| tokens)
+        .collect())
+}
+
+/// Check benchmark arguments for an agent against [`AGENT_OPTIONS`],
+/// returning the command line of its child run.
+fn agent_suite(args: &[String]) -> Result<Vec<OsString>> {
+    let parsed = parse_suite(args)?;
+    let mut suite = Vec::new();
+    for (id, tokens) in parsed.explicit_tokens {
+        if !AGENT_OPTIONS.contains(&id.as_str()) {
+            anyhow::bail!(
+                "{} is not accepted from a conductor",
+                tokens.first().map_or(id.as_str(), String::as_str)
+            );
+        }
+        suite.extend(tokens.into_iter().map(OsString::from));
+    }
+    Ok(suite)
+}
+
+/// Serve conductors on `--listen`, one at a time.
+pub fn run_agent(args: &AgentArgs) -> Result<()> {
+    let token = read_token(&args.token_file)?;
+    let listener = TcpListener::bind(&args.listen)
+        .with_context(|| format!("Failed to listen on {}", args.listen))?;
+    listener.set_nonblocking(true)?;
+    shutdown::install_handler("Interrupted, stopping the agent");
+    eprintln!("Agent listening on {}", listener.local_addr()?);
+
+    while !shutdown::requested() {
+        let (stream, peer) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        eprintln!("Conductor connected from {}", peer);
+        stream.set_nonblocking(false)?;
+        match serve(stream, &token, CONTROL_TIMEOUT) {
+            Ok(()) => eprintln!("Conductor {} done", peer),
+            Err(e) => eprintln!("Conductor {} failed: {:#}", peer, e),
+        }
+        if args.once {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Run one conductor session, once it has proved it holds `token`.
+///
+/// Every read until the start waits at most `timeout`, and lines before
+/// authentication are capped, so a peer that sends nothing, half a line or
+/// an endless one cannot hold the agent.
+fn serve(stream: TcpStream, token: &[u8], timeout: Duration) -> Result<()> {
+    let mut channel = Channel::new(stream)?;
+    channel.set_timeout(Some(timeout))?;
+    let challenge = hex(&rand::random::<[u8; 32]>());
+    let mut authenticated = false;
+    let mut suite = None;
+    loop {
+        if shutdown::requested() {
+            anyhow::bail!("agent interrupted");
+        }
+        let limit = if authenticated {
+            u64::MAX
+        } else {
+            MAX_UNAUTHENTICATED_LINE
+        };
+        let message = match channel.receive_within(limit) {
+            Ok(message) => message,
+            Err(e) if !authenticated => return Err(e.context("conductor did not authenticate")),
+            // The conductor hangs up once it has the results
+            Err(_) if suite.is_none() => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        match message {
+            Message::Hello { version } => {
+                channel.send(&Message::Welcome {
+                    version: PROTOCOL_VERSION,
+                    hostname: hostname(),
+                    clock_sync: ClockSync::query(),
+                    challenge: challenge.clone(),
+                })?;
+                if version != PROTOCOL_VERSION {
+                    anyhow::bail!(
+                        "conductor speaks protocol version {}, this agent {}",
+                        version,
+                        PROTOCOL_VERSION
+                    );
+                }
+            }
+            Message::Auth { proof } => {
+                if !verify_proof(token, &challenge, &proof) {
+                    channel.send(&Message::Error {
+                        message: "wrong token".to_string(),
+                    })?;
+                    anyhow::bail!("conductor does not hold the token");
+                }
+                authenticated = true;
+                channel.send(&Message::Authenticated)?;
+            }
+            other if !authenticated => {
+                anyhow::bail!("Unauthenticated message from conductor: {:?}", other)
+            }
+            Message::Probe { sent_ns } => channel.send(&Message::ProbeReply {
+                sent_ns,
+                agent_ns: now_ns(),
+            })?,
+            Message::Prepare { args } => match agent_suite(&args) {
+                Ok(prepared) => {
+                    suite = Some(prepared);
+                    channel.send(&Message::Ready)?;
+                }
+                Err(e) => channel.send(&Message::Error {
+                    message: format!("{:#}", e),
+                })?,
+            },
+            Message::Start { at_ns } => {
+                let Some(prepared) = suite.take() else {
+                    channel.send(&Message::Error {
+                        message: "start before prepare".to_string(),
+                    })?;
+                    continue;
+                };
+                let reply = match run_at(&prepared, at_ns) {
+                    Ok(reply) => reply,
+                    Err(e) => Message::Error {
+                        message: format!("{:#}", e),
+                    },
+                };
+                channel.send(&reply)?;
+                return Ok(());
+            }
+            other => anyhow::bail!("Unexpected message from conductor: {:?}", other),
+        }
+    }
+}
+
+/// Wait until `at_ns` on this host's clock, then run the benchmark.
+fn run_at(suite: &[OsString], at_ns: u64) -> Result<Message> {
+    loop {
+        if shutdown::requested() {
+            anyhow::bail!("agent interrupted before the start");
+        }
+        let now = now_ns();
+        if now >= at_ns {
+            break;
+        }
+        std::thread::sleep(Duration::from_nanos(at_ns - now).min(Duration::from_millis(50)));
+    }
+    let exe = std::env::current_exe().context("Failed to locate the benchmark executable")?;
+    // A private directory, so no other user can predict or replace the
+    // results file
+    let dir = tempfile::Builder::new()
+        .prefix("ipc-benchmark-agent-")
+        .tempdir()
+        .context("Failed to create a directory for the results")?;
+    let record = repeat::run_once(&exe, suite, 1, Some(&dir.path().join("results.json")))?;
+    Ok(Message::Finished {
+        started: record.started,
+        finished: record.finished,
+        exit_code: record.exit_code,
+        results: record.results,
+    })
+}
+
+/// `--agent` value: the control address and, after `=`, the agent's own
+/// benchmark arguments
+fn parse_agent(spec: &str, shared: &[String]) -> (String, Vec<String>) {
+    match spec.split_once('=') {
+        Some((address, args)) => (
+            address.to_string(),
+            args.split_whitespace().map(str::to_string).collect(),
+        ),
+        None => (spec.to_string(), shared.to_vec()),
+    }
+}
+
+/// A connected and prepared agent
+struct Session {
+    address: String,
+    channel: Channel,
+    hostname: Option<String>,
+    clock_sync: Option<ClockSync>,
+    clock_offset: ClockOffset,
+    args: Vec<String>,
+}
+
+fn connect(address: &str, token: &[u8], args: Vec<String>, probes: usize) -> Result<Session> {
+    let addr = address
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid agent address {}", address))?
+        .next()
+        .with_context(|| format!("Agent address {} did not resolve", address))?;
+    let stream = TcpStream::connect_timeout(&addr, CONTROL_TIMEOUT)
+        .with_context(|| format!("Failed to connect to agent {}", address))?;
+    stream.set_nodelay(true)?;
+    let mut channel = Channel::new(stream)?;
+    channel.set_timeout(Some(CONTROL_TIMEOUT))?;
+
+    channel.send(&Message::Hello {
+        version: PROTOCOL_VERSION,
+    })?;
+    let (hostname, clock_sync, challenge) = match channel.receive()? {
+        Message::Welcome {
+            version,
+            hostname,
+            clock_sync,
+            challenge,
+        } if version == PROTOCOL_VERSION => (hostname, clock_sync, challenge),
+        Message::Welcome { version, .. } => anyhow::bail!(
+            "agent speaks protocol version {}, this conductor {}",
+            version,
+            PROTOCOL_VERSION
+        ),
+        other => anyhow::bail!("Unexpected greeting: {:?}", other),
+    };
+
+    channel.send(&Message::Auth {
+        proof: prove(token, &challenge),
+    })?;
+    match channel.receive()? {
+        Message::Authenticated => {}
+        Message::Error { message } => anyhow::bail!("refused the conductor: {}", message),
+        other => anyhow::bail!("Unexpected reply to auth: {:?}", other),
+    }
+
+    let mut samples = Vec::with_capacity(probes);
+    for _ in 0..probes.max(1) {
+        channel.send(&Message::Probe { sent_ns: now_ns() })?;
+        match channel.receive()? {
+            Message::ProbeReply { sent_ns, agent_ns } => samples.push(ProbeSample {
+                sent_ns,
+                agent_ns,
+                received_ns: now_ns(),
+            }),
+            other => anyhow::bail!("Unexpected probe reply: {:?}", other),
+        }
+    }
+    let clock_offset =
+        estimate_offset(&samples).context("The conductor's clock went backwards while probing")?;
+
+    channel.send(&Message::Prepare { args: args.clone() })?;
+    match channel.receive()? {
+        Message::Ready => {}
+        Message::Error { message } => anyhow::bail!("rejected the arguments: {}", message),
+        other => anyhow::bail!("Unexpected reply to prepare: {:?}", other),
+    }
+
+    Ok(Session {
+        address: address.to_string(),
+        channel,
+        hostname,
+        clock_sync,
+        clock_offset,
+        args,
+    })
+}
+
+/// Start the session at `start_ns` on the conductor's clock and wait for
+/// its results.
+fn finish(mut session: Session, start_ns: u64) -> AgentRun {
+    let offset = session.clock_offset.offset_ns;
+    let at_ns = (start_ns as i128 + offset as i128) as u64;
+    let reply = session
+        .channel
+        .send(&Message::Start { at_ns })
+        .and_then(|_| session.channel.set_timeout(None))
+        .and_then(|_| session.channel.receive());
+
+    let mut run = AgentRun {
+        address: session.address,
+        hostname: session.hostname,
+        clock_sync: session.clock_sync,
+        clock_offset: session.clock_offset,
+        args: session.args,
+        started: None,
+        finished: None,
+        start_skew_ns: None,
+        exit_code: None,
+        results: None,
+        error: None,
+    };
+    match reply {
+        Ok(Message::Finished {
+            started,
+            finished,
+            exit_code,
+            results,
+        }) => {
+            run.start_skew_ns =
+                datetime_ns(started).map(|started| (started as i128 - at_ns as i128) as i64);
+            run.started = Some(started);
+            run.finished = Some(finished);
+            run.exit_code = exit_code;
+            run.results = results;
+        }
+        Ok(Message::Error { message }) => run.error = Some(message),
+        Ok(other) => run.error = Some(format!("Unexpected reply to start: {:?}", other)),
+        Err(e) => run.error = Some(format!("{:#}", e)),
+    }
+    run
+}
+
+/// Connect to every `--agent`, start them together and collect the
+/// results.
+pub fn run_conduct(args: &ConductArgs) -> Result<()> {
+    let token = read_token(&args.token_file)?;
+    let mut sessions = Vec::with_capacity(args.agents.len());
+    for spec in &args.agents {
+        let (address, agent_args) = parse_agent(spec, &args.args);
+        let session = conductor_suite(&agent_args)
+            .and_then(|agent_args| connect(&address, &token, agent_args, args.probes))
+            .with_context(|| format!("Agent {}", address))?;
+        let offset = session.clock_offset.offset_ns;
+        eprintln!(
+            "Agent {} ({}): clock offset {}{}, round trip {}",
+            session.address,
+            session.hostname.as_deref().unwrap_or("unknown host"),
+            if offset < 0 { '-' } else { '+' },
+            format_latency(offset.unsigned_abs()),
+            format_latency(session.clock_offset.rtt_ns)
+        );
+        sessions.push(session);
+    }
+
+    let start_ns = now_ns() + args.start_delay.as_nanos() as u64;
+    let scheduled_start = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_nanos(start_ns));
+    eprintln!(
+        "Starting {} agents at {}",
+        sessions.len(),
+        scheduled_start.to_rfc3339()
+    );
+    let handles: Vec<_> = sessions
+        .into_iter()
+        .map(|session| std::thread::spawn(move || finish(session, start_ns)))
+        .collect();
+    let agents: Vec<AgentRun> = handles
+        .into_iter()
+        .map(|handle| handle.join().expect("agent thread panicked"))
+        .collect();
+
+    let mut failed = 0;
+    for agent in &agents {
+        match (&agent.error, agent.exit_code) {
+            (Some(error), _) => {
+                failed += 1;
+                eprintln!("Agent {}: failed: {}", agent.address, error);
+            }
+            (None, code) => {
+                if code != Some(0) {
+                    failed += 1;
+                }
+                eprintln!(
+                    "Agent {}: exit code {}, start skew {}",
+                    agent.address,
+                    code.map_or("none".to_string(), |code| code.to_string()),
+                    agent.start_skew_ns.map_or("unknown".to_string(), |skew| {
+                        format!(
+                            "{}{}",
+                            if skew < 0 { '-' } else { '+' },
+                            format_latency(skew.unsigned_abs())
+                        )
+                    })
+                );
+            }
+        }
+    }
+
+    let run = ConductedRun {
+        scheduled_start,
+        agents,
+    };
+    if let Some(path) = &args.output_file {
+        std::fs::write(path, serde_json::to_string_pretty(&run)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("Results written to {}", path.display());
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} agents failed", failed, run.agents.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = Message::ProbeReply {
+            sent_ns: 1,
+            agent_ns: 2,
+        };
+        let line = serde_json::to_string(&message).unwrap();
+        assert_eq!(line, r#"{"type":"probe_reply","sent_ns":1,"agent_ns":2}"#);
+        assert_eq!(serde_json::from_str::<Message>(&line).unwrap(), message);
+        assert_eq!(
+            serde_json::from_str::<Message>(r#"{"type":"ready"}"#).unwrap(),
+            Message::Ready
+        );
+    }
+
+    #[test]
+    fn test_estimate_offset_uses_fastest_probe() {
+        let samples = [
+            // Slow round trip, queued on the way back
+            ProbeSample {
+                sent_ns: 1_000,
+                agent_ns: 6_000,
+                received_ns: 9_000,
+            },
+            ProbeSample {
+                sent_ns: 10_000,
+                agent_ns: 15_100,
+                received_ns: 10_200,
+            },
+        ];
+        let offset = estimate_offset(&samples).unwrap();
+        assert_eq!(offset.rtt_ns, 200);
+        assert_eq!(offset.offset_ns, 5_000);
+        assert_eq!(estimate_offset(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_agent() {
+        let shared = vec!["-m".to_string(), "tcp".to_string()];
+        assert_eq!(
+            parse_agent("host-a:7171", &shared),
+            ("host-a:7171".to_string(), shared.clone())
+        );
+        assert_eq!(
+            parse_agent("host-b:7171=--server  -m tcp", &shared),
+            (
+                "host-b:7171".to_string(),
+                vec!["--server".to_string(), "-m".to_string(), "tcp".to_string()]
+            )
+        );
+    }
+
+    /// Serve one conductor with `token` on a loopback port, returning its
+    /// address.
+    fn spawn_agent(token: &'static [u8]) -> (String, std::thread::JoinHandle<Result<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let agent = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, token, Duration::from_millis(200))
+        });
+        (address, agent)
+    }
+
+    #[test]
+    fn test_agent_rejects_invalid_arguments() {
+        let (address, agent) = spawn_agent(b"secret");
+        let error = connect(&address, b"secret", vec!["--no-such-option".to_string()], 3)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("rejected the arguments"));
+        agent.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_agent_refuses_a_wrong_token() {
+        let (address, agent) = spawn_agent(b"secret");
+        let error = connect(
+            &address,
+            b"guess",
+            vec!["-m".to_string(), "tcp".to_string()],
+            3,
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("wrong token"));
+        assert!(agent.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_agent_drops_a_silent_or_flooding_peer() {
+        // Half a line, then nothing: the read times out
+        let (address, agent) = spawn_agent(b"secret");
+        let mut peer = TcpStream::connect(&address).unwrap();
+        peer.write_all(br#"{"type":"hel"#).unwrap();
+        let error = agent.join().unwrap().unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("within the timeout"),
+            "{:#}",
+            error
+        );
+
+        // An endless line is cut off before it is buffered
+        let (address, agent) = spawn_agent(b"secret");
+        let mut peer = TcpStream::connect(&address).unwrap();
+        let flood = vec![b'x'; MAX_UNAUTHENTICATED_LINE as usize * 2];
+        let _ = peer.write_all(&flood);
+        let error = agent.join().unwrap().unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("longer than"),
+            "{:#}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_proof() {
+        let proof = prove(b"secret", "abcd");
+        assert_eq!(proof.len(), 64);
+        assert!(verify_proof(b"secret", "abcd", &proof));
+        assert!(!verify_proof(b"secret", "abce", &proof));
+        assert!(!verify_proof(b"other", "abcd", &proof));
+        assert!(!verify_proof(b"secret", "abcd", "not hex"));
+    }
+
+    #[test]
+    fn test_agent_suite_accepts_only_allowed_options() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let suite = agent_suite(&args(&["-m", "tcp", "--round-trip"])).unwrap();
+        assert_eq!(
+            suite,
+            vec![
+                OsString::from("-m"),
+                OsString::from("tcp"),
+                OsString::from("--round-trip")
+            ]
+        );
+        for refused in [
+            &["-o", "out.json"][..],
+            &["--server-remote", "host", "--remote-binary", "/bin/sh"],
+            &["--log-file", "/tmp/x"],
+        ] {
+            let error = agent_suite(&args(refused)).unwrap_err().to_string();
+            assert!(error.contains("not accepted from a conductor"), "{}", error);
+        }
+        assert!(agent_suite(&args(&["analyze", "x.csv"])).is_err());
+    }
+
+    #[test]
+    fn test_conductor_expands_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("suite.json");
+        std::fs::write(&config, r#"{"round-trip": true}"#).unwrap();
+        let suite = conductor_suite(&[
+            "--config".to_string(),
+            config.display().to_string(),
+            "-m".to_string(),
+            "tcp".to_string(),
+        ])
+        .unwrap();
+        assert!(suite.contains(&"--round-trip".to_string()));
+        assert!(!suite.iter().any(|arg| arg.contains("config")));
+        assert!(agent_suite(&suite).is_ok());
+    }
+}
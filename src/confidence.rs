@@ -0,0 +1,243 @@
+//! Data-quality indicators attached to each latency distribution.
+//!
+//! A percentile is only as good as the samples behind it: a P99.9 computed
+//! from 10,000 samples rests on the 10 slowest of them, and any one of
+//! those can move it a long way. Latency measured before the system
+//! settled, on a CPU that throttled, or close to the cost of reading the
+//! clock is suspect in the same way. Each [`LatencyMetrics`] carries a
+//! [`Confidence`] that says which of these apply:
+//!
+//! - whether each requested percentile has at least [`MIN_TAIL_SAMPLES`]
+//!   samples beyond it
+//! - whether the median of the first [`CONVERGENCE_WINDOW`] samples is
+//!   within [`CONVERGENCE_TOLERANCE_PERCENT`] of the overall median, that
+//!   is, whether warmup had settled latency before measurement began
+//! - whether CPU throttling was detected during the test
+//! - the cost of one timestamp read (the noise floor), and its ratio to
+//!   the median latency
+//!
+//! [`Confidence::concerns`] lists what makes a distribution untrustworthy,
+//! and is printed as `Confidence:` lines in the console summary.
+//!
+//! [`LatencyMetrics`]: crate::metrics::LatencyMetrics
+
+use crate::ipc::get_monotonic_time_ns;
+use crate::metrics::utils::format_latency;
+use serde::{Deserialize, Serialize};
+
+/// Samples needed beyond a percentile for it to be trusted
+///
+/// With fewer, the percentile is decided by a handful of outliers.
+pub const MIN_TAIL_SAMPLES: u64 = 100;
+
+/// Leading samples compared against the whole distribution to judge
+/// warmup convergence
+pub const CONVERGENCE_WINDOW: usize = 500;
+
+/// Largest difference, in percent, between the median of the leading
+/// samples and the overall median for latency to count as settled
+pub const CONVERGENCE_TOLERANCE_PERCENT: f64 = 25.0;
+
+/// Ratio of the noise floor to the median latency above which the
+/// measurement is dominated by the cost of reading the clock
+pub const NOISE_FLOOR_MAX_RATIO: f64 = 0.1;
+
+/// Timestamp read pairs timed to find the noise floor
+const NOISE_FLOOR_READS: usize = 1001;
+
+/// Whether one percentile rests on enough samples
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PercentileSupport {
+    /// Percentile level (0.0 to 100.0)
+    pub percentile: f64,
+
+    /// Samples slower than this percentile
+    pub tail_samples: u64,
+
+    /// Total samples needed for [`MIN_TAIL_SAMPLES`] beyond this percentile
+    pub required_samples: u64,
+
+    /// Whether `tail_samples` reaches [`MIN_TAIL_SAMPLES`]
+    pub adequate: bool,
+}
+
+/// Data-quality indicators of one latency distribution
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Confidence {
+    /// Sample support of each requested percentile below P100
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub percentiles: Vec<PercentileSupport>,
+
+    /// Whether latency had settled by the time measurement began; absent
+    /// with too few samples to tell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup_converged: Option<bool>,
+
+    /// Difference between the median of the first [`CONVERGENCE_WINDOW`]
+    /// samples and the overall median, in percent of the overall median
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup_drift_percent: Option<f64>,
+
+    /// Whether CPU throttling was detected during the test; absent where
+    /// frequency was not monitored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttled: Option<bool>,
+
+    /// Cost of one timestamp read in nanoseconds, the smallest latency
+    /// difference the measurement can resolve
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise_floor_ns: Option<u64>,
+
+    /// `noise_floor_ns` divided by the median latency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise_floor_ratio: Option<f64>,
+}
+
+impl Confidence {
+    /// Indicators of a distribution of `total_samples` samples with the
+    /// given overall median, where the first [`CONVERGENCE_WINDOW`]
+    /// samples had median `head_median_ns` (`None` if not tracked).
+    ///
+    /// Warmup convergence is only judged with at least four windows of
+    /// samples, so the leading ones are a small part of the whole.
+    pub fn assess(
+        total_samples: usize,
+        percentiles: &[f64],
+        median_ns: f64,
+        head_median_ns: Option<u64>,
+    ) -> Self {
+        let total = total_samples as u64;
+        let percentiles = percentiles
+            .iter()
+            .filter(|&&p| (0.0..100.0).contains(&p))
+            .map(|&percentile| {
+                // The epsilon keeps 10,000 x 0.1% from rounding down to 9
+                let tail_share = (100.0 - percentile) / 100.0;
+                let tail_samples = (total as f64 * tail_share + 1e-6).floor() as u64;
+                PercentileSupport {
+                    percentile,
+                    tail_samples,
+                    required_samples: (MIN_TAIL_SAMPLES as f64 / tail_share - 1e-6).ceil() as u64,
+                    adequate: tail_samples >= MIN_TAIL_SAMPLES,
+                }
+            })
+            .collect();
+        let warmup_drift_percent = head_median_ns
+            .filter(|_| total_samples >= 4 * CONVERGENCE_WINDOW && median_ns > 0.0)
+            .map(|head| (head as f64 - median_ns) * 100.0 / median_ns);
+        Self {
+            percentiles,
+            warmup_converged: warmup_drift_percent
+                .map(|drift| drift.abs() <= CONVERGENCE_TOLERANCE_PERCENT),
+            warmup_drift_percent,
+            ..Self::default()
+        }
+    }
+
+    /// Record the conditions of the test that produced the distribution:
+    /// whether the CPU throttled, and the noise floor against its median.
+    pub fn annotate_host(&mut self, throttled: Option<bool>, noise_floor_ns: u64, median_ns: f64) {
+        self.throttled = throttled;
+        self.noise_floor_ns = Some(noise_floor_ns);
+        self.noise_floor_ratio = (median_ns > 0.0).then(|| noise_floor_ns as f64 / median_ns);
+    }
+
+    /// What makes the distribution untrustworthy; empty if nothing does.
+    pub fn concerns(&self) -> Vec<String> {
+        let mut concerns: Vec<String> = self
+            .percentiles
+            .iter()
+            .filter(|support| !support.adequate)
+            .map(|support| {
+                format!(
+                    "P{} has {} samples beyond it (needs {} samples in total)",
+                    support.percentile, support.tail_samples, support.required_samples
+                )
+            })
+            .collect();
+        if let (Some(false), Some(drift)) = (self.warmup_converged, self.warmup_drift_percent) {
+            concerns.push(format!(
+                "latency had not settled after warmup (first {} samples {:+.1}% from the median)",
+                CONVERGENCE_WINDOW, drift
+            ));
+        }
+        if self.throttled == Some(true) {
+            concerns.push("CPU throttled during the test".to_string());
+        }
+        if let (Some(floor), Some(ratio)) = (self.noise_floor_ns, self.noise_floor_ratio) {
+            if ratio > NOISE_FLOOR_MAX_RATIO {
+                concerns.push(format!(
+                    "median is only {:.1}x the {} timer noise floor",
+                    1.0 / ratio,
+                    format_latency(floor)
+                ));
+            }
+        }
+        concerns
+    }
+}
+
+/// Cost of one timestamp read on this host, in nanoseconds.
+///
+/// The median of the differences between back-to-back reads of the
+/// monotonic clock, which every latency sample pays once.
+pub fn measure_noise_floor() -> u64 {
+    let mut deltas: Vec<u64> = (0..NOISE_FLOOR_READS)
+        .map(|_| {
+            let start = get_monotonic_time_ns();
+            get_monotonic_time_ns().saturating_sub(start)
+        })
+        .collect();
+    deltas.sort_unstable();
+    deltas[deltas.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_support_scales_with_the_tail() {
+        let confidence = Confidence::assess(10_000, &[50.0, 99.0, 99.9, 100.0], 1000.0, None);
+        assert_eq!(confidence.percentiles.len(), 3);
+        let p999 = &confidence.percentiles[2];
+        assert_eq!(p999.tail_samples, 10);
+        assert_eq!(p999.required_samples, 100_000);
+        assert!(!p999.adequate);
+        assert!(confidence.percentiles[..2].iter().all(|s| s.adequate));
+        // Too few samples to judge warmup
+        assert_eq!(confidence.warmup_converged, None);
+
+        let concerns = confidence.concerns();
+        assert_eq!(concerns.len(), 1);
+        assert!(concerns[0].starts_with("P99.9 has 10 samples beyond it"));
+    }
+
+    #[test]
+    fn test_warmup_throttling_and_noise_floor() {
+        let mut settled = Confidence::assess(10_000, &[], 1000.0, Some(1100));
+        assert_eq!(settled.warmup_converged, Some(true));
+        settled.annotate_host(Some(false), 20, 1000.0);
+        assert_eq!(settled.noise_floor_ratio, Some(0.02));
+        assert!(settled.concerns().is_empty());
+
+        let mut unsettled = Confidence::assess(10_000, &[], 1000.0, Some(2000));
+        assert_eq!(unsettled.warmup_converged, Some(false));
+        assert_eq!(unsettled.warmup_drift_percent, Some(100.0));
+        unsettled.annotate_host(Some(true), 500, 1000.0);
+        let concerns = unsettled.concerns();
+        assert_eq!(concerns.len(), 3);
+        assert_eq!(
+            concerns[2],
+            format!(
+                "median is only 2.0x the {} timer noise floor",
+                format_latency(500)
+            )
+        );
+    }
+
+    #[test]
+    fn test_noise_floor_is_small() {
+        assert!(measure_noise_floor() < 1_000_000);
+    }
+}
@@ -0,0 +1,269 @@
+//! CPU frequency and thermal throttling checks around each test.
+//!
+//! A laptop or a poorly cooled server can lose a large part of its clock
+//! speed partway through a run, and the latency numbers silently degrade
+//! with it. A [`FrequencyMonitor`] reads the current frequency of every CPU
+//! (`scaling_cur_freq` in cpufreq sysfs) and the thermal throttle counters
+//! before a test, optionally at an interval during it, and after it. The
+//! resulting [`FrequencyReport`] flags the test when the sustained frequency
+//! fell more than a threshold below the starting frequency, or when the
+//! throttle counters moved.
+//!
+//! Hosts without cpufreq in sysfs (non-Linux, many VMs and containers)
+//! produce no report.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+const CPU_SYSFS: &str = "/sys/devices/system/cpu";
+
+/// CPU frequency behavior over one test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyReport {
+    /// Mean frequency across CPUs before the test, in MHz
+    pub start_mhz: f64,
+
+    /// Mean frequency across CPUs after the test, in MHz
+    pub end_mhz: f64,
+
+    /// Lowest mean frequency seen during or after the test, in MHz
+    pub min_mhz: f64,
+
+    /// Average of the samples taken during and after the test, in MHz
+    pub sustained_mhz: f64,
+
+    /// Samples taken during and after the test
+    pub samples: usize,
+
+    /// How far the sustained frequency fell below the start, in percent
+    pub drop_percent: f64,
+
+    /// Thermal throttle events counted during the test, where the CPU
+    /// exposes `thermal_throttle` counters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle_events: Option<u64>,
+
+    /// Whether the drop exceeded the threshold or throttling was counted
+    pub throttled: bool,
+}
+
+impl std::fmt::Display for FrequencyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0} -> {:.0} MHz sustained ({:+.1}%)",
+            self.start_mhz, self.sustained_mhz, -self.drop_percent
+        )?;
+        if let Some(events) = self.throttle_events.filter(|&events| events > 0) {
+            write!(f, ", {} throttle events", events)?;
+        }
+        if self.throttled {
+            write!(f, " [THROTTLED]")?;
+        }
+        Ok(())
+    }
+}
+
+/// One reading of all CPUs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CpuSample {
+    mean_khz: f64,
+    throttle_count: Option<u64>,
+}
+
+/// Read the current frequency and throttle counters of every CPU under
+/// `root`, returning `None` if no CPU reports a frequency.
+fn read_sample(root: &Path) -> Option<CpuSample> {
+    let read =
+        |path: &Path| -> Option<u64> { std::fs::read_to_string(path).ok()?.trim().parse().ok() };
+
+    let mut khz_total = 0u64;
+    let mut cpus = 0u64;
+    let mut throttle_count = None;
+    for entry in std::fs::read_dir(root).ok()?.flatten() {
+        let name = entry.file_name();
+        let is_cpu = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("cpu"))
+            .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()));
+        if !is_cpu {
+            continue;
+        }
+        let dir = entry.path();
+        if let Some(khz) = read(&dir.join("cpufreq/scaling_cur_freq")) {
+            khz_total += khz;
+            cpus += 1;
+        }
+        for counter in ["core_throttle_count", "package_throttle_count"] {
+            if let Some(count) = read(&dir.join("thermal_throttle").join(counter)) {
+                *throttle_count.get_or_insert(0) += count;
+            }
+        }
+    }
+
+    (cpus > 0).then(|| CpuSample {
+        mean_khz: khz_total as f64 / cpus as f64,
+        throttle_count,
+    })
+}
+
+/// Build the report from the starting sample, the mean frequencies taken
+/// during the test and the final sample.
+fn summarize(
+    start: CpuSample,
+    during_khz: &[f64],
+    end: CpuSample,
+    threshold_percent: f64,
+) -> FrequencyReport {
+    let after: Vec<f64> = during_khz
+        .iter()
+        .copied()
+        .chain(std::iter::once(end.mean_khz))
+        .collect();
+    let sustained_khz = after.iter().sum::<f64>() / after.len() as f64;
+    let min_khz = after.iter().copied().fold(f64::INFINITY, f64::min);
+    let drop_percent = if start.mean_khz > 0.0 {
+        ((start.mean_khz - sustained_khz) / start.mean_khz * 100.0).max(0.0)
+    } else {
+        0.0
+    };
+    let throttle_events = match (start.throttle_count, end.throttle_count) {
+        (Some(before), Some(after)) => Some(after.saturating_sub(before)),
+        _ => None,
+    };
+
+    FrequencyReport {
+        start_mhz: start.mean_khz / 1000.0,
+        end_mhz: end.mean_khz / 1000.0,
+        min_mhz: min_khz / 1000.0,
+        sustained_mhz: sustained_khz / 1000.0,
+        samples: after.len(),
+        drop_percent,
+        throttle_events,
+        throttled: drop_percent > threshold_percent || throttle_events.unwrap_or(0) > 0,
+    }
+}
+
+/// Samples CPU frequency from the start of a test until [`finish`](Self::finish).
+pub struct FrequencyMonitor {
+    start: CpuSample,
+    during: Arc<Mutex<Vec<f64>>>,
+    sampler: Option<JoinHandle<()>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl FrequencyMonitor {
+    /// Take the starting sample and, with an `interval`, begin sampling in
+    /// the background. Returns `None` if this host does not expose CPU
+    /// frequencies.
+    pub fn start(interval: Option<Duration>) -> Option<Self> {
+        let start = read_sample(Path::new(CPU_SYSFS))?;
+        let during = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let sampler = interval.map(|interval| {
+            let during = Arc::clone(&during);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || loop {
+                std::thread::park_timeout(interval);
+                if stop.load(Ordering::Acquire) {
+                    break;
+                }
+                if let Some(sample) = read_sample(Path::new(CPU_SYSFS)) {
+                    during.lock().unwrap().push(sample.mean_khz);
+                }
+            })
+        });
+
+        Some(Self {
+            start,
+            during,
+            sampler,
+            stop,
+        })
+    }
+
+    /// Stop sampling, take the final sample and report whether the sustained
+    /// frequency dropped more than `threshold_percent` below the start.
+    pub fn finish(mut self, threshold_percent: f64) -> Option<FrequencyReport> {
+        self.stop.store(true, Ordering::Release);
+        if let Some(sampler) = self.sampler.take() {
+            sampler.thread().unpark();
+            let _ = sampler.join();
+        }
+        let end = read_sample(Path::new(CPU_SYSFS))?;
+        let during = self.during.lock().unwrap();
+        Some(summarize(self.start, &during, end, threshold_percent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(mhz: f64, throttle_count: Option<u64>) -> CpuSample {
+        CpuSample {
+            mean_khz: mhz * 1000.0,
+            throttle_count,
+        }
+    }
+
+    #[test]
+    fn test_summarize_flags_sustained_drop() {
+        let report = summarize(
+            sample(3000.0, Some(4)),
+            &[2_400_000.0, 2_100_000.0],
+            sample(2100.0, Some(4)),
+            10.0,
+        );
+        assert_eq!(report.start_mhz, 3000.0);
+        assert_eq!(report.end_mhz, 2100.0);
+        assert_eq!(report.min_mhz, 2100.0);
+        assert_eq!(report.sustained_mhz, 2200.0);
+        assert_eq!(report.samples, 3);
+        assert!((report.drop_percent - 26.666).abs() < 0.01);
+        assert_eq!(report.throttle_events, Some(0));
+        assert!(report.throttled);
+        assert_eq!(
+            report.to_string(),
+            "3000 -> 2200 MHz sustained (-26.7%) [THROTTLED]"
+        );
+    }
+
+    #[test]
+    fn test_summarize_steady_and_throttle_counters() {
+        let steady = summarize(sample(3000.0, None), &[], sample(2900.0, None), 10.0);
+        assert!(!steady.throttled);
+        assert_eq!(steady.throttle_events, None);
+
+        // A counted throttle event flags the test even without a large drop
+        let counted = summarize(sample(3000.0, Some(1)), &[], sample(3000.0, Some(3)), 10.0);
+        assert_eq!(counted.drop_percent, 0.0);
+        assert_eq!(counted.throttle_events, Some(2));
+        assert!(counted.throttled);
+    }
+
+    #[test]
+    fn test_read_sample() {
+        let root = tempfile::tempdir().unwrap();
+        for (cpu, khz, throttles) in [("cpu0", "3000000", "2"), ("cpu1", "2000000", "1")] {
+            let dir = root.path().join(cpu);
+            std::fs::create_dir_all(dir.join("cpufreq")).unwrap();
+            std::fs::create_dir_all(dir.join("thermal_throttle")).unwrap();
+            std::fs::write(dir.join("cpufreq/scaling_cur_freq"), khz).unwrap();
+            std::fs::write(dir.join("thermal_throttle/core_throttle_count"), throttles).unwrap();
+        }
+        // Non-CPU entries such as cpufreq/ and cpuidle/ are ignored
+        std::fs::create_dir_all(root.path().join("cpufreq")).unwrap();
+
+        let sample = read_sample(root.path()).unwrap();
+        assert_eq!(sample.mean_khz, 2_500_000.0);
+        assert_eq!(sample.throttle_count, Some(3));
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(read_sample(empty.path()).is_none());
+    }
+}
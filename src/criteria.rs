@@ -0,0 +1,480 @@
+//! Pass/fail criteria for benchmark scenarios.
+//!
+//! A criteria file, given with `--criteria FILE`, lists scenarios with the
+//! bounds their results must meet. Each scenario selects tests by mechanism,
+//! message size and latency type (any omitted field matches every test) and
+//! bounds P99 latency, throughput, or both:
+//!
+//! ```json
+//! {
+//!   "scenarios": [
+//!     { "name": "uds small", "mechanism": "uds", "message_size": 64,
+//!       "latency_type": "RoundTrip", "max_p99_ns": 50000,
+//!       "min_throughput_msgs_per_sec": 20000 },
+//!     { "name": "tcp", "mechanism": "tcp", "max_p99_ns": 200000 }
+//!   ]
+//! }
+//! ```
+//!
+//! The criteria are evaluated once every mechanism has run. The consolidated
+//! [`CriteriaReport`] is written to the final JSON results under `criteria`
+//! and printed with the summary, and the run exits with an error if any
+//! check failed.
+
+use crate::mechanism::IpcMechanism;
+use crate::metrics::{utils::format_latency, LatencyType, PerformanceMetrics};
+use crate::results::BenchmarkResults;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
+
+/// Scenarios and bounds read from a criteria file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CriteriaPlan {
+    /// Scenarios whose tests are checked
+    pub scenarios: Vec<Scenario>,
+}
+
+/// Expected bounds for the tests a scenario selects
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Scenario {
+    /// Name identifying the scenario in the report
+    pub name: String,
+
+    /// Mechanism of the tests, by its command-line name (`uds`, `tcp`, ...)
+    #[serde(default, deserialize_with = "deserialize_mechanism")]
+    pub mechanism: Option<IpcMechanism>,
+
+    /// Message size of the tests in bytes
+    #[serde(default)]
+    pub message_size: Option<usize>,
+
+    /// Latency measurement the bounds apply to (`OneWay` or `RoundTrip`)
+    #[serde(default)]
+    pub latency_type: Option<LatencyType>,
+
+    /// Highest acceptable P99 latency in nanoseconds
+    #[serde(default)]
+    pub max_p99_ns: Option<u64>,
+
+    /// Lowest acceptable throughput in messages per second
+    #[serde(default)]
+    pub min_throughput_msgs_per_sec: Option<f64>,
+}
+
+/// Accept a mechanism by its command-line name or its JSON results name.
+fn deserialize_mechanism<'de, D>(deserializer: D) -> Result<Option<IpcMechanism>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(name) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    if let Some(mechanism) = IpcMechanism::from_name(&name) {
+        return Ok(Some(mechanism));
+    }
+    serde_json::from_value(serde_json::Value::String(name.clone()))
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("unknown mechanism '{}'", name)))
+}
+
+/// Bound a criterion check compares against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bound {
+    /// P99 latency at most the limit, in nanoseconds
+    MaxP99Latency,
+    /// Throughput at least the limit, in messages per second
+    MinThroughput,
+}
+
+/// One bound checked against one test's measurement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionCheck {
+    /// Scenario the bound came from
+    pub scenario: String,
+
+    /// Mechanism of the checked test
+    pub mechanism: IpcMechanism,
+
+    /// Message size of the checked test in bytes
+    pub message_size: usize,
+
+    /// Latency measurement that was checked
+    pub latency_type: LatencyType,
+
+    /// Which bound was checked
+    pub bound: Bound,
+
+    /// The bound's limit
+    pub limit: f64,
+
+    /// Measured value (`None` if the test failed or did not measure it)
+    pub actual: Option<f64>,
+
+    /// Whether the measurement met the bound
+    pub passed: bool,
+}
+
+impl CriterionCheck {
+    fn new(
+        scenario: &Scenario,
+        result: &BenchmarkResults,
+        latency_type: LatencyType,
+        bound: Bound,
+        limit: f64,
+        actual: Option<f64>,
+    ) -> Self {
+        let passed = actual.is_some_and(|actual| match bound {
+            Bound::MaxP99Latency => actual <= limit,
+            Bound::MinThroughput => actual >= limit,
+        });
+        Self {
+            scenario: scenario.name.clone(),
+            mechanism: result.mechanism,
+            message_size: result.test_config.message_size,
+            latency_type,
+            bound,
+            limit,
+            actual,
+            passed,
+        }
+    }
+}
+
+impl std::fmt::Display for CriterionCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.latency_type {
+            LatencyType::OneWay => "one-way",
+            LatencyType::RoundTrip => "round-trip",
+            LatencyType::InterArrival => "inter-arrival",
+        };
+        let not_measured = || "N/A".to_string();
+        let measured = match self.bound {
+            Bound::MaxP99Latency => format!(
+                "P99 {} <= {}",
+                self.actual
+                    .map_or_else(not_measured, |ns| format_latency(ns as u64)),
+                format_latency(self.limit as u64)
+            ),
+            Bound::MinThroughput => format!(
+                "throughput {} >= {:.0} msg/s",
+                self.actual
+                    .map_or_else(not_measured, |rate| format!("{:.0}", rate)),
+                self.limit
+            ),
+        };
+        write!(
+            f,
+            "{}: {} {}B {} {} {}",
+            self.scenario,
+            self.mechanism,
+            self.message_size,
+            name,
+            measured,
+            if self.passed { "PASS" } else { "FAIL" }
+        )
+    }
+}
+
+/// Outcome of every scenario's checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriteriaReport {
+    /// Whether every check passed and every scenario matched a test
+    pub passed: bool,
+
+    /// Each bound checked against each matching test
+    pub checks: Vec<CriterionCheck>,
+
+    /// Scenarios that matched no test, which count as failures
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmatched_scenarios: Vec<String>,
+}
+
+impl CriteriaReport {
+    /// Number of failed checks and unmatched scenarios
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| !check.passed).count() + self.unmatched_scenarios.len()
+    }
+}
+
+impl std::fmt::Display for CriteriaReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "  {}", check)?;
+        }
+        for name in &self.unmatched_scenarios {
+            writeln!(f, "  {}: matched no test FAIL", name)?;
+        }
+        let total = self.checks.len() + self.unmatched_scenarios.len();
+        if self.passed {
+            writeln!(f, "  Result: PASSED ({} checks)", total)
+        } else {
+            writeln!(
+                f,
+                "  Result: FAILED ({} of {} checks)",
+                self.failures(),
+                total
+            )
+        }
+    }
+}
+
+impl CriteriaPlan {
+    /// Read a criteria file, rejecting scenarios without any bound.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read criteria file {}", path.display()))?;
+        let plan: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid criteria file {}", path.display()))?;
+        for scenario in &plan.scenarios {
+            if scenario.max_p99_ns.is_none() && scenario.min_throughput_msgs_per_sec.is_none() {
+                bail!(
+                    "Criteria scenario '{}' sets neither max_p99_ns nor min_throughput_msgs_per_sec",
+                    scenario.name
+                );
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Check every scenario against the tests it selects.
+    ///
+    /// Failed tests are still selected; their checks fail with no measured
+    /// value.
+    pub fn evaluate(&self, results: &[BenchmarkResults]) -> CriteriaReport {
+        let mut checks = Vec::new();
+        let mut unmatched_scenarios = Vec::new();
+
+        for scenario in &self.scenarios {
+            let before = checks.len();
+            let selected = results.iter().filter(|result| {
+                scenario.mechanism.map_or(true, |m| m == result.mechanism)
+                    && scenario
+                        .message_size
+                        .map_or(true, |size| size == result.test_config.message_size)
+            });
+            for result in selected {
+                let tests = [
+                    (
+                        LatencyType::OneWay,
+                        result.test_config.one_way_enabled,
+                        &result.one_way_results,
+                    ),
+                    (
+                        LatencyType::RoundTrip,
+                        result.test_config.round_trip_enabled,
+                        &result.round_trip_results,
+                    ),
+                ];
+                for (latency_type, enabled, metrics) in tests {
+                    if !enabled || scenario.latency_type.is_some_and(|t| t != latency_type) {
+                        continue;
+                    }
+                    checks.extend(Self::check(scenario, result, latency_type, metrics));
+                }
+            }
+            if checks.len() == before {
+                unmatched_scenarios.push(scenario.name.clone());
+            }
+        }
+
+        CriteriaReport {
+            passed: unmatched_scenarios.is_empty() && checks.iter().all(|check| check.passed),
+            checks,
+            unmatched_scenarios,
+        }
+    }
+
+    /// The checks of one scenario's bounds against one measurement.
+    fn check(
+        scenario: &Scenario,
+        result: &BenchmarkResults,
+        latency_type: LatencyType,
+        metrics: &Option<PerformanceMetrics>,
+    ) -> Vec<CriterionCheck> {
+        let mut checks = Vec::new();
+        if let Some(limit) = scenario.max_p99_ns {
+            let p99 = metrics
+                .as_ref()
+                .and_then(|metrics| metrics.latency.as_ref())
+                .and_then(|latency| {
+                    latency
+                        .percentiles
+                        .iter()
+                        .find(|p| (p.percentile - 99.0).abs() < 0.1)
+                })
+                .map(|p| p.value_ns as f64);
+            checks.push(CriterionCheck::new(
+                scenario,
+                result,
+                latency_type,
+                Bound::MaxP99Latency,
+                limit as f64,
+                p99,
+            ));
+        }
+        if let Some(limit) = scenario.min_throughput_msgs_per_sec {
+            let rate = metrics
+                .as_ref()
+                .filter(|metrics| metrics.throughput.total_messages > 0)
+                .map(|metrics| metrics.throughput.messages_per_second);
+            checks.push(CriterionCheck::new(
+                scenario,
+                result,
+                latency_type,
+                Bound::MinThroughput,
+                limit,
+                rate,
+            ));
+        }
+        checks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{LatencyCollector, ThroughputMetrics};
+    use std::io::Write;
+    use std::time::Duration;
+    use tempfile::NamedTempFile;
+
+    fn round_trip_result(mechanism: IpcMechanism, latency_ns: u64) -> BenchmarkResults {
+        let mut collector = LatencyCollector::new(LatencyType::RoundTrip).unwrap();
+        for _ in 0..10 {
+            collector.record(Duration::from_nanos(latency_ns)).unwrap();
+        }
+        let mut result =
+            BenchmarkResults::new(mechanism, 64, 1024, 1, Some(10), None, 0, false, true);
+        result.add_round_trip_results(PerformanceMetrics {
+            latency: Some(collector.get_metrics(&[50.0, 99.0])),
+            throughput: ThroughputMetrics {
+                messages_per_second: 1000.0,
+                bytes_per_second: 64000.0,
+                total_messages: 10,
+                total_bytes: 640,
+                duration_ns: 10_000_000,
+            },
+            error_count: 0,
+            expired_count: 0,
+            priority_latency: Vec::new(),
+            phase_latency: Vec::new(),
+            connection_latency: Vec::new(),
+            processing_latency: Vec::new(),
+            cpu_split: Vec::new(),
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_message: None,
+            first_latency_ns: None,
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            latency_reservoir: None,
+            goodput: None,
+            sla: Vec::new(),
+            timestamp: chrono::Utc::now(),
+        });
+        result
+    }
+
+    fn load(json: &str) -> Result<CriteriaPlan> {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(json.as_bytes()).unwrap();
+        CriteriaPlan::load(file.path())
+    }
+
+    #[test]
+    fn test_load_criteria() {
+        let plan = load(
+            r#"{"scenarios": [
+                {"name": "tcp", "mechanism": "tcp", "max_p99_ns": 5000},
+                {"name": "shm", "mechanism": "SharedMemory", "latency_type": "RoundTrip",
+                 "min_throughput_msgs_per_sec": 100}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(plan.scenarios[0].mechanism, Some(IpcMechanism::TcpSocket));
+        assert_eq!(
+            plan.scenarios[1].mechanism,
+            Some(IpcMechanism::SharedMemory)
+        );
+        assert_eq!(plan.scenarios[1].latency_type, Some(LatencyType::RoundTrip));
+
+        assert!(load(
+            r#"{"scenarios": [{"name": "x", "mechanism": "carrier-pigeon", "max_p99_ns": 1}]}"#
+        )
+        .is_err());
+        let err = load(r#"{"scenarios": [{"name": "no bounds", "mechanism": "tcp"}]}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no bounds"), "{}", err);
+    }
+
+    #[test]
+    fn test_evaluate_criteria() {
+        let results = [
+            round_trip_result(IpcMechanism::TcpSocket, 2_000),
+            round_trip_result(IpcMechanism::SharedMemory, 20_000),
+        ];
+        let plan = CriteriaPlan {
+            scenarios: vec![
+                Scenario {
+                    name: "all".to_string(),
+                    max_p99_ns: Some(10_000),
+                    min_throughput_msgs_per_sec: Some(500.0),
+                    ..Default::default()
+                },
+                Scenario {
+                    name: "one-way".to_string(),
+                    latency_type: Some(LatencyType::OneWay),
+                    max_p99_ns: Some(10_000),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let report = plan.evaluate(&results);
+        assert!(!report.passed);
+        assert_eq!(report.checks.len(), 4);
+        let failed: Vec<(IpcMechanism, Bound)> = report
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| (check.mechanism, check.bound))
+            .collect();
+        assert_eq!(failed, [(IpcMechanism::SharedMemory, Bound::MaxP99Latency)]);
+        assert_eq!(report.unmatched_scenarios, ["one-way"]);
+        assert_eq!(report.failures(), 2);
+        assert_eq!(
+            report.checks[0].to_string(),
+            "all: TCP Socket 64B round-trip P99 2.00μs <= 10.00μs PASS"
+        );
+        assert_eq!(
+            report.checks[1].to_string(),
+            "all: TCP Socket 64B round-trip throughput 1000 >= 500 msg/s PASS"
+        );
+        assert!(report
+            .to_string()
+            .ends_with("  Result: FAILED (2 of 5 checks)\n"));
+
+        let tcp_only = CriteriaPlan {
+            scenarios: vec![Scenario {
+                mechanism: Some(IpcMechanism::TcpSocket),
+                ..plan.scenarios[0].clone()
+            }],
+        };
+        assert!(tcp_only.evaluate(&results).passed);
+
+        // A failed test has nothing to measure and fails its checks
+        let mut failed = round_trip_result(IpcMechanism::TcpSocket, 2_000);
+        failed.set_failure("server did not start".to_string());
+        failed.round_trip_results = None;
+        let report = tcp_only.evaluate(&[failed]);
+        assert!(!report.passed);
+        assert!(report.checks.iter().all(|check| check.actual.is_none()));
+    }
+}
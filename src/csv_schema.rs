@@ -0,0 +1,372 @@
+//! Column schema of the streaming CSV output.
+//!
+//! The per-message CSV written by `--streaming-output-csv` has a documented
+//! set of columns, each with a fixed name, type and meaning. `--csv-columns`
+//! picks which of them are written and in what order, so downstream ETL can
+//! ask for exactly the columns it loads and keep working as columns are
+//! added. Next to the CSV, a `<file>.schema.json` records the schema
+//! version and the columns the file holds.
+//!
+//! Without `--csv-columns` the file holds [`CsvColumn::DEFAULT`], the
+//! columns it has always had.
+
+use crate::results::MessageLatencyRecord;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Version of the column schema; bumped when a column changes meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A column of the streaming CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvColumn {
+    TimestampNs,
+    MessageId,
+    Mechanism,
+    MessageSize,
+    OneWayLatencyNs,
+    RoundTripLatencyNs,
+    RunId,
+    WorkerId,
+    CpuCore,
+    Phase,
+}
+
+impl CsvColumn {
+    /// Every column, in schema order
+    pub const ALL: &'static [CsvColumn] = &[
+        CsvColumn::TimestampNs,
+        CsvColumn::MessageId,
+        CsvColumn::Mechanism,
+        CsvColumn::MessageSize,
+        CsvColumn::OneWayLatencyNs,
+        CsvColumn::RoundTripLatencyNs,
+        CsvColumn::RunId,
+        CsvColumn::WorkerId,
+        CsvColumn::CpuCore,
+        CsvColumn::Phase,
+    ];
+
+    /// Columns written without `--csv-columns`, matching
+    /// [`MessageLatencyRecord::HEADINGS`]
+    pub const DEFAULT: &'static [CsvColumn] = &[
+        CsvColumn::TimestampNs,
+        CsvColumn::MessageId,
+        CsvColumn::Mechanism,
+        CsvColumn::MessageSize,
+        CsvColumn::OneWayLatencyNs,
+        CsvColumn::RoundTripLatencyNs,
+        CsvColumn::RunId,
+    ];
+
+    /// Heading of the column
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TimestampNs => "timestamp_ns",
+            Self::MessageId => "message_id",
+            Self::Mechanism => "mechanism",
+            Self::MessageSize => "message_size",
+            Self::OneWayLatencyNs => "one_way_latency_ns",
+            Self::RoundTripLatencyNs => "round_trip_latency_ns",
+            Self::RunId => "run_id",
+            Self::WorkerId => "worker_id",
+            Self::CpuCore => "cpu_core",
+            Self::Phase => "phase",
+        }
+    }
+
+    /// Type of the values: "u64", "string" or "enum"
+    pub fn kind(self) -> &'static str {
+        match self {
+            Self::Mechanism | Self::Phase => "enum",
+            Self::RunId => "string",
+            _ => "u64",
+        }
+    }
+
+    /// What the column holds
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::TimestampNs => "Unix time the message was sent, in nanoseconds",
+            Self::MessageId => "Identifier of the message within its test",
+            Self::Mechanism => "IPC mechanism under test",
+            Self::MessageSize => "Payload size in bytes",
+            Self::OneWayLatencyNs => "One-way latency in nanoseconds; empty if not measured",
+            Self::RoundTripLatencyNs => "Round-trip latency in nanoseconds; empty if not measured",
+            Self::RunId => "Identifier of the run, shared with logs and results",
+            Self::WorkerId => "Client worker that measured the message, from 0",
+            Self::CpuCore => {
+                "CPU the client was running on when it recorded the message; empty if unknown"
+            }
+            Self::Phase => "Test phase the message belongs to: warmup or measurement",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|column| column.name() == name)
+    }
+}
+
+/// Columns selected with `--csv-columns`, in order
+pub type CsvColumns = Vec<CsvColumn>;
+
+/// Parse `--csv-columns`: column names separated by commas, where
+/// `default` stands for [`CsvColumn::DEFAULT`] and `all` for
+/// [`CsvColumn::ALL`]. A column named twice is written once.
+pub fn parse_columns(s: &str) -> Result<CsvColumns, String> {
+    let mut columns: Vec<CsvColumn> = Vec::new();
+    for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let named: &[CsvColumn] = match name {
+            "default" => CsvColumn::DEFAULT,
+            "all" => CsvColumn::ALL,
+            _ => &[CsvColumn::from_name(name).ok_or_else(|| {
+                format!(
+                    "unknown CSV column '{}'; expected default, all or one of: {}",
+                    name,
+                    CsvColumn::ALL
+                        .iter()
+                        .map(|column| column.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?],
+        };
+        for column in named {
+            if !columns.contains(column) {
+                columns.push(*column);
+            }
+        }
+    }
+    if columns.is_empty() {
+        return Err("--csv-columns needs at least one column".to_string());
+    }
+    Ok(columns)
+}
+
+/// Phase of a test a streamed message belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamPhase {
+    Warmup,
+    #[default]
+    Measurement,
+}
+
+impl std::fmt::Display for StreamPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warmup => write!(f, "warmup"),
+            Self::Measurement => write!(f, "measurement"),
+        }
+    }
+}
+
+/// Columns of one streaming CSV file and the context of its rows
+#[derive(Debug, Clone)]
+pub struct CsvLayout {
+    columns: Vec<CsvColumn>,
+
+    /// Client worker whose messages are being streamed. Only
+    /// single-worker tests stream records, so this stays 0.
+    pub worker_id: usize,
+
+    /// Phase of the messages being streamed
+    pub phase: StreamPhase,
+}
+
+impl Default for CsvLayout {
+    fn default() -> Self {
+        Self::new(CsvColumn::DEFAULT.to_vec())
+    }
+}
+
+/// Machine-readable description of a CSV file's columns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvSchema {
+    /// [`SCHEMA_VERSION`] the file was written with
+    pub schema_version: u32,
+
+    /// The file's columns, in order
+    pub columns: Vec<CsvSchemaColumn>,
+}
+
+/// One column of a [`CsvSchema`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvSchemaColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub description: String,
+}
+
+impl CsvLayout {
+    pub fn new(columns: Vec<CsvColumn>) -> Self {
+        Self {
+            columns,
+            worker_id: 0,
+            phase: StreamPhase::default(),
+        }
+    }
+
+    /// The columns written
+    pub fn columns(&self) -> &[CsvColumn] {
+        &self.columns
+    }
+
+    /// Header line, without the newline
+    pub fn header(&self) -> String {
+        self.columns
+            .iter()
+            .map(|column| column.name())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// One row for `record`, without the newline
+    pub fn row(&self, record: &MessageLatencyRecord) -> String {
+        if self.columns == CsvColumn::DEFAULT {
+            return record.to_csv_record();
+        }
+        let mut s = String::with_capacity(256);
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            // Writing to a String can't fail
+            let _ = match column {
+                CsvColumn::TimestampNs => write!(s, "{}", record.timestamp_ns),
+                CsvColumn::MessageId => write!(s, "{}", record.message_id),
+                CsvColumn::Mechanism => write!(s, "{}", record.mechanism),
+                CsvColumn::MessageSize => write!(s, "{}", record.message_size),
+                CsvColumn::OneWayLatencyNs => match record.one_way_latency_ns {
+                    Some(latency) => write!(s, "{}", latency),
+                    None => Ok(()),
+                },
+                CsvColumn::RoundTripLatencyNs => match record.round_trip_latency_ns {
+                    Some(latency) => write!(s, "{}", latency),
+                    None => Ok(()),
+                },
+                CsvColumn::RunId => write!(s, "{}", crate::run_id::get()),
+                CsvColumn::WorkerId => write!(s, "{}", self.worker_id),
+                CsvColumn::CpuCore => match current_cpu() {
+                    Some(cpu) => write!(s, "{}", cpu),
+                    None => Ok(()),
+                },
+                CsvColumn::Phase => write!(s, "{}", self.phase),
+            };
+        }
+        s
+    }
+
+    /// Schema of the file
+    pub fn schema(&self) -> CsvSchema {
+        CsvSchema {
+            schema_version: SCHEMA_VERSION,
+            columns: self
+                .columns
+                .iter()
+                .map(|column| CsvSchemaColumn {
+                    name: column.name().to_string(),
+                    kind: column.kind().to_string(),
+                    description: column.description().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Write the schema of `csv_path` next to it, returning the path.
+    pub fn write_schema(&self, csv_path: &Path) -> anyhow::Result<PathBuf> {
+        let path = schema_path(csv_path);
+        std::fs::write(&path, serde_json::to_string_pretty(&self.schema())?)?;
+        Ok(path)
+    }
+}
+
+/// Path of the schema file of the CSV file `csv_path`
+pub fn schema_path(csv_path: &Path) -> PathBuf {
+    csv_path.with_extension("schema.json")
+}
+
+/// CPU the calling thread is running on
+fn current_cpu() -> Option<usize> {
+    #[cfg(target_os = "linux")]
+    {
+        let cpu = unsafe { libc::sched_getcpu() };
+        (cpu >= 0).then_some(cpu as usize)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mechanism::IpcMechanism;
+    use crate::metrics::LatencyType;
+    use std::time::Duration;
+
+    fn record() -> MessageLatencyRecord {
+        MessageLatencyRecord::new(
+            7,
+            IpcMechanism::TcpSocket,
+            64,
+            LatencyType::OneWay,
+            Duration::from_nanos(1500),
+            1000,
+        )
+    }
+
+    #[test]
+    fn test_default_layout_matches_headings() {
+        let layout = CsvLayout::default();
+        assert_eq!(layout.header(), MessageLatencyRecord::HEADINGS.join(","));
+        assert_eq!(layout.row(&record()), record().to_csv_record());
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        assert_eq!(
+            parse_columns("message_id, one_way_latency_ns").unwrap(),
+            vec![CsvColumn::MessageId, CsvColumn::OneWayLatencyNs]
+        );
+        let extended = parse_columns("default,phase,run_id").unwrap();
+        assert_eq!(extended.len(), CsvColumn::DEFAULT.len() + 1);
+        assert_eq!(extended.last(), Some(&CsvColumn::Phase));
+        assert_eq!(parse_columns("all").unwrap(), CsvColumn::ALL);
+        assert!(parse_columns("latency").is_err());
+        assert!(parse_columns(",").is_err());
+    }
+
+    #[test]
+    fn test_selected_columns() {
+        let layout = CsvLayout::new(
+            parse_columns("message_id,round_trip_latency_ns,worker_id,phase").unwrap(),
+        );
+        assert_eq!(
+            layout.header(),
+            "message_id,round_trip_latency_ns,worker_id,phase"
+        );
+        assert_eq!(layout.row(&record()), "7,,0,measurement");
+    }
+
+    #[test]
+    fn test_write_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("stream.csv");
+        let layout = CsvLayout::new(vec![CsvColumn::MessageId, CsvColumn::CpuCore]);
+        let path = layout.write_schema(&csv).unwrap();
+        assert_eq!(path, dir.path().join("stream.schema.json"));
+        let schema: CsvSchema =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(schema.schema_version, SCHEMA_VERSION);
+        assert_eq!(schema.columns[1].name, "cpu_core");
+        assert_eq!(schema.columns[1].kind, "u64");
+    }
+}
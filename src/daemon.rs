@@ -0,0 +1,820 @@
+//! Long-running benchmark service with a local control API.
+//!
+//! `ipc-benchmark daemon --listen unix:/run/rusty-comms.sock` accepts
+//! benchmark jobs from lab automation and the performance dashboard, so
+//! they can drive the tool without shelling out and parsing files. Jobs
+//! are run one at a time, in submission order, each as a child process of
+//! this same executable, so no two benchmarks compete for the machine.
+//!
+//! ## Protocol
+//!
+//! JSON-RPC 2.0, one request or response object per line. Methods:
+//!
+//! - `submit` `{"args": [...]}` → `{"job": ID}`: queue a run with these
+//!   benchmark arguments, checked by the parser before they are accepted.
+//! - `status` `{"job": ID}` → the job's [`JobStatus`]: its state, queue
+//!   position and the mechanisms completed so far.
+//! - `list` → the status of every job.
+//! - `results` `{"job": ID}` → `{"job": ID, "results": {...}}`: the final
+//!   results document of a finished or failed job.
+//!
+//! The daemon reads each job's progress from its `--machine-output jsonl`
+//! lines, one per completed mechanism, and its results from the output
+//! file it wrote. Output file and upload options are owned by the daemon
+//! and dropped from the submitted arguments, along with `--config`, whose
+//! options are passed on individually. Scheduling options are rejected.
+//!
+//! Jobs run as the daemon's user, so the control socket is created with
+//! mode 0600 and only that user can submit them. There is no network
+//! listener; the conductor and agents cover runs driven from other hosts.
+//! Only the last [`MAX_ENDED_JOBS`] finished or failed jobs are kept, and
+//! at most [`MAX_QUEUED_JOBS`] wait to run; further submissions are refused
+//! until the queue drains.
+
+// Without Unix domain sockets nothing serves the job queue
+#![cfg_attr(not(unix), allow(dead_code))]
+
+use crate::cli::{Args, DaemonArgs, IpcMechanism};
+use crate::{repeat, shutdown};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Malformed JSON
+const PARSE_ERROR: i64 = -32700;
+
+/// Not a JSON-RPC request object
+const INVALID_REQUEST: i64 = -32600;
+
+/// No such method
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Missing or unusable parameters, including rejected benchmark arguments
+const INVALID_PARAMS: i64 = -32602;
+
+/// The job does not exist or has no results yet
+const JOB_ERROR: i64 = -32000;
+
+/// The queue already holds [`MAX_QUEUED_JOBS`] jobs
+const QUEUE_FULL: i64 = -32001;
+
+/// Finished and failed jobs kept for `status` and `results`; older ones
+/// are forgotten, so a long-running daemon does not grow without bound
+pub const MAX_ENDED_JOBS: usize = 100;
+
+/// Jobs waiting to run; a client submitting in a loop cannot grow the
+/// queue without bound
+pub const MAX_QUEUED_JOBS: usize = 100;
+
+/// Refusal of a submission while [`MAX_QUEUED_JOBS`] jobs are waiting
+#[derive(Debug)]
+pub struct QueueFull;
+
+impl std::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the queue is full ({} jobs waiting); submit again later",
+            MAX_QUEUED_JOBS
+        )
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+/// A JSON-RPC request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    /// Protocol version, "2.0"
+    pub jsonrpc: String,
+
+    /// Identifier echoed in the response
+    #[serde(default)]
+    pub id: Value,
+
+    /// Method name
+    pub method: String,
+
+    /// Method parameters
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// Error of a JSON-RPC response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    /// JSON-RPC error code
+    pub code: i64,
+
+    /// What went wrong
+    pub message: String,
+}
+
+/// A JSON-RPC response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    /// Protocol version, "2.0"
+    pub jsonrpc: String,
+
+    /// Identifier of the request answered
+    pub id: Value,
+
+    /// Result of a successful call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    /// Error of a failed call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn failure(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+/// Where a job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for the jobs ahead of it
+    Queued,
+
+    /// Being run
+    Running,
+
+    /// Ran and exited successfully
+    Finished,
+
+    /// Could not be started, or exited with an error
+    Failed,
+}
+
+/// Progress of a job, as returned by `status` and `list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    /// Job identifier, counting from 1
+    pub job: u64,
+
+    /// Where the job is in its life
+    pub state: JobState,
+
+    /// Benchmark arguments as submitted
+    pub args: Vec<String>,
+
+    /// When the job was submitted
+    pub submitted: DateTime<Utc>,
+
+    /// Jobs to run before this one, while queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+
+    /// When the run started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started: Option<DateTime<Utc>>,
+
+    /// When the run ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished: Option<DateTime<Utc>>,
+
+    /// Mechanisms the run covers
+    pub mechanisms_total: usize,
+
+    /// Mechanisms completed so far, in order
+    pub mechanisms_completed: Vec<String>,
+
+    /// Exit code of the run, if it exited normally
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+
+    /// Why the job failed to run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct Job {
+    status: JobStatus,
+    suite: Vec<OsString>,
+    results: Option<Value>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    jobs: BTreeMap<u64, Job>,
+    next_id: u64,
+}
+
+impl Jobs {
+    /// Forget the oldest ended jobs beyond [`MAX_ENDED_JOBS`].
+    fn evict_ended(&mut self) {
+        let ended: Vec<u64> = self
+            .jobs
+            .values()
+            .filter(|j| matches!(j.status.state, JobState::Finished | JobState::Failed))
+            .map(|j| j.status.job)
+            .collect();
+        for job in &ended[..ended.len().saturating_sub(MAX_ENDED_JOBS)] {
+            self.jobs.remove(job);
+        }
+    }
+
+    fn status(&self, job: &Job) -> JobStatus {
+        let mut status = job.status.clone();
+        if status.state == JobState::Queued {
+            status.queue_position = Some(
+                self.jobs
+                    .range(..status.job)
+                    .filter(|(_, j)| j.status.state == JobState::Queued)
+                    .count(),
+            );
+        }
+        status
+    }
+}
+
+/// Job queue shared by the connections and the runner
+#[derive(Clone, Default)]
+pub struct Daemon {
+    jobs: Arc<(Mutex<Jobs>, Condvar)>,
+}
+
+/// Check submitted benchmark arguments, returning the command line of the
+/// child run and the number of mechanisms it covers.
+fn job_suite(args: &[String]) -> Result<(Vec<OsString>, usize)> {
+    let parsed = Args::try_parse_explicit_from(
+        std::iter::once("ipc-benchmark").chain(args.iter().map(String::as_str)),
+    )
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    if parsed.command.is_some() {
+        anyhow::bail!("jobs run benchmarks, not subcommands");
+    }
+    if parsed.machine_output.is_some() {
+        anyhow::bail!("the daemon sets --machine-output itself");
+    }
+    // Checked on the parsed options, so a --config file cannot slip them in
+    if parsed.repeat_every.is_some() || parsed.until.is_some() {
+        anyhow::bail!("jobs run once; schedule repetitions by submitting them");
+    }
+    let mechanisms = IpcMechanism::expand_all(parsed.mechanisms.clone()).len();
+    Ok((repeat::suite_args(&parsed), mechanisms))
+}
+
+impl Daemon {
+    /// Answer one request.
+    pub fn handle(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        if request.jsonrpc != "2.0" {
+            return Response::failure(id, INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+        }
+        let job = || {
+            request
+                .params
+                .get("job")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| Response::failure(id.clone(), INVALID_PARAMS, "missing \"job\""))
+        };
+        match request.method.as_str() {
+            "submit" => {
+                let args: Vec<String> = match serde_json::from_value(request.params["args"].clone())
+                {
+                    Ok(args) => args,
+                    Err(_) => {
+                        return Response::failure(
+                            id,
+                            INVALID_PARAMS,
+                            "\"args\" must be an array of strings",
+                        )
+                    }
+                };
+                match self.submit(args) {
+                    Ok(job) => Response::success(id, json!({ "job": job })),
+                    Err(e) if e.is::<QueueFull>() => {
+                        Response::failure(id, QUEUE_FULL, e.to_string())
+                    }
+                    Err(e) => Response::failure(id, INVALID_PARAMS, format!("{:#}", e)),
+                }
+            }
+            "status" => {
+                let job = match job() {
+                    Ok(job) => job,
+                    Err(response) => return response,
+                };
+                match self.status(job) {
+                    Some(status) => Response::success(id, json!(status)),
+                    None => Response::failure(id, JOB_ERROR, format!("no job {}", job)),
+                }
+            }
+            "list" => Response::success(id, json!(self.list())),
+            "results" => {
+                let job = match job() {
+                    Ok(job) => job,
+                    Err(response) => return response,
+                };
+                match self.results(job) {
+                    Ok(results) => Response::success(id, json!({ "job": job, "results": results })),
+                    Err(e) => Response::failure(id, JOB_ERROR, format!("{:#}", e)),
+                }
+            }
+            other => Response::failure(id, METHOD_NOT_FOUND, format!("unknown method '{}'", other)),
+        }
+    }
+
+    /// Answer one line of the protocol.
+    pub fn handle_line(&self, line: &str) -> Response {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => return Response::failure(Value::Null, PARSE_ERROR, e.to_string()),
+        };
+        let id = value.get("id").cloned().unwrap_or(Value::Null);
+        match serde_json::from_value(value) {
+            Ok(request) => self.handle(request),
+            Err(e) => Response::failure(id, INVALID_REQUEST, e.to_string()),
+        }
+    }
+
+    /// Queue a run of `args`, returning its job identifier, or
+    /// [`QueueFull`] if [`MAX_QUEUED_JOBS`] are already waiting.
+    pub fn submit(&self, args: Vec<String>) -> Result<u64> {
+        let (suite, mechanisms_total) = job_suite(&args)?;
+        let (jobs, queued) = &*self.jobs;
+        let mut jobs = jobs.lock().unwrap();
+        let waiting = jobs
+            .jobs
+            .values()
+            .filter(|j| j.status.state == JobState::Queued)
+            .count();
+        if waiting >= MAX_QUEUED_JOBS {
+            return Err(QueueFull.into());
+        }
+        jobs.next_id += 1;
+        let job = jobs.next_id;
+        jobs.jobs.insert(
+            job,
+            Job {
+                status: JobStatus {
+                    job,
+                    state: JobState::Queued,
+                    args,
+                    submitted: Utc::now(),
+                    queue_position: None,
+                    started: None,
+                    finished: None,
+                    mechanisms_total,
+                    mechanisms_completed: Vec::new(),
+                    exit_code: None,
+                    error: None,
+                },
+                suite,
+                results: None,
+            },
+        );
+        queued.notify_one();
+        Ok(job)
+    }
+
+    /// Progress of `job`, if it exists.
+    pub fn status(&self, job: u64) -> Option<JobStatus> {
+        let jobs = self.jobs.0.lock().unwrap();
+        jobs.jobs.get(&job).map(|j| jobs.status(j))
+    }
+
+    /// Progress of every job, in submission order.
+    pub fn list(&self) -> Vec<JobStatus> {
+        let jobs = self.jobs.0.lock().unwrap();
+        jobs.jobs.values().map(|j| jobs.status(j)).collect()
+    }
+
+    /// Final results of `job`, once it has ended.
+    pub fn results(&self, job: u64) -> Result<Value> {
+        let jobs = self.jobs.0.lock().unwrap();
+        let entry = jobs.jobs.get(&job).context(format!("no job {}", job))?;
+        match entry.status.state {
+            JobState::Queued | JobState::Running => {
+                anyhow::bail!("job {} has not finished", job)
+            }
+            JobState::Finished | JobState::Failed => entry
+                .results
+                .clone()
+                .context(format!("job {} wrote no results", job)),
+        }
+    }
+
+    /// Take the next queued job, waiting for one until shutdown.
+    fn next_job(&self) -> Option<(u64, Vec<OsString>)> {
+        let (jobs, queued) = &*self.jobs;
+        let mut jobs = jobs.lock().unwrap();
+        loop {
+            if shutdown::requested() {
+                return None;
+            }
+            if let Some(job) = jobs
+                .jobs
+                .values_mut()
+                .find(|j| j.status.state == JobState::Queued)
+            {
+                job.status.state = JobState::Running;
+                job.status.started = Some(Utc::now());
+                return Some((job.status.job, job.suite.clone()));
+            }
+            jobs = queued
+                .wait_timeout(jobs, Duration::from_millis(100))
+                .unwrap()
+                .0;
+        }
+    }
+
+    fn update(&self, job: u64, change: impl FnOnce(&mut Job)) {
+        if let Some(entry) = self.jobs.0.lock().unwrap().jobs.get_mut(&job) {
+            change(entry);
+        }
+    }
+
+    /// Run queued jobs one at a time until shutdown.
+    fn run_jobs(&self) {
+        while let Some((job, suite)) = self.next_job() {
+            eprintln!("Job {} started", job);
+            let outcome = self.run_job(job, &suite);
+            self.update(job, |entry| {
+                entry.status.finished = Some(Utc::now());
+                match outcome {
+                    Ok((exit_code, results)) => {
+                        entry.status.exit_code = exit_code;
+                        entry.status.state = if exit_code == Some(0) {
+                            JobState::Finished
+                        } else {
+                            JobState::Failed
+                        };
+                        entry.results = results;
+                    }
+                    Err(e) => {
+                        entry.status.state = JobState::Failed;
+                        entry.status.error = Some(format!("{:#}", e));
+                    }
+                }
+                let ended = if entry.status.state == JobState::Finished {
+                    "finished"
+                } else {
+                    "failed"
+                };
+                eprintln!("Job {} {}", job, ended);
+            });
+            self.jobs.0.lock().unwrap().evict_ended();
+        }
+    }
+
+    /// Run one job as a child process, following its progress, and return
+    /// its exit code and results.
+    fn run_job(&self, job: u64, suite: &[OsString]) -> Result<(Option<i32>, Option<Value>)> {
+        let exe = std::env::current_exe().context("Failed to locate the benchmark executable")?;
+        // A private directory, so no other user can predict or replace the
+        // results file
+        let dir = tempfile::Builder::new()
+            .prefix("ipc-benchmark-daemon-")
+            .tempdir()
+            .context("Failed to create a directory for the results")?;
+        let output = dir.path().join("results.json");
+        let mut cmd = Command::new(exe);
+        cmd.args(suite)
+            .args(["--machine-output", "jsonl", "--output-file"])
+            .arg(&output)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        let mut child = cmd.spawn().context("Failed to start the benchmark")?;
+
+        // Each line is the results of one completed mechanism
+        let stdout = child.stdout.take().context("No benchmark output")?;
+        let daemon = self.clone();
+        let progress = std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let mechanism = serde_json::from_str::<Value>(&line)
+                    .ok()
+                    .and_then(|v| v["mechanism"].as_str().map(str::to_string));
+                if let Some(mechanism) = mechanism {
+                    daemon.update(job, |entry| {
+                        entry.status.mechanisms_completed.push(mechanism);
+                    });
+                }
+            }
+        });
+        let status = repeat::wait_forwarding_shutdown(&mut child)?;
+        let _ = progress.join();
+
+        let results = if output.exists() {
+            let content = std::fs::read_to_string(&output)?;
+            Some(serde_json::from_str(&content).context("The benchmark wrote invalid results")?)
+        } else {
+            None
+        };
+        Ok((status.code(), results))
+    }
+}
+
+/// Serve one connection, a request per line, until it closes.
+fn serve(daemon: &Daemon, reader: impl Read, mut writer: impl Write) -> Result<()> {
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut reply = serde_json::to_vec(&daemon.handle_line(&line))?;
+        reply.push(b'\n');
+        writer.write_all(&reply)?;
+    }
+    Ok(())
+}
+
+/// `--listen` value: `unix:PATH`
+///
+/// The socket is created with mode 0600 from the start, under a umask, so
+/// no other user can connect before its permissions are set. A socket
+/// already at the path is replaced only if no daemon answers on it.
+#[cfg(unix)]
+fn bind(listen: &str) -> Result<(UnixListener, PathBuf)> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let path = PathBuf::from(listen.strip_prefix("unix:").with_context(|| {
+        format!(
+            "Invalid --listen address '{}'; the control API is served on unix:PATH only",
+            listen
+        )
+    })?);
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        if UnixStream::connect(&path).is_ok() {
+            anyhow::bail!("another daemon is listening on {}", path.display());
+        }
+        eprintln!("Removing the stale socket {}", path.display());
+        std::fs::remove_file(&path)?;
+    }
+    // Jobs run as this user, so only this user may submit them. The daemon
+    // has no other threads yet, so the umask is safe to change.
+    let umask = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(&path);
+    unsafe { libc::umask(umask) };
+    let listener = listener.with_context(|| format!("Failed to listen on {}", path.display()))?;
+    Ok((listener, path))
+}
+
+#[cfg(unix)]
+fn spawn_connection(daemon: &Daemon, stream: UnixStream) {
+    let reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("Control connection failed: {}", e);
+            return;
+        }
+    };
+    let daemon = daemon.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = serve(&daemon, reader, stream) {
+            eprintln!("Control connection failed: {:#}", e);
+        }
+    });
+}
+
+/// Serve the control API on `--listen` and run submitted jobs until
+/// interrupted.
+#[cfg(unix)]
+pub fn run_daemon(args: &DaemonArgs) -> Result<()> {
+    let (listener, path) = bind(&args.listen)?;
+    shutdown::install_handler("Interrupted, stopping the daemon");
+    eprintln!("Daemon listening on {}", args.listen);
+
+    let daemon = Daemon::default();
+    let runner = {
+        let daemon = daemon.clone();
+        std::thread::spawn(move || daemon.run_jobs())
+    };
+
+    listener.set_nonblocking(true)?;
+    while !shutdown::requested() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let _ = stream.set_nonblocking(false);
+                spawn_connection(&daemon, stream);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let _ = runner.join();
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
+
+/// The control API is a Unix domain socket, so there is no daemon here.
+#[cfg(not(unix))]
+pub fn run_daemon(_args: &DaemonArgs) -> Result<()> {
+    anyhow::bail!("the daemon needs Unix domain sockets")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(daemon: &Daemon, line: &str) -> Response {
+        daemon.handle_line(line)
+    }
+
+    #[test]
+    fn test_daemon_queues_jobs_and_reports_them() {
+        let daemon = Daemon::default();
+
+        let submitted = call(
+            &daemon,
+            r#"{"jsonrpc":"2.0","id":1,"method":"submit","params":{"args":["-m","uds","shm","-i","100"]}}"#,
+        );
+        assert_eq!(submitted.id, json!(1));
+        assert_eq!(submitted.result, Some(json!({ "job": 1 })));
+        daemon.submit(vec!["-m".into(), "tcp".into()]).unwrap();
+
+        let status = call(
+            &daemon,
+            r#"{"jsonrpc":"2.0","id":2,"method":"status","params":{"job":2}}"#,
+        );
+        let status: JobStatus = serde_json::from_value(status.result.unwrap()).unwrap();
+        assert_eq!(status.state, JobState::Queued);
+        assert_eq!(status.queue_position, Some(1));
+        assert_eq!(status.mechanisms_total, 1);
+        assert_eq!(daemon.list()[0].mechanisms_total, 2);
+
+        // Nothing to fetch until the job has run
+        let results = call(
+            &daemon,
+            r#"{"jsonrpc":"2.0","id":3,"method":"results","params":{"job":1}}"#,
+        );
+        assert_eq!(results.error.unwrap().code, JOB_ERROR);
+        daemon.update(1, |job| {
+            job.status.state = JobState::Finished;
+            job.results = Some(json!({ "results": [] }));
+        });
+        assert_eq!(daemon.results(1).unwrap(), json!({ "results": [] }));
+        assert_eq!(daemon.status(2).unwrap().queue_position, Some(0));
+    }
+
+    #[test]
+    fn test_daemon_rejects_bad_requests() {
+        let daemon = Daemon::default();
+        let code = |line: &str| call(&daemon, line).error.map(|e| e.code);
+
+        assert_eq!(code("not json"), Some(PARSE_ERROR));
+        assert_eq!(code(r#"{"id":1}"#), Some(INVALID_REQUEST));
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"launch"}"#),
+            Some(METHOD_NOT_FOUND)
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"submit","params":{"args":["--bogus"]}}"#),
+            Some(INVALID_PARAMS)
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"submit","params":{"args":["selftest"]}}"#),
+            Some(INVALID_PARAMS)
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"status","params":{}}"#),
+            Some(INVALID_PARAMS)
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"status","params":{"job":9}}"#),
+            Some(JOB_ERROR)
+        );
+        assert!(daemon.list().is_empty());
+    }
+
+    #[test]
+    fn test_job_suite_drops_daemon_owned_options() {
+        let args: Vec<String> = ["-m", "all", "-o", "mine.json", "--upload", "s3://bucket/ci"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (suite, mechanisms) = job_suite(&args).unwrap();
+        assert_eq!(suite, ["-m", "all"]);
+        assert!(mechanisms > 1);
+        assert!(job_suite(&["--machine-output".to_string()]).is_err());
+        assert!(job_suite(&["--repeat-every".to_string(), "1h".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_job_suite_checks_config_file_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("job.json");
+        let args = vec!["--config".to_string(), config.display().to_string()];
+
+        std::fs::write(
+            &config,
+            r#"{"mechanisms": ["uds"], "output-file": "mine.json"}"#,
+        )
+        .unwrap();
+        let (suite, mechanisms) = job_suite(&args).unwrap();
+        assert_eq!(suite, ["-m", "uds"]);
+        assert_eq!(mechanisms, 1);
+
+        std::fs::write(&config, r#"{"repeat-every": "1h"}"#).unwrap();
+        let error = job_suite(&args).unwrap_err().to_string();
+        assert!(error.contains("jobs run once"), "{}", error);
+    }
+
+    #[test]
+    fn test_ended_jobs_are_capped() {
+        let daemon = Daemon::default();
+        // Ended as they are submitted, so the queue never fills
+        for _ in 0..=MAX_ENDED_JOBS {
+            let job = daemon.submit(vec!["-m".into(), "tcp".into()]).unwrap();
+            daemon.update(job, |job| job.status.state = JobState::Finished);
+        }
+        daemon.submit(vec!["-m".into(), "tcp".into()]).unwrap();
+        daemon.jobs.0.lock().unwrap().evict_ended();
+
+        assert!(daemon.status(1).is_none());
+        assert!(daemon.status(2).is_some());
+        let queued = MAX_ENDED_JOBS as u64 + 2;
+        assert_eq!(daemon.status(queued).unwrap().state, JobState::Queued);
+        assert_eq!(daemon.list().len(), MAX_ENDED_JOBS + 1);
+    }
+
+    #[test]
+    fn test_queued_jobs_are_capped() {
+        let daemon = Daemon::default();
+        for _ in 0..MAX_QUEUED_JOBS {
+            daemon.submit(vec!["-m".into(), "tcp".into()]).unwrap();
+        }
+        let submit = r#"{"jsonrpc":"2.0","id":1,"method":"submit","params":{"args":["-m","tcp"]}}"#;
+        let refused = call(&daemon, submit).error.unwrap();
+        assert_eq!(refused.code, QUEUE_FULL);
+        assert!(
+            refused.message.contains("queue is full"),
+            "{}",
+            refused.message
+        );
+        assert_eq!(daemon.list().len(), MAX_QUEUED_JOBS);
+
+        // A job leaving the queue makes room for another
+        daemon.update(1, |job| job.status.state = JobState::Running);
+        assert!(call(&daemon, submit).result.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_is_private_and_keeps_live_sockets() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let listen = format!("unix:{}", path.display());
+
+        let (listener, _) = bind(&listen).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let error = bind(&listen).unwrap_err().to_string();
+        assert!(error.contains("another daemon"), "{}", error);
+
+        // Left behind by a daemon that is gone
+        drop(listener);
+        bind(&listen).unwrap();
+
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(bind(&format!("unix:{}", file.display())).is_err());
+        assert!(bind("tcp:127.0.0.1:7272").is_err());
+    }
+}
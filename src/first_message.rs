@@ -0,0 +1,186 @@
+//! Cold-start penalty of a test's first message.
+//!
+//! By default each test sends a canary before the measured messages and
+//! throws it away, so the results describe a warm path; `--first-message
+//! include` counts the first message like any other. Either way, what the
+//! first message costs stays hidden. `--first-message both` runs each test
+//! both ways: the run behind a canary is the one reported, and the run that
+//! counted its first message is set against it in a [`FirstMessagePenalty`],
+//! with the first message's latency and the mean and maximum of each run.
+
+use crate::metrics::utils::format_latency;
+use crate::metrics::LatencyMetrics;
+use crate::results::BenchmarkResults;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How each test treats its first message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum FirstMessage {
+    /// Send a canary ahead of the measured messages and discard it
+    #[default]
+    Exclude,
+
+    /// Count the first message like any other
+    Include,
+
+    /// Run each test both ways and report the first message's penalty
+    Both,
+}
+
+impl fmt::Display for FirstMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FirstMessage::Exclude => write!(f, "exclude"),
+            FirstMessage::Include => write!(f, "include"),
+            FirstMessage::Both => write!(f, "both"),
+        }
+    }
+}
+
+/// A test run that counted its first message next to the same test run
+/// behind a canary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirstMessagePenalty {
+    /// Latency of the first message, in nanoseconds
+    pub first_ns: u64,
+
+    /// Median latency of the run behind a canary, in nanoseconds
+    pub rest_p50_ns: u64,
+
+    /// How much slower the first message was than that median, in
+    /// nanoseconds; negative when it was faster
+    pub penalty_ns: i64,
+
+    /// Mean latency of the run that counted the first message, in
+    /// nanoseconds
+    pub mean_with_ns: f64,
+
+    /// Mean latency of the run behind a canary, in nanoseconds
+    pub mean_without_ns: f64,
+
+    /// Largest latency of the run that counted the first message, in
+    /// nanoseconds
+    pub max_with_ns: u64,
+
+    /// Largest latency of the run behind a canary, in nanoseconds
+    pub max_without_ns: u64,
+}
+
+impl FirstMessagePenalty {
+    /// Compare the first message (`first_ns`) and the latencies of the run
+    /// that counted it (`with`) against those of the run that did not.
+    pub fn new(first_ns: u64, with: &LatencyMetrics, without: &LatencyMetrics) -> Self {
+        let rest_p50_ns = without.median_ns as u64;
+        Self {
+            first_ns,
+            rest_p50_ns,
+            penalty_ns: first_ns as i64 - rest_p50_ns as i64,
+            mean_with_ns: with.mean_ns,
+            mean_without_ns: without.mean_ns,
+            max_with_ns: with.max_ns,
+            max_without_ns: without.max_ns,
+        }
+    }
+
+    /// The first message's latency as a multiple of the rest's median
+    pub fn ratio(&self) -> f64 {
+        self.first_ns as f64 / self.rest_p50_ns.max(1) as f64
+    }
+}
+
+impl fmt::Display for FirstMessagePenalty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {:.2}x the P50 of the rest ({}{}); mean {} with it, {} without; \
+             max {} with it, {} without",
+            format_latency(self.first_ns),
+            self.ratio(),
+            if self.penalty_ns < 0 { '-' } else { '+' },
+            format_latency(self.penalty_ns.unsigned_abs()),
+            format_latency(self.mean_with_ns as u64),
+            format_latency(self.mean_without_ns as u64),
+            format_latency(self.max_with_ns),
+            format_latency(self.max_without_ns)
+        )
+    }
+}
+
+/// Set each test of `with`, a run that counted its first message, against
+/// the same test of `results`, run behind a canary, and attach the penalty
+/// to `results`.
+pub fn attach_penalty(results: &mut BenchmarkResults, with: &BenchmarkResults) {
+    for (without, with) in [
+        (&mut results.one_way_results, &with.one_way_results),
+        (&mut results.round_trip_results, &with.round_trip_results),
+    ] {
+        let (Some(without), Some(with)) = (without.as_mut(), with.as_ref()) else {
+            continue;
+        };
+        if let (Some(first_ns), Some(with_latency), Some(without_latency)) = (
+            with.first_latency_ns,
+            with.latency.as_ref(),
+            without.latency.as_ref(),
+        ) {
+            without.first_message = Some(FirstMessagePenalty::new(
+                first_ns,
+                with_latency,
+                without_latency,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{LatencyCollector, LatencyType};
+    use std::time::Duration;
+
+    fn latencies(us: &[u64]) -> LatencyMetrics {
+        let mut collector = LatencyCollector::new(LatencyType::OneWay).unwrap();
+        for &us in us {
+            collector.record(Duration::from_micros(us)).unwrap();
+        }
+        collector.get_metrics(&[50.0])
+    }
+
+    #[test]
+    fn test_penalty_against_the_rest() {
+        let with = latencies(&[100, 10, 10, 30]);
+        let without = latencies(&[10, 10, 10, 30]);
+        let penalty = FirstMessagePenalty::new(100_000, &with, &without);
+
+        assert_eq!(penalty.first_ns, 100_000);
+        assert_eq!(penalty.rest_p50_ns / 100, 100);
+        assert!((penalty.penalty_ns - 90_000).abs() < 100);
+        assert!((penalty.mean_without_ns - 15_000.0).abs() < 100.0);
+        assert!((penalty.mean_with_ns - 37_500.0).abs() < 100.0);
+        assert_eq!(penalty.max_with_ns / 100, 1000);
+        assert_eq!(penalty.max_without_ns / 100, 300);
+        assert!((penalty.ratio() - 10.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_display() {
+        let penalty = FirstMessagePenalty {
+            first_ns: 100_000,
+            rest_p50_ns: 10_000,
+            penalty_ns: 90_000,
+            mean_with_ns: 32_000.0,
+            mean_without_ns: 15_000.0,
+            max_with_ns: 100_000,
+            max_without_ns: 30_000,
+        };
+        assert_eq!(
+            penalty.to_string(),
+            "100.00μs, 10.00x the P50 of the rest (+90.00μs); mean 32.00μs with it, \
+             15.00μs without; max 100.00μs with it, 30.00μs without"
+        );
+    }
+}
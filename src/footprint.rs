@@ -0,0 +1,403 @@
+//! Resources a whole run will hold, checked against the host's limits.
+//!
+//! A matrix run that exhausts `/dev/shm`, the POSIX message queue quota,
+//! the open file limit or the ephemeral port range fails on the mechanism
+//! that tips it over, after the ones before it have already run. Before
+//! the first test, [`Footprint::plan`] estimates what each mechanism of the
+//! plan will hold from its resolved transport configuration, and
+//! [`Footprint::check`] compares the peak against [`HostLimits::query`],
+//! failing with a per-resource breakdown of everything that would run out.
+//!
+//! ## Estimates
+//!
+//! - `/dev/shm`: the ring of a shared memory test, or the spool of an
+//!   inotify test; a ring on hugetlbfs does not count
+//! - message queues: the two queues of a PMQ test, each charged
+//!   `depth x (message size + 64)` bytes of `ulimit -q` quota, the 64
+//!   covering the kernel's per-message bookkeeping
+//! - open files: one descriptor per connection, one per PMQ queue, and a
+//!   baseline of 32 for the client's own files, pipes and runtime
+//! - ephemeral ports: one per TCP or HTTP connection and one for the
+//!   server's listener, plus one per cold connection of `--compare-pooling`
+//!   over TCP, since closed connections linger in TIME_WAIT
+//!
+//! Mechanisms of a `--parallel-mechanisms` group hold their resources at
+//! the same time, so the footprint is the peak over the plan's groups. The
+//! estimates are coarse: they catch a plan that cannot fit, not one that
+//! only just does. Limits that cannot be read are not enforced.
+
+use anyhow::{bail, Result};
+use std::fmt::Write as _;
+use tracing::debug;
+
+use crate::benchmark::BenchmarkConfig;
+use crate::cli::Args;
+use crate::ipc::limits::TransportLimits;
+use crate::ipc::TransportConfig;
+use crate::mechanism::IpcMechanism;
+use crate::metrics::utils::format_bytes;
+
+/// Descriptors the client holds besides its connections
+pub const BASELINE_FDS: u64 = 32;
+
+/// Kernel bookkeeping charged per queued PMQ message, in bytes
+#[cfg(target_os = "linux")]
+const MQ_MESSAGE_OVERHEAD: u64 = 64;
+
+/// A host resource the plan draws on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// Bytes of `/dev/shm`
+    SharedMemory,
+    /// POSIX message queues
+    MessageQueues,
+    /// Bytes of POSIX message queue quota
+    MessageQueueBytes,
+    /// Open file descriptors of the client
+    FileDescriptors,
+    /// Ephemeral ports
+    EphemeralPorts,
+}
+
+impl Resource {
+    /// Every resource, in the order they are reported.
+    pub const ALL: [Resource; 5] = [
+        Resource::SharedMemory,
+        Resource::MessageQueues,
+        Resource::MessageQueueBytes,
+        Resource::FileDescriptors,
+        Resource::EphemeralPorts,
+    ];
+
+    /// What the resource is called in the breakdown, and where its limit
+    /// comes from.
+    fn describe(&self) -> (&'static str, &'static str) {
+        match self {
+            Resource::SharedMemory => ("/dev/shm", "free"),
+            Resource::MessageQueues => ("message queues", "fs.mqueue.queues_max"),
+            Resource::MessageQueueBytes => ("message queue bytes", "ulimit -q"),
+            Resource::FileDescriptors => ("open files", "ulimit -n"),
+            Resource::EphemeralPorts => ("ephemeral ports", "net.ipv4.ip_local_port_range"),
+        }
+    }
+
+    fn format(&self, amount: u64) -> String {
+        match self {
+            Resource::SharedMemory | Resource::MessageQueueBytes => format_bytes(amount as f64),
+            _ => amount.to_string(),
+        }
+    }
+}
+
+/// What one mechanism's test holds while it runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Demand {
+    /// The mechanism tested
+    pub mechanism: IpcMechanism,
+    /// Bytes of `/dev/shm`
+    pub shm_bytes: u64,
+    /// POSIX message queues
+    pub mq_queues: u64,
+    /// Bytes of POSIX message queue quota
+    pub mq_bytes: u64,
+    /// Client file descriptors, besides the baseline
+    pub fds: u64,
+    /// Ephemeral ports
+    pub ports: u64,
+}
+
+impl Demand {
+    /// Estimate the test of `mechanism` with its resolved `transport`.
+    pub fn of(
+        mechanism: IpcMechanism,
+        transport: &TransportConfig,
+        config: &BenchmarkConfig,
+        args: &Args,
+    ) -> Self {
+        let connections = config.concurrency.max(1) as u64;
+        let buffer = transport.buffer_size as u64;
+        let mut demand = Self {
+            mechanism,
+            shm_bytes: 0,
+            mq_queues: 0,
+            mq_bytes: 0,
+            fds: connections,
+            ports: 0,
+        };
+        match mechanism {
+            IpcMechanism::SharedMemory => {
+                if transport.shm_hugetlbfs.is_none() || args.shm_direct {
+                    demand.shm_bytes = buffer;
+                }
+                demand.fds = 1;
+            }
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Inotify => demand.shm_bytes = buffer,
+            #[cfg(target_os = "linux")]
+            IpcMechanism::PosixMessageQueue => {
+                let depth = transport.message_queue_depth as u64;
+                demand.mq_queues = 2;
+                demand.mq_bytes = 2 * depth * (buffer + MQ_MESSAGE_OVERHEAD);
+                demand.fds = 2;
+            }
+            IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc => {
+                demand.ports = connections + 1;
+                if args.compare_pooling && mechanism == IpcMechanism::TcpSocket {
+                    demand.ports += config.msg_count.unwrap_or(0) as u64 / 2;
+                }
+            }
+            _ => {}
+        }
+        demand
+    }
+
+    fn amount(&self, resource: Resource) -> u64 {
+        match resource {
+            Resource::SharedMemory => self.shm_bytes,
+            Resource::MessageQueues => self.mq_queues,
+            Resource::MessageQueueBytes => self.mq_bytes,
+            Resource::FileDescriptors => self.fds,
+            Resource::EphemeralPorts => self.ports,
+        }
+    }
+}
+
+/// The limits a plan is checked against, `None` where one is unknown or
+/// unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostLimits {
+    /// Free bytes in `/dev/shm`
+    pub shm_free_bytes: Option<u64>,
+    /// `fs.mqueue.queues_max`
+    pub mq_queues_max: Option<u64>,
+    /// `ulimit -q`, in bytes
+    pub mq_bytes: Option<u64>,
+    /// `ulimit -n`
+    pub open_files: Option<u64>,
+    /// Size of `net.ipv4.ip_local_port_range`
+    pub ephemeral_ports: Option<u64>,
+}
+
+impl HostLimits {
+    /// Read the limits in effect for this process.
+    pub fn query() -> Self {
+        Self {
+            shm_free_bytes: TransportLimits::discover(&IpcMechanism::SharedMemory).shm_free_bytes,
+            mq_queues_max: read_proc_u64("/proc/sys/fs/mqueue/queues_max"),
+            mq_bytes: crate::host_info::IpcLimits::query().mqueue_rlimit_bytes,
+            open_files: open_files_limit(),
+            ephemeral_ports: std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range")
+                .ok()
+                .and_then(|range| parse_port_range(&range)),
+        }
+    }
+
+    fn limit(&self, resource: Resource) -> Option<u64> {
+        match resource {
+            Resource::SharedMemory => self.shm_free_bytes,
+            Resource::MessageQueues => self.mq_queues_max,
+            Resource::MessageQueueBytes => self.mq_bytes,
+            Resource::FileDescriptors => self.open_files,
+            Resource::EphemeralPorts => self.ephemeral_ports,
+        }
+    }
+}
+
+/// Estimated resources of a whole plan.
+#[derive(Debug, Clone, Default)]
+pub struct Footprint {
+    /// Demands of the mechanisms that run at the same time, group by group
+    pub groups: Vec<Vec<Demand>>,
+}
+
+impl Footprint {
+    /// Estimate `mechanisms`, run `group_size` at a time, with `resolve`
+    /// giving the transport configuration a mechanism's test will use.
+    ///
+    /// Resolving applies the per-transport limits, so a configuration that
+    /// cannot work at all fails here too.
+    pub fn plan(
+        mechanisms: &[IpcMechanism],
+        group_size: usize,
+        config: &BenchmarkConfig,
+        args: &Args,
+        mut resolve: impl FnMut(IpcMechanism) -> Result<TransportConfig>,
+    ) -> Result<Self> {
+        let mut groups = Vec::new();
+        for group in mechanisms.chunks(group_size.max(1)) {
+            let mut demands = Vec::with_capacity(group.len());
+            for &mechanism in group {
+                let transport = resolve(mechanism)?;
+                demands.push(Demand::of(mechanism, &transport, config, args));
+            }
+            groups.push(demands);
+        }
+        Ok(Self { groups })
+    }
+
+    /// The most of `resource` any group holds, and that group.
+    pub fn peak(&self, resource: Resource) -> (u64, &[Demand]) {
+        let baseline = match resource {
+            Resource::FileDescriptors => BASELINE_FDS,
+            _ => 0,
+        };
+        self.groups
+            .iter()
+            .map(|group| {
+                let total = group.iter().map(|d| d.amount(resource)).sum::<u64>();
+                (baseline + total, group.as_slice())
+            })
+            .max_by_key(|(total, _)| *total)
+            .unwrap_or((baseline, &[]))
+    }
+
+    /// Fail, listing each exhausted resource and what draws on it, if the
+    /// peak of any resource exceeds its limit.
+    pub fn check(&self, limits: &HostLimits) -> Result<()> {
+        let mut breakdown = String::new();
+        for resource in Resource::ALL {
+            let (needed, group) = self.peak(resource);
+            let Some(limit) = limits.limit(resource) else {
+                continue;
+            };
+            debug!(
+                "Footprint of {}: {} of {}",
+                resource.describe().0,
+                resource.format(needed),
+                resource.format(limit)
+            );
+            if needed <= limit {
+                continue;
+            }
+            let (name, source) = resource.describe();
+            let _ = writeln!(
+                breakdown,
+                "  {}: {} needed, {} available ({})",
+                name,
+                resource.format(needed),
+                resource.format(limit),
+                source
+            );
+            if resource == Resource::FileDescriptors {
+                let _ = writeln!(breakdown, "    baseline: {}", BASELINE_FDS);
+            }
+            for demand in group.iter().filter(|d| d.amount(resource) > 0) {
+                let _ = writeln!(
+                    breakdown,
+                    "    {}: {}",
+                    demand.mechanism,
+                    resource.format(demand.amount(resource))
+                );
+            }
+        }
+        if !breakdown.is_empty() {
+            bail!(
+                "The planned run needs more than this host allows:\n{}\
+                 Lower --buffer-size, --concurrency or --parallel-mechanisms, raise the limits, \
+                 or pass --skip-footprint-check",
+                breakdown
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Number of ports in an `ip_local_port_range` such as "32768\t60999".
+fn parse_port_range(range: &str) -> Option<u64> {
+    let mut bounds = range.split_whitespace().map(|b| b.parse::<u64>());
+    let (low, high) = (bounds.next()?.ok()?, bounds.next()?.ok()?);
+    (high >= low).then(|| high - low + 1)
+}
+
+fn read_proc_u64(path: &str) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(unix)]
+fn open_files_limit() -> Option<u64> {
+    let mut limit: libc::rlimit = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    (limit.rlim_cur != libc::RLIM_INFINITY).then_some(limit.rlim_cur as u64)
+}
+
+#[cfg(not(unix))]
+fn open_files_limit() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demand(mechanism: IpcMechanism, shm_bytes: u64, fds: u64, ports: u64) -> Demand {
+        Demand {
+            mechanism,
+            shm_bytes,
+            mq_queues: 0,
+            mq_bytes: 0,
+            fds,
+            ports,
+        }
+    }
+
+    #[test]
+    fn test_peak_sums_groups_that_run_together() {
+        let footprint = Footprint {
+            groups: vec![
+                vec![demand(IpcMechanism::SharedMemory, 1 << 20, 1, 0)],
+                vec![
+                    demand(IpcMechanism::SharedMemory, 1 << 20, 1, 0),
+                    demand(IpcMechanism::TcpSocket, 0, 8, 9),
+                ],
+            ],
+        };
+        assert_eq!(footprint.peak(Resource::SharedMemory).0, 1 << 20);
+        assert_eq!(
+            footprint.peak(Resource::FileDescriptors).0,
+            BASELINE_FDS + 9
+        );
+        assert_eq!(footprint.peak(Resource::EphemeralPorts).1.len(), 2);
+        assert_eq!(
+            Footprint::default().peak(Resource::FileDescriptors).0,
+            BASELINE_FDS
+        );
+    }
+
+    #[test]
+    fn test_check_lists_each_exhausted_resource() {
+        let footprint = Footprint {
+            groups: vec![vec![
+                demand(IpcMechanism::SharedMemory, 4 << 20, 1, 0),
+                demand(IpcMechanism::TcpSocket, 0, 100, 101),
+            ]],
+        };
+        let roomy = HostLimits {
+            shm_free_bytes: Some(64 << 20),
+            open_files: Some(1024),
+            ..Default::default()
+        };
+        assert!(footprint.check(&roomy).is_ok());
+        assert!(footprint.check(&HostLimits::default()).is_ok());
+
+        let tight = HostLimits {
+            shm_free_bytes: Some(1 << 20),
+            open_files: Some(64),
+            ephemeral_ports: Some(1000),
+            ..Default::default()
+        };
+        let message = footprint.check(&tight).unwrap_err().to_string();
+        assert!(message.contains("/dev/shm: 4.00 MiB needed, 1.00 MiB available"));
+        assert!(message.contains("open files: 133 needed, 64 available (ulimit -n)"));
+        assert!(message.contains("    TCP Socket: 100"));
+        assert!(!message.contains("ephemeral ports"));
+    }
+
+    #[test]
+    fn test_parse_port_range() {
+        assert_eq!(parse_port_range("32768\t60999\n"), Some(28232));
+        assert_eq!(parse_port_range("100 99"), None);
+        assert_eq!(parse_port_range("garbage"), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Throughput within a latency budget.
+//!
+//! Raw throughput counts every message, however late it arrived. Capacity
+//! planning for a latency-sensitive system needs the rate it sustains while
+//! meeting its latency target instead. With `--goodput-budget DURATION`,
+//! each test reports the messages whose latency stayed within the budget
+//! and the message and byte rates they make up over the test, alongside the
+//! raw throughput. Messages are counted from the test's latency histogram,
+//! so the budget is matched to the histogram's three significant figures.
+
+use crate::metrics::utils::{format_latency, format_throughput};
+use crate::metrics::PerformanceMetrics;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Throughput of the messages that met a latency budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Goodput {
+    /// Latency budget in nanoseconds
+    pub budget_ns: u64,
+
+    /// Messages whose latency was within the budget
+    pub messages: u64,
+
+    /// Share of the latency samples within the budget, in percent
+    pub percent: f64,
+
+    /// Rate of the messages within the budget, in messages per second
+    pub messages_per_second: f64,
+
+    /// Rate of the messages within the budget, in bytes per second
+    pub bytes_per_second: f64,
+}
+
+impl Goodput {
+    /// Goodput of a test under `budget`, or `None` when the test measured
+    /// no latency or its histogram is gone, as after reading JSON back.
+    pub fn measure(metrics: &PerformanceMetrics, budget: Duration) -> Option<Self> {
+        let latency = metrics.latency.as_ref()?;
+        let histogram = latency.histogram.as_ref()?;
+        if latency.total_samples == 0 {
+            return None;
+        }
+        let budget_ns = budget.as_nanos() as u64;
+        let messages = histogram.count_between(0, budget_ns);
+        let share = messages as f64 / latency.total_samples as f64;
+        Some(Self {
+            budget_ns,
+            messages,
+            percent: share * 100.0,
+            messages_per_second: metrics.throughput.messages_per_second * share,
+            bytes_per_second: metrics.throughput.bytes_per_second * share,
+        })
+    }
+}
+
+impl fmt::Display for Goodput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} msg/s ({}) within {}, {:.2}% of messages",
+            self.messages_per_second,
+            format_throughput(self.bytes_per_second),
+            format_latency(self.budget_ns),
+            self.percent
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{LatencyType, MetricsCollector};
+
+    #[test]
+    fn test_goodput_counts_messages_within_the_budget() {
+        let mut collector =
+            MetricsCollector::new(Some(LatencyType::RoundTrip), vec![50.0]).unwrap();
+        for us in [10, 20, 30, 400] {
+            collector
+                .record_message(100, Some(Duration::from_micros(us)))
+                .unwrap();
+        }
+        let mut metrics = collector.get_metrics();
+        metrics.throughput.messages_per_second = 1000.0;
+        metrics.throughput.bytes_per_second = 100_000.0;
+
+        let goodput = Goodput::measure(&metrics, Duration::from_micros(100)).unwrap();
+        assert_eq!(goodput.messages, 3);
+        assert_eq!(goodput.percent, 75.0);
+        assert_eq!(goodput.messages_per_second, 750.0);
+        assert_eq!(goodput.bytes_per_second, 75_000.0);
+        assert_eq!(
+            goodput.to_string(),
+            "750 msg/s (73.24 KiB/s) within 100.00μs, 75.00% of messages"
+        );
+
+        // Every message fits a generous budget
+        let all = Goodput::measure(&metrics, Duration::from_millis(1)).unwrap();
+        assert_eq!(all.messages, 4);
+
+        metrics.latency.as_mut().unwrap().histogram = None;
+        assert!(Goodput::measure(&metrics, Duration::from_micros(100)).is_none());
+    }
+}
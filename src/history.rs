@@ -0,0 +1,270 @@
+//! Long-lived summary CSV appended across runs.
+//!
+//! The JSON results of a run are complete but one file per run; plotting a
+//! trend over weeks of nightly runs means collecting and parsing all of
+//! them. With `--append-summary history.csv`, each run also appends one row
+//! per mechanism to a CSV that outlives it: when the test ran, the run ID,
+//! the `--summary-label` labels, median and P99 latency, and throughput.
+//! Any spreadsheet or plotting tool can read the file as it grows.
+//!
+//! The header is written when the file is created. Appending to a file
+//! whose header differs, for example one written by a release with other
+//! columns, is an error rather than a silent mix of layouts. Latencies are
+//! in nanoseconds and left empty for tests that did not run or did not
+//! compute that percentile.
+
+#[cfg(feature = "cli")]
+use crate::cli::Args;
+use crate::metrics::PerformanceMetrics;
+use crate::results::{percentile_ns, BenchmarkResults, BenchmarkStatus};
+use anyhow::{bail, Context, Result};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Columns of the summary CSV, in order
+pub const COLUMNS: [&str; 13] = [
+    "timestamp",
+    "run_id",
+    "labels",
+    "mechanism",
+    "message_size",
+    "concurrency",
+    "status",
+    "one_way_p50_ns",
+    "one_way_p99_ns",
+    "one_way_messages_per_second",
+    "round_trip_p50_ns",
+    "round_trip_p99_ns",
+    "round_trip_messages_per_second",
+];
+
+/// A summary CSV and the labels given to the rows appended to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryHistory {
+    path: PathBuf,
+    labels: Vec<(String, String)>,
+}
+
+impl SummaryHistory {
+    /// Rows appended to `path`, labelled with `labels`.
+    pub fn new(path: &Path, labels: Vec<(String, String)>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            labels,
+        }
+    }
+
+    /// The history `--append-summary` and `--summary-label` ask for, if any.
+    #[cfg(feature = "cli")]
+    pub fn from_args(args: &Args) -> Option<Self> {
+        args.append_summary
+            .as_deref()
+            .map(|path| Self::new(path, args.summary_labels.clone()))
+    }
+
+    /// Append one row per result, creating the file with its header if it
+    /// does not exist. Returns the number of rows appended.
+    pub fn append(&self, results: &[BenchmarkResults]) -> Result<usize> {
+        let header = COLUMNS.join(",");
+        let existing = match std::fs::File::open(&self.path) {
+            Ok(file) => BufReader::new(file).lines().next().transpose()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(existing) = &existing {
+            if existing.trim_end() != header {
+                bail!(
+                    "{} has different summary columns ({}); append to a new file",
+                    self.path.display(),
+                    existing
+                );
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        let mut text = String::new();
+        if existing.is_none() {
+            text.push_str(&header);
+            text.push('\n');
+        }
+        for result in results {
+            text.push_str(&self.row(result).join(","));
+            text.push('\n');
+        }
+        // One write, so concurrent runs appending to the same file do not
+        // interleave their rows
+        file.write_all(text.as_bytes())
+            .with_context(|| format!("Failed to append to {}", self.path.display()))?;
+        Ok(results.len())
+    }
+
+    /// The CSV fields of `result`'s row.
+    fn row(&self, result: &BenchmarkResults) -> Vec<String> {
+        let labels = self
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(";");
+        let status = match &result.status {
+            BenchmarkStatus::Success => "success",
+            BenchmarkStatus::Failure(_) => "failure",
+            BenchmarkStatus::Aborted => "aborted",
+            BenchmarkStatus::Skipped(_) => "skipped",
+        };
+        let mut row = vec![
+            result.timestamp.to_rfc3339(),
+            crate::run_id::get().to_string(),
+            labels,
+            result.mechanism.to_string(),
+            result.test_config.message_size.to_string(),
+            result.test_config.concurrency.to_string(),
+            status.to_string(),
+        ];
+        for metrics in [&result.one_way_results, &result.round_trip_results] {
+            row.extend(metrics_fields(metrics.as_ref()));
+        }
+        row.into_iter().map(|field| csv_field(&field)).collect()
+    }
+}
+
+/// Median and P99 latency and message throughput of one test, empty where
+/// unknown.
+fn metrics_fields(metrics: Option<&PerformanceMetrics>) -> [String; 3] {
+    let Some(metrics) = metrics else {
+        return Default::default();
+    };
+    let percentile = |p: f64| {
+        metrics
+            .latency
+            .as_ref()
+            .and_then(|latency| percentile_ns(latency, p))
+            .map(|ns| ns.to_string())
+            .unwrap_or_default()
+    };
+    [
+        percentile(50.0),
+        percentile(99.0),
+        format!("{:.1}", metrics.throughput.messages_per_second),
+    ]
+}
+
+/// `field` quoted for CSV if it needs to be.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parse a `--summary-label` value of the form `KEY=VALUE`.
+pub fn parse_label(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() && !key.contains(';') && !value.contains(';') => {
+            Ok((key.to_string(), value.to_string()))
+        }
+        _ => Err(format!(
+            "invalid label '{}': expected KEY=VALUE without ';'",
+            s
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mechanism::IpcMechanism;
+    use crate::metrics::{LatencyCollector, LatencyType, ThroughputMetrics};
+    use std::time::Duration;
+
+    fn result(mechanism: IpcMechanism) -> BenchmarkResults {
+        let mut collector = LatencyCollector::new(LatencyType::OneWay).unwrap();
+        for i in 0..10 {
+            let latency_ns = if i == 9 { 2000 } else { 1000 };
+            collector.record(Duration::from_nanos(latency_ns)).unwrap();
+        }
+        let mut result =
+            BenchmarkResults::new(mechanism, 64, 1024, 1, Some(10), None, 0, true, false);
+        result.add_one_way_results(PerformanceMetrics {
+            latency: Some(collector.get_metrics(&[50.0, 99.0])),
+            throughput: ThroughputMetrics {
+                messages_per_second: 250000.0,
+                bytes_per_second: 16000000.0,
+                total_messages: 10,
+                total_bytes: 640,
+                duration_ns: 40_000,
+            },
+            error_count: 0,
+            expired_count: 0,
+            priority_latency: Vec::new(),
+            phase_latency: Vec::new(),
+            connection_latency: Vec::new(),
+            processing_latency: Vec::new(),
+            cpu_split: Vec::new(),
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_message: None,
+            first_latency_ns: None,
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            latency_reservoir: None,
+            goodput: None,
+            sla: Vec::new(),
+            timestamp: chrono::Utc::now(),
+        });
+        result
+    }
+
+    #[test]
+    fn test_rows_append_under_one_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.csv");
+        let labels = vec![
+            ("host".to_string(), "lab-1".to_string()),
+            ("kernel".to_string(), "6.8, rt".to_string()),
+        ];
+        let history = SummaryHistory::new(&path, labels);
+        history.append(&[result(IpcMechanism::TcpSocket)]).unwrap();
+        history.append(&[result(IpcMechanism::Http)]).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert!(lines[1].contains(",\"host=lab-1;kernel=6.8, rt\",TCP Socket,64,1,success,"));
+        assert!(lines[1].ends_with(",1000,2000,250000.0,,,"), "{}", lines[1]);
+        assert!(lines[2].contains("HTTP"));
+    }
+
+    #[test]
+    fn test_foreign_header_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.csv");
+        std::fs::write(&path, "timestamp,mechanism\n").unwrap();
+        let history = SummaryHistory::new(&path, Vec::new());
+        let error = history.append(&[]).unwrap_err();
+        assert!(error.to_string().contains("different summary columns"));
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_label("build=nightly-42"),
+            Ok(("build".to_string(), "nightly-42".to_string()))
+        );
+        assert_eq!(parse_label("empty="), Ok(("empty".into(), String::new())));
+        assert!(parse_label("novalue").is_err());
+        assert!(parse_label("=x").is_err());
+        assert!(parse_label("a=b;c").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Per-message processing run by the client and server.
+//!
+//! Transport latency alone understates what an application sees: every
+//! message is also deserialized, validated and acted on. A
+//! [`MessageHook`] adds that cost to the benchmark. `--client-work` and
+//! `--server-work` busy-wait a fixed time per message ([`SimulatedWork`]).
+//! `--server-spin` instead runs a fixed number of iterations of a compute
+//! kernel ([`SpinWork`]), a cost in CPU work rather than time that grows
+//! when the server's core is slowed or shared. Library users can run real code instead by handing a hook, or any
+//! `Fn(&Message)`, to a runner with `with_client_hook`.
+//!
+//! The client runs its hook on each one-way message before sending it,
+//! and on each round-trip request once its reply has arrived, outside the
+//! measured transport latency. The time it takes is reported as
+//! `client hook` processing latency, and transport latency plus that time
+//! as `end-to-end`. The server runs its hook on each message it receives
+//! and reports the time as `processing` in its report; for round trips,
+//! that time is part of the round-trip latency.
+//!
+//! ```rust
+//! use ipc_benchmark::{hook::MessageHook, Message};
+//!
+//! /// Checksum every payload, as a receiver validating frames would
+//! fn checksum(message: &Message) {
+//!     let sum = message.payload.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+//!     std::hint::black_box(sum);
+//! }
+//!
+//! let hook: std::sync::Arc<dyn MessageHook> = std::sync::Arc::new(checksum);
+//! ```
+
+use crate::cli::Args;
+use crate::ipc::{get_monotonic_time_ns, Message};
+use std::time::Duration;
+
+/// Application-level work done for each benchmark message.
+///
+/// Called on the measurement path, once per message. Hooks are shared
+/// between workers and must be `Send + Sync`.
+pub trait MessageHook: Send + Sync {
+    /// Process `message`.
+    fn process(&self, message: &Message);
+}
+
+impl<F: Fn(&Message) + Send + Sync> MessageHook for F {
+    fn process(&self, message: &Message) {
+        self(message)
+    }
+}
+
+/// Busy-waits a fixed time per message, standing in for CPU-bound work.
+///
+/// Spins on the monotonic clock rather than sleeping, so the work holds
+/// the CPU as real processing would, and costs the same on every message.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulatedWork {
+    duration: Duration,
+}
+
+impl SimulatedWork {
+    /// Work taking `duration` per message.
+    pub fn new(duration: Duration) -> Self {
+        Self { duration }
+    }
+}
+
+impl MessageHook for SimulatedWork {
+    fn process(&self, _message: &Message) {
+        let end = get_monotonic_time_ns().saturating_add(self.duration.as_nanos() as u64);
+        while get_monotonic_time_ns() < end {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Runs a fixed number of iterations of a compute kernel per message.
+///
+/// Each iteration is one xorshift step on the previous result, so the
+/// iterations can be neither vectorized nor skipped, and touch no memory.
+/// The time they take depends on the CPU: the same count costs more on a
+/// slower, throttled or shared core.
+#[derive(Debug, Clone, Copy)]
+pub struct SpinWork {
+    iterations: u64,
+}
+
+impl SpinWork {
+    /// Work of `iterations` kernel steps per message.
+    pub fn new(iterations: u64) -> Self {
+        Self { iterations }
+    }
+}
+
+impl MessageHook for SpinWork {
+    fn process(&self, message: &Message) {
+        let mut state = message.id | 1;
+        for _ in 0..std::hint::black_box(self.iterations) {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+        }
+        std::hint::black_box(state);
+    }
+}
+
+/// The server's per-message work: `--server-work` or `--server-spin`.
+pub fn server_work(args: &Args) -> Option<Box<dyn MessageHook>> {
+    match (args.server_work, args.server_spin) {
+        (Some(duration), _) => Some(Box::new(SimulatedWork::new(duration))),
+        (None, Some(iterations)) => Some(Box::new(SpinWork::new(iterations))),
+        (None, None) => None,
+    }
+}
+
+/// Run `hook` on `message`, returning how long it took.
+pub fn run_timed(hook: &dyn MessageHook, message: &Message) -> Duration {
+    let start = get_monotonic_time_ns();
+    hook.process(message);
+    Duration::from_nanos(get_monotonic_time_ns().saturating_sub(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_simulated_work_takes_its_duration() {
+        let message = Message::new(1, Vec::new(), MessageType::OneWay);
+        let work = SimulatedWork::new(Duration::from_micros(200));
+        assert!(run_timed(&work, &message) >= Duration::from_micros(200));
+    }
+
+    #[test]
+    fn test_spin_work_scales_with_iterations() {
+        let message = Message::new(1, Vec::new(), MessageType::OneWay);
+        let short = SpinWork::new(1_000);
+        let long = SpinWork::new(1_000_000);
+        // Best of a few runs, so a preemption does not decide the comparison
+        let fastest = |work: &SpinWork| (0..5).map(|_| run_timed(work, &message)).min().unwrap();
+        assert!(fastest(&long) > fastest(&short));
+    }
+
+    #[test]
+    fn test_closures_are_hooks() {
+        let seen = AtomicU64::new(0);
+        let hook = |message: &Message| {
+            seen.fetch_add(message.id, Ordering::Relaxed);
+        };
+        run_timed(&hook, &Message::new(7, Vec::new(), MessageType::Request));
+        assert_eq!(seen.load(Ordering::Relaxed), 7);
+    }
+}
@@ -0,0 +1,468 @@
+//! Operating system details that bound IPC results.
+//!
+//! Kernel and distribution identify the software a run measured. The kernel
+//! limits captured in [`IpcLimits`] cap what the mechanisms can do at all:
+//! POSIX message queue depth and message size, the largest shared memory
+//! segment and the largest socket buffers. Recording them with the results
+//! keeps a run reproducible on another host.
+//!
+//! [`Virtualization`] answers whether the run was on bare metal, in a
+//! container or in a virtual machine. Containers are recognized by runtime
+//! marker files, environment variables and cgroup paths; hypervisors by the
+//! CPUID hypervisor leaf on x86 and by DMI strings elsewhere. Both are
+//! heuristics, so an unrecognized environment reads as bare metal.
+
+use crate::IpcMechanism;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Kernel limits relevant to the benchmarked mechanisms.
+///
+/// Each field is `None` where the limit could not be read, which includes
+/// every non-Linux platform.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpcLimits {
+    /// `fs.mqueue.msg_max`: maximum messages in a POSIX message queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mqueue_msg_max: Option<u64>,
+
+    /// `fs.mqueue.msgsize_max`: maximum POSIX message queue message size, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mqueue_msgsize_max: Option<u64>,
+
+    /// `kernel.shmmax`: maximum shared memory segment size, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shmmax: Option<u64>,
+
+    /// `net.core.rmem_max`: maximum socket receive buffer, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rmem_max: Option<u64>,
+
+    /// `net.core.wmem_max`: maximum socket send buffer, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wmem_max: Option<u64>,
+
+    /// `ulimit -q`: bytes this process may allocate to POSIX message queues
+    ///
+    /// `None` when unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mqueue_rlimit_bytes: Option<u64>,
+}
+
+impl IpcLimits {
+    /// Read the limits in effect for this process.
+    #[cfg(target_os = "linux")]
+    pub fn query() -> Self {
+        Self {
+            mqueue_msg_max: read_sysctl("fs/mqueue/msg_max"),
+            mqueue_msgsize_max: read_sysctl("fs/mqueue/msgsize_max"),
+            shmmax: read_sysctl("kernel/shmmax"),
+            rmem_max: read_sysctl("net/core/rmem_max"),
+            wmem_max: read_sysctl("net/core/wmem_max"),
+            mqueue_rlimit_bytes: mqueue_rlimit(),
+        }
+    }
+
+    /// Read the limits in effect for this process.
+    #[cfg(not(target_os = "linux"))]
+    pub fn query() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_sysctl(name: &str) -> Option<u64> {
+    std::fs::read_to_string(format!("/proc/sys/{name}"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn mqueue_rlimit() -> Option<u64> {
+    let mut limit: libc::rlimit = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrlimit(libc::RLIMIT_MSGQUEUE, &mut limit) } != 0 {
+        return None;
+    }
+    (limit.rlim_cur != libc::RLIM_INFINITY).then_some(limit.rlim_cur)
+}
+
+/// Kernel release, e.g. "6.8.0-45-generic", from `uname(2)`.
+#[cfg(unix)]
+pub fn kernel_version() -> Option<String> {
+    let mut name: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut name) } != 0 {
+        return None;
+    }
+    let release = unsafe { std::ffi::CStr::from_ptr(name.release.as_ptr()) };
+    Some(release.to_string_lossy().into_owned())
+}
+
+/// Kernel release, e.g. "6.8.0-45-generic", from `uname(2)`.
+#[cfg(not(unix))]
+pub fn kernel_version() -> Option<String> {
+    None
+}
+
+/// Distribution name, e.g. "Fedora Linux 40 (Server Edition)", from
+/// `/etc/os-release`.
+pub fn distribution() -> Option<String> {
+    ["/etc/os-release", "/usr/lib/os-release"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| parse_os_release(&contents))
+}
+
+/// Take `PRETTY_NAME` from os-release, falling back to `NAME` and `VERSION_ID`.
+fn parse_os_release(contents: &str) -> Option<String> {
+    let value = |key: &str| {
+        contents.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim().trim_matches(|c| c == '"' || c == '\''))
+        })
+    };
+    if let Some(pretty) = value("PRETTY_NAME").filter(|v| !v.is_empty()) {
+        return Some(pretty.to_string());
+    }
+    let name = value("NAME")?;
+    Some(match value("VERSION_ID") {
+        Some(version) => format!("{name} {version}"),
+        None => name.to_string(),
+    })
+}
+
+/// Where the benchmark ran: container, virtual machine, both or neither.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Virtualization {
+    /// Container runtime (e.g., "docker", "podman", "kubernetes"), or `None`
+    /// outside a recognized container
+    #[serde(default)]
+    pub container: Option<String>,
+
+    /// Hypervisor (e.g., "kvm", "vmware", "hyperv"), or `None` on bare metal
+    #[serde(default)]
+    pub hypervisor: Option<String>,
+}
+
+impl Virtualization {
+    /// Detect the container runtime and hypervisor of this process.
+    pub fn detect() -> Self {
+        Self {
+            container: detect_container(),
+            hypervisor: detect_hypervisor(),
+        }
+    }
+
+    /// Warnings for split client/server runs whose traffic crosses a
+    /// container or VM boundary that may not be intended.
+    ///
+    /// `host` is the TCP address the run connects to or listens on.
+    pub fn boundary_warnings(&self, mechanism: IpcMechanism, host: &str) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let container = self.container.as_deref();
+        match mechanism {
+            IpcMechanism::SharedMemory => {
+                if let Some(runtime) = container {
+                    warnings.push(format!(
+                        "Running in a {runtime} container: a peer in another container only sees \
+                         the shared memory segment if both share an IPC namespace and /dev/shm \
+                         (e.g. --ipc=host)"
+                    ));
+                }
+            }
+            #[cfg(target_os = "linux")]
+            IpcMechanism::PosixMessageQueue => {
+                if let Some(runtime) = container {
+                    warnings.push(format!(
+                        "Running in a {runtime} container: a peer in another container only sees \
+                         the message queue if both share an IPC namespace (e.g. --ipc=host)"
+                    ));
+                }
+            }
+            #[cfg(unix)]
+            IpcMechanism::UnixDomainSocket => {
+                if let Some(runtime) = container {
+                    warnings.push(format!(
+                        "Running in a {runtime} container: a peer in another container can only \
+                         reach the socket through a volume both containers mount"
+                    ));
+                }
+            }
+            IpcMechanism::TcpSocket => {
+                let loopback = host
+                    .parse::<IpAddr>()
+                    .map_or(host == "localhost", |ip| ip.is_loopback());
+                if loopback {
+                    if let Some(runtime) = container {
+                        warnings.push(format!(
+                            "Running in a {runtime} container: {host} is private to this \
+                             container's network namespace, so the peer must share it \
+                             (same pod or --network=host)"
+                        ));
+                    }
+                } else if let Some(boundary) = self.boundary_name() {
+                    warnings.push(format!(
+                        "Running in a {boundary}: TCP traffic to {host} crosses its virtual \
+                         network, and latency includes that overhead"
+                    ));
+                }
+            }
+            _ => {}
+        }
+        warnings
+    }
+
+    fn boundary_name(&self) -> Option<String> {
+        match (&self.container, &self.hypervisor) {
+            (Some(runtime), _) => Some(format!("{runtime} container")),
+            (None, Some(hypervisor)) => Some(format!("{hypervisor} VM")),
+            (None, None) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Virtualization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.container, &self.hypervisor) {
+            (None, None) => write!(f, "bare metal"),
+            (Some(runtime), None) => write!(f, "{runtime} container"),
+            (None, Some(hypervisor)) => write!(f, "{hypervisor} VM"),
+            (Some(runtime), Some(hypervisor)) => {
+                write!(f, "{runtime} container in {hypervisor} VM")
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn detect_container() -> Option<String> {
+    use std::path::Path;
+
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        return Some("kubernetes".to_string());
+    }
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    // Set by podman, systemd-nspawn and LXC for the container's init
+    if let Some(runtime) = std::env::var("container").ok().filter(|v| !v.is_empty()) {
+        return Some(runtime);
+    }
+    ["/proc/1/cgroup", "/proc/self/cgroup"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .find_map(|contents| container_from_cgroup(&contents))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_container() -> Option<String> {
+    None
+}
+
+/// Recognize a container runtime from the cgroup paths in `/proc/<pid>/cgroup`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn container_from_cgroup(contents: &str) -> Option<String> {
+    const RUNTIMES: &[(&str, &str)] = &[
+        ("kubepods", "kubernetes"),
+        ("libpod", "podman"),
+        ("docker", "docker"),
+        ("containerd", "containerd"),
+        ("lxc", "lxc"),
+    ];
+    contents.lines().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        RUNTIMES
+            .iter()
+            .find(|(marker, _)| path.contains(marker))
+            .map(|(_, runtime)| runtime.to_string())
+    })
+}
+
+fn detect_hypervisor() -> Option<String> {
+    cpuid_hypervisor().or_else(dmi_hypervisor)
+}
+
+/// The hypervisor announced through CPUID: leaf 1 ECX bit 31 says one is
+/// present and leaf 0x4000_0000 carries its vendor signature.
+#[cfg(target_arch = "x86_64")]
+#[allow(unused_unsafe)]
+fn cpuid_hypervisor() -> Option<String> {
+    use std::arch::x86_64::__cpuid;
+
+    if unsafe { __cpuid(1) }.ecx & (1 << 31) == 0 {
+        return None;
+    }
+    let leaf = unsafe { __cpuid(0x4000_0000) };
+    let mut signature = [0u8; 12];
+    signature[..4].copy_from_slice(&leaf.ebx.to_le_bytes());
+    signature[4..8].copy_from_slice(&leaf.ecx.to_le_bytes());
+    signature[8..].copy_from_slice(&leaf.edx.to_le_bytes());
+    Some(hypervisor_from_cpuid_signature(&signature))
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cpuid_hypervisor() -> Option<String> {
+    None
+}
+
+fn hypervisor_from_cpuid_signature(signature: &[u8; 12]) -> String {
+    let name = match signature {
+        b"KVMKVMKVM\0\0\0" => "kvm",
+        b"Microsoft Hv" => "hyperv",
+        b"VMwareVMware" => "vmware",
+        b"XenVMMXenVMM" => "xen",
+        b"TCGTCGTCGTCG" => "qemu",
+        b"VBoxVBoxVBox" => "virtualbox",
+        b"bhyve bhyve " => "bhyve",
+        b"ACRNACRNACRN" => "acrn",
+        b" lrpepyh  vr" => "parallels",
+        _ => "unknown",
+    };
+    name.to_string()
+}
+
+/// The hypervisor named by the DMI system vendor and product, for
+/// architectures without a CPUID hypervisor leaf.
+#[cfg(target_os = "linux")]
+fn dmi_hypervisor() -> Option<String> {
+    let read = |name: &str| {
+        std::fs::read_to_string(format!("/sys/class/dmi/id/{name}")).unwrap_or_default()
+    };
+    hypervisor_from_dmi(&read("sys_vendor"), &read("product_name")).or_else(|| {
+        std::fs::read_to_string("/sys/hypervisor/type")
+            .ok()
+            .map(|kind| kind.trim().to_string())
+            .filter(|kind| !kind.is_empty())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn dmi_hypervisor() -> Option<String> {
+    None
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn hypervisor_from_dmi(vendor: &str, product: &str) -> Option<String> {
+    let vendor = vendor.trim();
+    let product = product.trim();
+    let name = if vendor == "QEMU" || product.starts_with("KVM") {
+        "kvm"
+    } else if vendor.starts_with("VMware") {
+        "vmware"
+    } else if vendor == "innotek GmbH" || product == "VirtualBox" {
+        "virtualbox"
+    } else if vendor == "Xen" {
+        "xen"
+    } else if vendor == "Microsoft Corporation" && product == "Virtual Machine" {
+        "hyperv"
+    } else if vendor == "Amazon EC2" {
+        "amazon"
+    } else if vendor == "Google" && product == "Google Compute Engine" {
+        "google"
+    } else {
+        return None;
+    };
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_os_release() {
+        let fedora = "NAME=\"Fedora Linux\"\nVERSION_ID=40\n\
+                      PRETTY_NAME=\"Fedora Linux 40 (Server Edition)\"\n";
+        assert_eq!(
+            parse_os_release(fedora).as_deref(),
+            Some("Fedora Linux 40 (Server Edition)")
+        );
+        let minimal = "NAME='Alpine Linux'\nVERSION_ID=3.20.0\n";
+        assert_eq!(
+            parse_os_release(minimal).as_deref(),
+            Some("Alpine Linux 3.20.0")
+        );
+        assert!(parse_os_release("ID=unknown\n").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kernel_version() {
+        assert!(!kernel_version().unwrap().is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_ipc_limits_query() {
+        let limits = IpcLimits::query();
+        assert!(limits.shmmax.is_some());
+    }
+
+    #[test]
+    fn test_container_from_cgroup() {
+        let docker = "12:memory:/docker/3f2a9c\n0::/docker/3f2a9c\n";
+        assert_eq!(container_from_cgroup(docker).as_deref(), Some("docker"));
+        let k8s = "0::/kubepods.slice/kubepods-burstable.slice/cri-containerd-ab12.scope\n";
+        assert_eq!(container_from_cgroup(k8s).as_deref(), Some("kubernetes"));
+        assert!(container_from_cgroup("0::/user.slice/session-2.scope\n").is_none());
+    }
+
+    #[test]
+    fn test_hypervisor_signatures() {
+        assert_eq!(hypervisor_from_cpuid_signature(b"KVMKVMKVM\0\0\0"), "kvm");
+        assert_eq!(hypervisor_from_cpuid_signature(b"Microsoft Hv"), "hyperv");
+        assert_eq!(hypervisor_from_cpuid_signature(b"NewHypervsr!"), "unknown");
+        assert_eq!(
+            hypervisor_from_dmi("QEMU\n", "Standard PC (Q35 + ICH9, 2009)\n").as_deref(),
+            Some("kvm")
+        );
+        assert_eq!(
+            hypervisor_from_dmi("Amazon EC2\n", "m7i.large\n").as_deref(),
+            Some("amazon")
+        );
+        assert!(hypervisor_from_dmi("Dell Inc.\n", "PowerEdge R760\n").is_none());
+    }
+
+    #[test]
+    fn test_boundary_warnings() {
+        let bare = Virtualization::default();
+        assert_eq!(bare.to_string(), "bare metal");
+        assert!(bare
+            .boundary_warnings(IpcMechanism::SharedMemory, "127.0.0.1")
+            .is_empty());
+        assert!(bare
+            .boundary_warnings(IpcMechanism::TcpSocket, "10.0.0.2")
+            .is_empty());
+
+        let vm = Virtualization {
+            container: None,
+            hypervisor: Some("kvm".to_string()),
+        };
+        assert!(vm
+            .boundary_warnings(IpcMechanism::SharedMemory, "127.0.0.1")
+            .is_empty());
+        assert_eq!(
+            vm.boundary_warnings(IpcMechanism::TcpSocket, "10.0.0.2")
+                .len(),
+            1
+        );
+
+        let container = Virtualization {
+            container: Some("podman".to_string()),
+            ..vm
+        };
+        assert_eq!(container.to_string(), "podman container in kvm VM");
+        assert_eq!(
+            container
+                .boundary_warnings(IpcMechanism::SharedMemory, "127.0.0.1")
+                .len(),
+            1
+        );
+        let tcp = container.boundary_warnings(IpcMechanism::TcpSocket, "localhost");
+        assert!(tcp[0].contains("network namespace"));
+    }
+}
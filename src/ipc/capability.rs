@@ -0,0 +1,189 @@
+//! Whether a mechanism can run on this host at all.
+//!
+//! `-m all` expands to every mechanism built for the platform, but one of
+//! them can still be unusable where the suite runs: a container without a
+//! POSIX message queue filesystem, a read-only `/dev/shm`, a sandbox with no
+//! loopback interface, a `queues_max` that is already used up. Rather than
+//! failing the whole suite on such a mechanism, or leaving it out of the
+//! results without a word, the runners call [`probe`] first. It tries the
+//! one system facility the mechanism depends on, undoes whatever it
+//! created, and returns why the mechanism cannot run; the mechanism is then
+//! recorded as skipped with that reason.
+//!
+//! Probes only catch what can be checked in a few syscalls. A mechanism
+//! that passes can still fail during its test, and is then reported as
+//! failed.
+
+use crate::mechanism::IpcMechanism;
+use std::io;
+
+/// Why `mechanism` cannot run on this host, or `None` if it can.
+///
+/// `host` is the address network transports bind to.
+pub fn probe(mechanism: &IpcMechanism, host: &str) -> Option<String> {
+    let result = match mechanism {
+        #[cfg(unix)]
+        IpcMechanism::UnixDomainSocket => probe_unix_socket(),
+        #[cfg(unix)]
+        IpcMechanism::Socketpair => std::os::unix::net::UnixStream::pair()
+            .map(drop)
+            .map_err(|e| describe("socketpair() failed", e)),
+        IpcMechanism::SharedMemory => probe_shared_memory(),
+        #[cfg(target_os = "linux")]
+        IpcMechanism::Futex | IpcMechanism::RtSignal | IpcMechanism::Inotify => {
+            probe_shared_memory()
+        }
+        IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc => {
+            std::net::TcpListener::bind((host, 0))
+                .map(drop)
+                .map_err(|e| describe(&format!("Cannot bind a TCP socket on {}", host), e))
+        }
+        #[cfg(all(target_os = "linux", feature = "pmq"))]
+        IpcMechanism::PosixMessageQueue => probe_message_queue(),
+        #[cfg(all(target_os = "linux", not(feature = "pmq")))]
+        IpcMechanism::PosixMessageQueue => {
+            Err("not built in; rebuild with the `pmq` feature".to_string())
+        }
+        #[cfg(target_os = "linux")]
+        IpcMechanism::UdpMulticast => probe_multicast(host),
+        #[cfg(windows)]
+        IpcMechanism::NamedPipe => probe_named_pipe(),
+        IpcMechanism::All => Ok(()),
+    };
+    result.err()
+}
+
+/// `what` failed with `e`, with a hint at the usual cause.
+fn describe(what: &str, e: io::Error) -> String {
+    let hint = match e.raw_os_error() {
+        Some(libc::EACCES) | Some(libc::EPERM) | Some(libc::EROFS) => "permission denied",
+        Some(libc::ENOSYS) | Some(libc::ENOENT) | Some(libc::ENODEV) => {
+            "not supported or not mounted on this host"
+        }
+        Some(libc::EMFILE) | Some(libc::ENFILE) | Some(libc::ENOSPC) | Some(libc::ENOMEM) => {
+            "a system limit is exhausted"
+        }
+        Some(libc::EADDRNOTAVAIL) => "no such interface address",
+        _ => return format!("{}: {}", what, e),
+    };
+    format!("{}: {}; {}", what, e, hint)
+}
+
+/// Name for a probe resource that cannot clash with a concurrent run.
+#[cfg(any(unix, windows))]
+fn probe_name() -> String {
+    format!("ipc_probe_{}", uuid::Uuid::new_v4().simple())
+}
+
+#[cfg(unix)]
+fn probe_unix_socket() -> Result<(), String> {
+    let dir = crate::utils::get_temp_dir();
+    let path = dir.join(format!("{}.sock", &probe_name()[..18]));
+    let listener = std::os::unix::net::UnixListener::bind(&path).map_err(|e| {
+        describe(
+            &format!("Cannot bind a Unix socket in {}", dir.display()),
+            e,
+        )
+    });
+    let _ = std::fs::remove_file(&path);
+    listener.map(drop)
+}
+
+#[cfg(unix)]
+fn probe_shared_memory() -> Result<(), String> {
+    let name = std::ffi::CString::new(format!("/{}", probe_name())).expect("no NUL in name");
+    let fd = unsafe {
+        libc::shm_open(
+            name.as_ptr(),
+            libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+            0o600,
+        )
+    };
+    if fd < 0 {
+        return Err(describe(
+            "Cannot create a POSIX shared memory object",
+            io::Error::last_os_error(),
+        ));
+    }
+    unsafe {
+        libc::close(fd);
+        libc::shm_unlink(name.as_ptr());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn probe_shared_memory() -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(windows)]
+fn probe_named_pipe() -> Result<(), String> {
+    use crate::ipc::named_pipe_blocking::{create_instance, pipe_path};
+    create_instance(&pipe_path(&probe_name()), 4096, true)
+        .map(drop)
+        .map_err(|e| format!("Cannot create a named pipe: {:#}", e))
+}
+
+#[cfg(all(target_os = "linux", feature = "pmq"))]
+fn probe_message_queue() -> Result<(), String> {
+    use nix::mqueue::{mq_close, mq_open, mq_unlink, MQ_OFlag, MqAttr};
+    use nix::sys::stat::Mode;
+
+    let name = format!("/{}", probe_name());
+    // The smallest queue any kernel accepts
+    let attrs = MqAttr::new(0, 1, 64, 0);
+    let flags = MQ_OFlag::O_CREAT | MQ_OFlag::O_EXCL | MQ_OFlag::O_RDWR;
+    let queue = mq_open(
+        name.as_str(),
+        flags,
+        Mode::S_IRUSR | Mode::S_IWUSR,
+        Some(&attrs),
+    )
+    .map_err(|e| {
+        describe(
+            "Cannot create a POSIX message queue",
+            io::Error::from_raw_os_error(e as i32),
+        )
+    })?;
+    let _ = mq_close(queue);
+    let _ = mq_unlink(name.as_str());
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn probe_multicast(host: &str) -> Result<(), String> {
+    let interface: std::net::Ipv4Addr = host
+        .parse()
+        .map_err(|_| format!("'{}' is not an IPv4 interface address", host))?;
+    let socket = std::net::UdpSocket::bind((interface, 0))
+        .map_err(|e| describe(&format!("Cannot bind a UDP socket on {}", host), e))?;
+    socket
+        .join_multicast_v4(&crate::defaults::MULTICAST_GROUP, &interface)
+        .map_err(|e| describe(&format!("Cannot join a multicast group on {}", host), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_hints_at_the_cause() {
+        let reason = describe(
+            "Cannot create a POSIX message queue",
+            io::Error::from_raw_os_error(libc::EACCES),
+        );
+        assert!(reason.starts_with("Cannot create a POSIX message queue: "));
+        assert!(reason.ends_with("; permission denied"));
+        let reason = describe("x", io::Error::from_raw_os_error(libc::EINVAL));
+        assert!(!reason.contains(';'));
+    }
+
+    #[test]
+    fn test_probe_reports_unusable_mechanisms() {
+        assert_eq!(probe(&IpcMechanism::All, "127.0.0.1"), None);
+        assert_eq!(probe(&IpcMechanism::TcpSocket, "127.0.0.1"), None);
+        let reason = probe(&IpcMechanism::TcpSocket, "192.0.2.1").expect("not a local address");
+        assert!(reason.contains("192.0.2.1"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Fixed-layout message records for the SHM ring (`--zero-copy`).
+//!
+//! By default the ring carries each message as bincode: the sender
+//! serializes it into a buffer that is then copied into the ring, and the
+//! receiver deserializes the record it copied out into a new message,
+//! copying the payload once more. With `--zero-copy` a record is a fixed
+//! header followed by the payload as is:
+//!
+//! ```text
+//! Offset  Size  Field
+//! ------  ----  ----------------------------------------
+//! 0       8     id
+//! 8       8     timestamp (same offset as in bincode)
+//! 16      8     server_receive_ns
+//! 24      8     ttl_ns (u64::MAX: no deadline)
+//! 32      4     message_type
+//! 36      1     priority
+//! 37      3     reserved
+//! 40      ...   payload
+//! ```
+//!
+//! All fields are little-endian. The sender writes the header and then
+//! the payload straight from the message into the ring, and the receiver
+//! copies the header out to the stack and the payload straight into the
+//! message's own buffer, so serde is not involved in either direction. The
+//! timestamp sits at [`Message::timestamp_offset`] in both layouts, so the
+//! ring stamps it right before the write either way.
+
+use crate::ipc::{Message, MessageType};
+
+/// Size of the header before the payload, in bytes
+pub const HEADER_SIZE: usize = 40;
+
+/// `ttl_ns` of a message without a deadline
+const NO_DEADLINE: u64 = u64::MAX;
+
+/// The header of `message`'s record, with a zero timestamp for the sender
+/// to stamp at [`Message::timestamp_offset`].
+pub fn header(message: &Message) -> [u8; HEADER_SIZE] {
+    let mut header = [0u8; HEADER_SIZE];
+    header[0..8].copy_from_slice(&message.id.to_le_bytes());
+    header[16..24].copy_from_slice(&message.server_receive_ns.to_le_bytes());
+    let ttl_ns = message.ttl_ns.unwrap_or(NO_DEADLINE);
+    header[24..32].copy_from_slice(&ttl_ns.to_le_bytes());
+    header[32..36].copy_from_slice(&(message.message_type as u32).to_le_bytes());
+    header[36] = message.priority;
+    header
+}
+
+/// The message of a record read from the ring as its `header` and the
+/// `payload` after it, whose allocation the message takes over.
+pub fn decode(header: &[u8; HEADER_SIZE], payload: Vec<u8>) -> Message {
+    let field = |at: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&header[at..at + 8]);
+        u64::from_le_bytes(bytes)
+    };
+    let (id, timestamp, server_receive_ns, ttl_ns) = (field(0), field(8), field(16), field(24));
+    let message_type = u32::from_le_bytes([header[32], header[33], header[34], header[35]]);
+    Message {
+        id,
+        timestamp,
+        payload,
+        message_type: MessageType::from(message_type),
+        ttl_ns: (ttl_ns != NO_DEADLINE).then_some(ttl_ns),
+        priority: header[36],
+        server_receive_ns,
+        receive_time_ns: 0,
+        first_byte_time_ns: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut message = Message::new(7, vec![1, 2, 3], MessageType::Request)
+            .with_priority(3)
+            .with_server_receive_ns(99);
+        message.ttl_ns = Some(5_000);
+
+        let mut head = header(&message);
+        assert_eq!(head[Message::timestamp_offset()], [0; 8]);
+        head[Message::timestamp_offset()].copy_from_slice(&message.timestamp.to_le_bytes());
+
+        let decoded = decode(&head, message.payload.clone());
+        assert_eq!(decoded.id, 7);
+        assert_eq!(decoded.timestamp, message.timestamp);
+        assert_eq!(decoded.payload, [1, 2, 3]);
+        assert_eq!(decoded.message_type, MessageType::Request);
+        assert_eq!(decoded.ttl_ns, Some(5_000));
+        assert_eq!(decoded.priority, 3);
+        assert_eq!(decoded.server_receive_ns, 99);
+
+        let plain = decode(
+            &header(&Message::new(1, Vec::new(), MessageType::OneWay)),
+            Vec::new(),
+        );
+        assert_eq!(plain.ttl_ns, None);
+    }
+}
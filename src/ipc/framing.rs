@@ -0,0 +1,386 @@
+//! Framing for the byte-stream transports.
+//!
+//! TCP and Unix domain sockets deliver a byte stream, not messages, so
+//! each message is sent as a frame: a little-endian `u32` body length
+//! followed by the serialized message.
+//!
+//! ```text
+//! ┌─────────────────┬──────────────────────┐
+//! │ body length u32 │ body (bincode bytes) │
+//! └─────────────────┴──────────────────────┘
+//! ```
+//!
+//! With `--verify-frames` the client proposes checked frames in its
+//! handshake, and once the server agrees both ends send them: the length
+//! word has its top bit ([`CHECKED`]) set and is followed by a CRC-32 of
+//! the body.
+//!
+//! ```text
+//! ┌───────────────────────────┬────────────────────┬──────────────────────┐
+//! │ body length | CHECKED u32 │ CRC-32 of body u32 │ body (bincode bytes) │
+//! └───────────────────────────┴────────────────────┴──────────────────────┘
+//! ```
+//!
+//! Receivers read both kinds, so the handshake itself travels in plain
+//! frames. A checked frame that fails its CRC (a torn read, a sender bug,
+//! or a peer that frames differently) is dropped and counted in
+//! [`torn_frames`], and the receiver moves on to the next frame, instead
+//! of failing on a body it cannot deserialize. A length outside the
+//! allowed range means the stream itself is out of step, and ends the
+//! connection with an error that says so.
+//!
+//! `--fragment-writes BYTES` makes senders write each frame in pieces of
+//! at most BYTES bytes, one write call each with a yield between them, so
+//! receivers see frames arrive in many partial reads. It exists to test
+//! the receive paths, and adds latency.
+//!
+//! The CRC is computed after the send timestamp is written into the body,
+//! so with `--verify-frames` its cost (tens of nanoseconds per kilobyte) is
+//! part of the measured latency. Plain frames cost no more than the length
+//! prefix itself.
+
+use anyhow::{anyhow, Context, Result};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::warn;
+
+/// Bytes of the length word every frame starts with
+pub const LENGTH_PREFIX_LEN: usize = 4;
+
+/// Bytes of the CRC-32 that follows the length word of a checked frame
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Set in the length word of a checked frame, whose CRC-32 follows it
+pub const CHECKED: u32 = 1 << 31;
+
+/// Largest frame body a receiver accepts (16 MB)
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Frames dropped by this process for failing their checksum
+static TORN_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of frames this process has dropped for failing their checksum.
+pub fn torn_frames() -> u64 {
+    TORN_FRAMES.load(Ordering::Relaxed)
+}
+
+/// Header of one frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    len: u32,
+    checksum: Option<u32>,
+}
+
+/// A frame header as sent on the wire
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderBytes {
+    bytes: [u8; LENGTH_PREFIX_LEN + CHECKSUM_LEN],
+    len: usize,
+}
+
+impl std::ops::Deref for HeaderBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl FrameHeader {
+    /// The header of a frame carrying `body`, checked if `checked` is set.
+    pub fn for_body(body: &[u8], checked: bool) -> Self {
+        Self {
+            len: body.len() as u32,
+            checksum: checked.then(|| crc32fast::hash(body)),
+        }
+    }
+
+    /// The header as sent on the wire.
+    pub fn to_bytes(self) -> HeaderBytes {
+        let mut bytes = [0u8; LENGTH_PREFIX_LEN + CHECKSUM_LEN];
+        match self.checksum {
+            Some(checksum) => {
+                bytes[..4].copy_from_slice(&(self.len | CHECKED).to_le_bytes());
+                bytes[4..].copy_from_slice(&checksum.to_le_bytes());
+                HeaderBytes { bytes, len: 8 }
+            }
+            None => {
+                bytes[..4].copy_from_slice(&self.len.to_le_bytes());
+                HeaderBytes { bytes, len: 4 }
+            }
+        }
+    }
+
+    /// Parse a received length word, rejecting body lengths outside
+    /// `1..=max_len`. Returns the body length and whether the frame is
+    /// checked, in which case its CRC-32 comes next.
+    pub fn parse_prefix(bytes: [u8; LENGTH_PREFIX_LEN], max_len: usize) -> Result<(usize, bool)> {
+        let word = u32::from_le_bytes(bytes);
+        let len = (word & !CHECKED) as usize;
+        if len == 0 || len > max_len {
+            return Err(anyhow!(
+                "Invalid message length: {} bytes (allowed: 1..={}); the stream is out of step \
+                 with its frames, or the peer is an ipc-benchmark release with different framing",
+                len,
+                max_len
+            ));
+        }
+        Ok((len, word & CHECKED != 0))
+    }
+
+    /// Length of the body that follows the header.
+    pub fn body_len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Whether `body` is intact; always true for a plain frame. A checked
+    /// body that fails its CRC is counted as torn.
+    pub fn verify(&self, body: &[u8]) -> bool {
+        let Some(expected) = self.checksum else {
+            return true;
+        };
+        let checksum = crc32fast::hash(body);
+        if checksum == expected {
+            return true;
+        }
+        let torn = TORN_FRAMES.fetch_add(1, Ordering::Relaxed) + 1;
+        warn!(
+            "Dropped a {}-byte frame that failed its checksum (expected {:08x}, got {:08x}; \
+             {} torn so far)",
+            body.len(),
+            expected,
+            checksum,
+            torn
+        );
+        false
+    }
+}
+
+/// Read the next intact frame body from `reader`, skipping torn frames.
+///
+/// `read_prefix` reads one raw length word. Returns the body and, when
+/// `stamp_header` is set, the monotonic time its length word arrived (else
+/// 0).
+pub fn read_frame<R: Read>(
+    reader: &mut R,
+    max_len: usize,
+    stamp_header: bool,
+    mut read_prefix: impl FnMut(&mut R) -> Result<[u8; LENGTH_PREFIX_LEN]>,
+) -> Result<(Vec<u8>, u64)> {
+    loop {
+        let (len, checked) = FrameHeader::parse_prefix(read_prefix(reader)?, max_len)?;
+        let header_time_ns = if stamp_header {
+            super::get_monotonic_time_ns()
+        } else {
+            0
+        };
+        let checksum = if checked {
+            let mut checksum = [0u8; CHECKSUM_LEN];
+            reader
+                .read_exact(&mut checksum)
+                .context("Failed to read frame checksum")?;
+            Some(u32::from_le_bytes(checksum))
+        } else {
+            None
+        };
+        let header = FrameHeader {
+            len: len as u32,
+            checksum,
+        };
+        let mut body = vec![0u8; len];
+        reader
+            .read_exact(&mut body)
+            .context("Failed to read message data")?;
+        if header.verify(&body) {
+            return Ok((body, header_time_ns));
+        }
+    }
+}
+
+/// Read one raw length word with `read_exact`.
+pub fn read_prefix<R: Read>(reader: &mut R) -> Result<[u8; LENGTH_PREFIX_LEN]> {
+    let mut prefix = [0u8; LENGTH_PREFIX_LEN];
+    reader.read_exact(&mut prefix).context(
+        "Failed to read message length. \
+         Connection may be closed or peer disconnected.",
+    )?;
+    Ok(prefix)
+}
+
+/// Async version of [`read_frame`] for readers whose length words need no
+/// special handling.
+pub async fn read_frame_async<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_len: usize,
+    stamp_header: bool,
+) -> Result<(Vec<u8>, u64)> {
+    loop {
+        let mut prefix = [0u8; LENGTH_PREFIX_LEN];
+        reader.read_exact(&mut prefix).await?;
+        let (len, checked) = FrameHeader::parse_prefix(prefix, max_len)?;
+        let header_time_ns = if stamp_header {
+            super::get_monotonic_time_ns()
+        } else {
+            0
+        };
+        let checksum = if checked {
+            Some(reader.read_u32_le().await?)
+        } else {
+            None
+        };
+        let header = FrameHeader {
+            len: len as u32,
+            checksum,
+        };
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body).await?;
+        if header.verify(&body) {
+            return Ok((body, header_time_ns));
+        }
+    }
+}
+
+/// Write the frame of `body`, checked if `checked` is set, in pieces of
+/// at most `piece` bytes, yielding the CPU between them
+/// (`--fragment-writes`).
+pub fn write_fragmented<W: Write>(
+    writer: &mut W,
+    body: &[u8],
+    checked: bool,
+    piece: usize,
+) -> Result<()> {
+    let header = FrameHeader::for_body(body, checked).to_bytes();
+    for chunk in header.chunks(piece.max(1)).chain(body.chunks(piece.max(1))) {
+        writer
+            .write_all(chunk)
+            .context("Failed to write a frame fragment")?;
+        writer.flush().context("Failed to flush socket")?;
+        std::thread::yield_now();
+    }
+    Ok(())
+}
+
+/// Write the frame of `body`, checked if `checked` is set, in pieces of at
+/// most `fragment` bytes if given (`--fragment-writes`).
+pub async fn write_frame_async<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    body: &[u8],
+    checked: bool,
+    fragment: Option<usize>,
+) -> std::io::Result<()> {
+    let header = FrameHeader::for_body(body, checked).to_bytes();
+    match fragment {
+        Some(piece) => {
+            for chunk in header.chunks(piece.max(1)).chain(body.chunks(piece.max(1))) {
+                writer.write_all(chunk).await?;
+                writer.flush().await?;
+                tokio::task::yield_now().await;
+            }
+        }
+        None => {
+            writer.write_all(&header).await?;
+            writer.write_all(body).await?;
+        }
+    }
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Hands out at most `piece` bytes per read, like a socket under load
+    struct Trickle<R> {
+        inner: R,
+        piece: usize,
+    }
+
+    impl<R: Read> Read for Trickle<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.piece);
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_fragmented_frames_reassemble() {
+        let mut stream = Vec::new();
+        for (i, piece) in [1usize, 3, 7, 64].into_iter().enumerate() {
+            let body = vec![i as u8 + 1; 50 + i * 17];
+            // Plain and checked frames mix on one stream
+            write_fragmented(&mut stream, &body, i % 2 == 1, piece).unwrap();
+        }
+        let mut reader = Trickle {
+            inner: Cursor::new(stream),
+            piece: 5,
+        };
+        for i in 0..4 {
+            let (body, stamped) = read_frame(&mut reader, 1024, true, read_prefix).unwrap();
+            assert_eq!(body, vec![i as u8 + 1; 50 + i * 17]);
+            assert!(stamped > 0);
+        }
+    }
+
+    #[test]
+    fn test_plain_frames_are_a_length_prefix() {
+        let header = FrameHeader::for_body(b"body", false).to_bytes();
+        assert_eq!(&*header, 4u32.to_le_bytes());
+        let header = FrameHeader::for_body(b"body", true).to_bytes();
+        assert_eq!(header.len(), LENGTH_PREFIX_LEN + CHECKSUM_LEN);
+        assert_eq!(header[..4], (4 | CHECKED).to_le_bytes());
+    }
+
+    #[test]
+    fn test_torn_frames_are_dropped_and_counted() {
+        let mut stream = Vec::new();
+        write_fragmented(&mut stream, b"first frame", true, 1024).unwrap();
+        write_fragmented(&mut stream, b"second frame", true, 1024).unwrap();
+        // Flip a byte of the first body
+        stream[LENGTH_PREFIX_LEN + CHECKSUM_LEN + 2] ^= 0xff;
+
+        let before = torn_frames();
+        let mut reader = Cursor::new(stream);
+        let (body, _) = read_frame(&mut reader, 1024, false, read_prefix).unwrap();
+        assert_eq!(body, b"second frame");
+        assert!(torn_frames() > before);
+    }
+
+    #[test]
+    fn test_out_of_step_lengths_are_errors() {
+        let prefix = 100u32.to_le_bytes();
+        let error = FrameHeader::parse_prefix(prefix, 64).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid message length: 100 bytes"));
+        assert!(FrameHeader::parse_prefix([0u8; LENGTH_PREFIX_LEN], 64).is_err());
+        assert_eq!(
+            FrameHeader::parse_prefix(prefix, 100).unwrap(),
+            (100, false)
+        );
+        let checked = (100 | CHECKED).to_le_bytes();
+        assert_eq!(
+            FrameHeader::parse_prefix(checked, 100).unwrap(),
+            (100, true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_frames_round_trip() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let writer = tokio::spawn(async move {
+            write_frame_async(&mut client, b"whole", false, None)
+                .await
+                .unwrap();
+            write_frame_async(&mut client, b"in pieces", true, Some(2))
+                .await
+                .unwrap();
+        });
+        let (body, _) = read_frame_async(&mut server, 1024, false).await.unwrap();
+        assert_eq!(body, b"whole");
+        let (body, _) = read_frame_async(&mut server, 1024, false).await.unwrap();
+        assert_eq!(body, b"in pieces");
+        writer.await.unwrap();
+    }
+}
@@ -0,0 +1,409 @@
+//! Futex wake/wait transport (blocking, Linux only).
+//!
+//! The smallest transport that still moves a message between processes: a
+//! shared memory segment with one slot per direction, each guarded by a
+//! 32-bit state word. The sender copies the serialized message into the
+//! slot, marks it full and calls `FUTEX_WAKE`; the receiver sleeps in
+//! `FUTEX_WAIT` while the slot is empty, copies the message out, marks the
+//! slot empty and wakes the sender in turn.
+//!
+//! There is no queue, so only one message per direction is in flight. What
+//! remains of the latency is essentially the cost of a cross-process
+//! wakeup, which makes this the floor the other mechanisms can be compared
+//! against.
+//!
+//! # Segment Layout
+//!
+//! ```text
+//! [ header (64 bytes) | request slot | reply slot ]
+//! slot: [ state: u32 | len: u32 | data: slot_capacity bytes ], 64-byte aligned
+//! ```
+//!
+//! The client sends into the request slot and the server into the reply
+//! slot, so round-trip tests work as well as one-way tests.
+
+use crate::ipc::{BlockingTransport, Message, TransportConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use shared_memory::{Shmem, ShmemConf};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+
+/// Slot state: nothing to read
+const EMPTY: u32 = 0;
+
+/// Slot state: a message is waiting
+const FULL: u32 = 1;
+
+/// Longest single futex wait before the shutdown flag is checked again
+const RECHECK: Duration = Duration::from_millis(100);
+
+/// How long a client waits for the server's segment
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+const HEADER_SIZE: usize = 64;
+const SLOT_HEADER_SIZE: usize = 8;
+const SLOT_ALIGN: usize = 64;
+
+/// Segment header, written by the server before it sets `ready`.
+#[repr(C)]
+struct Header {
+    slot_capacity: AtomicUsize,
+    ready: AtomicU32,
+    shutdown: AtomicU32,
+}
+
+/// One direction of the segment.
+#[repr(C)]
+struct Slot {
+    state: AtomicU32,
+    len: AtomicU32,
+}
+
+/// Distance between the two slots for `capacity` data bytes.
+fn slot_stride(capacity: usize) -> usize {
+    (SLOT_HEADER_SIZE + capacity + SLOT_ALIGN - 1) / SLOT_ALIGN * SLOT_ALIGN
+}
+
+/// Total segment size for `capacity` data bytes per slot.
+fn segment_size(capacity: usize) -> usize {
+    HEADER_SIZE + 2 * slot_stride(capacity)
+}
+
+/// Sleep while `word` still holds `expected`, for at most `timeout`.
+///
+/// Returns early on a wake, a changed value or a signal; callers recheck.
+fn futex_wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+    let ts = libc::timespec {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word as *const AtomicU32,
+            libc::FUTEX_WAIT,
+            expected,
+            &ts as *const libc::timespec,
+        );
+    }
+}
+
+/// Wake every waiter on `word`.
+fn futex_wake(word: &AtomicU32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word as *const AtomicU32,
+            libc::FUTEX_WAKE,
+            libc::c_int::MAX,
+        );
+    }
+}
+
+/// Blocking futex transport.
+///
+/// Supports one client per server and one message in flight per
+/// direction.
+pub struct BlockingFutex {
+    /// The mapped segment
+    shmem: Option<Shmem>,
+
+    /// Start of the mapping
+    base: *mut u8,
+
+    /// Data bytes per slot
+    slot_capacity: usize,
+
+    /// Whether this instance created the segment
+    is_server: bool,
+}
+
+// Safety: the segment is only accessed through atomics and the slot
+// protocol, which hands each slot's data to one side at a time
+unsafe impl Send for BlockingFutex {}
+
+impl BlockingFutex {
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            shmem: None,
+            base: std::ptr::null_mut(),
+            slot_capacity: 0,
+            is_server: false,
+        }
+    }
+
+    fn header(&self) -> Result<&Header> {
+        if self.base.is_null() {
+            bail!("Futex transport not initialized");
+        }
+        Ok(unsafe { &*(self.base as *const Header) })
+    }
+
+    /// Slot at `index` (0: request, 1: reply) and its data area.
+    fn slot(&self, index: usize) -> Result<(&Slot, *mut u8)> {
+        self.header()?;
+        let offset = HEADER_SIZE + index * slot_stride(self.slot_capacity);
+        unsafe {
+            let slot = self.base.add(offset);
+            Ok((&*(slot as *const Slot), slot.add(SLOT_HEADER_SIZE)))
+        }
+    }
+
+    /// The slot this side sends into.
+    fn send_slot(&self) -> Result<(&Slot, *mut u8)> {
+        self.slot(usize::from(self.is_server))
+    }
+
+    /// The slot this side receives from.
+    fn receive_slot(&self) -> Result<(&Slot, *mut u8)> {
+        self.slot(usize::from(!self.is_server))
+    }
+
+    /// Wait until `slot` leaves `state`, failing once the peer shut down.
+    fn wait_while(&self, slot: &Slot, state: u32) -> Result<()> {
+        let header = self.header()?;
+        while slot.state.load(Ordering::Acquire) == state {
+            if header.shutdown.load(Ordering::Acquire) != 0 {
+                return Err(anyhow!("Connection closed"));
+            }
+            futex_wait(&slot.state, state, RECHECK);
+        }
+        Ok(())
+    }
+
+    /// Copy the message out of a full receive slot, then free the slot.
+    fn take(&self, slot: &Slot, data: *mut u8) -> Result<Message> {
+        let len = slot.len.load(Ordering::Acquire) as usize;
+        let mut bytes = vec![0u8; len];
+        unsafe {
+            std::ptr::copy_nonoverlapping(data, bytes.as_mut_ptr(), len);
+        }
+        slot.state.store(EMPTY, Ordering::Release);
+        futex_wake(&slot.state);
+        bincode::deserialize(&bytes).context("Failed to deserialize message")
+    }
+}
+
+impl Default for BlockingFutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingTransport for BlockingFutex {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        debug!(
+            "Starting blocking futex server with segment: {}",
+            config.shared_memory_name
+        );
+
+        // Best-effort removal of a segment left behind by a killed run
+        if let Ok(name) = std::ffi::CString::new(format!(
+            "/{}",
+            config.shared_memory_name.trim_start_matches('/')
+        )) {
+            unsafe {
+                libc::shm_unlink(name.as_ptr());
+            }
+        }
+
+        let capacity = config.buffer_size;
+        let shmem = ShmemConf::new()
+            .size(segment_size(capacity))
+            .os_id(&config.shared_memory_name)
+            .create()
+            .with_context(|| {
+                format!(
+                    "Failed to create futex segment: {}",
+                    config.shared_memory_name
+                )
+            })?;
+
+        self.base = shmem.as_ptr();
+        self.slot_capacity = capacity;
+        self.is_server = true;
+        self.shmem = Some(shmem);
+
+        let header = self.header()?;
+        header.slot_capacity.store(capacity, Ordering::Relaxed);
+        header.shutdown.store(0, Ordering::Relaxed);
+        for index in 0..2 {
+            self.slot(index)?.0.state.store(EMPTY, Ordering::Relaxed);
+        }
+        header.ready.store(1, Ordering::Release);
+        Ok(())
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        debug!(
+            "Starting blocking futex client, connecting to: {}",
+            config.shared_memory_name
+        );
+
+        let start = Instant::now();
+        let shmem = loop {
+            match ShmemConf::new().os_id(&config.shared_memory_name).open() {
+                Ok(shmem) => {
+                    let header = unsafe { &*(shmem.as_ptr() as *const Header) };
+                    if header.ready.load(Ordering::Acquire) != 0 {
+                        break shmem;
+                    }
+                }
+                Err(e) if start.elapsed() > OPEN_TIMEOUT => {
+                    return Err(anyhow!(
+                        "Failed to open futex segment: {}. Is the server running? Error: {}",
+                        config.shared_memory_name,
+                        e
+                    ));
+                }
+                Err(_) => {}
+            }
+            if start.elapsed() > OPEN_TIMEOUT {
+                bail!(
+                    "Timeout waiting for the server to initialize futex segment {}",
+                    config.shared_memory_name
+                );
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        self.base = shmem.as_ptr();
+        self.is_server = false;
+        self.shmem = Some(shmem);
+        self.slot_capacity = self.header()?.slot_capacity.load(Ordering::Acquire);
+        Ok(())
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        trace!("Sending message ID {} via futex", message.id);
+
+        // Serialize with a placeholder timestamp, refreshed right before the
+        // copy so waiting for the slot is not counted as latency
+        let mut message = message.clone();
+        message.timestamp = 0;
+        let mut bytes = bincode::serialize(&message).context("Failed to serialize message")?;
+        if bytes.len() > self.slot_capacity {
+            bail!(
+                "Message of {} bytes does not fit the {}-byte futex slot; increase --buffer-size",
+                bytes.len(),
+                self.slot_capacity
+            );
+        }
+
+        let (slot, data) = self.send_slot()?;
+        self.wait_while(slot, FULL)?;
+
+        let ts = crate::ipc::get_monotonic_time_ns().to_le_bytes();
+        bytes[Message::timestamp_offset()].copy_from_slice(&ts);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+        }
+        slot.len.store(bytes.len() as u32, Ordering::Relaxed);
+        slot.state.store(FULL, Ordering::Release);
+        futex_wake(&slot.state);
+        Ok(())
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        let (slot, data) = self.receive_slot()?;
+        self.wait_while(slot, EMPTY)?;
+        self.take(slot, data)
+    }
+
+    fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
+        let (slot, data) = self.receive_slot()?;
+        self.wait_while(slot, EMPTY)?;
+        let receive_time_ns = crate::ipc::get_monotonic_time_ns();
+        Ok((self.take(slot, data)?, receive_time_ns))
+    }
+
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        let Ok((slot, data)) = self.receive_slot() else {
+            return Ok(None);
+        };
+        if slot.state.load(Ordering::Acquire) != FULL {
+            return Ok(None);
+        }
+        self.take(slot, data).map(Some)
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        debug!("Closing blocking futex transport");
+        if let Ok(header) = self.header() {
+            header.shutdown.store(1, Ordering::Release);
+            for index in 0..2 {
+                if let Ok((slot, _)) = self.slot(index) {
+                    futex_wake(&slot.state);
+                }
+            }
+        }
+        // Dropping the server's mapping unlinks the segment
+        self.shmem = None;
+        self.base = std::ptr::null_mut();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+
+    #[test]
+    fn test_segment_layout() {
+        assert_eq!(slot_stride(56), 64);
+        assert_eq!(slot_stride(57), 128);
+        assert_eq!(segment_size(56), 64 + 2 * 64);
+        assert!(std::mem::size_of::<Header>() <= HEADER_SIZE);
+        assert_eq!(std::mem::size_of::<Slot>(), SLOT_HEADER_SIZE);
+    }
+
+    #[test]
+    fn test_futex_round_trip_between_threads() {
+        let config = TransportConfig {
+            shared_memory_name: format!("ipc_benchmark_futex_test_{}", std::process::id()),
+            buffer_size: 1024,
+            ..Default::default()
+        };
+        let mut server = BlockingFutex::new();
+        server.start_server_blocking(&config).unwrap();
+
+        let client_config = config.clone();
+        let client = std::thread::spawn(move || {
+            let mut client = BlockingFutex::new();
+            client.start_client_blocking(&client_config).unwrap();
+            for id in 0..50 {
+                client
+                    .send_blocking(&Message::new(id, vec![7; 64], MessageType::Request))
+                    .unwrap();
+                let reply = client.receive_blocking().unwrap();
+                assert_eq!(reply.id, id + 1000);
+            }
+            client.close_blocking().unwrap();
+        });
+
+        for _ in 0..50 {
+            let (request, _) = server.receive_blocking_timed().unwrap();
+            assert_eq!(request.payload, vec![7; 64]);
+            assert!(request.timestamp > 0);
+            server
+                .send_blocking(&Message::new(
+                    request.id + 1000,
+                    Vec::new(),
+                    MessageType::Response,
+                ))
+                .unwrap();
+        }
+        client.join().unwrap();
+
+        // The client's close wakes and fails a waiting receive
+        assert!(server.receive_blocking().is_err());
+        server.close_blocking().unwrap();
+    }
+}
@@ -0,0 +1,505 @@
+//! gRPC loopback call transport.
+//!
+//! `-m grpc` makes every message a unary call to
+//! `/ipc_benchmark.Benchmark/Send` on a loopback gRPC server, to set what a
+//! gRPC hop between local services costs against `-m tcp` and `-m uds`.
+//! Both ends use [tonic]'s gRPC client and server over [hyper]'s HTTP/2
+//! connections, and messages are encoded as protobuf by [prost], so the
+//! measured latency covers a real gRPC stack: protobuf encoding, gRPC
+//! message framing, HPACK-encoded headers and the `grpc-status` trailers.
+//!
+//! # Service
+//!
+//! The service has no `.proto` file to build from; its one method and
+//! message are written out by hand here, as `tonic-build` would generate
+//! them from:
+//!
+//! ```protobuf
+//! package ipc_benchmark;
+//!
+//! service Benchmark {
+//!   rpc Send(Message) returns (Message);
+//! }
+//!
+//! message Message {
+//!   uint64 id = 1;
+//!   uint64 timestamp = 2;
+//!   bytes payload = 3;
+//!   uint32 message_type = 4;
+//!   optional uint64 ttl_ns = 5;
+//!   uint32 priority = 6;
+//!   uint64 server_receive_ns = 7;
+//! }
+//! ```
+//!
+//! # Call Mapping
+//!
+//! - One-way messages (and everything else that gets no reply) are
+//!   answered by the server with an empty message, which encodes as
+//!   `google.protobuf.Empty` does, as soon as it receives them. The client
+//!   waits for it before its next call.
+//! - Requests and pings are left open until the server sends its reply,
+//!   which becomes the call's response. A unary call is a single exchange,
+//!   so the client's send waits for the response and its next receive
+//!   returns the reply.
+//!
+//! HTTP/2 runs in cleartext with prior knowledge (h2c), with each call on a
+//! new stream. Both sides advertise the largest flow control windows so
+//! ordinary messages are never held back.
+//!
+//! The blocking transport, [`BlockingGrpc`](super::BlockingGrpc), drives
+//! this one on a runtime of its own.
+
+use crate::ipc::{get_monotonic_time_ns, IpcTransport, Message, MessageType, TransportConfig};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use hyper::client::conn::{self as client_conn, SendRequest};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Request, Uri};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::BoxFuture;
+use tonic::server::UnaryService;
+use tonic::Status;
+use tracing::{debug, trace};
+
+/// Path of the service's one method
+const METHOD_PATH: &str = "/ipc_benchmark.Benchmark/Send";
+
+/// Largest HTTP/2 flow control window
+const MAX_WINDOW: u32 = 0x7fff_ffff;
+
+/// Largest encoded message, matching the stream transports' guard
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Calls the connection task may read ahead of the transport
+const CALL_QUEUE: usize = 64;
+
+/// Whether the server replies to `message_type` with a message of its own,
+/// so the call is held open until it does.
+fn expects_reply(message_type: MessageType) -> bool {
+    matches!(
+        message_type,
+        MessageType::Request | MessageType::Ping | MessageType::Handshake
+    )
+}
+
+/// Protobuf form of [`Message`], `ipc_benchmark.Message`.
+///
+/// The fields never serialized by the other transports are left out too.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GrpcMessage {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(uint64, tag = "2")]
+    pub timestamp: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub payload: Vec<u8>,
+    #[prost(uint32, tag = "4")]
+    pub message_type: u32,
+    #[prost(uint64, optional, tag = "5")]
+    pub ttl_ns: Option<u64>,
+    #[prost(uint32, tag = "6")]
+    pub priority: u32,
+    #[prost(uint64, tag = "7")]
+    pub server_receive_ns: u64,
+}
+
+impl GrpcMessage {
+    /// `message`, stamped with the current time.
+    fn stamped(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            timestamp: get_monotonic_time_ns(),
+            payload: message.payload.clone(),
+            message_type: message.message_type as u32,
+            ttl_ns: message.ttl_ns,
+            priority: message.priority.into(),
+            server_receive_ns: message.server_receive_ns,
+        }
+    }
+}
+
+impl From<GrpcMessage> for Message {
+    fn from(message: GrpcMessage) -> Self {
+        let mut converted = Message::new(
+            message.id,
+            message.payload,
+            MessageType::from(message.message_type),
+        );
+        converted.timestamp = message.timestamp;
+        converted.ttl_ns = message.ttl_ns;
+        converted.priority = message.priority.min(u8::MAX.into()) as u8;
+        converted.server_receive_ns = message.server_receive_ns;
+        converted
+    }
+}
+
+/// A call the server's connection task has read, waiting for the
+/// transport to answer it.
+struct Call {
+    message: Message,
+
+    /// When the call's message had been decoded
+    receive_time_ns: u64,
+
+    /// The response message, or `None` for an empty one
+    reply: oneshot::Sender<Option<GrpcMessage>>,
+}
+
+/// Server: the `Send` method, handing each call to the transport.
+#[derive(Clone)]
+struct SendMethod {
+    calls: mpsc::Sender<Call>,
+}
+
+impl UnaryService<GrpcMessage> for SendMethod {
+    type Response = GrpcMessage;
+    type Future = BoxFuture<tonic::Response<GrpcMessage>, Status>;
+
+    fn call(&mut self, request: tonic::Request<GrpcMessage>) -> Self::Future {
+        let receive_time_ns = get_monotonic_time_ns();
+        let calls = self.calls.clone();
+        Box::pin(async move {
+            let (reply, response) = oneshot::channel();
+            let call = Call {
+                message: request.into_inner().into(),
+                receive_time_ns,
+                reply,
+            };
+            if calls.send(call).await.is_err() {
+                return Err(Status::unavailable("gRPC server is closing"));
+            }
+            match response.await {
+                Ok(reply) => Ok(tonic::Response::new(reply.unwrap_or_default())),
+                // The transport closed without replying
+                Err(_) => Err(Status::unavailable("gRPC server closed without replying")),
+            }
+        })
+    }
+}
+
+/// Server: route one HTTP/2 request to the `Send` method.
+async fn route(
+    request: Request<Body>,
+    method: SendMethod,
+) -> Result<hyper::Response<BoxBody>, Infallible> {
+    if request.uri().path() != METHOD_PATH {
+        return Ok(Status::unimplemented(request.uri().path()).to_http());
+    }
+    let mut grpc = tonic::server::Grpc::new(ProstCodec::default())
+        .max_decoding_message_size(MAX_MESSAGE_SIZE)
+        .max_encoding_message_size(MAX_MESSAGE_SIZE);
+    Ok(grpc.unary(method, request).await)
+}
+
+/// gRPC loopback transport.
+///
+/// Client messages travel as call requests and server messages as call
+/// responses; see the module documentation for the mapping.
+pub struct GrpcTransport {
+    /// Server listener socket (server mode only)
+    listener: Option<TcpListener>,
+
+    /// Whether this end answers calls
+    is_server: bool,
+
+    /// Client: the gRPC client on the connection
+    client: Option<tonic::client::Grpc<SendRequest<BoxBody>>>,
+
+    /// Client: replies received, in call order
+    replies: VecDeque<Message>,
+
+    /// Server: calls read from the accepted connection
+    calls: Option<mpsc::Receiver<Call>>,
+
+    /// Server: calls whose response waits for a reply
+    open_calls: VecDeque<oneshot::Sender<Option<GrpcMessage>>>,
+}
+
+impl GrpcTransport {
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            listener: None,
+            is_server: false,
+            client: None,
+            replies: VecDeque::new(),
+            calls: None,
+            open_calls: VecDeque::new(),
+        }
+    }
+
+    /// Accept the next connection on the listener and answer its calls
+    /// through the returned transport.
+    pub(crate) async fn accept(&self) -> Result<Self> {
+        let listener = self
+            .listener
+            .as_ref()
+            .context("Cannot accept: call start_server() first")?;
+        let (stream, peer_addr) = listener
+            .accept()
+            .await
+            .context("Failed to accept gRPC connection")?;
+        debug!("gRPC server accepted connection from: {}", peer_addr);
+        stream
+            .set_nodelay(true)
+            .context("Failed to set TCP_NODELAY on gRPC connection")?;
+
+        let (calls, requests) = mpsc::channel(CALL_QUEUE);
+        let method = SendMethod { calls };
+        let connection = Http::new()
+            .http2_only(true)
+            .http2_initial_stream_window_size(MAX_WINDOW)
+            .http2_initial_connection_window_size(MAX_WINDOW)
+            .serve_connection(
+                stream,
+                service_fn(move |request| route(request, method.clone())),
+            );
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("gRPC server connection ended: {}", e);
+            }
+        });
+
+        let mut accepted = Self::new();
+        accepted.is_server = true;
+        accepted.calls = Some(requests);
+        Ok(accepted)
+    }
+
+    /// Server: the next call's message, answered at once unless it asks for
+    /// a reply, and when it had been decoded.
+    pub(crate) async fn receive_timed(&mut self) -> Result<(Message, u64)> {
+        if !self.is_server {
+            let message = self
+                .replies
+                .pop_front()
+                .ok_or_else(|| anyhow!("gRPC client has no call awaiting a reply"))?;
+            return Ok((message, get_monotonic_time_ns()));
+        }
+        if self.calls.is_none() {
+            // Accept happens on first use, after readiness is signalled
+            self.calls = self.accept().await?.calls;
+        }
+        let calls = self
+            .calls
+            .as_mut()
+            .context("gRPC transport not connected")?;
+        let call = calls
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("gRPC client closed the connection"))?;
+        if expects_reply(call.message.message_type) {
+            self.open_calls.push_back(call.reply);
+        } else {
+            // The client is gone if nobody waits for the empty response
+            let _ = call.reply.send(None);
+        }
+        Ok((call.message, call.receive_time_ns))
+    }
+}
+
+impl Default for GrpcTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl IpcTransport for GrpcTransport {
+    async fn start_server(&mut self, config: &TransportConfig) -> Result<()> {
+        let addr = format!("{}:{}", config.host, config.port);
+        debug!("Starting gRPC server at: {}", addr);
+        let listener = TcpListener::bind(&addr).await.with_context(|| {
+            format!(
+                "Failed to bind gRPC server to {}. \
+                 Check if port {} is available and not in use.",
+                addr, config.port
+            )
+        })?;
+        self.listener = Some(listener);
+        self.is_server = true;
+        Ok(())
+    }
+
+    async fn start_client(&mut self, config: &TransportConfig) -> Result<()> {
+        let addr = format!("{}:{}", config.host, config.port);
+        debug!("Starting gRPC client, connecting to: {}", addr);
+        let stream = TcpStream::connect(&addr).await.with_context(|| {
+            format!(
+                "Failed to connect to gRPC server at {}. Is the server running?",
+                addr
+            )
+        })?;
+        stream
+            .set_nodelay(true)
+            .context("Failed to set TCP_NODELAY on gRPC connection")?;
+
+        let (sender, connection) = client_conn::Builder::new()
+            .http2_only(true)
+            .http2_initial_stream_window_size(MAX_WINDOW)
+            .http2_initial_connection_window_size(MAX_WINDOW)
+            .handshake(stream)
+            .await
+            .context("gRPC handshake failed")?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("gRPC client connection ended: {}", e);
+            }
+        });
+        // HTTP/2 requests carry their scheme and authority
+        let origin: Uri = format!("http://{}", addr)
+            .parse()
+            .context("Invalid gRPC server address")?;
+        let client = tonic::client::Grpc::with_origin(sender, origin)
+            .max_decoding_message_size(MAX_MESSAGE_SIZE)
+            .max_encoding_message_size(MAX_MESSAGE_SIZE);
+        self.client = Some(client);
+        Ok(())
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<bool> {
+        trace!("Sending message ID {} via gRPC", message.id);
+
+        if self.is_server {
+            let reply = self.open_calls.pop_front().ok_or_else(|| {
+                anyhow!("gRPC server can only send in reply to a request or ping")
+            })?;
+            reply
+                .send(Some(GrpcMessage::stamped(message)))
+                .map_err(|_| anyhow!("gRPC client closed the connection"))?;
+            return Ok(false);
+        }
+
+        let client = self.client.as_mut().context(
+            "gRPC transport not connected. Call start_server() or start_client() first.",
+        )?;
+        client.ready().await.context("gRPC connection closed")?;
+        let response = client
+            .unary(
+                tonic::Request::new(GrpcMessage::stamped(message)),
+                PathAndQuery::from_static(METHOD_PATH),
+                ProstCodec::<GrpcMessage, GrpcMessage>::default(),
+            )
+            .await
+            .context("gRPC call failed")?;
+        if expects_reply(message.message_type) {
+            self.replies.push_back(response.into_inner().into());
+        }
+        Ok(false)
+    }
+
+    async fn receive(&mut self) -> Result<Message> {
+        self.receive_timed().await.map(|(message, _)| message)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        debug!("Closing gRPC transport");
+        self.client = None;
+        self.replies.clear();
+        self.calls = None;
+        self.open_calls.clear();
+        self.listener = None;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "gRPC"
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        let listener = self.listener.as_ref()?;
+        listener.local_addr().ok().map(|addr| addr.port())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message as _;
+
+    #[test]
+    fn test_message_fields_survive_protobuf() {
+        let mut message = Message::new(7, vec![1, 2, 3], MessageType::Request)
+            .with_ttl(Some(std::time::Duration::from_micros(5)))
+            .with_priority(3);
+        message.server_receive_ns = 42;
+        let encoded = GrpcMessage::stamped(&message).encode_to_vec();
+        let decoded: Message = GrpcMessage::decode(encoded.as_slice()).unwrap().into();
+        assert_eq!(decoded.id, 7);
+        assert_eq!(decoded.payload, [1, 2, 3]);
+        assert_eq!(decoded.message_type, MessageType::Request);
+        assert_eq!(decoded.ttl_ns, Some(5_000));
+        assert_eq!(decoded.priority, 3);
+        assert_eq!(decoded.server_receive_ns, 42);
+        assert!(decoded.timestamp > 0);
+
+        // The empty reply to a one-way call encodes as google.protobuf.Empty
+        assert!(GrpcMessage::default().encode_to_vec().is_empty());
+    }
+
+    async fn round_trip(payload_len: usize) {
+        let mut server = GrpcTransport::new();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            ..Default::default()
+        };
+        server.start_server(&config).await.unwrap();
+        let client_config = TransportConfig {
+            port: server.local_port().unwrap(),
+            ..config
+        };
+
+        let client = tokio::spawn(async move {
+            let mut client = GrpcTransport::new();
+            client.start_client(&client_config).await.unwrap();
+            for id in 0..5 {
+                let one_way = Message::new(id, vec![1; payload_len], MessageType::OneWay);
+                client.send(&one_way).await.unwrap();
+            }
+            for id in 0..5 {
+                let request = Message::new(id, vec![2; payload_len], MessageType::Request);
+                client.send(&request).await.unwrap();
+                let reply = client.receive().await.unwrap();
+                assert_eq!(reply.id, id);
+                assert_eq!(reply.message_type, MessageType::Response);
+            }
+            client.close().await.unwrap();
+        });
+
+        for id in 0..5 {
+            let message = server.receive().await.unwrap();
+            assert_eq!((message.id, message.payload.len()), (id, payload_len));
+            // Nothing to reply to after a one-way message
+            assert!(server.open_calls.is_empty());
+        }
+        for _ in 0..5 {
+            let request = server.receive().await.unwrap();
+            assert!(request.timestamp > 0);
+            let reply = Message::new(request.id, vec![3; payload_len], MessageType::Response);
+            server.send(&reply).await.unwrap();
+        }
+        client.await.unwrap();
+        assert!(server.receive().await.is_err());
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_grpc_one_way_and_round_trip() {
+        round_trip(64).await;
+    }
+
+    #[tokio::test]
+    async fn test_grpc_large_messages_span_frames_and_windows() {
+        // Larger than both the default frame size and the initial window
+        round_trip(200 * 1024).await;
+    }
+}
@@ -0,0 +1,122 @@
+//! gRPC loopback call transport (blocking).
+//!
+//! tonic and hyper are asynchronous, so the blocking transport runs the
+//! async [`GrpcTransport`] on a Tokio runtime of its own with one worker
+//! thread, as [`BlockingHttp`](super::BlockingHttp) does. The connection's
+//! I/O runs on that worker and each call waits for it, so the handoff
+//! between the two threads is part of the measured latency. See
+//! [`super::grpc`] for how messages map onto calls.
+
+use crate::ipc::grpc::GrpcTransport;
+use crate::ipc::{BlockingTransport, IpcTransport, Message, TransportConfig};
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// Blocking gRPC loopback transport.
+pub struct BlockingGrpc {
+    /// Runtime the connection runs on, shared with accepted connections
+    runtime: Arc<Runtime>,
+
+    /// The transport the runtime drives
+    inner: GrpcTransport,
+}
+
+impl BlockingGrpc {
+    /// Create an unconnected transport and the runtime it runs on.
+    pub fn new() -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("grpc-transport")
+            .enable_all()
+            .build()
+            .context("Failed to start the gRPC transport's runtime")?;
+        Ok(Self {
+            runtime: Arc::new(runtime),
+            inner: GrpcTransport::new(),
+        })
+    }
+}
+
+impl BlockingTransport for BlockingGrpc {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.runtime.block_on(self.inner.start_server(config))
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.runtime.block_on(self.inner.start_client(config))
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        self.runtime.block_on(self.inner.send(message)).map(|_| ())
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        self.runtime.block_on(self.inner.receive())
+    }
+
+    fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
+        self.runtime.block_on(self.inner.receive_timed())
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        self.inner.local_port()
+    }
+
+    fn accept_blocking(&mut self) -> Result<Box<dyn BlockingTransport>> {
+        let inner = self.runtime.block_on(self.inner.accept())?;
+        Ok(Box::new(Self {
+            runtime: Arc::clone(&self.runtime),
+            inner,
+        }))
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.close())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+
+    #[test]
+    fn test_grpc_one_way_and_round_trip() {
+        let mut server = BlockingGrpc::new().unwrap();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            ..Default::default()
+        };
+        server.start_server_blocking(&config).unwrap();
+        let client_config = TransportConfig {
+            port: server.local_port().unwrap(),
+            ..config
+        };
+
+        let client = std::thread::spawn(move || {
+            let mut client = BlockingGrpc::new().unwrap();
+            client.start_client_blocking(&client_config).unwrap();
+            client
+                .send_blocking(&Message::new(0, vec![1; 64], MessageType::OneWay))
+                .unwrap();
+            client
+                .send_blocking(&Message::new(1, vec![2; 64], MessageType::Request))
+                .unwrap();
+            assert_eq!(client.receive_blocking().unwrap().id, 1);
+            client.close_blocking().unwrap();
+        });
+
+        let (one_way, receive_time_ns) = server.receive_blocking_timed().unwrap();
+        assert_eq!(one_way.id, 0);
+        assert!(receive_time_ns >= one_way.timestamp);
+        let request = server.receive_blocking().unwrap();
+        server
+            .send_blocking(&Message::new(request.id, Vec::new(), MessageType::Response))
+            .unwrap();
+        client.join().unwrap();
+        assert!(server.receive_blocking().is_err());
+        server.close_blocking().unwrap();
+    }
+}
@@ -0,0 +1,334 @@
+//! Version and capability handshake at connection establishment.
+//!
+//! A client and server built from different versions of the benchmark may
+//! disagree on the wire format of [`Message`], or on options that change
+//! it such as segmentation. Without a check they fail mid-run with a
+//! deserialization error that says nothing about the cause. So before any
+//! test traffic the client sends a [`Handshake`] with its protocol version,
+//! the largest payload it will send and the wire features it uses, and
+//! waits for the server's answer. The server compares the terms with its
+//! own and replies with the agreed terms, or with the reason it refuses
+//! them, after which both ends stop with that reason.
+//!
+//! ## Wire Format
+//!
+//! Handshakes are ordinary [`Message`]s of type
+//! [`MessageType::Handshake`] with ID `u64::MAX`, like canaries, so servers
+//! leave them out of their tallies. The payload is:
+//!
+//! ```text
+//! ┌──────────┬────────────┬─────────────┬──────────────────────┬─────────────┐
+//! │ magic u32│ version u16│ features u32│ max_message_size u64 │ reason bytes│
+//! └──────────┴────────────┴─────────────┴──────────────────────┴─────────────┘
+//! ```
+//!
+//! All integers are little-endian. The reason is empty except in a
+//! server's refusal, where it is UTF-8 text.
+//!
+//! Mechanisms without a separate reply path skip the handshake: UDP
+//! multicast, and shared memory, whose ring (or `--shm-direct` slot)
+//! carries both directions, so a client could read back its own hello.
+//! The shared memory ring instead starts with a magic and the creator's
+//! [`PROTOCOL_VERSION`], which a client checks when it attaches. A server
+//! built before the handshake existed cannot decode one and drops the
+//! connection, which the client reports as a missing reply.
+
+use super::{BlockingTransport, IpcTransport, Message, MessageType};
+use crate::mechanism::IpcMechanism;
+use anyhow::{anyhow, bail, Context, Result};
+use std::time::Duration;
+
+/// Marks a handshake payload ("IPCH" in little-endian byte order).
+const HANDSHAKE_MAGIC: u32 = u32::from_le_bytes(*b"IPCH");
+
+/// Bytes of the fixed part of a handshake payload.
+const HANDSHAKE_LEN: usize = 18;
+
+/// Version of the message protocol this build speaks.
+///
+/// Bump it whenever [`Message`] or the meaning of its fields changes, or
+/// the framing of a byte stream or the layout of the shared memory ring:
+///
+/// 1. Handshake before the first message
+/// 2. Byte streams frame each message with a CRC-32 header
+/// 3. Shared memory records carry a sequence and generation stamp
+/// 4. Fixed-layout shared memory records (`--zero-copy`)
+/// 5. The shared memory ring header starts with a magic and this version
+/// 6. Byte streams frame messages with their length alone unless the
+///    handshake agrees on checksums
+pub const PROTOCOL_VERSION: u16 = 6;
+
+/// Longest an async client waits for the server's answer.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Told to a client that gets no answer to its handshake
+const NO_REPLY: &str = "No handshake reply from the server; it may be an ipc-benchmark \
+                        release without version negotiation, or may have exited";
+
+/// Wire features of a connection.
+pub mod features {
+    /// Messages are split into segments (`--segment-size`)
+    pub const SEGMENTATION: u32 = 1 << 0;
+
+    /// Byte-stream frames carry a CRC-32 (`--verify-frames`); proposed by
+    /// the client and granted by any server
+    pub const FRAME_CHECKSUMS: u32 = 1 << 1;
+
+    /// Name and flag of every feature both ends must agree on, for error
+    /// messages
+    pub(super) const ALL: [(&str, u32); 1] = [("segmentation", SEGMENTATION)];
+}
+
+/// Terms of one end of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handshake {
+    /// Message protocol version
+    pub version: u16,
+
+    /// Largest payload, in bytes, the client will send or the server
+    /// accepts (`u64::MAX` for no limit); in agreed terms, the smaller
+    pub max_message_size: u64,
+
+    /// Wire features in use, a set of [`features`] flags
+    pub features: u32,
+}
+
+impl Handshake {
+    /// This build's terms, with payloads up to `max_message_size` bytes,
+    /// segmented or not.
+    pub fn new(max_message_size: u64, segmented: bool) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            max_message_size,
+            features: if segmented { features::SEGMENTATION } else { 0 },
+        }
+    }
+
+    /// These terms, proposing checked frames if `on`.
+    pub fn with_frame_checksums(mut self, on: bool) -> Self {
+        if on {
+            self.features |= features::FRAME_CHECKSUMS;
+        }
+        self
+    }
+
+    /// Whether these terms have byte-stream frames carry a CRC-32.
+    pub fn frame_checksums(&self) -> bool {
+        self.features & features::FRAME_CHECKSUMS != 0
+    }
+
+    /// The message a client opens the connection with.
+    pub fn hello(&self) -> Message {
+        self.encode("")
+    }
+
+    /// Terms agreed between this server and a client proposing `client`,
+    /// or why there are none.
+    pub fn negotiate(&self, client: &Handshake) -> Result<Handshake, String> {
+        if client.version != self.version {
+            return Err(format!(
+                "client speaks protocol version {} but the server speaks version {}; \
+                 run the same ipc-benchmark release on both ends",
+                client.version, self.version
+            ));
+        }
+        for (name, flag) in features::ALL {
+            let on = |features: u32| if features & flag != 0 { "on" } else { "off" };
+            if client.features & flag != self.features & flag {
+                return Err(format!(
+                    "{} is {} on the client but {} on the server; configure both ends alike",
+                    name,
+                    on(client.features),
+                    on(self.features)
+                ));
+            }
+        }
+        if client.max_message_size > self.max_message_size {
+            return Err(format!(
+                "client sends payloads of up to {} bytes but the server accepts at most {}; \
+                 give both ends the same --message-size",
+                client.max_message_size, self.max_message_size
+            ));
+        }
+        Ok(Handshake {
+            max_message_size: client.max_message_size,
+            features: (self.features & !features::FRAME_CHECKSUMS)
+                | (client.features & features::FRAME_CHECKSUMS),
+            ..*self
+        })
+    }
+
+    /// A handshake message with these terms and `reason`.
+    fn encode(&self, reason: &str) -> Message {
+        let mut payload = Vec::with_capacity(HANDSHAKE_LEN + reason.len());
+        payload.extend_from_slice(&HANDSHAKE_MAGIC.to_le_bytes());
+        payload.extend_from_slice(&self.version.to_le_bytes());
+        payload.extend_from_slice(&self.features.to_le_bytes());
+        payload.extend_from_slice(&self.max_message_size.to_le_bytes());
+        payload.extend_from_slice(reason.as_bytes());
+        Message::new(u64::MAX, payload, MessageType::Handshake)
+    }
+
+    /// The terms and reason a handshake message carries.
+    fn decode(message: &Message) -> Result<(Self, String)> {
+        let payload = &message.payload;
+        if message.message_type != MessageType::Handshake {
+            bail!(
+                "Expected a handshake, got a {:?} message",
+                message.message_type
+            );
+        }
+        if payload.len() < HANDSHAKE_LEN || payload[..4] != HANDSHAKE_MAGIC.to_le_bytes() {
+            bail!("Malformed handshake of {} bytes", payload.len());
+        }
+        let handshake = Self {
+            version: u16::from_le_bytes([payload[4], payload[5]]),
+            features: u32::from_le_bytes(payload[6..10].try_into().unwrap()),
+            max_message_size: u64::from_le_bytes(payload[10..18].try_into().unwrap()),
+        };
+        let reason = String::from_utf8_lossy(&payload[HANDSHAKE_LEN..]).into_owned();
+        Ok((handshake, reason))
+    }
+}
+
+/// Whether clients of `mechanism` can hear the server's answer, and so
+/// handshake.
+pub fn has_reply_path(mechanism: &IpcMechanism) -> bool {
+    match mechanism {
+        IpcMechanism::SharedMemory => false,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::UdpMulticast => false,
+        _ => true,
+    }
+}
+
+/// Answer a client's handshake `message` as a server with terms `server`.
+///
+/// Returns the reply to send back, and the agreed terms or the reason the
+/// server refuses the client, after which it should stop serving it.
+pub fn answer(server: &Handshake, message: &Message) -> (Message, Result<Handshake>) {
+    let outcome = Handshake::decode(message)
+        .map_err(|e| e.to_string())
+        .and_then(|(client, _)| server.negotiate(&client));
+    match outcome {
+        Ok(agreed) => (agreed.encode(""), Ok(agreed)),
+        Err(reason) => {
+            let reply = server.encode(&reason);
+            (reply, Err(anyhow!("Refused a client: {}", reason)))
+        }
+    }
+}
+
+/// Whether `reply`, a server's answer to a handshake, agrees on checked
+/// frames; the server switches to them once the reply is sent.
+pub fn agrees_on_frame_checksums(reply: &Message) -> bool {
+    accepted(reply).is_ok_and(|agreed| agreed.frame_checksums())
+}
+
+/// Handshake as a client with terms `client` on a connected blocking
+/// transport, returning the agreed terms.
+///
+/// The transport sends checked frames from then on if the terms say so.
+pub fn client_blocking(
+    transport: &mut dyn BlockingTransport,
+    client: &Handshake,
+) -> Result<Handshake> {
+    transport
+        .send_blocking(&client.hello())
+        .context("Failed to send the handshake")?;
+    let reply = transport.receive_blocking().context(NO_REPLY)?;
+    let agreed = accepted(&reply)?;
+    transport.set_frame_checksums(agreed.frame_checksums());
+    Ok(agreed)
+}
+
+/// Handshake as a client with terms `client` on a connected async
+/// transport, returning the agreed terms.
+///
+/// The transport sends checked frames from then on if the terms say so.
+pub async fn client(transport: &mut dyn IpcTransport, client: &Handshake) -> Result<Handshake> {
+    transport
+        .send(&client.hello())
+        .await
+        .context("Failed to send the handshake")?;
+    let reply = tokio::time::timeout(HANDSHAKE_TIMEOUT, transport.receive())
+        .await
+        .map_err(|_| anyhow!("{} (waited {:?})", NO_REPLY, HANDSHAKE_TIMEOUT))?
+        .context(NO_REPLY)?;
+    let agreed = accepted(&reply)?;
+    transport.set_frame_checksums(agreed.frame_checksums());
+    Ok(agreed)
+}
+
+/// The agreed terms in the server's `reply`, or its reason for refusing.
+fn accepted(reply: &Message) -> Result<Handshake> {
+    let (agreed, reason) = Handshake::decode(reply).context("Invalid handshake reply")?;
+    if !reason.is_empty() {
+        bail!("The server refused the connection: {}", reason);
+    }
+    Ok(agreed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_terms_are_agreed() {
+        let server = Handshake::new(u64::MAX, true);
+        let client = Handshake::new(4096, true);
+        let (reply, outcome) = answer(&server, &client.hello());
+        assert_eq!(reply.id, u64::MAX);
+        let agreed = accepted(&reply).unwrap();
+        assert_eq!(outcome.unwrap(), agreed);
+        assert_eq!(agreed.max_message_size, 4096);
+        assert_eq!(agreed.features, features::SEGMENTATION);
+        assert!(!agreed.frame_checksums());
+    }
+
+    #[test]
+    fn test_frame_checksums_follow_the_client() {
+        let server = Handshake::new(u64::MAX, false);
+        let client = Handshake::new(4096, false).with_frame_checksums(true);
+        let (reply, outcome) = answer(&server, &client.hello());
+        let agreed = accepted(&reply).unwrap();
+        assert_eq!(outcome.unwrap(), agreed);
+        assert!(agreed.frame_checksums());
+
+        let server = server.with_frame_checksums(true);
+        let (reply, _) = answer(&server, &Handshake::new(4096, false).hello());
+        assert!(!accepted(&reply).unwrap().frame_checksums());
+    }
+
+    #[test]
+    fn test_mismatches_are_refused_with_the_reason() {
+        let server = Handshake::new(1024, false);
+        let refusal = |client: Handshake| {
+            let (reply, outcome) = answer(&server, &client.hello());
+            assert!(outcome.is_err());
+            accepted(&reply).unwrap_err().to_string()
+        };
+
+        let newer = Handshake {
+            version: PROTOCOL_VERSION + 1,
+            ..Handshake::new(1024, false)
+        };
+        assert!(refusal(newer).contains(&format!(
+            "protocol version {} but the server speaks version {}",
+            PROTOCOL_VERSION + 1,
+            PROTOCOL_VERSION
+        )));
+        assert!(refusal(Handshake::new(1024, true))
+            .contains("segmentation is on on the client but off on the server"));
+        assert!(refusal(Handshake::new(2048, false)).contains("up to 2048 bytes"));
+
+        // Not a handshake at all
+        let request = Message::new(1, Vec::new(), MessageType::Request);
+        let (reply, outcome) = answer(&server, &request);
+        assert!(outcome.is_err());
+        assert!(accepted(&reply)
+            .unwrap_err()
+            .to_string()
+            .contains("Request"));
+    }
+}
@@ -0,0 +1,455 @@
+//! HTTP loopback request transport.
+//!
+//! Quantifies "REST between local services" against the raw transports:
+//! every message is the body of a `POST /message` request to a loopback
+//! [hyper] server, speaking either HTTP/1.1 or HTTP/2 (`--http-version`).
+//! Both ends are hyper's own client and server connections, so request and
+//! response heads are encoded and parsed (HPACK included, for HTTP/2) by a
+//! real HTTP stack on every message and their cost is part of the measured
+//! latency.
+//!
+//! # Request/Response Mapping
+//!
+//! HTTP has no one-way messages, so the transport maps the benchmark's
+//! patterns onto request/response pairs:
+//!
+//! - One-way messages (and everything else that gets no reply) are answered
+//!   by the server with an empty `204 No Content` as soon as it receives
+//!   them. The client waits for it before its next request, as a REST
+//!   caller would.
+//! - Requests and pings are left open until the server sends its reply,
+//!   which becomes the `200 OK` response body the client receives.
+//!
+//! # HTTP/2
+//!
+//! HTTP/2 runs in cleartext with prior knowledge (h2c), with each request
+//! on a new stream. Both sides advertise the largest flow control windows
+//! so ordinary messages are never held back.
+//!
+//! The blocking transport, [`BlockingHttp`](super::BlockingHttp), drives
+//! this one on a runtime of its own.
+
+use crate::ipc::{get_monotonic_time_ns, IpcTransport, Message, MessageType, TransportConfig};
+use crate::options::HttpVersion;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use hyper::client::conn::{self as client_conn, ResponseFuture, SendRequest};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{header, Body, Method, Request, Response, StatusCode, Uri};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, trace};
+
+/// Request target of every message
+const PATH: &str = "/message";
+
+/// Largest HTTP/2 flow control window
+const MAX_WINDOW: u32 = 0x7fff_ffff;
+
+/// Requests the connection task may read ahead of the transport
+const CALL_QUEUE: usize = 64;
+
+/// Whether the server replies to `message_type` with a message of its own,
+/// so the response is held open until it does.
+fn expects_reply(message_type: MessageType) -> bool {
+    matches!(
+        message_type,
+        MessageType::Request | MessageType::Ping | MessageType::Handshake
+    )
+}
+
+/// A request the server's connection task has read, waiting for the
+/// transport to answer it.
+struct Call {
+    message: Message,
+
+    /// When the request body had been read
+    receive_time_ns: u64,
+
+    /// The response body, or `None` for `204 No Content`
+    reply: oneshot::Sender<Option<Vec<u8>>>,
+}
+
+/// HTTP loopback transport.
+///
+/// Client messages travel as request bodies and server messages as
+/// response bodies; see the module documentation for the mapping.
+pub struct HttpTransport {
+    /// HTTP version spoken, from the transport configuration
+    version: HttpVersion,
+
+    /// Server listener socket (server mode only)
+    listener: Option<TcpListener>,
+
+    /// Whether this end answers requests
+    is_server: bool,
+
+    /// Client: the connection's request handle
+    sender: Option<SendRequest<Body>>,
+
+    /// Client: where requests are sent
+    uri: Uri,
+
+    /// Client: responses that carry a reply, in request order
+    responses: VecDeque<ResponseFuture>,
+
+    /// Server: requests read from the accepted connection
+    calls: Option<mpsc::Receiver<Call>>,
+
+    /// Server: calls whose response waits for a reply
+    open_calls: VecDeque<oneshot::Sender<Option<Vec<u8>>>>,
+}
+
+impl HttpTransport {
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            version: HttpVersion::H1,
+            listener: None,
+            is_server: false,
+            sender: None,
+            uri: Uri::from_static(PATH),
+            responses: VecDeque::new(),
+            calls: None,
+            open_calls: VecDeque::new(),
+        }
+    }
+
+    /// Accept the next connection on the listener and answer its requests
+    /// through the returned transport.
+    pub(crate) async fn accept(&self) -> Result<Self> {
+        let listener = self
+            .listener
+            .as_ref()
+            .context("Cannot accept: call start_server() first")?;
+        let (stream, peer_addr) = listener
+            .accept()
+            .await
+            .context("Failed to accept HTTP connection")?;
+        debug!(
+            "HTTP ({}) server accepted connection from: {}",
+            self.version, peer_addr
+        );
+        stream
+            .set_nodelay(true)
+            .context("Failed to set TCP_NODELAY on HTTP connection")?;
+
+        let (calls, requests) = mpsc::channel(CALL_QUEUE);
+        let mut http = Http::new();
+        match self.version {
+            HttpVersion::H1 => http.http1_only(true),
+            HttpVersion::H2 => http
+                .http2_only(true)
+                .http2_initial_stream_window_size(MAX_WINDOW)
+                .http2_initial_connection_window_size(MAX_WINDOW),
+        };
+        let connection = http.serve_connection(
+            stream,
+            service_fn(move |request| answer(request, calls.clone())),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("HTTP server connection ended: {}", e);
+            }
+        });
+
+        let mut accepted = Self::new();
+        accepted.version = self.version;
+        accepted.is_server = true;
+        accepted.calls = Some(requests);
+        Ok(accepted)
+    }
+
+    /// Server: the next request, answered at once unless the message asks
+    /// for a reply, and when its body had been read.
+    pub(crate) async fn receive_timed(&mut self) -> Result<(Message, u64)> {
+        if !self.is_server {
+            let message = self.receive_response().await?;
+            return Ok((message, get_monotonic_time_ns()));
+        }
+        if self.calls.is_none() {
+            // Accept happens on first use, after readiness is signalled
+            self.calls = self.accept().await?.calls;
+        }
+        let calls = self
+            .calls
+            .as_mut()
+            .context("HTTP transport not connected")?;
+        let call = calls
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("HTTP client closed the connection"))?;
+        if expects_reply(call.message.message_type) {
+            self.open_calls.push_back(call.reply);
+        } else {
+            // The client is gone if nobody waits for the 204
+            let _ = call.reply.send(None);
+        }
+        Ok((call.message, call.receive_time_ns))
+    }
+
+    /// Client: the reply carried by the oldest outstanding response.
+    async fn receive_response(&mut self) -> Result<Message> {
+        let response = self
+            .responses
+            .pop_front()
+            .ok_or_else(|| anyhow!("HTTP client has no request awaiting a reply"))?;
+        let response = response.await.context("HTTP request failed")?;
+        if response.status() != StatusCode::OK {
+            bail!("HTTP server answered {}", response.status());
+        }
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .context("Failed to read HTTP response body")?;
+        bincode::deserialize(&body).context("Failed to deserialize message")
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serialize `message`, stamped with the current time.
+fn stamped(message: &Message) -> Result<Vec<u8>> {
+    let mut with_timestamp = message.clone();
+    with_timestamp.timestamp = 0;
+    let mut body = bincode::serialize(&with_timestamp).context("Failed to serialize message")?;
+    body[Message::timestamp_offset()].copy_from_slice(&get_monotonic_time_ns().to_le_bytes());
+    Ok(body)
+}
+
+/// An empty response with `status`.
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// Server: hand one request to the transport and respond with its reply.
+async fn answer(
+    request: Request<Body>,
+    calls: mpsc::Sender<Call>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST || request.uri().path() != PATH {
+        return Ok(status(StatusCode::NOT_FOUND));
+    }
+    let Ok(body) = hyper::body::to_bytes(request.into_body()).await else {
+        return Ok(status(StatusCode::BAD_REQUEST));
+    };
+    let receive_time_ns = get_monotonic_time_ns();
+    let Ok(message) = bincode::deserialize(&body) else {
+        return Ok(status(StatusCode::BAD_REQUEST));
+    };
+    let (reply, response) = oneshot::channel();
+    let call = Call {
+        message,
+        receive_time_ns,
+        reply,
+    };
+    if calls.send(call).await.is_err() {
+        return Ok(status(StatusCode::SERVICE_UNAVAILABLE));
+    }
+    Ok(match response.await {
+        Ok(Some(body)) => Response::new(Body::from(body)),
+        Ok(None) => status(StatusCode::NO_CONTENT),
+        // The transport closed without replying
+        Err(_) => status(StatusCode::SERVICE_UNAVAILABLE),
+    })
+}
+
+#[async_trait]
+impl IpcTransport for HttpTransport {
+    async fn start_server(&mut self, config: &TransportConfig) -> Result<()> {
+        let addr = format!("{}:{}", config.host, config.port);
+        self.version = config.http_version;
+        debug!("Starting HTTP ({}) server at: {}", self.version, addr);
+        let listener = TcpListener::bind(&addr).await.with_context(|| {
+            format!(
+                "Failed to bind HTTP server to {}. \
+                 Check if port {} is available and not in use.",
+                addr, config.port
+            )
+        })?;
+        self.listener = Some(listener);
+        self.is_server = true;
+        Ok(())
+    }
+
+    async fn start_client(&mut self, config: &TransportConfig) -> Result<()> {
+        let addr = format!("{}:{}", config.host, config.port);
+        self.version = config.http_version;
+        debug!(
+            "Starting HTTP ({}) client, connecting to: {}",
+            self.version, addr
+        );
+        let stream = TcpStream::connect(&addr).await.with_context(|| {
+            format!(
+                "Failed to connect to HTTP server at {}. Is the server running?",
+                addr
+            )
+        })?;
+        stream
+            .set_nodelay(true)
+            .context("Failed to set TCP_NODELAY on HTTP connection")?;
+
+        let mut builder = client_conn::Builder::new();
+        if self.version == HttpVersion::H2 {
+            builder
+                .http2_only(true)
+                .http2_initial_stream_window_size(MAX_WINDOW)
+                .http2_initial_connection_window_size(MAX_WINDOW);
+            // HTTP/2 requests carry their scheme and authority
+            self.uri = format!("http://{}{}", addr, PATH)
+                .parse()
+                .context("Invalid HTTP server address")?;
+        }
+        let (sender, connection) = builder
+            .handshake(stream)
+            .await
+            .context("HTTP handshake failed")?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("HTTP client connection ended: {}", e);
+            }
+        });
+        self.sender = Some(sender);
+        Ok(())
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<bool> {
+        trace!(
+            "Sending message ID {} via HTTP ({})",
+            message.id,
+            self.version
+        );
+
+        if self.is_server {
+            let reply = self.open_calls.pop_front().ok_or_else(|| {
+                anyhow!("HTTP server can only send in reply to a request or ping")
+            })?;
+            reply
+                .send(Some(stamped(message)?))
+                .map_err(|_| anyhow!("HTTP client closed the connection"))?;
+            return Ok(false);
+        }
+
+        let sender = self.sender.as_mut().context(
+            "HTTP transport not connected. Call start_server() or start_client() first.",
+        )?;
+        std::future::poll_fn(|cx| sender.poll_ready(cx))
+            .await
+            .context("HTTP connection closed")?;
+        let request = Request::post(self.uri.clone())
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .body(Body::from(stamped(message)?))
+            .context("Failed to build HTTP request")?;
+        let response = sender.send_request(request);
+        if expects_reply(message.message_type) {
+            self.responses.push_back(response);
+            return Ok(false);
+        }
+        // Wait for the 204, as a REST caller would
+        let response = response.await.context("HTTP request failed")?;
+        if response.status() != StatusCode::NO_CONTENT {
+            bail!("HTTP server answered {}", response.status());
+        }
+        Ok(false)
+    }
+
+    async fn receive(&mut self) -> Result<Message> {
+        self.receive_timed().await.map(|(message, _)| message)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        debug!("Closing HTTP ({}) transport", self.version);
+        self.sender = None;
+        self.responses.clear();
+        self.calls = None;
+        self.open_calls.clear();
+        self.listener = None;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "HTTP"
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        let listener = self.listener.as_ref()?;
+        listener.local_addr().ok().map(|addr| addr.port())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn round_trip(version: HttpVersion, payload_len: usize) {
+        let mut server = HttpTransport::new();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            http_version: version,
+            ..Default::default()
+        };
+        server.start_server(&config).await.unwrap();
+        let client_config = TransportConfig {
+            port: server.local_port().unwrap(),
+            ..config
+        };
+
+        let client = tokio::spawn(async move {
+            let mut client = HttpTransport::new();
+            client.start_client(&client_config).await.unwrap();
+            for id in 0..5 {
+                let one_way = Message::new(id, vec![1; payload_len], MessageType::OneWay);
+                client.send(&one_way).await.unwrap();
+            }
+            for id in 0..5 {
+                let request = Message::new(id, vec![2; payload_len], MessageType::Request);
+                client.send(&request).await.unwrap();
+                let reply = client.receive().await.unwrap();
+                assert_eq!(reply.id, id);
+                assert_eq!(reply.message_type, MessageType::Response);
+            }
+            client.close().await.unwrap();
+        });
+
+        for id in 0..5 {
+            let message = server.receive().await.unwrap();
+            assert_eq!((message.id, message.payload.len()), (id, payload_len));
+            // Nothing to reply to after a one-way message
+            assert!(server.open_calls.is_empty());
+        }
+        for _ in 0..5 {
+            let request = server.receive().await.unwrap();
+            assert!(request.timestamp > 0);
+            let reply = Message::new(request.id, vec![3; payload_len], MessageType::Response);
+            server.send(&reply).await.unwrap();
+        }
+        client.await.unwrap();
+        assert!(server.receive().await.is_err());
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_http1_one_way_and_round_trip() {
+        round_trip(HttpVersion::H1, 64).await;
+    }
+
+    #[tokio::test]
+    async fn test_http2_one_way_and_round_trip() {
+        round_trip(HttpVersion::H2, 64).await;
+    }
+
+    #[tokio::test]
+    async fn test_http2_large_bodies_span_frames_and_windows() {
+        // Larger than both the default frame size and the initial window
+        round_trip(HttpVersion::H2, 200 * 1024).await;
+    }
+}
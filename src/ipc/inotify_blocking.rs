@@ -0,0 +1,524 @@
+//! File + inotify transport (blocking, Linux only).
+//!
+//! A deliberately "worst practice" baseline: the spool directory pattern
+//! many legacy systems still use to pass work between processes. Every
+//! message becomes a file in a directory on tmpfs (`/dev/shm`), written
+//! under a temporary name and renamed into place so it appears complete.
+//! The receiver watches the directory with inotify, reads each file as its
+//! `IN_MOVED_TO` event arrives and deletes it, and the sender learns from
+//! the matching `IN_DELETE` events how much of the spool is still unread.
+//!
+//! Each message therefore costs a create, write, rename, open, read and
+//! unlink plus an inotify event per side; putting the result next to the
+//! other mechanisms shows what replacing such a spool is worth.
+//!
+//! # Spool Layout
+//!
+//! ```text
+//! /dev/shm/<name>/
+//!     req-00000000000000000042    client -> server message
+//!     rep-00000000000000000042    server -> client message
+//!     client.closed, server.closed    shutdown markers
+//! ```
+//!
+//! `--buffer-size` bounds the bytes of unread messages a sender leaves in
+//! the spool before it waits for the receiver.
+
+use crate::ipc::{BlockingTransport, Message, TransportConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+/// Directory the spools are created in
+const SPOOL_ROOT: &str = "/dev/shm";
+
+/// Longest single wait for inotify events before the spool is checked
+/// again
+const RECHECK: Duration = Duration::from_millis(100);
+
+/// How long a client waits for the server's spool
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Prefix of client-to-server message files
+const REQUEST_PREFIX: &str = "req-";
+
+/// Prefix of server-to-client message files
+const REPLY_PREFIX: &str = "rep-";
+
+/// Path of the spool directory for `name`.
+fn spool_dir(name: &str) -> PathBuf {
+    Path::new(SPOOL_ROOT).join(name.trim_start_matches('/'))
+}
+
+/// File name of message `seq` sent with `prefix`; zero-padded so that
+/// name order is send order.
+fn message_file_name(prefix: &str, seq: u64) -> String {
+    format!("{}{:020}", prefix, seq)
+}
+
+/// An inotify instance watching one directory.
+struct Watch {
+    fd: libc::c_int,
+}
+
+/// One decoded inotify event.
+struct Event {
+    mask: u32,
+    name: String,
+}
+
+impl Watch {
+    fn new(dir: &Path) -> Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if fd < 0 {
+            bail!("inotify_init1 failed: {}", std::io::Error::last_os_error());
+        }
+        let watch = Self { fd };
+        let path = CString::new(dir.as_os_str().as_bytes())?;
+        let wd = unsafe {
+            libc::inotify_add_watch(fd, path.as_ptr(), libc::IN_MOVED_TO | libc::IN_DELETE)
+        };
+        if wd < 0 {
+            bail!(
+                "Failed to watch {}: {}",
+                dir.display(),
+                std::io::Error::last_os_error()
+            );
+        }
+        Ok(watch)
+    }
+
+    /// Wait up to `timeout` for events and return those available.
+    fn read(&self, timeout: Duration) -> Result<Vec<Event>> {
+        let mut pfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pfd, 1, timeout.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut events = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n =
+                unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+            if n < 0 {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::WouldBlock {
+                    return Ok(events);
+                }
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(anyhow!("Failed to read inotify events: {}", err));
+            }
+
+            let header_size = std::mem::size_of::<libc::inotify_event>();
+            let mut offset = 0;
+            while offset + header_size <= n as usize {
+                let event = unsafe {
+                    std::ptr::read_unaligned(buf.as_ptr().add(offset) as *const libc::inotify_event)
+                };
+                let name_start = offset + header_size;
+                let name_bytes = &buf[name_start..name_start + event.len as usize];
+                let name_len = name_bytes
+                    .iter()
+                    .position(|&b| b == 0)
+                    .unwrap_or(name_bytes.len());
+                events.push(Event {
+                    mask: event.mask,
+                    name: String::from_utf8_lossy(&name_bytes[..name_len]).into_owned(),
+                });
+                offset = name_start + event.len as usize;
+            }
+        }
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Blocking file + inotify transport.
+///
+/// Supports one client per server.
+pub struct BlockingInotify {
+    /// The spool directory
+    dir: PathBuf,
+
+    /// Watch on the spool directory
+    watch: Option<Watch>,
+
+    /// Whether this instance created the spool
+    is_server: bool,
+
+    /// Sequence number of the next message file sent
+    next_seq: u64,
+
+    /// Announced message files from the peer, in arrival order
+    pending: VecDeque<String>,
+
+    /// Sizes of this side's message files not yet consumed, by name
+    unread: VecDeque<(String, usize)>,
+
+    /// Bytes of this side's message files not yet consumed
+    unread_bytes: usize,
+
+    /// Upper bound on `unread_bytes` before a send waits
+    spool_limit: usize,
+
+    /// Whether the peer left a shutdown marker
+    peer_closed: bool,
+}
+
+impl BlockingInotify {
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            dir: PathBuf::new(),
+            watch: None,
+            is_server: false,
+            next_seq: 0,
+            pending: VecDeque::new(),
+            unread: VecDeque::new(),
+            unread_bytes: 0,
+            spool_limit: 0,
+            peer_closed: false,
+        }
+    }
+
+    fn send_prefix(&self) -> &'static str {
+        if self.is_server {
+            REPLY_PREFIX
+        } else {
+            REQUEST_PREFIX
+        }
+    }
+
+    fn receive_prefix(&self) -> &'static str {
+        if self.is_server {
+            REQUEST_PREFIX
+        } else {
+            REPLY_PREFIX
+        }
+    }
+
+    fn own_marker(&self) -> &'static str {
+        if self.is_server {
+            "server.closed"
+        } else {
+            "client.closed"
+        }
+    }
+
+    fn peer_marker(&self) -> &'static str {
+        if self.is_server {
+            "client.closed"
+        } else {
+            "server.closed"
+        }
+    }
+
+    /// Place `bytes` in the spool as `name`, atomically.
+    fn publish(&self, name: &str, bytes: &[u8]) -> Result<()> {
+        let tmp = self.dir.join(format!(".{}.tmp", name));
+        std::fs::write(&tmp, bytes)
+            .with_context(|| format!("Failed to write spool file {}", tmp.display()))?;
+        std::fs::rename(&tmp, self.dir.join(name))
+            .with_context(|| format!("Failed to publish spool file {}", name))
+    }
+
+    /// Wait up to `timeout` for inotify events and record what they say.
+    fn pump(&mut self, timeout: Duration) -> Result<()> {
+        let events = match self.watch.as_ref() {
+            Some(watch) => watch.read(timeout)?,
+            None => bail!("Inotify transport not initialized"),
+        };
+        for event in events {
+            if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                warn!("Inotify event queue overflowed; rescanning the spool");
+                self.rescan()?;
+            } else if event.mask & libc::IN_MOVED_TO != 0 {
+                if event.name.starts_with(self.receive_prefix()) {
+                    self.pending.push_back(event.name);
+                } else if event.name == self.peer_marker() {
+                    self.peer_closed = true;
+                }
+            } else if event.mask & libc::IN_DELETE != 0
+                && event.name.starts_with(self.send_prefix())
+            {
+                self.consumed(&event.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget a message file of ours the peer has consumed.
+    fn consumed(&mut self, name: &str) {
+        if let Some(index) = self.unread.iter().position(|(n, _)| n == name) {
+            if let Some((_, size)) = self.unread.remove(index) {
+                self.unread_bytes -= size;
+            }
+        }
+    }
+
+    /// Rebuild the pending and unread lists from the directory, after
+    /// events were lost.
+    fn rescan(&mut self) -> Result<()> {
+        let mut incoming = Vec::new();
+        let mut outgoing = std::collections::HashSet::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name.starts_with(self.receive_prefix()) {
+                incoming.push(name);
+            } else if name.starts_with(self.send_prefix()) {
+                outgoing.insert(name);
+            } else if name == self.peer_marker() {
+                self.peer_closed = true;
+            }
+        }
+        incoming.sort();
+        self.pending = incoming.into();
+        self.unread.retain(|(name, _)| outgoing.contains(name));
+        self.unread_bytes = self.unread.iter().map(|(_, size)| size).sum();
+        Ok(())
+    }
+
+    /// Wait until the peer announced a message file, failing once it shut
+    /// down and none are left. Gives up with `None` at `deadline`, if any.
+    fn next_file(&mut self, deadline: Option<Instant>) -> Result<Option<String>> {
+        loop {
+            if let Some(name) = self.pending.pop_front() {
+                return Ok(Some(name));
+            }
+            if self.peer_closed {
+                return Err(anyhow!("Connection closed"));
+            }
+            let wait = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => RECHECK,
+            };
+            self.pump(wait.min(RECHECK))?;
+            if self.pending.is_empty() {
+                if !self.dir.exists() {
+                    return Err(anyhow!("Connection closed"));
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Read, delete and decode the message file `name`.
+    fn take(&mut self, name: &str) -> Result<Message> {
+        let path = self.dir.join(name);
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read spool file {}", path.display()))?;
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove spool file {}", path.display()))?;
+        bincode::deserialize(&bytes).context("Failed to deserialize message")
+    }
+}
+
+impl Default for BlockingInotify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingTransport for BlockingInotify {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.dir = spool_dir(&config.shared_memory_name);
+        debug!(
+            "Starting blocking inotify server with spool: {}",
+            self.dir.display()
+        );
+
+        // Replace a spool left behind by a killed run
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)
+                .with_context(|| format!("Failed to remove stale spool {}", self.dir.display()))?;
+        }
+        std::fs::create_dir(&self.dir)
+            .with_context(|| format!("Failed to create spool {}", self.dir.display()))?;
+
+        self.watch = Some(Watch::new(&self.dir)?);
+        self.is_server = true;
+        self.spool_limit = config.buffer_size;
+        Ok(())
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.dir = spool_dir(&config.shared_memory_name);
+        debug!(
+            "Starting blocking inotify client, connecting to spool: {}",
+            self.dir.display()
+        );
+
+        let start = Instant::now();
+        while !self.dir.is_dir() {
+            if start.elapsed() > OPEN_TIMEOUT {
+                bail!(
+                    "Timeout waiting for spool {}. Is the server running?",
+                    self.dir.display()
+                );
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        self.watch = Some(Watch::new(&self.dir)?);
+        self.is_server = false;
+        self.spool_limit = config.buffer_size;
+        Ok(())
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        trace!("Sending message ID {} via inotify spool", message.id);
+
+        // Serialize with a placeholder timestamp, refreshed once the spool
+        // has room so waiting for the receiver is not counted as latency
+        let mut message = message.clone();
+        message.timestamp = 0;
+        let mut bytes = bincode::serialize(&message).context("Failed to serialize message")?;
+
+        while !self.unread.is_empty() && self.unread_bytes + bytes.len() > self.spool_limit {
+            if self.peer_closed {
+                return Err(anyhow!("Connection closed"));
+            }
+            self.pump(RECHECK)?;
+        }
+
+        let name = message_file_name(self.send_prefix(), self.next_seq);
+        self.next_seq += 1;
+        let ts = crate::ipc::get_monotonic_time_ns().to_le_bytes();
+        bytes[Message::timestamp_offset()].copy_from_slice(&ts);
+        self.publish(&name, &bytes)?;
+        self.unread_bytes += bytes.len();
+        self.unread.push_back((name, bytes.len()));
+        Ok(())
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        let name = self
+            .next_file(None)?
+            .ok_or_else(|| anyhow!("Connection closed"))?;
+        self.take(&name)
+    }
+
+    fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
+        let name = self
+            .next_file(None)?
+            .ok_or_else(|| anyhow!("Connection closed"))?;
+        let receive_time_ns = crate::ipc::get_monotonic_time_ns();
+        Ok((self.take(&name)?, receive_time_ns))
+    }
+
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        if self.watch.is_none() {
+            return Ok(None);
+        }
+        match self.next_file(Some(Instant::now())) {
+            Ok(Some(name)) => self.take(&name).map(Some),
+            Ok(None) | Err(_) => Ok(None),
+        }
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        debug!("Closing blocking inotify transport");
+        if self.watch.take().is_some() && self.dir.is_dir() {
+            let marker = self.own_marker();
+            if let Err(e) = self.publish(marker, &[]) {
+                debug!("Failed to leave shutdown marker: {}", e);
+            }
+            if self.is_server {
+                if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+                    debug!("Failed to remove spool {}: {}", self.dir.display(), e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+
+    #[test]
+    fn test_message_file_names_sort_in_send_order() {
+        let mut names: Vec<String> = [10, 9, 100]
+            .iter()
+            .map(|&seq| message_file_name(REQUEST_PREFIX, seq))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "req-00000000000000000009",
+                "req-00000000000000000010",
+                "req-00000000000000000100",
+            ]
+        );
+        assert_eq!(spool_dir("/ipc_x"), Path::new("/dev/shm/ipc_x"));
+    }
+
+    #[test]
+    fn test_inotify_round_trip_and_spool_limit() {
+        let config = TransportConfig {
+            shared_memory_name: format!("ipc_benchmark_inotify_test_{}", std::process::id()),
+            // Room for about two messages
+            buffer_size: 256,
+            ..Default::default()
+        };
+        let mut server = BlockingInotify::new();
+        server.start_server_blocking(&config).unwrap();
+
+        let client_config = config.clone();
+        let client = std::thread::spawn(move || {
+            let mut client = BlockingInotify::new();
+            client.start_client_blocking(&client_config).unwrap();
+            for id in 0..20 {
+                client
+                    .send_blocking(&Message::new(id, vec![5; 64], MessageType::OneWay))
+                    .unwrap();
+                assert!(client.unread_bytes <= 256);
+            }
+            let reply = client.receive_blocking().unwrap();
+            assert_eq!(reply.id, 1000);
+            client.close_blocking().unwrap();
+        });
+
+        for id in 0..20 {
+            let (message, _) = server.receive_blocking_timed().unwrap();
+            assert_eq!(message.id, id);
+            assert_eq!(message.payload, vec![5; 64]);
+            assert!(message.timestamp > 0);
+        }
+        server
+            .send_blocking(&Message::new(1000, Vec::new(), MessageType::Response))
+            .unwrap();
+        client.join().unwrap();
+
+        // The client's shutdown marker fails a waiting receive
+        assert!(server.receive_blocking().is_err());
+        server.close_blocking().unwrap();
+        assert!(!spool_dir(&config.shared_memory_name).exists());
+    }
+}
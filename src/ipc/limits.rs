@@ -0,0 +1,289 @@
+//! Discovery of system limits that bound each transport.
+//!
+//! The defaults the runners pick (8 KB PMQ messages, a depth-10 queue,
+//! multi-megabyte socket buffers, SHM rings sized to the message) only work
+//! if the host allows them. Rather than failing half-way through a run with
+//! an `EINVAL` from `mq_open` or a `SIGBUS` from a ring that did not fit in
+//! `/dev/shm`, [`TransportLimits::discover`] reads the relevant limits up
+//! front and [`TransportLimits::apply`] clamps or rejects the configuration
+//! before any resources are created.
+//!
+//! ## Probed Limits
+//!
+//! - `/proc/sys/fs/mqueue/msgsize_max` and `msg_max` for POSIX message queues
+//! - `/proc/sys/net/core/wmem_max` and `rmem_max`, the SO_SNDBUF/SO_RCVBUF
+//!   ceilings for unprivileged sockets
+//! - free space in `/dev/shm` for shared memory
+//!
+//! Limits that cannot be read (other platforms, restricted `/proc`) are
+//! left as `None` and not enforced.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use tracing::info;
+use tracing::warn;
+
+use crate::mechanism::IpcMechanism;
+
+/// System limits discovered for one mechanism.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransportLimits {
+    /// Largest POSIX message queue message, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pmq_msgsize_max: Option<usize>,
+
+    /// Deepest POSIX message queue, in messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pmq_msg_max: Option<usize>,
+
+    /// Largest socket send/receive buffer an unprivileged process can set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_buffer_max: Option<usize>,
+
+    /// Free space in `/dev/shm`, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_free_bytes: Option<u64>,
+}
+
+impl TransportLimits {
+    /// Probe the limits relevant to `mechanism`.
+    pub fn discover(mechanism: &IpcMechanism) -> Self {
+        let mut limits = Self::default();
+        match mechanism {
+            #[cfg(target_os = "linux")]
+            IpcMechanism::PosixMessageQueue => {
+                limits.pmq_msgsize_max = read_proc_usize("/proc/sys/fs/mqueue/msgsize_max");
+                limits.pmq_msg_max = read_proc_usize("/proc/sys/fs/mqueue/msg_max");
+            }
+            IpcMechanism::SharedMemory => {
+                limits.shm_free_bytes = shm_free_bytes();
+            }
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Futex | IpcMechanism::RtSignal | IpcMechanism::Inotify => {
+                limits.shm_free_bytes = shm_free_bytes();
+            }
+            IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc => {
+                limits.socket_buffer_max = socket_buffer_max();
+            }
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => {
+                limits.socket_buffer_max = socket_buffer_max();
+            }
+            #[cfg(unix)]
+            IpcMechanism::UnixDomainSocket | IpcMechanism::Socketpair => {
+                limits.socket_buffer_max = socket_buffer_max();
+            }
+            // Pipe buffers are sized at creation, with no ceiling to probe
+            #[cfg(windows)]
+            IpcMechanism::NamedPipe => {}
+            IpcMechanism::All => {}
+        }
+        limits
+    }
+
+    /// Whether no limit could be discovered.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fit a resolved buffer size and queue depth to these limits.
+    ///
+    /// Returns the (possibly clamped) `(buffer_size, queue_depth)`.
+    /// Automatic values are clamped; an explicit `--buffer-size` that cannot
+    /// work is rejected instead, so the user learns why. Socket buffers over
+    /// the ceiling are only warned about, since the kernel caps them itself.
+    pub fn apply(
+        &self,
+        mechanism: &IpcMechanism,
+        buffer_size: usize,
+        buffer_size_explicit: bool,
+        queue_depth: usize,
+    ) -> Result<(usize, usize)> {
+        // Only the Linux-only mechanisms clamp them
+        #[allow(unused_mut)]
+        let mut buffer_size = buffer_size;
+        #[allow(unused_mut)]
+        let mut queue_depth = queue_depth;
+
+        match mechanism {
+            #[cfg(target_os = "linux")]
+            IpcMechanism::PosixMessageQueue => {
+                if let Some(max) = self.pmq_msgsize_max {
+                    if buffer_size > max {
+                        if buffer_size_explicit {
+                            bail!(
+                                "--buffer-size {} exceeds the POSIX message queue limit of {} bytes \
+                                 (/proc/sys/fs/mqueue/msgsize_max). Lower --buffer-size or raise the sysctl",
+                                buffer_size,
+                                max
+                            );
+                        }
+                        info!(
+                            "Clamping PMQ message size from {} to the system limit of {} bytes",
+                            buffer_size, max
+                        );
+                        buffer_size = max;
+                    }
+                }
+                if let Some(max) = self.pmq_msg_max {
+                    if queue_depth > max {
+                        info!(
+                            "Clamping PMQ queue depth from {} to the system limit of {}",
+                            queue_depth, max
+                        );
+                        queue_depth = max;
+                    }
+                }
+            }
+            IpcMechanism::SharedMemory => {
+                if let Some(free) = self.shm_free_bytes {
+                    if buffer_size as u64 > free {
+                        bail!(
+                            "Shared memory buffer of {} bytes does not fit in /dev/shm ({} bytes free). \
+                             Lower --buffer-size or --message-size, or free space in /dev/shm",
+                            buffer_size,
+                            free
+                        );
+                    }
+                }
+            }
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Inotify => {
+                // The spool holds up to `buffer_size` bytes of unread files
+                if let Some(free) = self.shm_free_bytes {
+                    if buffer_size as u64 > free {
+                        if buffer_size_explicit {
+                            bail!(
+                                "Spool limit of {} bytes does not fit in /dev/shm ({} bytes free). \
+                                 Lower --buffer-size or free space in /dev/shm",
+                                buffer_size,
+                                free
+                            );
+                        }
+                        info!(
+                            "Clamping the inotify spool limit from {} to the {} bytes free in /dev/shm",
+                            buffer_size, free
+                        );
+                        buffer_size = free as usize;
+                    }
+                }
+            }
+            _ => {
+                if let Some(max) = self.socket_buffer_max {
+                    if buffer_size_explicit && buffer_size > max {
+                        warn!(
+                            "--buffer-size {} exceeds the socket buffer ceiling of {} bytes \
+                             (net.core.wmem_max/rmem_max); the kernel will cap it.",
+                            buffer_size, max
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok((buffer_size, queue_depth))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_proc_usize(path: &str) -> Option<usize> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn socket_buffer_max() -> Option<usize> {
+    let wmem = read_proc_usize("/proc/sys/net/core/wmem_max")?;
+    let rmem = read_proc_usize("/proc/sys/net/core/rmem_max")?;
+    Some(wmem.min(rmem))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn socket_buffer_max() -> Option<usize> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn shm_free_bytes() -> Option<u64> {
+    let path = std::ffi::CString::new("/dev/shm").ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn shm_free_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pmq_limits_clamp_defaults_and_reject_explicit_sizes() {
+        let limits = TransportLimits {
+            pmq_msgsize_max: Some(4096),
+            pmq_msg_max: Some(5),
+            ..Default::default()
+        };
+        let mechanism = IpcMechanism::PosixMessageQueue;
+        assert_eq!(
+            limits.apply(&mechanism, 8192, false, 10).unwrap(),
+            (4096, 5)
+        );
+        assert_eq!(limits.apply(&mechanism, 2048, true, 4).unwrap(), (2048, 4));
+        let err = limits.apply(&mechanism, 8192, true, 10).unwrap_err();
+        assert!(err.to_string().contains("msgsize_max"));
+    }
+
+    #[test]
+    fn test_shm_buffer_must_fit_in_dev_shm() {
+        let limits = TransportLimits {
+            shm_free_bytes: Some(1 << 20),
+            ..Default::default()
+        };
+        let mechanism = IpcMechanism::SharedMemory;
+        assert!(limits.apply(&mechanism, 65536, false, 10).is_ok());
+        assert!(limits.apply(&mechanism, 2 << 20, false, 10).is_err());
+    }
+
+    #[test]
+    fn test_socket_ceiling_is_advisory() {
+        let limits = TransportLimits {
+            socket_buffer_max: Some(1024),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits
+                .apply(&IpcMechanism::TcpSocket, 1 << 20, true, 10)
+                .unwrap(),
+            (1 << 20, 10)
+        );
+    }
+
+    #[test]
+    fn test_unknown_limits_are_not_enforced() {
+        let limits = TransportLimits::default();
+        assert!(limits.is_empty());
+        assert_eq!(
+            limits
+                .apply(&IpcMechanism::SharedMemory, usize::MAX / 2, true, 10)
+                .unwrap(),
+            (usize::MAX / 2, 10)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_discover_reads_proc() {
+        let limits = TransportLimits::discover(&IpcMechanism::TcpSocket);
+        if std::path::Path::new("/proc/sys/net/core/wmem_max").exists() {
+            assert!(limits.socket_buffer_max.is_some());
+        }
+        assert!(TransportLimits::discover(&IpcMechanism::All).is_empty());
+    }
+}
@@ -49,40 +49,246 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 #[cfg(not(unix))]
 use time::OffsetDateTime;
 use tokio::sync::mpsc;
 
+use crate::options::SpinWait;
+use crate::queue_depth::QueueDepthProbe;
 use crate::utils::get_temp_socket_path;
 
+/// Whether a stream socket already holds at least a complete 4-byte
+/// length prefix, checked without consuming data or blocking.
+///
+/// Once a frame's prefix has arrived the rest of the frame is already in
+/// flight, so a following blocking read completes promptly.
+#[cfg(unix)]
+pub(crate) fn socket_has_pending_frame(fd: std::os::unix::io::RawFd) -> bool {
+    let mut prefix = [0u8; framing::LENGTH_PREFIX_LEN];
+    let n = unsafe {
+        libc::recv(
+            fd,
+            prefix.as_mut_ptr() as *mut libc::c_void,
+            prefix.len(),
+            libc::MSG_PEEK | libc::MSG_DONTWAIT,
+        )
+    };
+    n == prefix.len() as isize
+}
+
+/// Enable TCP keepalive on a connected socket when an idle time is given.
+///
+/// Probing starts once the connection has been idle for `idle`; the probe
+/// interval and count are left at the OS defaults.
+#[cfg(feature = "tcp")]
+pub(crate) fn apply_tcp_keepalive(
+    socket: socket2::SockRef<'_>,
+    idle: Option<std::time::Duration>,
+) -> std::io::Result<()> {
+    match idle {
+        Some(idle) => socket.set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(idle)),
+        None => Ok(()),
+    }
+}
+
+/// Restrict a socket to one network interface (SO_BINDTODEVICE).
+///
+/// Packets then only leave and arrive through `device`, whatever the
+/// routing table says. Kernels before 5.7 require CAP_NET_RAW for this.
+#[cfg(all(target_os = "linux", feature = "tcp"))]
+pub(crate) fn apply_bind_device(
+    socket: socket2::SockRef<'_>,
+    device: Option<&str>,
+) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let Some(device) = device else {
+        return Ok(());
+    };
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            device.as_ptr().cast(),
+            device.len() as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Restrict a socket to one network interface (unsupported on this platform).
+#[cfg(all(not(target_os = "linux"), feature = "tcp"))]
+pub(crate) fn apply_bind_device(
+    _socket: socket2::SockRef<'_>,
+    device: Option<&str>,
+) -> std::io::Result<()> {
+    match device {
+        Some(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "binding to a network device requires Linux",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Set a socket's `SO_PRIORITY`, the priority the kernel's queueing
+/// disciplines give its packets. Values above 6 need `CAP_NET_ADMIN`.
+#[cfg(target_os = "linux")]
+pub(crate) fn apply_socket_priority(
+    socket: socket2::SockRef<'_>,
+    priority: u8,
+) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let value = libc::c_int::from(priority);
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PRIORITY,
+            (&value as *const libc::c_int).cast(),
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Set a socket's priority (unsupported on this platform).
+#[cfg(all(not(target_os = "linux"), any(unix, feature = "tcp")))]
+pub(crate) fn apply_socket_priority(
+    _socket: socket2::SockRef<'_>,
+    priority: u8,
+) -> std::io::Result<()> {
+    match priority {
+        0 => Ok(()),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "socket priorities require Linux",
+        )),
+    }
+}
+
+/// Apply the client-side interface and source address selections.
+///
+/// Must be called before `connect`. The source port is left to the kernel.
+#[cfg(feature = "tcp")]
+pub(crate) fn apply_tcp_source(
+    socket: socket2::SockRef<'_>,
+    config: &TransportConfig,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    if let Some(device) = config.bind_device.as_deref() {
+        apply_bind_device((&*socket).into(), Some(device))
+            .with_context(|| format!("Failed to bind TCP socket to device '{}'", device))?;
+    }
+    if let Some(ip) = config.source_addr {
+        socket
+            .bind(&std::net::SocketAddr::new(ip, 0).into())
+            .with_context(|| format!("Failed to bind TCP socket to source address {}", ip))?;
+    }
+    Ok(())
+}
+
 // Public module exports for specific transport implementations
+pub mod capability;
+pub mod fixed_layout;
+pub mod framing;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+pub mod futex_blocking;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "grpc")]
+pub mod grpc_blocking;
+pub mod handshake;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "http")]
+pub mod http_blocking;
 #[cfg(target_os = "linux")]
+pub mod inotify_blocking;
+pub mod limits;
+#[cfg(windows)]
+pub mod named_pipe;
+#[cfg(windows)]
+pub mod named_pipe_blocking;
+#[cfg(all(target_os = "linux", feature = "pmq"))]
 pub mod posix_message_queue;
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", feature = "pmq"))]
 pub mod posix_message_queue_blocking;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+pub mod rt_signal_blocking;
+pub mod segmentation;
+#[cfg(feature = "shm")]
 pub mod shared_memory;
+#[cfg(feature = "shm")]
 pub mod shared_memory_blocking;
-#[cfg(unix)]
+#[cfg(all(unix, feature = "shm"))]
 pub mod shared_memory_direct;
+#[cfg(all(windows, feature = "shm"))]
+mod shm_event;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+mod shm_hugetlbfs;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+mod shm_semaphore;
+#[cfg(feature = "tcp")]
 pub mod tcp_socket;
+#[cfg(feature = "tcp")]
 pub mod tcp_socket_blocking;
+#[cfg(target_os = "linux")]
+pub mod udp_multicast_blocking;
+#[cfg(unix)]
+pub mod uds_credentials;
 #[cfg(unix)]
 pub mod unix_domain_socket;
 #[cfg(unix)]
 pub mod unix_domain_socket_blocking;
 
 // Re-export transport implementations for convenient access
+#[cfg(feature = "shm")]
 pub use self::shared_memory::SharedMemoryTransport;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+pub use futex_blocking::BlockingFutex;
+#[cfg(feature = "grpc")]
+pub use grpc::GrpcTransport;
+#[cfg(feature = "grpc")]
+pub use grpc_blocking::BlockingGrpc;
+#[cfg(feature = "http")]
+pub use http::HttpTransport;
+#[cfg(feature = "http")]
+pub use http_blocking::BlockingHttp;
 #[cfg(target_os = "linux")]
+pub use inotify_blocking::BlockingInotify;
+#[cfg(windows)]
+pub use named_pipe::NamedPipeTransport;
+#[cfg(windows)]
+pub use named_pipe_blocking::BlockingNamedPipe;
+#[cfg(all(target_os = "linux", feature = "pmq"))]
 pub use posix_message_queue::PosixMessageQueueTransport;
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", feature = "pmq"))]
 pub use posix_message_queue_blocking::BlockingPosixMessageQueue;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+pub use rt_signal_blocking::BlockingRtSignal;
+#[cfg(feature = "shm")]
 pub use shared_memory_blocking::BlockingSharedMemory;
-#[cfg(unix)]
+#[cfg(all(unix, feature = "shm"))]
 pub use shared_memory_direct::BlockingSharedMemoryDirect;
+#[cfg(feature = "tcp")]
 pub use tcp_socket::TcpSocketTransport;
+#[cfg(feature = "tcp")]
 pub use tcp_socket_blocking::BlockingTcpSocket;
+#[cfg(target_os = "linux")]
+pub use udp_multicast_blocking::BlockingUdpMulticast;
 #[cfg(unix)]
 pub use unix_domain_socket::UnixDomainSocketTransport;
 #[cfg(unix)]
@@ -135,6 +341,48 @@ pub fn get_monotonic_time_ns() -> u64 {
     }
 }
 
+/// CPU time consumed so far by the calling thread, in nanoseconds.
+///
+/// Reads `CLOCK_THREAD_CPUTIME_ID`. The difference between two readings
+/// around a loop is the time that thread actually ran, as opposed to the
+/// wall time it spent blocked. `None` on platforms without a thread clock.
+pub fn get_thread_cpu_time_ns() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        let ret = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+        (ret == 0).then(|| (ts.tv_sec as u64) * 1_000_000_000 + (ts.tv_nsec as u64))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// CPU time consumed so far by the whole process, in nanoseconds.
+///
+/// Reads `CLOCK_PROCESS_CPUTIME_ID`, which sums every thread, so it also
+/// covers async runtimes that move work between worker threads. `None` on
+/// platforms without a process clock.
+pub fn get_process_cpu_time_ns() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        let ret = unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut ts) };
+        (ret == 0).then(|| (ts.tv_sec as u64) * 1_000_000_000 + (ts.tv_nsec as u64))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
 /// Custom error types for IPC operations.
 #[derive(Error, Debug)]
 pub enum IpcError {
@@ -193,11 +441,14 @@ pub struct Message {
     /// and to detect message loss or reordering in transport.
     pub id: u64,
 
-    /// Timestamp when message was created (nanoseconds since epoch)
+    /// Send timestamp, in nanoseconds of the monotonic clock
     ///
-    /// High-precision timestamp enables accurate latency measurement
-    /// from message creation to receipt, accounting for serialization
-    /// and transport overhead.
+    /// Set by [`get_monotonic_time_ns`] at creation and refreshed right
+    /// before the send. `CLOCK_MONOTONIC` counts from boot and is the same
+    /// clock in every process on a host, so it is a shared epoch: the
+    /// receiver subtracts this from its own reading to get the genuine
+    /// one-way delivery latency, with no handshake needed. Across hosts the
+    /// clocks are unrelated and the difference is meaningless.
     pub timestamp: u64,
 
     /// Message payload data
@@ -213,6 +464,30 @@ pub struct Message {
     /// request-response cycles, and ping-pong latency measurement.
     pub message_type: MessageType,
 
+    /// Time to live after `timestamp`, in nanoseconds (`--deadline`)
+    ///
+    /// The message's deadline is `timestamp + ttl_ns` on the monotonic
+    /// clock. Because the deadline is relative to the send timestamp, it
+    /// stays correct when blocking transports refresh the timestamp right
+    /// before the send. `None` means no deadline.
+    pub ttl_ns: Option<u64>,
+
+    /// Delivery priority (`--priorities`), higher is more urgent
+    ///
+    /// Transports map it onto their own priority mechanism: the PMQ message
+    /// priority, `SO_PRIORITY` on sockets, and the per-priority receive
+    /// lanes of the SHM ring. `0` is the default priority.
+    pub priority: u8,
+
+    /// Monotonic time the server received the request this message
+    /// answers, in nanoseconds
+    ///
+    /// Set by the benchmark server on replies. Together with the reply's
+    /// own `timestamp`, taken at its send, it splits a round trip into
+    /// request transit, server processing and reply transit. `0` on every
+    /// other message.
+    pub server_receive_ns: u64,
+
     /// Monotonic nanosecond timestamp captured inside the transport's
     /// receive path, as close to the condvar wake-up as possible.
     ///
@@ -232,6 +507,19 @@ pub struct Message {
     /// format is unchanged and backward-compatible.
     #[serde(skip, default)]
     pub receive_time_ns: u64,
+
+    /// Monotonic nanosecond timestamp captured when the first bytes of
+    /// this message arrived at the receiver.
+    ///
+    /// Stream transports (TCP, UDS) populate this when
+    /// `TransportConfig::first_byte_timestamps` is set, right after the
+    /// length prefix has been read; the rest of a large payload may still
+    /// be in flight at that point. Other transports deliver a message in
+    /// one piece and leave it at 0, meaning first byte == last byte.
+    ///
+    /// Never serialized, like `receive_time_ns`.
+    #[serde(skip, default)]
+    pub first_byte_time_ns: u64,
 }
 
 /// Message types for different benchmark patterns
@@ -294,6 +582,30 @@ pub enum MessageType {
     /// terminate gracefully. Only used by mechanisms that lack
     /// connection-based semantics (e.g., POSIX Message Queues).
     Shutdown,
+
+    /// Handshake message (protocol version and capabilities)
+    ///
+    /// Sent by the client before any test traffic and answered by the
+    /// server with the agreed terms or a refusal; see [`handshake`].
+    Handshake,
+}
+
+/// How a received message relates to the request awaiting its reply
+///
+/// Round-trip clients check every reply so that a transport delivering
+/// duplicated, reordered or late messages shows up as errors instead of
+/// as a latency sample for the wrong request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseCheck {
+    /// The reply to the outstanding request
+    Matched,
+
+    /// A reply to an earlier request: a duplicate, or a reply that arrived
+    /// after its request stopped waiting for it
+    Stale,
+
+    /// Anything else: a reply to a request not sent yet, or not a reply
+    Mismatched,
 }
 
 impl From<u32> for MessageType {
@@ -317,6 +629,7 @@ impl From<u32> for MessageType {
             3 => MessageType::Ping,
             4 => MessageType::Pong,
             5 => MessageType::Shutdown,
+            6 => MessageType::Handshake,
             _ => {
                 // Default to OneWay for unknown values (shouldn't happen in practice)
                 tracing::warn!(
@@ -359,7 +672,11 @@ impl Message {
             timestamp: get_monotonic_time_ns(),
             payload,
             message_type,
+            ttl_ns: None,
+            priority: 0,
+            server_receive_ns: 0,
             receive_time_ns: 0,
+            first_byte_time_ns: 0,
         }
     }
 
@@ -389,7 +706,11 @@ impl Message {
             timestamp: get_monotonic_time_ns(),
             payload,
             message_type,
+            ttl_ns: None,
+            priority: 0,
+            server_receive_ns: 0,
             receive_time_ns: 0,
+            first_byte_time_ns: 0,
         }
     }
 
@@ -437,6 +758,67 @@ impl Message {
         8..16
     }
 
+    /// Serialize into `buffer` with a zero timestamp, for the caller to
+    /// stamp at [`timestamp_offset`](Self::timestamp_offset) right before
+    /// the send.
+    ///
+    /// Reuses `buffer`'s allocation, so a transport that keeps one buffer
+    /// for its sends serializes without allocating once the buffer has
+    /// grown to the message size.
+    pub fn serialize_unstamped_into(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        use anyhow::Context;
+
+        buffer.clear();
+        bincode::serialize_into(&mut *buffer, self).context("Failed to serialize message")?;
+        buffer[Self::timestamp_offset()].fill(0);
+        Ok(())
+    }
+
+    /// Give the message a deadline `ttl` after its send timestamp.
+    pub fn with_ttl(mut self, ttl: Option<std::time::Duration>) -> Self {
+        self.ttl_ns = ttl.map(|ttl| ttl.as_nanos() as u64);
+        self
+    }
+
+    /// Send the message at `priority`.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Mark a reply with the time its request reached the server.
+    pub fn with_server_receive_ns(mut self, server_receive_ns: u64) -> Self {
+        self.server_receive_ns = server_receive_ns;
+        self
+    }
+
+    /// Monotonic time after which the message is late, if it has a deadline
+    pub fn deadline_ns(&self) -> Option<u64> {
+        self.ttl_ns.map(|ttl| self.timestamp.saturating_add(ttl))
+    }
+
+    /// Whether the message arrived at `receive_time_ns` past its deadline
+    pub fn is_expired(&self, receive_time_ns: u64) -> bool {
+        self.deadline_ns()
+            .is_some_and(|deadline| receive_time_ns > deadline)
+    }
+
+    /// Check whether this message is the reply to the request `request_id`.
+    ///
+    /// Servers answer each Request with a Response carrying the same ID, and
+    /// clients send IDs in increasing order, so a Response with an earlier
+    /// ID answers an earlier request. With `--id-width`, earlier is judged
+    /// across the wrap point.
+    pub fn check_response(&self, request_id: u64) -> ResponseCheck {
+        match self.message_type {
+            MessageType::Response if self.id == request_id => ResponseCheck::Matched,
+            MessageType::Response if crate::sequence::precedes(self.id, request_id) => {
+                ResponseCheck::Stale
+            }
+            _ => ResponseCheck::Mismatched,
+        }
+    }
+
     /// Get the message size in bytes
     ///
     /// Calculates the approximate serialized size of the message,
@@ -452,6 +834,7 @@ impl Message {
     /// - 8 bytes for timestamp (u64)  
     /// - Variable payload length
     /// - 1 byte for message type enum discriminant
+    /// - 1 byte for the TTL option tag, plus 8 when a TTL is set
     ///
     /// Note: This is an approximation; actual serialized size may
     /// vary slightly due to encoding overhead.
@@ -460,7 +843,10 @@ impl Message {
         8 + // id
         8 + // timestamp
         self.payload.len() + // payload
-        1 // message_type (enum discriminant)
+        1 + // message_type (enum discriminant)
+        if self.ttl_ns.is_some() { 9 } else { 1 } + // ttl_ns
+        1 + // priority
+        8 // server_receive_ns
     }
 
     /// Serialize the message to bytes
@@ -558,6 +944,7 @@ pub struct TransportConfig {
     ///
     /// Filesystem path for the Unix domain socket. Should be in
     /// a writable directory and will be cleaned up after testing.
+    /// On Windows this is the named pipe's path, `\\.\pipe\<name>`.
     pub socket_path: String,
 
     /// Shared memory segment name
@@ -591,6 +978,121 @@ pub struct TransportConfig {
     /// Sets the priority for messages sent via PMQ. Higher numbers
     /// indicate higher priority. This is only used by the PMQ transport.
     pub pmq_priority: u32,
+
+    /// Alignment of each record in the shared memory ring buffer
+    ///
+    /// Records start at a multiple of this many bytes. `1` packs records
+    /// back to back; 64 or 128 gives every record its own cache line(s).
+    /// Only used by the ring-buffer SHM transports, and only by the side
+    /// that creates the segment.
+    pub shm_slot_align: usize,
+
+    /// Place the SHM ring's read and write indices on separate cache lines
+    ///
+    /// Only used by the ring-buffer SHM transports, and only by the side
+    /// that creates the segment.
+    pub shm_pad_indices: bool,
+
+    /// Maximum number of messages in flight in the SHM ring
+    ///
+    /// `buffer_size` bounds the ring in bytes; this additionally bounds it in
+    /// messages, so a sender blocks once this many records are unread even if
+    /// bytes remain. `None` bounds the ring by bytes only. Only used by the
+    /// ring-buffer SHM transports, and only by the side that creates the segment.
+    pub shm_capacity_messages: Option<usize>,
+
+    /// Deliver SHM ring messages highest priority first
+    ///
+    /// The receiver moves every message already queued in the ring into a
+    /// lane for its [`Message::priority`] and delivers from the highest
+    /// non-empty lane, FIFO within a lane. Only used by the blocking
+    /// ring-buffer SHM transport, on the receiving side.
+    pub shm_priority_lanes: bool,
+
+    /// How the SHM ring wakes a blocked peer
+    ///
+    /// See [`crate::options::ShmWait`]. Only used by the blocking ring-buffer
+    /// SHM transport. Both ends must use the same mode.
+    pub shm_wait: crate::options::ShmWait,
+
+    /// hugetlbfs mount whose huge pages back the SHM ring
+    ///
+    /// `None` uses a POSIX shared memory object. Only used by the blocking
+    /// ring-buffer SHM transport (Linux only). Both ends must use the same
+    /// directory.
+    pub shm_hugetlbfs: Option<std::path::PathBuf>,
+
+    /// Carry SHM ring messages as fixed-layout records instead of bincode
+    ///
+    /// See [`fixed_layout`]. Only used by the blocking ring-buffer SHM
+    /// transport. Both ends must agree.
+    pub shm_zero_copy: bool,
+
+    /// HTTP version spoken by the HTTP transport
+    pub http_version: crate::options::HttpVersion,
+
+    /// Peer credential checks performed by the UDS transports
+    ///
+    /// See [`crate::options::UdsCredentialMode`]. Ignored by other transports.
+    pub uds_credentials: crate::options::UdsCredentialMode,
+
+    /// TCP keepalive idle time (None leaves keepalive disabled)
+    ///
+    /// Applied to both ends of TCP connections. Ignored by other transports.
+    pub tcp_keepalive: Option<std::time::Duration>,
+
+    /// Record when each message's first bytes arrive
+    ///
+    /// Stream transports (TCP, UDS) then fill in
+    /// `Message::first_byte_time_ns` on receive. Off by default so the
+    /// extra clock read stays out of ordinary measurements.
+    pub first_byte_timestamps: bool,
+
+    /// Write each frame in pieces of at most this many bytes
+    ///
+    /// A test mode for the stream transports (TCP, UDS), so receivers see
+    /// frames arrive in partial reads; see [`framing`]. `None` writes
+    /// every frame at once. Ignored by other transports.
+    pub fragment_writes: Option<usize>,
+
+    /// Propose checked frames in a client's handshake (`--verify-frames`)
+    ///
+    /// The stream transports (TCP, UDS) add a CRC-32 to each frame once
+    /// the server agrees; see [`framing`]. Ignored by other transports.
+    pub verify_frames: bool,
+
+    /// Split messages into segments of at most this many payload bytes
+    ///
+    /// Set when the message size exceeds what the mechanism can carry in
+    /// one message; see [`segmentation`]. Both ends must use the same value.
+    /// `None` sends every message whole.
+    pub segment_size: Option<usize>,
+
+    /// Network interface TCP sockets are restricted to (SO_BINDTODEVICE)
+    ///
+    /// Applied to the server's listener and the client's socket. `None`
+    /// lets the routing table pick. Ignored by other transports.
+    pub bind_device: Option<String>,
+
+    /// Local address TCP clients connect from
+    ///
+    /// Selects the source IP (and so the NIC) on multi-homed hosts. Servers
+    /// choose their address with `host` instead. Ignored by other transports.
+    pub source_addr: Option<std::net::IpAddr>,
+
+    /// Connected socket the transport adopts instead of binding or connecting
+    ///
+    /// Set for `socketpair`: the server's end is inherited from the client
+    /// process, which keeps the other end for itself (see
+    /// [`crate::server_handle::ServerHandle::spawn_with_socketpair`]). The
+    /// transport takes ownership of the descriptor when it starts.
+    pub inherited_fd: Option<i32>,
+
+    /// IPv4 multicast group of the UDP multicast transport
+    ///
+    /// Published to on the interface whose address is `host`. Ignored by
+    /// other transports.
+    pub multicast_group: std::net::Ipv4Addr,
 }
 
 impl Default for TransportConfig {
@@ -609,6 +1111,18 @@ impl Default for TransportConfig {
     /// - Max connections: 16 (reasonable concurrency for most systems)
     /// - Queue depth: 10 (typical system default for message queues)
     /// - Queue name: ipc_benchmark_pmq (descriptive unique name)
+    /// - SHM ring layout: packed records, unpadded indices, bounded by bytes only
+    /// - SHM priority lanes: off
+    /// - SHM wait: condition variables
+    /// - SHM backing: POSIX shared memory
+    /// - UDS credentials: not checked
+    /// - TCP keepalive: disabled
+    /// - First-byte timestamps: off
+    /// - Fragmented writes: off
+    /// - Segmentation: off
+    /// - TCP interface and source address: chosen by the kernel
+    /// - Inherited socket: none
+    /// - Multicast group: 239.255.0.1 (organization-local scope)
     fn default() -> Self {
         Self {
             buffer_size: 8192,
@@ -620,10 +1134,53 @@ impl Default for TransportConfig {
             message_queue_depth: 10, // Default POSIX Message Queue depth
             message_queue_name: "ipc_benchmark_pmq".to_string(), // Default PMQ name
             pmq_priority: 0,     // Default PMQ message priority
+            shm_slot_align: 1,   // Packed SHM ring records
+            shm_pad_indices: false,
+            shm_capacity_messages: None,
+            shm_priority_lanes: false,
+            shm_zero_copy: false,
+            shm_wait: crate::options::ShmWait::Condvar,
+            shm_hugetlbfs: None,
+            http_version: crate::options::HttpVersion::H1,
+            uds_credentials: crate::options::UdsCredentialMode::Off,
+            tcp_keepalive: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            verify_frames: false,
+            segment_size: None,
+            bind_device: None,
+            source_addr: None,
+            inherited_fd: None,
+            multicast_group: crate::defaults::MULTICAST_GROUP,
         }
     }
 }
 
+/// Transport-specific counters by name, such as `send_eagain` or
+/// `ring_wraps`, for telling what a transport went through during a test.
+pub type TransportStats = BTreeMap<String, u64>;
+
+/// Totals of the [`TransportStats`] of a test's client transports.
+///
+/// Clones share the totals, so every client of a test adds to the same map.
+#[derive(Debug, Clone, Default)]
+pub struct TransportStatsTotals(Arc<Mutex<TransportStats>>);
+
+impl TransportStatsTotals {
+    /// Add a transport's counters to the totals.
+    pub fn add(&self, stats: TransportStats) {
+        let mut totals = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for (name, count) in stats {
+            *totals.entry(name).or_default() += count;
+        }
+    }
+
+    /// The totals so far.
+    pub fn get(&self) -> TransportStats {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
 /// Generic IPC transport interface with multi-client support
 ///
 /// This trait defines the unified interface that all IPC transport
@@ -730,6 +1287,36 @@ pub trait IpcTransport: Send + Sync {
     /// - Async implementation allows cancellation
     async fn receive(&mut self) -> Result<Message>;
 
+    /// Receive a message only if one is already available
+    ///
+    /// Never waits for new data: returns `Ok(None)` when receiving would
+    /// have to wait. Used to drain several queued messages per wakeup.
+    ///
+    /// ## Default Implementation
+    ///
+    /// Returns `Ok(None)`, so transports without a cheap readiness check
+    /// simply never batch.
+    async fn try_receive(&mut self) -> Result<Option<Message>> {
+        Ok(None)
+    }
+
+    /// Receive up to `max` messages in one wakeup
+    ///
+    /// Waits for the first message like [`receive`](Self::receive), then
+    /// appends messages that are already available until `max` have been
+    /// collected or none remain. `out` is cleared first.
+    async fn receive_batch(&mut self, max: usize, out: &mut Vec<Message>) -> Result<()> {
+        out.clear();
+        out.push(self.receive().await?);
+        while out.len() < max {
+            match self.try_receive().await? {
+                Some(message) => out.push(message),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
     /// Close the transport
     ///
     /// Cleanly shuts down the transport, releasing all resources
@@ -790,6 +1377,54 @@ pub trait IpcTransport: Send + Sync {
         1024 * 1024 // 1MB default
     }
 
+    /// Get the local port a server is listening on
+    ///
+    /// Lets a server started on port 0 report the ephemeral port the OS
+    /// picked, so the client can be told where to connect.
+    ///
+    /// ## Default Implementation
+    ///
+    /// Returns `None`, for transports that are not bound to a port.
+    fn local_port(&self) -> Option<u16> {
+        None
+    }
+
+    /// Probe of the queue this transport sends into
+    ///
+    /// Lets `--queue-depth-interval` sample the queue's depth from another
+    /// thread while the transport is in use.
+    ///
+    /// ## Default Implementation
+    ///
+    /// Returns `None`, for transports without a queue to inspect.
+    fn queue_depth_probe(&self) -> Option<Box<dyn QueueDepthProbe>> {
+        None
+    }
+
+    /// Transport-specific counters since the transport was started
+    ///
+    /// Retries, partial writes, ring wraps and the like, which explain
+    /// anomalies in a test's latency without extra logging. They are summed
+    /// over a test's clients into `transport_stats` in the results.
+    ///
+    /// ## Default Implementation
+    ///
+    /// Returns no counters, for transports that keep none.
+    fn stats(&self) -> TransportStats {
+        TransportStats::new()
+    }
+
+    /// Send checked frames from now on
+    ///
+    /// Called on both ends once the handshake has agreed on
+    /// `--verify-frames`. Receivers recognise checked frames by themselves,
+    /// so only the sending side changes.
+    ///
+    /// ## Default Implementation
+    ///
+    /// Does nothing: only the byte-stream transports frame their messages.
+    fn set_frame_checksums(&mut self, _on: bool) {}
+
     // NEW MULTI-CLIENT INTERFACE
 
     /// Check if transport supports multiple concurrent connections
@@ -1022,22 +1657,65 @@ impl TransportFactory {
     /// - `SharedMemory`: Creates shared memory ring buffer transport
     /// - `TcpSocket`: Creates TCP socket transport with optimizations
     /// - `PosixMessageQueue`: Creates POSIX message queue transport
+    /// - `Http`: Creates the HTTP transport over hyper
+    /// - `Grpc`: Creates the gRPC transport over tonic
+    /// - `NamedPipe`: Creates the Windows named pipe transport
+    /// - `Futex`, `RtSignal`, `Inotify`, `Socketpair`, `UdpMulticast`:
+    ///   Fail; these transports are blocking-only
     ///
     /// ## Error Conditions
     ///
     /// - `All` mechanism should be expanded before calling this method
     /// - Transport-specific initialization failures
     /// - System resource limitations
-    pub fn create(mechanism: &crate::cli::IpcMechanism) -> Result<Box<dyn IpcTransport>> {
-        use crate::cli::IpcMechanism;
+    pub fn create(mechanism: &crate::mechanism::IpcMechanism) -> Result<Box<dyn IpcTransport>> {
+        use crate::mechanism::IpcMechanism;
 
         match mechanism {
             #[cfg(unix)]
             IpcMechanism::UnixDomainSocket => Ok(Box::new(UnixDomainSocketTransport::new())),
+            #[cfg(feature = "shm")]
             IpcMechanism::SharedMemory => Ok(Box::new(SharedMemoryTransport::new())),
+            #[cfg(not(feature = "shm"))]
+            IpcMechanism::SharedMemory => Err(not_built(mechanism, "shm")),
+            #[cfg(feature = "tcp")]
             IpcMechanism::TcpSocket => Ok(Box::new(TcpSocketTransport::new())),
-            #[cfg(target_os = "linux")]
+            #[cfg(not(feature = "tcp"))]
+            IpcMechanism::TcpSocket => Err(not_built(mechanism, "tcp")),
+            #[cfg(all(target_os = "linux", feature = "pmq"))]
             IpcMechanism::PosixMessageQueue => Ok(Box::new(PosixMessageQueueTransport::new())),
+            #[cfg(all(target_os = "linux", not(feature = "pmq")))]
+            IpcMechanism::PosixMessageQueue => Err(not_built(mechanism, "pmq")),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Futex => Err(anyhow::anyhow!(
+                "The futex transport is only available in blocking mode (--blocking)"
+            )),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::RtSignal => Err(anyhow::anyhow!(
+                "The RT signal transport is only available in blocking mode (--blocking)"
+            )),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Inotify => Err(anyhow::anyhow!(
+                "The inotify transport is only available in blocking mode (--blocking)"
+            )),
+            #[cfg(feature = "http")]
+            IpcMechanism::Http => Ok(Box::new(HttpTransport::new())),
+            #[cfg(not(feature = "http"))]
+            IpcMechanism::Http => Err(not_built(mechanism, "http")),
+            #[cfg(feature = "grpc")]
+            IpcMechanism::Grpc => Ok(Box::new(GrpcTransport::new())),
+            #[cfg(not(feature = "grpc"))]
+            IpcMechanism::Grpc => Err(not_built(mechanism, "grpc")),
+            #[cfg(unix)]
+            IpcMechanism::Socketpair => Err(anyhow::anyhow!(
+                "The socketpair transport is only available in blocking mode (--blocking)"
+            )),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => Err(anyhow::anyhow!(
+                "The UDP multicast transport is only available in blocking mode (--blocking)"
+            )),
+            #[cfg(windows)]
+            IpcMechanism::NamedPipe => Ok(Box::new(NamedPipeTransport::new())),
             IpcMechanism::All => Err(anyhow::anyhow!(
                 "'All' mechanism should be expanded before transport creation"
             )),
@@ -1045,6 +1723,17 @@ impl TransportFactory {
     }
 }
 
+/// Error for a mechanism whose transport this build leaves out, naming the
+/// cargo feature that adds it.
+#[allow(dead_code)]
+fn not_built(mechanism: &crate::mechanism::IpcMechanism, feature: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} support is not built in; rebuild with the `{}` feature",
+        mechanism,
+        feature
+    )
+}
+
 /// Blocking/synchronous transport interface.
 ///
 /// This trait defines the interface for IPC transports that use traditional
@@ -1086,7 +1775,7 @@ impl TransportFactory {
 /// ```rust,no_run
 /// use ipc_benchmark::ipc::{BlockingTransport, TransportConfig, Message, MessageType};
 /// use ipc_benchmark::ipc::BlockingTransportFactory;
-/// use ipc_benchmark::cli::IpcMechanism;
+/// use ipc_benchmark::IpcMechanism;
 ///
 /// # fn example() -> anyhow::Result<()> {
 /// // Create a blocking transport (use false for ring buffer mode)
@@ -1233,6 +1922,90 @@ pub trait BlockingTransport: Send {
         Ok((msg, get_monotonic_time_ns()))
     }
 
+    /// Receive a message only if one is already available.
+    ///
+    /// Never blocks waiting for new data: returns `Ok(None)` when a
+    /// receive would have to wait. Used to drain several queued messages
+    /// per wakeup.
+    ///
+    /// The default implementation returns `Ok(None)`, so transports
+    /// without a cheap readiness check simply never batch.
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        Ok(None)
+    }
+
+    /// Whether `try_receive_blocking()` detects queued messages.
+    ///
+    /// `false` for transports relying on the default implementation,
+    /// which would make a spinning receive wait forever.
+    fn supports_try_receive(&self) -> bool {
+        false
+    }
+
+    /// Receive up to `max` messages in one wakeup.
+    ///
+    /// Blocks for the first message like `receive_blocking()`, then
+    /// appends messages that are already available until `max` have been
+    /// collected or none remain. `out` is cleared first.
+    fn receive_batch_blocking(&mut self, max: usize, out: &mut Vec<Message>) -> Result<()> {
+        out.clear();
+        out.push(self.receive_blocking()?);
+        while out.len() < max {
+            match self.try_receive_blocking()? {
+                Some(message) => out.push(message),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Local port the server is listening on.
+    ///
+    /// Lets a server started on port 0 report the ephemeral port the OS
+    /// picked. The default implementation returns `None`, for transports
+    /// that are not bound to a port.
+    fn local_port(&self) -> Option<u16> {
+        None
+    }
+
+    /// Probe of the queue this transport sends into.
+    ///
+    /// Lets `--queue-depth-interval` sample the queue's depth from another
+    /// thread while the transport is in use. The default implementation
+    /// returns `None`, for transports without a queue to inspect.
+    fn queue_depth_probe(&self) -> Option<Box<dyn QueueDepthProbe>> {
+        None
+    }
+
+    /// Transport-specific counters since the transport was started.
+    ///
+    /// Retries, partial writes, ring wraps and the like, summed over a
+    /// test's clients into `transport_stats` in the results. The default
+    /// implementation returns no counters, for transports that keep none.
+    fn stats(&self) -> TransportStats {
+        TransportStats::new()
+    }
+
+    /// Send checked frames from now on.
+    ///
+    /// Called on both ends once the handshake has agreed on
+    /// `--verify-frames`; receivers recognise checked frames by themselves.
+    /// The default implementation does nothing: only the byte-stream
+    /// transports frame their messages.
+    fn set_frame_checksums(&mut self, _on: bool) {}
+
+    /// Accept another client on a listening server.
+    ///
+    /// The new connection is returned as a transport of its own, so a
+    /// server can answer several clients at once; the server's own lazily
+    /// accepted connection is untouched. The default implementation fails,
+    /// for transports without connections.
+    fn accept_blocking(&mut self) -> Result<Box<dyn BlockingTransport>> {
+        Err(anyhow::anyhow!(
+            "This transport does not accept separate connections"
+        ))
+    }
+
     /// Close the transport and release resources.
     ///
     /// This method cleanly shuts down the transport, closing connections
@@ -1255,6 +2028,100 @@ pub trait BlockingTransport: Send {
     fn close_blocking(&mut self) -> Result<()>;
 }
 
+/// Receive the next message, busy-polling when `spin` is set.
+///
+/// With a spin mode and a transport that supports it, polls
+/// `try_receive_blocking()` until a message arrives, executing a spin-loop
+/// hint between polls for [`SpinWait::Pause`]. Otherwise blocks in
+/// `receive_blocking()`.
+pub fn receive_blocking_with(
+    transport: &mut dyn BlockingTransport,
+    spin: Option<SpinWait>,
+) -> Result<Message> {
+    match spin {
+        Some(mode) if transport.supports_try_receive() => loop {
+            if let Some(message) = transport.try_receive_blocking()? {
+                return Ok(message);
+            }
+            if mode == SpinWait::Pause {
+                std::hint::spin_loop();
+            }
+        },
+        _ => transport.receive_blocking(),
+    }
+}
+
+/// Receive the reply to the request `request_id`.
+///
+/// Stale replies are counted in `errors` and skipped, and receiving
+/// continues. Returns `Ok(true)` once the matching reply arrives and
+/// `Ok(false)` after counting a mismatched message, whose timing must not be
+/// recorded.
+pub fn receive_response_blocking(
+    transport: &mut dyn BlockingTransport,
+    request_id: u64,
+    spin: Option<SpinWait>,
+    errors: &mut usize,
+) -> Result<bool> {
+    receive_reply_blocking(transport, request_id, spin, errors).map(|reply| reply.is_some())
+}
+
+/// Like [`receive_response_blocking`], but returns the matching reply
+/// itself, or `None` after a mismatched message.
+pub fn receive_reply_blocking(
+    transport: &mut dyn BlockingTransport,
+    request_id: u64,
+    spin: Option<SpinWait>,
+    errors: &mut usize,
+) -> Result<Option<Message>> {
+    loop {
+        let message = receive_blocking_with(transport, spin)?;
+        match message.check_response(request_id) {
+            ResponseCheck::Matched => return Ok(Some(message)),
+            check => {
+                *errors += 1;
+                tracing::debug!(
+                    "{:?} reply while awaiting request {}: {:?} {}",
+                    check,
+                    request_id,
+                    message.message_type,
+                    message.id
+                );
+                if check == ResponseCheck::Mismatched {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+/// Async counterpart of [`receive_response_blocking`].
+pub async fn receive_response(
+    transport: &mut dyn IpcTransport,
+    request_id: u64,
+    errors: &mut usize,
+) -> Result<bool> {
+    loop {
+        let message = transport.receive().await?;
+        match message.check_response(request_id) {
+            ResponseCheck::Matched => return Ok(true),
+            check => {
+                *errors += 1;
+                tracing::debug!(
+                    "{:?} reply while awaiting request {}: {:?} {}",
+                    check,
+                    request_id,
+                    message.message_type,
+                    message.id
+                );
+                if check == ResponseCheck::Mismatched {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+}
+
 /// Factory for creating blocking transport instances.
 ///
 /// This factory provides a centralized way to instantiate the appropriate
@@ -1273,7 +2140,7 @@ pub trait BlockingTransport: Send {
 ///
 /// ```rust,no_run
 /// use ipc_benchmark::ipc::BlockingTransportFactory;
-/// use ipc_benchmark::cli::IpcMechanism;
+/// use ipc_benchmark::IpcMechanism;
 ///
 /// # fn example() -> anyhow::Result<()> {
 /// // Create a Unix Domain Socket transport (ring buffer mode)
@@ -1311,12 +2178,22 @@ impl BlockingTransportFactory {
     /// - `TcpSocket` - Available in Stage 3
     /// - `SharedMemory` - Available in Stage 3
     /// - `PosixMessageQueue` (Linux only) - Available in Stage 3
+    /// - `Futex` (Linux only)
+    /// - `RtSignal` (Linux only)
+    /// - `Inotify` (Linux only)
+    /// - `Http`
+    /// - `Grpc`
+    /// - `Socketpair` (Unix only), the UDS transport over an inherited socket
+    /// - `UdpMulticast` (Linux only)
+    /// - `NamedPipe` (Windows only)
     ///
     /// # Platform Support
     ///
     /// Some mechanisms are platform-specific:
-    /// - Unix Domain Sockets: Unix/Linux/macOS only
-    /// - POSIX Message Queues: Linux only
+    /// - Unix Domain Sockets and socketpairs: Unix/Linux/macOS only
+    /// - POSIX Message Queues, futexes, RT signals, inotify and UDP
+    ///   multicast: Linux only
+    /// - Named pipes: Windows only
     /// - TCP and Shared Memory: All platforms
     ///
     /// # Errors
@@ -1330,7 +2207,7 @@ impl BlockingTransportFactory {
     ///
     /// ```rust,no_run
     /// use ipc_benchmark::ipc::BlockingTransportFactory;
-    /// use ipc_benchmark::cli::IpcMechanism;
+    /// use ipc_benchmark::IpcMechanism;
     ///
     /// # fn example() -> anyhow::Result<()> {
     /// // Create transport for TCP (send_delay=None for throughput mode)
@@ -1347,17 +2224,30 @@ impl BlockingTransportFactory {
     /// # }
     /// ```
     pub fn create(
-        mechanism: &crate::cli::IpcMechanism,
+        mechanism: &crate::mechanism::IpcMechanism,
         use_direct_memory: bool,
         send_delay: Option<std::time::Duration>,
     ) -> Result<Box<dyn BlockingTransport>> {
+        // Both only select among the shared memory transports, and the
+        // delay only the direct one, which needs Unix
+        #[cfg(not(feature = "shm"))]
+        let _ = use_direct_memory;
+        #[cfg(not(all(unix, feature = "shm")))]
+        let _ = send_delay;
+
         match mechanism {
             #[cfg(unix)]
-            crate::cli::IpcMechanism::UnixDomainSocket => {
+            crate::mechanism::IpcMechanism::UnixDomainSocket => {
                 Ok(Box::new(BlockingUnixDomainSocket::new()))
             }
-            crate::cli::IpcMechanism::TcpSocket => Ok(Box::new(BlockingTcpSocket::new())),
-            crate::cli::IpcMechanism::SharedMemory => {
+            #[cfg(feature = "tcp")]
+            crate::mechanism::IpcMechanism::TcpSocket => Ok(Box::new(BlockingTcpSocket::new())),
+            #[cfg(not(feature = "tcp"))]
+            crate::mechanism::IpcMechanism::TcpSocket => Err(not_built(mechanism, "tcp")),
+            #[cfg(not(feature = "shm"))]
+            crate::mechanism::IpcMechanism::SharedMemory => Err(not_built(mechanism, "shm")),
+            #[cfg(feature = "shm")]
+            crate::mechanism::IpcMechanism::SharedMemory => {
                 if use_direct_memory {
                     #[cfg(unix)]
                     {
@@ -1377,11 +2267,41 @@ impl BlockingTransportFactory {
                     Ok(Box::new(BlockingSharedMemory::new()))
                 }
             }
-            #[cfg(target_os = "linux")]
-            crate::cli::IpcMechanism::PosixMessageQueue => {
+            #[cfg(all(target_os = "linux", feature = "pmq"))]
+            crate::mechanism::IpcMechanism::PosixMessageQueue => {
                 Ok(Box::new(BlockingPosixMessageQueue::new()))
             }
-            crate::cli::IpcMechanism::All => Err(anyhow::anyhow!(
+            #[cfg(all(target_os = "linux", not(feature = "pmq")))]
+            crate::mechanism::IpcMechanism::PosixMessageQueue => Err(not_built(mechanism, "pmq")),
+            #[cfg(all(target_os = "linux", feature = "shm"))]
+            crate::mechanism::IpcMechanism::Futex => Ok(Box::new(BlockingFutex::new())),
+            #[cfg(all(target_os = "linux", feature = "shm"))]
+            crate::mechanism::IpcMechanism::RtSignal => Ok(Box::new(BlockingRtSignal::new())),
+            #[cfg(all(target_os = "linux", not(feature = "shm")))]
+            crate::mechanism::IpcMechanism::Futex | crate::mechanism::IpcMechanism::RtSignal => {
+                Err(not_built(mechanism, "shm"))
+            }
+            #[cfg(target_os = "linux")]
+            crate::mechanism::IpcMechanism::Inotify => Ok(Box::new(BlockingInotify::new())),
+            #[cfg(feature = "http")]
+            crate::mechanism::IpcMechanism::Http => Ok(Box::new(BlockingHttp::new()?)),
+            #[cfg(not(feature = "http"))]
+            crate::mechanism::IpcMechanism::Http => Err(not_built(mechanism, "http")),
+            #[cfg(feature = "grpc")]
+            crate::mechanism::IpcMechanism::Grpc => Ok(Box::new(BlockingGrpc::new()?)),
+            #[cfg(not(feature = "grpc"))]
+            crate::mechanism::IpcMechanism::Grpc => Err(not_built(mechanism, "grpc")),
+            #[cfg(unix)]
+            crate::mechanism::IpcMechanism::Socketpair => {
+                Ok(Box::new(BlockingUnixDomainSocket::new()))
+            }
+            #[cfg(target_os = "linux")]
+            crate::mechanism::IpcMechanism::UdpMulticast => {
+                Ok(Box::new(BlockingUdpMulticast::new()))
+            }
+            #[cfg(windows)]
+            crate::mechanism::IpcMechanism::NamedPipe => Ok(Box::new(BlockingNamedPipe::new())),
+            crate::mechanism::IpcMechanism::All => Err(anyhow::anyhow!(
                 "Cannot create transport for 'All' mechanism. \
                      Use IpcMechanism::expand_all() first."
             )),
@@ -1410,7 +2330,8 @@ mod tests {
     #[test]
     fn test_factory_rejects_all_mechanism() {
         // The 'All' mechanism should return an error
-        let result = BlockingTransportFactory::create(&crate::cli::IpcMechanism::All, false, None);
+        let result =
+            BlockingTransportFactory::create(&crate::mechanism::IpcMechanism::All, false, None);
         assert!(result.is_err());
         if let Err(e) = result {
             let err_msg = e.to_string();
@@ -1424,7 +2345,7 @@ mod tests {
         #[cfg(unix)]
         {
             let result = BlockingTransportFactory::create(
-                &crate::cli::IpcMechanism::UnixDomainSocket,
+                &crate::mechanism::IpcMechanism::UnixDomainSocket,
                 false,
                 None,
             );
@@ -1436,10 +2357,14 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "tcp")]
     fn test_factory_creates_tcp_transport() {
         // Stage 3.2: TCP implementation is now available
-        let result =
-            BlockingTransportFactory::create(&crate::cli::IpcMechanism::TcpSocket, false, None);
+        let result = BlockingTransportFactory::create(
+            &crate::mechanism::IpcMechanism::TcpSocket,
+            false,
+            None,
+        );
         assert!(
             result.is_ok(),
             "Factory should successfully create TCP transport"
@@ -1447,10 +2372,14 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "shm")]
     fn test_factory_creates_shm_transport() {
         // Stage 3.3: Shared Memory implementation is now available (ring buffer)
-        let result =
-            BlockingTransportFactory::create(&crate::cli::IpcMechanism::SharedMemory, false, None);
+        let result = BlockingTransportFactory::create(
+            &crate::mechanism::IpcMechanism::SharedMemory,
+            false,
+            None,
+        );
         assert!(
             result.is_ok(),
             "Factory should successfully create Shared Memory transport"
@@ -1458,11 +2387,14 @@ mod tests {
     }
 
     #[test]
-    #[cfg(unix)]
+    #[cfg(all(unix, feature = "shm"))]
     fn test_factory_creates_shm_direct_transport() {
         // Direct memory implementation (Unix-only)
-        let result =
-            BlockingTransportFactory::create(&crate::cli::IpcMechanism::SharedMemory, true, None);
+        let result = BlockingTransportFactory::create(
+            &crate::mechanism::IpcMechanism::SharedMemory,
+            true,
+            None,
+        );
         assert!(
             result.is_ok(),
             "Factory should successfully create Direct Memory Shared Memory transport"
@@ -1473,19 +2405,22 @@ mod tests {
     /// creating SHM-direct transports. The actual `precise_timestamps`
     /// field wiring is covered by unit tests in `shared_memory_direct`.
     #[test]
-    #[cfg(unix)]
+    #[cfg(all(unix, feature = "shm"))]
     fn test_factory_shm_direct_accepts_send_delay_variants() {
         use std::time::Duration;
 
-        let result_none =
-            BlockingTransportFactory::create(&crate::cli::IpcMechanism::SharedMemory, true, None);
+        let result_none = BlockingTransportFactory::create(
+            &crate::mechanism::IpcMechanism::SharedMemory,
+            true,
+            None,
+        );
         assert!(
             result_none.is_ok(),
             "Factory should create SHM-direct with send_delay=None"
         );
 
         let result_zero = BlockingTransportFactory::create(
-            &crate::cli::IpcMechanism::SharedMemory,
+            &crate::mechanism::IpcMechanism::SharedMemory,
             true,
             Some(Duration::ZERO),
         );
@@ -1495,7 +2430,7 @@ mod tests {
         );
 
         let result_delay = BlockingTransportFactory::create(
-            &crate::cli::IpcMechanism::SharedMemory,
+            &crate::mechanism::IpcMechanism::SharedMemory,
             true,
             Some(Duration::from_millis(10)),
         );
@@ -1506,11 +2441,11 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", feature = "pmq"))]
     fn test_factory_creates_pmq_transport() {
         // Stage 3.4: POSIX Message Queue implementation is now available
         let result = BlockingTransportFactory::create(
-            &crate::cli::IpcMechanism::PosixMessageQueue,
+            &crate::mechanism::IpcMechanism::PosixMessageQueue,
             false,
             None,
         );
@@ -1593,12 +2528,13 @@ mod tests {
         assert_eq!(MessageType::from(3), MessageType::Ping);
         assert_eq!(MessageType::from(4), MessageType::Pong);
         assert_eq!(MessageType::from(5), MessageType::Shutdown);
+        assert_eq!(MessageType::from(6), MessageType::Handshake);
     }
 
     #[test]
     fn test_message_type_from_unknown_defaults_to_oneway() {
         // Unknown values should default to OneWay
-        assert_eq!(MessageType::from(6), MessageType::OneWay);
+        assert_eq!(MessageType::from(7), MessageType::OneWay);
         assert_eq!(MessageType::from(100), MessageType::OneWay);
         assert_eq!(MessageType::from(u32::MAX), MessageType::OneWay);
     }
@@ -1662,6 +2598,65 @@ mod tests {
             t2 - t1
         );
     }
+
+    #[test]
+    fn test_message_deadline() {
+        let mut msg = Message::new(1, Vec::new(), MessageType::OneWay);
+        assert_eq!(msg.deadline_ns(), None);
+        assert!(!msg.is_expired(u64::MAX));
+
+        msg = msg.with_ttl(Some(std::time::Duration::from_micros(5)));
+        msg.timestamp = 1_000;
+        assert_eq!(msg.deadline_ns(), Some(6_000));
+        assert!(!msg.is_expired(6_000));
+        assert!(msg.is_expired(6_001));
+
+        let decoded = Message::from_bytes(&msg.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.ttl_ns, Some(5_000));
+    }
+
+    #[test]
+    fn test_check_response() {
+        let reply = |id| Message::new(id, Vec::new(), MessageType::Response);
+        assert_eq!(reply(7).check_response(7), ResponseCheck::Matched);
+        assert_eq!(reply(6).check_response(7), ResponseCheck::Stale);
+        assert_eq!(reply(8).check_response(7), ResponseCheck::Mismatched);
+        let echoed = Message::new(7, Vec::new(), MessageType::Request);
+        assert_eq!(echoed.check_response(7), ResponseCheck::Mismatched);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_transport_stats_totals_sum_shared_counters() {
+        let totals = TransportStatsTotals::default();
+        let client = totals.clone();
+        client.add(TransportStats::from([("send_eagain".to_string(), 2)]));
+        totals.add(TransportStats::from([
+            ("send_eagain".to_string(), 3),
+            ("ring_wraps".to_string(), 1),
+        ]));
+        assert_eq!(
+            totals.get(),
+            TransportStats::from([
+                ("ring_wraps".to_string(), 1),
+                ("send_eagain".to_string(), 5),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_get_thread_cpu_time_ns_counts_running_not_sleeping() {
+        let start = get_thread_cpu_time_ns().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let slept = get_thread_cpu_time_ns().unwrap() - start;
+        assert!(slept < 15_000_000, "sleep should use little CPU: {}", slept);
+
+        let spin_start = std::time::Instant::now();
+        while spin_start.elapsed() < std::time::Duration::from_millis(5) {
+            std::hint::spin_loop();
+        }
+        assert!(get_thread_cpu_time_ns().unwrap() - start > slept);
+    }
 }
 
 #[test]
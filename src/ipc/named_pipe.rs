@@ -0,0 +1,191 @@
+//! Named pipe transport for Windows.
+//!
+//! The async counterpart of [`BlockingNamedPipe`](super::BlockingNamedPipe),
+//! over Tokio's named pipes: the server creates the first instance of
+//! `\\.\pipe\<name>` as a byte-mode pipe that rejects remote clients and
+//! connects its client on first use, and the client opens the pipe,
+//! retrying while every instance is busy. Messages travel in the frames of
+//! the socket transports (see [`super::framing`]).
+
+use super::{framing, IpcError, IpcTransport, Message, TransportConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeServer, ServerOptions};
+use tokio::time::timeout;
+use tracing::{debug, trace};
+use windows_sys::Win32::Foundation::ERROR_PIPE_BUSY;
+
+/// How long a client waits for a free pipe instance
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Either end of a connected pipe
+trait Pipe: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync> Pipe for T {}
+
+/// Named pipe transport.
+pub struct NamedPipeTransport {
+    /// Path of the pipe, `\\.\pipe\<name>`
+    path: String,
+
+    /// Server: the instance the client will open
+    listener: Option<NamedPipeServer>,
+
+    /// The connected pipe, after connect or accept
+    stream: Option<Box<dyn Pipe>>,
+
+    /// Record `Message::first_byte_time_ns` on receive.
+    first_byte_timestamps: bool,
+
+    /// Write frames in pieces of at most this many bytes (test mode).
+    fragment_writes: Option<usize>,
+
+    /// Send checked frames, once the handshake has agreed on them.
+    frame_checksums: bool,
+}
+
+impl NamedPipeTransport {
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            path: String::new(),
+            listener: None,
+            stream: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+        }
+    }
+
+    fn configure(&mut self, config: &TransportConfig) {
+        self.path = config.socket_path.clone();
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
+    }
+
+    /// The connected pipe, accepting the client on first use.
+    async fn connected(&mut self) -> Result<&mut Box<dyn Pipe>> {
+        if self.stream.is_none() {
+            if let Some(listener) = self.listener.take() {
+                debug!("Accepting client on named pipe {}", self.path);
+                listener
+                    .connect()
+                    .await
+                    .context("Failed to accept named pipe client")?;
+                self.stream = Some(Box::new(listener));
+            }
+        }
+        self.stream
+            .as_mut()
+            .ok_or_else(|| anyhow!("Named pipe not connected"))
+    }
+}
+
+impl Default for NamedPipeTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl IpcTransport for NamedPipeTransport {
+    async fn start_server(&mut self, config: &TransportConfig) -> Result<()> {
+        self.configure(config);
+        debug!("Starting named pipe server at: {}", self.path);
+        let buffer_size = config.buffer_size.min(u32::MAX as usize) as u32;
+        let listener = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .in_buffer_size(buffer_size)
+            .out_buffer_size(buffer_size)
+            .create(&self.path)
+            .with_context(|| {
+                format!(
+                    "Failed to create named pipe {}. Is another server using it?",
+                    self.path
+                )
+            })?;
+        // Accept happens on first use, after readiness is signalled
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    async fn start_client(&mut self, config: &TransportConfig) -> Result<()> {
+        self.configure(config);
+        debug!("Starting named pipe client, opening: {}", self.path);
+        let deadline = Instant::now() + OPEN_TIMEOUT;
+        let client = loop {
+            match ClientOptions::new().open(&self.path) {
+                Ok(client) => break client,
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+                    if Instant::now() >= deadline {
+                        bail!(
+                            "Named pipe {} stayed busy for {:?}",
+                            self.path,
+                            OPEN_TIMEOUT
+                        );
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Failed to open named pipe {}. Is the server running?",
+                            self.path
+                        )
+                    })
+                }
+            }
+        };
+        self.stream = Some(Box::new(client));
+        Ok(())
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<bool> {
+        const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+        trace!("Sending message ID {} via named pipe", message.id);
+        let checked = self.frame_checksums;
+        let fragment_writes = self.fragment_writes;
+        let pipe = self.connected().await?;
+        let message_bytes = message.to_bytes()?;
+        let write = framing::write_frame_async(pipe, &message_bytes, checked, fragment_writes);
+        match timeout(WRITE_TIMEOUT, write).await {
+            Ok(result) => result.context("Failed to write to named pipe")?,
+            Err(_) => return Err(anyhow!(IpcError::BackpressureTimeout)),
+        }
+        Ok(false)
+    }
+
+    async fn receive(&mut self) -> Result<Message> {
+        let first_byte_timestamps = self.first_byte_timestamps;
+        let pipe = self.connected().await?;
+        let (body, first_byte_time_ns) =
+            framing::read_frame_async(pipe, framing::MAX_FRAME_LEN, first_byte_timestamps).await?;
+        let mut message = Message::from_bytes(&body)?;
+        message.first_byte_time_ns = first_byte_time_ns;
+        trace!("Received message ID {} via named pipe", message.id);
+        Ok(message)
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.frame_checksums = on;
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        debug!("Closing named pipe transport");
+        // Closing the handles disconnects the client and frees the name
+        self.stream = None;
+        self.listener = None;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Named Pipe"
+    }
+
+    fn max_message_size(&self) -> usize {
+        framing::MAX_FRAME_LEN
+    }
+}
@@ -0,0 +1,380 @@
+//! Named pipe transport for Windows (blocking).
+//!
+//! Windows has no Unix domain sockets; its local byte-stream IPC is the
+//! named pipe, so this is the transport `-m pipe` and `-m all` measure
+//! there in place of UDS.
+//!
+//! # Pipe Setup
+//!
+//! The server creates `\\.\pipe\<name>` with `CreateNamedPipeW` as a
+//! byte-mode pipe that rejects remote clients, and waits for its client
+//! with `ConnectNamedPipe` on first use, after readiness is signalled. The
+//! first instance is created with `FILE_FLAG_FIRST_PIPE_INSTANCE`, so a
+//! stale server holding the same name is an error rather than a silent
+//! peer. Every time an instance is connected the server creates the next
+//! one, so further clients always find one to open. The client opens the
+//! pipe like a file, waiting with `WaitNamedPipeW` while every instance is
+//! busy.
+//!
+//! # Wire Protocol
+//!
+//! Both ends read and write the pipe handle through `std::fs::File`, with
+//! the frames of the socket transports (see [`crate::ipc::framing`]).
+
+use crate::ipc::framing::{self, FrameHeader};
+use crate::ipc::{get_monotonic_time_ns, BlockingTransport, Message, TransportConfig};
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::windows::io::{AsRawHandle, FromRawHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+use windows_sys::Win32::Foundation::{ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE};
+use windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+use windows_sys::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PeekNamedPipe, WaitNamedPipeW, PIPE_READMODE_BYTE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+
+/// How long a client waits for a free pipe instance
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Path of the pipe called `name` in the local pipe namespace.
+pub fn pipe_path(name: &str) -> String {
+    format!(r"\\.\pipe\{}", name)
+}
+
+/// NUL-terminated UTF-16 form of `path`, for the wide Win32 calls.
+fn wide(path: &str) -> Vec<u16> {
+    path.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Create an unconnected instance of the pipe at `path`. The `first`
+/// instance fails if a server already holds the name.
+pub(crate) fn create_instance(path: &str, buffer_size: u32, first: bool) -> Result<File> {
+    let mut open_mode = PIPE_ACCESS_DUPLEX;
+    if first {
+        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+    let name = wide(path);
+    let handle = unsafe {
+        CreateNamedPipeW(
+            name.as_ptr(),
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            buffer_size,
+            buffer_size,
+            0,
+            std::ptr::null(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error()).with_context(|| {
+            format!(
+                "Failed to create named pipe {}. Is another server using it?",
+                path
+            )
+        });
+    }
+    // Safety: the handle is valid and owned by nothing else
+    Ok(unsafe { File::from_raw_handle(handle) })
+}
+
+/// Wait for a client to open `pipe`, an unconnected instance.
+fn connect(pipe: &File) -> Result<()> {
+    let connected = unsafe { ConnectNamedPipe(pipe.as_raw_handle(), std::ptr::null_mut()) };
+    if connected == 0 {
+        let e = std::io::Error::last_os_error();
+        // The client opened the instance before the server waited for it
+        if e.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+            return Err(e).context("Failed to accept named pipe client");
+        }
+    }
+    Ok(())
+}
+
+/// Open the pipe at `path` as a client, waiting while every instance is
+/// busy with another client.
+pub(crate) fn open(path: &str) -> Result<File> {
+    let deadline = Instant::now() + OPEN_TIMEOUT;
+    loop {
+        match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(pipe) => return Ok(pipe),
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+                if Instant::now() >= deadline {
+                    bail!("Named pipe {} stayed busy for {:?}", path, OPEN_TIMEOUT);
+                }
+                unsafe { WaitNamedPipeW(wide(path).as_ptr(), 100) };
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to open named pipe {}. Is the server running?", path)
+                })
+            }
+        }
+    }
+}
+
+/// Whether a whole frame length prefix is waiting in `pipe`.
+fn has_pending_frame(pipe: &File) -> Result<bool> {
+    let mut available = 0u32;
+    let peeked = unsafe {
+        PeekNamedPipe(
+            pipe.as_raw_handle(),
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            &mut available,
+            std::ptr::null_mut(),
+        )
+    };
+    if peeked == 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to peek named pipe");
+    }
+    Ok(available as usize >= framing::LENGTH_PREFIX_LEN)
+}
+
+/// Blocking named pipe transport.
+pub struct BlockingNamedPipe {
+    /// Path of the pipe, `\\.\pipe\<name>`
+    path: String,
+
+    /// Size of each instance's in and out buffers
+    buffer_size: u32,
+
+    /// Server: the instance the next client will open
+    listener: Option<File>,
+
+    /// The connected pipe, after connect or accept
+    stream: Option<File>,
+
+    /// Record `Message::first_byte_time_ns` on receive.
+    first_byte_timestamps: bool,
+
+    /// Write frames in pieces of at most this many bytes (test mode).
+    fragment_writes: Option<usize>,
+
+    /// Send checked frames, once the handshake has agreed on them.
+    frame_checksums: bool,
+
+    /// Serialized frame body, reused from send to send
+    send_buffer: Vec<u8>,
+}
+
+impl BlockingNamedPipe {
+    /// Maximum accepted frame size (matches the socket transports).
+    const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            path: String::new(),
+            buffer_size: 0,
+            listener: None,
+            stream: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+            send_buffer: Vec::new(),
+        }
+    }
+
+    fn configure(&mut self, config: &TransportConfig) {
+        self.path = config.socket_path.clone();
+        self.buffer_size = config.buffer_size.min(u32::MAX as usize) as u32;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
+    }
+
+    /// Accept a client if we haven't already.
+    /// This is called automatically on first send/receive in server mode.
+    fn ensure_connection(&mut self) -> Result<()> {
+        if self.stream.is_some() || self.listener.is_none() {
+            return Ok(());
+        }
+        debug!("Accepting client on named pipe {}", self.path);
+        self.stream = Some(self.accept_pipe()?);
+        Ok(())
+    }
+
+    /// Connect the waiting instance to the next client, and create the
+    /// instance the client after it will open.
+    fn accept_pipe(&mut self) -> Result<File> {
+        let pipe = self
+            .listener
+            .take()
+            .context("Cannot accept: call start_server_blocking() first")?;
+        connect(&pipe)?;
+        self.listener = Some(create_instance(&self.path, self.buffer_size, false)?);
+        debug!("Named pipe server accepted a client on {}", self.path);
+        Ok(pipe)
+    }
+
+    fn connected(&mut self) -> Result<&mut File> {
+        self.ensure_connection()?;
+        self.stream.as_mut().context(
+            "Named pipe not connected. \
+             Call start_server_blocking() or start_client_blocking() first.",
+        )
+    }
+}
+
+impl Default for BlockingNamedPipe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingTransport for BlockingNamedPipe {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.configure(config);
+        debug!("Starting blocking named pipe server at: {}", self.path);
+        // The client opens this instance; accept happens on first use
+        self.listener = Some(create_instance(&self.path, self.buffer_size, true)?);
+        Ok(())
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.configure(config);
+        debug!(
+            "Starting blocking named pipe client, opening: {}",
+            self.path
+        );
+        self.stream = Some(open(&self.path)?);
+        Ok(())
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        trace!("Sending message ID {} via blocking named pipe", message.id);
+        let checked = self.frame_checksums;
+        let fragment_writes = self.fragment_writes;
+        let mut serialized = std::mem::take(&mut self.send_buffer);
+        message.serialize_unstamped_into(&mut serialized)?;
+        let result = self.connected().and_then(|pipe| {
+            // Stamp immediately before the write, as the socket transports do
+            serialized[Message::timestamp_offset()]
+                .copy_from_slice(&get_monotonic_time_ns().to_le_bytes());
+            if let Some(piece) = fragment_writes {
+                return framing::write_fragmented(pipe, &serialized, checked, piece);
+            }
+            let header = FrameHeader::for_body(&serialized, checked).to_bytes();
+            pipe.write_all(&header)
+                .context("Failed to write message length")?;
+            pipe.write_all(&serialized)
+                .context("Failed to write message data")?;
+            Ok(())
+        });
+        self.send_buffer = serialized;
+        result
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        let first_byte_timestamps = self.first_byte_timestamps;
+        let pipe = self.connected()?;
+        let (buffer, first_byte_time_ns) = framing::read_frame(
+            pipe,
+            Self::MAX_MESSAGE_SIZE,
+            first_byte_timestamps,
+            framing::read_prefix,
+        )?;
+        let mut message: Message =
+            bincode::deserialize(&buffer).context("Failed to deserialize message")?;
+        message.first_byte_time_ns = first_byte_time_ns;
+        trace!("Received message ID {} via blocking named pipe", message.id);
+        Ok(message)
+    }
+
+    fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
+        let pipe = self.connected()?;
+        let (buffer, _) =
+            framing::read_frame(pipe, Self::MAX_MESSAGE_SIZE, false, framing::read_prefix)?;
+        // Capture timestamp after raw read, before deserialization
+        let receive_time_ns = get_monotonic_time_ns();
+        let message: Message =
+            bincode::deserialize(&buffer).context("Failed to deserialize message")?;
+        Ok((message, receive_time_ns))
+    }
+
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        match self.stream.as_ref() {
+            Some(pipe) if has_pending_frame(pipe)? => self.receive_blocking().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.frame_checksums = on;
+    }
+
+    fn accept_blocking(&mut self) -> Result<Box<dyn BlockingTransport>> {
+        let pipe = self.accept_pipe()?;
+        Ok(Box::new(Self {
+            path: self.path.clone(),
+            buffer_size: self.buffer_size,
+            listener: None,
+            stream: Some(pipe),
+            first_byte_timestamps: self.first_byte_timestamps,
+            fragment_writes: self.fragment_writes,
+            frame_checksums: false,
+            send_buffer: Vec::new(),
+        }))
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        debug!("Closing blocking named pipe transport");
+        // Closing the handles disconnects the client and frees the name
+        self.stream = None;
+        self.listener = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+
+    #[test]
+    fn test_one_way_and_round_trip() {
+        let config = TransportConfig {
+            socket_path: pipe_path(&format!("ipc_test_{}", std::process::id())),
+            ..Default::default()
+        };
+        let mut server = BlockingNamedPipe::new();
+        server.start_server_blocking(&config).unwrap();
+
+        // A second server cannot take over the name
+        assert!(BlockingNamedPipe::new()
+            .start_server_blocking(&config)
+            .is_err());
+
+        let client = std::thread::spawn(move || {
+            let mut client = BlockingNamedPipe::new();
+            client.start_client_blocking(&config).unwrap();
+            client
+                .send_blocking(&Message::new(0, vec![1; 64], MessageType::OneWay))
+                .unwrap();
+            client
+                .send_blocking(&Message::new(1, vec![2; 64], MessageType::Request))
+                .unwrap();
+            assert_eq!(client.receive_blocking().unwrap().id, 1);
+            client.close_blocking().unwrap();
+        });
+
+        let (one_way, receive_time_ns) = server.receive_blocking_timed().unwrap();
+        assert_eq!(one_way.id, 0);
+        assert!(receive_time_ns >= one_way.timestamp);
+        let request = server.receive_blocking().unwrap();
+        server
+            .send_blocking(&Message::new(request.id, Vec::new(), MessageType::Response))
+            .unwrap();
+        client.join().unwrap();
+        assert!(server.receive_blocking().is_err());
+        server.close_blocking().unwrap();
+    }
+}
@@ -57,7 +57,9 @@
 //! - **Platform**: UNIX-like systems only (Linux, macOS, BSD)
 //! - **Permissions**: Requires appropriate system permissions for queue operations
 
-use super::{ConnectionId, IpcError, IpcTransport, Message, TransportConfig, TransportState};
+use super::{
+    ConnectionId, IpcError, IpcTransport, Message, TransportConfig, TransportState, TransportStats,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use nix::errno::Errno;
@@ -66,7 +68,7 @@ use nix::sys::stat::Mode;
 use std::os::unix::io::{AsRawFd, FromRawFd};
 use std::time::Duration;
 use tokio::sync::mpsc;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, warn, Instrument};
 
 /// POSIX Message Queue transport implementation
 ///
@@ -126,6 +128,12 @@ pub struct PosixMessageQueueTransport {
     /// Flag to ensure the backpressure warning is only logged once.
     has_warned_backpressure: bool,
 
+    /// Sends retried because the queue was full
+    send_eagain: u64,
+
+    /// Receive polls that found the queue empty
+    receive_eagain: u64,
+
     /// Transport configuration, stored after initialization.
     ///
     /// This holds a copy of the `TransportConfig` used to initialize the
@@ -179,6 +187,8 @@ impl PosixMessageQueueTransport {
             max_msg_count: 10,
             is_creator: false,
             has_warned_backpressure: false,
+            send_eagain: 0,
+            receive_eagain: 0,
             config: None,
         }
     }
@@ -542,8 +552,13 @@ impl IpcTransport for PosixMessageQueueTransport {
         let raw_fd = fd_ref.as_raw_fd();
         let mut backpressure_detected = false;
 
-        // Get the priority from the config, which was set during transport creation.
-        let priority = self.config.as_ref().map_or(0, |c| c.pmq_priority);
+        // Get the priority from the config, which was set during transport
+        // creation, unless the message carries its own.
+        let priority = if message.priority > 0 {
+            u32::from(message.priority)
+        } else {
+            self.config.as_ref().map_or(0, |c| c.pmq_priority)
+        };
 
         // Pre-compute timestamp offset for efficient in-place updates
         let ts_offset = Message::timestamp_offset();
@@ -593,6 +608,7 @@ impl IpcTransport for PosixMessageQueueTransport {
                 }
                 Err(Errno::EAGAIN) => {
                     backpressure_detected = true;
+                    self.send_eagain += 1;
                     if !self.has_warned_backpressure {
                         warn!(
                             "POSIX Message Queue is full; backpressure is occurring. \n                            This may impact latency and throughput measurements."
@@ -712,6 +728,7 @@ impl IpcTransport for PosixMessageQueueTransport {
                 }
                 Err(Errno::EAGAIN) => {
                     // Queue is empty, wait and retry
+                    self.receive_eagain += 1;
                     if attempt == max_retries - 1 {
                         return Err(anyhow!(
                             "Receive failed after {} attempts - queue consistently empty",
@@ -820,6 +837,25 @@ impl IpcTransport for PosixMessageQueueTransport {
         true
     }
 
+    /// Probe of the queue through a separate read-only descriptor.
+    ///
+    /// Requests and replies share the one queue, so a round-trip test
+    /// samples both.
+    fn queue_depth_probe(&self) -> Option<Box<dyn crate::queue_depth::QueueDepthProbe>> {
+        if self.queue_name.is_empty() {
+            return None;
+        }
+        crate::queue_depth::MessageQueueProbe::open(&self.queue_name)
+            .map(|probe| Box::new(probe) as Box<dyn crate::queue_depth::QueueDepthProbe>)
+    }
+
+    fn stats(&self) -> TransportStats {
+        TransportStats::from([
+            ("send_eagain".to_string(), self.send_eagain),
+            ("receive_eagain".to_string(), self.receive_eagain),
+        ])
+    }
+
     /// Get maximum message size supported by this transport
     ///
     /// Returns the maximum size of individual messages that can be sent
@@ -907,48 +943,51 @@ impl IpcTransport for PosixMessageQueueTransport {
         let raw_fd = fd_ref.as_raw_fd();
         let max_msg_size = self.max_msg_size;
 
-        tokio::spawn(async move {
-            let connection_id = 1;
-
-            loop {
-                let result = tokio::task::spawn_blocking({
-                    let raw_fd_copy = raw_fd;
-                    move || {
-                        let fd = unsafe { MqdT::from_raw_fd(raw_fd_copy) };
-                        let mut buffer = vec![0u8; max_msg_size];
-                        let mut priority = 0u32;
-                        // std::mem::forget(fd); // Don't close the fd when this MqdT drops
-                        mq_receive(&fd, &mut buffer, &mut priority).map(|bytes_read| {
-                            buffer.truncate(bytes_read);
-                            buffer
-                        })
-                    }
-                })
-                .await;
+        tokio::spawn(
+            async move {
+                let connection_id = 1;
+
+                loop {
+                    let result = tokio::task::spawn_blocking({
+                        let raw_fd_copy = raw_fd;
+                        move || {
+                            let fd = unsafe { MqdT::from_raw_fd(raw_fd_copy) };
+                            let mut buffer = vec![0u8; max_msg_size];
+                            let mut priority = 0u32;
+                            // std::mem::forget(fd); // Don't close the fd when this MqdT drops
+                            mq_receive(&fd, &mut buffer, &mut priority).map(|bytes_read| {
+                                buffer.truncate(bytes_read);
+                                buffer
+                            })
+                        }
+                    })
+                    .await;
 
-                match result {
-                    Ok(Ok(buffer)) => {
-                        debug!(
-                            "Received message {} bytes via POSIX message queue",
-                            buffer.len()
-                        );
-                        if let Ok(message) = Message::from_bytes(&buffer) {
-                            if tx.send((connection_id, message)).await.is_err() {
-                                break;
+                    match result {
+                        Ok(Ok(buffer)) => {
+                            debug!(
+                                "Received message {} bytes via POSIX message queue",
+                                buffer.len()
+                            );
+                            if let Ok(message) = Message::from_bytes(&buffer) {
+                                if tx.send((connection_id, message)).await.is_err() {
+                                    break;
+                                }
                             }
                         }
-                    }
-                    Ok(Err(e)) => {
-                        error!("Failed to receive message: {}", e);
-                        break;
-                    }
-                    Err(e) => {
-                        error!("Task join error: {}", e);
-                        break;
+                        Ok(Err(e)) => {
+                            error!("Failed to receive message: {}", e);
+                            break;
+                        }
+                        Err(e) => {
+                            error!("Task join error: {}", e);
+                            break;
+                        }
                     }
                 }
             }
-        });
+            .in_current_span(),
+        );
 
         Ok(rx)
     }
@@ -57,10 +57,13 @@
 //! # }
 //! ```
 
-use crate::ipc::{BlockingTransport, Message, TransportConfig};
+use crate::ipc::{BlockingTransport, Message, TransportConfig, TransportStats};
+use crate::queue_depth::{MessageQueueProbe, QueueDepthProbe};
 use anyhow::{anyhow, Context, Result};
 use nix::errno::Errno;
-use nix::mqueue::{mq_close, mq_open, mq_receive, mq_send, mq_unlink, MQ_OFlag, MqAttr, MqdT};
+use nix::mqueue::{
+    mq_close, mq_getattr, mq_open, mq_receive, mq_send, mq_unlink, MQ_OFlag, MqAttr, MqdT,
+};
 use nix::sys::stat::Mode;
 use std::time::Duration;
 use tracing::{debug, trace, warn};
@@ -109,6 +112,15 @@ pub struct BlockingPosixMessageQueue {
 
     /// Message priority for sends (0-31, higher = higher priority)
     priority: u32,
+
+    /// Sends retried because the queue was full
+    send_eagain: u64,
+
+    /// Receives retried because the queue was empty
+    receive_eagain: u64,
+
+    /// Serialized message, reused from send to send
+    send_buffer: Vec<u8>,
 }
 
 impl BlockingPosixMessageQueue {
@@ -140,6 +152,9 @@ impl BlockingPosixMessageQueue {
             max_msg_count: 10,
             is_creator: false,
             priority: 0,
+            send_eagain: 0,
+            receive_eagain: 0,
+            send_buffer: Vec::new(),
         }
     }
 
@@ -378,10 +393,8 @@ impl BlockingTransport for BlockingPosixMessageQueue {
         // only the timestamp bytes immediately before send. This ensures any
         // scheduling delays between timestamp capture and send are included in
         // the measured latency.
-        let mut message_with_timestamp = message.clone();
-        message_with_timestamp.timestamp = 0; // Dummy timestamp for pre-serialization
-        let mut serialized =
-            bincode::serialize(&message_with_timestamp).context("Failed to serialize message")?;
+        let serialized = &mut self.send_buffer;
+        message.serialize_unstamped_into(serialized)?;
 
         if serialized.len() > self.max_msg_size {
             return Err(anyhow!(
@@ -391,6 +404,13 @@ impl BlockingTransport for BlockingPosixMessageQueue {
             ));
         }
 
+        // A prioritized message overrides the configured --pmq-priority
+        let priority = if message.priority > 0 {
+            u32::from(message.priority)
+        } else {
+            self.priority
+        };
+
         // Pre-compute the timestamp offset for efficient in-place updates
         let ts_offset = Message::timestamp_offset();
 
@@ -406,13 +426,14 @@ impl BlockingTransport for BlockingPosixMessageQueue {
             let timestamp_bytes = ts_now.to_le_bytes();
             serialized[ts_offset.clone()].copy_from_slice(&timestamp_bytes);
 
-            match mq_send(fd, &serialized, self.priority) {
+            match mq_send(fd, serialized, priority) {
                 Ok(()) => {
                     trace!("Message ID {} sent successfully", message.id);
                     return Ok(());
                 }
                 Err(Errno::EAGAIN) => {
                     // Queue is full
+                    self.send_eagain += 1;
                     if start.elapsed() > timeout {
                         return Err(anyhow!(
                             "Timeout: message queue full, possible backpressure"
@@ -454,6 +475,7 @@ impl BlockingTransport for BlockingPosixMessageQueue {
                 }
                 Err(Errno::EAGAIN) => {
                     // No message available (shouldn't happen in blocking mode)
+                    self.receive_eagain += 1;
                     std::thread::yield_now();
                     std::thread::sleep(Duration::from_millis(10));
                 }
@@ -491,6 +513,7 @@ impl BlockingTransport for BlockingPosixMessageQueue {
                     break buffer;
                 }
                 Err(Errno::EAGAIN) => {
+                    self.receive_eagain += 1;
                     std::thread::yield_now();
                     std::thread::sleep(Duration::from_millis(10));
                 }
@@ -509,6 +532,43 @@ impl BlockingTransport for BlockingPosixMessageQueue {
         Ok((message, receive_time_ns))
     }
 
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        let queued = match self.recv_fd.as_ref() {
+            Some(fd) => mq_getattr(fd)
+                .map_err(|e| anyhow!("Failed to query message queue: {}", e))?
+                .curmsgs(),
+            None => return Ok(None),
+        };
+        if queued == 0 {
+            return Ok(None);
+        }
+        self.receive_blocking().map(Some)
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn queue_depth_probe(&self) -> Option<Box<dyn QueueDepthProbe>> {
+        if self.queue_name_base.is_empty() {
+            return None;
+        }
+        // The server sends replies, the client requests
+        let name = if self.is_creator {
+            self.response_queue_name()
+        } else {
+            self.request_queue_name()
+        };
+        MessageQueueProbe::open(&name).map(|probe| Box::new(probe) as Box<dyn QueueDepthProbe>)
+    }
+
+    fn stats(&self) -> TransportStats {
+        TransportStats::from([
+            ("send_eagain".to_string(), self.send_eagain),
+            ("receive_eagain".to_string(), self.receive_eagain),
+        ])
+    }
+
     fn close_blocking(&mut self) -> Result<()> {
         debug!("Closing blocking POSIX message queue transport");
         self.cleanup_queues();
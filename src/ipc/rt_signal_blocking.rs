@@ -0,0 +1,565 @@
+//! Real-time signal transport (blocking, Linux only).
+//!
+//! Messages travel through a shared memory segment laid out like the futex
+//! transport's, one slot per direction, but the notification path is the
+//! one many embedded stacks still use: POSIX real-time signals. A sender
+//! that finds the receiver asleep queues `SIGRTMIN` to the receiving thread
+//! with `rt_tgsigqueueinfo` (the thread-directed form of `sigqueue`), and
+//! the receiver collects it with `sigtimedwait`. Freeing a slot wakes a
+//! sender blocked on it the same way with `SIGRTMIN + 1`.
+//!
+//! The measured latency therefore includes signal generation, queueing and
+//! delivery on top of the copy through shared memory; comparing it with
+//! `-m futex` isolates the signal path's cost.
+//!
+//! # Signal Handling
+//!
+//! A thread that waits on the transport blocks both signals in its own
+//! mask and registers its thread ID in the slot before sleeping, so a
+//! queued signal stays pending for exactly that thread until
+//! `sigtimedwait` takes it. The signals stay blocked in that thread
+//! afterwards. As a safety net, a no-op handler is installed for both
+//! signals so that one reaching any other thread is discarded instead of
+//! terminating the process.
+//!
+//! # Segment Layout
+//!
+//! ```text
+//! [ header (64 bytes) | request slot | reply slot ]
+//! slot: [ state | len | reader waiter | writer waiter | data ], 64-byte aligned
+//! ```
+
+use crate::ipc::{BlockingTransport, Message, TransportConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use shared_memory::{Shmem, ShmemConf};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicI32, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+
+/// Slot state: nothing to read
+const EMPTY: u32 = 0;
+
+/// Slot state: a message is waiting
+const FULL: u32 = 1;
+
+/// Longest single signal wait before the slot and shutdown flag are
+/// checked again
+const RECHECK: Duration = Duration::from_millis(100);
+
+/// How long a client waits for the server's segment
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+const HEADER_SIZE: usize = 64;
+const SLOT_HEADER_SIZE: usize = 32;
+const SLOT_ALIGN: usize = 64;
+
+/// Segment header, written by the server before it sets `ready`.
+#[repr(C)]
+struct Header {
+    slot_capacity: AtomicUsize,
+    ready: AtomicU32,
+    shutdown: AtomicU32,
+}
+
+/// A thread that may be asleep waiting for a signal.
+#[repr(C)]
+struct Waiter {
+    /// Set by the waiter before it sleeps, cleared by whoever signals it
+    waiting: AtomicU32,
+    pid: AtomicI32,
+    tid: AtomicI32,
+}
+
+/// One direction of the segment.
+#[repr(C)]
+struct Slot {
+    state: AtomicU32,
+    len: AtomicU32,
+    /// Waits for the slot to fill
+    reader: Waiter,
+    /// Waits for the slot to drain
+    writer: Waiter,
+}
+
+/// The signal announcing a message.
+fn data_signal() -> libc::c_int {
+    libc::SIGRTMIN()
+}
+
+/// The signal announcing a freed slot.
+fn space_signal() -> libc::c_int {
+    libc::SIGRTMIN() + 1
+}
+
+/// Distance between the two slots for `capacity` data bytes.
+fn slot_stride(capacity: usize) -> usize {
+    (SLOT_HEADER_SIZE + capacity + SLOT_ALIGN - 1) / SLOT_ALIGN * SLOT_ALIGN
+}
+
+/// Total segment size for `capacity` data bytes per slot.
+fn segment_size(capacity: usize) -> usize {
+    HEADER_SIZE + 2 * slot_stride(capacity)
+}
+
+extern "C" fn ignore_signal(_: libc::c_int, _: *mut libc::siginfo_t, _: *mut libc::c_void) {}
+
+/// Set of the transport's two signals.
+fn signal_set() -> libc::sigset_t {
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, data_signal());
+        libc::sigaddset(&mut set, space_signal());
+        set
+    }
+}
+
+thread_local! {
+    /// Thread ID of the current thread once its signal mask is prepared
+    static PREPARED_TID: Cell<libc::pid_t> = const { Cell::new(0) };
+}
+
+/// Block the transport's signals in the calling thread and return its
+/// thread ID, installing the process-wide fallback handler on first use.
+fn prepare_thread() -> Result<libc::pid_t> {
+    static HANDLER: Once = Once::new();
+    HANDLER.call_once(|| unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = ignore_signal as *const () as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        for signo in [data_signal(), space_signal()] {
+            libc::sigaction(signo, &action, std::ptr::null_mut());
+        }
+    });
+
+    let tid = PREPARED_TID.with(Cell::get);
+    if tid != 0 {
+        return Ok(tid);
+    }
+    let set = signal_set();
+    let rc = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) };
+    if rc != 0 {
+        bail!(
+            "Failed to block real-time signals: {}",
+            std::io::Error::from_raw_os_error(rc)
+        );
+    }
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::pid_t;
+    PREPARED_TID.with(|cell| cell.set(tid));
+    Ok(tid)
+}
+
+/// Wait up to `timeout` for `signo` to be pending for this thread.
+///
+/// Returns early on the signal or an interruption; callers recheck.
+fn wait_signal(signo: libc::c_int, timeout: Duration) {
+    let ts = libc::timespec {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, signo);
+        libc::sigtimedwait(&set, std::ptr::null_mut(), &ts);
+    }
+}
+
+/// The leading fields of the kernel's `siginfo_t` as `sigqueue` fills them.
+#[allow(dead_code)] // Read by the kernel
+#[repr(C)]
+struct QueuedSiginfo {
+    signo: libc::c_int,
+    errno: libc::c_int,
+    code: libc::c_int,
+    /// The union member follows, aligned like the pointer in `sigval`
+    rt: QueuedFields,
+}
+
+/// The `_rt` member of the `siginfo_t` union.
+#[allow(dead_code)] // Read by the kernel
+#[repr(C)]
+struct QueuedFields {
+    pid: libc::pid_t,
+    uid: libc::uid_t,
+    value: libc::sigval,
+}
+
+/// Queue `signo` to thread `tid` of process `pid`.
+fn queue_signal(pid: libc::pid_t, tid: libc::pid_t, signo: libc::c_int) {
+    const _: () =
+        assert!(std::mem::size_of::<QueuedSiginfo>() <= std::mem::size_of::<libc::siginfo_t>());
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        let queued = &mut *(&mut info as *mut libc::siginfo_t as *mut QueuedSiginfo);
+        queued.signo = signo;
+        queued.code = libc::SI_QUEUE;
+        queued.rt.pid = std::process::id() as libc::pid_t;
+        queued.rt.uid = libc::getuid();
+    }
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_rt_tgsigqueueinfo,
+            pid,
+            tid,
+            signo,
+            &info as *const libc::siginfo_t,
+        )
+    };
+    if rc != 0 {
+        // The waiter notices the slot on its next recheck
+        trace!(
+            "rt_tgsigqueueinfo to {}/{} failed: {}",
+            pid,
+            tid,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Signal `waiter` with `signo` if it is asleep.
+fn notify(waiter: &Waiter, signo: libc::c_int) {
+    if waiter.waiting.swap(0, Ordering::SeqCst) != 0 {
+        queue_signal(
+            waiter.pid.load(Ordering::Relaxed),
+            waiter.tid.load(Ordering::Relaxed),
+            signo,
+        );
+    }
+}
+
+/// Blocking real-time signal transport.
+///
+/// Supports one client per server and one message in flight per
+/// direction.
+pub struct BlockingRtSignal {
+    /// The mapped segment
+    shmem: Option<Shmem>,
+
+    /// Start of the mapping
+    base: *mut u8,
+
+    /// Data bytes per slot
+    slot_capacity: usize,
+
+    /// Whether this instance created the segment
+    is_server: bool,
+}
+
+// Safety: the segment is only accessed through atomics and the slot
+// protocol, which hands each slot's data to one side at a time
+unsafe impl Send for BlockingRtSignal {}
+
+impl BlockingRtSignal {
+    /// Create an unconnected transport.
+    pub fn new() -> Self {
+        Self {
+            shmem: None,
+            base: std::ptr::null_mut(),
+            slot_capacity: 0,
+            is_server: false,
+        }
+    }
+
+    fn header(&self) -> Result<&Header> {
+        if self.base.is_null() {
+            bail!("RT signal transport not initialized");
+        }
+        Ok(unsafe { &*(self.base as *const Header) })
+    }
+
+    /// Slot at `index` (0: request, 1: reply) and its data area.
+    fn slot(&self, index: usize) -> Result<(&Slot, *mut u8)> {
+        self.header()?;
+        let offset = HEADER_SIZE + index * slot_stride(self.slot_capacity);
+        unsafe {
+            let slot = self.base.add(offset);
+            Ok((&*(slot as *const Slot), slot.add(SLOT_HEADER_SIZE)))
+        }
+    }
+
+    /// The slot this side sends into.
+    fn send_slot(&self) -> Result<(&Slot, *mut u8)> {
+        self.slot(usize::from(self.is_server))
+    }
+
+    /// The slot this side receives from.
+    fn receive_slot(&self) -> Result<(&Slot, *mut u8)> {
+        self.slot(usize::from(!self.is_server))
+    }
+
+    /// Sleep on `signo` as `waiter` until `slot` leaves `state`, failing
+    /// once the peer shut down.
+    fn wait_while(
+        &self,
+        slot: &Slot,
+        state: u32,
+        waiter: &Waiter,
+        signo: libc::c_int,
+    ) -> Result<()> {
+        let header = self.header()?;
+        let tid = prepare_thread()?;
+        waiter
+            .pid
+            .store(std::process::id() as libc::pid_t, Ordering::Relaxed);
+        waiter.tid.store(tid, Ordering::Relaxed);
+        loop {
+            waiter.waiting.store(1, Ordering::SeqCst);
+            if slot.state.load(Ordering::SeqCst) != state {
+                waiter.waiting.store(0, Ordering::Relaxed);
+                return Ok(());
+            }
+            if header.shutdown.load(Ordering::Acquire) != 0 {
+                waiter.waiting.store(0, Ordering::Relaxed);
+                return Err(anyhow!("Connection closed"));
+            }
+            wait_signal(signo, RECHECK);
+        }
+    }
+
+    /// Wait for a message in the receive slot.
+    fn wait_for_message(&self) -> Result<(&Slot, *mut u8)> {
+        let (slot, data) = self.receive_slot()?;
+        self.wait_while(slot, EMPTY, &slot.reader, data_signal())?;
+        Ok((slot, data))
+    }
+
+    /// Copy the message out of a full receive slot, then free the slot.
+    fn take(&self, slot: &Slot, data: *mut u8) -> Result<Message> {
+        let len = slot.len.load(Ordering::Acquire) as usize;
+        let mut bytes = vec![0u8; len];
+        unsafe {
+            std::ptr::copy_nonoverlapping(data, bytes.as_mut_ptr(), len);
+        }
+        slot.state.store(EMPTY, Ordering::SeqCst);
+        notify(&slot.writer, space_signal());
+        bincode::deserialize(&bytes).context("Failed to deserialize message")
+    }
+}
+
+impl Default for BlockingRtSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingTransport for BlockingRtSignal {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        debug!(
+            "Starting blocking RT signal server with segment: {}",
+            config.shared_memory_name
+        );
+
+        // Best-effort removal of a segment left behind by a killed run
+        if let Ok(name) = std::ffi::CString::new(format!(
+            "/{}",
+            config.shared_memory_name.trim_start_matches('/')
+        )) {
+            unsafe {
+                libc::shm_unlink(name.as_ptr());
+            }
+        }
+
+        let capacity = config.buffer_size;
+        let shmem = ShmemConf::new()
+            .size(segment_size(capacity))
+            .os_id(&config.shared_memory_name)
+            .create()
+            .with_context(|| {
+                format!(
+                    "Failed to create RT signal segment: {}",
+                    config.shared_memory_name
+                )
+            })?;
+
+        self.base = shmem.as_ptr();
+        self.slot_capacity = capacity;
+        self.is_server = true;
+        self.shmem = Some(shmem);
+
+        let header = self.header()?;
+        header.slot_capacity.store(capacity, Ordering::Relaxed);
+        header.shutdown.store(0, Ordering::Relaxed);
+        for index in 0..2 {
+            let (slot, _) = self.slot(index)?;
+            slot.state.store(EMPTY, Ordering::Relaxed);
+            slot.reader.waiting.store(0, Ordering::Relaxed);
+            slot.writer.waiting.store(0, Ordering::Relaxed);
+        }
+        header.ready.store(1, Ordering::Release);
+        Ok(())
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        debug!(
+            "Starting blocking RT signal client, connecting to: {}",
+            config.shared_memory_name
+        );
+
+        let start = Instant::now();
+        let shmem = loop {
+            match ShmemConf::new().os_id(&config.shared_memory_name).open() {
+                Ok(shmem) => {
+                    let header = unsafe { &*(shmem.as_ptr() as *const Header) };
+                    if header.ready.load(Ordering::Acquire) != 0 {
+                        break shmem;
+                    }
+                }
+                Err(e) if start.elapsed() > OPEN_TIMEOUT => {
+                    return Err(anyhow!(
+                        "Failed to open RT signal segment: {}. Is the server running? Error: {}",
+                        config.shared_memory_name,
+                        e
+                    ));
+                }
+                Err(_) => {}
+            }
+            if start.elapsed() > OPEN_TIMEOUT {
+                bail!(
+                    "Timeout waiting for the server to initialize RT signal segment {}",
+                    config.shared_memory_name
+                );
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        self.base = shmem.as_ptr();
+        self.is_server = false;
+        self.shmem = Some(shmem);
+        self.slot_capacity = self.header()?.slot_capacity.load(Ordering::Acquire);
+        Ok(())
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        trace!("Sending message ID {} via RT signal", message.id);
+
+        // Serialize with a placeholder timestamp, refreshed right before the
+        // copy so waiting for the slot is not counted as latency
+        let mut message = message.clone();
+        message.timestamp = 0;
+        let mut bytes = bincode::serialize(&message).context("Failed to serialize message")?;
+        if bytes.len() > self.slot_capacity {
+            bail!(
+                "Message of {} bytes does not fit the {}-byte RT signal slot; increase --buffer-size",
+                bytes.len(),
+                self.slot_capacity
+            );
+        }
+
+        let (slot, data) = self.send_slot()?;
+        self.wait_while(slot, FULL, &slot.writer, space_signal())?;
+
+        let ts = crate::ipc::get_monotonic_time_ns().to_le_bytes();
+        bytes[Message::timestamp_offset()].copy_from_slice(&ts);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+        }
+        slot.len.store(bytes.len() as u32, Ordering::Relaxed);
+        slot.state.store(FULL, Ordering::SeqCst);
+        notify(&slot.reader, data_signal());
+        Ok(())
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        let (slot, data) = self.wait_for_message()?;
+        self.take(slot, data)
+    }
+
+    fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
+        let (slot, data) = self.wait_for_message()?;
+        let receive_time_ns = crate::ipc::get_monotonic_time_ns();
+        Ok((self.take(slot, data)?, receive_time_ns))
+    }
+
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        let Ok((slot, data)) = self.receive_slot() else {
+            return Ok(None);
+        };
+        if slot.state.load(Ordering::Acquire) != FULL {
+            return Ok(None);
+        }
+        self.take(slot, data).map(Some)
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        debug!("Closing blocking RT signal transport");
+        if let Ok(header) = self.header() {
+            header.shutdown.store(1, Ordering::Release);
+            for index in 0..2 {
+                if let Ok((slot, _)) = self.slot(index) {
+                    notify(&slot.reader, data_signal());
+                    notify(&slot.writer, space_signal());
+                }
+            }
+        }
+        // Dropping the server's mapping unlinks the segment
+        self.shmem = None;
+        self.base = std::ptr::null_mut();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+
+    #[test]
+    fn test_segment_layout() {
+        assert_eq!(std::mem::size_of::<Slot>(), SLOT_HEADER_SIZE);
+        assert!(std::mem::size_of::<Header>() <= HEADER_SIZE);
+        assert_eq!(slot_stride(32), 64);
+        assert_eq!(slot_stride(33), 128);
+        assert_eq!(segment_size(32), 64 + 2 * 64);
+    }
+
+    #[test]
+    fn test_rt_signal_round_trip_between_threads() {
+        let config = TransportConfig {
+            shared_memory_name: format!("ipc_benchmark_rtsig_test_{}", std::process::id()),
+            buffer_size: 1024,
+            ..Default::default()
+        };
+        let mut server = BlockingRtSignal::new();
+        server.start_server_blocking(&config).unwrap();
+
+        let client_config = config.clone();
+        let client = std::thread::spawn(move || {
+            let mut client = BlockingRtSignal::new();
+            client.start_client_blocking(&client_config).unwrap();
+            for id in 0..50 {
+                client
+                    .send_blocking(&Message::new(id, vec![3; 64], MessageType::Request))
+                    .unwrap();
+                let reply = client.receive_blocking().unwrap();
+                assert_eq!(reply.id, id + 1000);
+            }
+            client.close_blocking().unwrap();
+        });
+
+        for _ in 0..50 {
+            let (request, _) = server.receive_blocking_timed().unwrap();
+            assert_eq!(request.payload, vec![3; 64]);
+            assert!(request.timestamp > 0);
+            server
+                .send_blocking(&Message::new(
+                    request.id + 1000,
+                    Vec::new(),
+                    MessageType::Response,
+                ))
+                .unwrap();
+        }
+        client.join().unwrap();
+
+        // The client's close wakes and fails a waiting receive
+        assert!(server.receive_blocking().is_err());
+        server.close_blocking().unwrap();
+    }
+}
@@ -0,0 +1,647 @@
+//! Message segmentation for payloads larger than a transport's limit.
+//!
+//! Every mechanism has a ceiling on the size of a single message: POSIX
+//! message queues are bounded by `msgsize_max`, the SHM ring by its byte
+//! capacity, direct-memory SHM by its fixed slot, and the stream sockets by
+//! their 16 MB frame guard. [`SegmentedTransport`] and
+//! [`SegmentedBlockingTransport`] wrap any transport and split each message
+//! into segments that fit, then reassemble them on the receiving side, so
+//! `--message-size` can exceed the limit on every mechanism.
+//!
+//! ## Segment Protocol
+//!
+//! Each segment is an ordinary [`Message`] carrying the original id,
+//! timestamp and message type. Its payload starts with a fixed header:
+//!
+//! ```text
+//! ┌──────────┬──────────┬──────────┬──────────────┬─────────────┐
+//! │ magic u32│ index u32│ count u32│ total_len u64│ chunk bytes │
+//! └──────────┴──────────┴──────────┴──────────────┴─────────────┘
+//! ```
+//!
+//! All integers are little-endian. Segments of one message are sent back to
+//! back, so the receiver expects them in order and rejects interleaving.
+//! Both ends must agree on segmentation: when it is on, every message
+//! (including control messages) is framed, even if it fits in one segment.
+
+use super::{BlockingTransport, IpcTransport, Message, TransportConfig, TransportStats};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tracing::debug;
+
+/// Marks a segment header ("SEGM" in little-endian byte order).
+const SEGMENT_MAGIC: u32 = u32::from_le_bytes(*b"SEGM");
+
+/// Bytes of segment header at the start of every segment payload.
+pub const SEGMENT_HEADER_LEN: usize = 20;
+
+/// Serialization overhead reserved per message when sizing segments.
+///
+/// Matches the runners' per-message allowance: bincode id, timestamp,
+/// payload length and message type, rounded up for safety.
+const MESSAGE_OVERHEAD: usize = 64;
+
+/// Frame size guard shared by the TCP and UDS transports.
+const STREAM_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest payload a single message can carry on `mechanism`.
+///
+/// `buffer_size` is the resolved transport buffer size. `shm_direct`
+/// selects the fixed-slot SHM implementation.
+pub fn max_payload_for(
+    mechanism: &crate::mechanism::IpcMechanism,
+    buffer_size: usize,
+    shm_direct: bool,
+) -> usize {
+    use crate::mechanism::IpcMechanism;
+    #[cfg(not(all(unix, feature = "shm")))]
+    let _ = shm_direct;
+
+    match mechanism {
+        #[cfg(all(unix, feature = "shm"))]
+        IpcMechanism::SharedMemory if shm_direct => super::shared_memory_direct::MAX_PAYLOAD_SIZE,
+        // The ring transports reserve 1 KB of the buffer for metadata.
+        IpcMechanism::SharedMemory => buffer_size
+            .saturating_sub(1024)
+            .saturating_sub(MESSAGE_OVERHEAD),
+        #[cfg(target_os = "linux")]
+        IpcMechanism::PosixMessageQueue => buffer_size.max(1024) - MESSAGE_OVERHEAD,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::Futex | IpcMechanism::RtSignal => {
+            buffer_size.saturating_sub(MESSAGE_OVERHEAD)
+        }
+        #[cfg(target_os = "linux")]
+        IpcMechanism::UdpMulticast => {
+            super::udp_multicast_blocking::MAX_DATAGRAM_SIZE - MESSAGE_OVERHEAD
+        }
+        _ => STREAM_MAX_MESSAGE_SIZE - MESSAGE_OVERHEAD,
+    }
+}
+
+/// Segment payload size needed to send `message_size`-byte messages over a
+/// transport whose single-message limit is `max_payload`.
+///
+/// Returns `None` when messages already fit and no segmentation is needed.
+pub fn auto_segment_size(message_size: usize, max_payload: usize) -> Option<usize> {
+    if message_size <= max_payload {
+        None
+    } else {
+        Some(max_payload.saturating_sub(SEGMENT_HEADER_LEN).max(1))
+    }
+}
+
+/// Number of segments a `payload_len`-byte payload is split into.
+pub fn segment_count(payload_len: usize, segment_size: usize) -> usize {
+    if payload_len == 0 {
+        1
+    } else {
+        (payload_len + segment_size - 1) / segment_size
+    }
+}
+
+/// Wrap an async transport so it segments messages when `segment_size` is set.
+pub fn wrap(
+    transport: Box<dyn IpcTransport>,
+    segment_size: Option<usize>,
+) -> Box<dyn IpcTransport> {
+    match segment_size {
+        Some(size) => Box::new(SegmentedTransport::new(transport, size)),
+        None => transport,
+    }
+}
+
+/// Wrap a blocking transport so it segments messages when `segment_size` is set.
+pub fn wrap_blocking(
+    transport: Box<dyn BlockingTransport>,
+    segment_size: Option<usize>,
+) -> Box<dyn BlockingTransport> {
+    match segment_size {
+        Some(size) => Box::new(SegmentedBlockingTransport::new(transport, size)),
+        None => transport,
+    }
+}
+
+struct SegmentHeader {
+    index: u32,
+    count: u32,
+    total_len: u64,
+}
+
+impl SegmentHeader {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&SEGMENT_MAGIC.to_le_bytes());
+        out.extend_from_slice(&self.index.to_le_bytes());
+        out.extend_from_slice(&self.count.to_le_bytes());
+        out.extend_from_slice(&self.total_len.to_le_bytes());
+    }
+
+    fn read(payload: &[u8]) -> Result<Self> {
+        if payload.len() < SEGMENT_HEADER_LEN {
+            return Err(anyhow!(
+                "Segment too short: {} bytes, header needs {}",
+                payload.len(),
+                SEGMENT_HEADER_LEN
+            ));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(payload[at..at + 4].try_into().unwrap());
+        if u32_at(0) != SEGMENT_MAGIC {
+            return Err(anyhow!(
+                "Missing segment header; is segmentation enabled on both ends?"
+            ));
+        }
+        let header = Self {
+            index: u32_at(4),
+            count: u32_at(8),
+            total_len: u64::from_le_bytes(payload[12..20].try_into().unwrap()),
+        };
+        if header.count == 0 || header.index >= header.count {
+            return Err(anyhow!(
+                "Invalid segment {} of {}",
+                header.index,
+                header.count
+            ));
+        }
+        Ok(header)
+    }
+}
+
+/// Splits outgoing messages into segments, reusing one scratch message.
+struct Segmenter {
+    segment_size: usize,
+    scratch: Message,
+    segments_sent: u64,
+}
+
+impl Segmenter {
+    fn new(segment_size: usize) -> Self {
+        Self {
+            segment_size,
+            scratch: Message::new(0, Vec::new(), super::MessageType::OneWay),
+            segments_sent: 0,
+        }
+    }
+
+    /// Number of segments `message` is sent as.
+    fn count(&self, message: &Message) -> usize {
+        segment_count(message.payload.len(), self.segment_size)
+    }
+
+    /// Fill the scratch message with segment `index` of `message`.
+    fn segment(&mut self, message: &Message, index: usize, count: usize) -> &Message {
+        let start = index * self.segment_size;
+        let end = (start + self.segment_size).min(message.payload.len());
+        let chunk = &message.payload[start.min(end)..end];
+
+        self.scratch.id = message.id;
+        self.scratch.timestamp = message.timestamp;
+        self.scratch.message_type = message.message_type;
+        self.scratch.ttl_ns = message.ttl_ns;
+        self.scratch.priority = message.priority;
+        self.scratch.server_receive_ns = message.server_receive_ns;
+        self.scratch.payload.clear();
+        SegmentHeader {
+            index: index as u32,
+            count: count as u32,
+            total_len: message.payload.len() as u64,
+        }
+        .write(&mut self.scratch.payload);
+        self.scratch.payload.extend_from_slice(chunk);
+        self.segments_sent += 1;
+        &self.scratch
+    }
+}
+
+/// Collects incoming segments until a message is complete.
+#[derive(Default)]
+pub struct Reassembler {
+    pending: Option<Pending>,
+    segments_received: u64,
+}
+
+struct Pending {
+    message: Message,
+    next_index: u32,
+    count: u32,
+    total_len: u64,
+}
+
+impl Reassembler {
+    /// Create an empty reassembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a message has been started but not completed.
+    pub fn in_progress(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Total segments accepted so far.
+    pub fn segments_received(&self) -> u64 {
+        self.segments_received
+    }
+
+    /// Add one received segment.
+    ///
+    /// Returns the reassembled message once its last segment arrives. The
+    /// result keeps the first segment's id, timestamp and first-byte time,
+    /// and the last segment's receive time, so latency spans the whole
+    /// message.
+    pub fn push(&mut self, mut segment: Message) -> Result<Option<Message>> {
+        let header = SegmentHeader::read(&segment.payload)?;
+        self.segments_received += 1;
+
+        if header.index == 0 {
+            if let Some(pending) = &self.pending {
+                return Err(anyhow!(
+                    "Message {} interrupted after {} of {} segments by message {}",
+                    pending.message.id,
+                    pending.next_index,
+                    pending.count,
+                    segment.id
+                ));
+            }
+            segment.payload.drain(..SEGMENT_HEADER_LEN);
+            if header.count == 1 {
+                return Self::complete(segment, header.total_len).map(Some);
+            }
+            self.pending = Some(Pending {
+                message: segment,
+                next_index: 1,
+                count: header.count,
+                total_len: header.total_len,
+            });
+            return Ok(None);
+        }
+
+        let pending = match &mut self.pending {
+            Some(pending)
+                if pending.message.id == segment.id
+                    && pending.next_index == header.index
+                    && pending.count == header.count =>
+            {
+                pending
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unexpected segment {} of {} for message {}",
+                    header.index,
+                    header.count,
+                    segment.id
+                ))
+            }
+        };
+        pending
+            .message
+            .payload
+            .extend_from_slice(&segment.payload[SEGMENT_HEADER_LEN..]);
+        pending.message.receive_time_ns = segment.receive_time_ns;
+        pending.next_index += 1;
+        if pending.next_index < pending.count {
+            return Ok(None);
+        }
+
+        let pending = self.pending.take().expect("pending message");
+        Self::complete(pending.message, pending.total_len).map(Some)
+    }
+
+    fn complete(message: Message, total_len: u64) -> Result<Message> {
+        if message.payload.len() as u64 != total_len {
+            return Err(anyhow!(
+                "Reassembled message {} has {} bytes, expected {}",
+                message.id,
+                message.payload.len(),
+                total_len
+            ));
+        }
+        Ok(message)
+    }
+}
+
+/// Async transport wrapper that segments large messages.
+///
+/// Only the single-connection interface is segmented; the multi-connection
+/// methods fall back to the trait defaults.
+pub struct SegmentedTransport {
+    inner: Box<dyn IpcTransport>,
+    segmenter: Segmenter,
+    reassembler: Reassembler,
+}
+
+impl SegmentedTransport {
+    /// Wrap `inner`, splitting payloads into `segment_size`-byte chunks.
+    pub fn new(inner: Box<dyn IpcTransport>, segment_size: usize) -> Self {
+        Self {
+            inner,
+            segmenter: Segmenter::new(segment_size.max(1)),
+            reassembler: Reassembler::new(),
+        }
+    }
+
+    /// Receive segments until `first` completes a message.
+    async fn finish(&mut self, first: Message) -> Result<Message> {
+        let mut segment = first;
+        loop {
+            if let Some(message) = self.reassembler.push(segment)? {
+                return Ok(message);
+            }
+            segment = self.inner.receive().await?;
+        }
+    }
+}
+
+#[async_trait]
+impl IpcTransport for SegmentedTransport {
+    async fn start_server(&mut self, config: &TransportConfig) -> Result<()> {
+        self.inner.start_server(config).await
+    }
+
+    async fn start_client(&mut self, config: &TransportConfig) -> Result<()> {
+        self.inner.start_client(config).await
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<bool> {
+        let count = self.segmenter.count(message);
+        let mut backpressure = false;
+        for index in 0..count {
+            let segment = self.segmenter.segment(message, index, count);
+            backpressure |= self.inner.send(segment).await?;
+        }
+        Ok(backpressure)
+    }
+
+    async fn receive(&mut self) -> Result<Message> {
+        let first = self.inner.receive().await?;
+        self.finish(first).await
+    }
+
+    async fn try_receive(&mut self) -> Result<Option<Message>> {
+        match self.inner.try_receive().await? {
+            // The rest of a started message is already in flight.
+            Some(first) => self.finish(first).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        debug!(
+            "Segmented {}: sent {} segments, received {}",
+            self.inner.name(),
+            self.segmenter.segments_sent,
+            self.reassembler.segments_received()
+        );
+        self.inner.close().await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn supports_bidirectional(&self) -> bool {
+        self.inner.supports_bidirectional()
+    }
+
+    fn max_message_size(&self) -> usize {
+        usize::MAX
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        self.inner.local_port()
+    }
+
+    fn queue_depth_probe(&self) -> Option<Box<dyn crate::queue_depth::QueueDepthProbe>> {
+        self.inner.queue_depth_probe()
+    }
+
+    fn stats(&self) -> TransportStats {
+        self.inner.stats()
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.inner.set_frame_checksums(on)
+    }
+}
+
+/// Blocking transport wrapper that segments large messages.
+pub struct SegmentedBlockingTransport {
+    inner: Box<dyn BlockingTransport>,
+    segmenter: Segmenter,
+    reassembler: Reassembler,
+}
+
+impl SegmentedBlockingTransport {
+    /// Wrap `inner`, splitting payloads into `segment_size`-byte chunks.
+    pub fn new(inner: Box<dyn BlockingTransport>, segment_size: usize) -> Self {
+        Self {
+            inner,
+            segmenter: Segmenter::new(segment_size.max(1)),
+            reassembler: Reassembler::new(),
+        }
+    }
+
+    /// Receive segments until `first` completes a message.
+    fn finish(&mut self, first: Message) -> Result<Message> {
+        let mut segment = first;
+        loop {
+            if let Some(message) = self.reassembler.push(segment)? {
+                return Ok(message);
+            }
+            segment = self.inner.receive_blocking()?;
+        }
+    }
+}
+
+impl BlockingTransport for SegmentedBlockingTransport {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.inner.start_server_blocking(config)
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        self.inner.start_client_blocking(config)
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        let count = self.segmenter.count(message);
+        for index in 0..count {
+            let segment = self.segmenter.segment(message, index, count);
+            self.inner.send_blocking(segment)?;
+        }
+        Ok(())
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        let first = self.inner.receive_blocking()?;
+        self.finish(first)
+    }
+
+    fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
+        loop {
+            let (segment, received_ns) = self.inner.receive_blocking_timed()?;
+            if let Some(message) = self.reassembler.push(segment)? {
+                return Ok((message, received_ns));
+            }
+        }
+    }
+
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        match self.inner.try_receive_blocking()? {
+            // The rest of a started message is already in flight.
+            Some(first) => self.finish(first).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        self.inner.supports_try_receive()
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        self.inner.local_port()
+    }
+
+    fn queue_depth_probe(&self) -> Option<Box<dyn crate::queue_depth::QueueDepthProbe>> {
+        self.inner.queue_depth_probe()
+    }
+
+    fn stats(&self) -> TransportStats {
+        self.inner.stats()
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.inner.set_frame_checksums(on)
+    }
+
+    fn accept_blocking(&mut self) -> Result<Box<dyn BlockingTransport>> {
+        let connection = self.inner.accept_blocking()?;
+        Ok(Box::new(Self::new(connection, self.segmenter.segment_size)))
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        debug!(
+            "Segmented transport: sent {} segments, received {}",
+            self.segmenter.segments_sent,
+            self.reassembler.segments_received()
+        );
+        self.inner.close_blocking()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::MessageType;
+    use std::time::Duration;
+
+    fn split(message: &Message, segment_size: usize) -> Vec<Message> {
+        let mut segmenter = Segmenter::new(segment_size);
+        let count = segmenter.count(message);
+        (0..count)
+            .map(|i| segmenter.segment(message, i, count).clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_and_reassemble_round_trip() {
+        let payload: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let message = Message::new(7, payload.clone(), MessageType::Request)
+            .with_ttl(Some(Duration::from_millis(5)))
+            .with_priority(3);
+        let segments = split(&message, 4096);
+        assert_eq!(segments.len(), 3);
+        assert!(segments
+            .iter()
+            .all(|s| s.payload.len() <= 4096 + SEGMENT_HEADER_LEN));
+
+        let mut reassembler = Reassembler::new();
+        let mut done = None;
+        for (i, mut segment) in segments.into_iter().enumerate() {
+            segment.receive_time_ns = 100 + i as u64;
+            done = reassembler.push(segment).unwrap();
+            assert_eq!(done.is_some(), i == 2);
+        }
+        let done = done.unwrap();
+        assert_eq!(done.id, 7);
+        assert_eq!(done.timestamp, message.timestamp);
+        assert_eq!(done.message_type, MessageType::Request);
+        assert_eq!(done.ttl_ns, message.ttl_ns);
+        assert_eq!(done.priority, 3);
+        assert_eq!(done.payload, payload);
+        assert_eq!(done.receive_time_ns, 102);
+        assert!(!reassembler.in_progress());
+        assert_eq!(reassembler.segments_received(), 3);
+    }
+
+    #[test]
+    fn test_empty_payload_is_one_segment() {
+        let message = Message::new(1, Vec::new(), MessageType::Shutdown);
+        let segments = split(&message, 128);
+        assert_eq!(segments.len(), 1);
+        let done = Reassembler::new()
+            .push(segments[0].clone())
+            .unwrap()
+            .unwrap();
+        assert!(done.payload.is_empty());
+        assert_eq!(done.message_type, MessageType::Shutdown);
+    }
+
+    #[test]
+    fn test_rejects_unframed_and_out_of_order_segments() {
+        let mut reassembler = Reassembler::new();
+        assert!(reassembler
+            .push(Message::new(1, vec![0u8; 64], MessageType::OneWay))
+            .is_err());
+
+        let first = split(&Message::new(2, vec![1u8; 300], MessageType::OneWay), 100);
+        let second = split(&Message::new(3, vec![2u8; 300], MessageType::OneWay), 100);
+        assert!(reassembler.push(first[0].clone()).unwrap().is_none());
+        assert!(reassembler.push(first[2].clone()).is_err());
+
+        let mut reassembler = Reassembler::new();
+        reassembler.push(first[0].clone()).unwrap();
+        assert!(reassembler.push(second[0].clone()).is_err());
+    }
+
+    #[test]
+    fn test_auto_segment_size_only_when_needed() {
+        assert_eq!(auto_segment_size(1024, 8128), None);
+        assert_eq!(
+            auto_segment_size(65536, 8128),
+            Some(8128 - SEGMENT_HEADER_LEN)
+        );
+        assert_eq!(segment_count(65536, 8108), 9);
+        assert_eq!(segment_count(0, 8108), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "tcp")]
+    fn test_blocking_wrapper_over_tcp() {
+        use crate::ipc::BlockingTcpSocket;
+        use std::thread;
+
+        let config = TransportConfig {
+            port: 18092,
+            ..Default::default()
+        };
+        let server_config = config.clone();
+        let server = thread::spawn(move || {
+            let mut server = wrap_blocking(Box::new(BlockingTcpSocket::new()), Some(1000));
+            server.start_server_blocking(&server_config).unwrap();
+            let received = server.receive_blocking().unwrap();
+            server.send_blocking(&received).unwrap();
+            received
+        });
+        thread::sleep(std::time::Duration::from_millis(100));
+
+        let mut client = wrap_blocking(Box::new(BlockingTcpSocket::new()), Some(1000));
+        client.start_client_blocking(&config).unwrap();
+        let payload: Vec<u8> = (0..5500u32).map(|i| (i % 251) as u8).collect();
+        let message = Message::new(42, payload.clone(), MessageType::Request);
+        client.send_blocking(&message).unwrap();
+        let echoed = client.receive_blocking().unwrap();
+
+        let received = server.join().unwrap();
+        assert_eq!(received.payload, payload);
+        assert_eq!(echoed.id, 42);
+        assert_eq!(echoed.payload, payload);
+    }
+}
@@ -1,49 +1,361 @@
+use super::handshake::PROTOCOL_VERSION;
 use super::{
     ConnectionId, ConnectionRole, IpcError, IpcTransport, Message, TransportConfig, TransportState,
+    TransportStats,
 };
-use anyhow::{anyhow, Result};
+use crate::queue_depth::{QueueCapacity, QueueDepth, QueueDepthProbe};
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
 use parking_lot::Mutex;
 use shared_memory::{Shmem, ShmemConf};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, Notify};
 use tokio::time::sleep;
-use tracing::{debug, warn};
+use tracing::{debug, warn, Instrument};
+
+/// Cache-line layout and limits of a shared memory ring buffer.
+///
+/// The side that creates the segment picks the layout and records the
+/// resulting offsets in the ring header, so the opening side always follows
+/// the creator. Packed records and adjacent indices (the defaults) keep the
+/// historical layout; padding and alignment exist to measure false sharing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ShmRingLayout {
+    /// Every record starts at a multiple of this many bytes (1 = packed)
+    pub slot_align: usize,
+    /// Keep the read and write indices on separate cache lines
+    pub pad_indices: bool,
+    /// Maximum unread records in the ring (0 = bounded by bytes only)
+    pub max_messages: usize,
+}
+
+impl Default for ShmRingLayout {
+    fn default() -> Self {
+        Self {
+            slot_align: 1,
+            pad_indices: false,
+            max_messages: 0,
+        }
+    }
+}
+
+impl ShmRingLayout {
+    const MIN_LINE_SIZE: usize = 64;
+
+    pub fn from_config(config: &TransportConfig) -> Self {
+        Self {
+            slot_align: config.shm_slot_align.max(1),
+            pad_indices: config.shm_pad_indices,
+            max_messages: config.shm_capacity_messages.unwrap_or(0),
+        }
+    }
+
+    fn line_size(&self) -> usize {
+        self.slot_align.max(Self::MIN_LINE_SIZE)
+    }
+
+    /// Offsets of the read index, write index and data area from the start
+    /// of a segment whose fixed header is `header_size` bytes.
+    ///
+    /// Each index is followed by its side's message counter (records read
+    /// and records written), so with padding the reader never touches a
+    /// line the writer stores to except the record it is consuming.
+    pub fn offsets(&self, header_size: usize) -> (usize, usize, usize) {
+        let line = self.line_size();
+        let index_base = round_up(header_size, line);
+        let write_index = if self.pad_indices {
+            index_base + line
+        } else {
+            index_base + 2 * std::mem::size_of::<usize>()
+        };
+        (index_base, write_index, index_base + 2 * line)
+    }
+
+    /// Ring capacity actually used for a requested size, so that aligned
+    /// records never straddle the wrap point unevenly.
+    pub fn capacity(&self, requested: usize) -> usize {
+        round_up(requested, self.slot_align)
+    }
+}
+
+/// Marks a ring header its creator has finished initializing ("IPCR" in
+/// little-endian byte order).
+pub(crate) const RING_MAGIC: u32 = u32::from_le_bytes(*b"IPCR");
+
+/// Whether a ring header found by the side opening a segment, which starts
+/// with `magic` and `version`, is ready to use: `Ok(false)` while the
+/// creator is still initializing it, and an error when another build of the
+/// benchmark, or another program, laid out the segment.
+///
+/// The creator stores the magic last, so once it reads [`RING_MAGIC`] the
+/// rest of the header is in place.
+pub(crate) fn ring_header_ready(magic: &AtomicU32, version: &u32, name: &str) -> Result<bool> {
+    match magic.load(Ordering::Acquire) {
+        0 => Ok(false),
+        RING_MAGIC if *version == u32::from(PROTOCOL_VERSION) => Ok(true),
+        RING_MAGIC => bail!(
+            "Shared memory segment {} was laid out for protocol version {} but this build \
+             speaks version {}; run the same ipc-benchmark release on both ends",
+            name,
+            version,
+            PROTOCOL_VERSION
+        ),
+        _ => bail!(
+            "Shared memory segment {} does not hold an ipc-benchmark ring buffer",
+            name
+        ),
+    }
+}
+
+/// Bytes ahead of every record's payload: its length, then its
+/// [`RecordStamp`], each a little-endian `u32`.
+pub(crate) const RECORD_HEADER: usize = 12;
+
+/// Bytes a record with `payload_len` bytes occupies in the ring, including
+/// its header and alignment padding.
+#[inline]
+pub(crate) fn record_size(payload_len: usize, slot_align: usize) -> usize {
+    round_up(payload_len + RECORD_HEADER, slot_align)
+}
+
+/// Where a record belongs in the stream of records written to a ring.
+///
+/// The writer stamps each record with the number of records written before
+/// it (the sequence) and the number of times the write index had wrapped
+/// (the generation). The reader expects the stamp its own counters predict:
+/// a record with any other stamp was left over from an earlier pass over
+/// the ring (stale), and one whose stamp changed while its payload was being
+/// copied was overwritten mid-read (torn). Neither is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecordStamp {
+    pub sequence: u32,
+    pub generation: u32,
+}
+
+impl RecordStamp {
+    /// The stamp for the record after `records` records, with the index
+    /// having wrapped `wraps` times; both are kept modulo 2^32.
+    #[inline]
+    pub fn new(records: usize, wraps: usize) -> Self {
+        Self {
+            sequence: records as u32,
+            generation: wraps as u32,
+        }
+    }
+
+    /// Check the stamp a reader found before copying a record's payload
+    /// (`before`) and after (`after`) against the one it expected.
+    #[inline]
+    pub fn check(self, before: Self, after: Self) -> std::result::Result<(), BadRecord> {
+        if before != self {
+            Err(BadRecord::Stale)
+        } else if after != before {
+            Err(BadRecord::Torn)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A record the reader discarded instead of delivering its payload.
+///
+/// Reads return it as the error, after moving the read index up to the
+/// writer's, so the next read waits for a fresh record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BadRecord {
+    /// The record's stamp was not the one expected next
+    Stale,
+    /// The record's stamp changed while its payload was copied
+    Torn,
+}
+
+impl std::fmt::Display for BadRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BadRecord::Stale => write!(f, "Stale shared memory record discarded"),
+            BadRecord::Torn => write!(f, "Torn shared memory record discarded"),
+        }
+    }
+}
+
+impl std::error::Error for BadRecord {}
+
+/// Counts of the records a reader discarded, by kind.
+#[derive(Debug, Default)]
+pub(crate) struct DiscardedRecords {
+    stale: AtomicU64,
+    torn: AtomicU64,
+}
+
+impl DiscardedRecords {
+    /// Count `error` if it reports a discarded record; returns whether it
+    /// did, so the caller can read again instead of failing.
+    pub fn count(&self, error: &anyhow::Error) -> bool {
+        match error.downcast_ref::<BadRecord>() {
+            Some(BadRecord::Stale) => self.stale.fetch_add(1, Ordering::Relaxed),
+            Some(BadRecord::Torn) => self.torn.fetch_add(1, Ordering::Relaxed),
+            None => return false,
+        };
+        true
+    }
+
+    /// The counts as `stale_records` and `torn_records`
+    pub fn stats(&self) -> TransportStats {
+        TransportStats::from([
+            (
+                "stale_records".to_string(),
+                self.stale.load(Ordering::Relaxed),
+            ),
+            (
+                "torn_records".to_string(),
+                self.torn.load(Ordering::Relaxed),
+            ),
+        ])
+    }
+}
+
+/// Write the header of a `len`-byte record at `pos` in the ring of
+/// `capacity` bytes at `data`.
+///
+/// # Safety
+/// `data` must point to `capacity` writable bytes.
+#[inline]
+pub(crate) unsafe fn write_record_header(
+    data: *mut u8,
+    capacity: usize,
+    pos: usize,
+    len: usize,
+    stamp: RecordStamp,
+) {
+    let mut header = [0u8; RECORD_HEADER];
+    header[0..4].copy_from_slice(&(len as u32).to_le_bytes());
+    header[4..8].copy_from_slice(&stamp.sequence.to_le_bytes());
+    header[8..12].copy_from_slice(&stamp.generation.to_le_bytes());
+    for (i, &byte) in header.iter().enumerate() {
+        *data.add((pos + i) % capacity) = byte;
+    }
+}
+
+/// Read the length and stamp of the record at `pos` in the ring of
+/// `capacity` bytes at `data`.
+///
+/// The bytes are read volatile, so reading a header again after copying
+/// the payload sees whatever the writer has stored since.
+///
+/// # Safety
+/// `data` must point to `capacity` readable bytes.
+#[inline]
+pub(crate) unsafe fn read_record_header(
+    data: *const u8,
+    capacity: usize,
+    pos: usize,
+) -> (usize, RecordStamp) {
+    let mut header = [0u8; RECORD_HEADER];
+    for (i, byte) in header.iter_mut().enumerate() {
+        *byte = std::ptr::read_volatile(data.add((pos + i) % capacity));
+    }
+    let word = |at: usize| {
+        u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    (
+        word(0) as usize,
+        RecordStamp {
+            sequence: word(4),
+            generation: word(8),
+        },
+    )
+}
+
+/// Ring capacity in bytes that holds `messages` records of `payload_len`
+/// bytes each, so that a message-count limit of the same size is reached
+/// before the byte limit.
+pub fn ring_bytes_for_messages(messages: usize, payload_len: usize, slot_align: usize) -> usize {
+    let slot_align = slot_align.max(1);
+    // One extra slot-aligned chunk covers the byte the ring always keeps free
+    messages * record_size(payload_len, slot_align) + slot_align
+}
+
+#[inline]
+fn round_up(value: usize, align: usize) -> usize {
+    (value + align - 1) / align * align
+}
 
 /// Shared memory ring buffer structure
+///
+/// The read/write indices live outside this struct at offsets chosen by
+/// [`ShmRingLayout`], so they can be placed on shared or separate cache
+/// lines at runtime.
 #[repr(C)]
 struct SharedMemoryRingBuffer {
+    // RING_MAGIC once initialized, and the layout's protocol version; the
+    // opening side checks both before trusting anything after them
+    magic: AtomicU32,
+    version: u32,
+
     // Ring buffer metadata
     capacity: AtomicUsize,
-    read_pos: AtomicUsize,
-    write_pos: AtomicUsize,
+
+    // Layout recorded by the creator; read-only once the segment is shared
+    slot_align: usize,
+    max_messages: usize,
+    read_index_offset: usize,
+    write_index_offset: usize,
+    data_offset: usize,
+
+    // Times each index has wrapped, for the record stamps; each is touched
+    // once per pass over the ring, so they share the header's line
+    write_wraps: AtomicUsize,
+    read_wraps: AtomicUsize,
 
     // Synchronization flags
     server_ready: AtomicBool,
     client_ready: AtomicBool,
     shutdown: AtomicBool,
-
-    // Message count for coordination
-    message_count: AtomicUsize,
-    // Data follows after this header
+    // Indices and data follow at the offsets above
 }
 
 impl SharedMemoryRingBuffer {
     const HEADER_SIZE: usize = std::mem::size_of::<Self>();
 
-    fn new(capacity: usize) -> Self {
-        Self {
-            capacity: AtomicUsize::new(capacity),
-            read_pos: AtomicUsize::new(0),
-            write_pos: AtomicUsize::new(0),
-            server_ready: AtomicBool::new(false),
-            client_ready: AtomicBool::new(false),
-            shutdown: AtomicBool::new(false),
-            message_count: AtomicUsize::new(0),
-        }
+    /// Total segment size needed for `capacity` data bytes with `layout`.
+    fn segment_size(capacity: usize, layout: &ShmRingLayout) -> usize {
+        layout.offsets(Self::HEADER_SIZE).2 + layout.capacity(capacity)
+    }
+
+    /// Initialize the header and indices of a freshly created segment.
+    ///
+    /// # Safety
+    /// `ptr` must point to at least `segment_size(capacity, layout)` writable bytes.
+    unsafe fn initialize(ptr: *mut Self, capacity: usize, layout: &ShmRingLayout) {
+        let (read_index_offset, write_index_offset, data_offset) =
+            layout.offsets(Self::HEADER_SIZE);
+        std::ptr::write(
+            ptr,
+            Self {
+                magic: AtomicU32::new(0),
+                version: u32::from(PROTOCOL_VERSION),
+                capacity: AtomicUsize::new(layout.capacity(capacity)),
+                slot_align: layout.slot_align,
+                max_messages: layout.max_messages,
+                read_index_offset,
+                write_index_offset,
+                data_offset,
+                write_wraps: AtomicUsize::new(0),
+                read_wraps: AtomicUsize::new(0),
+                server_ready: AtomicBool::new(false),
+                client_ready: AtomicBool::new(false),
+                shutdown: AtomicBool::new(false),
+            },
+        );
+        let rb = &*ptr;
+        rb.read_pos().store(0, Ordering::Release);
+        rb.write_pos().store(0, Ordering::Release);
+        rb.message_count().store(0, Ordering::Release);
+        rb.consumed_count().store(0, Ordering::Release);
+        rb.magic.store(RING_MAGIC, Ordering::Release);
     }
 
     // PERF: #[inline] on all ring buffer hot-path functions below
@@ -52,16 +364,70 @@ impl SharedMemoryRingBuffer {
     // send/receive. Inlining eliminates call overhead and lets LLVM
     // optimize across the call boundary (e.g. keeping the data pointer
     // in a register across consecutive field accesses).
+    #[inline]
+    fn index_at(&self, offset: usize) -> &AtomicUsize {
+        unsafe { &*((self as *const Self as *const u8).add(offset) as *const AtomicUsize) }
+    }
+
+    #[inline]
+    fn read_pos(&self) -> &AtomicUsize {
+        self.index_at(self.read_index_offset)
+    }
+
+    #[inline]
+    fn write_pos(&self) -> &AtomicUsize {
+        self.index_at(self.write_index_offset)
+    }
+
+    /// Records written so far; owned by the writer
+    #[inline]
+    fn message_count(&self) -> &AtomicUsize {
+        self.index_at(self.write_index_offset + std::mem::size_of::<usize>())
+    }
+
+    /// Records consumed so far; owned by the reader
+    #[inline]
+    fn consumed_count(&self) -> &AtomicUsize {
+        self.index_at(self.read_index_offset + std::mem::size_of::<usize>())
+    }
+
+    /// Whether the message-count limit leaves room for another record
+    #[inline]
+    fn has_message_slot(&self) -> bool {
+        self.max_messages == 0
+            || self
+                .message_count()
+                .load(Ordering::Acquire)
+                .wrapping_sub(self.consumed_count().load(Ordering::Acquire))
+                < self.max_messages
+    }
+
+    /// Reject records that could never fit, instead of waiting for space
+    /// that will not appear.
+    #[inline]
+    fn check_record_fits(&self, required_space: usize) -> Result<()> {
+        let capacity = self.capacity.load(Ordering::Acquire);
+        if required_space >= capacity {
+            return Err(anyhow!(
+                "Message record of {} bytes can never fit in a {}-byte shared memory ring; \
+                 increase --buffer-size",
+                required_space,
+                capacity
+            ));
+        }
+        Ok(())
+    }
+
     #[inline]
     fn data_ptr(&self) -> *mut u8 {
-        unsafe { (self as *const Self as *mut u8).add(Self::HEADER_SIZE) }
+        unsafe { (self as *const Self as *mut u8).add(self.data_offset) }
     }
 
     #[inline]
     fn available_write_space(&self) -> usize {
         let capacity = self.capacity.load(Ordering::Acquire);
-        let read_pos = self.read_pos.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
 
         if write_pos >= read_pos {
             capacity - (write_pos - read_pos) - 1
@@ -72,8 +438,8 @@ impl SharedMemoryRingBuffer {
 
     #[inline]
     fn available_read_data(&self) -> usize {
-        let read_pos = self.read_pos.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
 
         if write_pos >= read_pos {
             write_pos - read_pos
@@ -85,26 +451,25 @@ impl SharedMemoryRingBuffer {
     #[inline]
     fn write_data(&self, data: &[u8]) -> Result<()> {
         let data_len = data.len();
-        let required_space = data_len + 4; // 4 bytes for length prefix
+        let required_space = record_size(data_len, self.slot_align);
 
-        if self.available_write_space() < required_space {
+        if self.available_write_space() < required_space || !self.has_message_slot() {
             return Err(anyhow!("Not enough space in ring buffer"));
         }
 
         let capacity = self.capacity.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
         let data_ptr = self.data_ptr();
 
-        // Write length prefix (always fits in 4 bytes, handle wrap)
-        let len_bytes = (data_len as u32).to_le_bytes();
-        unsafe {
-            for (i, &byte) in len_bytes.iter().enumerate() {
-                *data_ptr.add((write_pos + i) % capacity) = byte;
-            }
-        }
+        // Write the header (length and stamp, handle wrap)
+        let stamp = RecordStamp::new(
+            self.message_count().load(Ordering::Acquire),
+            self.write_wraps.load(Ordering::Acquire),
+        );
+        unsafe { write_record_header(data_ptr, capacity, write_pos, data_len, stamp) };
 
         // Write data using bulk copy when possible
-        let data_start = (write_pos + 4) % capacity;
+        let data_start = (write_pos + RECORD_HEADER) % capacity;
         unsafe {
             if data_start + data_len <= capacity {
                 // Data fits contiguously - use fast memcpy
@@ -121,44 +486,51 @@ impl SharedMemoryRingBuffer {
             }
         }
 
-        self.write_pos
+        if write_pos + required_space >= capacity {
+            self.write_wraps.fetch_add(1, Ordering::Release);
+        }
+        self.write_pos()
             .store((write_pos + required_space) % capacity, Ordering::Release);
-        self.message_count.fetch_add(1, Ordering::Release);
+        self.message_count().fetch_add(1, Ordering::Release);
 
         Ok(())
     }
 
+    /// Read the next record, discarding it with a [`BadRecord`] error when
+    /// its stamp is not the one expected or changes during the copy.
     #[inline]
     fn read_data(&self) -> Result<Vec<u8>> {
-        if self.available_read_data() < 4 {
+        if self.available_read_data() < RECORD_HEADER {
             return Err(anyhow!("No data available"));
         }
 
         let capacity = self.capacity.load(Ordering::Acquire);
-        let read_pos = self.read_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
         let data_ptr = self.data_ptr();
 
-        // Read length prefix (handle potential wrap)
-        let mut len_bytes = [0u8; 4];
-        unsafe {
-            for (i, byte) in len_bytes.iter_mut().enumerate() {
-                *byte = *data_ptr.add((read_pos + i) % capacity);
-            }
+        // Read the header (handle potential wrap); the stamp is checked
+        // before the length is trusted
+        let expected = RecordStamp::new(
+            self.consumed_count().load(Ordering::Acquire),
+            self.read_wraps.load(Ordering::Acquire),
+        );
+        let (data_len, stamp) = unsafe { read_record_header(data_ptr, capacity, read_pos) };
+        if stamp != expected {
+            return Err(self.discard(BadRecord::Stale));
         }
-        let data_len = u32::from_le_bytes(len_bytes) as usize;
 
         // Basic validation to prevent reading garbage
         if data_len == 0 || data_len > capacity {
             return Err(anyhow!("Invalid message length: {}", data_len));
         }
 
-        if self.available_read_data() < data_len + 4 {
+        if self.available_read_data() < data_len + RECORD_HEADER {
             return Err(anyhow!("Incomplete message"));
         }
 
         // Read data using bulk copy when possible
         let mut data = vec![0u8; data_len];
-        let data_start = (read_pos + 4) % capacity;
+        let data_start = (read_pos + RECORD_HEADER) % capacity;
         unsafe {
             if data_start + data_len <= capacity {
                 // Data is contiguous - use fast memcpy
@@ -183,11 +555,36 @@ impl SharedMemoryRingBuffer {
             }
         }
 
-        self.read_pos
-            .store((read_pos + data_len + 4) % capacity, Ordering::Release);
+        std::sync::atomic::fence(Ordering::Acquire);
+        let (_, after) = unsafe { read_record_header(data_ptr, capacity, read_pos) };
+        if let Err(bad) = expected.check(stamp, after) {
+            return Err(self.discard(bad));
+        }
+
+        let record = record_size(data_len, self.slot_align);
+        if read_pos + record >= capacity {
+            self.read_wraps.fetch_add(1, Ordering::Release);
+        }
+        self.read_pos()
+            .store((read_pos + record) % capacity, Ordering::Release);
+        self.consumed_count().fetch_add(1, Ordering::Release);
 
         Ok(data)
     }
+
+    /// Skip every record written so far, so that reading resumes with the
+    /// writer's next one, and return `bad` as the read's error.
+    fn discard(&self, bad: BadRecord) -> anyhow::Error {
+        self.read_wraps
+            .store(self.write_wraps.load(Ordering::Acquire), Ordering::Release);
+        self.consumed_count().store(
+            self.message_count().load(Ordering::Acquire),
+            Ordering::Release,
+        );
+        self.read_pos()
+            .store(self.write_pos().load(Ordering::Acquire), Ordering::Release);
+        bad.into()
+    }
 }
 
 #[derive(Clone)]
@@ -199,21 +596,52 @@ struct SharedMemoryConnection {
     // Async notification primitives for efficient waiting (replaces sleep loops)
     notify_data_ready: Arc<Notify>, // Signals when data is available to read
     notify_space_ready: Arc<Notify>, // Signals when space is available to write
+    discarded: Arc<DiscardedRecords>,
 }
 
 unsafe impl Send for SharedMemoryConnection {}
 unsafe impl Sync for SharedMemoryConnection {}
 
+/// Depth probe of a ring, holding on to the transport's mapping.
+struct RingDepthProbe {
+    ring_buffer: *mut SharedMemoryRingBuffer,
+    _shmem: Arc<Mutex<Shmem>>,
+}
+
+// Safety: the probe only reads the ring's atomic counters and indices
+unsafe impl Send for RingDepthProbe {}
+
+impl QueueDepthProbe for RingDepthProbe {
+    fn depth(&mut self) -> Option<QueueDepth> {
+        let ring = unsafe { &*self.ring_buffer };
+        let written = ring.message_count().load(Ordering::Acquire);
+        let consumed = ring.consumed_count().load(Ordering::Acquire);
+        Some(QueueDepth {
+            messages: written.wrapping_sub(consumed),
+            bytes: Some(ring.available_read_data()),
+        })
+    }
+
+    fn capacity(&self) -> QueueCapacity {
+        let ring = unsafe { &*self.ring_buffer };
+        QueueCapacity {
+            messages: (ring.max_messages > 0).then_some(ring.max_messages),
+            bytes: Some(ring.capacity.load(Ordering::Acquire)),
+        }
+    }
+}
+
 #[allow(clippy::arc_with_non_send_sync)]
 impl SharedMemoryConnection {
     fn new(
         connection_id: ConnectionId,
         segment_name: String,
         buffer_size: usize,
+        layout: ShmRingLayout,
         role: ConnectionRole,
         create: bool,
     ) -> Result<Self> {
-        let total_size = SharedMemoryRingBuffer::HEADER_SIZE + buffer_size;
+        let total_size = SharedMemoryRingBuffer::segment_size(buffer_size, &layout);
 
         let shmem = if create {
             ShmemConf::new()
@@ -229,7 +657,15 @@ impl SharedMemoryConnection {
         if create {
             // Initialize ring buffer for new segment
             unsafe {
-                *ring_buffer_ptr = SharedMemoryRingBuffer::new(buffer_size);
+                SharedMemoryRingBuffer::initialize(ring_buffer_ptr, buffer_size, &layout);
+            }
+        } else {
+            let ring_buffer = unsafe { &*ring_buffer_ptr };
+            if !ring_header_ready(&ring_buffer.magic, &ring_buffer.version, &segment_name)? {
+                bail!(
+                    "Shared memory segment {} is not initialized yet",
+                    segment_name
+                );
             }
         }
 
@@ -240,6 +676,7 @@ impl SharedMemoryConnection {
             _shmem: Arc::new(Mutex::new(shmem)),
             notify_data_ready: Arc::new(Notify::new()),
             notify_space_ready: Arc::new(Notify::new()),
+            discarded: Arc::new(DiscardedRecords::default()),
         })
     }
 
@@ -299,6 +736,9 @@ impl SharedMemoryConnection {
         let mut message_bytes =
             bincode::serialize(&message).map_err(|e| IpcError::Generic(e.into()))?;
         let mut backpressure_detected = false;
+        ring_buffer
+            .check_record_fits(record_size(message_bytes.len(), ring_buffer.slot_align))
+            .map_err(IpcError::Generic)?;
 
         // Pre-compute timestamp offset for efficient in-place updates
         let ts_offset = Message::timestamp_offset();
@@ -341,6 +781,22 @@ impl SharedMemoryConnection {
         }
     }
 
+    /// Receives a message only if a complete record is already in the ring.
+    fn try_receive_message(&self) -> Result<Option<Message>> {
+        let ring_buffer = self.get_ring_buffer();
+        // Only this side consumes from the ring, so data seen here stays available
+        if ring_buffer.available_read_data() < RECORD_HEADER {
+            return Ok(None);
+        }
+        let data = match ring_buffer.read_data() {
+            Err(e) if self.discarded.count(&e) => return Ok(None),
+            read => read?,
+        };
+        let message = Message::from_bytes(&data)?;
+        self.notify_space_ready.notify_one();
+        Ok(Some(message))
+    }
+
     /// Receives a message from the shared-memory ring buffer.
     ///
     /// Uses a short poll-and-sleep loop (10µs) to yield to the
@@ -366,7 +822,9 @@ impl SharedMemoryConnection {
                     self.notify_space_ready.notify_one();
                     return Ok(message);
                 }
-                Err(_) => {
+                Err(e) => {
+                    // A discarded record is not delivered; wait for the next
+                    self.discarded.count(&e);
                     if start.elapsed() > timeout_duration {
                         return Err(anyhow!("Timeout receiving message"));
                     }
@@ -390,6 +848,7 @@ pub struct SharedMemoryTransport {
     next_connection_id: Arc<AtomicU64>,
     shared_memory_name: String,
     buffer_size: usize,
+    layout: ShmRingLayout,
     message_receiver: Option<mpsc::Receiver<(ConnectionId, Message)>>,
     has_warned_buffer_full: bool,
 }
@@ -414,6 +873,7 @@ impl SharedMemoryTransport {
             next_connection_id: Arc::new(AtomicU64::new(1)),
             shared_memory_name: String::new(),
             buffer_size: 0,
+            layout: ShmRingLayout::default(),
             message_receiver: None,
             has_warned_buffer_full: false,
         }
@@ -443,6 +903,7 @@ impl SharedMemoryTransport {
                             0, // Connection ID 0 for single connection mode
                             self.shared_memory_name.clone(),
                             self.buffer_size,
+                            self.layout,
                             ConnectionRole::Client,
                             false, // Open existing segment
                         ) {
@@ -530,6 +991,7 @@ impl IpcTransport for SharedMemoryTransport {
 
         self.shared_memory_name = config.shared_memory_name.clone();
         self.buffer_size = config.buffer_size;
+        self.layout = ShmRingLayout::from_config(config);
         self.role = Some(ConnectionRole::Server);
 
         // Create the shared memory segment immediately so clients can find it
@@ -537,6 +999,7 @@ impl IpcTransport for SharedMemoryTransport {
             0, // Connection ID 0 for single connection mode
             config.shared_memory_name.clone(),
             config.buffer_size,
+            self.layout,
             ConnectionRole::Server,
             true, // Create the segment
         )?;
@@ -558,6 +1021,7 @@ impl IpcTransport for SharedMemoryTransport {
 
         self.shared_memory_name = config.shared_memory_name.clone();
         self.buffer_size = config.buffer_size;
+        self.layout = ShmRingLayout::from_config(config);
         self.role = Some(ConnectionRole::Client);
         self.state = TransportState::Connected; // Mark as ready immediately
 
@@ -627,6 +1091,15 @@ impl IpcTransport for SharedMemoryTransport {
         }
     }
 
+    async fn try_receive(&mut self) -> Result<Option<Message>> {
+        match self.single_connection {
+            Some(ref connection) if self.state == TransportState::Connected => {
+                connection.try_receive_message()
+            }
+            _ => Ok(None),
+        }
+    }
+
     async fn close(&mut self) -> Result<()> {
         debug!("Closing Shared Memory transport");
 
@@ -679,7 +1152,35 @@ impl IpcTransport for SharedMemoryTransport {
     }
 
     fn supports_bidirectional(&self) -> bool {
-        true
+        // One ring, written by the client and read by the server
+        false
+    }
+
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn queue_depth_probe(&self) -> Option<Box<dyn QueueDepthProbe>> {
+        let (ring_buffer, shmem) = match &self.single_connection {
+            Some(connection) => (connection.ring_buffer, Arc::clone(&connection._shmem)),
+            // Clients attach on first send; map the server's segment now
+            None => {
+                let shmem = ShmemConf::new()
+                    .os_id(&self.shared_memory_name)
+                    .open()
+                    .ok()?;
+                let ring_buffer = shmem.as_ptr() as *mut SharedMemoryRingBuffer;
+                (ring_buffer, Arc::new(Mutex::new(shmem)))
+            }
+        };
+        Some(Box::new(RingDepthProbe {
+            ring_buffer,
+            _shmem: shmem,
+        }))
+    }
+
+    fn stats(&self) -> TransportStats {
+        self.single_connection
+            .as_ref()
+            .map(|connection| connection.discarded.stats())
+            .unwrap_or_default()
     }
 
     fn max_message_size(&self) -> usize {
@@ -704,6 +1205,7 @@ impl IpcTransport for SharedMemoryTransport {
 
         self.shared_memory_name = config.shared_memory_name.clone();
         self.buffer_size = config.buffer_size;
+        self.layout = ShmRingLayout::from_config(config);
         self.role = Some(ConnectionRole::Server);
         self.state = TransportState::Initializing;
 
@@ -718,51 +1220,59 @@ impl IpcTransport for SharedMemoryTransport {
         let _next_connection_id = self.next_connection_id.clone();
         let base_name = config.shared_memory_name.clone();
         let buffer_size = config.buffer_size;
+        let layout = self.layout;
         let max_connections = config.max_connections; // Clone the value to avoid borrowing issues
 
         // Start monitoring task for new connections
-        tokio::spawn(async move {
-            let mut known_connections = std::collections::HashSet::new();
-
-            loop {
-                // Check for new shared memory segments matching our pattern
-                // This is a simplified approach - in a real implementation, you might
-                // use inotify or similar mechanisms to detect new segments
-
-                for i in 1..=max_connections {
-                    let connection_id = i as ConnectionId;
-                    let segment_name = format!("{}_{}", base_name, connection_id);
-
-                    if !known_connections.contains(&connection_id) {
-                        // Try to open the segment (client creates it)
-                        if let Ok(connection) = SharedMemoryConnection::new(
-                            connection_id,
-                            segment_name,
-                            buffer_size,
-                            ConnectionRole::Server,
-                            false, // Don't create, just open
-                        ) {
-                            debug!("Detected new shared memory connection {}", connection_id);
-                            known_connections.insert(connection_id);
-
-                            // Handle this connection
-                            let handler_sender = message_sender.clone();
-                            let handler_connections = connections.clone();
-
-                            tokio::spawn(Self::handle_connection(
+        tokio::spawn(
+            async move {
+                let mut known_connections = std::collections::HashSet::new();
+
+                loop {
+                    // Check for new shared memory segments matching our pattern
+                    // This is a simplified approach - in a real implementation, you might
+                    // use inotify or similar mechanisms to detect new segments
+
+                    for i in 1..=max_connections {
+                        let connection_id = i as ConnectionId;
+                        let segment_name = format!("{}_{}", base_name, connection_id);
+
+                        if !known_connections.contains(&connection_id) {
+                            // Try to open the segment (client creates it)
+                            if let Ok(connection) = SharedMemoryConnection::new(
                                 connection_id,
-                                connection,
-                                handler_sender,
-                                handler_connections,
-                            ));
+                                segment_name,
+                                buffer_size,
+                                layout,
+                                ConnectionRole::Server,
+                                false, // Don't create, just open
+                            ) {
+                                debug!("Detected new shared memory connection {}", connection_id);
+                                known_connections.insert(connection_id);
+
+                                // Handle this connection
+                                let handler_sender = message_sender.clone();
+                                let handler_connections = connections.clone();
+
+                                tokio::spawn(
+                                    Self::handle_connection(
+                                        connection_id,
+                                        connection,
+                                        handler_sender,
+                                        handler_connections,
+                                    )
+                                    .in_current_span(),
+                                );
+                            }
                         }
                     }
-                }
 
-                // Justification: Polling interval to check for new client connections in multi-server mode.
-                sleep(Duration::from_millis(100)).await;
+                    // Justification: Polling interval to check for new client connections in multi-server mode.
+                    sleep(Duration::from_millis(100)).await;
+                }
             }
-        });
+            .in_current_span(),
+        );
 
         self.state = TransportState::Connected;
         Ok(message_receiver)
@@ -950,41 +1460,35 @@ mod tests {
         // Allocate a buffer with a small capacity so we can
         // force the write position near the end.
         let capacity: usize = 64;
-        let total_size = SharedMemoryRingBuffer::HEADER_SIZE + capacity;
-        let layout = std::alloc::Layout::from_size_align(total_size, 8).unwrap();
+        let ring_layout = ShmRingLayout::default();
+        let total_size = SharedMemoryRingBuffer::segment_size(capacity, &ring_layout);
+        let layout = std::alloc::Layout::from_size_align(total_size, 64).unwrap();
         let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
         assert!(!ptr.is_null());
 
-        let rb = unsafe { &mut *(ptr as *mut SharedMemoryRingBuffer) };
-        rb.capacity.store(capacity, Ordering::Release);
-        rb.read_pos.store(0, Ordering::Release);
-        rb.write_pos.store(0, Ordering::Release);
-        rb.shutdown.store(false, Ordering::Release);
-        rb.message_count.store(0, Ordering::Release);
+        unsafe {
+            SharedMemoryRingBuffer::initialize(ptr as *mut _, capacity, &ring_layout);
+        }
+        let rb = unsafe { &*(ptr as *const SharedMemoryRingBuffer) };
 
         // Write a small message to advance write_pos partway
-        // through the buffer (payload=10 bytes + 4 len prefix
-        // = 14 bytes consumed).
+        // through the buffer (payload=10 bytes + 12-byte header
+        // = 22 bytes consumed).
         let payload_a = vec![0xAAu8; 10];
         rb.write_data(&payload_a).unwrap();
         let read_a = rb.read_data().unwrap();
         assert_eq!(read_a, payload_a);
 
-        // Now write_pos and read_pos are both at 14. Advance
-        // write_pos to near the end by writing and reading a
-        // series of small messages.
-        // Each 10-byte payload consumes 14 bytes. After 3 more
-        // write+read cycles: pos = 14 + 3*14 = 56.
-        for _ in 0..3 {
-            let p = vec![0xBBu8; 10];
-            rb.write_data(&p).unwrap();
-            let r = rb.read_data().unwrap();
-            assert_eq!(r, p);
-        }
-        // write_pos = read_pos = 56. Capacity = 64.
-        // Next write of 10 bytes needs 14 bytes total.
-        // data_start = (56 + 4) % 64 = 60.
-        // data_start(60) + data_len(10) = 70 > 64 → wraps!
+        // Now write_pos and read_pos are both at 22. One more
+        // write+read cycle advances them to 44.
+        let p = vec![0xBBu8; 10];
+        rb.write_data(&p).unwrap();
+        assert_eq!(rb.read_data().unwrap(), p);
+
+        // write_pos = read_pos = 44. Capacity = 64.
+        // Next write of 10 bytes needs 22 bytes total.
+        // data_start = (44 + 12) % 64 = 56.
+        // data_start(56) + data_len(10) = 66 > 64 → wraps!
         let payload_wrap = vec![0xCCu8; 10];
         rb.write_data(&payload_wrap).unwrap();
         let read_wrap = rb.read_data().unwrap();
@@ -995,4 +1499,195 @@ mod tests {
 
         unsafe { std::alloc::dealloc(ptr, layout) };
     }
+
+    #[test]
+    fn test_record_stamp_check() {
+        let expected = RecordStamp::new(7, 1);
+        assert_eq!(expected.check(expected, expected), Ok(()));
+        let earlier = RecordStamp::new(7, 0);
+        assert_eq!(expected.check(earlier, earlier), Err(BadRecord::Stale));
+        let later = RecordStamp::new(8, 1);
+        assert_eq!(expected.check(expected, later), Err(BadRecord::Torn));
+        // Counters past 2^32 wrap with the stamp
+        assert_eq!(RecordStamp::new(1 << 32, 0), RecordStamp::new(0, 0));
+    }
+
+    /// An opening side waits for the magic, then accepts only its own
+    /// protocol version
+    #[test]
+    fn test_ring_header_ready() {
+        let capacity: usize = 64;
+        let ring_layout = ShmRingLayout::default();
+        let total_size = SharedMemoryRingBuffer::segment_size(capacity, &ring_layout);
+        let layout = std::alloc::Layout::from_size_align(total_size, 64).unwrap();
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        let rb = ptr as *mut SharedMemoryRingBuffer;
+        let ready = |rb: *const SharedMemoryRingBuffer| unsafe {
+            ring_header_ready(&(*rb).magic, &(*rb).version, "ring")
+        };
+
+        assert!(!ready(rb).unwrap());
+        unsafe { SharedMemoryRingBuffer::initialize(rb, capacity, &ring_layout) };
+        assert!(ready(rb).unwrap());
+
+        unsafe { (*rb).version = u32::from(PROTOCOL_VERSION) - 1 };
+        assert_eq!(
+            ready(rb).unwrap_err().to_string(),
+            format!(
+                "Shared memory segment ring was laid out for protocol version {} but this \
+                 build speaks version {}; run the same ipc-benchmark release on both ends",
+                PROTOCOL_VERSION - 1,
+                PROTOCOL_VERSION
+            )
+        );
+        unsafe { (*rb).magic.store(0xDEAD_BEEF, Ordering::Release) };
+        assert!(ready(rb)
+            .unwrap_err()
+            .to_string()
+            .contains("does not hold an ipc-benchmark ring buffer"));
+
+        unsafe { std::alloc::dealloc(ptr, layout) };
+    }
+
+    /// A record whose stamp is not the one expected is discarded with
+    /// everything queued behind it, and reading carries on with the next
+    /// record written, across wraps of the ring.
+    #[test]
+    fn test_ring_buffer_discards_stale_record() {
+        let capacity: usize = 64;
+        let ring_layout = ShmRingLayout::default();
+        let total_size = SharedMemoryRingBuffer::segment_size(capacity, &ring_layout);
+        let layout = std::alloc::Layout::from_size_align(total_size, 64).unwrap();
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        unsafe {
+            SharedMemoryRingBuffer::initialize(ptr as *mut _, capacity, &ring_layout);
+        }
+        let rb = unsafe { &*(ptr as *const SharedMemoryRingBuffer) };
+        let discarded = DiscardedRecords::default();
+
+        rb.write_data(&[1; 10]).unwrap();
+        rb.write_data(&[2; 10]).unwrap();
+        // Mark the first record as written on an earlier pass
+        let read_pos = rb.read_pos().load(Ordering::Acquire);
+        unsafe { *rb.data_ptr().add(read_pos + 8) ^= 1 };
+
+        let error = rb.read_data().unwrap_err();
+        assert!(discarded.count(&error));
+        assert_eq!(rb.available_read_data(), 0);
+        assert_eq!(discarded.stats()["stale_records"], 1);
+
+        for i in 0..10u8 {
+            rb.write_data(&[i; 10]).unwrap();
+            assert_eq!(rb.read_data().unwrap(), [i; 10]);
+        }
+        assert!(rb.read_wraps.load(Ordering::Acquire) > 0);
+        assert!(!discarded.count(&anyhow!("No data available")));
+
+        unsafe { std::alloc::dealloc(ptr, layout) };
+    }
+
+    /// With padding the indices must sit on different cache lines and
+    /// every record must start on a slot boundary; without padding the
+    /// indices share a line.
+    #[test]
+    fn test_ring_layout_offsets_and_alignment() {
+        let header = SharedMemoryRingBuffer::HEADER_SIZE;
+
+        let packed = ShmRingLayout::default();
+        let (read, write, data) = packed.offsets(header);
+        assert_eq!(write - read, 2 * std::mem::size_of::<usize>());
+        assert_eq!(data % 64, 0);
+
+        let padded = ShmRingLayout {
+            slot_align: 128,
+            pad_indices: true,
+            ..Default::default()
+        };
+        let (read, write, data) = padded.offsets(header);
+        assert_eq!(read % 128, 0);
+        assert_eq!(write - read, 128);
+        assert_eq!(data % 128, 0);
+        assert_eq!(padded.capacity(1000), 1024);
+
+        let capacity = 1024;
+        let total_size = SharedMemoryRingBuffer::segment_size(capacity, &padded);
+        let layout = std::alloc::Layout::from_size_align(total_size, 128).unwrap();
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        unsafe {
+            SharedMemoryRingBuffer::initialize(ptr as *mut _, capacity, &padded);
+        }
+        let rb = unsafe { &*(ptr as *const SharedMemoryRingBuffer) };
+
+        // Records smaller than a slot still advance by a whole slot, across
+        // several wraps of the ring.
+        for i in 0..40u8 {
+            let payload = vec![i; 10 + i as usize];
+            rb.write_data(&payload).unwrap();
+            assert_eq!(rb.write_pos().load(Ordering::Acquire) % 128, 0);
+            assert_eq!(rb.read_data().unwrap(), payload);
+        }
+        assert_eq!(rb.message_count().load(Ordering::Acquire), 40);
+
+        unsafe { std::alloc::dealloc(ptr, layout) };
+    }
+
+    /// A message-count limit causes backpressure before the byte limit, and
+    /// records that can never fit are rejected up front.
+    #[test]
+    fn test_ring_message_capacity_and_oversized_records() {
+        let ring_layout = ShmRingLayout {
+            max_messages: 3,
+            ..Default::default()
+        };
+        let capacity = 4096;
+        let total_size = SharedMemoryRingBuffer::segment_size(capacity, &ring_layout);
+        let layout = std::alloc::Layout::from_size_align(total_size, 64).unwrap();
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        unsafe {
+            SharedMemoryRingBuffer::initialize(ptr as *mut _, capacity, &ring_layout);
+        }
+        let rb = unsafe { &*(ptr as *const SharedMemoryRingBuffer) };
+
+        // Plenty of bytes left, but only three records may be unread.
+        for i in 0..3u8 {
+            rb.write_data(&[i; 16]).unwrap();
+        }
+        assert!(rb.available_write_space() > 1000);
+        assert!(rb.write_data(&[9; 16]).is_err());
+
+        // Consuming one record frees one message slot.
+        assert_eq!(rb.read_data().unwrap(), vec![0u8; 16]);
+        rb.write_data(&[3; 16]).unwrap();
+        assert!(rb.write_data(&[4; 16]).is_err());
+
+        assert!(rb.check_record_fits(record_size(100, 1)).is_ok());
+        assert!(rb.check_record_fits(record_size(capacity, 1)).is_err());
+
+        unsafe { std::alloc::dealloc(ptr, layout) };
+    }
+
+    #[test]
+    fn test_ring_bytes_for_messages_holds_exactly_n() {
+        for slot_align in [1, 64, 128] {
+            let ring_layout = ShmRingLayout {
+                slot_align,
+                ..Default::default()
+            };
+            let capacity = ring_bytes_for_messages(5, 100, slot_align);
+            let total_size = SharedMemoryRingBuffer::segment_size(capacity, &ring_layout);
+            let layout = std::alloc::Layout::from_size_align(total_size, 128).unwrap();
+            let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+            unsafe {
+                SharedMemoryRingBuffer::initialize(ptr as *mut _, capacity, &ring_layout);
+            }
+            let rb = unsafe { &*(ptr as *const SharedMemoryRingBuffer) };
+
+            for _ in 0..5 {
+                rb.write_data(&[7; 100]).unwrap();
+            }
+            assert!(rb.write_data(&[7; 100]).is_err());
+
+            unsafe { std::alloc::dealloc(ptr, layout) };
+        }
+    }
 }
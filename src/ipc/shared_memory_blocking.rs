@@ -13,15 +13,22 @@
 //! - `create()` blocks during shared memory segment creation
 //! - `open()` blocks until segment is available
 //! - `send()` blocks until space is available in ring buffer (using pthread
-//!   condition variables on Unix)
+//!   condition variables on Unix, named event objects on Windows)
 //! - `recv()` blocks until data is available (using pthread condition variables
-//!   on Unix)
+//!   on Unix, named event objects on Windows)
+//!
+//! On Linux, `TransportConfig::shm_wait` can replace the condition variables
+//! with POSIX named semaphores (see `shm_semaphore`).
 //!
 //! # Ring Buffer Protocol
 //!
 //! Uses a circular ring buffer in shared memory with atomic operations for
-//! coordination. Messages are length-prefixed (4-byte u32, little-endian) +
-//! bincode serialized data.
+//! coordination. Each record is a 12-byte header (u32 length, sequence and
+//! generation, little-endian) followed by bincode serialized data, or with
+//! `TransportConfig::shm_zero_copy` by a [`fixed_layout`] record. The reader
+//! checks the sequence and generation against its own counts and discards a
+//! record that does not match, or whose header changes while it is copied,
+//! counting it in `stale_records` or `torn_records` instead of delivering it.
 //!
 //! # Example
 //!
@@ -46,11 +53,18 @@
 //! # }
 //! ```
 
-use crate::ipc::{BlockingTransport, Message, TransportConfig};
+use crate::ipc::handshake::PROTOCOL_VERSION;
+use crate::ipc::shared_memory::{
+    read_record_header, record_size, ring_header_ready, write_record_header, BadRecord,
+    DiscardedRecords, RecordStamp, ShmRingLayout, RECORD_HEADER, RING_MAGIC,
+};
+use crate::ipc::{fixed_layout, BlockingTransport, Message, TransportConfig, TransportStats};
+use crate::queue_depth::{QueueCapacity, QueueDepth, QueueDepthProbe};
 use anyhow::{anyhow, Context, Result};
 use parking_lot::Mutex;
 use shared_memory::{Shmem, ShmemConf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -63,6 +77,104 @@ use nix::libc;
 #[cfg(unix)]
 use libc::{pthread_cond_t, pthread_mutex_t};
 
+#[cfg(windows)]
+use crate::ipc::shm_event::ShmDoorbells;
+#[cfg(target_os = "linux")]
+use crate::ipc::shm_semaphore::ShmSemaphores;
+
+#[cfg(target_os = "linux")]
+use crate::ipc::shm_hugetlbfs::HugetlbfsSegment;
+
+/// Copy `bytes` into the ring's data area at `start`, continuing at its
+/// beginning if they run past the end, and return the position after them.
+///
+/// # Safety
+/// `data_ptr` must point to `capacity` writable bytes and `bytes` must fit.
+#[inline]
+unsafe fn copy_into_ring(data_ptr: *mut u8, capacity: usize, start: usize, bytes: &[u8]) -> usize {
+    let first_part = bytes.len().min(capacity - start);
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), data_ptr.add(start), first_part);
+    std::ptr::copy_nonoverlapping(
+        bytes.as_ptr().add(first_part),
+        data_ptr,
+        bytes.len() - first_part,
+    );
+    (start + bytes.len()) % capacity
+}
+
+/// A record read from the ring, split after its first `head_len` bytes:
+/// the header of a fixed-layout record lands in `head` and the payload in
+/// `body`, so neither is moved again. Other records have no head.
+struct RingRecord {
+    head: [u8; fixed_layout::HEADER_SIZE],
+    body: Vec<u8>,
+}
+
+impl RingRecord {
+    /// Copy the `data_len`-byte record at `start` out of the ring, its
+    /// first `head_len` bytes into the head.
+    ///
+    /// # Safety
+    /// `data_ptr` must point to `capacity` readable bytes, `data_len` must
+    /// not exceed `capacity` and `head_len` must not exceed `data_len` or
+    /// the head.
+    unsafe fn copy_out(
+        data_ptr: *const u8,
+        capacity: usize,
+        start: usize,
+        data_len: usize,
+        head_len: usize,
+    ) -> Self {
+        let mut head = [0u8; fixed_layout::HEADER_SIZE];
+        let start = copy_from_ring(data_ptr, capacity, start, &mut head[..head_len]);
+        // PERF: with_capacity instead of vec![0u8; len] avoids a redundant
+        // memset, since the copy overwrites every byte before set_len()
+        let body_len = data_len - head_len;
+        let mut body = Vec::with_capacity(body_len);
+        copy_from_ring(
+            data_ptr,
+            capacity,
+            start,
+            std::slice::from_raw_parts_mut(body.as_mut_ptr(), body_len),
+        );
+        body.set_len(body_len);
+        Self { head, body }
+    }
+}
+
+/// Copy `out.len()` bytes out of the ring's data area from `start`,
+/// continuing at its beginning if they run past the end, and return the
+/// position after them.
+///
+/// # Safety
+/// `data_ptr` must point to `capacity` readable bytes and `out` must fit.
+#[inline]
+unsafe fn copy_from_ring(
+    data_ptr: *const u8,
+    capacity: usize,
+    start: usize,
+    out: &mut [u8],
+) -> usize {
+    let first_part = out.len().min(capacity - start);
+    std::ptr::copy_nonoverlapping(data_ptr.add(start), out.as_mut_ptr(), first_part);
+    std::ptr::copy_nonoverlapping(
+        data_ptr,
+        out.as_mut_ptr().add(first_part),
+        out.len() - first_part,
+    );
+    (start + out.len()) % capacity
+}
+
+/// Error for a `data_len`-byte record too short for its `head_len`-byte
+/// fixed-layout header.
+fn short_record(data_len: usize, head_len: usize) -> anyhow::Error {
+    anyhow!(
+        "Fixed-layout record of {} bytes is shorter than its {}-byte header",
+        data_len,
+        head_len
+    )
+}
+
 /// Shared memory ring buffer structure.
 ///
 /// This structure is placed at the start of the shared memory segment and
@@ -72,19 +184,32 @@ use libc::{pthread_cond_t, pthread_mutex_t};
 /// synchronization.
 #[repr(C)]
 struct SharedMemoryRingBuffer {
+    // RING_MAGIC once initialized, and the layout's protocol version; the
+    // client checks both on attach (see ring_header_ready)
+    magic: AtomicU32,
+    version: u32,
+
     // Ring buffer metadata
     capacity: AtomicUsize,
-    read_pos: AtomicUsize,
-    write_pos: AtomicUsize,
+
+    // Layout recorded by the creator (see ShmRingLayout); read-only afterwards.
+    // The read/write indices live at these offsets rather than in the struct
+    // so they can be placed on shared or separate cache lines at runtime.
+    slot_align: usize,
+    max_messages: usize,
+    read_index_offset: usize,
+    write_index_offset: usize,
+    data_offset: usize,
+
+    // Times each index has wrapped, for the record stamps (see RecordStamp)
+    write_wraps: AtomicUsize,
+    read_wraps: AtomicUsize,
 
     // Synchronization flags
     server_ready: AtomicBool,
     client_ready: AtomicBool,
     shutdown: AtomicBool,
 
-    // Message count for coordination
-    message_count: AtomicUsize,
-
     // Process-shared synchronization primitives
     #[cfg(unix)]
     mutex: pthread_mutex_t,
@@ -93,14 +218,37 @@ struct SharedMemoryRingBuffer {
     #[cfg(unix)]
     space_ready: pthread_cond_t, // Signals when space is available to write
 
-                                 // Data follows after this header
+                                 // Indices and data follow at the offsets above
 }
 
 impl SharedMemoryRingBuffer {
     const HEADER_SIZE: usize = std::mem::size_of::<Self>();
 
+    /// Total segment size needed for `capacity` data bytes with `layout`.
+    fn segment_size(capacity: usize, layout: &ShmRingLayout) -> usize {
+        layout.offsets(Self::HEADER_SIZE).2 + layout.capacity(capacity)
+    }
+
+    /// Initialize the header and indices of a freshly created segment.
+    ///
+    /// # Safety
+    /// `ptr` must point to at least `segment_size(capacity, layout)` writable bytes.
+    unsafe fn initialize(ptr: *mut Self, capacity: usize, layout: &ShmRingLayout) {
+        std::ptr::write(ptr, Self::new(capacity, layout));
+        let rb = &*ptr;
+        rb.read_pos().store(0, Ordering::Release);
+        rb.write_pos().store(0, Ordering::Release);
+        rb.message_count().store(0, Ordering::Release);
+        rb.consumed_count().store(0, Ordering::Release);
+        rb.magic.store(RING_MAGIC, Ordering::Release);
+    }
+
     /// Create a new ring buffer header with process-shared synchronization
-    fn new(capacity: usize) -> Self {
+    fn new(capacity: usize, layout: &ShmRingLayout) -> Self {
+        let (read_index_offset, write_index_offset, data_offset) =
+            layout.offsets(Self::HEADER_SIZE);
+        let capacity = layout.capacity(capacity);
+
         #[cfg(unix)]
         unsafe {
             use std::mem::MaybeUninit;
@@ -131,13 +279,19 @@ impl SharedMemoryRingBuffer {
             libc::pthread_condattr_destroy(cond_attr.as_mut_ptr());
 
             Self {
+                magic: AtomicU32::new(0),
+                version: u32::from(PROTOCOL_VERSION),
                 capacity: AtomicUsize::new(capacity),
-                read_pos: AtomicUsize::new(0),
-                write_pos: AtomicUsize::new(0),
+                slot_align: layout.slot_align,
+                max_messages: layout.max_messages,
+                read_index_offset,
+                write_index_offset,
+                data_offset,
+                write_wraps: AtomicUsize::new(0),
+                read_wraps: AtomicUsize::new(0),
                 server_ready: AtomicBool::new(false),
                 client_ready: AtomicBool::new(false),
                 shutdown: AtomicBool::new(false),
-                message_count: AtomicUsize::new(0),
                 mutex: mutex.assume_init(),
                 data_ready: data_ready.assume_init(),
                 space_ready: space_ready.assume_init(),
@@ -147,29 +301,148 @@ impl SharedMemoryRingBuffer {
         #[cfg(not(unix))]
         {
             Self {
+                magic: AtomicU32::new(0),
+                version: u32::from(PROTOCOL_VERSION),
                 capacity: AtomicUsize::new(capacity),
-                read_pos: AtomicUsize::new(0),
-                write_pos: AtomicUsize::new(0),
+                slot_align: layout.slot_align,
+                max_messages: layout.max_messages,
+                read_index_offset,
+                write_index_offset,
+                data_offset,
+                write_wraps: AtomicUsize::new(0),
+                read_wraps: AtomicUsize::new(0),
                 server_ready: AtomicBool::new(false),
                 client_ready: AtomicBool::new(false),
                 shutdown: AtomicBool::new(false),
-                message_count: AtomicUsize::new(0),
             }
         }
     }
 
-    /// Get pointer to the data area (after the header)
+    #[inline]
+    fn index_at(&self, offset: usize) -> &AtomicUsize {
+        unsafe { &*((self as *const Self as *const u8).add(offset) as *const AtomicUsize) }
+    }
+
+    #[inline]
+    fn read_pos(&self) -> &AtomicUsize {
+        self.index_at(self.read_index_offset)
+    }
+
+    #[inline]
+    fn write_pos(&self) -> &AtomicUsize {
+        self.index_at(self.write_index_offset)
+    }
+
+    /// Writer-owned message counter, kept on the write index's cache line
+    #[inline]
+    fn message_count(&self) -> &AtomicUsize {
+        self.index_at(self.write_index_offset + std::mem::size_of::<usize>())
+    }
+
+    /// Reader-owned message counter, kept on the read index's cache line
+    #[inline]
+    fn consumed_count(&self) -> &AtomicUsize {
+        self.index_at(self.read_index_offset + std::mem::size_of::<usize>())
+    }
+
+    /// Whether the message-count limit leaves room for another record
+    #[inline]
+    fn has_message_slot(&self) -> bool {
+        self.max_messages == 0
+            || self
+                .message_count()
+                .load(Ordering::Acquire)
+                .wrapping_sub(self.consumed_count().load(Ordering::Acquire))
+                < self.max_messages
+    }
+
+    /// Whether a record of `required_space` bytes can be written right now
+    #[inline]
+    fn can_write(&self, required_space: usize) -> bool {
+        self.available_write_space() >= required_space && self.has_message_slot()
+    }
+
+    /// Reject records that could never fit, instead of waiting for space
+    /// that will not appear.
+    fn check_record_fits(&self, required_space: usize) -> Result<()> {
+        let capacity = self.capacity.load(Ordering::Acquire);
+        if required_space >= capacity {
+            return Err(anyhow!(
+                "Message record of {} bytes can never fit in a {}-byte shared memory ring; \
+                 increase --buffer-size",
+                required_space,
+                capacity
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get pointer to the data area (after the header and indices)
     #[inline]
     fn data_ptr(&self) -> *mut u8 {
-        unsafe { (self as *const Self as *mut u8).add(Self::HEADER_SIZE) }
+        unsafe { (self as *const Self as *mut u8).add(self.data_offset) }
+    }
+
+    /// Stamp for the next record written
+    #[inline]
+    fn write_stamp(&self) -> RecordStamp {
+        RecordStamp::new(
+            self.message_count().load(Ordering::Acquire),
+            self.write_wraps.load(Ordering::Acquire),
+        )
+    }
+
+    /// Stamp the reader expects on the next record
+    #[inline]
+    fn read_stamp(&self) -> RecordStamp {
+        RecordStamp::new(
+            self.consumed_count().load(Ordering::Acquire),
+            self.read_wraps.load(Ordering::Acquire),
+        )
+    }
+
+    /// Publish the `required_space`-byte record written at `write_pos`.
+    #[inline]
+    fn commit_write(&self, write_pos: usize, required_space: usize, capacity: usize) {
+        if write_pos + required_space >= capacity {
+            self.write_wraps.fetch_add(1, Ordering::Release);
+        }
+        self.write_pos()
+            .store((write_pos + required_space) % capacity, Ordering::Release);
+        self.message_count().fetch_add(1, Ordering::Release);
+    }
+
+    /// Release the `record`-byte record read at `read_pos`.
+    #[inline]
+    fn commit_read(&self, read_pos: usize, record: usize, capacity: usize) {
+        if read_pos + record >= capacity {
+            self.read_wraps.fetch_add(1, Ordering::Release);
+        }
+        self.read_pos()
+            .store((read_pos + record) % capacity, Ordering::Release);
+        self.consumed_count().fetch_add(1, Ordering::Release);
+    }
+
+    /// Skip every record written so far, so that reading resumes with the
+    /// writer's next one, and return `bad` as the read's error.
+    fn discard(&self, bad: BadRecord) -> anyhow::Error {
+        self.read_wraps
+            .store(self.write_wraps.load(Ordering::Acquire), Ordering::Release);
+        self.consumed_count().store(
+            self.message_count().load(Ordering::Acquire),
+            Ordering::Release,
+        );
+        self.read_pos()
+            .store(self.write_pos().load(Ordering::Acquire), Ordering::Release);
+        bad.into()
     }
 
     /// Calculate available space for writing
     #[inline]
     fn available_write_space(&self) -> usize {
         let capacity = self.capacity.load(Ordering::Acquire);
-        let read_pos = self.read_pos.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
 
         if write_pos >= read_pos {
             capacity - (write_pos - read_pos) - 1
@@ -181,8 +454,8 @@ impl SharedMemoryRingBuffer {
     /// Calculate available data for reading
     #[inline]
     fn available_read_data(&self) -> usize {
-        let read_pos = self.read_pos.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
 
         if write_pos >= read_pos {
             write_pos - read_pos
@@ -193,88 +466,85 @@ impl SharedMemoryRingBuffer {
 
     /// Write data to the ring buffer (non-blocking, returns error if no space)
     ///
-    /// Note: This method is currently unused as we've switched to the direct
-    /// memory implementation, but kept for potential future use or testing.
-    #[allow(dead_code)]
-    fn write_data(&self, data: &[u8]) -> Result<()> {
-        let data_len = data.len();
-        let required_space = data_len + 4; // 4 bytes for length prefix
-
-        if self.available_write_space() < required_space {
+    /// Used by the semaphore and Windows doorbell wrappers below, which do
+    /// the waiting; the condition variable versions lock around their own copy.
+    #[cfg_attr(all(unix, not(target_os = "linux")), allow(dead_code))]
+    fn write_data(&self, data: &[u8], tail: &[u8]) -> Result<()> {
+        let data_len = data.len() + tail.len();
+        let required_space = record_size(data_len, self.slot_align);
+        self.check_record_fits(required_space)?;
+
+        if !self.can_write(required_space) {
             return Err(anyhow!("Not enough space in ring buffer"));
         }
 
         let capacity = self.capacity.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
         let data_ptr = self.data_ptr();
 
-        // Write length prefix (little-endian)
-        let len_bytes = (data_len as u32).to_le_bytes();
-        for (i, &byte) in len_bytes.iter().enumerate() {
-            unsafe {
-                *data_ptr.add((write_pos + i) % capacity) = byte;
-            }
-        }
+        // Write the header: length prefix and stamp (little-endian)
+        unsafe { write_record_header(data_ptr, capacity, write_pos, data_len, self.write_stamp()) };
 
-        // Write data
-        for (i, &byte) in data.iter().enumerate() {
-            unsafe {
-                *data_ptr.add((write_pos + 4 + i) % capacity) = byte;
-            }
+        // Bulk copy, split where the record wraps (see write_data_blocking)
+        let data_start = (write_pos + RECORD_HEADER) % capacity;
+        unsafe {
+            let tail_start = copy_into_ring(data_ptr, capacity, data_start, data);
+            copy_into_ring(data_ptr, capacity, tail_start, tail);
         }
 
-        self.write_pos
-            .store((write_pos + required_space) % capacity, Ordering::Release);
-        self.message_count.fetch_add(1, Ordering::Release);
+        self.commit_write(write_pos, required_space, capacity);
 
-        // On non-Unix there are no condvars; readers poll with
-        // yield + sleep, so no signal is needed here.
+        // On Windows the caller rings the reader's doorbell.
 
         Ok(())
     }
 
     /// Read data from the ring buffer (non-blocking, returns error if no data)
     ///
-    /// Note: This method is currently unused as we've switched to the direct
-    /// memory implementation, but kept for potential future use or testing.
-    #[allow(dead_code)]
-    fn read_data(&self) -> Result<Vec<u8>> {
-        if self.available_read_data() < 4 {
+    /// Used by the semaphore and Windows doorbell wrappers below, which do
+    /// the waiting; the condition variable versions lock around their own copy.
+    #[cfg_attr(all(unix, not(target_os = "linux")), allow(dead_code))]
+    fn read_data(&self, head_len: usize) -> Result<RingRecord> {
+        if self.available_read_data() < RECORD_HEADER {
             return Err(anyhow!("No data available"));
         }
 
         let capacity = self.capacity.load(Ordering::Acquire);
-        let read_pos = self.read_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
         let data_ptr = self.data_ptr();
 
-        // Read length prefix
-        let mut len_bytes = [0u8; 4];
-        for (i, byte) in len_bytes.iter_mut().enumerate() {
-            unsafe {
-                *byte = *data_ptr.add((read_pos + i) % capacity);
-            }
+        // Read the header; the stamp is checked before the length is trusted
+        let expected = self.read_stamp();
+        let (data_len, stamp) = unsafe { read_record_header(data_ptr, capacity, read_pos) };
+        if stamp != expected {
+            return Err(self.discard(BadRecord::Stale));
         }
-        let data_len = u32::from_le_bytes(len_bytes) as usize;
 
         // Basic validation to prevent reading garbage
         if data_len == 0 || data_len > capacity {
             return Err(anyhow!("Invalid message length: {}", data_len));
         }
+        if data_len < head_len {
+            return Err(short_record(data_len, head_len));
+        }
 
-        if self.available_read_data() < data_len + 4 {
+        if self.available_read_data() < data_len + RECORD_HEADER {
             return Err(anyhow!("Incomplete message"));
         }
 
-        // Read data
-        let mut data = vec![0u8; data_len];
-        for (i, byte) in data.iter_mut().enumerate() {
-            unsafe {
-                *byte = *data_ptr.add((read_pos + 4 + i) % capacity);
-            }
+        // Bulk copy, split where the record wraps (see read_data_blocking)
+        let data_start = (read_pos + RECORD_HEADER) % capacity;
+        let data =
+            unsafe { RingRecord::copy_out(data_ptr, capacity, data_start, data_len, head_len) };
+
+        // The writer never reuses unread space, so a stamp that changed
+        // during the copy means the ring was written by something else
+        let (_, after) = unsafe { read_record_header(data_ptr, capacity, read_pos) };
+        if let Err(bad) = expected.check(stamp, after) {
+            return Err(self.discard(bad));
         }
 
-        self.read_pos
-            .store((read_pos + data_len + 4) % capacity, Ordering::Release);
+        self.commit_read(read_pos, record_size(data_len, self.slot_align), capacity);
 
         Ok(data)
     }
@@ -289,6 +559,8 @@ impl SharedMemoryRingBuffer {
     ///
     /// * `data` - Mutable serialized message bytes. The timestamp
     ///   region will be updated in-place right before the write.
+    /// * `tail` - Bytes that follow `data` in the same record, copied
+    ///   into the ring as they are (the payload of a fixed-layout record).
     /// * `timestamp_offset` - Byte range of the timestamp field
     ///   within `data`. When `Some`, the timestamp is refreshed
     ///   immediately before the memory write so that measured
@@ -301,16 +573,18 @@ impl SharedMemoryRingBuffer {
     unsafe fn write_data_blocking(
         &self,
         data: &mut [u8],
+        tail: &[u8],
         timestamp_offset: Option<std::ops::Range<usize>>,
     ) -> Result<()> {
-        let data_len = data.len();
-        let required_space = data_len + 4; // 4 bytes for length prefix
+        let data_len = data.len() + tail.len();
+        let required_space = record_size(data_len, self.slot_align);
+        self.check_record_fits(required_space)?;
 
         // Lock mutex
         libc::pthread_mutex_lock(&self.mutex as *const _ as *mut _);
 
-        // Wait for space to become available
-        while self.available_write_space() < required_space {
+        // Wait for space (and a free message slot) to become available
+        while !self.can_write(required_space) {
             if self.shutdown.load(Ordering::Acquire) {
                 libc::pthread_mutex_unlock(&self.mutex as *const _ as *mut _);
                 return Err(anyhow!("Connection closed"));
@@ -334,14 +608,11 @@ impl SharedMemoryRingBuffer {
 
         // Space is available, write the data
         let capacity = self.capacity.load(Ordering::Acquire);
-        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let write_pos = self.write_pos().load(Ordering::Acquire);
         let data_ptr = self.data_ptr();
 
-        // Write length prefix (little-endian)
-        let len_bytes = (data_len as u32).to_le_bytes();
-        for (i, &byte) in len_bytes.iter().enumerate() {
-            *data_ptr.add((write_pos + i) % capacity) = byte;
-        }
+        // Write the header: length prefix and stamp (little-endian)
+        write_record_header(data_ptr, capacity, write_pos, data_len, self.write_stamp());
 
         // PERF: Bulk copy using copy_nonoverlapping instead of the
         // original byte-by-byte loop. The old code was:
@@ -352,25 +623,14 @@ impl SharedMemoryRingBuffer {
         // auto-vectorizing, and forces single-byte stores. The bulk
         // copy lets the CPU transfer data in cache-line-sized bursts.
         // For a 4096-byte message this replaces 4096 individual stores
-        // (each with an integer division) with 1-2 memcpy calls. The
-        // split handles ring buffer wrap-around: if the write would
-        // cross the end of the buffer, we copy in two parts.
-        let data_start = (write_pos + 4) % capacity;
-        if data_start + data_len <= capacity {
-            std::ptr::copy_nonoverlapping(data.as_ptr(), data_ptr.add(data_start), data_len);
-        } else {
-            let first_part = capacity - data_start;
-            std::ptr::copy_nonoverlapping(data.as_ptr(), data_ptr.add(data_start), first_part);
-            std::ptr::copy_nonoverlapping(
-                data.as_ptr().add(first_part),
-                data_ptr,
-                data_len - first_part,
-            );
-        }
+        // (each with an integer division) with 1-2 memcpy calls per
+        // part. copy_into_ring handles ring buffer wrap-around: if the
+        // write would cross the end of the buffer, it copies in two parts.
+        let data_start = (write_pos + RECORD_HEADER) % capacity;
+        let tail_start = copy_into_ring(data_ptr, capacity, data_start, data);
+        copy_into_ring(data_ptr, capacity, tail_start, tail);
 
-        self.write_pos
-            .store((write_pos + required_space) % capacity, Ordering::Release);
-        self.message_count.fetch_add(1, Ordering::Release);
+        self.commit_write(write_pos, required_space, capacity);
 
         // Signal reader that data is available
         libc::pthread_cond_signal(&self.data_ready as *const _ as *mut _);
@@ -392,12 +652,12 @@ impl SharedMemoryRingBuffer {
     /// Only available on Unix platforms with pthread support.
     #[cfg(unix)]
     #[inline]
-    unsafe fn read_data_blocking(&self) -> Result<Vec<u8>> {
+    unsafe fn read_data_blocking(&self, head_len: usize) -> Result<RingRecord> {
         // Lock mutex
         libc::pthread_mutex_lock(&self.mutex as *const _ as *mut _);
 
         // Wait for data to become available
-        while self.available_read_data() < 4 {
+        while self.available_read_data() < RECORD_HEADER {
             // Check for shutdown while waiting
             if self.shutdown.load(Ordering::Acquire) {
                 libc::pthread_mutex_unlock(&self.mutex as *const _ as *mut _);
@@ -413,15 +673,18 @@ impl SharedMemoryRingBuffer {
 
         // Data is available, read it
         let capacity = self.capacity.load(Ordering::Acquire);
-        let read_pos = self.read_pos.load(Ordering::Acquire);
+        let read_pos = self.read_pos().load(Ordering::Acquire);
         let data_ptr = self.data_ptr();
 
-        // Read length prefix
-        let mut len_bytes = [0u8; 4];
-        for (i, byte) in len_bytes.iter_mut().enumerate() {
-            *byte = *data_ptr.add((read_pos + i) % capacity);
+        // Read the header; the stamp is checked before the length is trusted
+        let expected = self.read_stamp();
+        let (data_len, stamp) = read_record_header(data_ptr, capacity, read_pos);
+        if stamp != expected {
+            let bad = self.discard(BadRecord::Stale);
+            libc::pthread_cond_signal(&self.space_ready as *const _ as *mut _);
+            libc::pthread_mutex_unlock(&self.mutex as *const _ as *mut _);
+            return Err(bad);
         }
-        let data_len = u32::from_le_bytes(len_bytes) as usize;
 
         // Validate data length
         if data_len > capacity {
@@ -432,6 +695,10 @@ impl SharedMemoryRingBuffer {
                 capacity
             ));
         }
+        if data_len < head_len {
+            libc::pthread_mutex_unlock(&self.mutex as *const _ as *mut _);
+            return Err(short_record(data_len, head_len));
+        }
 
         // PERF: Bulk read + zero-fill elimination. Two improvements:
         //
@@ -445,23 +712,18 @@ impl SharedMemoryRingBuffer {
         //      }
         //    Same rationale as the write path — eliminates per-byte modulo
         //    and enables cache-line-sized bulk transfers.
-        let mut data = Vec::with_capacity(data_len);
-        let data_start = (read_pos + 4) % capacity;
-        if data_start + data_len <= capacity {
-            std::ptr::copy_nonoverlapping(data_ptr.add(data_start), data.as_mut_ptr(), data_len);
-        } else {
-            let first_part = capacity - data_start;
-            std::ptr::copy_nonoverlapping(data_ptr.add(data_start), data.as_mut_ptr(), first_part);
-            std::ptr::copy_nonoverlapping(
-                data_ptr,
-                data.as_mut_ptr().add(first_part),
-                data_len - first_part,
-            );
+        let data_start = (read_pos + RECORD_HEADER) % capacity;
+        let data = RingRecord::copy_out(data_ptr, capacity, data_start, data_len, head_len);
+
+        let (_, after) = read_record_header(data_ptr, capacity, read_pos);
+        if let Err(bad) = expected.check(stamp, after) {
+            let bad = self.discard(bad);
+            libc::pthread_cond_signal(&self.space_ready as *const _ as *mut _);
+            libc::pthread_mutex_unlock(&self.mutex as *const _ as *mut _);
+            return Err(bad);
         }
-        data.set_len(data_len);
 
-        self.read_pos
-            .store((read_pos + data_len + 4) % capacity, Ordering::Release);
+        self.commit_read(read_pos, record_size(data_len, self.slot_align), capacity);
 
         // Signal writer that space is available
         libc::pthread_cond_signal(&self.space_ready as *const _ as *mut _);
@@ -473,6 +735,114 @@ impl SharedMemoryRingBuffer {
     }
 }
 
+#[cfg(target_os = "linux")]
+impl SharedMemoryRingBuffer {
+    /// Write a record, waiting on the space semaphore while the ring is
+    /// full, then post the items semaphore.
+    ///
+    /// As with `write_data_blocking`, the timestamp region of `data` is
+    /// refreshed once space is available so measured latency excludes the
+    /// backpressure wait.
+    fn write_data_semaphore(
+        &self,
+        data: &mut [u8],
+        tail: &[u8],
+        timestamp_offset: Option<std::ops::Range<usize>>,
+        semaphores: &ShmSemaphores,
+    ) -> Result<()> {
+        let required_space = record_size(data.len() + tail.len(), self.slot_align);
+        self.check_record_fits(required_space)?;
+
+        while !self.can_write(required_space) {
+            if self.shutdown.load(Ordering::Acquire) {
+                return Err(anyhow!("Connection closed"));
+            }
+            semaphores.wait_space()?;
+        }
+
+        if let Some(ts_range) = timestamp_offset {
+            let ts_now = crate::ipc::get_monotonic_time_ns();
+            data[ts_range].copy_from_slice(&ts_now.to_le_bytes());
+        }
+        self.write_data(data, tail)?;
+        semaphores.post_item();
+        Ok(())
+    }
+
+    /// Wait on the items semaphore for a record, read it, then wake a
+    /// writer waiting for space.
+    fn read_data_semaphore(
+        &self,
+        semaphores: &ShmSemaphores,
+        head_len: usize,
+    ) -> Result<RingRecord> {
+        loop {
+            semaphores.wait_item()?;
+            if self.available_read_data() >= RECORD_HEADER {
+                break;
+            }
+            // Woken without a record: the peer is shutting down
+            if self.shutdown.load(Ordering::Acquire) {
+                return Err(anyhow!("Connection closed"));
+            }
+        }
+        // A discarded record frees its space too
+        let data = self.read_data(head_len);
+        semaphores.post_space();
+        data
+    }
+}
+
+#[cfg(windows)]
+impl SharedMemoryRingBuffer {
+    /// Write a record, waiting on the space doorbell while the ring is full,
+    /// then ring the data doorbell.
+    ///
+    /// As with `write_data_blocking`, the timestamp region of `data` is
+    /// refreshed once space is available so measured latency excludes the
+    /// backpressure wait.
+    fn write_data_signalled(
+        &self,
+        data: &mut [u8],
+        tail: &[u8],
+        timestamp_offset: Option<std::ops::Range<usize>>,
+        doorbells: &ShmDoorbells,
+    ) -> Result<()> {
+        let required_space = record_size(data.len() + tail.len(), self.slot_align);
+        self.check_record_fits(required_space)?;
+
+        while !self.can_write(required_space) {
+            if self.shutdown.load(Ordering::Acquire) {
+                return Err(anyhow!("Connection closed"));
+            }
+            doorbells.wait_space();
+        }
+
+        if let Some(ts_range) = timestamp_offset {
+            let ts_now = crate::ipc::get_monotonic_time_ns();
+            data[ts_range].copy_from_slice(&ts_now.to_le_bytes());
+        }
+        self.write_data(data, tail)?;
+        doorbells.ring_data();
+        Ok(())
+    }
+
+    /// Read a record, waiting on the data doorbell while the ring is empty,
+    /// then ring the space doorbell.
+    fn read_data_signalled(&self, doorbells: &ShmDoorbells, head_len: usize) -> Result<RingRecord> {
+        while self.available_read_data() < RECORD_HEADER {
+            if self.shutdown.load(Ordering::Acquire) {
+                return Err(anyhow!("Connection closed"));
+            }
+            doorbells.wait_data();
+        }
+        // A discarded record frees its space too
+        let data = self.read_data(head_len);
+        doorbells.ring_space();
+        data
+    }
+}
+
 /// Blocking shared memory transport.
 ///
 /// This struct implements the `BlockingTransport` trait using shared memory
@@ -491,7 +861,7 @@ pub struct BlockingSharedMemory {
 
     /// The shared memory segment (wrapped in Arc for safety)
     #[allow(dead_code)]
-    shmem: Option<Arc<Mutex<Shmem>>>,
+    shmem: Option<Arc<Mutex<Segment>>>,
 
     /// Whether this instance is the server (creator)
     is_server: bool,
@@ -501,6 +871,87 @@ pub struct BlockingSharedMemory {
     /// Stored so close_blocking() can perform deterministic cleanup
     /// (e.g., shm_unlink on server instances).
     shared_memory_name: String,
+
+    /// Deliver queued messages highest priority first
+    /// (`TransportConfig::shm_priority_lanes`).
+    priority_lanes: bool,
+
+    /// Messages taken from the ring but not yet delivered, in one FIFO lane
+    /// per priority.
+    lanes: BTreeMap<u8, VecDeque<Message>>,
+
+    /// Event objects that stand in for the ring's condition variables.
+    #[cfg(windows)]
+    doorbells: Option<ShmDoorbells>,
+
+    /// Named semaphores used instead of the condition variables
+    /// (`TransportConfig::shm_wait`).
+    #[cfg(target_os = "linux")]
+    semaphores: Option<ShmSemaphores>,
+
+    /// Sends that found the ring full and had to wait for the reader
+    ring_full: u64,
+
+    /// Records written past the end of the ring, back to its start
+    ring_wraps: u64,
+
+    /// Records received with an unexpected stamp and dropped
+    discarded: DiscardedRecords,
+
+    /// Serialized message, reused from send to send
+    send_buffer: Vec<u8>,
+
+    /// Carry fixed-layout records instead of bincode
+    /// (`TransportConfig::shm_zero_copy`).
+    zero_copy: bool,
+}
+
+/// Mapping that holds the ring.
+enum Segment {
+    /// A POSIX shared memory object (the default)
+    Posix(Shmem),
+    /// A file on hugetlbfs (`TransportConfig::shm_hugetlbfs`)
+    #[cfg(target_os = "linux")]
+    Hugetlbfs(HugetlbfsSegment),
+}
+
+impl Segment {
+    fn as_ptr(&self) -> *mut u8 {
+        match self {
+            Segment::Posix(shmem) => shmem.as_ptr(),
+            #[cfg(target_os = "linux")]
+            Segment::Hugetlbfs(segment) => segment.as_ptr(),
+        }
+    }
+}
+
+/// Depth probe of a ring, holding on to the transport's mapping.
+struct RingDepthProbe {
+    ring_buffer: *mut SharedMemoryRingBuffer,
+    _shmem: Arc<Mutex<Segment>>,
+}
+
+// Safety: the probe only reads the ring's atomic counters and indices
+unsafe impl Send for RingDepthProbe {}
+
+impl QueueDepthProbe for RingDepthProbe {
+    fn depth(&mut self) -> Option<QueueDepth> {
+        let ring = unsafe { &*self.ring_buffer };
+        let written = ring.message_count().load(Ordering::Acquire);
+        let consumed = ring.consumed_count().load(Ordering::Acquire);
+        Some(QueueDepth {
+            messages: written.wrapping_sub(consumed),
+            bytes: Some(ring.available_read_data()),
+        })
+    }
+
+    fn capacity(&self) -> QueueCapacity {
+        let ring = unsafe { &*self.ring_buffer };
+        QueueCapacity {
+            messages: (ring.max_messages > 0).then_some(ring.max_messages),
+            bytes: Some(ring.capacity.load(Ordering::Acquire)),
+        }
+    }
 }
 
 // Safety: The ring buffer uses atomic operations for coordination
@@ -527,7 +978,120 @@ impl BlockingSharedMemory {
             shmem: None,
             is_server: false,
             shared_memory_name: String::new(),
+            priority_lanes: false,
+            lanes: BTreeMap::new(),
+            #[cfg(windows)]
+            doorbells: None,
+            #[cfg(target_os = "linux")]
+            semaphores: None,
+            ring_full: 0,
+            ring_wraps: 0,
+            discarded: DiscardedRecords::default(),
+            send_buffer: Vec::new(),
+            zero_copy: false,
+        }
+    }
+
+    /// Count a wrap when the record just written moved the write index
+    /// from `write_pos` past the end of the ring.
+    fn note_write(&mut self, ring_buffer: *mut SharedMemoryRingBuffer, write_pos: usize) {
+        let ring = unsafe { &*ring_buffer };
+        if ring.write_pos().load(Ordering::Acquire) <= write_pos {
+            self.ring_wraps += 1;
+        }
+    }
+
+    /// Read the next record from the ring, waiting for one if it is empty.
+    ///
+    /// Records with an unexpected stamp are counted and skipped.
+    fn read_record(&self, ring_buffer: *mut SharedMemoryRingBuffer) -> Result<RingRecord> {
+        loop {
+            if let Some(data) = self.next_record(ring_buffer)? {
+                return Ok(data);
+            }
+        }
+    }
+
+    /// Read one record, waiting for one if the ring is empty; `None` when
+    /// the record was discarded.
+    fn next_record(&self, ring_buffer: *mut SharedMemoryRingBuffer) -> Result<Option<RingRecord>> {
+        // Fixed-layout records leave their header out of the payload
+        let head_len = if self.zero_copy {
+            fixed_layout::HEADER_SIZE
+        } else {
+            0
+        };
+
+        #[cfg(target_os = "linux")]
+        if let Some(semaphores) = &self.semaphores {
+            let read = unsafe { (*ring_buffer).read_data_semaphore(semaphores, head_len) };
+            return self.keep_record(read);
+        }
+
+        #[cfg(unix)]
+        let read = unsafe { (*ring_buffer).read_data_blocking(head_len) };
+
+        #[cfg(windows)]
+        let read = unsafe { (*ring_buffer).read_data_signalled(self.doorbells()?, head_len) };
+
+        self.keep_record(read)
+    }
+
+    /// Count a discarded record instead of failing on it.
+    fn keep_record(&self, read: Result<RingRecord>) -> Result<Option<RingRecord>> {
+        match read {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if self.discarded.count(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(windows)]
+    fn doorbells(&self) -> Result<&ShmDoorbells> {
+        self.doorbells
+            .as_ref()
+            .ok_or_else(|| anyhow!("Shared memory doorbells not initialized"))
+    }
+
+    /// Deliver the next message through the priority lanes.
+    ///
+    /// Moves every message already queued in the ring into the lane for its
+    /// priority, first waiting for one if all lanes are empty, then takes
+    /// the oldest message of the highest non-empty lane.
+    fn receive_from_lanes(&mut self, ring_buffer: *mut SharedMemoryRingBuffer) -> Result<Message> {
+        if self.lanes.values().all(VecDeque::is_empty) {
+            let data = self.read_record(ring_buffer)?;
+            self.push_to_lane(data)?;
+        }
+        // Only this side consumes from the ring, so these reads never wait
+        while unsafe { (*ring_buffer).available_read_data() } >= RECORD_HEADER {
+            if let Some(data) = self.next_record(ring_buffer)? {
+                self.push_to_lane(data)?;
+            }
         }
+        self.lanes
+            .values_mut()
+            .rev()
+            .find_map(VecDeque::pop_front)
+            .ok_or_else(|| anyhow!("Priority lanes are empty"))
+    }
+
+    /// Turn a record read from the ring back into its message.
+    fn decode(&self, data: RingRecord) -> Result<Message> {
+        if self.zero_copy {
+            return Ok(fixed_layout::decode(&data.head, data.body));
+        }
+        bincode::deserialize(&data.body).context("Failed to deserialize message")
+    }
+
+    /// Decode a record into the lane for its priority.
+    fn push_to_lane(&mut self, data: RingRecord) -> Result<()> {
+        let message = self.decode(data)?;
+        self.lanes
+            .entry(message.priority)
+            .or_default()
+            .push_back(message);
+        Ok(())
     }
 
     /// Wait for the peer to be ready (busy-wait with yields)
@@ -590,7 +1154,8 @@ impl BlockingTransport for BlockingSharedMemory {
         );
 
         let buffer_size = config.buffer_size;
-        let total_size = SharedMemoryRingBuffer::HEADER_SIZE + buffer_size;
+        let layout = ShmRingLayout::from_config(config);
+        let total_size = SharedMemoryRingBuffer::segment_size(buffer_size, &layout);
 
         // Try to remove any existing segment first (cleanup from previous runs)
         // This is a best-effort cleanup - we ignore errors
@@ -610,23 +1175,43 @@ impl BlockingTransport for BlockingSharedMemory {
             }
         }
 
+        // Created before the segment, so a client that can open the segment
+        // can open them too
+        #[cfg(target_os = "linux")]
+        let semaphores = match config.shm_wait {
+            crate::options::ShmWait::Semaphore => {
+                Some(ShmSemaphores::create(&config.shared_memory_name)?)
+            }
+            crate::options::ShmWait::Condvar => None,
+        };
+
         // Create shared memory segment
-        let shmem = ShmemConf::new()
-            .size(total_size)
-            .os_id(&config.shared_memory_name)
-            .create()
-            .with_context(|| {
-                format!(
-                    "Failed to create shared memory segment: {}. \
-                     Ensure no existing segment with this name.",
-                    config.shared_memory_name
-                )
-            })?;
+        let shmem = match &config.shm_hugetlbfs {
+            #[cfg(target_os = "linux")]
+            Some(dir) => Segment::Hugetlbfs(HugetlbfsSegment::create(
+                dir,
+                &config.shared_memory_name,
+                total_size,
+            )?),
+            _ => Segment::Posix(
+                ShmemConf::new()
+                    .size(total_size)
+                    .os_id(&config.shared_memory_name)
+                    .create()
+                    .with_context(|| {
+                        format!(
+                            "Failed to create shared memory segment: {}. \
+                             Ensure no existing segment with this name.",
+                            config.shared_memory_name
+                        )
+                    })?,
+            ),
+        };
 
         // Initialize the ring buffer
         let ptr = shmem.as_ptr() as *mut SharedMemoryRingBuffer;
         unsafe {
-            std::ptr::write(ptr, SharedMemoryRingBuffer::new(buffer_size));
+            SharedMemoryRingBuffer::initialize(ptr, buffer_size, &layout);
             (*ptr).server_ready.store(true, Ordering::Release);
         }
 
@@ -634,6 +1219,16 @@ impl BlockingTransport for BlockingSharedMemory {
         self.shmem = Some(Arc::new(Mutex::new(shmem)));
         self.is_server = true;
         self.shared_memory_name = config.shared_memory_name.clone();
+        self.priority_lanes = config.shm_priority_lanes;
+        self.zero_copy = config.shm_zero_copy;
+        #[cfg(target_os = "linux")]
+        {
+            self.semaphores = semaphores;
+        }
+        #[cfg(windows)]
+        {
+            self.doorbells = Some(ShmDoorbells::open(&config.shared_memory_name)?);
+        }
 
         debug!("Shared memory server created successfully");
 
@@ -649,32 +1244,52 @@ impl BlockingTransport for BlockingSharedMemory {
             config.shared_memory_name
         );
 
-        let buffer_size = config.buffer_size;
-        let total_size = SharedMemoryRingBuffer::HEADER_SIZE + buffer_size;
+        let total_size = SharedMemoryRingBuffer::segment_size(
+            config.buffer_size,
+            &ShmRingLayout::from_config(config),
+        );
 
         // Open existing shared memory segment (retry with timeout)
         let start = std::time::Instant::now();
         let timeout = Duration::from_secs(30);
         let shmem = loop {
-            match ShmemConf::new()
-                .size(total_size)
-                .os_id(&config.shared_memory_name)
-                .open()
-            {
-                Ok(shm) => break shm,
-                Err(e) => {
-                    if start.elapsed() > timeout {
-                        return Err(anyhow!(
-                            "Failed to open shared memory segment: {}. \
-                             Is the server running? Error: {}",
-                            config.shared_memory_name,
-                            e
-                        ));
+            let opened = match &config.shm_hugetlbfs {
+                #[cfg(target_os = "linux")]
+                Some(dir) => HugetlbfsSegment::open(dir, &config.shared_memory_name, total_size)
+                    .map(Segment::Hugetlbfs),
+                _ => ShmemConf::new()
+                    .size(total_size)
+                    .os_id(&config.shared_memory_name)
+                    .open()
+                    .map(Segment::Posix)
+                    .map_err(anyhow::Error::from),
+            };
+            let error = match opened {
+                Ok(shm) => {
+                    // The server creates the segment before it writes the
+                    // header; a header from another build is an error
+                    let ring_buffer = unsafe { &*(shm.as_ptr() as *const SharedMemoryRingBuffer) };
+                    if ring_header_ready(
+                        &ring_buffer.magic,
+                        &ring_buffer.version,
+                        &config.shared_memory_name,
+                    )? {
+                        break shm;
                     }
-                    // Wait a bit and retry
-                    thread::sleep(Duration::from_millis(100));
+                    anyhow!("the ring buffer was never initialized")
                 }
+                Err(e) => e,
+            };
+            if start.elapsed() > timeout {
+                return Err(anyhow!(
+                    "Failed to open shared memory segment: {}. \
+                     Is the server running? Error: {}",
+                    config.shared_memory_name,
+                    error
+                ));
             }
+            // Wait a bit and retry
+            thread::sleep(Duration::from_millis(100));
         };
 
         let ptr = shmem.as_ptr() as *mut SharedMemoryRingBuffer;
@@ -688,6 +1303,16 @@ impl BlockingTransport for BlockingSharedMemory {
         self.shmem = Some(Arc::new(Mutex::new(shmem)));
         self.is_server = false;
         self.shared_memory_name = config.shared_memory_name.clone();
+        self.priority_lanes = config.shm_priority_lanes;
+        self.zero_copy = config.shm_zero_copy;
+        #[cfg(target_os = "linux")]
+        if config.shm_wait == crate::options::ShmWait::Semaphore {
+            self.semaphores = Some(ShmSemaphores::open(&config.shared_memory_name)?);
+        }
+        #[cfg(windows)]
+        {
+            self.doorbells = Some(ShmDoorbells::open(&config.shared_memory_name)?);
+        }
 
         debug!("Client connected to shared memory successfully");
         Ok(())
@@ -716,50 +1341,78 @@ impl BlockingTransport for BlockingSharedMemory {
         // only the timestamp bytes immediately before send. This ensures any
         // scheduling delays between timestamp capture and send are included in
         // the measured latency.
-        let mut message_with_timestamp = message.clone();
-        message_with_timestamp.timestamp = 0; // Dummy timestamp for pre-serialization
-        let mut serialized =
-            bincode::serialize(&message_with_timestamp).context("Failed to serialize message")?;
+        // Taken for the send and put back after, so a failed send only
+        // costs the next one an allocation
+        let mut serialized = std::mem::take(&mut self.send_buffer);
+        // A fixed-layout record is its header here, followed in the ring by
+        // the payload straight from the message
+        let payload: &[u8] = if self.zero_copy {
+            serialized.clear();
+            serialized.extend_from_slice(&fixed_layout::header(message));
+            &message.payload
+        } else {
+            message.serialize_unstamped_into(&mut serialized)?;
+            &[]
+        };
 
         // Timestamp will be captured inside write_data_blocking right before
         // the actual memory write, ensuring accurate latency even under backpressure
 
-        // Send message - timestamp is updated atomically right before memory write
-        // Use condition variable-based blocking write
-        #[cfg(unix)]
-        {
+        // Only this side writes while a send is under way, so the write
+        // index read here is the one the record starts at
+        let write_pos = unsafe {
+            let ring = &*ring_buffer;
+            if !ring.can_write(record_size(
+                serialized.len() + payload.len(),
+                ring.slot_align,
+            )) {
+                self.ring_full += 1;
+            }
+            ring.write_pos().load(Ordering::Acquire)
+        };
+
+        #[cfg(target_os = "linux")]
+        if let Some(semaphores) = &self.semaphores {
             unsafe {
-                (*ring_buffer)
-                    .write_data_blocking(&mut serialized, Some(Message::timestamp_offset()))?;
+                (*ring_buffer).write_data_semaphore(
+                    &mut serialized,
+                    payload,
+                    Some(Message::timestamp_offset()),
+                    semaphores,
+                )?;
             }
+            self.note_write(ring_buffer, write_pos);
+            self.send_buffer = serialized;
             trace!("Message ID {} sent successfully", message.id);
-            Ok(())
+            return Ok(());
         }
 
-        #[cfg(not(unix))]
-        {
-            // Fallback to busy-wait for non-Unix platforms
-            let start = std::time::Instant::now();
-            let timeout = Duration::from_secs(5);
-
-            loop {
-                match unsafe { (*ring_buffer).write_data(&serialized) } {
-                    Ok(()) => {
-                        trace!("Message ID {} sent successfully", message.id);
-                        return Ok(());
-                    }
-                    Err(_) => {
-                        if start.elapsed() > timeout {
-                            return Err(anyhow!(
-                                "Timeout: ring buffer full, possible backpressure"
-                            ));
-                        }
-                        thread::yield_now();
-                        thread::sleep(Duration::from_micros(100));
-                    }
-                }
-            }
+        // Send message - timestamp is updated atomically right before memory write
+        // Use condition variable-based blocking write
+        #[cfg(unix)]
+        unsafe {
+            (*ring_buffer).write_data_blocking(
+                &mut serialized,
+                payload,
+                Some(Message::timestamp_offset()),
+            )?;
+        }
+
+        // Event-object doorbells stand in for the condition variables
+        #[cfg(windows)]
+        unsafe {
+            (*ring_buffer).write_data_signalled(
+                &mut serialized,
+                payload,
+                Some(Message::timestamp_offset()),
+                self.doorbells()?,
+            )?;
         }
+
+        self.note_write(ring_buffer, write_pos);
+        self.send_buffer = serialized;
+        trace!("Message ID {} sent successfully", message.id);
+        Ok(())
     }
 
     fn receive_blocking(&mut self) -> Result<Message> {
@@ -775,28 +1428,16 @@ impl BlockingTransport for BlockingSharedMemory {
             )
         })?;
 
-        // Use condition variable-based blocking read
-        #[cfg(unix)]
-        let data = unsafe { (*ring_buffer).read_data_blocking()? };
+        if self.priority_lanes {
+            let message = self.receive_from_lanes(ring_buffer)?;
+            trace!("Received message ID {}", message.id);
+            return Ok(message);
+        }
 
-        #[cfg(not(unix))]
-        let data = loop {
-            match unsafe { (*ring_buffer).read_data() } {
-                Ok(d) => break d,
-                Err(_) => {
-                    // Check for shutdown
-                    if unsafe { (*ring_buffer).shutdown.load(Ordering::Acquire) } {
-                        return Err(anyhow!("Connection closed"));
-                    }
-                    thread::yield_now();
-                    thread::sleep(Duration::from_micros(100));
-                }
-            }
-        };
+        // Use condition variable-based blocking read
+        let data = self.read_record(ring_buffer)?;
 
-        // Deserialize message
-        let message: Message =
-            bincode::deserialize(&data).context("Failed to deserialize message")?;
+        let message = self.decode(data)?;
 
         trace!("Received message ID {}", message.id);
         Ok(message)
@@ -812,37 +1453,81 @@ impl BlockingTransport for BlockingSharedMemory {
             )
         })?;
 
-        #[cfg(unix)]
-        let data = unsafe { (*ring_buffer).read_data_blocking()? };
+        // A message that waited in its lane is received when it leaves it
+        if self.priority_lanes {
+            let message = self.receive_from_lanes(ring_buffer)?;
+            return Ok((message, crate::ipc::get_monotonic_time_ns()));
+        }
 
-        #[cfg(not(unix))]
-        let data = loop {
-            match unsafe { (*ring_buffer).read_data() } {
-                Ok(d) => break d,
-                Err(_) => {
-                    if unsafe { (*ring_buffer).shutdown.load(Ordering::Acquire) } {
-                        return Err(anyhow!("Connection closed"));
-                    }
-                    thread::yield_now();
-                    thread::sleep(Duration::from_micros(100));
-                }
-            }
-        };
+        let data = self.read_record(ring_buffer)?;
 
         // Capture timestamp after raw read, before deserialization
         let receive_time_ns = crate::ipc::get_monotonic_time_ns();
 
-        let message: Message =
-            bincode::deserialize(&data).context("Failed to deserialize message")?;
+        let message = self.decode(data)?;
 
         Ok((message, receive_time_ns))
     }
 
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        let ring_buffer = match self.ring_buffer {
+            Some(ring_buffer) => ring_buffer,
+            None => return Ok(None),
+        };
+
+        // Only this side consumes from the ring, so data seen here stays
+        // available and the read below never waits.
+        let queued = unsafe { (*ring_buffer).available_read_data() } >= RECORD_HEADER;
+        if self.priority_lanes {
+            if !queued && self.lanes.values().all(VecDeque::is_empty) {
+                return Ok(None);
+            }
+            return self.receive_from_lanes(ring_buffer).map(Some);
+        }
+        if !queued {
+            return Ok(None);
+        }
+
+        let Some(data) = self.next_record(ring_buffer)? else {
+            return Ok(None);
+        };
+
+        self.decode(data).map(Some)
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn queue_depth_probe(&self) -> Option<Box<dyn QueueDepthProbe>> {
+        Some(Box::new(RingDepthProbe {
+            ring_buffer: self.ring_buffer?,
+            _shmem: Arc::clone(self.shmem.as_ref()?),
+        }))
+    }
+
+    fn stats(&self) -> TransportStats {
+        TransportStats::from([
+            ("ring_full".to_string(), self.ring_full),
+            ("ring_wraps".to_string(), self.ring_wraps),
+        ])
+        .into_iter()
+        .chain(self.discarded.stats())
+        .collect()
+    }
+
     fn close_blocking(&mut self) -> Result<()> {
         debug!("Closing blocking shared memory transport");
 
         // Capture cleanup metadata before dropping local state.
-        let should_unlink = self.is_server && !self.shared_memory_name.is_empty();
+        // A ring on hugetlbfs has no POSIX object; its file removes itself.
+        let should_unlink = self.is_server
+            && !self.shared_memory_name.is_empty()
+            && self
+                .shmem
+                .as_ref()
+                .map_or(true, |shmem| matches!(*shmem.lock(), Segment::Posix(_)));
+        #[cfg(unix)]
         let shm_name = self.shared_memory_name.clone();
 
         if let Some(ring_buffer) = self.ring_buffer {
@@ -857,6 +1542,14 @@ impl BlockingTransport for BlockingSharedMemory {
                 }
             }
         }
+        #[cfg(windows)]
+        if let Some(doorbells) = self.doorbells.take() {
+            doorbells.ring_all();
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(semaphores) = self.semaphores.take() {
+            semaphores.wake_all();
+        }
 
         self.ring_buffer = None;
         self.shmem = None;
@@ -1040,6 +1733,264 @@ mod tests {
         server_handle.join().unwrap();
     }
 
+    /// Aligned records and padded indices must be transparent to the peer:
+    /// the client follows the layout the server recorded in the header.
+    #[test]
+    fn test_aligned_padded_layout_round_trips_messages() {
+        let segment_name = "test_shm_blocking_aligned_padded";
+        let server_config = TransportConfig {
+            shared_memory_name: segment_name.to_string(),
+            buffer_size: 1000,
+            shm_slot_align: 128,
+            shm_pad_indices: true,
+            ..Default::default()
+        };
+
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingSharedMemory::new();
+            server.start_server_blocking(&server_config).unwrap();
+            for id in 0..50u64 {
+                let msg = server.receive_blocking().unwrap();
+                assert_eq!(msg.id, id);
+                assert_eq!(msg.payload.len(), 10 + id as usize);
+            }
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(200));
+
+        let mut client = BlockingSharedMemory::new();
+        let config = TransportConfig {
+            shared_memory_name: segment_name.to_string(),
+            buffer_size: 1000,
+            shm_slot_align: 128,
+            shm_pad_indices: true,
+            ..Default::default()
+        };
+        client.start_client_blocking(&config).unwrap();
+        for id in 0..50u64 {
+            let msg = Message::new(id, vec![0u8; 10 + id as usize], MessageType::OneWay);
+            client.send_blocking(&msg).unwrap();
+        }
+        client.close_blocking().unwrap();
+
+        server_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_receive_batch_drains_only_queued_messages() {
+        let segment_name = "test_shm_blocking_receive_batch";
+        let config = TransportConfig {
+            shared_memory_name: segment_name.to_string(),
+            buffer_size: 8192,
+            ..Default::default()
+        };
+
+        let (sent_tx, sent_rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let server_config = config.clone();
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingSharedMemory::new();
+            server.start_server_blocking(&server_config).unwrap();
+            sent_rx.recv().unwrap();
+
+            let mut batch = Vec::new();
+            server.receive_batch_blocking(3, &mut batch).unwrap();
+            assert_eq!(batch.iter().map(|m| m.id).collect::<Vec<_>>(), [0, 1, 2]);
+            server.receive_batch_blocking(10, &mut batch).unwrap();
+            assert_eq!(batch.iter().map(|m| m.id).collect::<Vec<_>>(), [3, 4]);
+            assert!(server.try_receive_blocking().unwrap().is_none());
+
+            done_tx.send(()).unwrap();
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(200));
+
+        let mut client = BlockingSharedMemory::new();
+        client.start_client_blocking(&config).unwrap();
+        for id in 0..5u64 {
+            let msg = Message::new(id, vec![0u8; 32], MessageType::OneWay);
+            client.send_blocking(&msg).unwrap();
+        }
+        sent_tx.send(()).unwrap();
+        done_rx.recv().unwrap();
+        client.close_blocking().unwrap();
+
+        server_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_stale_record_is_discarded_and_counted() {
+        let config = TransportConfig {
+            shared_memory_name: "test_shm_blocking_stale_record".to_string(),
+            buffer_size: 8192,
+            ..Default::default()
+        };
+        let mut server = BlockingSharedMemory::new();
+        server.start_server_blocking(&config).unwrap();
+        let mut client = BlockingSharedMemory::new();
+        client.start_client_blocking(&config).unwrap();
+
+        for id in 0..3u64 {
+            let msg = Message::new(id, vec![0u8; 32], MessageType::OneWay);
+            client.send_blocking(&msg).unwrap();
+        }
+        assert_eq!(server.receive_blocking().unwrap().id, 0);
+
+        // Give the next record the sequence number of an earlier pass
+        let ring = unsafe { &*server.ring_buffer.unwrap() };
+        let read_pos = ring.read_pos().load(Ordering::Acquire);
+        unsafe { *ring.data_ptr().add(read_pos + 4) ^= 0xFF };
+
+        // It is dropped along with everything queued behind it
+        assert!(server.try_receive_blocking().unwrap().is_none());
+        assert_eq!(server.stats()["stale_records"], 1);
+        assert_eq!(server.stats()["torn_records"], 0);
+
+        let msg = Message::new(3, vec![0u8; 32], MessageType::OneWay);
+        client.send_blocking(&msg).unwrap();
+        assert_eq!(server.receive_blocking().unwrap().id, 3);
+
+        client.close_blocking().unwrap();
+        server.close_blocking().unwrap();
+    }
+
+    #[test]
+    fn test_priority_lanes_deliver_highest_priority_first() {
+        let segment_name = "test_shm_blocking_priority_lanes";
+        let config = TransportConfig {
+            shared_memory_name: segment_name.to_string(),
+            buffer_size: 8192,
+            shm_priority_lanes: true,
+            ..Default::default()
+        };
+
+        let (sent_tx, sent_rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let server_config = config.clone();
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingSharedMemory::new();
+            server.start_server_blocking(&server_config).unwrap();
+            sent_rx.recv().unwrap();
+
+            let received: Vec<u64> = (0..5)
+                .map(|_| server.receive_blocking().unwrap().id)
+                .collect();
+            assert_eq!(received, [1, 4, 2, 0, 3]);
+            assert!(server.try_receive_blocking().unwrap().is_none());
+
+            done_tx.send(()).unwrap();
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(200));
+
+        let mut client = BlockingSharedMemory::new();
+        client.start_client_blocking(&config).unwrap();
+        for (id, priority) in [0u8, 6, 3, 0, 6].into_iter().enumerate() {
+            let msg =
+                Message::new(id as u64, vec![0u8; 32], MessageType::OneWay).with_priority(priority);
+            client.send_blocking(&msg).unwrap();
+        }
+        sent_tx.send(()).unwrap();
+        done_rx.recv().unwrap();
+        client.close_blocking().unwrap();
+
+        server_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_ring_record_splits_head_across_the_wrap() {
+        let ring: Vec<u8> = (0..64).collect();
+        // The head starts 8 bytes before the end and continues at the start
+        let record = unsafe { RingRecord::copy_out(ring.as_ptr(), 64, 56, 50, 40) };
+        let head: Vec<u8> = (56..64).chain(0..32).collect();
+        assert_eq!(record.head[..], head[..]);
+        assert_eq!(record.body, (32..42).collect::<Vec<u8>>());
+
+        let record = unsafe { RingRecord::copy_out(ring.as_ptr(), 64, 60, 6, 0) };
+        assert_eq!(record.body, [60, 61, 62, 63, 0, 1]);
+    }
+
+    #[test]
+    fn test_zero_copy_records_round_trip_across_wraps() {
+        let segment_name = "test_shm_blocking_zero_copy";
+        let config = TransportConfig {
+            shared_memory_name: segment_name.to_string(),
+            buffer_size: 1024,
+            shm_zero_copy: true,
+            ..Default::default()
+        };
+
+        let server_config = config.clone();
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingSharedMemory::new();
+            server.start_server_blocking(&server_config).unwrap();
+            for id in 0..40u64 {
+                let message = server.receive_blocking().unwrap();
+                assert_eq!(message.id, id);
+                assert_eq!(message.priority, (id % 4) as u8);
+                assert_eq!(message.payload, vec![id as u8; (id as usize * 37) % 300]);
+                assert_ne!(message.timestamp, 0);
+            }
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(200));
+
+        let mut client = BlockingSharedMemory::new();
+        client.start_client_blocking(&config).unwrap();
+        // Sizes that don't divide the ring, so records wrap mid-payload
+        for id in 0..40u64 {
+            let payload = vec![id as u8; (id as usize * 37) % 300];
+            let msg = Message::new(id, payload, MessageType::OneWay).with_priority((id % 4) as u8);
+            client.send_blocking(&msg).unwrap();
+        }
+        server_handle.join().unwrap();
+        client.close_blocking().unwrap();
+    }
+
+    #[test]
+    fn test_message_capacity_limit_round_trips_and_rejects_oversized() {
+        let segment_name = "test_shm_blocking_message_capacity";
+        let config = TransportConfig {
+            shared_memory_name: segment_name.to_string(),
+            buffer_size: 4096,
+            shm_capacity_messages: Some(2),
+            ..Default::default()
+        };
+
+        let server_config = config.clone();
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingSharedMemory::new();
+            server.start_server_blocking(&server_config).unwrap();
+            for id in 0..20u64 {
+                // Slow reader: the sender must block on the two-message limit
+                thread::sleep(Duration::from_millis(1));
+                assert_eq!(server.receive_blocking().unwrap().id, id);
+            }
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(200));
+
+        let mut client = BlockingSharedMemory::new();
+        client.start_client_blocking(&config).unwrap();
+
+        // A record larger than the whole ring fails instead of waiting forever
+        let oversized = Message::new(0, vec![0u8; 8192], MessageType::OneWay);
+        assert!(client.send_blocking(&oversized).is_err());
+
+        for id in 0..20u64 {
+            let msg = Message::new(id, vec![0u8; 64], MessageType::OneWay);
+            client.send_blocking(&msg).unwrap();
+        }
+        client.close_blocking().unwrap();
+
+        server_handle.join().unwrap();
+    }
+
     #[test]
     #[ignore] // TODO: Ring buffer needs bidirectional support for round-trip
     fn test_round_trip_communication() {
@@ -1446,6 +2397,11 @@ mod tests {
             client.send_blocking(&msg).unwrap();
         }
 
+        // The payloads alone fill the ring six times over
+        let stats = client.stats();
+        assert!(stats["ring_wraps"] >= msg_count * msg_size as u64 / buffer_size as u64);
+        assert!(stats["ring_wraps"] < msg_count);
+
         client.close_blocking().unwrap();
         server_handle.join().unwrap();
     }
@@ -49,7 +49,7 @@ use tracing::{debug, trace};
 /// Set to 8KB to match typical IPC benchmark message sizes.
 /// This is large enough for most tests while keeping shared memory
 /// segments small for reliable cross-process initialization.
-const MAX_PAYLOAD_SIZE: usize = 8192; // 8 KB
+pub(crate) const MAX_PAYLOAD_SIZE: usize = 8192; // 8 KB
 
 /// Raw message structure stored directly in shared memory.
 ///
@@ -130,6 +130,15 @@ struct RawSharedMessage {
     /// Stored as u32 for stable memory layout.
     message_type: u32,
 
+    /// Time to live after `timestamp` in nanoseconds (0: no deadline).
+    ttl_ns: u64,
+
+    /// Delivery priority (carried through; one slot has no lanes).
+    priority: u8,
+
+    /// Server receive time of the request a reply answers (0: not a reply).
+    server_receive_ns: u64,
+
     /// Coordination flag.
     ///
     /// - `0`: No message ready (receiver should wait)
@@ -206,6 +215,9 @@ impl RawSharedMessage {
         self.ready = 0;
         self.client_ready = 0;
         self.payload_len = 0;
+        self.ttl_ns = 0;
+        self.priority = 0;
+        self.server_receive_ns = 0;
 
         debug!("RawSharedMessage initialized successfully (mutex + cond var)");
         Ok(())
@@ -573,6 +585,9 @@ impl BlockingTransport for BlockingSharedMemoryDirect {
             (*ptr).id = message.id;
             (*ptr).timestamp = timestamp_ns;
             (*ptr).message_type = message.message_type as u32;
+            (*ptr).ttl_ns = message.ttl_ns.unwrap_or(0);
+            (*ptr).priority = message.priority;
+            (*ptr).server_receive_ns = message.server_receive_ns;
 
             // Copy only the actual payload bytes (variable length)
             let len = message.payload.len().min(MAX_PAYLOAD_SIZE);
@@ -672,6 +687,9 @@ impl BlockingTransport for BlockingSharedMemoryDirect {
             let timestamp = (*ptr).timestamp;
             let message_type_u32 = (*ptr).message_type;
             let message_type = <MessageType as From<u32>>::from(message_type_u32);
+            let ttl_ns = Some((*ptr).ttl_ns).filter(|&ttl| ttl != 0);
+            let priority = (*ptr).priority;
+            let server_receive_ns = (*ptr).server_receive_ns;
             let payload_len = (*ptr).payload_len;
 
             // PERF: Allocate payload without zero-filling. The original
@@ -715,7 +733,11 @@ impl BlockingTransport for BlockingSharedMemoryDirect {
                 timestamp,
                 payload,
                 message_type,
+                ttl_ns,
+                priority,
+                server_receive_ns,
                 receive_time_ns,
+                first_byte_time_ns: 0,
             }
         };
 
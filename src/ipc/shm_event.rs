@@ -0,0 +1,111 @@
+//! Event-object doorbells for the shared memory ring on Windows.
+//!
+//! Unix builds wake the other side of a ring through process-shared pthread
+//! condition variables in the segment itself. Windows has no process-shared
+//! condition variable, so each ring gets a pair of named auto-reset event
+//! objects instead: the writer rings `data` after publishing a record and
+//! the reader rings `space` after consuming one.
+//!
+//! An event stays signalled until a waiter consumes it, so a ring that
+//! happens between a waiter's check of the ring and its wait is not lost.
+//! Waits are still bounded so the shutdown flag is noticed even if the peer
+//! exited without ringing.
+
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+use windows_sys::Win32::System::Threading::{CreateEventW, SetEvent, WaitForSingleObject};
+
+/// One named auto-reset event.
+struct Event {
+    handle: HANDLE,
+}
+
+impl Event {
+    /// Create the event `name`, or open it if the peer already created it.
+    fn open_or_create(name: &str) -> Result<Self> {
+        let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+        // Auto-reset, initially not signalled
+        let handle = unsafe { CreateEventW(std::ptr::null(), 0, 0, wide.as_ptr()) };
+        if handle.is_null() {
+            return Err(anyhow!(
+                "Failed to create event {}: {}",
+                name,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(Self { handle })
+    }
+
+    fn set(&self) {
+        unsafe {
+            SetEvent(self.handle);
+        }
+    }
+
+    /// Wait up to `timeout` for the event; `true` if it was signalled.
+    fn wait(&self, timeout: Duration) -> bool {
+        let millis = timeout.as_millis().min(u32::MAX as u128) as u32;
+        unsafe { WaitForSingleObject(self.handle, millis) == WAIT_OBJECT_0 }
+    }
+}
+
+impl Drop for Event {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// The data/space event pair of one shared memory ring.
+pub(crate) struct ShmDoorbells {
+    data: Event,
+    space: Event,
+}
+
+// Safety: event handles may be used from any thread
+unsafe impl Send for ShmDoorbells {}
+unsafe impl Sync for ShmDoorbells {}
+
+impl ShmDoorbells {
+    /// Longest single wait before the caller rechecks the ring and the
+    /// shutdown flag.
+    pub(crate) const RECHECK: Duration = Duration::from_millis(50);
+
+    /// Open the doorbells of the segment `segment_name`, creating them if
+    /// this side is first. Both sides derive the same session-local names.
+    pub(crate) fn open(segment_name: &str) -> Result<Self> {
+        let base = segment_name.trim_start_matches('/').replace('\\', "_");
+        Ok(Self {
+            data: Event::open_or_create(&format!("Local\\{}_data", base))?,
+            space: Event::open_or_create(&format!("Local\\{}_space", base))?,
+        })
+    }
+
+    /// Tell the reader a record was published.
+    pub(crate) fn ring_data(&self) {
+        self.data.set();
+    }
+
+    /// Tell the writer a record was consumed.
+    pub(crate) fn ring_space(&self) {
+        self.space.set();
+    }
+
+    /// Wait for the writer to publish a record; `false` on timeout.
+    pub(crate) fn wait_data(&self) -> bool {
+        self.data.wait(Self::RECHECK)
+    }
+
+    /// Wait for the reader to free space; `false` on timeout.
+    pub(crate) fn wait_space(&self) -> bool {
+        self.space.wait(Self::RECHECK)
+    }
+
+    /// Wake both sides, as on shutdown.
+    pub(crate) fn ring_all(&self) {
+        self.data.set();
+        self.space.set();
+    }
+}
@@ -0,0 +1,193 @@
+//! Shared memory segments backed by a file on hugetlbfs.
+//!
+//! With `--shm-hugetlbfs <DIR>` the blocking ring lives in a file created in
+//! a hugetlbfs mount instead of a POSIX shared memory object. This is how
+//! hosts that pre-reserve huge pages (often 1 GB ones, for DPDK-style
+//! workloads) hand them out, so the benchmark can run on the memory
+//! configuration those workloads actually use.
+//!
+//! - The file is named after the segment (`<DIR>/<segment>`) and its length
+//!   is rounded up to the mount's page size, which is what hugetlbfs
+//!   reports as its block size.
+//! - Huge pages are reserved when the file is mapped, so a pool that is too
+//!   small fails there with an error rather than with SIGBUS later on.
+//! - The creator removes a stale file left by an earlier run before
+//!   creating its own. The opener removes the file as soon as it has mapped
+//!   it, so the pages go back to the pool once both sides unmap, even if
+//!   neither reaches `close`. The creator removes it again on drop for the
+//!   case where no peer ever opened it.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// A mapping of a file on hugetlbfs.
+pub(crate) struct HugetlbfsSegment {
+    ptr: *mut u8,
+    len: usize,
+    path: PathBuf,
+    owner: bool,
+}
+
+impl HugetlbfsSegment {
+    /// Create and map the file for `segment_name` in the hugetlbfs mount
+    /// `dir`, large enough for `size` bytes.
+    pub(crate) fn create(dir: &Path, segment_name: &str, size: usize) -> Result<Self> {
+        let page_size = hugetlbfs_page_size(dir)?;
+        let path = segment_path(dir, segment_name);
+        let _ = std::fs::remove_file(&path);
+        let len = round_up(size, page_size);
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut segment = Self {
+            ptr: std::ptr::null_mut(),
+            len,
+            path,
+            owner: true,
+        };
+        file.set_len(len as u64).with_context(|| {
+            format!("Failed to size {} to {} bytes", segment.path.display(), len)
+        })?;
+        segment.ptr = map(&file, len).map_err(|e| {
+            anyhow!(
+                "Failed to map {} bytes of huge pages ({} byte pages) from {}: {}. \
+                 Reserve more pages in the pool or lower --buffer-size",
+                len,
+                page_size,
+                segment.path.display(),
+                e
+            )
+        })?;
+        Ok(segment)
+    }
+
+    /// Map the file for `segment_name` that the peer created in `dir`, then
+    /// remove its name.
+    pub(crate) fn open(dir: &Path, segment_name: &str, size: usize) -> Result<Self> {
+        let path = segment_path(dir, segment_name);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let len = file.metadata()?.len() as usize;
+        if len < size {
+            bail!(
+                "{} holds {} bytes, {} are needed; both sides must use the same ring size",
+                path.display(),
+                len,
+                size
+            );
+        }
+        let ptr =
+            map(&file, len).map_err(|e| anyhow!("Failed to map {}: {}", path.display(), e))?;
+        let _ = std::fs::remove_file(&path);
+        Ok(Self {
+            ptr,
+            len,
+            path,
+            owner: false,
+        })
+    }
+
+    /// Start of the mapping.
+    pub(crate) fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+}
+
+impl Drop for HugetlbfsSegment {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe {
+                libc::munmap(self.ptr as *mut libc::c_void, self.len);
+            }
+        }
+        if self.owner {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Path of the file backing `segment_name` in `dir`.
+fn segment_path(dir: &Path, segment_name: &str) -> PathBuf {
+    dir.join(segment_name.trim_start_matches('/'))
+}
+
+fn round_up(size: usize, page_size: usize) -> usize {
+    (size.max(1) + page_size - 1) / page_size * page_size
+}
+
+/// Page size of the hugetlbfs mount `dir`, or an error if `dir` is not on
+/// hugetlbfs.
+fn hugetlbfs_page_size(dir: &Path) -> Result<usize> {
+    let c_dir = CString::new(dir.as_os_str().as_bytes())?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_dir.as_ptr(), &mut stat) } != 0 {
+        bail!(
+            "Cannot use {} for --shm-hugetlbfs: {}",
+            dir.display(),
+            std::io::Error::last_os_error()
+        );
+    }
+    #[allow(clippy::unnecessary_cast)] // f_type's type varies by target
+    if stat.f_type as i64 != libc::HUGETLBFS_MAGIC as i64 {
+        bail!(
+            "{} is not on hugetlbfs; mount one with `mount -t hugetlbfs -o pagesize=1G none <DIR>`",
+            dir.display()
+        );
+    }
+    Ok(stat.f_bsize as usize)
+}
+
+fn map(file: &std::fs::File, len: usize) -> std::io::Result<*mut u8> {
+    use std::os::unix::io::AsRawFd;
+
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(ptr as *mut u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_length_rounds_up_to_pages() {
+        const HUGE_2M: usize = 2 * 1024 * 1024;
+        assert_eq!(round_up(1, HUGE_2M), HUGE_2M);
+        assert_eq!(round_up(HUGE_2M, HUGE_2M), HUGE_2M);
+        assert_eq!(round_up(HUGE_2M + 1, HUGE_2M), 2 * HUGE_2M);
+        assert_eq!(
+            segment_path(Path::new("/mnt/huge"), "/ipc_benchmark_x"),
+            Path::new("/mnt/huge/ipc_benchmark_x")
+        );
+    }
+
+    #[test]
+    fn test_rejects_directories_off_hugetlbfs() {
+        let dir = std::env::temp_dir();
+        let err = HugetlbfsSegment::create(&dir, "ipc_test_not_huge", 4096)
+            .err()
+            .expect("temp dir is not hugetlbfs");
+        assert!(err.to_string().contains("not on hugetlbfs"));
+        assert!(!segment_path(&dir, "ipc_test_not_huge").exists());
+    }
+}
@@ -0,0 +1,212 @@
+//! POSIX named semaphore signaling for the shared memory ring.
+//!
+//! With `--shm-wait semaphore` the blocking ring is coordinated the way many
+//! legacy producer/consumer implementations do it, with `sem_post` and
+//! `sem_wait` on a pair of named semaphores instead of a process-shared
+//! mutex and condition variables:
+//!
+//! - `items` counts published records. The writer posts it once per record
+//!   and the reader waits on it once per record, so the reader never touches
+//!   the ring until a record is there.
+//! - `space` wakes a writer blocked on a full ring. The reader posts it after
+//!   consuming a record if it is not already posted, so it never counts past
+//!   one however long the run.
+//!
+//! The ring's indices are single-producer/single-consumer atomics, so no
+//! lock is taken around the copy itself. The semaphores are named after the
+//! segment (`/<segment>_items`, `/<segment>_space`); the side that creates
+//! the segment creates them and unlinks them again.
+
+use anyhow::{anyhow, Result};
+use std::ffi::CString;
+
+/// One named semaphore.
+struct Semaphore {
+    sem: *mut libc::sem_t,
+    name: CString,
+}
+
+impl Semaphore {
+    /// Create `name` with a count of zero, replacing a stale one left by an
+    /// earlier run.
+    fn create(name: &str) -> Result<Self> {
+        let c_name = CString::new(name)?;
+        unsafe {
+            libc::sem_unlink(c_name.as_ptr());
+        }
+        let sem = unsafe {
+            libc::sem_open(
+                c_name.as_ptr(),
+                libc::O_CREAT | libc::O_EXCL,
+                0o600 as libc::c_uint,
+                0 as libc::c_uint,
+            )
+        };
+        Self::checked(sem, c_name, "create")
+    }
+
+    /// Open `name`, created by the peer.
+    fn open(name: &str) -> Result<Self> {
+        let c_name = CString::new(name)?;
+        let sem = unsafe { libc::sem_open(c_name.as_ptr(), 0) };
+        Self::checked(sem, c_name, "open")
+    }
+
+    fn checked(sem: *mut libc::sem_t, name: CString, action: &str) -> Result<Self> {
+        if sem == libc::SEM_FAILED {
+            return Err(anyhow!(
+                "Failed to {} semaphore {}: {}",
+                action,
+                name.to_string_lossy(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(Self { sem, name })
+    }
+
+    fn post(&self) {
+        unsafe {
+            libc::sem_post(self.sem);
+        }
+    }
+
+    /// Post unless already posted, keeping the count at one at most.
+    fn post_once(&self) {
+        let mut value: libc::c_int = 0;
+        let read = unsafe { libc::sem_getvalue(self.sem, &mut value) };
+        if read != 0 || value == 0 {
+            self.post();
+        }
+    }
+
+    /// Wait for a post, resuming after signal interruptions.
+    fn wait(&self) -> Result<()> {
+        loop {
+            if unsafe { libc::sem_wait(self.sem) } == 0 {
+                return Ok(());
+            }
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                return Err(anyhow!(
+                    "sem_wait on {} failed: {}",
+                    self.name.to_string_lossy(),
+                    err
+                ));
+            }
+        }
+    }
+}
+
+impl Drop for Semaphore {
+    fn drop(&mut self) {
+        unsafe {
+            libc::sem_close(self.sem);
+        }
+    }
+}
+
+/// The items/space semaphore pair of one shared memory ring.
+pub(crate) struct ShmSemaphores {
+    items: Semaphore,
+    space: Semaphore,
+    owner: bool,
+}
+
+// Safety: named semaphores may be posted and waited on from any thread
+unsafe impl Send for ShmSemaphores {}
+unsafe impl Sync for ShmSemaphores {}
+
+impl ShmSemaphores {
+    fn names(segment_name: &str) -> (String, String) {
+        let base = segment_name.trim_start_matches('/');
+        (format!("/{}_items", base), format!("/{}_space", base))
+    }
+
+    /// Create the semaphores of the segment `segment_name`; they are
+    /// unlinked when the returned value is dropped.
+    pub(crate) fn create(segment_name: &str) -> Result<Self> {
+        let (items, space) = Self::names(segment_name);
+        Ok(Self {
+            items: Semaphore::create(&items)?,
+            space: Semaphore::create(&space)?,
+            owner: true,
+        })
+    }
+
+    /// Open the semaphores the creator of `segment_name` made.
+    pub(crate) fn open(segment_name: &str) -> Result<Self> {
+        let (items, space) = Self::names(segment_name);
+        Ok(Self {
+            items: Semaphore::open(&items)?,
+            space: Semaphore::open(&space)?,
+            owner: false,
+        })
+    }
+
+    /// Announce one published record.
+    pub(crate) fn post_item(&self) {
+        self.items.post();
+    }
+
+    /// Wait for a record to be published (or for a shutdown wakeup).
+    pub(crate) fn wait_item(&self) -> Result<()> {
+        self.items.wait()
+    }
+
+    /// Wake a writer waiting for space.
+    pub(crate) fn post_space(&self) {
+        self.space.post_once();
+    }
+
+    /// Wait for the reader to consume a record (or for a shutdown wakeup).
+    pub(crate) fn wait_space(&self) -> Result<()> {
+        self.space.wait()
+    }
+
+    /// Wake both sides so they notice a shutdown.
+    pub(crate) fn wake_all(&self) {
+        self.items.post();
+        self.space.post();
+    }
+}
+
+impl Drop for ShmSemaphores {
+    fn drop(&mut self) {
+        if self.owner {
+            unsafe {
+                libc::sem_unlink(self.items.name.as_ptr());
+                libc::sem_unlink(self.space.name.as_ptr());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_count_and_space_stays_binary() {
+        let name = format!("ipc_benchmark_sem_test_{}", std::process::id());
+        let creator = ShmSemaphores::create(&name).unwrap();
+        let peer = ShmSemaphores::open(&name).unwrap();
+
+        // Every published record is counted
+        creator.post_item();
+        creator.post_item();
+        peer.wait_item().unwrap();
+        peer.wait_item().unwrap();
+
+        // Repeated space posts collapse into one wakeup
+        peer.post_space();
+        peer.post_space();
+        let mut value: libc::c_int = -1;
+        unsafe { libc::sem_getvalue(creator.space.sem, &mut value) };
+        assert_eq!(value, 1);
+        creator.wait_space().unwrap();
+
+        drop(peer);
+        drop(creator);
+        assert!(ShmSemaphores::open(&name).is_err());
+    }
+}
@@ -1,4 +1,6 @@
-use super::{ConnectionId, IpcError, IpcTransport, Message, TransportConfig, TransportState};
+use super::{
+    framing, ConnectionId, IpcError, IpcTransport, Message, TransportConfig, TransportState,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -6,11 +8,10 @@ use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::timeout;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, warn, Instrument};
 
 /// TCP Socket transport implementation with multi-client support
 pub struct TcpSocketTransport {
@@ -24,7 +25,11 @@ pub struct TcpSocketTransport {
     address: Option<SocketAddr>,
     message_receiver: Option<mpsc::Receiver<(ConnectionId, Message)>>,
     buffer_size: usize,
+    keepalive: Option<Duration>,
     has_warned_backpressure: bool,
+    first_byte_timestamps: bool,
+    fragment_writes: Option<usize>,
+    frame_checksums: bool,
 }
 
 impl Default for TcpSocketTransport {
@@ -45,49 +50,46 @@ impl TcpSocketTransport {
             address: None,
             message_receiver: None,
             buffer_size: 8192, // Default buffer size
+            keepalive: None,
             has_warned_backpressure: false,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
         }
     }
 
     /// Read a message from the TCP stream
-    async fn read_message(stream: &mut TcpStream) -> Result<Message> {
-        // Read message length (4 bytes)
-        let mut len_bytes = [0u8; 4];
-        stream.read_exact(&mut len_bytes).await?;
-        let message_len = u32::from_le_bytes(len_bytes) as usize;
-
-        // Validate message length
-        if message_len > 16 * 1024 * 1024 {
-            return Err(anyhow!("Message too large: {} bytes", message_len));
-        }
-
-        // Read message data
-        let mut message_data = vec![0u8; message_len];
-        stream.read_exact(&mut message_data).await?;
-
-        // Deserialize message
-        Message::from_bytes(&message_data)
+    ///
+    /// Frames that fail their checksum are skipped (see [`framing`]). With
+    /// `first_byte_timestamps`, stamps `Message::first_byte_time_ns` as soon
+    /// as the frame header has arrived.
+    async fn read_message(stream: &mut TcpStream, first_byte_timestamps: bool) -> Result<Message> {
+        let (body, first_byte_time_ns) =
+            framing::read_frame_async(stream, framing::MAX_FRAME_LEN, first_byte_timestamps)
+                .await?;
+        let mut message = Message::from_bytes(&body)?;
+        message.first_byte_time_ns = first_byte_time_ns;
+        Ok(message)
     }
 
     /// Write a message to the TCP stream.
     ///
     /// Unlike SHM and PMQ, the timestamp is not refreshed
     /// after serialization. The gap between `to_bytes()` and
-    /// the kernel `write_all()` is only a length-prefix write
+    /// the kernel `write_all()` is only a short frame header write
     /// plus the data write (nanoseconds of CPU work, no
     /// userspace backpressure loop or async scheduling hop).
     /// This makes the timestamp error negligible for TCP.
-    async fn write_message(stream: &mut TcpStream, message: &Message) -> Result<(), IpcError> {
+    async fn write_message(
+        stream: &mut TcpStream,
+        message: &Message,
+        frame_checksums: bool,
+        fragment_writes: Option<usize>,
+    ) -> Result<(), IpcError> {
         const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
         let message_bytes = message.to_bytes().map_err(IpcError::Generic)?;
-        let message_len = message_bytes.len() as u32;
-
-        let write_fut = async {
-            stream.write_all(&message_len.to_le_bytes()).await?;
-            stream.write_all(&message_bytes).await?;
-            stream.flush().await?;
-            Ok(()) as Result<(), std::io::Error>
-        };
+        let write_fut =
+            framing::write_frame_async(stream, &message_bytes, frame_checksums, fragment_writes);
 
         // Justification: Prevent indefinite blocking on send operations when the receiver is unresponsive,
         // which is a clear sign of backpressure. This timeout ensures the system remains responsive.
@@ -161,7 +163,7 @@ impl TcpSocketTransport {
 
         // Read messages from this connection
         loop {
-            match Self::read_message(&mut stream).await {
+            match Self::read_message(&mut stream, false).await {
                 Ok(message) => {
                     debug!(
                         "Received message {} from connection {}",
@@ -189,6 +191,26 @@ impl TcpSocketTransport {
     }
 }
 
+/// Bind a listener, restricted to `device` when one is given.
+async fn bind_listener(addr: &str, device: Option<&str>) -> Result<TcpListener> {
+    let Some(device) = device else {
+        return Ok(TcpListener::bind(addr).await?);
+    };
+    let local: SocketAddr = addr
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse address {}: {}", addr, e))?;
+    let socket = if local.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    super::apply_bind_device((&socket).into(), Some(device))
+        .map_err(|e| anyhow!("Failed to bind TCP listener to device '{}': {}", device, e))?;
+    socket.bind(local)?;
+    Ok(socket.listen(1024)?)
+}
+
 #[async_trait]
 impl IpcTransport for TcpSocketTransport {
     async fn start_server(&mut self, config: &TransportConfig) -> Result<()> {
@@ -198,11 +220,14 @@ impl IpcTransport for TcpSocketTransport {
         self.state = TransportState::Initializing;
 
         // Create listener
-        let listener = TcpListener::bind(&addr).await?;
+        let listener = bind_listener(&addr, config.bind_device.as_deref()).await?;
         let local_addr = listener.local_addr()?;
         self.address = Some(local_addr);
         self.listener = Some(listener);
         self.buffer_size = config.buffer_size; // Store buffer size for later use
+        self.keepalive = config.tcp_keepalive;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
 
         debug!("TCP Socket server listening on: {}", local_addr);
         self.state = TransportState::Connected;
@@ -214,11 +239,27 @@ impl IpcTransport for TcpSocketTransport {
     async fn start_client(&mut self, config: &TransportConfig) -> Result<()> {
         let addr = format!("{}:{}", config.host, config.port);
         debug!("Starting TCP Socket client connecting to: {}", addr);
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
 
         self.state = TransportState::Initializing;
 
-        // Connect to server
-        let stream = TcpStream::connect(&addr).await?;
+        // Connect to server, from a chosen interface or address if requested
+        let stream = if config.bind_device.is_some() || config.source_addr.is_some() {
+            let peer = tokio::net::lookup_host(&addr)
+                .await?
+                .next()
+                .ok_or_else(|| anyhow!("No address found for {}", addr))?;
+            let socket = if peer.is_ipv4() {
+                TcpSocket::new_v4()?
+            } else {
+                TcpSocket::new_v6()?
+            };
+            super::apply_tcp_source((&socket).into(), config)?;
+            socket.connect(peer).await?
+        } else {
+            TcpStream::connect(&addr).await?
+        };
         self.address = Some(stream.peer_addr()?);
 
         // Configure socket options for low latency
@@ -227,6 +268,7 @@ impl IpcTransport for TcpSocketTransport {
         socket.set_nodelay(true)?;
         socket.set_recv_buffer_size(config.buffer_size)?;
         socket.set_send_buffer_size(config.buffer_size)?;
+        super::apply_tcp_keepalive((&socket).into(), config.tcp_keepalive)?;
 
         self.stream = Some(TcpStream::from_std(std_stream)?);
         self.state = TransportState::Connected;
@@ -260,6 +302,7 @@ impl IpcTransport for TcpSocketTransport {
                 socket.set_nodelay(true)?;
                 socket.set_recv_buffer_size(self.buffer_size)?;
                 socket.set_send_buffer_size(self.buffer_size)?;
+                super::apply_tcp_keepalive((&socket).into(), self.keepalive)?;
 
                 self.stream = Some(TcpStream::from_std(std_stream)?);
             }
@@ -267,7 +310,9 @@ impl IpcTransport for TcpSocketTransport {
 
         if let Some(ref mut stream) = self.stream {
             let start_time = std::time::Instant::now();
-            match Self::write_message(stream, message).await {
+            match Self::write_message(stream, message, self.frame_checksums, self.fragment_writes)
+                .await
+            {
                 Ok(()) => {
                     let elapsed = start_time.elapsed();
                     let mut backpressure_detected = false;
@@ -326,13 +371,14 @@ impl IpcTransport for TcpSocketTransport {
                 socket.set_nodelay(true)?;
                 socket.set_recv_buffer_size(self.buffer_size)?;
                 socket.set_send_buffer_size(self.buffer_size)?;
+                super::apply_tcp_keepalive((&socket).into(), self.keepalive)?;
 
                 self.stream = Some(TcpStream::from_std(std_stream)?);
             }
         }
 
         if let Some(ref mut stream) = self.stream {
-            let message = Self::read_message(stream).await?;
+            let message = Self::read_message(stream, self.first_byte_timestamps).await?;
             debug!("Received message {} via TCP Socket", message.id);
             Ok(message)
         } else {
@@ -340,6 +386,19 @@ impl IpcTransport for TcpSocketTransport {
         }
     }
 
+    async fn try_receive(&mut self) -> Result<Option<Message>> {
+        #[cfg(unix)]
+        if let Some(ref mut stream) = self.stream {
+            use std::os::unix::io::AsRawFd;
+            if super::socket_has_pending_frame(stream.as_raw_fd()) {
+                return Self::read_message(stream, self.first_byte_timestamps)
+                    .await
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+
     async fn close(&mut self) -> Result<()> {
         debug!("Closing TCP Socket transport");
 
@@ -371,6 +430,15 @@ impl IpcTransport for TcpSocketTransport {
         16 * 1024 * 1024 // 16MB for TCP
     }
 
+    fn local_port(&self) -> Option<u16> {
+        let listener = self.listener.as_ref()?;
+        listener.local_addr().ok().map(|addr| addr.port())
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.frame_checksums = on;
+    }
+
     // NEW MULTI-CLIENT INTERFACE
 
     fn supports_multiple_connections(&self) -> bool {
@@ -387,7 +455,7 @@ impl IpcTransport for TcpSocketTransport {
         self.state = TransportState::Initializing;
 
         // Create listener
-        let listener = TcpListener::bind(&addr).await?;
+        let listener = bind_listener(&addr, config.bind_device.as_deref()).await?;
         let local_addr = listener.local_addr()?;
         self.address = Some(local_addr);
 
@@ -400,57 +468,76 @@ impl IpcTransport for TcpSocketTransport {
         let connections = self.connections.clone();
         let next_connection_id = self.next_connection_id.clone();
         let buffer_size = config.buffer_size; // Clone the value to avoid borrowing issues
+        let keepalive = config.tcp_keepalive;
 
         // Start accept loop
-        tokio::spawn(async move {
-            loop {
-                match listener.accept().await {
-                    Ok((stream, client_addr)) => {
-                        let connection_id = next_connection_id.fetch_add(1, Ordering::Relaxed);
-                        debug!(
-                            "Accepted TCP connection {} from: {}",
-                            connection_id, client_addr
-                        );
+        tokio::spawn(
+            async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, client_addr)) => {
+                            let connection_id = next_connection_id.fetch_add(1, Ordering::Relaxed);
+                            debug!(
+                                "Accepted TCP connection {} from: {}",
+                                connection_id, client_addr
+                            );
 
-                        // Configure socket options for low latency.
-                        // Failure here means the socket would run
-                        // without TCP_NODELAY, which skews benchmark
-                        // results, so we drop the connection instead.
-                        if let Ok(std_stream) = stream.into_std() {
-                            let socket =
-                                socket2::Socket::from(std_stream.try_clone().unwrap_or_else(|e| {
-                                    panic!(
-                                        "Failed to clone TCP stream for \
+                            // Configure socket options for low latency.
+                            // Failure here means the socket would run
+                            // without TCP_NODELAY, which skews benchmark
+                            // results, so we drop the connection instead.
+                            if let Ok(std_stream) = stream.into_std() {
+                                let socket = socket2::Socket::from(
+                                    std_stream.try_clone().unwrap_or_else(|e| {
+                                        panic!(
+                                            "Failed to clone TCP stream for \
                                          socket tuning on connection {}: {}",
+                                            connection_id, e
+                                        );
+                                    }),
+                                );
+                                socket.set_nodelay(true).unwrap_or_else(|e| {
+                                    warn!(
+                                        "set_nodelay failed on connection {}: {}",
                                         connection_id, e
                                     );
-                                }));
-                            socket.set_nodelay(true).unwrap_or_else(|e| {
-                                warn!("set_nodelay failed on connection {}: {}", connection_id, e);
-                            });
-                            let _ = socket.set_recv_buffer_size(buffer_size);
-                            let _ = socket.set_send_buffer_size(buffer_size);
-
-                            if let Ok(tokio_stream) = TcpStream::from_std(std_stream) {
-                                let handler_sender = message_sender.clone();
-                                let handler_connections = connections.clone();
-
-                                tokio::spawn(Self::handle_connection(
-                                    connection_id,
-                                    tokio_stream,
-                                    handler_sender,
-                                    handler_connections,
-                                ));
+                                });
+                                let _ = socket.set_recv_buffer_size(buffer_size);
+                                let _ = socket.set_send_buffer_size(buffer_size);
+                                if let Err(e) =
+                                    super::apply_tcp_keepalive((&socket).into(), keepalive)
+                                {
+                                    warn!(
+                                        "Failed to enable keepalive on connection {}: {}",
+                                        connection_id, e
+                                    );
+                                }
+
+                                if let Ok(tokio_stream) = TcpStream::from_std(std_stream) {
+                                    let handler_sender = message_sender.clone();
+                                    let handler_connections = connections.clone();
+
+                                    tokio::spawn(
+                                        Self::handle_connection(
+                                            connection_id,
+                                            tokio_stream,
+                                            handler_sender,
+                                            handler_connections,
+                                        )
+                                        .in_current_span(),
+                                    );
+                                }
                             }
                         }
-                    }
-                    Err(e) => {
-                        error!("Failed to accept TCP connection: {}", e);
-                        break;
+                        Err(e) => {
+                            error!("Failed to accept TCP connection: {}", e);
+                            break;
+                        }
                     }
                 }
             }
-        });
+            .in_current_span(),
+        );
 
         self.state = TransportState::Connected;
         Ok(message_receiver)
@@ -464,7 +551,8 @@ impl IpcTransport for TcpSocketTransport {
         let mut conns = self.connections.lock().await;
 
         if let Some(stream) = conns.get_mut(&connection_id) {
-            Self::write_message(stream, message).await?;
+            Self::write_message(stream, message, self.frame_checksums, self.fragment_writes)
+                .await?;
             debug!(
                 "Sent message {} to TCP connection {}",
                 message.id, connection_id
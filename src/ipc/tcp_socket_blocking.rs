@@ -18,8 +18,9 @@
 //!
 //! # Wire Protocol
 //!
-//! Messages are sent with a simple length-prefixed protocol:
-//! 1. Send 4-byte message length (u32, little-endian)
+//! Messages are sent as checksummed frames (see [`crate::ipc::framing`]):
+//! 1. Send an 8-byte header: message length and CRC-32 (u32 each,
+//!    little-endian)
 //! 2. Send serialized message bytes (bincode format)
 //!
 //! This matches the protocol used by the async TCP transport for consistency.
@@ -47,11 +48,12 @@
 //! # }
 //! ```
 
-use crate::ipc::{BlockingTransport, Message, TransportConfig};
-use anyhow::{anyhow, Context, Result};
+use crate::ipc::framing::{self, FrameHeader};
+use crate::ipc::{get_monotonic_time_ns, BlockingTransport, Message, TransportConfig};
+use anyhow::{Context, Result};
 use socket2::{Domain, Socket, Type};
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
 use tracing::{debug, trace};
 
 /// Blocking TCP socket transport.
@@ -64,6 +66,10 @@ use tracing::{debug, trace};
 /// - `listener`: The server socket listener (only used in server mode)
 /// - `stream`: The connected socket stream (used in both client and server
 ///   mode)
+/// - `keepalive`: TCP keepalive idle time applied to connections, if any
+/// - `first_byte_timestamps`: Whether to stamp first-byte arrival on receive
+/// - `fragment_writes`: Piece size frames are written in, if fragmented
+/// - `frame_checksums`: Whether sent frames carry a CRC-32 (`--verify-frames`)
 ///
 /// # Lifecycle
 ///
@@ -80,6 +86,25 @@ pub struct BlockingTcpSocket {
     /// Connected socket stream for sending/receiving data.
     /// Populated after accept() in server mode or connect() in client mode.
     stream: Option<TcpStream>,
+
+    /// TCP keepalive idle time applied to each connection.
+    keepalive: Option<std::time::Duration>,
+
+    /// Record `Message::first_byte_time_ns` on receive.
+    first_byte_timestamps: bool,
+
+    /// Write frames in pieces of at most this many bytes (test mode).
+    fragment_writes: Option<usize>,
+
+    /// Send checked frames, once the handshake has agreed on them.
+    frame_checksums: bool,
+
+    /// `SO_PRIORITY` last set on the stream from `Message::priority`
+    /// (0: the kernel default).
+    priority: u8,
+
+    /// Serialized frame body, reused from send to send
+    send_buffer: Vec<u8>,
 }
 
 impl BlockingTcpSocket {
@@ -103,6 +128,12 @@ impl BlockingTcpSocket {
         Self {
             listener: None,
             stream: None,
+            keepalive: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+            priority: 0,
+            send_buffer: Vec::new(),
         }
     }
 
@@ -114,43 +145,71 @@ impl BlockingTcpSocket {
         Self {
             listener: None,
             stream: Some(stream),
+            keepalive: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+            priority: 0,
+            send_buffer: Vec::new(),
         }
     }
 
     /// Accept a connection if we haven't already.
     /// This is called automatically on first send/receive in server mode.
     fn ensure_connection(&mut self) -> Result<()> {
-        // If we already have a stream, we're connected
-        if self.stream.is_some() {
+        // If we already have a stream, or are not a server, there is
+        // nothing to accept
+        if self.stream.is_some() || self.listener.is_none() {
             return Ok(());
         }
 
-        // If we have a listener, accept a connection
-        if let Some(ref listener) = self.listener {
-            debug!("Accepting connection on TCP server");
-            let (stream, peer_addr) = listener
-                .accept()
-                .context("Failed to accept connection on TCP socket")?;
-
-            // Disable Nagle's algorithm for low latency
-            stream
-                .set_nodelay(true)
-                .context("Failed to set TCP_NODELAY on accepted connection")?;
-
-            debug!("TCP server accepted connection from: {}", peer_addr);
-            self.stream = Some(stream);
-            Ok(())
-        } else {
-            // Not a server or already connected - this is fine
-            Ok(())
-        }
+        debug!("Accepting connection on TCP server");
+        self.stream = Some(self.accept_stream()?);
+        Ok(())
     }
+
+    /// Accept the next client on the listener and configure the stream.
+    fn accept_stream(&self) -> Result<TcpStream> {
+        let listener = self
+            .listener
+            .as_ref()
+            .context("Cannot accept: call start_server_blocking() first")?;
+        let (stream, peer_addr) = listener
+            .accept()
+            .context("Failed to accept connection on TCP socket")?;
+
+        // Disable Nagle's algorithm for low latency
+        stream
+            .set_nodelay(true)
+            .context("Failed to set TCP_NODELAY on accepted connection")?;
+        crate::ipc::apply_tcp_keepalive((&stream).into(), self.keepalive)
+            .context("Failed to enable TCP keepalive on accepted connection")?;
+
+        debug!("TCP server accepted connection from: {}", peer_addr);
+        Ok(stream)
+    }
+}
+
+/// Connect to `addr` with the interface and source address from `config`.
+fn connect_from(addr: &str, config: &TransportConfig) -> Result<TcpStream> {
+    let peer = addr
+        .to_socket_addrs()?
+        .next()
+        .with_context(|| format!("No address found for {}", addr))?;
+    let socket = Socket::new(Domain::for_address(peer), Type::STREAM, None)
+        .context("Failed to create TCP socket")?;
+    crate::ipc::apply_tcp_source((&socket).into(), config)?;
+    socket.connect(&peer.into())?;
+    Ok(socket.into())
 }
 
 impl BlockingTransport for BlockingTcpSocket {
     fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
         let addr = format!("{}:{}", config.host, config.port);
         debug!("Starting blocking TCP server at: {}", addr);
+        self.keepalive = config.tcp_keepalive;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
 
         // Create socket with SO_REUSEADDR to allow immediate port reuse
         // This prevents "Address already in use" errors when tests run quickly
@@ -162,6 +221,12 @@ impl BlockingTransport for BlockingTcpSocket {
             .set_reuse_address(true)
             .context("Failed to set SO_REUSEADDR on socket")?;
 
+        // Restrict the listener to one interface if requested
+        if let Some(device) = config.bind_device.as_deref() {
+            crate::ipc::apply_bind_device((&socket).into(), Some(device))
+                .with_context(|| format!("Failed to bind TCP socket to device '{}'", device))?;
+        }
+
         // Parse and bind to the address
         let socket_addr: std::net::SocketAddr = addr
             .parse()
@@ -196,8 +261,14 @@ impl BlockingTransport for BlockingTcpSocket {
         let addr = format!("{}:{}", config.host, config.port);
         debug!("Starting blocking TCP client, connecting to: {}", addr);
 
-        // Connect to server socket (blocks until connected or fails)
-        let stream = TcpStream::connect(&addr).with_context(|| {
+        // Connect to server socket (blocks until connected or fails),
+        // from a chosen interface or address if requested
+        let connected = if config.bind_device.is_some() || config.source_addr.is_some() {
+            connect_from(&addr, config)
+        } else {
+            TcpStream::connect(&addr).map_err(Into::into)
+        };
+        let stream = connected.with_context(|| {
             format!(
                 "Failed to connect to TCP socket at {}. \
                      Is the server running?",
@@ -210,6 +281,11 @@ impl BlockingTransport for BlockingTcpSocket {
         stream
             .set_nodelay(true)
             .context("Failed to set TCP_NODELAY on client connection")?;
+        self.keepalive = config.tcp_keepalive;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
+        crate::ipc::apply_tcp_keepalive((&stream).into(), self.keepalive)
+            .context("Failed to enable TCP keepalive on client connection")?;
 
         debug!("TCP client connected successfully");
 
@@ -223,6 +299,16 @@ impl BlockingTransport for BlockingTcpSocket {
         // Accept connection if server and not yet accepted
         self.ensure_connection()?;
 
+        // Map the message priority onto SO_PRIORITY when it changes
+        if message.priority != self.priority {
+            if let Some(stream) = &self.stream {
+                crate::ipc::apply_socket_priority(stream.into(), message.priority).with_context(
+                    || format!("Failed to set socket priority {}", message.priority),
+                )?;
+                self.priority = message.priority;
+            }
+        }
+
         let stream = self.stream.as_mut().context(
             "Cannot send: socket not connected. \
                  Call start_server_blocking() or start_client_blocking() first.",
@@ -235,25 +321,26 @@ impl BlockingTransport for BlockingTcpSocket {
         // only the timestamp bytes immediately before send. This ensures any
         // scheduling delays between timestamp capture and send are included in
         // the measured latency.
-        let mut message_with_timestamp = message.clone();
-        message_with_timestamp.timestamp = 0; // Dummy timestamp for pre-serialization
-        let mut serialized =
-            bincode::serialize(&message_with_timestamp).context("Failed to serialize message")?;
+        let serialized = &mut self.send_buffer;
+        message.serialize_unstamped_into(serialized)?;
 
         // Capture timestamp immediately before send and update bytes in buffer
-        message_with_timestamp.set_timestamp_now();
-        let timestamp_bytes = message_with_timestamp.timestamp.to_le_bytes();
+        let timestamp_bytes = get_monotonic_time_ns().to_le_bytes();
         let ts_offset = Message::timestamp_offset();
         serialized[ts_offset].copy_from_slice(&timestamp_bytes);
 
-        // Send immediately - no intervening work
-        let len_bytes = (serialized.len() as u32).to_le_bytes();
+        if let Some(piece) = self.fragment_writes {
+            return framing::write_fragmented(stream, serialized, self.frame_checksums, piece);
+        }
+
+        // Send immediately - only the checksum in between
+        let header = FrameHeader::for_body(serialized, self.frame_checksums).to_bytes();
         stream
-            .write_all(&len_bytes)
+            .write_all(&header)
             .context("Failed to write message length")?;
 
         stream
-            .write_all(&serialized)
+            .write_all(serialized)
             .context("Failed to write message data")?;
 
         stream.flush().context("Failed to flush socket")?;
@@ -273,32 +360,19 @@ impl BlockingTransport for BlockingTcpSocket {
                  Call start_server_blocking() or start_client_blocking() first.",
         )?;
 
-        // Read length prefix (4 bytes, little-endian) to match async protocol
-        let mut len_bytes = [0u8; 4];
-        stream.read_exact(&mut len_bytes).context(
-            "Failed to read message length. \
-                 Connection may be closed or peer disconnected.",
+        // Read the next intact frame, stamping its header's arrival if asked
+        let (buffer, first_byte_time_ns) = framing::read_frame(
+            stream,
+            Self::MAX_MESSAGE_SIZE,
+            self.first_byte_timestamps,
+            framing::read_prefix,
         )?;
-        let len = u32::from_le_bytes(len_bytes) as usize;
-        if len == 0 || len > Self::MAX_MESSAGE_SIZE {
-            return Err(anyhow!(
-                "Invalid message length: {} bytes (allowed: 1..={})",
-                len,
-                Self::MAX_MESSAGE_SIZE
-            ));
-        }
-
-        trace!("Receiving message of {} bytes", len);
-
-        // Read message data
-        let mut buffer = vec![0u8; len];
-        stream
-            .read_exact(&mut buffer)
-            .context("Failed to read message data")?;
+        trace!("Received frame of {} bytes", buffer.len());
 
         // Deserialize message
-        let message: Message =
+        let mut message: Message =
             bincode::deserialize(&buffer).context("Failed to deserialize message")?;
+        message.first_byte_time_ns = first_byte_time_ns;
 
         trace!("Received message ID {}", message.id);
         Ok(message)
@@ -312,24 +386,8 @@ impl BlockingTransport for BlockingTcpSocket {
                  Call start_server_blocking() or start_client_blocking() first.",
         )?;
 
-        let mut len_bytes = [0u8; 4];
-        stream.read_exact(&mut len_bytes).context(
-            "Failed to read message length. \
-                 Connection may be closed or peer disconnected.",
-        )?;
-        let len = u32::from_le_bytes(len_bytes) as usize;
-        if len == 0 || len > Self::MAX_MESSAGE_SIZE {
-            return Err(anyhow!(
-                "Invalid message length: {} bytes (allowed: 1..={})",
-                len,
-                Self::MAX_MESSAGE_SIZE
-            ));
-        }
-
-        let mut buffer = vec![0u8; len];
-        stream
-            .read_exact(&mut buffer)
-            .context("Failed to read message data")?;
+        let (buffer, _) =
+            framing::read_frame(stream, Self::MAX_MESSAGE_SIZE, false, framing::read_prefix)?;
 
         // Capture timestamp after raw read, before deserialization
         let receive_time_ns = crate::ipc::get_monotonic_time_ns();
@@ -340,6 +398,60 @@ impl BlockingTransport for BlockingTcpSocket {
         Ok((message, receive_time_ns))
     }
 
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        let stream = match self.stream.as_ref() {
+            Some(stream) => stream,
+            None => return Ok(None),
+        };
+
+        #[cfg(unix)]
+        let pending = {
+            use std::os::unix::io::AsRawFd;
+            crate::ipc::socket_has_pending_frame(stream.as_raw_fd())
+        };
+
+        #[cfg(not(unix))]
+        let pending = {
+            let mut prefix = [0u8; framing::LENGTH_PREFIX_LEN];
+            stream.set_nonblocking(true)?;
+            let peeked = stream.peek(&mut prefix);
+            stream.set_nonblocking(false)?;
+            match peeked {
+                Ok(n) => n == prefix.len(),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => false,
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        if !pending {
+            return Ok(None);
+        }
+        self.receive_blocking().map(Some)
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        let listener = self.listener.as_ref()?;
+        listener.local_addr().ok().map(|addr| addr.port())
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.frame_checksums = on;
+    }
+
+    fn accept_blocking(&mut self) -> Result<Box<dyn BlockingTransport>> {
+        let stream = self.accept_stream()?;
+        Ok(Box::new(Self {
+            keepalive: self.keepalive,
+            first_byte_timestamps: self.first_byte_timestamps,
+            fragment_writes: self.fragment_writes,
+            ..Self::from_stream(stream)
+        }))
+    }
+
     fn close_blocking(&mut self) -> Result<()> {
         debug!("Closing blocking TCP transport");
 
@@ -475,6 +587,141 @@ mod tests {
         server_handle.join().unwrap();
     }
 
+    #[test]
+    fn test_keepalive_applied_to_both_ends() {
+        let port = 18090; // Use unique port
+        let idle = Duration::from_secs(7);
+
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingTcpSocket::new();
+            let config = TransportConfig {
+                host: "127.0.0.1".to_string(),
+                port,
+                tcp_keepalive: Some(idle),
+                ..Default::default()
+            };
+            server.start_server_blocking(&config).unwrap();
+            server.receive_blocking().unwrap();
+
+            let stream = socket2::SockRef::from(server.stream.as_ref().unwrap());
+            assert!(stream.keepalive().unwrap());
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = BlockingTcpSocket::new();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            tcp_keepalive: Some(idle),
+            ..Default::default()
+        };
+        client.start_client_blocking(&config).unwrap();
+        let stream = socket2::SockRef::from(client.stream.as_ref().unwrap());
+        assert!(stream.keepalive().unwrap());
+
+        client
+            .send_blocking(&Message::new(1, vec![0u8; 16], MessageType::OneWay))
+            .unwrap();
+        client.close_blocking().unwrap();
+        server_handle.join().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_client_connects_from_source_address_and_device() {
+        let port = 18093; // Use unique port
+        let source: std::net::IpAddr = "127.0.0.2".parse().unwrap();
+
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingTcpSocket::new();
+            let config = TransportConfig {
+                host: "127.0.0.1".to_string(),
+                port,
+                bind_device: Some("lo".to_string()),
+                ..Default::default()
+            };
+            server.start_server_blocking(&config).unwrap();
+            server.receive_blocking().unwrap();
+
+            let peer = server.stream.as_ref().unwrap().peer_addr().unwrap();
+            server.close_blocking().unwrap();
+            peer
+        });
+
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = BlockingTcpSocket::new();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            bind_device: Some("lo".to_string()),
+            source_addr: Some(source),
+            ..Default::default()
+        };
+        client.start_client_blocking(&config).unwrap();
+        client
+            .send_blocking(&Message::new(1, vec![0u8; 16], MessageType::OneWay))
+            .unwrap();
+        client.close_blocking().unwrap();
+
+        assert_eq!(server_handle.join().unwrap().ip(), source);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_unknown_bind_device_is_rejected() {
+        let mut client = BlockingTcpSocket::new();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port: 18094,
+            bind_device: Some("no-such-if0".to_string()),
+            ..Default::default()
+        };
+        let err = client.start_client_blocking(&config).unwrap_err();
+        assert!(format!("{:#}", err).contains("no-such-if0"));
+    }
+
+    #[test]
+    fn test_first_byte_timestamp_only_when_enabled() {
+        let port = 18091; // Use unique port
+
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingTcpSocket::new();
+            let config = TransportConfig {
+                host: "127.0.0.1".to_string(),
+                port,
+                first_byte_timestamps: true,
+                ..Default::default()
+            };
+            server.start_server_blocking(&config).unwrap();
+
+            let msg = server.receive_blocking().unwrap();
+            assert!(msg.first_byte_time_ns >= msg.timestamp);
+            assert!(msg.first_byte_time_ns <= crate::ipc::get_monotonic_time_ns());
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = BlockingTcpSocket::new();
+        let config = TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            ..Default::default()
+        };
+        client.start_client_blocking(&config).unwrap();
+        client
+            .send_blocking(&Message::new(1, vec![0u8; 256 * 1024], MessageType::OneWay))
+            .unwrap();
+        client.close_blocking().unwrap();
+        server_handle.join().unwrap();
+
+        // The default config leaves the stamp unset
+        assert!(!TransportConfig::default().first_byte_timestamps);
+    }
+
     #[test]
     fn test_round_trip_communication() {
         let port = 18084; // Use unique port
@@ -656,10 +903,11 @@ mod tests {
         // Give server time to bind
         thread::sleep(Duration::from_millis(100));
 
-        // Connect as raw TCP client and send invalid length
+        // Connect as raw TCP client and send a header with an invalid length
         let mut stream = std::net::TcpStream::connect("127.0.0.1:18089").unwrap();
-        let zero_len: u32 = 0;
-        stream.write_all(&zero_len.to_le_bytes()).unwrap();
+        stream
+            .write_all(&[0u8; framing::LENGTH_PREFIX_LEN])
+            .unwrap();
         drop(stream);
 
         server_handle.join().unwrap();
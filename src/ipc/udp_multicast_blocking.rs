@@ -0,0 +1,298 @@
+//! UDP multicast fan-out transport (blocking).
+//!
+//! One sender publishes every message as a single datagram to a multicast
+//! group, and any number of subscribers that joined the group receive a
+//! copy each. This is the shape of multicast-based local bus architectures
+//! (SOME/IP service discovery and the like), where what matters is how
+//! fast each subscriber sees a message and how many it misses.
+//!
+//! # Roles
+//!
+//! - The server side is a subscriber: it binds the group address with
+//!   `SO_REUSEADDR`, so several subscriber processes can share one port,
+//!   and joins the group on the interface whose address is
+//!   `TransportConfig::host` (loopback by default).
+//! - The client side is the publisher: it sends to the group through the
+//!   same interface, with multicast loopback on so subscribers on this host
+//!   get the datagrams, and a TTL of 1 so they never leave it.
+//!
+//! # Loss and Shutdown
+//!
+//! Datagrams that do not fit in a subscriber's receive buffer are dropped
+//! by the kernel; nothing is retransmitted. Closing the publisher sends a
+//! few `Shutdown` datagrams in case some are lost, and a subscriber also
+//! gives up once the group has been silent for [`IDLE_LIMIT`] after its
+//! first datagram, so a lost shutdown cannot leave it waiting forever.
+
+use crate::ipc::{BlockingTransport, Message, MessageType, TransportConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+
+/// Largest UDP payload over IPv4; every message travels in one datagram.
+pub(crate) const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Silence after which a subscriber that has received traffic assumes the
+/// publisher is gone.
+pub const IDLE_LIMIT: Duration = Duration::from_secs(5);
+
+/// How often a waiting subscriber checks the idle limit
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// `Shutdown` datagrams the publisher sends when it closes
+const SHUTDOWN_REPEATS: usize = 3;
+
+/// Blocking UDP multicast transport.
+///
+/// # Lifecycle
+///
+/// 1. Create with `new()`
+/// 2. Join the group as a subscriber with `start_server_blocking()` OR
+///    become the publisher with `start_client_blocking()`
+/// 3. Publish with `send_blocking()` / receive with `receive_blocking()`
+/// 4. Clean up with `close_blocking()`
+pub struct BlockingUdpMulticast {
+    /// Bound socket; connected to the group on the publisher
+    socket: Option<UdpSocket>,
+
+    /// Whether this end publishes (and so announces shutdown on close)
+    is_publisher: bool,
+
+    /// Receive buffer, large enough for any datagram
+    buffer: Vec<u8>,
+
+    /// When the last datagram arrived, once one has
+    last_datagram: Option<Instant>,
+}
+
+impl BlockingUdpMulticast {
+    /// Create a new, unstarted UDP multicast transport.
+    pub fn new() -> Self {
+        Self {
+            socket: None,
+            is_publisher: false,
+            buffer: Vec::new(),
+            last_datagram: None,
+        }
+    }
+
+    /// Interface address the group is joined and published on.
+    fn interface(config: &TransportConfig) -> Result<Ipv4Addr> {
+        config.host.parse().with_context(|| {
+            format!(
+                "-m udp-multicast needs an IPv4 interface address in --host, not '{}'",
+                config.host
+            )
+        })
+    }
+
+    fn socket(&self) -> Result<&UdpSocket> {
+        self.socket.as_ref().ok_or_else(|| {
+            anyhow!(
+                "UDP multicast transport not started. \
+                 Call start_server_blocking() or start_client_blocking() first."
+            )
+        })
+    }
+}
+
+impl BlockingTransport for BlockingUdpMulticast {
+    fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        let group = config.multicast_group;
+        let interface = Self::interface(config)?;
+        debug!(
+            "Joining multicast group {}:{} on {}",
+            group, config.port, interface
+        );
+
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        // Every subscriber binds the same group port
+        socket.set_reuse_address(true)?;
+        socket
+            .bind(&SocketAddrV4::new(group, config.port).into())
+            .with_context(|| format!("Failed to bind {}:{}", group, config.port))?;
+        socket
+            .join_multicast_v4(&group, &interface)
+            .with_context(|| format!("Failed to join {} on {}", group, interface))?;
+        // Best effort: the kernel caps it at net.core.rmem_max
+        let _ = socket.set_recv_buffer_size(config.buffer_size);
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+
+        self.socket = Some(socket.into());
+        self.is_publisher = false;
+        self.buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        self.last_datagram = None;
+        Ok(())
+    }
+
+    fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        let group = config.multicast_group;
+        let interface = Self::interface(config)?;
+        debug!(
+            "Publishing to multicast group {}:{} on {}",
+            group, config.port, interface
+        );
+
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.bind(&SocketAddrV4::new(interface, 0).into())?;
+        socket.set_multicast_if_v4(&interface)?;
+        socket.set_multicast_loop_v4(true)?;
+        socket.set_multicast_ttl_v4(1)?;
+        let _ = socket.set_send_buffer_size(config.buffer_size);
+        socket
+            .connect(&SocketAddrV4::new(group, config.port).into())
+            .with_context(|| format!("Failed to address {}:{}", group, config.port))?;
+
+        self.socket = Some(socket.into());
+        self.is_publisher = true;
+        Ok(())
+    }
+
+    fn send_blocking(&mut self, message: &Message) -> Result<()> {
+        trace!("Publishing message ID {} via UDP multicast", message.id);
+        let socket = self.socket()?;
+
+        // Pre-serialize with a dummy timestamp, then patch in the send time
+        // right before the syscall, as the stream transports do.
+        let mut message_with_timestamp = message.clone();
+        message_with_timestamp.timestamp = 0;
+        let mut datagram =
+            bincode::serialize(&message_with_timestamp).context("Failed to serialize message")?;
+        if datagram.len() > MAX_DATAGRAM_SIZE {
+            bail!(
+                "Message of {} bytes does not fit in one {}-byte UDP datagram",
+                datagram.len(),
+                MAX_DATAGRAM_SIZE
+            );
+        }
+        message_with_timestamp.set_timestamp_now();
+        datagram[Message::timestamp_offset()]
+            .copy_from_slice(&message_with_timestamp.timestamp.to_le_bytes());
+
+        socket
+            .send(&datagram)
+            .context("Failed to send multicast datagram")?;
+        Ok(())
+    }
+
+    fn receive_blocking(&mut self) -> Result<Message> {
+        let socket = self.socket.as_ref().ok_or_else(|| {
+            anyhow!("UDP multicast transport not started. Call start_server_blocking() first.")
+        })?;
+        loop {
+            match socket.recv(&mut self.buffer) {
+                Ok(len) => {
+                    self.last_datagram = Some(Instant::now());
+                    return bincode::deserialize(&self.buffer[..len])
+                        .context("Failed to deserialize multicast datagram");
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if self
+                        .last_datagram
+                        .is_some_and(|last| last.elapsed() >= IDLE_LIMIT)
+                    {
+                        bail!(
+                            "No multicast datagrams for {:?}; assuming the publisher is gone",
+                            IDLE_LIMIT
+                        );
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e).context("Failed to receive multicast datagram"),
+            }
+        }
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        if self.is_publisher {
+            return None;
+        }
+        let addr = self.socket.as_ref()?.local_addr().ok()?;
+        Some(addr.port())
+    }
+
+    fn close_blocking(&mut self) -> Result<()> {
+        if self.is_publisher {
+            if let Some(socket) = &self.socket {
+                let shutdown =
+                    bincode::serialize(&Message::new(u64::MAX, Vec::new(), MessageType::Shutdown))?;
+                for _ in 0..SHUTDOWN_REPEATS {
+                    let _ = socket.send(&shutdown);
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+        }
+        self.socket = None;
+        self.is_publisher = false;
+        Ok(())
+    }
+}
+
+impl Default for BlockingUdpMulticast {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(port: u16) -> TransportConfig {
+        TransportConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            multicast_group: Ipv4Addr::new(239, 255, 0, 42),
+            buffer_size: 1 << 20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_every_subscriber_gets_each_message() {
+        let mut first = BlockingUdpMulticast::new();
+        first.start_server_blocking(&config(0)).unwrap();
+        let port = first.local_port().unwrap();
+        let mut second = BlockingUdpMulticast::new();
+        second.start_server_blocking(&config(port)).unwrap();
+
+        let mut publisher = BlockingUdpMulticast::new();
+        publisher.start_client_blocking(&config(port)).unwrap();
+        assert_eq!(publisher.local_port(), None);
+        for id in 0..3 {
+            let message = Message::new(id, vec![id as u8; 100], MessageType::OneWay);
+            publisher.send_blocking(&message).unwrap();
+        }
+        publisher.close_blocking().unwrap();
+
+        for subscriber in [&mut first, &mut second] {
+            for id in 0..3 {
+                let message = subscriber.receive_blocking().unwrap();
+                assert_eq!(message.id, id);
+                assert_eq!(message.payload, vec![id as u8; 100]);
+                assert!(message.timestamp > 0);
+            }
+            let shutdown = subscriber.receive_blocking().unwrap();
+            assert_eq!(shutdown.message_type, MessageType::Shutdown);
+        }
+    }
+
+    #[test]
+    fn test_oversized_message_is_rejected() {
+        let mut subscriber = BlockingUdpMulticast::new();
+        subscriber.start_server_blocking(&config(0)).unwrap();
+        let mut publisher = BlockingUdpMulticast::new();
+        publisher
+            .start_client_blocking(&config(subscriber.local_port().unwrap()))
+            .unwrap();
+        let message = Message::new(1, vec![0; MAX_DATAGRAM_SIZE], MessageType::OneWay);
+        assert!(publisher.send_blocking(&message).is_err());
+    }
+}
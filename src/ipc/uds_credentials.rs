@@ -0,0 +1,288 @@
+//! Peer credential checks for Unix Domain Sockets.
+//!
+//! Security-sensitive deployments often refuse to talk to a local peer
+//! until its identity has been checked. This module provides the pieces
+//! the UDS transports use to model that cost:
+//!
+//! - [`peer_credentials`] reads the connected peer's pid/uid/gid once per
+//!   connection (`SO_PEERCRED` on Linux, `getpeereid` elsewhere).
+//! - [`send_with_credentials`] and [`recv_with_credentials`] attach and
+//!   extract `SCM_CREDENTIALS` ancillary data on every message (Linux only).
+//!
+//! The validation policy is deliberately simple: the peer must run as the
+//! same effective user as this process (or as root), and per-message
+//! credentials must match the credentials seen at connect time.
+
+use anyhow::{anyhow, Result};
+use std::os::unix::io::RawFd;
+
+/// Credentials of the process on the other end of a Unix socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    /// Peer process id (`None` where the platform does not report it)
+    pub pid: Option<i32>,
+    /// Peer effective user id
+    pub uid: u32,
+    /// Peer effective group id
+    pub gid: u32,
+}
+
+impl std::fmt::Display for PeerCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "pid={} uid={} gid={}", pid, self.uid, self.gid),
+            None => write!(f, "uid={} gid={}", self.uid, self.gid),
+        }
+    }
+}
+
+/// Read the credentials of the peer connected to `fd`.
+#[cfg(target_os = "linux")]
+pub fn peer_credentials(fd: RawFd) -> Result<PeerCredentials> {
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(anyhow!(
+            "SO_PEERCRED failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(PeerCredentials {
+        pid: Some(cred.pid),
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
+
+/// Read the credentials of the peer connected to `fd`.
+#[cfg(not(target_os = "linux"))]
+pub fn peer_credentials(fd: RawFd) -> Result<PeerCredentials> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+        return Err(anyhow!(
+            "getpeereid failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(PeerCredentials {
+        pid: None,
+        uid,
+        gid,
+    })
+}
+
+/// Check that a connected peer is allowed to talk to this process.
+///
+/// The peer must run as this process's effective user, or as root.
+pub fn validate_peer(peer: &PeerCredentials) -> Result<()> {
+    let own_uid = unsafe { libc::geteuid() };
+    if peer.uid == own_uid || peer.uid == 0 {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Rejected UDS peer ({}): expected uid {} or root",
+            peer,
+            own_uid
+        ))
+    }
+}
+
+/// Fetch and validate the credentials of the peer connected to `fd`.
+pub fn check_peer(fd: RawFd) -> Result<PeerCredentials> {
+    let peer = peer_credentials(fd)?;
+    validate_peer(&peer)?;
+    Ok(peer)
+}
+
+/// Check that per-message credentials match the peer seen at connect time.
+pub fn validate_message_credentials(
+    expected: &PeerCredentials,
+    received: Option<PeerCredentials>,
+) -> Result<()> {
+    let received =
+        received.ok_or_else(|| anyhow!("UDS message arrived without SCM_CREDENTIALS"))?;
+    if received.uid != expected.uid || (expected.pid.is_some() && received.pid != expected.pid) {
+        return Err(anyhow!(
+            "UDS message credentials ({}) do not match connected peer ({})",
+            received,
+            expected
+        ));
+    }
+    Ok(())
+}
+
+/// Ask the kernel to deliver `SCM_CREDENTIALS` with received data.
+#[cfg(target_os = "linux")]
+pub fn enable_passcred(fd: RawFd) -> Result<()> {
+    let on: libc::c_int = 1;
+    let rc = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PASSCRED,
+            &on as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if rc != 0 {
+        return Err(anyhow!(
+            "SO_PASSCRED failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+const CRED_CMSG_SPACE: usize = 64;
+
+/// Send `iov` in one `sendmsg` call with this process's credentials attached.
+///
+/// Returns the number of bytes written, which may be short like `writev`.
+#[cfg(target_os = "linux")]
+pub fn send_with_credentials(fd: RawFd, iov: &[libc::iovec]) -> std::io::Result<usize> {
+    let cred = libc::ucred {
+        pid: unsafe { libc::getpid() },
+        uid: unsafe { libc::getuid() },
+        gid: unsafe { libc::getgid() },
+    };
+    let cred_len = std::mem::size_of::<libc::ucred>() as libc::c_uint;
+    let mut control = [0u64; CRED_CMSG_SPACE / 8];
+
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = iov.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = iov.len() as _;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(cred_len) } as _;
+
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_CREDENTIALS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(cred_len) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut libc::ucred, cred);
+    }
+
+    let n = unsafe { libc::sendmsg(fd, &msg, 0) };
+    if n < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Receive into `buf` with one `recvmsg` call, returning the byte count and
+/// any `SCM_CREDENTIALS` that arrived with the data.
+///
+/// Requires [`enable_passcred`] on `fd`. Returns `Ok((0, _))` at end of stream.
+#[cfg(target_os = "linux")]
+pub fn recv_with_credentials(
+    fd: RawFd,
+    buf: &mut [u8],
+) -> std::io::Result<(usize, Option<PeerCredentials>)> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let mut control = [0u64; CRED_CMSG_SPACE / 8];
+
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = std::mem::size_of_val(&control) as _;
+
+    let n = unsafe { libc::recvmsg(fd, &mut msg, 0) };
+    if n < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut credentials = None;
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_CREDENTIALS
+            {
+                let cred = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::ucred);
+                credentials = Some(PeerCredentials {
+                    pid: Some(cred.pid),
+                    uid: cred.uid,
+                    gid: cred.gid,
+                });
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    Ok((n as usize, credentials))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn test_peer_credentials_of_socketpair_match_self() {
+        let (a, _b) = UnixStream::pair().unwrap();
+        let peer = check_peer(a.as_raw_fd()).unwrap();
+        assert_eq!(peer.uid, unsafe { libc::geteuid() });
+        #[cfg(target_os = "linux")]
+        assert_eq!(peer.pid, Some(std::process::id() as i32));
+    }
+
+    #[test]
+    fn test_validate_rejects_foreign_uid_and_mismatched_messages() {
+        let own = unsafe { libc::geteuid() };
+        let stranger = PeerCredentials {
+            pid: Some(1),
+            uid: own.wrapping_add(12345).max(1),
+            gid: 0,
+        };
+        if stranger.uid != own {
+            assert!(validate_peer(&stranger).is_err());
+        }
+
+        let peer = PeerCredentials {
+            pid: Some(42),
+            uid: own,
+            gid: 0,
+        };
+        assert!(validate_message_credentials(&peer, Some(peer)).is_ok());
+        assert!(validate_message_credentials(&peer, None).is_err());
+        let other_pid = PeerCredentials {
+            pid: Some(43),
+            ..peer
+        };
+        assert!(validate_message_credentials(&peer, Some(other_pid)).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_scm_credentials_round_trip() {
+        let (a, b) = UnixStream::pair().unwrap();
+        enable_passcred(b.as_raw_fd()).unwrap();
+        let payload = *b"hello";
+        let iov = [libc::iovec {
+            iov_base: payload.as_ptr() as *mut libc::c_void,
+            iov_len: payload.len(),
+        }];
+        assert_eq!(send_with_credentials(a.as_raw_fd(), &iov).unwrap(), 5);
+
+        let mut buf = [0u8; 16];
+        let (n, creds) = recv_with_credentials(b.as_raw_fd(), &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        let expected = peer_credentials(b.as_raw_fd()).unwrap();
+        validate_message_credentials(&expected, creds).unwrap();
+    }
+}
@@ -1,15 +1,18 @@
-use super::{ConnectionId, IpcError, IpcTransport, Message, TransportConfig, TransportState};
+use super::{
+    framing, ConnectionId, IpcError, IpcTransport, Message, TransportConfig, TransportState,
+};
+use crate::options::UdsCredentialMode;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::os::unix::io::AsRawFd;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::timeout;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, warn, Instrument};
 
 /// Unix Domain Socket transport implementation with multi-client support
 pub struct UnixDomainSocketTransport {
@@ -23,6 +26,10 @@ pub struct UnixDomainSocketTransport {
     socket_path: String,
     message_receiver: Option<mpsc::Receiver<(ConnectionId, Message)>>,
     has_warned_backpressure: bool,
+    first_byte_timestamps: bool,
+    fragment_writes: Option<usize>,
+    frame_checksums: bool,
+    credentials: UdsCredentialMode,
 }
 
 impl Default for UnixDomainSocketTransport {
@@ -43,48 +50,68 @@ impl UnixDomainSocketTransport {
             socket_path: String::new(),
             message_receiver: None,
             has_warned_backpressure: false,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+            credentials: UdsCredentialMode::Off,
         }
     }
 
-    /// Read a message from the Unix stream
-    async fn read_message(stream: &mut UnixStream) -> Result<Message> {
-        // Read message length (4 bytes)
-        let mut len_bytes = [0u8; 4];
-        stream.read_exact(&mut len_bytes).await?;
-        let message_len = u32::from_le_bytes(len_bytes) as usize;
-
-        // Validate message length
-        if message_len > 16 * 1024 * 1024 {
-            return Err(anyhow!("Message too large: {} bytes", message_len));
+    /// Record the requested credential mode, rejecting per-message checks,
+    /// which are only implemented by the blocking transport.
+    fn set_credential_mode(&mut self, config: &TransportConfig) -> Result<()> {
+        if config.uds_credentials == UdsCredentialMode::Message {
+            return Err(anyhow!(
+                "Per-message UDS credentials are only supported in blocking mode (--blocking)"
+            ));
         }
+        self.credentials = config.uds_credentials;
+        Ok(())
+    }
 
-        // Read message data
-        let mut message_data = vec![0u8; message_len];
-        stream.read_exact(&mut message_data).await?;
+    /// Validate the peer of a freshly established connection if requested.
+    fn validate_connection(mode: UdsCredentialMode, stream: &UnixStream) -> Result<()> {
+        if mode == UdsCredentialMode::Off {
+            return Ok(());
+        }
+        let start = std::time::Instant::now();
+        let peer = super::uds_credentials::check_peer(stream.as_raw_fd())?;
+        debug!("Validated UDS peer ({}) in {:?}", peer, start.elapsed());
+        Ok(())
+    }
 
-        // Deserialize message
-        Message::from_bytes(&message_data)
+    /// Read a message from the Unix stream
+    ///
+    /// Frames that fail their checksum are skipped (see [`framing`]). With
+    /// `first_byte_timestamps`, stamps `Message::first_byte_time_ns` as soon
+    /// as the frame header has arrived.
+    async fn read_message(stream: &mut UnixStream, first_byte_timestamps: bool) -> Result<Message> {
+        let (body, first_byte_time_ns) =
+            framing::read_frame_async(stream, framing::MAX_FRAME_LEN, first_byte_timestamps)
+                .await?;
+        let mut message = Message::from_bytes(&body)?;
+        message.first_byte_time_ns = first_byte_time_ns;
+        Ok(message)
     }
 
     /// Write a message to the Unix stream.
     ///
     /// Unlike SHM and PMQ, the timestamp is not refreshed
     /// after serialization. The gap between `to_bytes()` and
-    /// the kernel `write_all()` is only a length-prefix write
+    /// the kernel `write_all()` is only a short frame header write
     /// plus the data write (nanoseconds of CPU work, no
     /// userspace backpressure loop or async scheduling hop).
     /// This makes the timestamp error negligible for UDS.
-    async fn write_message(stream: &mut UnixStream, message: &Message) -> Result<(), IpcError> {
+    async fn write_message(
+        stream: &mut UnixStream,
+        message: &Message,
+        frame_checksums: bool,
+        fragment_writes: Option<usize>,
+    ) -> Result<(), IpcError> {
         const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
         let message_bytes = message.to_bytes().map_err(IpcError::Generic)?;
-        let message_len = message_bytes.len() as u32;
-
-        let write_fut = async {
-            stream.write_all(&message_len.to_le_bytes()).await?;
-            stream.write_all(&message_bytes).await?;
-            stream.flush().await?;
-            Ok(()) as Result<(), std::io::Error>
-        };
+        let write_fut =
+            framing::write_frame_async(stream, &message_bytes, frame_checksums, fragment_writes);
 
         // Justification: Prevent indefinite blocking on send operations when the receiver is unresponsive,
         // which is a clear sign of backpressure. This timeout ensures the system remains responsive.
@@ -170,7 +197,7 @@ impl UnixDomainSocketTransport {
 
         // Read messages from this connection
         loop {
-            match Self::read_message(&mut stream).await {
+            match Self::read_message(&mut stream, false).await {
                 Ok(message) => {
                     debug!(
                         "Received message {} from connection {}",
@@ -207,6 +234,9 @@ impl IpcTransport for UnixDomainSocketTransport {
         );
 
         self.socket_path = config.socket_path.clone();
+        self.set_credential_mode(config)?;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
         self.state = TransportState::Initializing;
 
         // Clean up existing socket file
@@ -228,10 +258,14 @@ impl IpcTransport for UnixDomainSocketTransport {
         );
 
         self.socket_path = config.socket_path.clone();
+        self.set_credential_mode(config)?;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
         self.state = TransportState::Initializing;
 
         // Connect to server
         let stream = UnixStream::connect(&config.socket_path).await?;
+        Self::validate_connection(self.credentials, &stream)?;
         self.stream = Some(stream);
         self.state = TransportState::Connected;
 
@@ -253,13 +287,16 @@ impl IpcTransport for UnixDomainSocketTransport {
             if let Some(listener) = self.listener.as_ref() {
                 debug!("Server accepting connection on first send");
                 let (stream, _) = listener.accept().await?;
+                Self::validate_connection(self.credentials, &stream)?;
                 self.stream = Some(stream);
             }
         }
 
         if let Some(ref mut stream) = self.stream {
             let start_time = std::time::Instant::now();
-            match Self::write_message(stream, message).await {
+            match Self::write_message(stream, message, self.frame_checksums, self.fragment_writes)
+                .await
+            {
                 Ok(()) => {
                     let elapsed = start_time.elapsed();
                     let mut backpressure_detected = false;
@@ -307,12 +344,13 @@ impl IpcTransport for UnixDomainSocketTransport {
             if let Some(listener) = self.listener.as_ref() {
                 debug!("Server accepting connection on first receive");
                 let (stream, _) = listener.accept().await?;
+                Self::validate_connection(self.credentials, &stream)?;
                 self.stream = Some(stream);
             }
         }
 
         if let Some(ref mut stream) = self.stream {
-            let message = Self::read_message(stream).await?;
+            let message = Self::read_message(stream, self.first_byte_timestamps).await?;
             debug!("Received message {} via Unix Domain Socket", message.id);
             Ok(message)
         } else {
@@ -320,6 +358,21 @@ impl IpcTransport for UnixDomainSocketTransport {
         }
     }
 
+    async fn try_receive(&mut self) -> Result<Option<Message>> {
+        match self.stream {
+            Some(ref mut stream) if super::socket_has_pending_frame(stream.as_raw_fd()) => {
+                Self::read_message(stream, self.first_byte_timestamps)
+                    .await
+                    .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.frame_checksums = on;
+    }
+
     async fn close(&mut self) -> Result<()> {
         debug!("Closing Unix Domain Socket transport");
 
@@ -371,6 +424,9 @@ impl IpcTransport for UnixDomainSocketTransport {
         );
 
         self.socket_path = config.socket_path.clone();
+        self.set_credential_mode(config)?;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
         self.state = TransportState::Initializing;
 
         // Clean up existing socket file
@@ -389,33 +445,44 @@ impl IpcTransport for UnixDomainSocketTransport {
         // Clone shared state for the accept loop
         let connections = self.connections.clone();
         let next_connection_id = self.next_connection_id.clone();
+        let credentials = self.credentials;
 
         // Start accept loop
-        tokio::spawn(async move {
-            loop {
-                match listener.accept().await {
-                    Ok((stream, _addr)) => {
-                        let connection_id = next_connection_id.fetch_add(1, Ordering::Relaxed);
-                        debug!("Accepted Unix Domain Socket connection {}", connection_id);
-
-                        // Spawn handler for this connection
-                        let handler_sender = message_sender.clone();
-                        let handler_connections = connections.clone();
-
-                        tokio::spawn(Self::handle_connection(
-                            connection_id,
-                            stream,
-                            handler_sender,
-                            handler_connections,
-                        ));
-                    }
-                    Err(e) => {
-                        error!("Failed to accept Unix Domain Socket connection: {}", e);
-                        break;
+        tokio::spawn(
+            async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _addr)) => {
+                            if let Err(e) = Self::validate_connection(credentials, &stream) {
+                                warn!("Dropping Unix Domain Socket connection: {}", e);
+                                continue;
+                            }
+                            let connection_id = next_connection_id.fetch_add(1, Ordering::Relaxed);
+                            debug!("Accepted Unix Domain Socket connection {}", connection_id);
+
+                            // Spawn handler for this connection
+                            let handler_sender = message_sender.clone();
+                            let handler_connections = connections.clone();
+
+                            tokio::spawn(
+                                Self::handle_connection(
+                                    connection_id,
+                                    stream,
+                                    handler_sender,
+                                    handler_connections,
+                                )
+                                .in_current_span(),
+                            );
+                        }
+                        Err(e) => {
+                            error!("Failed to accept Unix Domain Socket connection: {}", e);
+                            break;
+                        }
                     }
                 }
             }
-        });
+            .in_current_span(),
+        );
 
         self.state = TransportState::Connected;
         Ok(message_receiver)
@@ -429,7 +496,8 @@ impl IpcTransport for UnixDomainSocketTransport {
         let mut conns = self.connections.lock().await;
 
         if let Some(stream) = conns.get_mut(&connection_id) {
-            Self::write_message(stream, message).await?;
+            Self::write_message(stream, message, self.frame_checksums, self.fragment_writes)
+                .await?;
             debug!(
                 "Sent message {} to Unix Domain Socket connection {}",
                 message.id, connection_id
@@ -19,13 +19,29 @@
 //!
 //! # Wire Protocol
 //!
-//! Messages are sent with a simple length-prefixed protocol:
-//! 1. Send 4-byte message length (u32, little-endian)
+//! Messages are sent as checksummed frames (see [`crate::ipc::framing`]):
+//! 1. Send an 8-byte header: message length and CRC-32 (u32 each,
+//!    little-endian)
 //! 2. Send serialized message bytes (bincode format)
 //!
 //! This matches the protocol used by the async UDS transport for
 //! consistency.
 //!
+//! # Peer Credentials
+//!
+//! With `TransportConfig::uds_credentials` set, each side validates the
+//! peer's credentials once the connection is established. In `message` mode
+//! every frame is additionally sent with `sendmsg` carrying
+//! `SCM_CREDENTIALS`, and the receiver checks them against the connected
+//! peer before accepting the frame (Linux only).
+//!
+//! # Pre-connected Sockets
+//!
+//! When `TransportConfig::inherited_fd` is set, both `start_*` methods adopt
+//! that already-connected socket instead of binding, connecting or
+//! accepting. This is how `-m socketpair` runs the same data path over a
+//! `socketpair()` shared with the spawned server.
+//!
 //! # Example
 //!
 //! ```rust,no_run
@@ -49,9 +65,14 @@
 //! # }
 //! ```
 
-use crate::ipc::{BlockingTransport, Message, TransportConfig};
+use crate::ipc::framing::{self, FrameHeader, LENGTH_PREFIX_LEN};
+use crate::ipc::uds_credentials::{self, PeerCredentials};
+use crate::ipc::{
+    get_monotonic_time_ns, BlockingTransport, Message, TransportConfig, TransportStats,
+};
+use crate::options::UdsCredentialMode;
 use anyhow::{anyhow, Context, Result};
-use std::io::{Read, Write};
+use std::io::Write;
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
 #[cfg(unix)]
@@ -68,6 +89,11 @@ use tracing::{debug, trace};
 /// - `listener`: The server socket listener (only used in server mode)
 /// - `stream`: The connected socket stream (used in both client and server
 ///   mode)
+/// - `credentials`: Peer credential checks to perform
+/// - `peer`: Validated peer credentials, when checks are enabled
+/// - `first_byte_timestamps`: Whether to stamp first-byte arrival on receive
+/// - `fragment_writes`: Piece size frames are written in, if fragmented
+/// - `frame_checksums`: Whether sent frames carry a CRC-32 (`--verify-frames`)
 ///
 /// # Lifecycle
 ///
@@ -84,6 +110,32 @@ pub struct BlockingUnixDomainSocket {
     /// Connected socket stream for sending/receiving data.
     /// Populated after accept() in server mode or connect() in client mode.
     stream: Option<UnixStream>,
+
+    /// Peer credential checks requested by the transport config.
+    credentials: UdsCredentialMode,
+
+    /// Credentials of the connected peer, captured when the connection was
+    /// validated. None when credential checks are off.
+    peer: Option<PeerCredentials>,
+
+    /// Record `Message::first_byte_time_ns` on receive.
+    first_byte_timestamps: bool,
+
+    /// Write frames in pieces of at most this many bytes (test mode).
+    fragment_writes: Option<usize>,
+
+    /// Send checked frames, once the handshake has agreed on them.
+    frame_checksums: bool,
+
+    /// `SO_PRIORITY` last set on the stream from `Message::priority`
+    /// (0: the kernel default).
+    priority: u8,
+
+    /// Sends whose frame `writev` took only part of
+    partial_writes: u64,
+
+    /// Serialized frame body, reused from send to send
+    send_buffer: Vec<u8>,
 }
 
 impl BlockingUnixDomainSocket {
@@ -107,6 +159,14 @@ impl BlockingUnixDomainSocket {
         Self {
             listener: None,
             stream: None,
+            credentials: UdsCredentialMode::Off,
+            peer: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+            priority: 0,
+            partial_writes: 0,
+            send_buffer: Vec::new(),
         }
     }
 
@@ -118,34 +178,159 @@ impl BlockingUnixDomainSocket {
         Self {
             listener: None,
             stream: Some(stream),
+            credentials: UdsCredentialMode::Off,
+            peer: None,
+            first_byte_timestamps: false,
+            fragment_writes: None,
+            frame_checksums: false,
+            priority: 0,
+            partial_writes: 0,
+            send_buffer: Vec::new(),
         }
     }
 
     /// Accept a connection if we haven't already.
     /// This is called automatically on first send/receive in server mode.
     fn ensure_connection(&mut self) -> Result<()> {
-        // If we already have a stream, we're connected
-        if self.stream.is_some() {
+        // If we already have a stream, or are not a server, there is
+        // nothing to accept
+        if self.stream.is_some() || self.listener.is_none() {
             return Ok(());
         }
 
-        // If we have a listener, accept a connection
-        if let Some(ref listener) = self.listener {
-            debug!("Accepting connection on UDS server");
-            let (stream, addr) = listener
-                .accept()
-                .context("Failed to accept connection on Unix domain socket")?;
-
-            // Optimize socket buffer sizes for lower latency
-            Self::configure_socket_buffers(&stream);
-
-            debug!("UDS server accepted connection from: {:?}", addr);
-            self.stream = Some(stream);
-            Ok(())
-        } else {
-            // Not a server or already connected - this is fine
-            Ok(())
+        debug!("Accepting connection on UDS server");
+        let (stream, peer) = self.accept_stream()?;
+        self.peer = peer;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Accept the next client on the listener, configure the stream and
+    /// validate the peer.
+    fn accept_stream(&self) -> Result<(UnixStream, Option<PeerCredentials>)> {
+        let listener = self
+            .listener
+            .as_ref()
+            .context("Cannot accept: call start_server_blocking() first")?;
+        let (stream, addr) = listener
+            .accept()
+            .context("Failed to accept connection on Unix domain socket")?;
+
+        // Optimize socket buffer sizes for lower latency
+        Self::configure_socket_buffers(&stream);
+        let peer = Self::validate_connection(self.credentials, &stream)?;
+
+        debug!("UDS server accepted connection from: {:?}", addr);
+        Ok((stream, peer))
+    }
+
+    /// Validate the peer of a freshly established connection.
+    ///
+    /// Returns the peer's credentials when checks are enabled. In `message`
+    /// mode also enables SO_PASSCRED so per-message credentials are delivered.
+    fn validate_connection(
+        mode: UdsCredentialMode,
+        stream: &UnixStream,
+    ) -> Result<Option<PeerCredentials>> {
+        if mode == UdsCredentialMode::Off {
+            return Ok(None);
+        }
+
+        let fd = stream.as_raw_fd();
+        let start = std::time::Instant::now();
+        let peer = uds_credentials::check_peer(fd)?;
+        debug!("Validated UDS peer ({}) in {:?}", peer, start.elapsed());
+
+        if mode == UdsCredentialMode::Message {
+            #[cfg(target_os = "linux")]
+            uds_credentials::enable_passcred(fd)?;
+            #[cfg(not(target_os = "linux"))]
+            return Err(anyhow!(
+                "Per-message UDS credentials (SCM_CREDENTIALS) require Linux"
+            ));
         }
+
+        Ok(Some(peer))
+    }
+
+    /// Take ownership of the pre-connected socket `fd` and validate the peer.
+    fn adopt_inherited(&mut self, config: &TransportConfig, fd: i32) -> Result<()> {
+        use std::os::unix::io::FromRawFd;
+
+        debug!("Adopting pre-connected UDS socket (fd {})", fd);
+        // SAFETY: the descriptor was handed over in the transport config for
+        // this transport to own; see `TransportConfig::inherited_fd`.
+        let stream = unsafe { UnixStream::from_raw_fd(fd) };
+        Self::configure_socket_buffers(&stream);
+        self.credentials = config.uds_credentials;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
+        self.peer = Self::validate_connection(self.credentials, &stream)?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Read the length word of the next frame.
+    ///
+    /// In `message` mode the length word is read with `recvmsg` and the
+    /// attached SCM_CREDENTIALS are checked against the connected peer.
+    fn read_prefix(
+        stream: &mut UnixStream,
+        mode: UdsCredentialMode,
+        peer: Option<&PeerCredentials>,
+    ) -> Result<[u8; LENGTH_PREFIX_LEN]> {
+        let mut len_bytes = [0u8; LENGTH_PREFIX_LEN];
+
+        #[cfg(target_os = "linux")]
+        if mode == UdsCredentialMode::Message {
+            let peer = peer.context("UDS peer credentials were not validated")?;
+            let fd = stream.as_raw_fd();
+            let mut filled = 0;
+            let mut received = None;
+            while filled < len_bytes.len() {
+                let (n, creds) =
+                    uds_credentials::recv_with_credentials(fd, &mut len_bytes[filled..])
+                        .context("Failed to read message length")?;
+                if n == 0 {
+                    return Err(anyhow!(
+                        "Failed to read message length. \
+                         Connection may be closed or peer disconnected."
+                    ));
+                }
+                if filled == 0 {
+                    received = creds;
+                }
+                filled += n;
+            }
+            uds_credentials::validate_message_credentials(peer, received)?;
+            return Ok(len_bytes);
+        }
+        let _ = (mode, peer, &mut len_bytes);
+
+        framing::read_prefix(stream)
+    }
+
+    /// Read the next intact frame, stamping its header's arrival if
+    /// `stamp_header` is set.
+    fn read_frame(&mut self, stamp_header: bool) -> Result<(Vec<u8>, u64)> {
+        let stream = self.stream.as_mut().context(
+            "Cannot receive: socket not connected. \
+                 Call start_server_blocking() or start_client_blocking() first.",
+        )?;
+        let (mode, peer) = (self.credentials, self.peer.as_ref());
+        framing::read_frame(stream, Self::MAX_MESSAGE_SIZE, stamp_header, |stream| {
+            Self::read_prefix(stream, mode, peer)
+        })
+    }
+
+    /// Write both iovecs with a single `writev` call.
+    fn writev(fd: std::os::unix::io::RawFd, iov: &[libc::iovec; 2]) -> Result<usize> {
+        let result = unsafe { libc::writev(fd, iov.as_ptr(), 2) };
+        if result < 0 {
+            return Err(std::io::Error::last_os_error())
+                .context("Failed to write message via writev");
+        }
+        Ok(result as usize)
     }
 
     /// Configure socket buffer sizes for optimal latency.
@@ -184,7 +369,13 @@ impl BlockingUnixDomainSocket {
 
 impl BlockingTransport for BlockingUnixDomainSocket {
     fn start_server_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        if let Some(fd) = config.inherited_fd {
+            return self.adopt_inherited(config, fd);
+        }
         debug!("Starting blocking UDS server at: {}", config.socket_path);
+        self.credentials = config.uds_credentials;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
 
         // Remove existing socket file if present to avoid "address in use"
         // errors from previous runs. Ignore errors (file might not exist).
@@ -210,6 +401,9 @@ impl BlockingTransport for BlockingUnixDomainSocket {
     }
 
     fn start_client_blocking(&mut self, config: &TransportConfig) -> Result<()> {
+        if let Some(fd) = config.inherited_fd {
+            return self.adopt_inherited(config, fd);
+        }
         debug!(
             "Starting blocking UDS client, connecting to: {}",
             config.socket_path
@@ -226,6 +420,10 @@ impl BlockingTransport for BlockingUnixDomainSocket {
 
         // Optimize socket buffer sizes for lower latency
         Self::configure_socket_buffers(&stream);
+        self.credentials = config.uds_credentials;
+        self.first_byte_timestamps = config.first_byte_timestamps;
+        self.fragment_writes = config.fragment_writes;
+        self.peer = Self::validate_connection(self.credentials, &stream)?;
 
         debug!("UDS client connected successfully");
 
@@ -239,6 +437,16 @@ impl BlockingTransport for BlockingUnixDomainSocket {
         // Ensure we have a connection (accept if server, no-op if client)
         self.ensure_connection()?;
 
+        // Map the message priority onto SO_PRIORITY when it changes
+        if message.priority != self.priority {
+            if let Some(stream) = &self.stream {
+                crate::ipc::apply_socket_priority(stream.into(), message.priority).with_context(
+                    || format!("Failed to set socket priority {}", message.priority),
+                )?;
+                self.priority = message.priority;
+            }
+        }
+
         let stream = self.stream.as_mut().context(
             "Cannot send: socket not connected. \
                  Call start_server_blocking() or start_client_blocking() first.",
@@ -251,25 +459,26 @@ impl BlockingTransport for BlockingUnixDomainSocket {
         // only the timestamp bytes immediately before send. This ensures any
         // scheduling delays between timestamp capture and send are included in
         // the measured latency.
-        let mut message_with_timestamp = message.clone();
-        message_with_timestamp.timestamp = 0; // Dummy timestamp for pre-serialization
-        let mut serialized =
-            bincode::serialize(&message_with_timestamp).context("Failed to serialize message")?;
+        let serialized = &mut self.send_buffer;
+        message.serialize_unstamped_into(serialized)?;
 
         // Capture timestamp immediately before send and update bytes in buffer
-        message_with_timestamp.set_timestamp_now();
-        let timestamp_bytes = message_with_timestamp.timestamp.to_le_bytes();
+        let timestamp_bytes = get_monotonic_time_ns().to_le_bytes();
         let ts_offset = Message::timestamp_offset();
         serialized[ts_offset].copy_from_slice(&timestamp_bytes);
 
+        if let Some(piece) = self.fragment_writes {
+            return framing::write_fragmented(stream, serialized, self.frame_checksums, piece);
+        }
+
         // Use writev for scatter-gather I/O: single syscall, no extra allocation
-        let len_bytes = (serialized.len() as u32).to_le_bytes();
+        let header = FrameHeader::for_body(serialized, self.frame_checksums).to_bytes();
         let fd = stream.as_raw_fd();
 
         let iov = [
             libc::iovec {
-                iov_base: len_bytes.as_ptr() as *mut libc::c_void,
-                iov_len: 4,
+                iov_base: header.as_ptr() as *mut libc::c_void,
+                iov_len: header.len(),
             },
             libc::iovec {
                 iov_base: serialized.as_ptr() as *mut libc::c_void,
@@ -277,23 +486,31 @@ impl BlockingTransport for BlockingUnixDomainSocket {
             },
         ];
 
-        let total_len = 4 + serialized.len();
+        let total_len = header.len() + serialized.len();
         let mut written = 0usize;
 
         // writev may not write everything in one call, so loop until complete
         while written < total_len {
-            let result = unsafe { libc::writev(fd, iov.as_ptr(), 2) };
-            if result < 0 {
-                return Err(std::io::Error::last_os_error())
-                    .context("Failed to write message via writev");
+            #[cfg(target_os = "linux")]
+            if self.credentials == UdsCredentialMode::Message {
+                written += uds_credentials::send_with_credentials(fd, &iov)
+                    .context("Failed to write message via sendmsg with credentials")?;
+            } else {
+                written += Self::writev(fd, &iov)?;
+            }
+            #[cfg(not(target_os = "linux"))]
+            {
+                written += Self::writev(fd, &iov)?;
             }
-            written += result as usize;
             if written < total_len {
                 // Partial write - fall back to regular write for remainder
                 // This is rare for small messages on UDS
-                let remaining = &serialized[written.saturating_sub(4)..];
+                self.partial_writes += 1;
+                let header_left = header.get(written..).unwrap_or_default();
+                let remaining = &serialized[written.saturating_sub(header.len())..];
                 stream
-                    .write_all(remaining)
+                    .write_all(header_left)
+                    .and_then(|()| stream.write_all(remaining))
                     .context("Failed to write remaining data")?;
                 break;
             }
@@ -309,37 +526,14 @@ impl BlockingTransport for BlockingUnixDomainSocket {
         // Ensure we have a connection (accept if server, no-op if client)
         self.ensure_connection()?;
 
-        let stream = self.stream.as_mut().context(
-            "Cannot receive: socket not connected. \
-                 Call start_server_blocking() or start_client_blocking() first.",
-        )?;
-
-        // Read length prefix (4 bytes, little-endian) to match async protocol
-        let mut len_bytes = [0u8; 4];
-        stream.read_exact(&mut len_bytes).context(
-            "Failed to read message length. \
-                 Connection may be closed or peer disconnected.",
-        )?;
-        let len = u32::from_le_bytes(len_bytes) as usize;
-        if len == 0 || len > Self::MAX_MESSAGE_SIZE {
-            return Err(anyhow!(
-                "Invalid message length: {} bytes (allowed: 1..={})",
-                len,
-                Self::MAX_MESSAGE_SIZE
-            ));
-        }
-
-        trace!("Receiving message of {} bytes", len);
-
-        // Read message data
-        let mut buffer = vec![0u8; len];
-        stream
-            .read_exact(&mut buffer)
-            .context("Failed to read message data")?;
+        // Read the next intact frame, stamping its header's arrival if asked
+        let (buffer, first_byte_time_ns) = self.read_frame(self.first_byte_timestamps)?;
+        trace!("Received frame of {} bytes", buffer.len());
 
         // Deserialize message
-        let message: Message =
+        let mut message: Message =
             bincode::deserialize(&buffer).context("Failed to deserialize message")?;
+        message.first_byte_time_ns = first_byte_time_ns;
 
         trace!("Received message ID {}", message.id);
         Ok(message)
@@ -348,29 +542,7 @@ impl BlockingTransport for BlockingUnixDomainSocket {
     fn receive_blocking_timed(&mut self) -> Result<(Message, u64)> {
         self.ensure_connection()?;
 
-        let stream = self.stream.as_mut().context(
-            "Cannot receive: socket not connected. \
-                 Call start_server_blocking() or start_client_blocking() first.",
-        )?;
-
-        let mut len_bytes = [0u8; 4];
-        stream.read_exact(&mut len_bytes).context(
-            "Failed to read message length. \
-                 Connection may be closed or peer disconnected.",
-        )?;
-        let len = u32::from_le_bytes(len_bytes) as usize;
-        if len == 0 || len > Self::MAX_MESSAGE_SIZE {
-            return Err(anyhow!(
-                "Invalid message length: {} bytes (allowed: 1..={})",
-                len,
-                Self::MAX_MESSAGE_SIZE
-            ));
-        }
-
-        let mut buffer = vec![0u8; len];
-        stream
-            .read_exact(&mut buffer)
-            .context("Failed to read message data")?;
+        let (buffer, _) = self.read_frame(false)?;
 
         // Capture timestamp after raw read, before deserialization
         let receive_time_ns = crate::ipc::get_monotonic_time_ns();
@@ -381,11 +553,44 @@ impl BlockingTransport for BlockingUnixDomainSocket {
         Ok((message, receive_time_ns))
     }
 
+    fn try_receive_blocking(&mut self) -> Result<Option<Message>> {
+        match self.stream.as_ref() {
+            Some(stream) if crate::ipc::socket_has_pending_frame(stream.as_raw_fd()) => {
+                self.receive_blocking().map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn supports_try_receive(&self) -> bool {
+        true
+    }
+
+    fn stats(&self) -> TransportStats {
+        TransportStats::from([("partial_writes".to_string(), self.partial_writes)])
+    }
+
+    fn set_frame_checksums(&mut self, on: bool) {
+        self.frame_checksums = on;
+    }
+
+    fn accept_blocking(&mut self) -> Result<Box<dyn BlockingTransport>> {
+        let (stream, peer) = self.accept_stream()?;
+        Ok(Box::new(Self {
+            credentials: self.credentials,
+            peer,
+            first_byte_timestamps: self.first_byte_timestamps,
+            fragment_writes: self.fragment_writes,
+            ..Self::from_stream(stream)
+        }))
+    }
+
     fn close_blocking(&mut self) -> Result<()> {
         debug!("Closing blocking UDS transport");
 
         // Close stream (if open). Drop handles cleanup automatically.
         self.stream = None;
+        self.peer = None;
 
         // Close listener (if server). Drop handles cleanup automatically.
         self.listener = None;
@@ -459,6 +664,108 @@ mod tests {
         let _ = std::fs::remove_file(&socket_path);
     }
 
+    #[test]
+    fn test_receive_batch_drains_only_queued_messages() {
+        let socket_path = get_temp_socket_path("test_uds_blocking_batch.sock");
+        let _ = std::fs::remove_file(&socket_path);
+
+        let (sent_tx, sent_rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let server_path = socket_path.clone();
+        let server_handle = thread::spawn(move || {
+            let mut server = BlockingUnixDomainSocket::new();
+            let config = TransportConfig {
+                socket_path: server_path,
+                ..Default::default()
+            };
+            server.start_server_blocking(&config).unwrap();
+            server.ensure_connection().unwrap();
+            sent_rx.recv().unwrap();
+
+            let mut batch = Vec::new();
+            server.receive_batch_blocking(3, &mut batch).unwrap();
+            assert_eq!(batch.iter().map(|m| m.id).collect::<Vec<_>>(), [0, 1, 2]);
+            server.receive_batch_blocking(10, &mut batch).unwrap();
+            assert_eq!(batch.iter().map(|m| m.id).collect::<Vec<_>>(), [3, 4]);
+            assert!(server.try_receive_blocking().unwrap().is_none());
+
+            done_tx.send(()).unwrap();
+            server.close_blocking().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = BlockingUnixDomainSocket::new();
+        let client_config = TransportConfig {
+            socket_path: socket_path.clone(),
+            ..Default::default()
+        };
+        client.start_client_blocking(&client_config).unwrap();
+        for id in 0..5 {
+            let msg = Message::new(id, vec![0u8; 32], MessageType::OneWay);
+            client.send_blocking(&msg).unwrap();
+        }
+        sent_tx.send(()).unwrap();
+        done_rx.recv().unwrap();
+        client.close_blocking().unwrap();
+
+        server_handle.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_credential_modes_round_trip_and_record_peer() {
+        #[cfg(target_os = "linux")]
+        let modes = [UdsCredentialMode::Connection, UdsCredentialMode::Message];
+        #[cfg(not(target_os = "linux"))]
+        let modes = [UdsCredentialMode::Connection];
+
+        for mode in modes {
+            let socket_path = get_temp_socket_path(&format!("test_uds_blocking_creds_{mode}.sock"));
+            let _ = std::fs::remove_file(&socket_path);
+
+            let server_path = socket_path.clone();
+            let server_handle = thread::spawn(move || {
+                let mut server = BlockingUnixDomainSocket::new();
+                let config = TransportConfig {
+                    socket_path: server_path,
+                    uds_credentials: mode,
+                    ..Default::default()
+                };
+                server.start_server_blocking(&config).unwrap();
+                for _ in 0..3 {
+                    let msg = server.receive_blocking().unwrap();
+                    server.send_blocking(&msg).unwrap();
+                }
+                let peer = server.peer.expect("server should record peer credentials");
+                assert_eq!(peer.uid, unsafe { libc::geteuid() });
+                server.close_blocking().unwrap();
+            });
+
+            thread::sleep(Duration::from_millis(100));
+
+            let mut client = BlockingUnixDomainSocket::new();
+            let client_config = TransportConfig {
+                socket_path: socket_path.clone(),
+                uds_credentials: mode,
+                ..Default::default()
+            };
+            client.start_client_blocking(&client_config).unwrap();
+            assert!(client.peer.is_some());
+            for id in 0..3 {
+                let msg = Message::new(id, vec![7u8; 64], MessageType::Request);
+                client.send_blocking(&msg).unwrap();
+                let (reply, _) = client.receive_blocking_timed().unwrap();
+                assert_eq!(reply.id, id);
+                assert_eq!(reply.payload, vec![7u8; 64]);
+            }
+            client.close_blocking().unwrap();
+
+            server_handle.join().unwrap();
+            let _ = std::fs::remove_file(&socket_path);
+        }
+    }
+
     #[test]
     fn test_client_fails_if_server_not_running() {
         let socket_path = get_temp_socket_path("test_uds_blocking_no_server.sock");
@@ -572,6 +879,41 @@ mod tests {
         let _ = std::fs::remove_file(&socket_path);
     }
 
+    #[test]
+    fn test_inherited_socketpair_round_trip() {
+        use std::os::unix::io::IntoRawFd;
+
+        let (client_end, server_end) = UnixStream::pair().unwrap();
+        let config_for = |stream: UnixStream| TransportConfig {
+            socket_path: String::new(),
+            inherited_fd: Some(stream.into_raw_fd()),
+            ..Default::default()
+        };
+
+        let mut server = BlockingUnixDomainSocket::new();
+        server
+            .start_server_blocking(&config_for(server_end))
+            .unwrap();
+        let mut client = BlockingUnixDomainSocket::new();
+        client
+            .start_client_blocking(&config_for(client_end))
+            .unwrap();
+
+        let request = Message::new(7, vec![4, 5, 6], MessageType::Request);
+        client.send_blocking(&request).unwrap();
+        let received = server.receive_blocking().unwrap();
+        assert_eq!(received.payload, vec![4, 5, 6]);
+        server
+            .send_blocking(&Message::new(7, Vec::new(), MessageType::Response))
+            .unwrap();
+        assert_eq!(client.receive_blocking().unwrap().id, 7);
+
+        // Nothing was bound, so there is no listener to accept on
+        assert!(server.accept_blocking().is_err());
+        client.close_blocking().unwrap();
+        server.close_blocking().unwrap();
+    }
+
     #[test]
     fn test_close_cleanup() {
         let socket_path = get_temp_socket_path("test_uds_blocking_close.sock");
@@ -1579,10 +1921,11 @@ mod tests {
         // Give server time to bind
         thread::sleep(Duration::from_millis(100));
 
-        // Connect as raw Unix client and send invalid length
+        // Connect as raw Unix client and send a header with an invalid length
         let mut stream = std::os::unix::net::UnixStream::connect(&socket_path).unwrap();
-        let zero_len: u32 = 0;
-        stream.write_all(&zero_len.to_le_bytes()).unwrap();
+        stream
+            .write_all(&[0u8; framing::LENGTH_PREFIX_LEN])
+            .unwrap();
         drop(stream);
 
         server_handle.join().unwrap();
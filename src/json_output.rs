@@ -0,0 +1,256 @@
+//! Formatting and trimming of the final JSON results.
+//!
+//! The final results document carries everything a run measured, including
+//! each latency distribution's `histogram_data` samples. Multi-scenario
+//! files grow to hundreds of megabytes that way, while most consumers only
+//! read the summaries. `--output-compact` drops the indentation, and
+//! `--output-fields` trims the document before it is written:
+//!
+//! - `-NAME` removes every member called `NAME`, at any depth, and
+//!   `-a.b.c` removes just the member at that path
+//! - `a.b.c` keeps the member at that path; once any such path is given,
+//!   members on no listed path are removed
+//!
+//! Paths start at the top of the document and step through arrays, so
+//! `results.one_way_results.latency` keeps the one-way latency of every
+//! result. Keeping is applied before removing.
+//!
+//! This applies to the results file (`-o`) and `--machine-output`; the
+//! streaming outputs are not affected.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How the final JSON results are written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonOutput {
+    /// Write without indentation
+    compact: bool,
+
+    /// Paths whose members are kept; empty keeps everything
+    keep: Vec<Vec<String>>,
+
+    /// Member names removed at any depth
+    remove_names: Vec<String>,
+
+    /// Paths of members removed
+    remove_paths: Vec<Vec<String>>,
+}
+
+impl JsonOutput {
+    /// Output formatted as `--output-compact` and `--output-fields` ask.
+    pub fn new(compact: bool, fields: &[String]) -> Result<Self> {
+        let mut output = Self {
+            compact,
+            ..Self::default()
+        };
+        for field in fields {
+            let (remove, spec) = match field.strip_prefix('-') {
+                Some(spec) => (true, spec),
+                None => (false, field.as_str()),
+            };
+            let path: Vec<String> = spec.split('.').map(str::to_string).collect();
+            if path.iter().any(String::is_empty) {
+                bail!("Invalid --output-fields entry '{}'", field);
+            }
+            match (remove, path.len()) {
+                (true, 1) => output.remove_names.extend(path),
+                (true, _) => output.remove_paths.push(path),
+                (false, _) => output.keep.push(path),
+            }
+        }
+        Ok(output)
+    }
+
+    /// Render `value` as JSON, trimmed and formatted.
+    pub fn render<T: Serialize>(&self, value: &T) -> Result<String> {
+        self.render_as(value, self.compact)
+    }
+
+    /// Render `value` trimmed, on one line whatever the formatting.
+    pub fn render_line<T: Serialize>(&self, value: &T) -> Result<String> {
+        self.render_as(value, true)
+    }
+
+    fn render_as<T: Serialize>(&self, value: &T, compact: bool) -> Result<String> {
+        let trims =
+            !self.keep.is_empty() || !self.remove_names.is_empty() || !self.remove_paths.is_empty();
+        if !trims {
+            return Ok(format(value, compact)?);
+        }
+        let mut document = serde_json::to_value(value)?;
+        if !self.keep.is_empty() {
+            let paths: Vec<&[String]> = self.keep.iter().map(Vec::as_slice).collect();
+            keep_paths(&mut document, &paths);
+        }
+        for path in &self.remove_paths {
+            remove_path(&mut document, path);
+        }
+        if !self.remove_names.is_empty() {
+            remove_named(&mut document, &self.remove_names);
+        }
+        Ok(format(&document, compact)?)
+    }
+}
+
+fn format<T: Serialize>(value: &T, compact: bool) -> serde_json::Result<String> {
+    if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+}
+
+/// Remove the members of `value` that lie on none of `paths`.
+fn keep_paths(value: &mut Value, paths: &[&[String]]) {
+    match value {
+        Value::Object(map) => map.retain(|key, child| {
+            let rest: Vec<&[String]> = paths
+                .iter()
+                .filter(|path| path[0] == *key)
+                .map(|path| &path[1..])
+                .collect();
+            if rest.is_empty() {
+                return false;
+            }
+            // A path ending here keeps the whole member
+            if rest.iter().all(|path| !path.is_empty()) {
+                keep_paths(child, &rest);
+            }
+            true
+        }),
+        Value::Array(items) => {
+            for item in items {
+                keep_paths(item, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove the member at `path`, in every element of the arrays on the way.
+fn remove_path(value: &mut Value, path: &[String]) {
+    match value {
+        Value::Object(map) => match path {
+            [last] => {
+                map.remove(last);
+            }
+            [first, rest @ ..] => {
+                if let Some(child) = map.get_mut(first) {
+                    remove_path(child, rest);
+                }
+            }
+            [] => {}
+        },
+        Value::Array(items) => {
+            for item in items {
+                remove_path(item, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove every member named one of `names`, at any depth.
+fn remove_named(value: &mut Value, names: &[String]) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !names.contains(key));
+            for child in map.values_mut() {
+                remove_named(child, names);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                remove_named(item, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "metadata": {"version": "1"},
+            "results": [
+                {
+                    "mechanism": "TcpSocket",
+                    "one_way_results": {
+                        "latency": {"mean_ns": 10.0, "histogram_data": [1, 2, 3]},
+                        "throughput": {"messages_per_second": 5.0}
+                    },
+                    "round_trip_results": {
+                        "latency": {"mean_ns": 20.0, "histogram_data": [4]}
+                    }
+                },
+                {"mechanism": "SharedMemory", "one_way_results": null}
+            ]
+        })
+    }
+
+    fn render(fields: &[&str]) -> Value {
+        let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+        let output = JsonOutput::new(true, &fields).unwrap();
+        serde_json::from_str(&output.render(&document()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_compact_and_pretty() {
+        let value = json!({"a": [1, 2]});
+        let compact = JsonOutput::new(true, &[]).unwrap();
+        assert_eq!(compact.render(&value).unwrap(), r#"{"a":[1,2]}"#);
+        let pretty = JsonOutput::default();
+        assert!(pretty.render(&value).unwrap().contains('\n'));
+        assert_eq!(pretty.render_line(&value).unwrap(), r#"{"a":[1,2]}"#);
+    }
+
+    #[test]
+    fn test_remove_by_name_and_path() {
+        let trimmed = render(&["-histogram_data"]);
+        let first = &trimmed["results"][0];
+        assert!(first["one_way_results"]["latency"]
+            .get("histogram_data")
+            .is_none());
+        assert!(first["round_trip_results"]["latency"]
+            .get("histogram_data")
+            .is_none());
+        assert_eq!(first["one_way_results"]["latency"]["mean_ns"], 10.0);
+
+        let trimmed = render(&["-results.round_trip_results"]);
+        assert!(trimmed["results"][0].get("round_trip_results").is_none());
+        assert!(trimmed["results"][0].get("one_way_results").is_some());
+        assert!(trimmed.get("metadata").is_some());
+    }
+
+    #[test]
+    fn test_keep_paths_through_arrays() {
+        let trimmed = render(&[
+            "results.mechanism",
+            "results.one_way_results.latency",
+            "-histogram_data",
+        ]);
+        assert!(trimmed.get("metadata").is_none());
+        let first = &trimmed["results"][0];
+        assert_eq!(first["mechanism"], "TcpSocket");
+        assert!(first.get("round_trip_results").is_none());
+        assert!(first["one_way_results"].get("throughput").is_none());
+        assert_eq!(
+            first["one_way_results"]["latency"],
+            json!({"mean_ns": 10.0})
+        );
+        assert_eq!(trimmed["results"][1]["one_way_results"], Value::Null);
+    }
+
+    #[test]
+    fn test_rejects_empty_entries() {
+        for field in ["", "-", "results..latency", "results."] {
+            assert!(JsonOutput::new(false, &[field.to_string()]).is_err());
+        }
+    }
+}
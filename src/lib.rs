@@ -16,21 +16,78 @@
 //!
 //! The library is organized into several key modules:
 //!
+//! - `affinity_check`: Verification that pinned client and server threads stayed on their cores
+//! - `alloc_count`: Per-thread heap allocation counting for `--no-alloc-hot-path`
+//! - `analysis`: Offline post-processing of per-message streaming files
 //! - `benchmark`: Core benchmarking engine and test execution logic
+//! - `bootstrap`: Bootstrap confidence intervals of latency percentiles
+//! - `buffer_pool`: Reusable payload buffers for the client's send loops
+//! - `campaign`: Manifest of completed cells for resumable runs
 //! - `cli`: Command-line interface parsing and configuration management
+//! - `cli_schema`: Machine-readable JSON description of the command line
+//! - `clock_sync`: Clock synchronization quality for cross-host runs
+//! - `conductor`: Agents and a conductor for coordinated multi-host runs
+//! - `confidence`: Data-quality indicators of each latency distribution
+//! - `first_message`: Cold-start penalty of each test's first message
+//! - `footprint`: Resources a whole run will hold, checked against host limits before it starts
+//! - `goodput`: Throughput of the messages within a latency budget
+//! - `history`: Summary CSV appended across runs
+//! - `hook`: Per-message client and server processing cost
+//! - `host_info`: Kernel, distribution and IPC-related kernel limits
+//! - `cpu_frequency`: CPU frequency and thermal throttling checks around tests
+//! - `criteria`: Per-scenario pass/fail bounds checked at the end of a run
+//! - `csv_schema`: Documented, selectable columns of the streaming CSV
+//! - `daemon`: Benchmark service running jobs submitted over a local control API
 //! - `ipc`: Transport abstraction layer and specific IPC implementations
+//! - `json_output`: Compact output and field trimming of the final JSON results
+//! - `memory_info`: Host memory, swap, huge page and cgroup limit detection
+//! - `mechanism`: The IPC mechanisms a benchmark can select
 //! - `metrics`: Performance measurement using HDR histograms and statistical analysis
+//! - `multicast`: Per-subscriber delivery and loss of UDP multicast tests
+//! - `options`: Transport and output modes held by the transport configuration and results
+//! - `pacing`: High-resolution pacing of `--send-delay` and its achieved intervals
+//! - `pair`: Client-to-server and server-to-client phases over one connection for `--pair`
+//! - `payload`: Pluggable size, content and metadata of benchmark messages
+//! - `priority_inversion`: Critical-path latency of PMQ and SHM under low-priority bulk traffic
+//! - `producers`: Several client processes sending into one PMQ queue
+//! - `profile`: Latency, throughput and balanced presets per mechanism
+//! - `queue_depth`: Sampling of PMQ and SHM queue depth during tests
+//! - `repeat`: Periodic re-runs of the suite with a results history
+//! - `reproduce`: Command line that re-runs a single test of a larger run
+//! - `reservoir`: Uniform samples of raw latencies kept in the results
 //! - `results`: Result aggregation, formatting, and output management
+//! - `runtime`: Tokio runtime configuration for async mode
+//! - `run_id`: Per-run identifier for correlating logs, streams and results
+//! - `scheduler_noise`: Oversleep of a sleeping probe thread during each test
+//! - `selftest`: Loopback pass/fail check of each mechanism before a campaign
+//! - `sequence`: Message ID width, wraparound, and lost and reordered ID accounting
+//! - `serialization`: Per-message cost of bincode next to fixed-layout SHM records
+//! - `server_handle`: Spawning and signaling of benchmark server processes
+//! - `shutdown`: Graceful handling of SIGINT and SIGTERM
+//! - `smt`: SMT sibling detection for the pinned client and server cores
+//! - `sla`: Percentile of each latency threshold for `--sla-thresholds`
+//! - `system_load`: Busy-system detection before a run and the `--require-idle` gate
+//! - `upload`: Upload of result files to S3 or HTTP with a manifest
+//! - `warmup`: Round-trip latencies of the warmup phase for `--capture-warmup`
 //! - `utils`: Utility functions for formatting, validation, and system information
 //!
+//! ## Cargo Features
+//!
+//! The runners, the command line and the modes built on them need the
+//! default `cli` feature; metrics and results need `reports`. Without them
+//! the crate provides the transports, `IpcMechanism` and `TransportConfig`
+//! alone, without `clap`, `chrono` or the report writers. `tcp`, `shm`,
+//! `pmq`, `http` and `grpc` each add their transports.
+//!
 //! ## Usage Example
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "cli")]
 //! use ipc_benchmark::{BenchmarkRunner, BenchmarkConfig, IpcMechanism, cli::Args};
 //!
 //! #[tokio::main]
 //! async fn main() -> anyhow::Result<()> {
-//!     #[cfg(unix)]
+//!     #[cfg(all(unix, feature = "cli"))]
 //!     {
 //!         let args = Args::default();
 //!         let config = BenchmarkConfig {
@@ -72,6 +129,34 @@
 //! - **Configurable buffer sizes** and queue depths for optimal performance tuning
 //! - **Comprehensive metrics** including percentiles, throughput, and error rates
 
+/// Affinity verification
+///
+/// Samples the CPU the pinned client thread and server threads run on
+/// during a test and counts samples off their core and migrations.
+#[cfg(feature = "reports")]
+pub mod affinity_check;
+
+/// Heap allocation counting
+///
+/// A global allocator that counts allocations per thread, which the binary
+/// installs so `--no-alloc-hot-path` can check the measured send loop.
+pub mod alloc_count;
+
+/// Offline analysis of streaming output files
+///
+/// Implements the `analyze` subcommand, which recomputes percentiles,
+/// time-windowed series and outlier lists from per-message CSV or JSON
+/// streaming files captured by earlier runs.
+#[cfg(feature = "cli")]
+pub mod analysis;
+
+/// Resumable campaigns
+///
+/// Saves each completed (mechanism, message size, concurrency) cell to a
+/// manifest so an interrupted run can be resumed with `--resume`.
+#[cfg(feature = "cli")]
+pub mod campaign;
+
 /// Core benchmarking functionality
 ///
 /// Contains the main `BenchmarkRunner` and `BenchmarkConfig` types that orchestrate
@@ -80,6 +165,7 @@
 /// - Concurrency management for multi-threaded scenarios  
 /// - Resource management and cleanup between tests
 /// - Adaptive configuration based on mechanism capabilities
+#[cfg(feature = "cli")]
 pub mod benchmark;
 
 /// Blocking benchmarking functionality
@@ -91,8 +177,22 @@ pub mod benchmark;
 /// - Identical measurement methodology to async version for fair comparison
 /// - Uses `BlockingTransport` trait for blocking I/O operations
 /// - Supports all IPC mechanisms in blocking mode
+#[cfg(feature = "cli")]
 pub mod benchmark_blocking;
 
+/// Percentile confidence intervals
+///
+/// Resamples raw latencies to bound each reported percentile, so
+/// comparisons can tell a real difference from run-to-run spread.
+#[cfg(feature = "reports")]
+pub mod bootstrap;
+
+/// Payload buffer pool
+///
+/// Hands out zeroed payload buffers and takes them back after the send, so
+/// send loops do not allocate per message.
+pub mod buffer_pool;
+
 /// Command-line interface and configuration
 ///
 /// Provides argument parsing using clap and converts user-friendly CLI options
@@ -101,8 +201,66 @@ pub mod benchmark_blocking;
 /// - Duration parsing with human-readable formats (e.g., "10s", "5m")
 /// - Mechanism selection with "all" expansion capability
 /// - Output file and streaming configuration
+#[cfg(feature = "cli")]
 pub mod cli;
 
+/// Command-line schema
+///
+/// Describes every option and subcommand as JSON for `--dump-cli-schema`,
+/// from the same clap definition that parses the arguments.
+#[cfg(feature = "cli")]
+pub mod cli_schema;
+
+/// Clock synchronization quality reporting
+///
+/// Queries chrony, PTP or the kernel for how far this host's clock may be
+/// from true time. Split client/server runs record it alongside results so
+/// timestamps from different hosts can be judged against the clocks' error.
+#[cfg(feature = "reports")]
+pub mod clock_sync;
+
+/// Coordinated multi-host runs
+///
+/// Agents on several hosts run benchmarks handed to them by a conductor
+/// over a TCP control channel, starting together at a common instant.
+#[cfg(feature = "cli")]
+pub mod conductor;
+
+/// Confidence annotations of latency results
+///
+/// Judges whether each latency distribution can be trusted: enough samples
+/// behind each percentile, warmup convergence, CPU throttling and the
+/// timer noise floor.
+#[cfg(feature = "reports")]
+pub mod confidence;
+
+/// CPU frequency drift and thermal throttling detection
+///
+/// Samples cpufreq and thermal throttle counters around each test and flags
+/// results whose sustained frequency dropped past a threshold.
+pub mod cpu_frequency;
+
+/// Pass/fail criteria for benchmark scenarios
+///
+/// Reads the per-scenario P99 latency and throughput bounds of a
+/// `--criteria` file and checks them against the results of a run.
+#[cfg(feature = "reports")]
+pub mod criteria;
+
+/// Column schema of the streaming CSV
+///
+/// Names, types and meanings of the per-message CSV columns, the
+/// `--csv-columns` selection and the schema file written next to the CSV.
+#[cfg(feature = "reports")]
+pub mod csv_schema;
+
+/// Benchmark daemon
+///
+/// Queues benchmark jobs submitted as JSON-RPC over a Unix domain socket
+/// and runs them one at a time, reporting their progress and results.
+#[cfg(feature = "cli")]
+pub mod daemon;
+
 /// Execution mode configuration
 ///
 /// Defines the execution model (async vs blocking) for IPC operations.
@@ -111,6 +269,55 @@ pub mod cli;
 /// approaches. The mode is selected at runtime via CLI flags.
 pub mod execution_mode;
 
+/// Per-message processing hooks
+///
+/// `MessageHook` adds application-level work to each message, from
+/// `--client-work`/`--server-work` or library code, reported separately
+/// from transport latency.
+#[cfg(feature = "cli")]
+pub mod hook;
+
+/// Cold-start penalty of the first message
+///
+/// Runs each test with and without its first message and sets the two
+/// against each other, with `--first-message both`.
+#[cfg(feature = "reports")]
+pub mod first_message;
+
+/// Resource footprint check before a run
+///
+/// Estimates the shared memory, message queues, file descriptors and
+/// ephemeral ports the plan will hold and fails before the first test if
+/// any of them exceeds the host's limit.
+#[cfg(feature = "cli")]
+pub mod footprint;
+
+/// Throughput within a latency budget
+///
+/// Counts the messages of each test that met `--goodput-budget` and the
+/// rates they make up, reported alongside the raw throughput.
+#[cfg(feature = "reports")]
+pub mod goodput;
+
+/// Summary CSV appended across runs
+///
+/// Appends one row per mechanism and run to the `--append-summary` file,
+/// for trend plots over many runs.
+#[cfg(feature = "reports")]
+pub mod history;
+
+/// Operating system and kernel limit capture
+///
+/// Records the kernel release, distribution and the sysctls and rlimits that
+/// bound IPC results (message queue sizes, `shmmax`, socket buffer maxima).
+pub mod host_info;
+
+/// Final JSON results formatting
+///
+/// Writes the results document compact or indented, and trims it to the
+/// fields `--output-fields` asks for.
+pub mod json_output;
+
 /// IPC transport implementations and abstractions
 ///
 /// Contains the core transport abstraction (`IpcTransport` trait) and specific
@@ -121,6 +328,20 @@ pub mod execution_mode;
 /// - Transport-specific optimizations (e.g., TCP_NODELAY, ring buffers)
 pub mod ipc;
 
+/// Host memory detection
+///
+/// Reads physical memory, swap and huge page pools from `/proc/meminfo`, and
+/// the cgroup memory limit when running in a container, for the system
+/// information recorded with results.
+pub mod memory_info;
+
+/// IPC mechanism selection
+///
+/// The `IpcMechanism` enumeration of the transports, kept free of the
+/// command-line types so transports and results can name a mechanism without
+/// them.
+pub mod mechanism;
+
 /// Performance measurement and statistical analysis
 ///
 /// Implements comprehensive performance metrics collection using HDR histograms
@@ -129,8 +350,91 @@ pub mod ipc;
 /// - Throughput calculation for messages and bytes per second
 /// - Percentile analysis (P50, P95, P99, P99.9, etc.)
 /// - Histogram aggregation for multi-worker scenarios
+#[cfg(feature = "reports")]
 pub mod metrics;
 
+/// Multi-subscriber UDP multicast tests
+///
+/// Tallies what each `--subscribers` process received of the published
+/// messages, and its latency and loss.
+#[cfg(feature = "reports")]
+pub mod multicast;
+
+/// Transport and output options
+///
+/// The credential, spin, shared memory wait, HTTP version, response,
+/// machine output, profile and runtime modes, kept free of the command-line
+/// types so the transport configuration and results can hold them.
+pub mod options;
+
+/// Send pacing
+///
+/// Waits out `--send-delay` with the engine `--pacing` selects and reports
+/// the intervals achieved against the requested one.
+#[cfg(feature = "reports")]
+pub mod pacing;
+
+/// Directional asymmetry of one connection
+///
+/// Streams the one-way test's messages back from the server over the same
+/// connection with `--pair`, and compares the two directions.
+#[cfg(feature = "reports")]
+pub mod pair;
+
+/// Benchmark message payloads
+///
+/// The `PayloadGenerator` trait lets library users choose the size,
+/// content and metadata of every message a runner sends.
+pub mod payload;
+
+/// Priority inversion scenario
+///
+/// Compares the latency of high-priority messages sent alone and among
+/// low-priority bulk messages to a rate-limited consumer for
+/// `--priority-inversion`.
+#[cfg(feature = "reports")]
+pub mod priority_inversion;
+
+/// Multi-producer PMQ tests
+///
+/// Runs extra producer processes alongside the benchmark's client for
+/// `--producers` and splits delivery, throughput and fairness by producer.
+#[cfg(feature = "reports")]
+pub mod producers;
+
+/// Benchmark profiles
+///
+/// Tunes warmup, receiver batching, reply waiting and buffer sizes of each
+/// mechanism for `--profile latency`, `throughput` or `balanced`.
+#[cfg(feature = "reports")]
+pub mod profile;
+
+/// Queue depth sampling during measurement
+///
+/// Polls the depth of a PMQ queue or SHM ring from a background thread
+/// while a test runs, for the results summary and the streaming output.
+pub mod queue_depth;
+
+/// Per-test reproduction
+///
+/// Narrows the run's command line to one test's mechanism and pinned CPUs,
+/// recorded with its results so a single cell can be re-run.
+#[cfg(feature = "reports")]
+pub mod reproduce;
+
+/// Reservoir samples of raw latencies
+///
+/// Keeps a bounded, uniformly sampled set of each test's raw latencies for
+/// `--latency-reservoir`.
+pub mod reservoir;
+
+/// Periodic re-runs of the configured suite
+///
+/// Runs the suite as a child process at each `--repeat-every` slot and
+/// appends the final results of every repetition to a JSON Lines history.
+#[cfg(feature = "cli")]
+pub mod repeat;
+
 /// Result collection, aggregation, and output formatting
 ///
 /// Manages the collection and presentation of benchmark results with support for:
@@ -138,6 +442,7 @@ pub mod metrics;
 /// - Real-time streaming results during execution
 /// - Cross-mechanism comparison and ranking
 /// - System information collection for reproducibility
+#[cfg(feature = "reports")]
 pub mod results;
 
 /// Blocking results management module
@@ -152,13 +457,115 @@ pub mod results;
 /// - Real-time streaming results during execution (blocking I/O)
 /// - Cross-mechanism comparison and ranking
 /// - System information collection for reproducibility
+#[cfg(feature = "reports")]
 pub mod results_blocking;
 
+/// Run identifier shared by logs, streaming output and results
+///
+/// Holds the per-invocation run ID and the root tracing span that carries it,
+/// so artifacts from concurrent or repeated runs can be correlated.
+pub mod run_id;
+
+/// Tokio runtime configuration
+///
+/// Builds the async-mode runtime from `--runtime`, `--worker-threads` and
+/// `--event-interval`, and records those settings with each async result.
+#[cfg(feature = "reports")]
+pub mod runtime;
+
+/// Message ID sequences
+///
+/// Narrow, wrapping ID spaces for `--id-width` and the server-side tracking
+/// of gaps, late arrivals and repeats across the wrap point.
+pub mod sequence;
+
+/// Scheduler wakeup noise
+///
+/// Sleeps a probe thread repeatedly during each test for
+/// `--scheduler-noise-interval` and reports how late its wakeups came.
+#[cfg(feature = "reports")]
+pub mod scheduler_noise;
+
+/// Transport self-test
+///
+/// Exchanges a few verified round trips over each mechanism for the
+/// `selftest` subcommand, reporting pass or fail and the latency seen.
+#[cfg(feature = "cli")]
+pub mod selftest;
+
+/// Serialization cost
+///
+/// Times bincode and fixed-layout records of the test's message size, so
+/// `--zero-copy` results show what serde would have added.
+#[cfg(feature = "reports")]
+pub mod serialization;
+
+/// Lifecycle management for spawned benchmark servers
+///
+/// Provides `ServerHandle`, which spawns a server process, waits for its
+/// readiness signal, reports the endpoint it bound, and shuts it down or
+/// kills it. Used by both runners and available to external orchestration.
+#[cfg(feature = "reports")]
+pub mod server_handle;
+
+/// Retrying transport setup after transient failures
+///
+/// Retries `start_server` and `start_client` after races such as an address
+/// still in use or a socket path not yet created, with a doubling backoff,
+/// and counts the retries for the results.
+#[cfg(feature = "cli")]
+pub mod setup_retry;
+
+/// Graceful shutdown on SIGINT and SIGTERM
+///
+/// A shared flag set by the first signal, which benchmark loops and the
+/// standalone server poll to stop early and clean up.
+pub mod shutdown;
+
+/// SMT sibling detection
+///
+/// Reads the CPU topology to tell when the pinned client and server share
+/// a physical core, and warns, moves the server or records it per
+/// `--smt-siblings`.
+#[cfg(feature = "reports")]
+pub mod smt;
+
+/// Latency SLA quantiles
+///
+/// Reports the share of each test's messages within every
+/// `--sla-thresholds` latency, the inverse of a percentile.
+#[cfg(feature = "reports")]
+pub mod sla;
+
+/// Busy-system detection
+///
+/// Samples load average, core utilization and competing processes before a
+/// run, records them with the results and refuses to run on a busy machine
+/// under `--require-idle`.
+pub mod system_load;
+
+/// Upload of results to object storage
+///
+/// Pushes the result files of a run to S3 or an HTTP endpoint for
+/// `--upload`, with retries and a manifest of what was uploaded.
+#[cfg(feature = "cli")]
+pub mod upload;
+
+#[cfg(feature = "cli")]
 pub mod logging;
+#[cfg(feature = "cli")]
 pub mod standalone_client;
+#[cfg(feature = "cli")]
 pub mod standalone_server;
 pub mod utils;
 
+/// Warmup-phase latency capture
+///
+/// Times the warmup messages as round trips for `--capture-warmup` and
+/// reports their distribution and curve apart from the measured results.
+#[cfg(feature = "reports")]
+pub mod warmup;
+
 // Re-export commonly used utilities for convenient access
 pub use utils::{get_temp_dir, get_temp_socket_path};
 
@@ -170,14 +577,18 @@ pub use utils::{get_temp_dir, get_temp_socket_path};
 /// Re-exported from the benchmark module for easy access. The `BenchmarkRunner`
 /// is the primary interface for executing performance tests in async mode.
 /// `BlockingBenchmarkRunner` provides the blocking/synchronous execution mode.
+#[cfg(feature = "cli")]
 pub use benchmark::{BenchmarkConfig, BenchmarkRunner};
+#[cfg(feature = "cli")]
 pub use benchmark_blocking::BlockingBenchmarkRunner;
 
 /// Command-line interface types
 ///
 /// Re-exported for applications that want to use the same CLI parsing logic
 /// or need access to the `IpcMechanism` enumeration for programmatic usage.
-pub use cli::{Args, IpcMechanism};
+#[cfg(feature = "cli")]
+pub use cli::Args;
+pub use mechanism::IpcMechanism;
 
 /// Core IPC abstractions
 ///
@@ -189,14 +600,23 @@ pub use ipc::{IpcTransport, Message};
 ///
 /// Essential metrics types for collecting and analyzing benchmark results.
 /// These provide detailed latency and throughput measurements.
+#[cfg(feature = "reports")]
 pub use metrics::{LatencyMetrics, ThroughputMetrics};
 
+/// Custom message payloads
+///
+/// Implement `PayloadGenerator` and pass it to a runner's
+/// `with_payload_generator` to benchmark domain-specific messages.
+pub use payload::PayloadGenerator;
+
 /// Result collection and management
 ///
 /// Key types for handling benchmark results, including the main `BenchmarkResults`
 /// structure and the `ResultsManager` for output handling (async mode) and
 /// `BlockingResultsManager` for blocking mode.
+#[cfg(feature = "reports")]
 pub use results::{BenchmarkResults, ResultsManager};
+#[cfg(feature = "reports")]
 pub use results_blocking::BlockingResultsManager;
 
 /// The current version of the IPC benchmark suite
@@ -211,6 +631,7 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// These values are chosen based on common usage patterns and performance
 /// characteristics of typical IPC workloads.
 pub mod defaults {
+    use std::net::Ipv4Addr;
     use std::time::Duration;
 
     /// Default message size in bytes
@@ -256,4 +677,10 @@ pub mod defaults {
     /// - Establishing network connections and OS buffers
     /// - Reducing measurement variance from cold-start effects
     pub const WARMUP_ITERATIONS: usize = 1000;
+
+    /// Default multicast group for `-m udp-multicast`
+    ///
+    /// 239.255.0.0/16 is the IPv4 local scope, meant for groups that stay
+    /// within one site, so the default never collides with a global group.
+    pub const MULTICAST_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 0, 1);
 }
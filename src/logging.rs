@@ -15,6 +15,8 @@
 //!   to be set via environment variables (e.g., `RUST_LOG`).
 //! - **Human-Readable Format**: Configures a clean, readable format for
 //!   console output to improve developer experience.
+//! - **Hot-Path Rate Limiting**: Caps how often each debug or trace call
+//!   site may log, so `-v`/`-vv` can stay on during measurement.
 //!
 //! ## Usage
 //!
@@ -36,10 +38,17 @@
 
 use colored::*;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
-use tracing::{Event, Level, Subscriber};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Instant;
+use tracing::callsite::Identifier;
+use tracing::subscriber::Interest;
+use tracing::{info, Event, Level, Metadata, Subscriber};
 use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
 use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::layer::{Context, Layer};
 use tracing_subscriber::registry::LookupSpan;
 
 // A thread-local buffer for formatting log messages to avoid allocations on every event.
@@ -89,6 +98,96 @@ where
     }
 }
 
+/// Debug and trace events dropped by [`HotPathLimiter`] in this process
+static SUPPRESSED_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of debug and trace events this process has suppressed.
+pub fn suppressed_events() -> u64 {
+    SUPPRESSED_EVENTS.load(Ordering::Relaxed)
+}
+
+/// Log how many debug and trace events were suppressed, if any were.
+pub fn report_suppressed_events(per_second: u32) {
+    let suppressed = suppressed_events();
+    if suppressed > 0 {
+        info!(
+            "Suppressed {} debug/trace log events from call sites logging more than {} \
+             times a second (--log-rate-limit)",
+            suppressed, per_second
+        );
+    }
+}
+
+/// What one call site has logged in the current one-second window
+#[derive(Debug, Default)]
+struct CallsiteBudget {
+    window: AtomicU64,
+    logged: AtomicU64,
+}
+
+/// A layer that lets each debug or trace call site log at most a set
+/// number of events per second, and drops the rest.
+///
+/// Per-message code logs at debug and trace level, so at `-v` and above
+/// its output would otherwise scale with the message rate, and writing it
+/// would dominate the measured latency. Call sites that log rarely, such
+/// as setup and teardown, stay under the limit and lose nothing. Info and
+/// more severe events are never limited. Dropped events are counted in
+/// [`suppressed_events`].
+pub struct HotPathLimiter {
+    per_second: u64,
+    started: Instant,
+    budgets: RwLock<HashMap<Identifier, CallsiteBudget>>,
+}
+
+impl HotPathLimiter {
+    /// A limiter letting each call site log `per_second` events a second.
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            per_second: per_second as u64,
+            started: Instant::now(),
+            budgets: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for HotPathLimiter {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if !metadata.is_event() || *metadata.level() < Level::DEBUG {
+            return Interest::always();
+        }
+        self.budgets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(metadata.callsite())
+            .or_default();
+        // Ask for every event, so each can be checked against the budget
+        Interest::sometimes()
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let budgets = self.budgets.read().unwrap_or_else(|e| e.into_inner());
+        let Some(budget) = budgets.get(&event.metadata().callsite()) else {
+            return true;
+        };
+        let window = self.started.elapsed().as_secs();
+        let current = budget.window.load(Ordering::Relaxed);
+        if current != window
+            && budget
+                .window
+                .compare_exchange(current, window, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            budget.logged.store(0, Ordering::Relaxed);
+        }
+        if budget.logged.fetch_add(1, Ordering::Relaxed) < self.per_second {
+            return true;
+        }
+        SUPPRESSED_EVENTS.fetch_add(1, Ordering::Relaxed);
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +279,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hot_path_limiter_caps_each_call_site() {
+        use tracing_subscriber::prelude::*;
+
+        let writer = TestWriter::new();
+        let subscriber = tracing_subscriber::registry()
+            .with(HotPathLimiter::new(3))
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .event_format(ColorizedFormatter)
+                    .with_writer(writer.clone())
+                    .with_filter(tracing_subscriber::filter::LevelFilter::TRACE),
+            );
+
+        let before = suppressed_events();
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..10 {
+                tracing::debug!("hot debug {}", i);
+                tracing::trace!("hot trace {}", i);
+                tracing::info!("info {}", i);
+            }
+        });
+
+        let output = writer.get_output();
+        assert_eq!(output.matches("hot debug").count(), 3);
+        assert_eq!(output.matches("hot trace").count(), 3);
+        assert_eq!(output.matches("info").count(), 10);
+        assert!(suppressed_events() >= before + 14);
+    }
+
     #[test]
     fn test_colorized_formatter_struct_exists() {
         // Simple test to verify the struct can be instantiated
@@ -32,25 +32,52 @@
 //! - Resource cleanup between benchmark runs
 
 use anyhow::{Context, Result};
-use clap::Parser;
 use ipc_benchmark::{
     benchmark::{BenchmarkConfig, BenchmarkRunner},
     benchmark_blocking::BlockingBenchmarkRunner,
-    cli::{Args, IpcMechanism},
+    campaign::{Campaign, Cell},
+    cli::{Args, IpcMechanism, ResponseMode},
+    criteria::CriteriaPlan,
+    first_message::{self, FirstMessage},
+    footprint::{Footprint, HostLimits},
+    history::SummaryHistory,
+    hook,
+    host_info::Virtualization,
     ipc::{
-        get_monotonic_time_ns, BlockingTransportFactory, Message, MessageType, TransportFactory,
+        get_monotonic_time_ns,
+        handshake::{self, Handshake},
+        segmentation, BlockingTransport, BlockingTransportFactory, Message, MessageType,
+        TransportFactory,
     },
+    json_output::JsonOutput,
+    mechanism::MechanismSupport,
+    metrics, pair, priority_inversion, producers,
     results::{BenchmarkResults, ResultsManager},
     results_blocking::BlockingResultsManager,
+    runtime::RuntimeConfig,
+    sequence::{self, IdSpace},
+    server_handle::{
+        check_remote_target, server_ready_signal, server_report_frame, start_heartbeats,
+        ServerReport, ServerTally, HEARTBEAT_INTERVAL,
+    },
+    setup_retry::SetupRetry,
+    shutdown, smt, system_load,
+    upload::Upload,
 };
 use std::io::{self, Write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tracing::{debug, error, info, warn};
 
 use tracing_subscriber::{filter::LevelFilter, prelude::*, Layer};
 
 use ipc_benchmark::cli;
-use ipc_benchmark::logging::ColorizedFormatter;
+use ipc_benchmark::logging::{self, ColorizedFormatter, HotPathLimiter};
+
+/// Counts allocations per thread for `--no-alloc-hot-path`
+#[cfg(feature = "alloc-count")]
+#[global_allocator]
+static ALLOCATOR: ipc_benchmark::alloc_count::CountingAllocator =
+    ipc_benchmark::alloc_count::CountingAllocator;
 
 /// Main entry point for the IPC benchmark suite.
 ///
@@ -77,7 +104,30 @@ use ipc_benchmark::logging::ColorizedFormatter;
 /// ```
 fn main() -> Result<()> {
     // Parse CLI arguments to determine execution mode
-    let mut args = Args::parse();
+    let mut args = Args::parse_explicit();
+
+    // Describing the command line needs none of the setup below
+    if args.dump_cli_schema {
+        let schema = ipc_benchmark::cli_schema::schema();
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+    if let Some(cli::Command::Completions(completions)) = &args.command {
+        use clap::CommandFactory;
+        clap_complete::generate(
+            completions.shell,
+            &mut Args::command(),
+            "ipc-benchmark",
+            &mut io::stdout(),
+        );
+        return Ok(());
+    }
+
+    // Every summary and report below is written in these units
+    metrics::utils::set_units(metrics::utils::Units {
+        bytes: args.units,
+        precision: args.precision,
+    });
 
     // Auto-enable blocking mode when --shm-direct is used
     // Direct memory shared memory is only available in blocking mode
@@ -89,8 +139,416 @@ fn main() -> Result<()> {
         args.blocking = true;
     }
 
+    // Busy-polled receives use the blocking transports' non-blocking
+    // try_receive
+    if args.spin_wait.is_some() && !args.blocking {
+        eprintln!(
+            "Note: --spin-wait automatically enables --blocking mode \
+             (busy-poll receive is implemented by the blocking transports)"
+        );
+        args.blocking = true;
+    }
+
+    // Priority mapping and per-priority reporting are implemented by the
+    // blocking transports and runner
+    if !args.priorities.is_empty() && !args.blocking {
+        eprintln!(
+            "Note: --priorities automatically enables --blocking mode \
+             (prioritized delivery is implemented by the blocking transports)"
+        );
+        args.blocking = true;
+    }
+
+    // Named semaphores replace the condition variables of the blocking ring
+    if args.shm_wait != cli::ShmWait::Condvar {
+        if cfg!(not(target_os = "linux")) {
+            anyhow::bail!("--shm-wait {} requires Linux", args.shm_wait);
+        }
+        if args.shm_direct {
+            anyhow::bail!("--shm-wait applies to the ring buffer; drop --shm-direct");
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --shm-wait {} automatically enables --blocking mode \
+                 (the signaling variants are implemented by the blocking ring)",
+                args.shm_wait
+            );
+            args.blocking = true;
+        }
+    }
+
+    // Only the blocking ring can live on hugetlbfs
+    if args.shm_hugetlbfs.is_some() {
+        if cfg!(not(target_os = "linux")) {
+            anyhow::bail!("--shm-hugetlbfs requires Linux");
+        }
+        if args.shm_direct {
+            anyhow::bail!("--shm-hugetlbfs applies to the ring buffer; drop --shm-direct");
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --shm-hugetlbfs automatically enables --blocking mode \
+                 (hugetlbfs backing is implemented by the blocking ring)"
+            );
+            args.blocking = true;
+        }
+    }
+
+    // Fixed-layout records are written by the blocking ring only
+    if args.zero_copy {
+        if args.shm_direct {
+            anyhow::bail!("--zero-copy applies to the ring buffer; drop --shm-direct");
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --zero-copy automatically enables --blocking mode \
+                 (fixed-layout records are implemented by the blocking ring)"
+            );
+            args.blocking = true;
+        }
+    }
+
+    // The futex, RT signal, inotify, socketpair and UDP multicast transports
+    // have no async implementation; only the blocking runner hands the
+    // server an inherited socket
+    let blocking_only: &[(IpcMechanism, &str, &str)] = &[
+        #[cfg(target_os = "linux")]
+        (IpcMechanism::Futex, "futex", "futex"),
+        #[cfg(target_os = "linux")]
+        (IpcMechanism::RtSignal, "rtsig", "RT signal"),
+        #[cfg(target_os = "linux")]
+        (IpcMechanism::Inotify, "inotify", "inotify"),
+        #[cfg(target_os = "linux")]
+        (IpcMechanism::UdpMulticast, "udp-multicast", "UDP multicast"),
+        #[cfg(unix)]
+        (IpcMechanism::Socketpair, "socketpair", "socketpair"),
+    ];
+    for &(mechanism, flag, transport) in blocking_only {
+        if args.mechanisms.contains(&mechanism) && !args.blocking {
+            eprintln!(
+                "Note: -m {} automatically enables --blocking mode \
+                 (the {} transport is implemented in blocking mode only)",
+                flag, transport
+            );
+            args.blocking = true;
+        }
+    }
+
+    // The server answers fresh connections alongside the persistent one in
+    // the blocking server only
+    if args.compare_pooling && !args.blocking {
+        eprintln!(
+            "Note: --compare-pooling automatically enables --blocking mode \
+             (the multi-connection server is implemented in blocking mode)"
+        );
+        args.blocking = true;
+    }
+
+    // Both directions of a pair test are timed against one monotonic
+    // clock, by a server streaming back in the blocking server loop
+    if args.pair {
+        if args.server_remote.is_some() {
+            anyhow::bail!("--pair times both directions on one host's clock; drop --server-remote");
+        }
+        if args.producers > 1 || !args.priorities.is_empty() {
+            anyhow::bail!("--pair cannot be combined with --producers or --priorities");
+        }
+        if args.round_trip && !args.one_way {
+            eprintln!("Note: --pair measures the one-way test; adding --one-way");
+            args.one_way = true;
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --pair automatically enables --blocking mode \
+                 (the server streams back from the blocking server loop)"
+            );
+            args.blocking = true;
+        }
+    }
+
+    // Counting allocations costs every allocation, so only builds that ask
+    // for it install the counting allocator
+    if args.no_alloc_hot_path && !cfg!(feature = "alloc-count") {
+        anyhow::bail!(
+            "--no-alloc-hot-path needs ipc-benchmark built with `--features alloc-count`"
+        );
+    }
+
+    // Allocations are counted per thread, and an async send loop moves
+    // between runtime threads
+    if args.no_alloc_hot_path && !args.blocking {
+        eprintln!(
+            "Note: --no-alloc-hot-path automatically enables --blocking mode \
+             (allocations are counted on the sending thread)"
+        );
+        args.blocking = true;
+    }
+
+    // The thread CPU clock only covers a call made on one thread, which
+    // async tasks do not guarantee
+    if args.cpu_split && !args.blocking {
+        eprintln!(
+            "Note: --cpu-split automatically enables --blocking mode \
+             (a thread's CPU clock only covers calls made on that thread)"
+        );
+        args.blocking = true;
+    }
+
+    // Mechanisms run side by side on threads of the blocking runner, each
+    // pinned to cores of its own
+    if args.parallel_mechanisms > 1 {
+        if args.server_affinity.is_some() || args.client_affinity.is_some() {
+            anyhow::bail!(
+                "--parallel-mechanisms assigns CPU cores itself; \
+                 drop --server-affinity and --client-affinity"
+            );
+        }
+        if args.streaming_output_json.is_some() || args.streaming_output_csv.is_some() {
+            anyhow::bail!("--parallel-mechanisms does not support per-message streaming output");
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --parallel-mechanisms automatically enables --blocking mode \
+                 (mechanisms run on pinned threads of the blocking runner)"
+            );
+            args.blocking = true;
+        }
+    }
+
+    // Per-message SCM_CREDENTIALS are only implemented by the blocking UDS
+    // transport
+    if args.uds_credentials == cli::UdsCredentialMode::Message && !args.blocking {
+        eprintln!(
+            "Note: --uds-credentials message automatically enables --blocking mode \
+             (per-message credentials require blocking I/O)"
+        );
+        args.blocking = true;
+    }
+
+    // A remote server is only reachable over the network, and one-way latency
+    // would subtract timestamps taken on two unsynchronized clocks
+    if let Some(target) = &args.server_remote {
+        check_remote_target(target)?;
+        if IpcMechanism::expand_all(args.mechanisms.clone())
+            .iter()
+            .any(|m| *m != IpcMechanism::TcpSocket)
+        {
+            anyhow::bail!("--server-remote only supports -m tcp");
+        }
+        if args.one_way {
+            anyhow::bail!(
+                "--server-remote cannot measure one-way latency across hosts; use --round-trip"
+            );
+        }
+        if !args.round_trip {
+            eprintln!("Note: --server-remote runs round-trip tests only");
+            args.round_trip = true;
+        }
+    }
+
+    // Without replies there is nothing to time a round trip by
+    if args.response_mode == ResponseMode::None && !args.internal_run_as_server {
+        if args.round_trip || args.server_remote.is_some() {
+            anyhow::bail!("--response-mode none sends no replies; drop --round-trip");
+        }
+        if args.compare_pooling || args.capture_warmup {
+            anyhow::bail!(
+                "--compare-pooling and --capture-warmup time round trips; \
+                 they cannot be combined with --response-mode none"
+            );
+        }
+        if !args.one_way {
+            eprintln!("Note: --response-mode none runs one-way tests only");
+            args.one_way = true;
+        }
+    }
+
+    // A timeout within a few heartbeats would kill healthy servers whose
+    // heartbeat was merely delayed
+    if let Some(timeout) = args.heartbeat_timeout.filter(|t| !t.is_zero()) {
+        if timeout < HEARTBEAT_INTERVAL * 4 {
+            anyhow::bail!(
+                "--heartbeat-timeout must be at least {:?}, or 0 to disable it",
+                HEARTBEAT_INTERVAL * 4
+            );
+        }
+    }
+
+    // Extra producers share the PMQ request queue; their replies would have
+    // no single client to go back to
+    if args.producers > 1 {
+        #[cfg(target_os = "linux")]
+        let pmq_only = IpcMechanism::expand_all(args.mechanisms.clone())
+            .iter()
+            .all(|m| *m == IpcMechanism::PosixMessageQueue);
+        #[cfg(not(target_os = "linux"))]
+        let pmq_only = false;
+        if !pmq_only {
+            anyhow::bail!("--producers only supports -m pmq");
+        }
+        if args.round_trip {
+            anyhow::bail!("--producers measures one-way delivery; drop --round-trip");
+        }
+        if !args.one_way {
+            eprintln!("Note: --producers runs one-way tests only");
+            args.one_way = true;
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --producers automatically enables --blocking mode \
+                 (producer processes are run by the blocking runner)"
+            );
+            args.blocking = true;
+        }
+    }
+
+    // Narrow IDs are drawn from one sequence per test; producer processes
+    // keep their number in the top bits of 64-bit IDs instead
+    if let Some(space) = IdSpace::from_args(&args)? {
+        if args.producers > 1 {
+            anyhow::bail!("--id-width cannot be combined with --producers");
+        }
+        if args.client {
+            anyhow::bail!("--id-width applies to tests with a spawned server, not --client");
+        }
+        sequence::set_id_space(space);
+    }
+
+    // The standalone client counts its warmup messages
+    if args.client && (args.warmup.is_some() || !args.warmup_for.is_empty()) {
+        anyhow::bail!(
+            "--warmup and --warmup-for apply to tests with a spawned server; use -w with --client"
+        );
+    }
+
+    // The scenario prioritizes through PMQ message priorities and the SHM
+    // ring's priority lanes, both one-way
+    if let Some(bulk_per_critical) = args.priority_inversion {
+        #[cfg(target_os = "linux")]
+        let prioritized = |m: &IpcMechanism| {
+            matches!(
+                m,
+                IpcMechanism::PosixMessageQueue | IpcMechanism::SharedMemory
+            )
+        };
+        #[cfg(not(target_os = "linux"))]
+        let prioritized = |m: &IpcMechanism| *m == IpcMechanism::SharedMemory;
+        if !IpcMechanism::expand_all(args.mechanisms.clone())
+            .iter()
+            .all(prioritized)
+        {
+            anyhow::bail!("--priority-inversion only supports -m pmq and -m shm");
+        }
+        if args.shm_direct {
+            anyhow::bail!(
+                "--priority-inversion needs the SHM ring's priority lanes; drop --shm-direct"
+            );
+        }
+        if args.round_trip {
+            anyhow::bail!("--priority-inversion measures one-way delivery; drop --round-trip");
+        }
+        if !args.one_way {
+            eprintln!("Note: --priority-inversion runs one-way tests only");
+            args.one_way = true;
+        }
+        if args.server_rate.is_none() {
+            eprintln!(
+                "Note: without --server-rate the consumer keeps up with the senders, \
+                 so bulk messages may never queue ahead of critical ones"
+            );
+        }
+        if !args.blocking {
+            eprintln!(
+                "Note: --priority-inversion automatically enables --blocking mode \
+                 (prioritized delivery is implemented by the blocking transports)"
+            );
+            args.blocking = true;
+        }
+        args.priorities = priority_inversion::priorities(bulk_per_critical);
+    }
+
+    // Multicast subscribers have no way to reply, and each message has to
+    // fit in one datagram since a lost segment could not be told apart from
+    // a lost message
+    #[cfg(target_os = "linux")]
+    let multicast = args.mechanisms.contains(&IpcMechanism::UdpMulticast);
+    #[cfg(not(target_os = "linux"))]
+    let multicast = false;
+    if args.subscribers > 1 && !multicast {
+        anyhow::bail!("--subscribers only applies to -m udp-multicast");
+    }
+    #[cfg(target_os = "linux")]
+    if multicast && !args.server {
+        let max_payload = ipc_benchmark::ipc::segmentation::max_payload_for(
+            &IpcMechanism::UdpMulticast,
+            args.buffer_size.unwrap_or(0),
+            false,
+        );
+        if args.message_size > max_payload {
+            anyhow::bail!(
+                "-m udp-multicast sends each message in one datagram; \
+                 --message-size must be at most {} bytes",
+                max_payload
+            );
+        }
+        if args.segment_size.is_some() {
+            anyhow::bail!("-m udp-multicast does not segment messages; drop --segment-size");
+        }
+        if args.round_trip {
+            anyhow::bail!(
+                "-m udp-multicast measures one-way delivery to subscribers; drop --round-trip"
+            );
+        }
+        if !args.one_way {
+            eprintln!("Note: -m udp-multicast runs one-way tests only");
+            args.one_way = true;
+        }
+    }
+
+    let upload = Upload::from_args(&args);
+
+    // Each repetition is a child run of this same command line, which does
+    // the rest of the setup itself
+    if args.repeat_every.is_some() {
+        if args.server || args.client || args.command.is_some() {
+            anyhow::bail!("--repeat-every only applies to benchmark runs");
+        }
+        if args.manifest.is_some() || args.resume.is_some() {
+            anyhow::bail!(
+                "--repeat-every runs every cell in each repetition; drop --manifest and --resume"
+            );
+        }
+        if args.streaming_output_json.is_some() || args.streaming_output_csv.is_some() {
+            anyhow::bail!("--repeat-every does not support per-message streaming output");
+        }
+        let result = ipc_benchmark::repeat::run(&args);
+        return finish_upload(
+            upload,
+            ipc_benchmark::run_id::init(args.run_id.as_deref()),
+            result,
+        );
+    }
+
+    let run_id = ipc_benchmark::run_id::init(args.run_id.as_deref());
+
+    if let Some(cli::Command::Analyze(analyze_args)) = &args.command {
+        return ipc_benchmark::analysis::run_analyze(analyze_args);
+    }
+    if let Some(cli::Command::Agent(agent_args)) = &args.command {
+        return ipc_benchmark::conductor::run_agent(agent_args);
+    }
+    if let Some(cli::Command::Conduct(conduct_args)) = &args.command {
+        return ipc_benchmark::conductor::run_conduct(conduct_args);
+    }
+    if let Some(cli::Command::Daemon(daemon_args)) = &args.command {
+        return ipc_benchmark::daemon::run_daemon(daemon_args);
+    }
+    if let Some(cli::Command::Selftest(selftest_args)) = &args.command {
+        return ipc_benchmark::selftest::run_selftest(&args, selftest_args);
+    }
+
     // Branch to appropriate execution path based on mode
-    if args.server {
+    let result = if args.server {
         ipc_benchmark::standalone_server::run_standalone_server(args)
     } else if args.client {
         ipc_benchmark::standalone_client::run_standalone_client(args)
@@ -100,7 +558,31 @@ fn main() -> Result<()> {
     } else {
         // Async mode: use Tokio runtime with async/await
         run_async_mode(args)
+    };
+    finish_upload(upload, run_id, result)
+}
+
+/// Upload the results of a finished run for `--upload`, also after a
+/// failed or interrupted run that may have written partial results.
+fn finish_upload(upload: Option<Upload>, run_id: &str, result: Result<()>) -> Result<()> {
+    let Some(upload) = upload else {
+        return result;
+    };
+    match upload.push(run_id) {
+        Ok(manifest) => eprintln!(
+            "Uploaded {} result files and a manifest",
+            manifest.files.len()
+        ),
+        Err(e) if result.is_ok() => return Err(e),
+        Err(e) => eprintln!("Upload failed: {:#}", e),
     }
+    result
+}
+
+/// The layer rate-limiting hot-path debug/trace logging, unless
+/// `--log-rate-limit 0` turns it off.
+fn hot_path_limiter(args: &Args) -> Option<HotPathLimiter> {
+    (args.log_rate_limit > 0).then(|| HotPathLimiter::new(args.log_rate_limit))
 }
 
 /// Run the benchmark in async mode using Tokio runtime.
@@ -116,8 +598,7 @@ fn main() -> Result<()> {
 ///
 /// * `Ok(())` - Benchmark completed successfully
 /// * `Err(anyhow::Error)` - Benchmark failed with error
-#[tokio::main]
-async fn run_async_mode(args: Args) -> Result<()> {
+fn run_async_mode(args: Args) -> Result<()> {
     // === ALL EXISTING MAIN() LOGIC STARTS HERE ===
 
     // Configure logging level based on verbosity flags.
@@ -171,8 +652,10 @@ async fn run_async_mode(args: Args) -> Result<()> {
     // It is only enabled if the --quiet flag is NOT present.
     // Its verbosity is controlled by the `log_level` derived from `-v` flags.
     // Disable stdout logging when running as the spawned server process to
-    // keep stdout reserved for the readiness byte signaling.
-    let stdout_log = if !args.quiet && !args.internal_run_as_server {
+    // keep stdout reserved for the frames sent to the parent, and when
+    // --machine-output reserves it for JSON results.
+    let stdout_log = if !args.quiet && !args.internal_run_as_server && args.machine_output.is_none()
+    {
         Some(
             tracing_subscriber::fmt::layer()
                 .with_writer(std::io::stdout)
@@ -186,6 +669,7 @@ async fn run_async_mode(args: Args) -> Result<()> {
     // Initialize the tracing subscriber by combining the layers.
     // The `with` method on the registry conveniently handles the Option from the stdout layer.
     tracing_subscriber::registry()
+        .with(hot_path_limiter(&args))
         .with(detailed_log_layer)
         .with(stdout_log)
         .init();
@@ -194,6 +678,21 @@ async fn run_async_mode(args: Args) -> Result<()> {
     // If we don't assign it to a variable, it gets dropped immediately, and file logging stops working.
     let _log_guard = guard;
 
+    // Tag every log line from this thread, and from the runtime's main
+    // future below, with the run ID
+    let _run_span = ipc_benchmark::run_id::span().entered();
+
+    let runtime = RuntimeConfig::from_args(&args)
+        .build()
+        .context("Failed to build the Tokio runtime")?;
+    let log_rate_limit = args.log_rate_limit;
+    let result = runtime.block_on(run_async_benchmarks(args));
+    logging::report_suppressed_events(log_rate_limit);
+    result
+}
+
+/// Run the benchmarks on the Tokio runtime once logging is set up.
+async fn run_async_benchmarks(args: Args) -> Result<()> {
     // If the internal server flag is present, run in server-only mode and exit.
     if args.internal_run_as_server {
         return run_server_mode(args).await;
@@ -214,12 +713,23 @@ async fn run_async_mode(args: Args) -> Result<()> {
     }
 
     info!("Starting IPC Benchmark Suite");
+    info!("Run ID: {}", ipc_benchmark::run_id::get());
+    info!("Environment: {}", Virtualization::detect());
+    if args.mechanisms.contains(&IpcMechanism::All) {
+        info!("{}", MechanismSupport::of_this_build());
+    }
+    let system_load = system_load::check(args.require_idle)?;
     // The detailed configuration will be printed for each mechanism run.
 
     // Create benchmark configuration from parsed CLI arguments
     // This converts the user-friendly CLI format into the internal
     // configuration structure used by the benchmark engine
-    let config = BenchmarkConfig::from_args(&args)?;
+    let mut config = BenchmarkConfig::from_args(&args)?;
+    config.server_affinity = smt::check(
+        args.smt_siblings,
+        config.client_affinity,
+        config.server_affinity,
+    );
 
     // Calculate today's date string once to ensure consistency across all branches.
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
@@ -237,6 +747,17 @@ async fn run_async_mode(args: Args) -> Result<()> {
     // This manages both final JSON output and optional streaming results
     let mut results_manager =
         ResultsManager::new(args.output_file.as_deref(), log_file_for_manager.as_deref())?;
+    results_manager.set_machine_output(args.machine_output);
+    results_manager.set_json_output(JsonOutput::new(args.output_compact, &args.output_fields)?);
+    results_manager.set_percentile_distribution_dir(args.percentile_distribution.as_deref())?;
+    results_manager.set_criteria(
+        args.criteria
+            .as_deref()
+            .map(CriteriaPlan::load)
+            .transpose()?,
+    );
+    results_manager.set_summary_history(SummaryHistory::from_args(&args));
+    results_manager.set_system_load(system_load);
 
     // Enable per-message latency streaming if specified
     // Per-message streaming captures individual message latency values with
@@ -261,6 +782,9 @@ async fn run_async_mode(args: Args) -> Result<()> {
     // Enable CSV latency streaming if specified
     if let Some(ref streaming_file) = args.streaming_output_csv {
         info!("Enabling CSV latency streaming to: {:?}", streaming_file);
+        if let Some(columns) = &args.csv_columns {
+            results_manager.set_csv_columns(columns.clone());
+        }
         results_manager.enable_csv_streaming(streaming_file)?;
     }
 
@@ -272,10 +796,50 @@ async fn run_async_mode(args: Args) -> Result<()> {
     // Run benchmarks for each selected mechanism.
     // This loop iterates through the list of IPC mechanisms to be tested,
     // executing the benchmark for each one sequentially.
+    // Let SIGINT/SIGTERM stop the run cleanly: the current test ends early
+    // and the partial results are still finalized.
+    shutdown::install_handler("Interrupted, finishing with partial results");
+
+    // Cells a resumed campaign already completed are skipped, and their
+    // saved results carried into this run's output
+    let mut campaign = Campaign::from_args(&args)?;
+    let mut pending = Vec::new();
     for &mechanism in &mechanisms {
+        let cell = Cell::new(mechanism, &config);
+        match campaign
+            .as_ref()
+            .and_then(|campaign| campaign.completed(&cell))
+        {
+            Some(saved) => {
+                info!("Skipping {}: completed earlier in the campaign", cell);
+                results_manager.add_results(saved.clone()).await?;
+            }
+            None => match unavailable_reason(&args, &mechanism) {
+                Some(reason) => {
+                    warn!("Skipping {}: {}", mechanism, reason);
+                    results_manager
+                        .add_results(skipped_result(&config, mechanism, reason))
+                        .await?;
+                }
+                None => pending.push(mechanism),
+            },
+        }
+    }
+
+    check_footprint(&args, &config, &pending, 1)?;
+
+    for &mechanism in &pending {
         // Execute the benchmark for the current mechanism and handle the result.
         // The `run_benchmark_for_mechanism` function encapsulates all logic for a single test.
-        match run_benchmark_for_mechanism(&config, &mechanism, &mut results_manager, &args).await {
+        match run_benchmark_for_mechanism(
+            &config,
+            &mechanism,
+            &mut results_manager,
+            campaign.as_mut(),
+            &args,
+        )
+        .await
+        {
             Ok(()) => {
                 // On success, the `run_benchmark_for_mechanism` function has already added
                 // the results to the `results_manager`. No further action is needed here.
@@ -294,9 +858,10 @@ async fn run_async_mode(args: Args) -> Result<()> {
                     }
                 );
 
-                // If --continue-on-error is enabled, record the failure and proceed.
+                // If --continue-on-error is enabled, or the run was interrupted
+                // and is about to finalize, record the failure and proceed.
                 // Otherwise, propagate the error and terminate the application.
-                if args.continue_on_error {
+                if args.continue_on_error || shutdown::requested() {
                     // Create a `BenchmarkResults` object with a `Failure` status
                     // to ensure the failed test is included in the final report.
                     let mut failed_result = BenchmarkResults::new(
@@ -318,6 +883,11 @@ async fn run_async_mode(args: Args) -> Result<()> {
                 }
             }
         }
+
+        if shutdown::requested() {
+            warn!("Interrupted; skipping the remaining mechanisms");
+            break;
+        }
     }
 
     // Finalize results and output
@@ -326,8 +896,25 @@ async fn run_async_mode(args: Args) -> Result<()> {
     results_manager.finalize().await?;
 
     // Print a human-readable summary of the results to the console
-    if let Err(e) = results_manager.print_summary() {
-        error!("Failed to print results summary: {}", e);
+    if args.machine_output.is_none() {
+        if let Err(e) = results_manager.print_summary() {
+            error!("Failed to print results summary: {}", e);
+        }
+    }
+
+    if shutdown::requested() {
+        anyhow::bail!("Interrupted; the results written are partial");
+    }
+
+    // Fail the run if the results missed any --criteria bound
+    if let Some(report) = results_manager.criteria_report() {
+        if !report.passed {
+            anyhow::bail!(
+                "{} of {} pass criteria checks failed",
+                report.failures(),
+                report.checks.len() + report.unmatched_scenarios.len()
+            );
+        }
     }
 
     info!("IPC Benchmark Suite completed successfully");
@@ -372,9 +959,24 @@ fn run_blocking_mode(args: Args) -> Result<()> {
         tracing_subscriber::fmt()
             .with_writer(std::io::stderr)
             .with_max_level(tracing::Level::DEBUG)
+            .finish()
+            .with(hot_path_limiter(&args))
             .init();
+        let _run_span = ipc_benchmark::run_id::span().entered();
         return run_server_mode_blocking(args);
     }
+    #[cfg(target_os = "linux")]
+    if let Some(producer) = args.internal_run_as_producer {
+        // Stdout carries the readiness byte, as for the server
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_max_level(tracing::Level::DEBUG)
+            .finish()
+            .with(hot_path_limiter(&args))
+            .init();
+        let _run_span = ipc_benchmark::run_id::span().entered();
+        return ipc_benchmark::producers::run_producer(producer, &args);
+    }
 
     // Configure logging level based on verbosity flags
     let log_level = match args.verbose {
@@ -418,8 +1020,9 @@ fn run_blocking_mode(args: Args) -> Result<()> {
         guard = Some(file_guard);
     }
 
-    // Stdout layer for user-facing output (disabled in server mode)
-    let stdout_log = if !args.quiet {
+    // Stdout layer for user-facing output (disabled in server mode and
+    // when --machine-output reserves stdout for JSON results)
+    let stdout_log = if !args.quiet && args.machine_output.is_none() {
         Some(
             tracing_subscriber::fmt::layer()
                 .with_writer(std::io::stdout)
@@ -432,17 +1035,30 @@ fn run_blocking_mode(args: Args) -> Result<()> {
 
     // Initialize the tracing subscriber
     tracing_subscriber::registry()
+        .with(hot_path_limiter(&args))
         .with(detailed_log_layer)
         .with(stdout_log)
         .init();
 
     // Keep the logging guard alive
     let _log_guard = guard;
+    let _run_span = ipc_benchmark::run_id::span().entered();
 
     info!("Starting IPC Benchmark Suite (Blocking Mode)");
+    info!("Run ID: {}", ipc_benchmark::run_id::get());
+    info!("Environment: {}", Virtualization::detect());
+    if args.mechanisms.contains(&IpcMechanism::All) {
+        info!("{}", MechanismSupport::of_this_build());
+    }
+    let system_load = system_load::check(args.require_idle)?;
 
     // Create benchmark configuration from parsed CLI arguments
-    let config = BenchmarkConfig::from_args(&args)?;
+    let mut config = BenchmarkConfig::from_args(&args)?;
+    config.server_affinity = smt::check(
+        args.smt_siblings,
+        config.client_affinity,
+        config.server_affinity,
+    );
 
     // Calculate today's date string once to ensure consistency across all
     // branches. This is used for daily log rotation.
@@ -462,6 +1078,17 @@ fn run_blocking_mode(args: Args) -> Result<()> {
     // using blocking I/O operations.
     let mut results_manager =
         BlockingResultsManager::new(args.output_file.as_deref(), log_file_for_manager.as_deref())?;
+    results_manager.set_machine_output(args.machine_output);
+    results_manager.set_json_output(JsonOutput::new(args.output_compact, &args.output_fields)?);
+    results_manager.set_percentile_distribution_dir(args.percentile_distribution.as_deref())?;
+    results_manager.set_criteria(
+        args.criteria
+            .as_deref()
+            .map(CriteriaPlan::load)
+            .transpose()?,
+    );
+    results_manager.set_summary_history(SummaryHistory::from_args(&args));
+    results_manager.set_system_load(system_load);
 
     // Enable per-message latency streaming if specified
     // Per-message streaming captures individual message latency values with
@@ -490,64 +1117,130 @@ fn run_blocking_mode(args: Args) -> Result<()> {
     // Enable CSV latency streaming if specified
     if let Some(ref streaming_file) = args.streaming_output_csv {
         info!("Enabling CSV latency streaming to: {:?}", streaming_file);
+        if let Some(columns) = &args.csv_columns {
+            results_manager.set_csv_columns(columns.clone());
+        }
         results_manager.enable_csv_streaming(streaming_file)?;
     }
 
     // Get expanded mechanisms (handles 'all' expansion)
     let mechanisms = IpcMechanism::expand_all(args.mechanisms.clone());
 
-    // Run benchmarks for each selected mechanism
+    // Let SIGINT/SIGTERM stop the run cleanly: the current test ends early
+    // and the partial results are still finalized.
+    shutdown::install_handler("Interrupted, finishing with partial results");
+
+    // Cells a resumed campaign already completed are skipped, and their
+    // saved results carried into this run's output
+    let mut campaign = Campaign::from_args(&args)?;
+    let mut pending = Vec::new();
     for &mechanism in &mechanisms {
-        match run_blocking_benchmark_for_mechanism(&config, &mechanism, &args, &mut results_manager)
+        let cell = Cell::new(mechanism, &config);
+        match campaign
+            .as_ref()
+            .and_then(|campaign| campaign.completed(&cell))
         {
-            Ok(results) => {
-                info!(
-                    "Successfully completed benchmark for {} mechanism",
-                    mechanism
-                );
-                // Add results to manager (blocking operation)
-                results_manager.add_results(results)?;
+            Some(saved) => {
+                info!("Skipping {}: completed earlier in the campaign", cell);
+                results_manager.add_results(saved.clone())?;
             }
-            Err(e) => {
-                let error_msg = e.to_string();
-                error!(
-                    "Benchmark for {} failed: {}. {}",
-                    mechanism,
-                    error_msg,
-                    if args.continue_on_error {
-                        "Continuing to next mechanism."
-                    } else {
-                        "Aborting."
-                    }
-                );
+            None => match unavailable_reason(&args, &mechanism) {
+                Some(reason) => {
+                    warn!("Skipping {}: {}", mechanism, reason);
+                    results_manager.add_results(skipped_result(&config, mechanism, reason))?;
+                }
+                None => pending.push(mechanism),
+            },
+        }
+    }
 
-                // If --continue-on-error is enabled, record the failure and
-                // proceed. Otherwise, propagate the error and terminate the
-                // application.
-                if args.continue_on_error {
-                    // Create a `BenchmarkResults` object with a `Failure`
-                    // status to ensure the failed test is included in the
-                    // final report.
-                    let mut failed_result = BenchmarkResults::new(
+    // Run benchmarks for each selected mechanism, in groups of
+    // --parallel-mechanisms that run at the same time
+    let group_size = args.parallel_mechanisms.max(1);
+    check_footprint(&args, &config, &pending, group_size)?;
+    if group_size > 1 {
+        let needed = 2 * group_size.min(pending.len());
+        let available = core_affinity::get_core_ids().map_or(0, |ids| ids.len());
+        if available < needed {
+            anyhow::bail!(
+                "--parallel-mechanisms {} needs {} CPU cores (a server and a client core \
+                 per mechanism), but only {} are available",
+                group_size,
+                needed,
+                available
+            );
+        }
+    }
+    for group in pending.chunks(group_size) {
+        let outcomes = if let [mechanism] = group {
+            vec![run_blocking_benchmark_for_mechanism(
+                &config,
+                mechanism,
+                &args,
+                &mut results_manager,
+            )]
+        } else {
+            run_parallel_blocking_benchmarks(&config, group, &args)
+        };
+        for (&mechanism, outcome) in group.iter().zip(outcomes) {
+            match outcome {
+                Ok(results) => {
+                    info!(
+                        "Successfully completed benchmark for {} mechanism",
+                        mechanism
+                    );
+                    if let Some(campaign) = campaign.as_mut() {
+                        campaign.record(Cell::new(mechanism, &config), &results)?;
+                    }
+                    // Add results to manager (blocking operation)
+                    results_manager.add_results(results)?;
+                }
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    error!(
+                        "Benchmark for {} failed: {}. {}",
                         mechanism,
-                        config.message_size,
-                        0, // Buffer size unknown in failure case
-                        config.concurrency,
-                        config.msg_count,
-                        config.duration,
-                        config.warmup_iterations,
-                        config.one_way,
-                        config.round_trip,
+                        error_msg,
+                        if args.continue_on_error {
+                            "Continuing to next mechanism."
+                        } else {
+                            "Aborting."
+                        }
                     );
-                    failed_result.set_failure(error_msg);
-                    results_manager.add_results(failed_result)?;
-                } else {
-                    // If not continuing on error, abort the entire benchmark
-                    // suite.
-                    return Err(e);
+
+                    // If --continue-on-error is enabled, or the run was
+                    // interrupted and is about to finalize, record the failure and
+                    // proceed. Otherwise, propagate the error and terminate the
+                    // application.
+                    if args.continue_on_error || shutdown::requested() {
+                        // Create a `BenchmarkResults` object with a `Failure`
+                        // status to ensure the failed test is included in the
+                        // final report.
+                        let mut failed_result = BenchmarkResults::new(
+                            mechanism,
+                            config.message_size,
+                            0, // Buffer size unknown in failure case
+                            config.concurrency,
+                            config.msg_count,
+                            config.duration,
+                            config.warmup_iterations,
+                            config.one_way,
+                            config.round_trip,
+                        );
+                        failed_result.set_failure(error_msg);
+                        results_manager.add_results(failed_result)?;
+                    } else {
+                        // If not continuing on error, abort the entire benchmark
+                        // suite.
+                        return Err(e);
+                    }
                 }
             }
         }
+        if shutdown::requested() {
+            warn!("Interrupted; skipping the remaining mechanisms");
+            break;
+        }
     }
 
     // Finalize results and output (blocking operation)
@@ -556,8 +1249,27 @@ fn run_blocking_mode(args: Args) -> Result<()> {
     results_manager.finalize()?;
 
     // Print a human-readable summary of the results to the console
-    if let Err(e) = results_manager.print_summary() {
-        error!("Failed to print results summary: {}", e);
+    if args.machine_output.is_none() {
+        if let Err(e) = results_manager.print_summary() {
+            error!("Failed to print results summary: {}", e);
+        }
+    }
+
+    logging::report_suppressed_events(args.log_rate_limit);
+
+    if shutdown::requested() {
+        anyhow::bail!("Interrupted; the results written are partial");
+    }
+
+    // Fail the run if the results missed any --criteria bound
+    if let Some(report) = results_manager.criteria_report() {
+        if !report.passed {
+            anyhow::bail!(
+                "{} of {} pass criteria checks failed",
+                report.failures(),
+                report.checks.len() + report.unmatched_scenarios.len()
+            );
+        }
     }
 
     info!("IPC Benchmark Suite (Blocking Mode) completed successfully");
@@ -565,6 +1277,60 @@ fn run_blocking_mode(args: Args) -> Result<()> {
     Ok(())
 }
 
+/// Why a mechanism `-m all` expanded to cannot run on this host, if it
+/// cannot. Mechanisms named explicitly are always attempted, so a problem
+/// with one the user asked for fails the run as before.
+fn unavailable_reason(args: &Args, mechanism: &IpcMechanism) -> Option<String> {
+    if !args.mechanisms.contains(&IpcMechanism::All) {
+        return None;
+    }
+    ipc_benchmark::ipc::capability::probe(mechanism, &args.host)
+}
+
+/// Fail before the first test if the resources `pending` will hold, run
+/// `group_size` at a time, exceed the host's limits.
+fn check_footprint(
+    args: &Args,
+    config: &BenchmarkConfig,
+    pending: &[IpcMechanism],
+    group_size: usize,
+) -> Result<()> {
+    if args.skip_footprint_check {
+        return Ok(());
+    }
+    let footprint = Footprint::plan(pending, group_size, config, args, |mechanism| {
+        if args.blocking {
+            BlockingBenchmarkRunner::new(config.clone(), mechanism, args.clone())
+                .resolved_transport_config()
+        } else {
+            BenchmarkRunner::new(config.clone(), mechanism, args.clone())
+                .resolved_transport_config()
+        }
+    })?;
+    footprint.check(&HostLimits::query())
+}
+
+/// Result recording that `mechanism` was skipped for `reason`.
+fn skipped_result(
+    config: &BenchmarkConfig,
+    mechanism: IpcMechanism,
+    reason: String,
+) -> BenchmarkResults {
+    let mut result = BenchmarkResults::new(
+        mechanism,
+        config.message_size,
+        0, // Buffer size never resolved for a skipped mechanism
+        config.concurrency,
+        config.msg_count,
+        config.duration,
+        config.warmup_iterations,
+        config.one_way,
+        config.round_trip,
+    );
+    result.set_skipped(reason);
+    result
+}
+
 /// Run a blocking benchmark for a specific mechanism
 ///
 /// This function executes the complete benchmark lifecycle for a single IPC mechanism
@@ -592,13 +1358,88 @@ fn run_blocking_benchmark_for_mechanism(
 
     // Run the benchmark (this blocks until complete)
     // Pass results_manager for streaming latency records
-    let results = runner
+    let mut results = runner
         .run(Some(results_manager))
         .context(format!("Benchmark failed for {}", mechanism))?;
+    compare_first_message_blocking(config, *mechanism, args, &mut results)?;
 
     Ok(results)
 }
 
+/// With `--first-message both`, run the test again counting its first
+/// message and set that run against `results`.
+fn compare_first_message_blocking(
+    config: &BenchmarkConfig,
+    mechanism: IpcMechanism,
+    args: &Args,
+    results: &mut BenchmarkResults,
+) -> Result<()> {
+    if args.first_message_mode() != FirstMessage::Both {
+        return Ok(());
+    }
+    let mut config = config.clone();
+    config.include_first_message = true;
+    let with = BlockingBenchmarkRunner::new(config, mechanism, args.clone())
+        .run(None)
+        .context(format!(
+            "Benchmark counting the first message failed for {}",
+            mechanism
+        ))?;
+    first_message::attach_penalty(results, &with);
+    Ok(())
+}
+
+/// Run a group of mechanisms at the same time (`--parallel-mechanisms`).
+///
+/// Each mechanism runs on its own thread with a server and a client core of
+/// its own (cores `2i` and `2i + 1` for the `i`th in the group), and its
+/// results list the rest of the group as concurrent with it. Streaming output
+/// is not supported here, so no results manager is passed to the runners.
+fn run_parallel_blocking_benchmarks(
+    config: &BenchmarkConfig,
+    group: &[IpcMechanism],
+    args: &Args,
+) -> Vec<Result<BenchmarkResults>> {
+    let names: Vec<String> = group.iter().map(ToString::to_string).collect();
+    warn!(
+        "Running {} concurrently; each result may include the others' load",
+        names.join(", ")
+    );
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = group
+            .iter()
+            .enumerate()
+            .map(|(slot, &mechanism)| {
+                let mut config = config.clone();
+                config.server_affinity = Some(2 * slot);
+                config.client_affinity = Some(2 * slot + 1);
+                let span = tracing::Span::current();
+                scope.spawn(move || {
+                    let _run_span = span.entered();
+                    let runner =
+                        BlockingBenchmarkRunner::new(config.clone(), mechanism, args.clone());
+                    let mut results = runner
+                        .run(None)
+                        .context(format!("Benchmark failed for {}", mechanism))?;
+                    compare_first_message_blocking(&config, mechanism, args, &mut results)?;
+                    results.test_config.concurrent_with =
+                        group.iter().copied().filter(|&m| m != mechanism).collect();
+                    Ok(results)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Benchmark thread panicked")))
+            })
+            .collect()
+    })
+}
+
 /// Run server mode in blocking mode
 ///
 /// This function handles the `--internal-run-as-server` flag for blocking mode.
@@ -651,7 +1492,18 @@ fn run_server_mode_blocking(args: cli::Args) -> Result<()> {
                 transport_config.socket_path = p.clone();
             }
         }
-        IpcMechanism::TcpSocket => {
+        #[cfg(windows)]
+        IpcMechanism::NamedPipe => {
+            if let Some(ref p) = args.socket_path {
+                transport_config.socket_path = p.clone();
+            }
+        }
+        IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc => {
+            transport_config.host = args.host.clone();
+            transport_config.port = args.port;
+        }
+        #[cfg(target_os = "linux")]
+        IpcMechanism::UdpMulticast => {
             transport_config.host = args.host.clone();
             transport_config.port = args.port;
         }
@@ -666,20 +1518,48 @@ fn run_server_mode_blocking(args: cli::Args) -> Result<()> {
                 transport_config.message_queue_name = n.clone();
             }
         }
+        #[cfg(target_os = "linux")]
+        IpcMechanism::Futex | IpcMechanism::RtSignal | IpcMechanism::Inotify => {
+            if let Some(ref n) = args.shared_memory_name {
+                transport_config.shared_memory_name = n.clone();
+            }
+        }
+        #[cfg(unix)]
+        IpcMechanism::Socketpair => {
+            transport_config.inherited_fd = Some(args.internal_socketpair_fd.context(
+                "-m socketpair servers are spawned by the client, which passes them their socket",
+            )?);
+        }
         IpcMechanism::All => {}
     }
 
-    let mut transport =
-        BlockingTransportFactory::create(&mechanism, args.shm_direct, args.send_delay)?;
-    transport
-        .start_server_blocking(&transport_config)
+    let mut transport = segmentation::wrap_blocking(
+        BlockingTransportFactory::create(&mechanism, args.shm_direct, args.send_delay)?,
+        transport_config.segment_size,
+    );
+    let setup_retry = SetupRetry::from_args(&args);
+    setup_retry
+        .start_server_blocking(transport.as_mut(), &transport_config)
         .context("Server failed to start transport")?;
 
-    // Signal to the parent process that the server is ready
+    // Signal to the parent process that the server is ready, and where it listens
     io::stdout()
-        .write_all(&[1])
-        .context("Failed to write server ready byte to stdout")?;
+        .write_all(&server_ready_signal(transport.local_port()))
+        .context("Failed to write server ready signal to stdout")?;
     io::stdout().flush().ok();
+    let mut tally = ServerTally::new(config.percentiles.clone())?;
+    if let Some(space) = IdSpace::from_args(&args)? {
+        tally.track_sequence(space);
+    }
+    start_heartbeats(tally.progress());
+    let terms = Handshake::new(
+        config.message_size as u64,
+        transport_config.segment_size.is_some(),
+    );
+
+    if args.compare_pooling {
+        return serve_connections_blocking(transport, terms, args.response_mode);
+    }
 
     // Buffer latencies in memory instead of per-message file I/O
     // This avoids the massive overhead of writing to disk for each message
@@ -689,70 +1569,166 @@ fn run_server_mode_blocking(args: cli::Args) -> Result<()> {
     } else {
         Vec::new()
     };
-
-    // Persistent server loop: receive messages and optionally reply
+    let first_byte_file_path = args.internal_first_byte_file.clone();
+    let mut first_byte_buffer: Vec<(u64, u64)> = Vec::new();
+    let mut expired_count = 0usize;
+    let priority_file_path = args.internal_priority_file.clone();
+    let mut priority_buffer: Vec<u8> = Vec::new();
+    let producer_file_path = args.internal_producer_file.clone();
+    let mut producer_buffer: Vec<u8> = Vec::new();
+
+    // Persistent server loop: receive up to --server-batch messages per
+    // wakeup, then process them in order and optionally reply
+    let batch_limit = args.server_batch.max(1);
+    let mut batch: Vec<Message> = Vec::with_capacity(batch_limit);
+    let mut batch_stats = ServerBatchStats::default();
+    let mut pacer = args.server_rate.map(ServerPacer::new);
+    let server_work = hook::server_work(&args);
     loop {
-        match transport.receive_blocking() {
-            Ok(message) => {
-                // PERF: Prefer the transport-level receive timestamp when
-                // available. SHM-direct populates receive_time_ns either
-                // inside the mutex (precise mode, with --send-delay) or
-                // immediately after mutex unlock (throughput mode). Other
-                // transports leave receive_time_ns at 0, so we fall back
-                // to a clock read here (the original behavior).
-                let receive_time_ns = if message.receive_time_ns != 0 {
-                    message.receive_time_ns
-                } else {
-                    get_monotonic_time_ns()
-                };
-                let wall_now_ns = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_nanos() as u64;
-                let latency_ns = receive_time_ns.saturating_sub(message.timestamp);
-
-                if should_buffer_latency(latency_file_path.is_some(), message.id) {
-                    let wall_send_ns = wall_now_ns.saturating_sub(latency_ns);
-                    latency_buffer.push((wall_send_ns, latency_ns));
-                }
+        if let Err(e) = transport.receive_batch_blocking(batch_limit, &mut batch) {
+            debug!("Server receive error (client likely disconnected): {}", e);
+            tally.ended_by(&e);
+            break;
+        }
+        let batch_len = batch.len();
+        let mut stop = false;
 
-                // Check for shutdown message (used by PMQ and other queue-based transports)
-                if message.message_type == MessageType::Shutdown {
-                    debug!("Server received shutdown message, exiting cleanly");
-                    break;
+        for mut message in batch.drain(..) {
+            if message.message_type == MessageType::Handshake {
+                answer_handshake_blocking(transport.as_mut(), &terms, &message)?;
+                continue;
+            }
+            // --pair: a request hands the connection over for the
+            // server-to-client phase
+            if args.pair && message.message_type == MessageType::Request {
+                pair::stream_reverse_blocking(transport.as_mut(), &message, config.message_size)?;
+                continue;
+            }
+
+            // --server-rate: hold the message until its consumption slot,
+            // leaving later ones queued in the transport
+            if let Some(pacer) = pacer.as_mut() {
+                if message.message_type != MessageType::Shutdown {
+                    std::thread::sleep(pacer.wait(Instant::now()));
                 }
+            }
+
+            // PERF: Prefer the transport-level receive timestamp when
+            // available. SHM-direct populates receive_time_ns either
+            // inside the mutex (precise mode, with --send-delay) or
+            // immediately after mutex unlock (throughput mode). Other
+            // transports leave receive_time_ns at 0, so we fall back
+            // to a clock read here (the original behavior).
+            let receive_time_ns = if message.receive_time_ns != 0 {
+                message.receive_time_ns
+            } else {
+                get_monotonic_time_ns()
+            };
+            let wall_now_ns = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            let latency_ns = receive_time_ns.saturating_sub(message.timestamp);
+
+            // A one-way message past its deadline is dropped: counted, but
+            // neither recorded nor processed. Requests are still answered,
+            // since the client waits for each reply and counts late ones.
+            if message.message_type == MessageType::OneWay && message.is_expired(receive_time_ns) {
+                expired_count += 1;
+                continue;
+            }
 
-                // If it's a Request, send a Response back
-                if message.message_type == MessageType::Request {
-                    let response = Message::new(message.id, Vec::new(), MessageType::Response);
+            // --server-work or --server-spin: process the message before any reply
+            let work = match &server_work {
+                Some(work) if is_test_traffic(&message) => {
+                    Some(hook::run_timed(work.as_ref(), &message))
+                }
+                _ => None,
+            };
+
+            // If it's a Request, send the Response --response-mode asks for
+            if message.message_type == MessageType::Request {
+                let payload = std::mem::take(&mut message.payload);
+                if let Some(payload) = args.response_mode.reply_payload(payload) {
+                    let response = Message::new(message.id, payload, MessageType::Response)
+                        .with_priority(message.priority)
+                        .with_server_receive_ns(receive_time_ns);
                     if let Err(e) = transport.send_blocking(&response) {
                         warn!(
                             "Server failed to send response: {}. Exiting server loop.",
                             e
                         );
-                        break;
-                    }
-                } else if message.message_type == MessageType::Ping {
-                    let pong = Message::new(message.id, Vec::new(), MessageType::Pong);
-                    if let Err(e) = transport.send_blocking(&pong) {
-                        warn!("Server failed to send pong: {}. Exiting server loop.", e);
-                        break;
+                        tally.send_failed();
+                        stop = true;
                     }
                 }
-                // For OneWay messages, no response needed
+            } else if message.message_type == MessageType::Ping {
+                let pong = Message::new(message.id, Vec::new(), MessageType::Pong);
+                if let Err(e) = transport.send_blocking(&pong) {
+                    warn!("Server failed to send pong: {}. Exiting server loop.", e);
+                    tally.send_failed();
+                    stop = true;
+                }
             }
-            Err(e) => {
-                debug!("Server receive error (client likely disconnected): {}", e);
+            // For OneWay messages, no response needed
+
+            // Bookkeeping waits until the reply is out, so it stays out of
+            // the client's round trip
+            tally.record(message.id, receive_time_ns, latency_ns)?;
+            if let Some(work) = work {
+                tally.processed(work)?;
+            }
+            if should_buffer_latency(latency_file_path.is_some(), message.id) {
+                let wall_send_ns = wall_now_ns.saturating_sub(latency_ns);
+                latency_buffer.push((wall_send_ns, latency_ns));
+            }
+            if should_buffer_latency(first_byte_file_path.is_some(), message.id) {
+                let wall_send_ns = wall_now_ns.saturating_sub(latency_ns);
+                first_byte_buffer.push((wall_send_ns, first_byte_latency_ns(&message, latency_ns)));
+            }
+            if should_buffer_latency(priority_file_path.is_some(), message.id) {
+                priority_buffer.push(message.priority);
+            }
+            if should_buffer_latency(producer_file_path.is_some(), message.id) {
+                producer_buffer.push(producers::message_producer(message.id));
+            }
+
+            // Check for shutdown message (used by PMQ and other queue-based transports)
+            if message.message_type == MessageType::Shutdown {
+                debug!("Server received shutdown message, exiting cleanly");
+                stop = true;
+            }
+            if stop {
                 break;
             }
         }
+        batch_stats.record(batch_len);
+        if stop {
+            break;
+        }
     }
+    batch_stats.log(batch_limit);
+    logging::report_suppressed_events(args.log_rate_limit);
+    let mut report = tally.finish();
+    report.setup_retries = setup_retry.count();
+    report.transport_stats = transport.stats();
 
     let close_result = transport.close_blocking();
 
     if let Some(ref path) = latency_file_path {
         write_latency_buffer(path, &latency_buffer)?;
     }
+    if let Some(ref path) = first_byte_file_path {
+        write_latency_buffer(path, &first_byte_buffer)?;
+    }
+    write_expired_count(args.internal_expired_file.as_deref(), expired_count)?;
+    if let Some(ref path) = priority_file_path {
+        write_tag_buffer(path, &priority_buffer)?;
+    }
+    if let Some(ref path) = producer_file_path {
+        write_tag_buffer(path, &producer_buffer)?;
+    }
+    write_server_report(&report)?;
 
     close_result?;
 
@@ -776,6 +1752,8 @@ fn run_server_mode_blocking(args: cli::Args) -> Result<()> {
 /// 4. **Signaling**: Prints a "SERVER_READY" message to stdout to signal the parent
 ///    (client) process that it is ready to accept connections.
 /// 5. **Execution**: Enters the main server loop to handle incoming client messages.
+/// 6. **Report**: Sends the parent what it received, its CPU time and its
+///    receive-side latency, on the same stdout pipe.
 ///
 /// ## Parameters
 ///
@@ -820,10 +1798,21 @@ async fn run_server_mode(args: cli::Args) -> Result<()> {
                 transport_config.socket_path = p.clone();
             }
         }
-        IpcMechanism::TcpSocket => {
+        #[cfg(windows)]
+        IpcMechanism::NamedPipe => {
+            if let Some(ref p) = args.socket_path {
+                transport_config.socket_path = p.clone();
+            }
+        }
+        IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc => {
             transport_config.host = args.host.clone();
             transport_config.port = args.port; // use exact port provided by parent
         }
+        #[cfg(target_os = "linux")]
+        IpcMechanism::UdpMulticast => {
+            transport_config.host = args.host.clone();
+            transport_config.port = args.port;
+        }
         IpcMechanism::SharedMemory => {
             if let Some(ref n) = args.shared_memory_name {
                 transport_config.shared_memory_name = n.clone();
@@ -835,21 +1824,43 @@ async fn run_server_mode(args: cli::Args) -> Result<()> {
                 transport_config.message_queue_name = n.clone();
             }
         }
+        #[cfg(target_os = "linux")]
+        IpcMechanism::Futex | IpcMechanism::RtSignal | IpcMechanism::Inotify => {
+            if let Some(ref n) = args.shared_memory_name {
+                transport_config.shared_memory_name = n.clone();
+            }
+        }
+        #[cfg(unix)]
+        IpcMechanism::Socketpair => {
+            transport_config.inherited_fd = Some(args.internal_socketpair_fd.context(
+                "-m socketpair servers are spawned by the client, which passes them their socket",
+            )?);
+        }
         IpcMechanism::All => {}
     }
 
-    let mut transport = TransportFactory::create(&mechanism)?;
-    transport
-        .start_server(&transport_config)
+    let mut transport = segmentation::wrap(
+        TransportFactory::create(&mechanism)?,
+        transport_config.segment_size,
+    );
+    let setup_retry = SetupRetry::from_args(&args);
+    setup_retry
+        .start_server(transport.as_mut(), &transport_config)
         .await
         .context("Server failed to start transport")?;
 
-    // Signal to the parent process that the server is ready by writing a single
-    // byte to stdout. The parent connected the pipe writer to the child's stdout.
+    // Signal to the parent process that the server is ready by writing the ready
+    // signal (including any ephemeral port) to stdout. The parent connected the
+    // pipe writer to the child's stdout.
     io::stdout()
-        .write_all(&[1])
-        .context("Failed to write server ready byte to stdout")?;
+        .write_all(&server_ready_signal(transport.local_port()))
+        .context("Failed to write server ready signal to stdout")?;
     io::stdout().flush().ok();
+    let mut tally = ServerTally::new(config.percentiles.clone())?;
+    if let Some(space) = IdSpace::from_args(&args)? {
+        tally.track_sequence(space);
+    }
+    start_heartbeats(tally.progress());
 
     // Buffer latencies in memory instead of per-message file I/O
     // This avoids the massive overhead of writing to disk for each message
@@ -859,68 +1870,141 @@ async fn run_server_mode(args: cli::Args) -> Result<()> {
     } else {
         Vec::new()
     };
-
-    // Persistent server loop: receive messages and optionally reply to
-    // round-trip patterns. Exit cleanly on disconnect or receive error.
+    let first_byte_file_path = args.internal_first_byte_file.clone();
+    let mut first_byte_buffer: Vec<(u64, u64)> = Vec::new();
+    let mut expired_count = 0usize;
+
+    // Persistent server loop: receive up to --server-batch messages per
+    // wakeup and optionally reply to round-trip patterns. Exit cleanly on
+    // disconnect or receive error.
+    let batch_limit = args.server_batch.max(1);
+    let mut batch: Vec<Message> = Vec::with_capacity(batch_limit);
+    let mut batch_stats = ServerBatchStats::default();
+    let mut pacer = args.server_rate.map(ServerPacer::new);
+    let server_work = hook::server_work(&args);
+    let terms = Handshake::new(
+        config.message_size as u64,
+        transport_config.segment_size.is_some(),
+    );
     loop {
         // Await directly on receive so that transport-level errors (including
         // client disconnects) are observed and the server can exit cleanly.
-        match transport.receive().await {
-            Ok(msg) => {
-                // PERF: Same transport-level timestamp preference as the
-                // blocking loop above. Currently no async transport sets
-                // receive_time_ns, so this always falls back to the clock
-                // read — preserving existing behavior for TCP/UDS/PMQ.
-                let receive_time_ns = if msg.receive_time_ns != 0 {
-                    msg.receive_time_ns
-                } else {
-                    get_monotonic_time_ns()
-                };
-                let wall_now_ns = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_nanos() as u64;
-                let latency_ns = receive_time_ns.saturating_sub(msg.timestamp);
-
-                if should_buffer_latency(latency_file_path.is_some(), msg.id) {
-                    let wall_send_ns = wall_now_ns.saturating_sub(latency_ns);
-                    latency_buffer.push((wall_send_ns, latency_ns));
-                }
+        if let Err(e) = transport.receive_batch(batch_limit, &mut batch).await {
+            info!("Server receive loop ending due to transport error: {}", e);
+            tally.ended_by(&e);
+            break;
+        }
+        let batch_len = batch.len();
+        let mut stop = false;
+
+        for mut msg in batch.drain(..) {
+            if msg.message_type == MessageType::Handshake {
+                let (reply, agreed) = handshake::answer(&terms, &msg);
+                transport
+                    .send(&reply)
+                    .await
+                    .context("Failed to answer the client's handshake")?;
+                let agreed = agreed?;
+                transport.set_frame_checksums(agreed.frame_checksums());
+                debug!("Handshake agreed: {:?}", agreed);
+                continue;
+            }
 
-                // Message received
-                match msg.message_type {
-                    MessageType::Request => {
-                        // Echo a response to complete round-trip flows.
-                        let resp = Message::new(msg.id, Vec::new(), MessageType::Response);
-                        if transport.send(&resp).await.is_err() {
-                            info!("Client disconnected during send, exiting server loop.");
-                            break;
-                        }
-                    }
-                    MessageType::Ping => {
-                        let resp = Message::new(msg.id, Vec::new(), MessageType::Pong);
-                        if transport.send(&resp).await.is_err() {
-                            info!("Client disconnected during send, exiting server loop.");
-                            break;
-                        }
-                    }
-                    // OneWay and other types need no reply.
-                    _ => {}
+            if let Some(pacer) = pacer.as_mut() {
+                tokio::time::sleep(pacer.wait(Instant::now())).await;
+            }
+
+            // PERF: Same transport-level timestamp preference as the
+            // blocking loop above. Currently no async transport sets
+            // receive_time_ns, so this always falls back to the clock
+            // read — preserving existing behavior for TCP/UDS/PMQ.
+            let receive_time_ns = if msg.receive_time_ns != 0 {
+                msg.receive_time_ns
+            } else {
+                get_monotonic_time_ns()
+            };
+            let wall_now_ns = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            let latency_ns = receive_time_ns.saturating_sub(msg.timestamp);
+
+            // A one-way message past its deadline is dropped: counted, but
+            // neither recorded nor processed. Requests are still answered,
+            // since the client waits for each reply and counts late ones.
+            if msg.message_type == MessageType::OneWay && msg.is_expired(receive_time_ns) {
+                expired_count += 1;
+                continue;
+            }
+
+            // --server-work or --server-spin: process the message before any reply
+            let work = match &server_work {
+                Some(work) if is_test_traffic(&msg) => Some(hook::run_timed(work.as_ref(), &msg)),
+                _ => None,
+            };
+
+            // Message received
+            let reply = match msg.message_type {
+                // Reply as --response-mode asks to complete round-trip flows.
+                MessageType::Request => args
+                    .response_mode
+                    .reply_payload(std::mem::take(&mut msg.payload))
+                    .map(|payload| {
+                        Message::new(msg.id, payload, MessageType::Response)
+                            .with_priority(msg.priority)
+                            .with_server_receive_ns(receive_time_ns)
+                    }),
+                MessageType::Ping => Some(Message::new(msg.id, Vec::new(), MessageType::Pong)),
+                // OneWay and other types need no reply.
+                _ => None,
+            };
+            if let Some(reply) = reply {
+                if transport.send(&reply).await.is_err() {
+                    info!("Client disconnected during send, exiting server loop.");
+                    tally.send_failed();
+                    stop = true;
                 }
             }
-            Err(e) => {
-                // Transport error
-                info!("Server receive loop ending due to transport error: {}", e);
+
+            // Bookkeeping waits until the reply is out, so it stays out of
+            // the client's round trip
+            tally.record(msg.id, receive_time_ns, latency_ns)?;
+            if let Some(work) = work {
+                tally.processed(work)?;
+            }
+            if should_buffer_latency(latency_file_path.is_some(), msg.id) {
+                let wall_send_ns = wall_now_ns.saturating_sub(latency_ns);
+                latency_buffer.push((wall_send_ns, latency_ns));
+            }
+            if should_buffer_latency(first_byte_file_path.is_some(), msg.id) {
+                let wall_send_ns = wall_now_ns.saturating_sub(latency_ns);
+                first_byte_buffer.push((wall_send_ns, first_byte_latency_ns(&msg, latency_ns)));
+            }
+            if stop {
                 break;
             }
         }
+        batch_stats.record(batch_len);
+        if stop {
+            break;
+        }
     }
+    batch_stats.log(batch_limit);
+    logging::report_suppressed_events(args.log_rate_limit);
+    let mut report = tally.finish();
+    report.setup_retries = setup_retry.count();
+    report.transport_stats = transport.stats();
 
     let close_result = transport.close().await;
 
     if let Some(ref path) = latency_file_path {
         write_latency_buffer(path, &latency_buffer)?;
     }
+    if let Some(ref path) = first_byte_file_path {
+        write_latency_buffer(path, &first_byte_buffer)?;
+    }
+    write_expired_count(args.internal_expired_file.as_deref(), expired_count)?;
+    write_server_report(&report)?;
 
     if let Err(e) = close_result {
         warn!("Transport close error: {}", e);
@@ -940,6 +2024,7 @@ async fn run_server_mode(args: cli::Args) -> Result<()> {
 /// - `config`: Benchmark configuration (message size, message count, etc.)
 /// - `mechanism`: The specific IPC mechanism to test
 /// - `results_manager`: Manager for collecting and outputting results
+/// - `campaign`: Campaign manifest recording completed cells, if any
 ///
 /// ## Returns
 /// - `Ok(())` if benchmark completes successfully
@@ -954,6 +2039,7 @@ async fn run_benchmark_for_mechanism(
     config: &BenchmarkConfig,
     mechanism: &IpcMechanism,
     results_manager: &mut ResultsManager,
+    campaign: Option<&mut Campaign>,
     args: &Args,
 ) -> Result<()> {
     // Create a benchmark runner for this specific mechanism
@@ -964,7 +2050,24 @@ async fn run_benchmark_for_mechanism(
     // Execute the benchmark and collect comprehensive results
     // This includes latency histograms, throughput measurements,
     // and statistical analysis (percentiles, mean, std dev, etc.)
-    let results = runner.run(Some(results_manager)).await?;
+    let mut results = runner.run(Some(results_manager)).await?;
+
+    if args.first_message_mode() == FirstMessage::Both {
+        let mut config = config.clone();
+        config.include_first_message = true;
+        let with = BenchmarkRunner::new(config, *mechanism, args.clone())
+            .run(None)
+            .await
+            .context(format!(
+                "Benchmark counting the first message failed for {}",
+                mechanism
+            ))?;
+        first_message::attach_penalty(&mut results, &with);
+    }
+
+    if let Some(campaign) = campaign {
+        campaign.record(Cell::new(*mechanism, config), &results)?;
+    }
 
     // Add results to the manager for aggregation and output
     // The manager handles both immediate streaming (if enabled)
@@ -973,6 +2076,17 @@ async fn run_benchmark_for_mechanism(
     Ok(())
 }
 
+/// Send the parent this server's report, after its readiness signal.
+fn write_server_report(report: &ServerReport) -> Result<()> {
+    // Under the lock, so no heartbeat lands inside the frame
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(&server_report_frame(report)?)
+        .context("Failed to write server report to stdout")?;
+    stdout.flush().ok();
+    Ok(())
+}
+
 /// Returns `true` if a latency value should be buffered.
 ///
 /// Latencies are only buffered when a latency file path is
@@ -982,6 +2096,111 @@ fn should_buffer_latency(latency_file_enabled: bool, message_id: u64) -> bool {
     latency_file_enabled && message_id != u64::MAX
 }
 
+/// Returns `true` for the one-way messages and requests the server does
+/// `--server-work` for: not canaries, shutdowns or pings.
+fn is_test_traffic(message: &Message) -> bool {
+    message.id != u64::MAX
+        && matches!(
+            message.message_type,
+            MessageType::OneWay | MessageType::Request
+        )
+}
+
+/// Counts of how many messages the server drained per wakeup.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct ServerBatchStats {
+    wakeups: u64,
+    messages: u64,
+    largest: usize,
+}
+
+impl ServerBatchStats {
+    fn record(&mut self, batch_len: usize) {
+        self.wakeups += 1;
+        self.messages += batch_len as u64;
+        self.largest = self.largest.max(batch_len);
+    }
+
+    fn mean(&self) -> f64 {
+        if self.wakeups == 0 {
+            0.0
+        } else {
+            self.messages as f64 / self.wakeups as f64
+        }
+    }
+
+    /// Log the batching summary when receiver batching was enabled.
+    fn log(&self, batch_limit: usize) {
+        if batch_limit > 1 {
+            info!(
+                "Server drained {} messages in {} wakeups (mean batch {:.2}, largest {}, limit {})",
+                self.messages,
+                self.wakeups,
+                self.mean(),
+                self.largest,
+                batch_limit
+            );
+        }
+    }
+}
+
+/// Consumption schedule for a server limited by `--server-rate`.
+///
+/// Each message gets the next slot, one interval after the previous one.
+/// A server that falls behind, through sleeps overshooting or sitting idle,
+/// may catch up by at most one interval, which absorbs timer granularity
+/// without allowing a burst after an idle period.
+#[derive(Debug, Clone, Copy)]
+struct ServerPacer {
+    interval: Duration,
+    next_slot: Option<Instant>,
+}
+
+impl ServerPacer {
+    fn new(messages_per_sec: u64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / messages_per_sec as f64),
+            next_slot: None,
+        }
+    }
+
+    /// How long to wait before consuming a message available at `now`.
+    fn wait(&mut self, now: Instant) -> Duration {
+        let earliest = now.checked_sub(self.interval).unwrap_or(now);
+        let slot = self.next_slot.map_or(now, |next| next.max(earliest));
+        self.next_slot = Some(slot + self.interval);
+        slot.saturating_duration_since(now)
+    }
+}
+
+/// Latency from send to the arrival of a message's first bytes.
+///
+/// Falls back to the full-message latency when the transport did not stamp
+/// first-byte arrival (message-oriented transports deliver all at once).
+fn first_byte_latency_ns(message: &Message, latency_ns: u64) -> u64 {
+    if message.first_byte_time_ns != 0 {
+        message.first_byte_time_ns.saturating_sub(message.timestamp)
+    } else {
+        latency_ns
+    }
+}
+
+/// Log the number of one-way messages the server dropped for arriving past
+/// their deadline and, when the client asked for it, write it to `path`.
+fn write_expired_count(path: Option<&str>, expired_count: usize) -> Result<()> {
+    if expired_count > 0 {
+        info!(
+            "Server dropped {} messages that arrived past their deadline",
+            expired_count
+        );
+    }
+    if let Some(path) = path {
+        std::fs::write(path, expired_count.to_string())
+            .with_context(|| format!("Failed to write expired count file: {}", path))?;
+    }
+    Ok(())
+}
+
 /// Write a buffer of latency values to a file.
 ///
 /// Each entry is written as a single line containing a
@@ -1009,11 +2228,125 @@ fn write_latency_buffer(path: &str, buffer: &[(u64, u64)]) -> Result<()> {
     Ok(())
 }
 
+/// Answer requests on every connection the client opens (`--compare-pooling`)
+///
+/// The first connection is the client's persistent one and is served on
+/// this thread; the server exits when it closes. Every later connection is
+/// a fresh one for a single request and gets a thread of its own.
+fn serve_connections_blocking(
+    mut listener: Box<dyn BlockingTransport>,
+    terms: Handshake,
+    mode: ResponseMode,
+) -> Result<()> {
+    let persistent = listener
+        .accept_blocking()
+        .context("Server failed to accept the persistent connection")?;
+    std::thread::spawn(move || {
+        while let Ok(connection) = listener.accept_blocking() {
+            std::thread::spawn(move || answer_requests_blocking(connection, terms, mode));
+        }
+    });
+    answer_requests_blocking(persistent, terms, mode);
+    Ok(())
+}
+
+/// Reply to each request on `connection` as `mode` asks until the client
+/// disconnects or sends a shutdown message, or fails the handshake.
+fn answer_requests_blocking(
+    mut connection: Box<dyn BlockingTransport>,
+    terms: Handshake,
+    mode: ResponseMode,
+) {
+    while let Ok(mut message) = connection.receive_blocking() {
+        let receive_time_ns = get_monotonic_time_ns();
+        match message.message_type {
+            MessageType::Handshake => {
+                if let Err(e) = answer_handshake_blocking(connection.as_mut(), &terms, &message) {
+                    warn!("{:#}", e);
+                    break;
+                }
+            }
+            MessageType::Request => {
+                let payload = std::mem::take(&mut message.payload);
+                let Some(payload) = mode.reply_payload(payload) else {
+                    continue;
+                };
+                let response = Message::new(message.id, payload, MessageType::Response)
+                    .with_priority(message.priority)
+                    .with_server_receive_ns(receive_time_ns);
+                if connection.send_blocking(&response).is_err() {
+                    break;
+                }
+            }
+            MessageType::Shutdown => break,
+            _ => {}
+        }
+    }
+    let _ = connection.close_blocking();
+}
+
+/// Answer a client's handshake, failing if the server refused it.
+fn answer_handshake_blocking(
+    transport: &mut dyn BlockingTransport,
+    terms: &Handshake,
+    message: &Message,
+) -> Result<()> {
+    let (reply, agreed) = handshake::answer(terms, message);
+    transport
+        .send_blocking(&reply)
+        .context("Failed to answer the client's handshake")?;
+    let agreed = agreed?;
+    transport.set_frame_checksums(agreed.frame_checksums());
+    debug!("Handshake agreed: {:?}", agreed);
+    Ok(())
+}
+
+/// Write a tag of each buffered latency, such as its priority or producer,
+/// to a file, one per line in the order of the latency file.
+fn write_tag_buffer(path: &str, buffer: &[u8]) -> Result<()> {
+    let mut file =
+        std::fs::File::create(path).with_context(|| format!("Failed to create {}", path))?;
+    for tag in buffer {
+        writeln!(file, "{}", tag)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::{BufRead, BufReader};
 
+    #[test]
+    fn test_server_batch_stats_mean_and_largest() {
+        let mut stats = ServerBatchStats::default();
+        assert_eq!(stats.mean(), 0.0);
+        for len in [1, 4, 1] {
+            stats.record(len);
+        }
+        assert_eq!(stats.wakeups, 3);
+        assert_eq!(stats.messages, 6);
+        assert_eq!(stats.largest, 4);
+        assert_eq!(stats.mean(), 2.0);
+    }
+
+    #[test]
+    fn test_server_pacer_spaces_messages_without_bursts() {
+        let mut pacer = ServerPacer::new(100);
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        assert_eq!(pacer.wait(start), Duration::ZERO);
+        assert_eq!(pacer.wait(start), ms(10));
+        assert_eq!(pacer.wait(start + ms(5)), ms(15));
+        // A late wakeup is made up by the following message
+        assert_eq!(pacer.wait(start + ms(34)), Duration::ZERO);
+        assert_eq!(pacer.wait(start + ms(34)), ms(6));
+        // After an idle period only one message goes through early
+        assert_eq!(pacer.wait(start + ms(200)), Duration::ZERO);
+        assert_eq!(pacer.wait(start + ms(200)), Duration::ZERO);
+        assert_eq!(pacer.wait(start + ms(200)), ms(10));
+    }
+
     /// Canary messages (id == u64::MAX) must not be buffered
     /// because they are warmup probes, not real measurements.
     #[test]
@@ -1043,6 +2376,31 @@ mod tests {
         assert!(!should_buffer_latency(false, u64::MAX));
     }
 
+    /// Only one-way messages and requests get `--server-work`.
+    #[test]
+    fn test_is_test_traffic() {
+        assert!(is_test_traffic(&Message::new(
+            1,
+            Vec::new(),
+            MessageType::OneWay
+        )));
+        assert!(is_test_traffic(&Message::new(
+            1,
+            Vec::new(),
+            MessageType::Request
+        )));
+        assert!(!is_test_traffic(&Message::new(
+            1,
+            Vec::new(),
+            MessageType::Ping
+        )));
+        assert!(!is_test_traffic(&Message::new(
+            u64::MAX,
+            Vec::new(),
+            MessageType::Request
+        )));
+    }
+
     /// Verify that write_latency_buffer produces one
     /// "wall_send_ns,latency_ns" pair per line, matching
     /// the format that parse_latency_file_line() expects.
@@ -1071,6 +2429,18 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    /// First-byte latency uses the transport's stamp when present and
+    /// falls back to the full-message latency otherwise.
+    #[test]
+    fn test_first_byte_latency_falls_back_to_full_latency() {
+        let mut message = Message::new(1, vec![0u8; 8], MessageType::OneWay);
+        message.timestamp = 1_000;
+        assert_eq!(first_byte_latency_ns(&message, 700), 700);
+
+        message.first_byte_time_ns = 1_250;
+        assert_eq!(first_byte_latency_ns(&message, 700), 250);
+    }
+
     /// An empty buffer should produce an empty file.
     #[test]
     fn test_write_latency_buffer_empty() {
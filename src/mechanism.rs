@@ -0,0 +1,496 @@
+//! IPC mechanism selection.
+//!
+//! [`IpcMechanism`] names the transports the benchmark can run. It lives
+//! apart from the command line so the transport factories, results and
+//! library users can name a mechanism without the clap-derived argument
+//! types; `cli` parses it from `-m` and re-exports it.
+//!
+//! [`MechanismSupport`] reports which mechanisms `-m all` runs on this
+//! build and which it leaves out and why, so a suite on Windows or macOS
+//! says what it did not cover instead of silently testing less.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Available IPC mechanisms for benchmarking
+///
+/// This enumeration defines all supported Inter-Process Communication mechanisms.
+/// Each variant corresponds to a specific implementation with its own performance
+/// characteristics and use cases.
+///
+/// ## Performance Characteristics
+///
+/// - **UnixDomainSocket**: Excellent for local communication, supports multiple clients
+/// - **SharedMemory**: Highest throughput, lowest latency, but limited to single process pairs
+/// - **TcpSocket**: Network-capable, good performance, supports multiple clients
+/// - **PosixMessageQueue**: System-integrated, message boundaries preserved, limited throughput
+/// - **Futex**: Bare wakeup baseline, one message in flight per direction
+/// - **RtSignal**: Real-time signal notification over a shared memory data plane
+/// - **Inotify**: Spool files on tmpfs announced through inotify, a legacy baseline
+/// - **Http**: HTTP/1.1 or HTTP/2 requests over loopback TCP, for REST comparisons
+/// - **Grpc**: gRPC unary calls over loopback HTTP/2
+/// - **Socketpair**: Unix sockets connected before the server starts, the pure data path
+/// - **UdpMulticast**: One publisher fanning datagrams out to N subscriber processes
+/// - **NamedPipe**: Windows named pipes, the local byte stream in place of UDS
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcMechanism {
+    /// Unix Domain Sockets
+    ///
+    /// High-performance local sockets that provide reliable, ordered communication
+    /// between processes on the same machine. Supports full-duplex communication
+    /// and multiple concurrent clients. Ideal for local service architectures.
+    #[cfg(unix)]
+    UnixDomainSocket,
+
+    /// Shared Memory
+    ///
+    /// Direct memory sharing between processes using a custom ring buffer implementation.
+    /// Provides the highest throughput and lowest latency but requires careful
+    /// synchronization. Limited to single client-server pairs in current implementation.
+    SharedMemory,
+
+    /// TCP Sockets
+    ///
+    /// Standard network sockets that can work locally or across networks.
+    /// Provides good performance with broad compatibility and multi-client support.
+    /// Socket options are tuned for low latency (TCP_NODELAY, buffer sizes).
+    TcpSocket,
+
+    /// POSIX Message Queues
+    ///
+    /// System-level message queues that preserve message boundaries and support
+    /// priority-based delivery. Integrated with OS scheduling but limited by
+    /// system-imposed queue depth restrictions (typically 10 messages).
+    #[cfg(target_os = "linux")]
+    PosixMessageQueue,
+
+    /// Futex wake/wait
+    ///
+    /// One shared memory slot per direction whose state word is waited on
+    /// and woken with raw futex calls, with no queue or serialization layer
+    /// around it beyond the message itself. Establishes the floor of
+    /// cross-process wakeup latency on the host, as a baseline for the other
+    /// mechanisms. Blocking mode only, and not part of "all".
+    #[cfg(target_os = "linux")]
+    Futex,
+
+    /// Real-time signals
+    ///
+    /// The futex transport's shared memory slots, with the receiver woken by
+    /// a queued POSIX real-time signal (`sigqueue`-style, collected with
+    /// `sigtimedwait`) instead of a futex. Measures signal delivery latency
+    /// for stacks that still notify through RT signals. Blocking mode only,
+    /// and not part of "all".
+    #[cfg(target_os = "linux")]
+    RtSignal,
+
+    /// File + inotify
+    ///
+    /// Each message is written as a file into a spool directory on tmpfs and
+    /// picked up by a peer watching the directory with inotify. A
+    /// deliberately "worst practice" baseline that many legacy systems
+    /// still use, to show what migrating away from it is worth. Blocking
+    /// mode only, and not part of "all".
+    #[cfg(target_os = "linux")]
+    Inotify,
+
+    /// HTTP loopback requests
+    ///
+    /// Messages travel as HTTP request and response bodies over a loopback
+    /// TCP connection, speaking HTTP/1.1 or HTTP/2 (--http-version). Puts a
+    /// number on REST between local services next to the raw transports.
+    /// Not part of "all".
+    Http,
+
+    /// gRPC loopback calls
+    ///
+    /// Every message is a unary gRPC call over a loopback HTTP/2
+    /// connection, to compare the overhead of gRPC between local services
+    /// with raw TCP and UDS. Blocking mode only, and not part of "all".
+    Grpc,
+
+    /// Pre-connected socketpair
+    ///
+    /// The Unix domain socket transport over a `socketpair()` the client
+    /// creates before spawning the server, which inherits its end across
+    /// exec. There is no bind, connect or accept, so this measures the pure
+    /// data-path cost of Unix sockets. Blocking mode only, and not part of
+    /// "all".
+    #[cfg(unix)]
+    Socketpair,
+
+    /// UDP multicast fan-out
+    ///
+    /// One publisher sends each message as a datagram to a multicast group
+    /// (--multicast-group) and --subscribers processes receive a copy each,
+    /// reporting delivery latency and loss per subscriber. For evaluating
+    /// multicast-based local bus architectures. One-way, blocking mode
+    /// only, and not part of "all".
+    #[cfg(target_os = "linux")]
+    UdpMulticast,
+
+    /// Windows named pipes
+    ///
+    /// A local-only byte-mode pipe in the `\\.\pipe\` namespace, Windows'
+    /// native stream IPC and its counterpart of Unix domain sockets.
+    #[cfg(windows)]
+    NamedPipe,
+
+    /// All available mechanisms
+    ///
+    /// Convenience option that expands to test all supported IPC mechanisms
+    /// sequentially. Useful for comprehensive performance comparisons across
+    /// all available transport types.
+    All,
+}
+
+impl std::fmt::Display for IpcMechanism {
+    /// Provide human-readable names for IPC mechanisms
+    ///
+    /// Used in output formatting and logging to present user-friendly
+    /// names rather than the internal enum variant names.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(unix)]
+            IpcMechanism::UnixDomainSocket => write!(f, "Unix Domain Socket"),
+            IpcMechanism::SharedMemory => write!(f, "Shared Memory"),
+            IpcMechanism::TcpSocket => write!(f, "TCP Socket"),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::PosixMessageQueue => write!(f, "POSIX Message Queue"),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Futex => write!(f, "Futex"),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::RtSignal => write!(f, "RT Signal"),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Inotify => write!(f, "File + inotify"),
+            IpcMechanism::Http => write!(f, "HTTP"),
+            IpcMechanism::Grpc => write!(f, "gRPC"),
+            #[cfg(unix)]
+            IpcMechanism::Socketpair => write!(f, "Socketpair"),
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => write!(f, "UDP Multicast"),
+            #[cfg(windows)]
+            IpcMechanism::NamedPipe => write!(f, "Named Pipe"),
+            IpcMechanism::All => write!(f, "All Mechanisms"),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl IpcMechanism {
+    /// Every mechanism of this platform, in declaration order
+    pub const VARIANTS: &'static [IpcMechanism] = &[
+        #[cfg(unix)]
+        IpcMechanism::UnixDomainSocket,
+        IpcMechanism::SharedMemory,
+        IpcMechanism::TcpSocket,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::PosixMessageQueue,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::Futex,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::RtSignal,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::Inotify,
+        IpcMechanism::Http,
+        IpcMechanism::Grpc,
+        #[cfg(unix)]
+        IpcMechanism::Socketpair,
+        #[cfg(target_os = "linux")]
+        IpcMechanism::UdpMulticast,
+        #[cfg(windows)]
+        IpcMechanism::NamedPipe,
+        IpcMechanism::All,
+    ];
+
+    /// Name of the mechanism on the command line (`-m`), in spawned server
+    /// arguments and in file names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(unix)]
+            IpcMechanism::UnixDomainSocket => "uds",
+            IpcMechanism::SharedMemory => "shm",
+            IpcMechanism::TcpSocket => "tcp",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::PosixMessageQueue => "pmq",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Futex => "futex",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::RtSignal => "rtsig",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::Inotify => "inotify",
+            IpcMechanism::Http => "http",
+            IpcMechanism::Grpc => "grpc",
+            #[cfg(unix)]
+            IpcMechanism::Socketpair => "socketpair",
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => "udp-multicast",
+            #[cfg(windows)]
+            IpcMechanism::NamedPipe => "pipe",
+            IpcMechanism::All => "all",
+        }
+    }
+
+    /// The mechanism named `name` on the command line, ignoring case.
+    pub fn from_name(name: &str) -> Option<IpcMechanism> {
+        Self::VARIANTS
+            .iter()
+            .copied()
+            .find(|mechanism| mechanism.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Expand the "All" variant to all available mechanisms
+    ///
+    /// This function handles the special "all" mechanism by expanding it to
+    /// the complete list of concrete IPC mechanisms. If "all" is present
+    /// anywhere in the input list, it returns all mechanisms; otherwise,
+    /// it returns the input list unchanged.
+    ///
+    /// ## Parameters
+    /// - `mechanisms`: Vector of mechanisms which may include the "All" variant
+    ///
+    /// ## Returns
+    /// Vector of concrete mechanisms with "All" expanded if present
+    ///
+    /// ## Example (Linux)
+    /// ```rust
+    /// # use ipc_benchmark::IpcMechanism;
+    /// // On Linux, UDS, SHM, TCP and PMQ, less those built without
+    /// #[cfg(target_os = "linux")]
+    /// {
+    ///     let input = vec![IpcMechanism::All];
+    ///     let expanded = IpcMechanism::expand_all(input);
+    ///     let built = [cfg!(feature = "shm"), cfg!(feature = "tcp"), cfg!(feature = "pmq")];
+    ///     assert_eq!(expanded.len(), 1 + built.iter().filter(|&&b| b).count());
+    /// }
+    /// ```
+    ///
+    /// ## Example (macOS/BSD)
+    /// ```rust
+    /// # use ipc_benchmark::IpcMechanism;
+    /// // On non-Linux Unix, PMQ is not available
+    /// #[cfg(all(unix, not(target_os = "linux")))]
+    /// {
+    ///     let input = vec![IpcMechanism::All];
+    ///     let expanded = IpcMechanism::expand_all(input);
+    ///     let built = [cfg!(feature = "shm"), cfg!(feature = "tcp")];
+    ///     assert_eq!(expanded.len(), 1 + built.iter().filter(|&&b| b).count());
+    /// }
+    /// ```
+    ///
+    /// ## Example (Windows)
+    /// ```rust
+    /// # use ipc_benchmark::IpcMechanism;
+    /// // On Windows, named pipes take the place of UDS and PMQ is not available
+    /// #[cfg(windows)]
+    /// {
+    ///     let input = vec![IpcMechanism::All];
+    ///     let expanded = IpcMechanism::expand_all(input);
+    ///     let built = [cfg!(feature = "shm"), cfg!(feature = "tcp")];
+    ///     assert_eq!(expanded.len(), 1 + built.iter().filter(|&&b| b).count());
+    /// }
+    /// ```
+    pub fn expand_all(mechanisms: Vec<IpcMechanism>) -> Vec<IpcMechanism> {
+        if mechanisms.contains(&IpcMechanism::All) {
+            // Start with a base list of mechanisms that work on all platforms,
+            // in a logical order.
+            let mut all = vec![
+                // SHM second (highest performance)
+                #[cfg(feature = "shm")]
+                IpcMechanism::SharedMemory,
+                // TCP third (network-capable)
+                #[cfg(feature = "tcp")]
+                IpcMechanism::TcpSocket,
+            ];
+
+            // Conditionally add POSIX Message Queues on non-Windows platforms
+            // UDS first (most commonly used for local IPC)
+            #[cfg(unix)]
+            all.insert(0, IpcMechanism::UnixDomainSocket);
+            // Named pipes in its place on Windows
+            #[cfg(windows)]
+            all.insert(0, IpcMechanism::NamedPipe);
+            // PMQ last (most constrained), and only on Linux
+            #[cfg(all(target_os = "linux", feature = "pmq"))]
+            all.push(IpcMechanism::PosixMessageQueue);
+
+            all
+        } else {
+            mechanisms
+        }
+    }
+}
+
+/// Reason for mechanisms that are built but only run when named with `-m`
+pub const OPT_IN: &str = "opt-in, name them with -m";
+
+/// Reason for mechanisms that need a Unix platform
+pub const UNIX_ONLY: &str = "Unix only";
+
+/// Reason for mechanisms that need Linux
+pub const LINUX_ONLY: &str = "Linux only";
+
+/// Reason for mechanisms that need Windows
+pub const WINDOWS_ONLY: &str = "Windows only";
+
+/// A mechanism `-m all` leaves out on this build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Omission {
+    /// `-m` name of the mechanism
+    pub mechanism: String,
+
+    /// Why it is left out
+    pub reason: String,
+}
+
+impl Omission {
+    fn new(mechanism: &str, reason: &str) -> Self {
+        Self {
+            mechanism: mechanism.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// What `-m all` covers on this build
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MechanismSupport {
+    /// `-m` names of the mechanisms `-m all` runs, in order
+    pub in_all: Vec<String>,
+
+    /// Mechanisms it leaves out: opt-in ones, ones for another platform
+    /// and ones this build was compiled without
+    pub left_out: Vec<Omission>,
+}
+
+impl MechanismSupport {
+    /// Coverage of `-m all` for the platform and features of this build.
+    pub fn of_this_build() -> Self {
+        let in_all = IpcMechanism::expand_all(vec![IpcMechanism::All]);
+        // Extended below with the mechanisms of other platforms
+        #[allow(unused_mut)]
+        let mut left_out: Vec<Omission> = IpcMechanism::VARIANTS
+            .iter()
+            .filter(|m| **m != IpcMechanism::All && !in_all.contains(m))
+            .map(|m| Omission::new(m.name(), m.omission_reason()))
+            .collect();
+        #[cfg(not(unix))]
+        for name in ["uds", "socketpair"] {
+            left_out.push(Omission::new(name, UNIX_ONLY));
+        }
+        #[cfg(not(target_os = "linux"))]
+        for name in ["pmq", "futex", "rtsig", "inotify", "udp-multicast"] {
+            left_out.push(Omission::new(name, LINUX_ONLY));
+        }
+        #[cfg(not(windows))]
+        left_out.push(Omission::new("pipe", WINDOWS_ONLY));
+        Self {
+            in_all: in_all.iter().map(|m| m.name().to_string()).collect(),
+            left_out,
+        }
+    }
+}
+
+impl IpcMechanism {
+    /// Why `-m all` leaves out this built mechanism.
+    fn omission_reason(&self) -> &'static str {
+        match self {
+            #[cfg(not(feature = "shm"))]
+            IpcMechanism::SharedMemory => "built without the `shm` feature",
+            #[cfg(not(feature = "tcp"))]
+            IpcMechanism::TcpSocket => "built without the `tcp` feature",
+            #[cfg(all(target_os = "linux", not(feature = "pmq")))]
+            IpcMechanism::PosixMessageQueue => "built without the `pmq` feature",
+            _ => OPT_IN,
+        }
+    }
+}
+
+impl fmt::Display for MechanismSupport {
+    /// `-m all runs uds, shm; leaves out futex, http (opt-in, ...)`, with
+    /// the left-out mechanisms grouped by reason.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "-m all runs {}", self.in_all.join(", "))?;
+        let mut reasons: Vec<&str> = Vec::new();
+        for omission in &self.left_out {
+            if !reasons.contains(&omission.reason.as_str()) {
+                reasons.push(&omission.reason);
+            }
+        }
+        for (i, reason) in reasons.iter().enumerate() {
+            let names: Vec<&str> = self
+                .left_out
+                .iter()
+                .filter(|o| o.reason == *reason)
+                .map(|o| o.mechanism.as_str())
+                .collect();
+            let lead = if i == 0 { "; leaves out" } else { "," };
+            write!(f, "{} {} ({})", lead, names.join(", "), reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_support_accounts_for_every_mechanism() {
+        let support = MechanismSupport::of_this_build();
+        assert_eq!(
+            support.in_all,
+            IpcMechanism::expand_all(vec![IpcMechanism::All])
+                .iter()
+                .map(|m| m.name())
+                .collect::<Vec<_>>()
+        );
+        // Every mechanism of the CLI is either run or left out with a reason
+        for mechanism in IpcMechanism::VARIANTS {
+            if *mechanism == IpcMechanism::All {
+                continue;
+            }
+            let name = mechanism.name();
+            let covered = support.in_all.iter().any(|m| m == name);
+            let omitted = support.left_out.iter().any(|o| o.mechanism == name);
+            assert!(covered != omitted, "{}", name);
+        }
+        #[cfg(not(target_os = "linux"))]
+        assert!(support.left_out.contains(&Omission::new("pmq", LINUX_ONLY)));
+        #[cfg(not(windows))]
+        assert!(support
+            .left_out
+            .contains(&Omission::new("pipe", WINDOWS_ONLY)));
+    }
+
+    #[test]
+    fn test_support_display_groups_reasons() {
+        let support = MechanismSupport {
+            in_all: vec!["shm".into(), "tcp".into()],
+            left_out: vec![
+                Omission::new("uds", UNIX_ONLY),
+                Omission::new("http", OPT_IN),
+                Omission::new("socketpair", UNIX_ONLY),
+            ],
+        };
+        assert_eq!(
+            support.to_string(),
+            "-m all runs shm, tcp; leaves out uds, socketpair (Unix only), \
+             http (opt-in, name them with -m)"
+        );
+    }
+
+    #[test]
+    fn test_from_name_ignores_case() {
+        assert_eq!(
+            IpcMechanism::from_name("TCP"),
+            Some(IpcMechanism::TcpSocket)
+        );
+        assert_eq!(IpcMechanism::from_name("all"), Some(IpcMechanism::All));
+        assert_eq!(IpcMechanism::from_name("carrier-pigeon"), None);
+        for mechanism in IpcMechanism::VARIANTS {
+            assert_eq!(IpcMechanism::from_name(mechanism.name()), Some(*mechanism));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Memory capacity of the benchmark host.
+//!
+//! Records how much memory the benchmark could actually use, so results from
+//! a small CI runner are not mistaken for ones from a large host. On Linux
+//! the figures come from `/proc/meminfo` and, when the process runs inside a
+//! memory-limited cgroup (as in most containers), from the cgroup's limit.
+//! Other Unix systems report physical memory only.
+
+use serde::{Deserialize, Serialize};
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Memory available to this host and process.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryInfo {
+    /// Physical memory of the host, in bytes
+    pub total_bytes: u64,
+
+    /// Memory available for new allocations without swapping, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub available_bytes: Option<u64>,
+
+    /// Configured swap space, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap_total_bytes: Option<u64>,
+
+    /// Unused swap space, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap_free_bytes: Option<u64>,
+
+    /// Pages in the default huge page pool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hugepages_total: Option<u64>,
+
+    /// Unallocated pages in the default huge page pool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hugepages_free: Option<u64>,
+
+    /// Size of a page in the default huge page pool, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hugepage_size_bytes: Option<u64>,
+
+    /// Memory limit of the process's cgroup, in bytes
+    ///
+    /// `None` when there is no limit or it is no lower than `total_bytes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_limit_bytes: Option<u64>,
+}
+
+impl MemoryInfo {
+    /// Detect the memory of this host, returning `None` if it can't be read.
+    pub fn query() -> Option<Self> {
+        query_platform()
+    }
+
+    /// Memory usable by this process in gigabytes: the physical total,
+    /// capped by the cgroup limit.
+    pub fn effective_gb(&self) -> f64 {
+        let bytes = self
+            .cgroup_limit_bytes
+            .map_or(self.total_bytes, |limit| limit.min(self.total_bytes));
+        bytes as f64 / BYTES_PER_GB
+    }
+}
+
+/// Parse `/proc/meminfo`, whose sizes are in KiB and page counts unitless.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_meminfo(meminfo: &str) -> Option<MemoryInfo> {
+    let value = |key: &str| {
+        meminfo.lines().find_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            if name != key {
+                return None;
+            }
+            let mut parts = rest.split_whitespace();
+            let number: u64 = parts.next()?.parse().ok()?;
+            Some(match parts.next() {
+                Some("kB") => number * 1024,
+                _ => number,
+            })
+        })
+    };
+
+    Some(MemoryInfo {
+        total_bytes: value("MemTotal")?,
+        available_bytes: value("MemAvailable"),
+        swap_total_bytes: value("SwapTotal"),
+        swap_free_bytes: value("SwapFree"),
+        hugepages_total: value("HugePages_Total"),
+        hugepages_free: value("HugePages_Free"),
+        hugepage_size_bytes: value("Hugepagesize"),
+        cgroup_limit_bytes: None,
+    })
+}
+
+/// Parse a cgroup memory limit file: v2 `memory.max` ("max" when unlimited)
+/// or v1 `memory.limit_in_bytes`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cgroup_limit(contents: &str) -> Option<u64> {
+    contents.trim().parse().ok()
+}
+
+/// Find the process's cgroup paths in `/proc/self/cgroup`: the unified (v2)
+/// hierarchy and the v1 memory controller, whichever are present.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_cgroup(contents: &str) -> (Option<&str>, Option<&str>) {
+    let mut unified = None;
+    let mut memory_v1 = None;
+    for line in contents.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if id == "0" && controllers.is_empty() {
+            unified = Some(path);
+        } else if controllers.split(',').any(|c| c == "memory") {
+            memory_v1 = Some(path);
+        }
+    }
+    (unified, memory_v1)
+}
+
+#[cfg(target_os = "linux")]
+fn query_platform() -> Option<MemoryInfo> {
+    let mut info = parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)?;
+    info.cgroup_limit_bytes = cgroup_limit().filter(|&limit| limit < info.total_bytes);
+    Some(info)
+}
+
+/// The tightest memory limit on this process's cgroup or its ancestors.
+///
+/// Inside a container the cgroup namespace usually makes the process's own
+/// cgroup appear as the root of the mount, so the mount root is checked too.
+#[cfg(target_os = "linux")]
+fn cgroup_limit() -> Option<u64> {
+    use std::path::Path;
+
+    let read = |path: &Path| {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| parse_cgroup_limit(&contents))
+    };
+    let proc_cgroup = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    let (unified, memory_v1) = parse_proc_cgroup(&proc_cgroup);
+
+    if let Some(path) = unified {
+        let root = Path::new("/sys/fs/cgroup");
+        let own = root.join(path.trim_start_matches('/'));
+        let limit = own
+            .ancestors()
+            .take_while(|dir| dir.starts_with(root))
+            .filter_map(|dir| read(&dir.join("memory.max")))
+            .min();
+        if limit.is_some() {
+            return limit;
+        }
+    }
+
+    let root = Path::new("/sys/fs/cgroup/memory");
+    let own = root.join(memory_v1.unwrap_or("/").trim_start_matches('/'));
+    read(&own.join("memory.limit_in_bytes")).or_else(|| read(&root.join("memory.limit_in_bytes")))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn query_platform() -> Option<MemoryInfo> {
+    let pages = unsafe { libc::sysconf(libc::_SC_PHYS_PAGES) };
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if pages <= 0 || page_size <= 0 {
+        return None;
+    }
+    Some(MemoryInfo {
+        total_bytes: pages as u64 * page_size as u64,
+        ..Default::default()
+    })
+}
+
+#[cfg(not(unix))]
+fn query_platform() -> Option<MemoryInfo> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = "MemTotal:        4028760 kB\n\
+                       MemFree:          312456 kB\n\
+                       MemAvailable:    2516788 kB\n\
+                       SwapTotal:       2097148 kB\n\
+                       SwapFree:        2097148 kB\n\
+                       HugePages_Total:      64\n\
+                       HugePages_Free:       60\n\
+                       Hugepagesize:       2048 kB\n";
+        let info = parse_meminfo(meminfo).unwrap();
+        assert_eq!(info.total_bytes, 4_028_760 * 1024);
+        assert_eq!(info.available_bytes, Some(2_516_788 * 1024));
+        assert_eq!(info.swap_total_bytes, Some(2_097_148 * 1024));
+        assert_eq!(info.swap_free_bytes, Some(2_097_148 * 1024));
+        assert_eq!(info.hugepages_total, Some(64));
+        assert_eq!(info.hugepages_free, Some(60));
+        assert_eq!(info.hugepage_size_bytes, Some(2 * 1024 * 1024));
+        assert_eq!(info.cgroup_limit_bytes, None);
+        assert!(parse_meminfo("MemFree: 312456 kB\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_cgroup() {
+        assert_eq!(parse_cgroup_limit("1073741824\n"), Some(1 << 30));
+        assert_eq!(parse_cgroup_limit("max\n"), None);
+
+        let v2 = "0::/system.slice/ci-runner.service\n";
+        assert_eq!(
+            parse_proc_cgroup(v2),
+            (Some("/system.slice/ci-runner.service"), None)
+        );
+        let v1 = "12:cpu,cpuacct:/docker/abc\n9:memory:/docker/abc\n1:name=systemd:/docker/abc\n";
+        assert_eq!(parse_proc_cgroup(v1), (None, Some("/docker/abc")));
+    }
+
+    #[test]
+    fn test_effective_gb() {
+        let mut info = MemoryInfo {
+            total_bytes: 16 << 30,
+            ..Default::default()
+        };
+        assert_eq!(info.effective_gb(), 16.0);
+        info.cgroup_limit_bytes = Some(4 << 30);
+        assert_eq!(info.effective_gb(), 4.0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_query() {
+        let info = MemoryInfo::query().expect("memory size is readable on Unix");
+        assert!(info.total_bytes > 0);
+        assert!(info.effective_gb() > 0.0);
+    }
+}
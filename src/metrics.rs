@@ -51,9 +51,23 @@
 //! # }
 //! ```
 
+use crate::affinity_check::AffinityCheck;
+use crate::bootstrap::{self, PercentileInterval};
+use crate::confidence::{Confidence, CONVERGENCE_WINDOW};
+use crate::first_message::FirstMessagePenalty;
+use crate::goodput::Goodput;
+use crate::multicast::SubscriberReport;
+use crate::pacing::PacingReport;
+use crate::pair::PairReport;
+use crate::producers::ProducerReport;
+use crate::queue_depth::QueueDepthReport;
+use crate::reservoir::{LatencyReservoir, ReservoirSampler};
+use crate::server_handle::ServerReport;
+use crate::sla::SlaQuantile;
 use anyhow::Result;
 use hdrhistogram::Histogram;
 use serde::{Deserialize, Serialize};
+use std::collections::{btree_map, BTreeMap};
 use std::time::{Duration, Instant};
 
 /// Latency measurement types
@@ -84,6 +98,12 @@ pub enum LatencyType {
     /// the corresponding response. This includes transmission time in both
     /// directions plus any processing time on the server side.
     RoundTrip,
+
+    /// Gap between consecutive messages arriving at the server
+    ///
+    /// Not a latency of any one message: it shows how bursty delivery is,
+    /// whatever the sender's pacing.
+    InterArrival,
 }
 
 /// Comprehensive latency metrics including percentiles and statistics
@@ -137,6 +157,31 @@ pub struct LatencyMetrics {
     /// This data can be used for creating latency distribution plots
     /// or performing custom statistical analysis beyond the provided metrics.
     pub histogram_data: Vec<u64>,
+
+    /// Sample counts in power-of-two latency buckets, from the lowest
+    /// occupied bucket to the highest
+    ///
+    /// Drives the latency histogram in the terminal summary.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buckets: Vec<LatencyBucket>,
+
+    /// The full HDR histogram behind these metrics, for exporting the
+    /// percentile distribution
+    ///
+    /// Not serialized; absent when the metrics were read back from JSON.
+    #[serde(skip)]
+    pub histogram: Option<Histogram<u64>>,
+
+    /// Whether the distribution can be trusted: sample support of each
+    /// percentile, warmup convergence, throttling and the noise floor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+
+    /// Bootstrap confidence interval of each requested percentile, where
+    /// raw latencies were kept (`--latency-reservoir`, or the streaming
+    /// file in `analyze`); empty otherwise
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub percentile_intervals: Vec<PercentileInterval>,
 }
 
 /// Percentile value pair
@@ -160,6 +205,10 @@ pub struct LatencyMetrics {
 /// #     percentiles: vec![PercentileValue { percentile: 50.0, value_ns: 1000 }],
 /// #     total_samples: 1,
 /// #     histogram_data: vec![],
+/// #     buckets: vec![],
+/// #     histogram: None,
+/// #     confidence: None,
+/// #     percentile_intervals: vec![],
 /// # };
 /// for percentile in &metrics.percentiles {
 ///     println!("P{}: {}μs", percentile.percentile, percentile.value_ns / 1000);
@@ -177,6 +226,19 @@ pub struct PercentileValue {
     pub value_ns: u64,
 }
 
+/// Number of samples that fell into one latency bucket
+///
+/// A bucket covers latencies above the previous bucket's upper bound up to
+/// and including its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    /// Highest latency in this bucket, in nanoseconds
+    pub upper_bound_ns: u64,
+
+    /// Samples recorded in this bucket
+    pub count: u64,
+}
+
 /// Throughput metrics including message rate and bandwidth
 ///
 /// This structure provides comprehensive throughput analysis, measuring
@@ -245,6 +307,106 @@ pub struct PerformanceMetrics {
     /// Throughput measurement results (always present)
     pub throughput: ThroughputMetrics,
 
+    /// Round-trip replies that did not correlate with their request
+    #[serde(default)]
+    pub error_count: usize,
+
+    /// Messages dropped for arriving past their deadline (`--deadline`);
+    /// they are not part of the latency distribution
+    #[serde(default)]
+    pub expired_count: usize,
+
+    /// Latency of each message priority (`--priorities`), lowest first;
+    /// empty when messages were not prioritized
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_latency: Vec<LatencyBreakdown<Priority>>,
+
+    /// Latency of each phase of a round trip, in phase order; empty when
+    /// replies carried no server timestamps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phase_latency: Vec<LatencyBreakdown<RoundTripPhase>>,
+
+    /// Latency of requests on the persistent connection and on fresh
+    /// connections (`--compare-pooling`); empty when not compared
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connection_latency: Vec<LatencyBreakdown<ConnectionReuse>>,
+
+    /// Cost of the client's per-message processing (`--client-work` or a
+    /// client hook), and the latency including it; empty without a hook
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processing_latency: Vec<LatencyBreakdown<ProcessingStage>>,
+
+    /// On-CPU and off-CPU time of the client thread over each measured call
+    /// (`--cpu-split`); empty when not measured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_split: Vec<LatencyBreakdown<CpuSplit>>,
+
+    /// Queue depth sampled during the test (`--queue-depth-interval`); None
+    /// when not sampled or the transport has no queue to inspect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_depth: Option<QueueDepthReport>,
+
+    /// Requested and achieved send intervals (`--send-delay`); None when
+    /// sends were not paced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pacing: Option<PacingReport>,
+
+    /// Server-to-client phase of a one-way test run with `--pair`; None
+    /// otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pair: Option<PairReport>,
+
+    /// Heap allocations of the client's send loop after its first message
+    /// (`--no-alloc-hot-path`); None when not counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_allocations: Option<u64>,
+
+    /// The test run counting its first message next to this one
+    /// (`--first-message both`); None when it was not run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_message: Option<FirstMessagePenalty>,
+
+    /// Latency of the first message counted, for comparing runs with
+    /// `--first-message both`; not serialized
+    #[serde(skip)]
+    pub first_latency_ns: Option<u64>,
+
+    /// Where the pinned client and server ran during the test
+    /// (`--client-affinity`, `--server-affinity`); empty when neither was
+    /// pinned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affinity: Vec<AffinityCheck>,
+
+    /// Delivery of each producer (`--producers`); None with a single
+    /// producer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producers: Option<ProducerReport>,
+
+    /// Delivery and loss at each multicast subscriber (`-m udp-multicast`);
+    /// None for other mechanisms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscribers: Option<SubscriberReport>,
+
+    /// What the spawned server reported about its side of the test; None
+    /// when the server sent no report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerReport>,
+
+    /// Raw latencies sampled uniformly from the test
+    /// (`--latency-reservoir`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_reservoir: Option<LatencyReservoir>,
+
+    /// Throughput of the messages within the latency budget
+    /// (`--goodput-budget`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goodput: Option<Goodput>,
+
+    /// Share of the latency samples within each `--sla-thresholds`
+    /// threshold
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sla: Vec<SlaQuantile>,
+
     /// Timestamp when these metrics were collected
     ///
     /// Used for correlating results across multiple test runs and
@@ -252,6 +414,175 @@ pub struct PerformanceMetrics {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+impl PerformanceMetrics {
+    /// Percentage of messages dropped for missing their deadline, out of
+    /// all messages sent (delivered in time plus dropped)
+    pub fn deadline_miss_percent(&self) -> f64 {
+        let sent = self.throughput.total_messages + self.expired_count;
+        if sent == 0 {
+            0.0
+        } else {
+            self.expired_count as f64 / sent as f64 * 100.0
+        }
+    }
+}
+
+/// Latency of the messages that share one key: a priority, a round-trip
+/// phase, a kind of connection, a processing stage or a CPU split
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBreakdown<K> {
+    /// What these messages share; older results name this field after the
+    /// kind of key
+    #[serde(
+        alias = "priority",
+        alias = "phase",
+        alias = "connection",
+        alias = "stage",
+        alias = "split"
+    )]
+    pub key: K,
+
+    /// Latency of those messages
+    pub latency: LatencyMetrics,
+}
+
+/// Priority a message was sent at (`--priorities`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Priority(pub u8);
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "priority {}", self.0)
+    }
+}
+
+/// One leg of a round trip, split out using the server's timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundTripPhase {
+    /// Client send until the server received the request
+    RequestTransit,
+
+    /// Server receive of the request until the server sent the reply
+    ServerProcessing,
+
+    /// Server send of the reply until the client received it
+    ReplyTransit,
+}
+
+impl RoundTripPhase {
+    /// Split a round trip into its phases from four monotonic timestamps:
+    /// the client's send, the server's receive and send, and the client's
+    /// receive. The clocks must be the same, so this only holds when
+    /// client and server share a host. Returns `None` when the server did
+    /// not stamp its receive or the timestamps are out of order.
+    pub fn split(
+        client_send_ns: u64,
+        server_receive_ns: u64,
+        server_send_ns: u64,
+        client_receive_ns: u64,
+    ) -> Option<[(Self, Duration); 3]> {
+        let ordered = client_send_ns <= server_receive_ns
+            && server_receive_ns <= server_send_ns
+            && server_send_ns <= client_receive_ns;
+        if server_receive_ns == 0 || !ordered {
+            return None;
+        }
+        Some([
+            (
+                Self::RequestTransit,
+                Duration::from_nanos(server_receive_ns - client_send_ns),
+            ),
+            (
+                Self::ServerProcessing,
+                Duration::from_nanos(server_send_ns - server_receive_ns),
+            ),
+            (
+                Self::ReplyTransit,
+                Duration::from_nanos(client_receive_ns - server_send_ns),
+            ),
+        ])
+    }
+}
+
+impl std::fmt::Display for RoundTripPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::RequestTransit => "request transit",
+            Self::ServerProcessing => "server processing",
+            Self::ReplyTransit => "reply transit",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Whether a request reused the persistent connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionReuse {
+    /// Sent on the connection kept open for the whole test, as a pooled
+    /// client would
+    Warm,
+
+    /// Sent on a connection opened for this request alone; the latency
+    /// includes connection setup
+    Cold,
+}
+
+impl std::fmt::Display for ConnectionReuse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Warm => "warm",
+            Self::Cold => "cold",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a processing latency measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingStage {
+    /// Time the client's hook spent on each message
+    ClientHook,
+
+    /// Transport latency plus the client's hook time, as seen by an
+    /// application doing that work for every message
+    EndToEnd,
+}
+
+impl std::fmt::Display for ProcessingStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::ClientHook => "client hook",
+            Self::EndToEnd => "end-to-end",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Which part of a measured call a CPU split latency covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CpuSplit {
+    /// Time the client thread spent running: copies, syscalls, serialization
+    OnCpu,
+
+    /// The rest of the call: blocked waiting for the peer or descheduled
+    OffCpu,
+}
+
+impl std::fmt::Display for CpuSplit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::OnCpu => "on-CPU",
+            Self::OffCpu => "off-CPU",
+        };
+        f.write_str(name)
+    }
+}
+
 /// Latency collector using HDR histogram for accurate measurement
 ///
 /// The `LatencyCollector` implements high-precision latency measurement using
@@ -302,6 +633,11 @@ pub struct LatencyCollector {
 
     /// Exact maximum latency observed (in nanoseconds)
     observed_max_ns: Option<u64>,
+
+    /// Median of the first [`CONVERGENCE_WINDOW`] samples, for judging
+    /// whether latency had settled when measurement began; taken from the
+    /// histogram once the window fills
+    head_median_ns: Option<u64>,
 }
 
 impl LatencyCollector {
@@ -336,6 +672,7 @@ impl LatencyCollector {
             sample_count: 0,
             observed_min_ns: None,
             observed_max_ns: None,
+            head_median_ns: None,
         })
     }
 
@@ -359,6 +696,9 @@ impl LatencyCollector {
         let latency_ns = latency.as_nanos() as u64;
         self.histogram.record(latency_ns)?;
         self.sample_count += 1;
+        if self.sample_count == CONVERGENCE_WINDOW {
+            self.head_median_ns = Some(self.histogram.value_at_quantile(0.50));
+        }
 
         // Track exact min/max as observed to avoid histogram quantization effects
         self.observed_min_ns = Some(match self.observed_min_ns {
@@ -404,6 +744,11 @@ impl LatencyCollector {
         // Calculate standard deviation using HDR histogram's built-in method
         let mean = self.histogram.mean();
         let std_dev = self.histogram.stdev();
+        let median_ns = self.histogram.value_at_quantile(0.50) as f64;
+        // Until the window fills, every sample is in it
+        let head_median_ns = self
+            .head_median_ns
+            .or_else(|| (!self.histogram.is_empty()).then_some(median_ns as u64));
 
         LatencyMetrics {
             latency_type: self.latency_type,
@@ -411,11 +756,20 @@ impl LatencyCollector {
             min_ns: self.observed_min_ns.unwrap_or_else(|| self.histogram.min()),
             max_ns: self.observed_max_ns.unwrap_or_else(|| self.histogram.max()),
             mean_ns: mean,
-            median_ns: self.histogram.value_at_quantile(0.50) as f64,
+            median_ns,
             std_dev_ns: std_dev,
             percentiles: percentile_values,
             total_samples: self.sample_count,
             histogram_data: self.get_histogram_data(),
+            buckets: self.get_buckets(),
+            histogram: Some(self.histogram.clone()),
+            confidence: Some(Confidence::assess(
+                self.sample_count,
+                percentiles,
+                median_ns,
+                head_median_ns,
+            )),
+            percentile_intervals: Vec::new(),
         }
     }
 
@@ -442,6 +796,19 @@ impl LatencyCollector {
         data
     }
 
+    /// Sample counts in power-of-two buckets, without the empty buckets
+    /// below the fastest sample.
+    fn get_buckets(&self) -> Vec<LatencyBucket> {
+        self.histogram
+            .iter_log(1, 2.0)
+            .map(|bucket| LatencyBucket {
+                upper_bound_ns: bucket.value_iterated_to(),
+                count: bucket.count_since_last_iteration(),
+            })
+            .skip_while(|bucket| bucket.count == 0)
+            .collect()
+    }
+
     /// Reset the collector
     ///
     /// Clears all collected measurements and resets timing information.
@@ -454,6 +821,7 @@ impl LatencyCollector {
     /// - Updates start time to current instant
     pub fn reset(&mut self) {
         self.histogram.reset();
+        self.head_median_ns = None;
         self.sample_count = 0;
         self.start_time = Instant::now();
         self.observed_min_ns = None;
@@ -624,6 +992,58 @@ pub struct MetricsCollector {
 
     /// Percentiles to calculate for latency analysis
     pub percentiles: Vec<f64>,
+
+    /// Errors counted during measurement, such as replies that did not
+    /// correlate with their request
+    pub error_count: usize,
+
+    /// Messages dropped for arriving past their deadline
+    pub expired_count: usize,
+
+    /// Latency collectors for each message priority seen
+    priority_collectors: BTreeMap<Priority, LatencyCollector>,
+
+    /// Latency collectors for each round-trip phase recorded
+    phase_collectors: BTreeMap<RoundTripPhase, LatencyCollector>,
+
+    /// Latency collectors for warm and cold connections
+    connection_collectors: BTreeMap<ConnectionReuse, LatencyCollector>,
+
+    /// Latency collectors for the client hook and end-to-end latency
+    processing_collectors: BTreeMap<ProcessingStage, LatencyCollector>,
+
+    /// Latency collectors for on-CPU and off-CPU time of measured calls
+    cpu_split_collectors: BTreeMap<CpuSplit, LatencyCollector>,
+
+    /// Queue depth sampled during the test (`--queue-depth-interval`)
+    queue_depth: Option<QueueDepthReport>,
+
+    /// Send intervals achieved by the pacer (`--send-delay`)
+    pacing: Option<PacingReport>,
+
+    /// Server-to-client phase of a pair test (`--pair`)
+    pair: Option<PairReport>,
+
+    /// Allocations of the send loop (`--no-alloc-hot-path`)
+    send_allocations: Option<u64>,
+
+    /// Latency of the first message recorded
+    first_latency: Option<Duration>,
+
+    /// Where the pinned client and server ran
+    affinity: Vec<AffinityCheck>,
+
+    /// Delivery of each producer (`--producers`)
+    producers: Option<ProducerReport>,
+
+    /// Delivery at each multicast subscriber (`-m udp-multicast`)
+    subscribers: Option<SubscriberReport>,
+
+    /// The spawned server's own report
+    server: Option<ServerReport>,
+
+    /// Uniform sample of the latencies recorded (`--latency-reservoir`)
+    reservoir: Option<ReservoirSampler>,
 }
 
 impl MetricsCollector {
@@ -657,6 +1077,23 @@ impl MetricsCollector {
             latency_collector,
             throughput_calculator: ThroughputCalculator::new(),
             percentiles,
+            error_count: 0,
+            expired_count: 0,
+            priority_collectors: BTreeMap::new(),
+            phase_collectors: BTreeMap::new(),
+            connection_collectors: BTreeMap::new(),
+            processing_collectors: BTreeMap::new(),
+            cpu_split_collectors: BTreeMap::new(),
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_latency: None,
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            reservoir: None,
         })
     }
 
@@ -685,11 +1122,179 @@ impl MetricsCollector {
         // Record latency only if both collector exists and latency is provided
         if let (Some(collector), Some(lat)) = (&mut self.latency_collector, latency) {
             collector.record(lat)?;
+            self.first_latency.get_or_insert(lat);
+            if let Some(reservoir) = &mut self.reservoir {
+                reservoir.record(lat.as_nanos() as u64);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record the latency of a message sent at `priority`
+    ///
+    /// Kept in addition to the overall latency recorded by
+    /// [`record_message`](Self::record_message), so each priority gets its
+    /// own distribution. Ignored when latency collection is disabled.
+    pub fn record_priority_latency(&mut self, priority: u8, latency: Duration) -> Result<()> {
+        let latency_type = self.latency_type();
+        Self::record_keyed(
+            &mut self.priority_collectors,
+            latency_type,
+            Priority(priority),
+            latency,
+        )
+    }
+
+    /// Record the time one round trip spent in each phase
+    ///
+    /// Ignored when latency collection is disabled, like
+    /// [`record_priority_latency`](Self::record_priority_latency).
+    pub fn record_phase_latency(&mut self, phases: [(RoundTripPhase, Duration); 3]) -> Result<()> {
+        let latency_type = self.latency_type();
+        for (phase, latency) in phases {
+            Self::record_keyed(&mut self.phase_collectors, latency_type, phase, latency)?;
         }
+        Ok(())
+    }
+
+    /// Record the latency of a request sent on a warm or cold connection
+    ///
+    /// Ignored when latency collection is disabled, like
+    /// [`record_priority_latency`](Self::record_priority_latency).
+    pub fn record_connection_latency(
+        &mut self,
+        connection: ConnectionReuse,
+        latency: Duration,
+    ) -> Result<()> {
+        let latency_type = self.latency_type();
+        Self::record_keyed(
+            &mut self.connection_collectors,
+            latency_type,
+            connection,
+            latency,
+        )
+    }
 
+    /// Record the time the client's hook spent on one message, and the
+    /// message's transport `latency` if it was measured, which makes its
+    /// end-to-end latency
+    ///
+    /// Ignored when latency collection is disabled, like
+    /// [`record_priority_latency`](Self::record_priority_latency).
+    pub fn record_processing(&mut self, work: Duration, latency: Option<Duration>) -> Result<()> {
+        let latency_type = self.latency_type();
+        let collectors = &mut self.processing_collectors;
+        Self::record_keyed(collectors, latency_type, ProcessingStage::ClientHook, work)?;
+        if let Some(latency) = latency {
+            Self::record_keyed(
+                collectors,
+                latency_type,
+                ProcessingStage::EndToEnd,
+                latency + work,
+            )?;
+        }
         Ok(())
     }
 
+    /// Split a measured call that took `wall` into the `on_cpu` time the
+    /// client thread ran and the remainder, spent off the CPU
+    ///
+    /// Ignored when latency collection is disabled, like
+    /// [`record_priority_latency`](Self::record_priority_latency).
+    pub fn record_cpu_split(&mut self, on_cpu: Duration, wall: Duration) -> Result<()> {
+        // The two clocks tick at different granularities; CPU time can
+        // exceed a short wall time by a tick
+        let on_cpu = on_cpu.min(wall);
+        let latency_type = self.latency_type();
+        let collectors = &mut self.cpu_split_collectors;
+        Self::record_keyed(collectors, latency_type, CpuSplit::OnCpu, on_cpu)?;
+        Self::record_keyed(collectors, latency_type, CpuSplit::OffCpu, wall - on_cpu)
+    }
+
+    /// Attach the queue depth sampled during the test
+    pub fn set_queue_depth(&mut self, report: Option<QueueDepthReport>) {
+        self.queue_depth = report;
+    }
+
+    /// Attach the send intervals achieved by the pacer
+    pub fn set_pacing(&mut self, report: Option<PacingReport>) {
+        self.pacing = report;
+    }
+
+    /// Attach the server-to-client phase of a pair test
+    pub fn set_pair(&mut self, report: Option<PairReport>) {
+        self.pair = report;
+    }
+
+    /// Attach the allocations counted in the send loop
+    pub fn set_send_allocations(&mut self, allocations: Option<u64>) {
+        self.send_allocations = allocations;
+    }
+
+    /// Attach where the pinned client and server ran
+    pub fn set_affinity(&mut self, checks: Vec<AffinityCheck>) {
+        self.affinity = checks;
+    }
+
+    /// Attach the per-producer delivery of a `--producers` test
+    pub fn set_producers(&mut self, report: Option<ProducerReport>) {
+        self.producers = report;
+    }
+
+    /// Attach the per-subscriber delivery of a multicast test
+    pub fn set_subscribers(&mut self, report: SubscriberReport) {
+        self.subscribers = Some(report);
+    }
+
+    /// Attach the report of the server the test ran against
+    pub fn set_server(&mut self, report: Option<ServerReport>) {
+        self.server = report;
+    }
+
+    /// Keep a uniform sample of up to `capacity` of the latencies
+    /// recorded, if `capacity` is set
+    pub fn set_reservoir(&mut self, capacity: Option<usize>) {
+        self.reservoir = capacity.map(ReservoirSampler::new);
+    }
+
+    /// Latency type being collected, if any
+    fn latency_type(&self) -> Option<LatencyType> {
+        self.latency_collector.as_ref().map(|c| c.latency_type)
+    }
+
+    /// Record `latency` in the collector for `key`, creating it on first
+    /// use. Does nothing without a `latency_type`.
+    fn record_keyed<K: Ord>(
+        collectors: &mut BTreeMap<K, LatencyCollector>,
+        latency_type: Option<LatencyType>,
+        key: K,
+        latency: Duration,
+    ) -> Result<()> {
+        let Some(latency_type) = latency_type else {
+            return Ok(());
+        };
+        let collector = match collectors.entry(key) {
+            btree_map::Entry::Occupied(entry) => entry.into_mut(),
+            btree_map::Entry::Vacant(entry) => entry.insert(LatencyCollector::new(latency_type)?),
+        };
+        collector.record(latency)
+    }
+
+    /// Metrics of each keyed collector, in key order
+    fn keyed_metrics<K: Copy>(
+        collectors: &BTreeMap<K, LatencyCollector>,
+        percentiles: &[f64],
+    ) -> Vec<LatencyBreakdown<K>> {
+        collectors
+            .iter()
+            .map(|(&key, collector)| LatencyBreakdown {
+                key,
+                latency: collector.get_metrics(percentiles),
+            })
+            .collect()
+    }
+
     /// Get current performance metrics
     ///
     /// Generates a comprehensive performance metrics structure containing
@@ -704,17 +1309,49 @@ impl MetricsCollector {
     /// latency and throughput measurements for accurate correlation.
     pub fn get_metrics(&self) -> PerformanceMetrics {
         // Generate latency metrics if latency collection is enabled
-        let latency = self
-            .latency_collector
+        let latency_reservoir = self
+            .reservoir
             .as_ref()
-            .map(|collector| collector.get_metrics(&self.percentiles));
+            .map(|sampler| sampler.reservoir().clone());
+        let latency = self.latency_collector.as_ref().map(|collector| {
+            let mut latency = collector.get_metrics(&self.percentiles);
+            if let Some(reservoir) = &latency_reservoir {
+                latency.percentile_intervals =
+                    bootstrap::percentile_intervals(&reservoir.samples_ns, &self.percentiles);
+            }
+            latency
+        });
 
         // Always generate throughput metrics
         let throughput = self.throughput_calculator.get_metrics();
+        let pair = self
+            .pair
+            .clone()
+            .map(|report| report.compared_with(latency.as_ref()));
 
         PerformanceMetrics {
             latency,
             throughput,
+            error_count: self.error_count,
+            expired_count: self.expired_count,
+            priority_latency: Self::keyed_metrics(&self.priority_collectors, &self.percentiles),
+            phase_latency: Self::keyed_metrics(&self.phase_collectors, &self.percentiles),
+            connection_latency: Self::keyed_metrics(&self.connection_collectors, &self.percentiles),
+            processing_latency: Self::keyed_metrics(&self.processing_collectors, &self.percentiles),
+            cpu_split: Self::keyed_metrics(&self.cpu_split_collectors, &self.percentiles),
+            queue_depth: self.queue_depth.clone(),
+            pacing: self.pacing.clone(),
+            pair,
+            send_allocations: self.send_allocations,
+            first_message: None,
+            first_latency_ns: self.first_latency.map(|latency| latency.as_nanos() as u64),
+            affinity: self.affinity.clone(),
+            producers: self.producers.clone(),
+            subscribers: self.subscribers.clone(),
+            server: self.server.clone(),
+            latency_reservoir,
+            goodput: None,
+            sla: Vec::new(),
             timestamp: chrono::Utc::now(),
         }
     }
@@ -727,13 +1364,23 @@ impl MetricsCollector {
     /// ## Side Effects
     ///
     /// - Clears latency histogram (if present)
-    /// - Resets throughput counters
+    /// - Resets throughput counters and the error and expired counts
     /// - Updates timing baselines to current instant
     pub fn reset(&mut self) {
         if let Some(collector) = &mut self.latency_collector {
             collector.reset();
         }
         self.throughput_calculator.reset();
+        self.error_count = 0;
+        self.expired_count = 0;
+        self.priority_collectors.clear();
+        self.phase_collectors.clear();
+        self.connection_collectors.clear();
+        self.processing_collectors.clear();
+        self.cpu_split_collectors.clear();
+        if let Some(reservoir) = &mut self.reservoir {
+            reservoir.reset();
+        }
     }
 
     /// Merge multiple worker metrics into a single aggregated result
@@ -779,22 +1426,106 @@ impl MetricsCollector {
             .filter_map(|m| m.latency.as_ref())
             .collect();
 
+        let latency_reservoir = Self::aggregate_reservoirs(&worker_metrics);
         let aggregated_latency = if !latency_metrics.is_empty() {
-            Some(Self::aggregate_latency_metrics(
-                latency_metrics,
-                percentiles,
-            )?)
+            let mut latency = Self::aggregate_latency_metrics(latency_metrics, percentiles)?;
+            if let Some(reservoir) = &latency_reservoir {
+                latency.percentile_intervals =
+                    bootstrap::percentile_intervals(&reservoir.samples_ns, percentiles);
+            }
+            Some(latency)
         } else {
             None
         };
 
+        // Aggregate each keyed latency across the workers that recorded it
+        let priority_latency = Self::aggregate_keyed(
+            worker_metrics.iter().flat_map(|m| &m.priority_latency),
+            percentiles,
+        )?;
+        let phase_latency = Self::aggregate_keyed(
+            worker_metrics.iter().flat_map(|m| &m.phase_latency),
+            percentiles,
+        )?;
+        let connection_latency = Self::aggregate_keyed(
+            worker_metrics.iter().flat_map(|m| &m.connection_latency),
+            percentiles,
+        )?;
+        let processing_latency = Self::aggregate_keyed(
+            worker_metrics.iter().flat_map(|m| &m.processing_latency),
+            percentiles,
+        )?;
+        let cpu_split = Self::aggregate_keyed(
+            worker_metrics.iter().flat_map(|m| &m.cpu_split),
+            percentiles,
+        )?;
+
         Ok(PerformanceMetrics {
             latency: aggregated_latency,
             throughput: aggregated_throughput,
+            error_count: worker_metrics.iter().map(|m| m.error_count).sum(),
+            expired_count: worker_metrics.iter().map(|m| m.expired_count).sum(),
+            priority_latency,
+            phase_latency,
+            connection_latency,
+            processing_latency,
+            cpu_split,
+            // Queue depth, pacing, affinity, producers, subscribers and the
+            // server report are only reported for single-client tests
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_message: None,
+            // Every client sends a first message; the slowest stands for
+            // the test's cold start
+            first_latency_ns: worker_metrics
+                .iter()
+                .filter_map(|m| m.first_latency_ns)
+                .max(),
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            latency_reservoir,
+            goodput: None,
+            sla: Vec::new(),
             timestamp: chrono::Utc::now(),
         })
     }
 
+    /// Merge the workers' latency reservoirs into one as large as the
+    /// largest of them
+    fn aggregate_reservoirs(worker_metrics: &[PerformanceMetrics]) -> Option<LatencyReservoir> {
+        let reservoirs: Vec<&LatencyReservoir> = worker_metrics
+            .iter()
+            .filter_map(|m| m.latency_reservoir.as_ref())
+            .collect();
+        let capacity = reservoirs.iter().map(|r| r.capacity).max()?;
+        LatencyReservoir::merge(&reservoirs, capacity)
+    }
+
+    /// Merge keyed latency distributions from several workers, one entry
+    /// per key in key order
+    fn aggregate_keyed<'a, K: Ord + Copy + 'a>(
+        entries: impl Iterator<Item = &'a LatencyBreakdown<K>>,
+        percentiles: &[f64],
+    ) -> Result<Vec<LatencyBreakdown<K>>> {
+        let mut by_key: BTreeMap<K, Vec<&LatencyMetrics>> = BTreeMap::new();
+        for entry in entries {
+            by_key.entry(entry.key).or_default().push(&entry.latency);
+        }
+        by_key
+            .into_iter()
+            .map(|(key, latencies)| {
+                Ok(LatencyBreakdown {
+                    key,
+                    latency: Self::aggregate_latency_metrics(latencies, percentiles)?,
+                })
+            })
+            .collect()
+    }
+
     /// Aggregate throughput metrics from multiple workers
     ///
     /// Combines throughput measurements from multiple workers by summing
@@ -962,6 +1693,15 @@ impl MetricsCollector {
             percentiles: percentile_values,
             total_samples,
             histogram_data: Vec::new(), // No longer used for calculations
+            buckets: merge_buckets(&latency_metrics),
+            histogram: merge_histograms(&latency_metrics)?,
+            confidence: Some(merge_confidence(
+                &latency_metrics,
+                total_samples,
+                percentiles,
+                median_ns,
+            )),
+            percentile_intervals: Vec::new(),
         })
     }
 
@@ -1006,6 +1746,8 @@ impl MetricsCollector {
         // Add throughput data by incrementing counters
         self.throughput_calculator.message_count += worker_metrics.throughput.total_messages;
         self.throughput_calculator.byte_count += worker_metrics.throughput.total_bytes;
+        self.error_count += worker_metrics.error_count;
+        self.expired_count += worker_metrics.expired_count;
 
         // For latency, we'd need access to the raw histogram data for proper aggregation
         // This is a limitation of the current design - ideally we'd aggregate at the histogram level
@@ -1014,12 +1756,253 @@ impl MetricsCollector {
     }
 }
 
+/// Sum the bucket counts of several workers' metrics.
+///
+/// Every collector uses the same power-of-two boundaries, so buckets with
+/// equal upper bounds cover the same latencies.
+fn merge_buckets(latency_metrics: &[&LatencyMetrics]) -> Vec<LatencyBucket> {
+    let mut merged = std::collections::BTreeMap::new();
+    for bucket in latency_metrics.iter().flat_map(|m| &m.buckets) {
+        *merged.entry(bucket.upper_bound_ns).or_insert(0) += bucket.count;
+    }
+    merged
+        .into_iter()
+        .map(|(upper_bound_ns, count)| LatencyBucket {
+            upper_bound_ns,
+            count,
+        })
+        .collect()
+}
+
+/// Combine the HDR histograms of several workers' metrics, if all have one.
+fn merge_histograms(latency_metrics: &[&LatencyMetrics]) -> Result<Option<Histogram<u64>>> {
+    let mut histograms = latency_metrics.iter().map(|m| m.histogram.as_ref());
+    let Some(Some(first)) = histograms.next() else {
+        return Ok(None);
+    };
+    let mut merged = first.clone();
+    for histogram in histograms {
+        let Some(histogram) = histogram else {
+            return Ok(None);
+        };
+        merged
+            .add(histogram)
+            .map_err(|e| anyhow::anyhow!("Failed to merge latency histograms: {:?}", e))?;
+    }
+    Ok(Some(merged))
+}
+
+/// Confidence of the aggregate of several workers' metrics.
+///
+/// Sample support is judged on the combined sample count. Warmup
+/// convergence is that of the worker whose leading samples drifted
+/// furthest from its median.
+fn merge_confidence(
+    latency_metrics: &[&LatencyMetrics],
+    total_samples: usize,
+    percentiles: &[f64],
+    median_ns: f64,
+) -> Confidence {
+    let mut confidence = Confidence::assess(total_samples, percentiles, median_ns, None);
+    if let Some(worst) = latency_metrics
+        .iter()
+        .filter_map(|m| m.confidence.as_ref())
+        .filter(|c| c.warmup_drift_percent.is_some())
+        .max_by(|a, b| {
+            let drift = |c: &Confidence| c.warmup_drift_percent.unwrap_or(0.0).abs();
+            drift(a).total_cmp(&drift(b))
+        })
+    {
+        confidence.warmup_converged = worst.warmup_converged;
+        confidence.warmup_drift_percent = worst.warmup_drift_percent;
+    }
+    confidence
+}
+
+/// Write a histogram in HdrHistogram's percentile distribution text format.
+///
+/// This is the `outputPercentileDistribution` layout read by the
+/// HdrHistogram plotter and jHiccup tooling: value, percentile, total
+/// count and 1/(1-percentile) per line, followed by summary lines. Values
+/// are divided by `value_scale`, e.g. 1000.0 to report nanosecond samples
+/// in microseconds.
+pub fn write_percentile_distribution<W: std::io::Write>(
+    histogram: &Histogram<u64>,
+    value_scale: f64,
+    out: &mut W,
+) -> std::io::Result<()> {
+    const TICKS_PER_HALF_DISTANCE: u32 = 5;
+
+    writeln!(
+        out,
+        "{:>12} {:>14} {:>10} {:>14}\n",
+        "Value", "Percentile", "TotalCount", "1/(1-Percentile)"
+    )?;
+
+    let mut total_count = 0;
+    for step in histogram.iter_quantiles(TICKS_PER_HALF_DISTANCE) {
+        total_count += step.count_since_last_iteration();
+        let value = step.value_iterated_to() as f64 / value_scale;
+        let quantile = step.quantile_iterated_to();
+        if quantile < 1.0 {
+            writeln!(
+                out,
+                "{:12.3} {:2.12} {:10} {:14.2}",
+                value,
+                quantile,
+                total_count,
+                1.0 / (1.0 - quantile)
+            )?;
+        } else {
+            writeln!(out, "{:12.3} {:2.12} {:10}", value, quantile, total_count)?;
+        }
+    }
+
+    // Sub-buckets per bucket cover 2 * 10^sigfig values at unit resolution
+    let sub_buckets = (2 * 10u32.pow(histogram.sigfig() as u32)).next_power_of_two();
+    writeln!(
+        out,
+        "#[Mean    = {:12.3}, StdDeviation   = {:12.3}]",
+        histogram.mean() / value_scale,
+        histogram.stdev() / value_scale
+    )?;
+    writeln!(
+        out,
+        "#[Max     = {:12.3}, Total count    = {:12}]",
+        histogram.max() as f64 / value_scale,
+        histogram.len()
+    )?;
+    writeln!(
+        out,
+        "#[Buckets = {:12}, SubBuckets     = {:12}]",
+        histogram.buckets(),
+        sub_buckets
+    )
+}
+
 /// Utility functions for metrics calculation
 ///
 /// This module provides helper functions for common metrics calculations
 /// and formatting operations. These utilities support both internal
 /// calculations and external analysis of benchmark results.
+///
+/// ## Formatting
+///
+/// Every human-readable size, rate and latency printed by the benchmark
+/// goes through the formatters here, so summaries, tables and reports
+/// agree on units. Byte units are binary (KiB, MiB, GiB) by default, or
+/// decimal SI (kB, MB, GB) with `--units si`, and `--precision` sets the
+/// decimal places of scaled values. Output never depends on the locale:
+/// the decimal separator is always `.` and no digit grouping is applied.
 pub mod utils {
+    use serde::{Deserialize, Serialize};
+    use std::sync::OnceLock;
+
+    static UNITS: OnceLock<Units> = OnceLock::new();
+
+    /// Scale used for byte sizes and rates (`--units`)
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+    #[serde(rename_all = "lowercase")]
+    pub enum ByteUnits {
+        /// Powers of 1024: KiB, MiB, GiB
+        #[default]
+        Binary,
+
+        /// Powers of 1000: kB, MB, GB
+        Si,
+    }
+
+    /// How sizes, rates and latencies are written for people
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Units {
+        /// Scale of byte sizes and rates
+        pub bytes: ByteUnits,
+
+        /// Decimal places of scaled values
+        pub precision: usize,
+    }
+
+    impl Default for Units {
+        fn default() -> Self {
+            Self {
+                bytes: ByteUnits::Binary,
+                precision: 2,
+            }
+        }
+    }
+
+    impl Units {
+        /// `bytes` scaled to the largest unit it reaches, e.g. "1.50 MiB".
+        pub fn bytes(&self, bytes: f64) -> String {
+            let (base, labels) = match self.bytes {
+                ByteUnits::Binary => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB"]),
+                ByteUnits::Si => (1000.0, ["B", "kB", "MB", "GB", "TB"]),
+            };
+            let mut value = bytes;
+            let mut unit = 0;
+            while value.abs() >= base && unit < labels.len() - 1 {
+                value /= base;
+                unit += 1;
+            }
+            format!("{:.*} {}", self.precision, value, labels[unit])
+        }
+
+        /// A rate in bytes per second, e.g. "1.50 MiB/s".
+        pub fn throughput(&self, bytes_per_second: f64) -> String {
+            format!("{}/s", self.bytes(bytes_per_second))
+        }
+
+        /// A latency in nanoseconds, e.g. "1.50μs".
+        pub fn latency(&self, latency_ns: u64) -> String {
+            if latency_ns < 1_000 {
+                format!("{}ns", latency_ns)
+            } else if latency_ns < 1_000_000 {
+                format!("{:.*}μs", self.precision, latency_ns as f64 / 1_000.0)
+            } else if latency_ns < 1_000_000_000 {
+                format!("{:.*}ms", self.precision, latency_ns as f64 / 1_000_000.0)
+            } else {
+                format!(
+                    "{:.*}s",
+                    self.precision,
+                    latency_ns as f64 / 1_000_000_000.0
+                )
+            }
+        }
+
+        /// Label of a whole-megabyte rate column in this scale.
+        pub fn megabytes_per_second_label(&self) -> &'static str {
+            match self.bytes {
+                ByteUnits::Binary => "MiB/s",
+                ByteUnits::Si => "MB/s",
+            }
+        }
+
+        /// `bytes_per_second` in the unit of
+        /// [`megabytes_per_second_label`](Self::megabytes_per_second_label).
+        pub fn megabytes_per_second(&self, bytes_per_second: f64) -> f64 {
+            match self.bytes {
+                ByteUnits::Binary => bytes_per_second / (1024.0 * 1024.0),
+                ByteUnits::Si => bytes_per_second / 1_000_000.0,
+            }
+        }
+    }
+
+    /// Use `units` for the rest of this process; later calls are ignored.
+    pub fn set_units(units: Units) {
+        let _ = UNITS.set(units);
+    }
+
+    /// The units set for this process, or the defaults.
+    pub fn units() -> Units {
+        UNITS.get().copied().unwrap_or_default()
+    }
+
+    /// Format a byte count for human-readable output, in the units set for
+    /// this process.
+    pub fn format_bytes(bytes: f64) -> String {
+        units().bytes(bytes)
+    }
 
     /// Calculate percentiles from raw latency data
     ///
@@ -1055,24 +2038,16 @@ pub mod utils {
     ///
     /// ## Precision
     ///
-    /// Uses 2 decimal places for scaled units to provide meaningful
-    /// precision without overwhelming detail.
+    /// Uses the `--precision` decimal places (2 by default) for scaled
+    /// units to provide meaningful precision without overwhelming detail.
     pub fn format_latency(latency_ns: u64) -> String {
-        if latency_ns < 1_000 {
-            format!("{}ns", latency_ns)
-        } else if latency_ns < 1_000_000 {
-            format!("{:.2}μs", latency_ns as f64 / 1_000.0)
-        } else if latency_ns < 1_000_000_000 {
-            format!("{:.2}ms", latency_ns as f64 / 1_000_000.0)
-        } else {
-            format!("{:.2}s", latency_ns as f64 / 1_000_000_000.0)
-        }
+        units().latency(latency_ns)
     }
 
     /// Format throughput value for human-readable output
     ///
     /// Converts bytes-per-second throughput values to human-readable
-    /// strings with appropriate units (B/s, KB/s, MB/s, GB/s).
+    /// strings with appropriate units (B/s, KiB/s, MiB/s, GiB/s).
     ///
     /// ## Parameters
     /// - `bytes_per_second`: Throughput in bytes per second
@@ -1082,28 +2057,25 @@ pub mod utils {
     ///
     /// ## Unit Scaling
     ///
-    /// Uses binary (1024-based) scaling for consistency with system
-    /// memory and storage conventions:
+    /// Uses binary (1024-based) scaling by default, for consistency with
+    /// system memory and storage conventions, or decimal SI scaling with
+    /// `--units si`:
     /// - Bytes: < 1024 B/s
-    /// - Kilobytes: 1024 B/s to 1,048,576 B/s
-    /// - Megabytes: 1,048,576 B/s to 1,073,741,824 B/s
-    /// - Gigabytes: ≥ 1,073,741,824 B/s
+    /// - Kibibytes: 1024 B/s to 1,048,576 B/s
+    /// - Mebibytes: 1,048,576 B/s to 1,073,741,824 B/s
+    /// - Gibibytes: ≥ 1,073,741,824 B/s
     pub fn format_throughput(bytes_per_second: f64) -> String {
-        if bytes_per_second < 1024.0 {
-            format!("{:.2} B/s", bytes_per_second)
-        } else if bytes_per_second < 1024.0 * 1024.0 {
-            format!("{:.2} KB/s", bytes_per_second / 1024.0)
-        } else if bytes_per_second < 1024.0 * 1024.0 * 1024.0 {
-            format!("{:.2} MB/s", bytes_per_second / (1024.0 * 1024.0))
-        } else {
-            format!("{:.2} GB/s", bytes_per_second / (1024.0 * 1024.0 * 1024.0))
-        }
+        units().throughput(bytes_per_second)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{utils, LatencyCollector, LatencyType, ThroughputCalculator};
+    use super::{
+        merge_buckets, utils, write_percentile_distribution, ConnectionReuse, CpuSplit,
+        LatencyBreakdown, LatencyCollector, LatencyType, MetricsCollector, Priority,
+        ProcessingStage, RoundTripPhase, ThroughputCalculator,
+    };
     use std::time::Duration;
 
     /// Test latency collector basic functionality
@@ -1122,6 +2094,56 @@ mod tests {
         assert!(metrics.mean_ns > 0.0);
     }
 
+    /// Buckets cover every sample and merge across workers by boundary
+    #[test]
+    fn test_latency_buckets() {
+        let mut collector = LatencyCollector::new(LatencyType::OneWay).unwrap();
+        for ns in [900, 1000, 1100, 5000] {
+            collector.record(Duration::from_nanos(ns)).unwrap();
+        }
+        let metrics = collector.get_metrics(&[50.0]);
+        let buckets = &metrics.buckets;
+        assert_eq!(buckets.iter().map(|b| b.count).sum::<u64>(), 4);
+        assert!(buckets.first().unwrap().count > 0);
+        assert!(buckets.last().unwrap().upper_bound_ns >= 5000);
+        assert!(buckets
+            .windows(2)
+            .all(|w| w[0].upper_bound_ns < w[1].upper_bound_ns));
+
+        let merged = merge_buckets(&[&metrics, &metrics]);
+        assert_eq!(merged.len(), buckets.len());
+        assert_eq!(merged.iter().map(|b| b.count).sum::<u64>(), 8);
+    }
+
+    /// The exported distribution follows HdrHistogram's text layout
+    #[test]
+    fn test_write_percentile_distribution() {
+        let mut collector = LatencyCollector::new(LatencyType::RoundTrip).unwrap();
+        for us in 1..=100 {
+            collector.record(Duration::from_micros(us)).unwrap();
+        }
+        let histogram = collector.get_metrics(&[50.0]).histogram.unwrap();
+
+        let mut out = Vec::new();
+        write_percentile_distribution(&histogram, 1000.0, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["Value", "Percentile", "TotalCount", "1/(1-Percentile)"]
+        );
+        assert!(lines[1].is_empty());
+        let first: Vec<&str> = lines[2].split_whitespace().collect();
+        assert_eq!(first, ["1.000", "0.000000000000", "1", "1.00"]);
+
+        let last: Vec<&str> = lines[lines.len() - 4].split_whitespace().collect();
+        assert_eq!(last[1..], ["1.000000000000", "100"]);
+        assert!(lines[lines.len() - 3].starts_with("#[Mean    ="));
+        assert!(lines[lines.len() - 2].ends_with("Total count    =          100]"));
+        assert!(lines[lines.len() - 1].ends_with("SubBuckets     =         2048]"));
+    }
+
     /// Test throughput calculator functionality
     #[test]
     fn test_throughput_calculator() {
@@ -1138,6 +2160,183 @@ mod tests {
         assert!(metrics.bytes_per_second >= 0.0);
     }
 
+    /// Test that error and deadline-miss counts survive aggregation and reset
+    #[test]
+    fn test_error_count_aggregates() {
+        let mut worker = MetricsCollector::new(Some(LatencyType::RoundTrip), vec![50.0]).unwrap();
+        worker
+            .record_message(64, Some(Duration::from_micros(5)))
+            .unwrap();
+        worker.error_count += 2;
+        worker.expired_count += 1;
+        let first = worker.get_metrics();
+        assert_eq!(first.error_count, 2);
+        assert_eq!(first.expired_count, 1);
+
+        let total = MetricsCollector::aggregate_worker_metrics(vec![first.clone(), first], &[50.0])
+            .unwrap();
+        assert_eq!(total.error_count, 4);
+        assert_eq!(total.expired_count, 2);
+
+        worker.reset();
+        assert_eq!(worker.get_metrics().error_count, 0);
+        assert_eq!(worker.get_metrics().expired_count, 0);
+    }
+
+    #[test]
+    fn test_priority_latency() {
+        let mut worker = MetricsCollector::new(Some(LatencyType::OneWay), vec![50.0]).unwrap();
+        for (priority, micros) in [(6, 2), (0, 9), (6, 4)] {
+            let latency = Duration::from_micros(micros);
+            worker.record_message(64, Some(latency)).unwrap();
+            worker.record_priority_latency(priority, latency).unwrap();
+        }
+        let metrics = worker.get_metrics();
+        let priorities: Vec<(u8, usize)> = metrics
+            .priority_latency
+            .iter()
+            .map(|p| (p.key.0, p.latency.total_samples))
+            .collect();
+        assert_eq!(priorities, [(0, 1), (6, 2)]);
+
+        let total =
+            MetricsCollector::aggregate_worker_metrics(vec![metrics.clone(), metrics], &[50.0])
+                .unwrap();
+        assert_eq!(total.priority_latency[1].latency.total_samples, 4);
+
+        // Results written before the key was generic name it by its kind
+        let mut json = serde_json::to_value(&total.priority_latency[1]).unwrap();
+        let key = json.as_object_mut().unwrap().remove("key").unwrap();
+        assert_eq!(key, 6);
+        json["priority"] = key;
+        let entry: LatencyBreakdown<Priority> = serde_json::from_value(json).unwrap();
+        assert_eq!(entry.key, Priority(6));
+        assert_eq!(entry.key.to_string(), "priority 6");
+
+        // Throughput-only collectors keep no per-priority latency
+        let mut throughput_only = MetricsCollector::new(None, vec![50.0]).unwrap();
+        throughput_only
+            .record_priority_latency(1, Duration::from_micros(1))
+            .unwrap();
+        assert!(throughput_only.get_metrics().priority_latency.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_phases() {
+        let phases = RoundTripPhase::split(1_000, 4_000, 4_500, 6_000).unwrap();
+        assert_eq!(
+            phases,
+            [
+                (RoundTripPhase::RequestTransit, Duration::from_nanos(3_000)),
+                (RoundTripPhase::ServerProcessing, Duration::from_nanos(500)),
+                (RoundTripPhase::ReplyTransit, Duration::from_nanos(1_500)),
+            ]
+        );
+        // No server stamp, or clocks that disagree, give no breakdown
+        assert!(RoundTripPhase::split(1_000, 0, 4_500, 6_000).is_none());
+        assert!(RoundTripPhase::split(5_000, 4_000, 4_500, 6_000).is_none());
+
+        let mut collector =
+            MetricsCollector::new(Some(LatencyType::RoundTrip), vec![50.0]).unwrap();
+        collector.record_phase_latency(phases).unwrap();
+        collector.record_phase_latency(phases).unwrap();
+        let metrics = collector.get_metrics();
+        assert_eq!(metrics.phase_latency.len(), 3);
+        assert_eq!(metrics.phase_latency[0].key, RoundTripPhase::RequestTransit);
+        assert_eq!(metrics.phase_latency[0].latency.total_samples, 2);
+        assert_eq!(
+            metrics.phase_latency[1].key.to_string(),
+            "server processing"
+        );
+
+        let total =
+            MetricsCollector::aggregate_worker_metrics(vec![metrics.clone(), metrics], &[50.0])
+                .unwrap();
+        assert_eq!(total.phase_latency[2].latency.total_samples, 4);
+    }
+
+    #[test]
+    fn test_connection_latency() {
+        let mut collector =
+            MetricsCollector::new(Some(LatencyType::RoundTrip), vec![50.0]).unwrap();
+        collector
+            .record_connection_latency(ConnectionReuse::Cold, Duration::from_micros(40))
+            .unwrap();
+        collector
+            .record_connection_latency(ConnectionReuse::Warm, Duration::from_micros(10))
+            .unwrap();
+        collector
+            .record_connection_latency(ConnectionReuse::Warm, Duration::from_micros(12))
+            .unwrap();
+        let metrics = collector.get_metrics();
+        let counts: Vec<(ConnectionReuse, usize)> = metrics
+            .connection_latency
+            .iter()
+            .map(|c| (c.key, c.latency.total_samples))
+            .collect();
+        assert_eq!(
+            counts,
+            [(ConnectionReuse::Warm, 2), (ConnectionReuse::Cold, 1)]
+        );
+        assert_eq!(metrics.connection_latency[1].key.to_string(), "cold");
+
+        let total =
+            MetricsCollector::aggregate_worker_metrics(vec![metrics.clone(), metrics], &[50.0])
+                .unwrap();
+        assert_eq!(total.connection_latency[0].latency.total_samples, 4);
+
+        collector.reset();
+        assert!(collector.get_metrics().connection_latency.is_empty());
+    }
+
+    #[test]
+    fn test_processing_latency() {
+        let mut collector = MetricsCollector::new(Some(LatencyType::OneWay), vec![50.0]).unwrap();
+        collector
+            .record_processing(Duration::from_micros(5), Some(Duration::from_micros(10)))
+            .unwrap();
+        collector
+            .record_processing(Duration::from_micros(5), None)
+            .unwrap();
+        let metrics = collector.get_metrics();
+        let hook = &metrics.processing_latency[0];
+        assert_eq!(hook.key, ProcessingStage::ClientHook);
+        assert_eq!(hook.latency.total_samples, 2);
+        let end_to_end = &metrics.processing_latency[1];
+        assert_eq!(end_to_end.key.to_string(), "end-to-end");
+        assert_eq!(end_to_end.latency.total_samples, 1);
+        assert_eq!(end_to_end.latency.max_ns, 15_000);
+
+        // Without latency collection there is nothing to add the work to
+        let mut throughput_only = MetricsCollector::new(None, vec![]).unwrap();
+        throughput_only
+            .record_processing(Duration::from_micros(5), None)
+            .unwrap();
+        assert!(throughput_only.get_metrics().processing_latency.is_empty());
+    }
+
+    #[test]
+    fn test_cpu_split() {
+        let mut collector =
+            MetricsCollector::new(Some(LatencyType::RoundTrip), vec![50.0]).unwrap();
+        collector
+            .record_cpu_split(Duration::from_micros(3), Duration::from_micros(10))
+            .unwrap();
+        // CPU time past the wall time is clamped to it
+        collector
+            .record_cpu_split(Duration::from_micros(5), Duration::from_micros(4))
+            .unwrap();
+        let metrics = collector.get_metrics();
+        let on_cpu = &metrics.cpu_split[0];
+        assert_eq!(on_cpu.key, CpuSplit::OnCpu);
+        assert_eq!(on_cpu.latency.total_samples, 2);
+        assert_eq!(on_cpu.latency.max_ns, 4_000);
+        let off_cpu = &metrics.cpu_split[1];
+        assert_eq!(off_cpu.key.to_string(), "off-CPU");
+        assert_eq!(off_cpu.latency.min_ns, 0);
+        assert_eq!(off_cpu.latency.max_ns, 7_000);
+    }
+
     /// Test latency formatting utility
     #[test]
     fn test_format_latency() {
@@ -1151,8 +2350,31 @@ mod tests {
     #[test]
     fn test_format_throughput() {
         assert_eq!(utils::format_throughput(500.0), "500.00 B/s");
-        assert_eq!(utils::format_throughput(1536.0), "1.50 KB/s");
-        assert_eq!(utils::format_throughput(1572864.0), "1.50 MB/s");
-        assert_eq!(utils::format_throughput(1610612736.0), "1.50 GB/s");
+        assert_eq!(utils::format_throughput(1536.0), "1.50 KiB/s");
+        assert_eq!(utils::format_throughput(1572864.0), "1.50 MiB/s");
+        assert_eq!(utils::format_throughput(1610612736.0), "1.50 GiB/s");
+    }
+
+    /// Test SI units and precision of the shared formatters
+    #[test]
+    fn test_units_scale_and_precision() {
+        let si = utils::Units {
+            bytes: utils::ByteUnits::Si,
+            precision: 1,
+        };
+        assert_eq!(si.bytes(999.0), "999.0 B");
+        assert_eq!(si.bytes(1_500_000.0), "1.5 MB");
+        assert_eq!(si.throughput(2_000.0), "2.0 kB/s");
+        assert_eq!(si.latency(1_234_567), "1.2ms");
+        assert_eq!(si.latency(42), "42ns");
+        assert_eq!(si.megabytes_per_second_label(), "MB/s");
+        assert_eq!(si.megabytes_per_second(3_000_000.0), 3.0);
+
+        let binary = utils::Units {
+            precision: 0,
+            ..utils::Units::default()
+        };
+        assert_eq!(binary.bytes(3.0 * 1024.0 * 1024.0), "3 MiB");
+        assert_eq!(binary.megabytes_per_second(1024.0 * 1024.0), 1.0);
     }
 }
@@ -0,0 +1,153 @@
+//! Several subscribers receiving one UDP multicast stream.
+//!
+//! With `-m udp-multicast --subscribers N`, the benchmark's client publishes
+//! each message once to a multicast group, and N server processes that
+//! joined the group record the latency of every datagram they receive. The
+//! first server is the usual one, as subscriber 0; the runner starts the
+//! other N-1 on the same group port once the first has reported it.
+//!
+//! Multicast delivery is not reliable, so besides latency each subscriber
+//! is scored on what it missed: the runner knows how many messages it
+//! published, and a [`SubscriberReport`] gives every subscriber's received
+//! count, loss and latency distribution.
+
+use crate::metrics::{LatencyCollector, LatencyMetrics, LatencyType};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Delivery of the published messages to one subscriber
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriberMetrics {
+    /// Subscriber number; 0 is the benchmark's own server
+    pub subscriber: u16,
+
+    /// Messages this subscriber received
+    pub received: usize,
+
+    /// Published messages this subscriber never received
+    pub lost: usize,
+
+    /// `lost` as a percentage of the messages published
+    pub loss_percent: f64,
+
+    /// Latency of the messages this subscriber received
+    pub latency: LatencyMetrics,
+}
+
+/// Per-subscriber delivery of a `--subscribers` test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriberReport {
+    /// Messages the publisher sent to the group
+    pub sent: usize,
+
+    /// Each subscriber, in subscriber order
+    pub subscribers: Vec<SubscriberMetrics>,
+}
+
+impl SubscriberReport {
+    /// Largest loss of any subscriber, as a percentage of the messages sent.
+    pub fn worst_loss_percent(&self) -> f64 {
+        self.subscribers
+            .iter()
+            .map(|s| s.loss_percent)
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Accumulates the latencies each subscriber measured.
+pub struct SubscriberTally {
+    percentiles: Vec<f64>,
+    subscribers: Vec<Tally>,
+}
+
+struct Tally {
+    received: usize,
+    latency: LatencyCollector,
+}
+
+impl SubscriberTally {
+    /// Tally of `subscribers` subscribers, reporting `percentiles`.
+    pub fn new(subscribers: u16, percentiles: Vec<f64>) -> Result<Self> {
+        let subscribers = (0..subscribers)
+            .map(|_| {
+                Ok(Tally {
+                    received: 0,
+                    latency: LatencyCollector::new(LatencyType::OneWay)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            percentiles,
+            subscribers,
+        })
+    }
+
+    /// Record a message `subscriber` received after `latency`.
+    pub fn record(&mut self, subscriber: u16, latency: Duration) -> Result<()> {
+        let tally = self
+            .subscribers
+            .get_mut(usize::from(subscriber))
+            .ok_or_else(|| anyhow::anyhow!("No subscriber {}", subscriber))?;
+        tally.received += 1;
+        tally.latency.record(latency)
+    }
+
+    /// Report of every subscriber's delivery of the `sent` messages.
+    pub fn finish(self, sent: usize) -> SubscriberReport {
+        let subscribers = (0..)
+            .zip(self.subscribers)
+            .map(|(subscriber, tally)| {
+                let lost = sent.saturating_sub(tally.received);
+                SubscriberMetrics {
+                    subscriber,
+                    received: tally.received,
+                    lost,
+                    loss_percent: if sent == 0 {
+                        0.0
+                    } else {
+                        lost as f64 * 100.0 / sent as f64
+                    },
+                    latency: tally.latency.get_metrics(&self.percentiles),
+                }
+            })
+            .collect();
+        SubscriberReport { sent, subscribers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally_reports_loss_per_subscriber() {
+        let mut tally = SubscriberTally::new(3, vec![50.0]).unwrap();
+        for _ in 0..10 {
+            tally.record(0, Duration::from_micros(10)).unwrap();
+        }
+        for _ in 0..8 {
+            tally.record(1, Duration::from_micros(20)).unwrap();
+        }
+        assert!(tally.record(3, Duration::from_micros(1)).is_err());
+
+        let report = tally.finish(10);
+        assert_eq!(report.sent, 10);
+        assert_eq!(report.subscribers.len(), 3);
+        assert_eq!(report.subscribers[0].lost, 0);
+        assert_eq!(report.subscribers[1].received, 8);
+        assert_eq!(report.subscribers[1].lost, 2);
+        assert_eq!(report.subscribers[1].loss_percent, 20.0);
+        assert!((report.subscribers[1].latency.mean_ns - 20_000.0).abs() < 100.0);
+        // Subscriber 2 received nothing at all
+        assert_eq!(report.subscribers[2].lost, 10);
+        assert_eq!(report.worst_loss_percent(), 100.0);
+    }
+
+    #[test]
+    fn test_nothing_sent_is_no_loss() {
+        let report = SubscriberTally::new(1, Vec::new()).unwrap().finish(0);
+        assert_eq!(report.subscribers[0].loss_percent, 0.0);
+        assert_eq!(report.worst_loss_percent(), 0.0);
+    }
+}
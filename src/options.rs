@@ -0,0 +1,199 @@
+//! Transport and output options.
+//!
+//! The enums behind `--uds-credentials`, `--spin-wait`, `--shm-wait`,
+//! `--http-version`, `--response-mode`, `--machine-output`, `--profile` and
+//! `--runtime`. They live apart from the command line so
+//! [`crate::ipc::TransportConfig`] and the results can hold them without the
+//! clap-derived argument types; `cli` parses them and re-exports them.
+
+use serde::{Deserialize, Serialize};
+
+/// Peer credential checking for Unix Domain Sockets
+///
+/// Controls how much identity checking the UDS transport performs, so the
+/// cost of credential-checked IPC can be measured against plain UDS.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UdsCredentialMode {
+    /// No credential checks
+    #[default]
+    Off,
+
+    /// Fetch and validate the peer's credentials once per connection
+    ///
+    /// Uses SO_PEERCRED on Linux and getpeereid elsewhere. The peer must run
+    /// as the same user as this process, or as root.
+    Connection,
+
+    /// Validate per connection and attach SCM_CREDENTIALS to every message
+    ///
+    /// Each message is sent with sendmsg carrying the sender's credentials,
+    /// and the receiver checks them against the connected peer. Linux only.
+    Message,
+}
+
+/// Format of the results printed to stdout by `--machine-output`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MachineOutput {
+    /// One JSON document with all results, printed when the run finishes
+    Json,
+
+    /// One JSON line per mechanism, printed as each mechanism completes
+    Jsonl,
+}
+
+/// How a spinning receive waits between polls (`--spin-wait`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpinWait {
+    /// Poll again immediately
+    Busy,
+
+    /// Execute a spin-loop hint (x86 `pause`, ARM `yield`) between polls,
+    /// which saves power and yields pipeline resources to an SMT sibling
+    Pause,
+}
+
+impl std::fmt::Display for SpinWait {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpinWait::Busy => write!(f, "busy"),
+            SpinWait::Pause => write!(f, "pause"),
+        }
+    }
+}
+
+/// How the shared memory ring signals waiting peers (`--shm-wait`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShmWait {
+    /// Process-shared pthread mutex and condition variables
+    #[default]
+    Condvar,
+
+    /// POSIX named semaphores posted per record
+    Semaphore,
+}
+
+impl std::fmt::Display for ShmWait {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShmWait::Condvar => write!(f, "condvar"),
+            ShmWait::Semaphore => write!(f, "semaphore"),
+        }
+    }
+}
+
+/// HTTP version spoken by the HTTP transport (`--http-version`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpVersion {
+    /// HTTP/1.1 with keep-alive
+    #[default]
+    H1,
+
+    /// HTTP/2 over cleartext (h2c) with prior knowledge
+    H2,
+}
+
+impl std::fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpVersion::H1 => write!(f, "h1"),
+            HttpVersion::H2 => write!(f, "h2"),
+        }
+    }
+}
+
+/// What the server sends back for each request (`--response-mode`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseMode {
+    /// The request's payload
+    Echo,
+
+    /// An empty message carrying the request's ID
+    #[default]
+    Ack,
+
+    /// No reply; one-way tests only
+    None,
+}
+
+impl ResponseMode {
+    /// Payload of the reply to a request carrying `payload`, or `None` when
+    /// no reply is sent.
+    pub fn reply_payload(self, payload: Vec<u8>) -> Option<Vec<u8>> {
+        match self {
+            ResponseMode::Echo => Some(payload),
+            ResponseMode::Ack => Some(Vec::new()),
+            ResponseMode::None => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ResponseMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponseMode::Echo => write!(f, "echo"),
+            ResponseMode::Ack => write!(f, "ack"),
+            ResponseMode::None => write!(f, "none"),
+        }
+    }
+}
+
+impl std::fmt::Display for UdsCredentialMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UdsCredentialMode::Off => write!(f, "off"),
+            UdsCredentialMode::Connection => write!(f, "connection"),
+            UdsCredentialMode::Message => write!(f, "message"),
+        }
+    }
+}
+
+/// Goal a run is tuned for (`--profile`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Lowest per-message latency: long warmup, no batching, busy-polled
+    /// replies
+    Latency,
+
+    /// Most messages per second: large buffers and deep receiver batches
+    Throughput,
+
+    /// Moderate batching and warmup, for runs that report both
+    Balanced,
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Profile::Latency => write!(f, "latency"),
+            Profile::Throughput => write!(f, "throughput"),
+            Profile::Balanced => write!(f, "balanced"),
+        }
+    }
+}
+
+/// Tokio scheduler used in async mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeFlavor {
+    /// Everything runs on the thread that drives the benchmark
+    CurrentThread,
+
+    /// Work-stealing scheduler with a pool of worker threads
+    #[default]
+    MultiThread,
+}
+
+impl std::fmt::Display for RuntimeFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeFlavor::CurrentThread => write!(f, "current-thread"),
+            RuntimeFlavor::MultiThread => write!(f, "multi-thread"),
+        }
+    }
+}
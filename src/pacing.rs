@@ -0,0 +1,396 @@
+//! Send pacing for `--send-delay`.
+//!
+//! A timer sleep overshoots by the kernel's timer slack and, under tokio, by
+//! up to a millisecond of timer granularity, so sub-millisecond delays are
+//! paced slower and less evenly than requested, which distorts open-loop
+//! results. `--pacing` selects how the client waits between messages:
+//!
+//! - `sleep` (default): sleep for the delay after each send, as the
+//!   runtime's timer allows.
+//! - `spin-sleep`: sleep until [`SPIN_WINDOW`] before the next deadline,
+//!   then spin until it. Accurate to about a microsecond, at the cost of a
+//!   busy core during the spin.
+//! - `timerfd` (Linux): wait on a `timerfd` armed with the absolute
+//!   `CLOCK_MONOTONIC` deadline. Elsewhere it falls back to `spin-sleep`.
+//!
+//! The deadline engines release one message per interval from the first,
+//! so the time spent sending is part of the interval instead of added to
+//! it. A client that falls more than an interval behind restarts the
+//! schedule rather than sending a burst to catch up. They wait on the
+//! client's thread even in the async runner, whose client has a thread of
+//! its own.
+//!
+//! Whatever the engine, the interval achieved between successive releases
+//! is recorded and reported against the requested one under `pacing`.
+
+use crate::metrics::utils::format_latency;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// How long before a deadline `spin-sleep` stops sleeping and spins
+pub const SPIN_WINDOW: Duration = Duration::from_micros(100);
+
+/// How the client waits between messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum PacingMode {
+    /// Sleep for the delay after each send
+    #[default]
+    Sleep,
+
+    /// Sleep until shortly before an absolute deadline, then spin
+    SpinSleep,
+
+    /// Wait on a timerfd armed with absolute deadlines (Linux)
+    Timerfd,
+}
+
+impl fmt::Display for PacingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacingMode::Sleep => write!(f, "sleep"),
+            PacingMode::SpinSleep => write!(f, "spin-sleep"),
+            PacingMode::Timerfd => write!(f, "timerfd"),
+        }
+    }
+}
+
+/// Requested and achieved send intervals of one test
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PacingReport {
+    /// Engine that paced the sends
+    pub mode: PacingMode,
+
+    /// Interval asked for with `--send-delay`, in nanoseconds
+    pub requested_ns: u64,
+
+    /// Intervals measured
+    pub intervals: u64,
+
+    /// Mean achieved interval, in nanoseconds
+    pub mean_ns: f64,
+
+    /// Standard deviation of the achieved intervals, in nanoseconds
+    pub std_dev_ns: f64,
+
+    /// Shortest achieved interval, in nanoseconds
+    pub min_ns: u64,
+
+    /// Median achieved interval, in nanoseconds
+    pub p50_ns: u64,
+
+    /// P99 achieved interval, in nanoseconds
+    pub p99_ns: u64,
+
+    /// Longest achieved interval, in nanoseconds
+    pub max_ns: u64,
+}
+
+impl fmt::Display for PacingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, requested {}, achieved mean {} (std dev {}), P50 {}, P99 {}, max {} over {} intervals",
+            self.mode,
+            format_latency(self.requested_ns),
+            format_latency(self.mean_ns as u64),
+            format_latency(self.std_dev_ns as u64),
+            format_latency(self.p50_ns),
+            format_latency(self.p99_ns),
+            format_latency(self.max_ns),
+            self.intervals
+        )
+    }
+}
+
+/// Paces a client's sends and measures the intervals it achieves
+pub struct Pacer {
+    mode: PacingMode,
+    interval: Duration,
+    deadline: Option<Instant>,
+    last_release: Option<Instant>,
+    intervals: Histogram<u64>,
+    #[cfg(target_os = "linux")]
+    timer: Option<linux::TimerFd>,
+}
+
+impl Pacer {
+    /// A pacer releasing a message every `interval` with `mode`.
+    ///
+    /// Falls back to `spin-sleep` if `timerfd` is unavailable.
+    pub fn new(mode: PacingMode, interval: Duration) -> Self {
+        let mut pacer = Self {
+            mode,
+            interval,
+            deadline: None,
+            last_release: None,
+            intervals: Histogram::new(3).expect("3 significant figures is a valid precision"),
+            #[cfg(target_os = "linux")]
+            timer: None,
+        };
+        if mode == PacingMode::Timerfd {
+            #[cfg(target_os = "linux")]
+            match linux::TimerFd::new() {
+                Ok(timer) => pacer.timer = Some(timer),
+                Err(e) => {
+                    warn!("Failed to create a timerfd ({}); pacing with spin-sleep", e);
+                    pacer.mode = PacingMode::SpinSleep;
+                }
+            }
+            #[cfg(not(target_os = "linux"))]
+            {
+                warn!("--pacing timerfd is only available on Linux; pacing with spin-sleep");
+                pacer.mode = PacingMode::SpinSleep;
+            }
+        }
+        pacer
+    }
+
+    /// The pacer for `--send-delay`, if one was given.
+    pub fn for_delay(mode: PacingMode, send_delay: Option<Duration>) -> Option<Self> {
+        send_delay.map(|delay| Self::new(mode, delay))
+    }
+
+    /// Wait until the next message may be sent.
+    pub fn pace_blocking(&mut self) {
+        match self.mode {
+            PacingMode::Sleep => std::thread::sleep(self.interval),
+            PacingMode::SpinSleep => spin_sleep_until(self.next_deadline()),
+            PacingMode::Timerfd => {
+                let deadline = self.next_deadline();
+                #[cfg(target_os = "linux")]
+                if let Some(timer) = &self.timer {
+                    if let Err(e) = timer.wait_until(deadline) {
+                        warn!("timerfd wait failed ({}); pacing with spin-sleep", e);
+                        self.timer = None;
+                        self.mode = PacingMode::SpinSleep;
+                    }
+                }
+                spin_sleep_until(deadline);
+            }
+        }
+        self.release();
+    }
+
+    /// Wait until the next message may be sent, yielding to the runtime
+    /// when sleeping.
+    pub async fn pace(&mut self) {
+        if self.mode == PacingMode::Sleep {
+            tokio::time::sleep(self.interval).await;
+            self.release();
+        } else {
+            self.pace_blocking();
+        }
+    }
+
+    /// The requested and achieved intervals, or `None` before two releases.
+    pub fn finish(self) -> Option<PacingReport> {
+        let intervals = &self.intervals;
+        if intervals.is_empty() {
+            return None;
+        }
+        Some(PacingReport {
+            mode: self.mode,
+            requested_ns: self.interval.as_nanos() as u64,
+            intervals: intervals.len(),
+            mean_ns: intervals.mean(),
+            std_dev_ns: intervals.stdev(),
+            min_ns: intervals.min(),
+            p50_ns: intervals.value_at_quantile(0.5),
+            p99_ns: intervals.value_at_quantile(0.99),
+            max_ns: intervals.max(),
+        })
+    }
+
+    /// The next absolute deadline, restarting the schedule from now when
+    /// more than an interval behind it.
+    fn next_deadline(&mut self) -> Instant {
+        let now = Instant::now();
+        let deadline = match self.deadline {
+            Some(previous) if now <= previous + self.interval * 2 => previous + self.interval,
+            Some(_) => now,
+            None => now + self.interval,
+        };
+        self.deadline = Some(deadline);
+        deadline
+    }
+
+    fn release(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_release {
+            let _ = self.intervals.record((now - last).as_nanos() as u64);
+        }
+        self.last_release = Some(now);
+    }
+}
+
+/// Sleep until [`SPIN_WINDOW`] before `deadline`, then spin until it.
+fn spin_sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now + SPIN_WINDOW {
+        std::thread::sleep(deadline - now - SPIN_WINDOW);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use crate::ipc::get_monotonic_time_ns;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::time::Instant;
+
+    /// A `CLOCK_MONOTONIC` timerfd armed with absolute deadlines
+    pub struct TimerFd {
+        fd: OwnedFd,
+        // `Instant` reads CLOCK_MONOTONIC on Linux; this pair converts one
+        // to the other
+        base: Instant,
+        base_ns: u64,
+    }
+
+    impl TimerFd {
+        pub fn new() -> io::Result<Self> {
+            let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self {
+                fd: unsafe { OwnedFd::from_raw_fd(fd) },
+                base: Instant::now(),
+                base_ns: get_monotonic_time_ns(),
+            })
+        }
+
+        /// Block until `deadline`; returns at once if it has passed.
+        pub fn wait_until(&self, deadline: Instant) -> io::Result<()> {
+            if deadline <= Instant::now() {
+                return Ok(());
+            }
+            let at_ns = self.base_ns + deadline.duration_since(self.base).as_nanos() as u64;
+            let spec = libc::itimerspec {
+                it_interval: libc::timespec {
+                    tv_sec: 0,
+                    tv_nsec: 0,
+                },
+                it_value: libc::timespec {
+                    tv_sec: (at_ns / 1_000_000_000) as libc::time_t,
+                    tv_nsec: (at_ns % 1_000_000_000) as libc::c_long,
+                },
+            };
+            let fd = self.fd.as_raw_fd();
+            let ret = unsafe {
+                libc::timerfd_settime(fd, libc::TFD_TIMER_ABSTIME, &spec, std::ptr::null_mut())
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut expirations = 0u64;
+            loop {
+                let read = unsafe {
+                    libc::read(
+                        fd,
+                        &mut expirations as *mut u64 as *mut libc::c_void,
+                        std::mem::size_of::<u64>(),
+                    )
+                };
+                if read >= 0 {
+                    return Ok(());
+                }
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paced(mode: PacingMode, interval: Duration, releases: usize) -> PacingReport {
+        let mut pacer = Pacer::new(mode, interval);
+        for _ in 0..releases {
+            pacer.pace_blocking();
+        }
+        pacer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_no_report_before_two_releases() {
+        let mut pacer = Pacer::new(PacingMode::SpinSleep, Duration::from_micros(10));
+        assert!(Pacer::for_delay(PacingMode::Sleep, None).is_none());
+        pacer.pace_blocking();
+        assert!(pacer.finish().is_none());
+    }
+
+    #[test]
+    fn test_deadline_engines_hold_the_interval() {
+        let interval = Duration::from_micros(500);
+        for mode in [PacingMode::SpinSleep, PacingMode::Timerfd] {
+            let report = paced(mode, interval, 41);
+            assert_eq!(report.intervals, 40);
+            assert_eq!(report.requested_ns, 500_000);
+            // Absolute deadlines keep the mean on the requested interval
+            // however late single releases are
+            let total = report.mean_ns * report.intervals as f64;
+            assert!(
+                total < 500_000.0 * 40.0 * 1.5,
+                "{} paced {} intervals in {}ns",
+                mode,
+                report.intervals,
+                total
+            );
+            assert!(report.min_ns > 0);
+        }
+    }
+
+    #[test]
+    fn test_sleep_never_undershoots() {
+        let report = paced(PacingMode::Sleep, Duration::from_micros(200), 11);
+        assert_eq!(report.mode, PacingMode::Sleep);
+        assert!(report.min_ns >= 200_000);
+    }
+
+    #[test]
+    fn test_schedule_restarts_when_far_behind() {
+        let interval = Duration::from_millis(1);
+        let mut pacer = Pacer::new(PacingMode::SpinSleep, interval);
+        let first = pacer.next_deadline();
+        std::thread::sleep(Duration::from_millis(5));
+        // No burst of missed deadlines, the schedule starts over
+        let restarted = pacer.next_deadline();
+        assert!(restarted >= first + Duration::from_millis(4));
+        assert_eq!(pacer.next_deadline(), restarted + interval);
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = PacingReport {
+            mode: PacingMode::Timerfd,
+            requested_ns: 50_000,
+            intervals: 99,
+            mean_ns: 50_100.0,
+            std_dev_ns: 900.0,
+            min_ns: 49_000,
+            p50_ns: 50_000,
+            p99_ns: 53_000,
+            max_ns: 60_000,
+        };
+        assert_eq!(
+            report.to_string(),
+            "timerfd, requested 50.00μs, achieved mean 50.10μs (std dev 900ns), \
+             P50 50.00μs, P99 53.00μs, max 60.00μs over 99 intervals"
+        );
+    }
+}
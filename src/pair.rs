@@ -0,0 +1,204 @@
+//! Each direction of one connection measured in turn (`--pair`).
+//!
+//! A one-way test only ever measures client to server. Some transports are
+//! not symmetric: a socket's send and receive paths take different locks
+//! and buffers, the two PMQ queues can be sized differently, and NIC
+//! offloads for TCP treat transmit and receive differently. With `--pair`
+//! the one-way test runs two measured phases over the same established
+//! connection:
+//!
+//! 1. Client to server: the usual one-way test, timed by the server.
+//! 2. Server to client: the client sends a turn message, a request whose
+//!    ID is the number of messages it sent, and the server streams that
+//!    many messages of the same size back, each stamped as it is sent. The
+//!    client times their arrival.
+//!
+//! Both ends stamp and read the same monotonic clock, so the server must
+//! run on the client's host. The report holds the server-to-client
+//! latency and its ratio to the client-to-server latency.
+
+use crate::ipc::{BlockingTransport, Message, MessageType};
+use crate::mechanism::IpcMechanism;
+use crate::metrics::{LatencyCollector, LatencyMetrics, LatencyType};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Whether the server of `mechanism` can stream messages to its client.
+///
+/// Shared memory and multicast have no way back, and HTTP and gRPC servers
+/// only answer requests.
+pub fn supports(mechanism: &IpcMechanism) -> bool {
+    crate::ipc::handshake::has_reply_path(mechanism)
+        && !matches!(mechanism, IpcMechanism::Http | IpcMechanism::Grpc)
+}
+
+/// How much slower the server-to-client direction was than client to
+/// server; above 1 when server to client was slower.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Asymmetry {
+    /// Ratio of the median latencies
+    pub p50_ratio: f64,
+
+    /// Ratio of the P99 latencies, when P99 was calculated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99_ratio: Option<f64>,
+}
+
+impl Asymmetry {
+    /// Compare `server_to_client` with `client_to_server`, or `None` when
+    /// either has no samples.
+    pub fn between(
+        client_to_server: &LatencyMetrics,
+        server_to_client: &LatencyMetrics,
+    ) -> Option<Self> {
+        if client_to_server.total_samples == 0 || server_to_client.total_samples == 0 {
+            return None;
+        }
+        let ratio = |reverse: f64, forward: f64| reverse / forward.max(1.0);
+        let p99 = |latency: &LatencyMetrics| {
+            latency
+                .percentiles
+                .iter()
+                .find(|p| (p.percentile - 99.0).abs() < f64::EPSILON)
+                .map(|p| p.value_ns as f64)
+        };
+        Some(Self {
+            p50_ratio: ratio(server_to_client.median_ns, client_to_server.median_ns),
+            p99_ratio: p99(server_to_client)
+                .zip(p99(client_to_server))
+                .map(|(reverse, forward)| ratio(reverse, forward)),
+        })
+    }
+}
+
+impl fmt::Display for Asymmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2}x at P50", self.p50_ratio)?;
+        if let Some(ratio) = self.p99_ratio {
+            write!(f, ", {:.2}x at P99", ratio)?;
+        }
+        Ok(())
+    }
+}
+
+/// The server-to-client phase of a pair test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairReport {
+    /// Latency of the messages the server streamed back
+    pub server_to_client: LatencyMetrics,
+
+    /// Rate the client received them at
+    pub messages_per_second: f64,
+
+    /// Server-to-client latency relative to the client-to-server latency
+    /// of the same test
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asymmetry: Option<Asymmetry>,
+}
+
+impl PairReport {
+    /// Fill in the asymmetry against the test's client-to-server latency.
+    pub fn compared_with(mut self, client_to_server: Option<&LatencyMetrics>) -> Self {
+        self.asymmetry = client_to_server
+            .and_then(|forward| Asymmetry::between(forward, &self.server_to_client));
+        self
+    }
+}
+
+/// Hand the connection over to the server and time the `count` messages it
+/// streams back, discarding the first when `discard_first` is set, as the
+/// client-to-server phase discards its first.
+pub fn receive_reverse_blocking(
+    transport: &mut dyn BlockingTransport,
+    count: usize,
+    discard_first: bool,
+    percentiles: &[f64],
+) -> Result<PairReport> {
+    let total = count + usize::from(discard_first);
+    let turn = Message::new(total as u64, Vec::new(), MessageType::Request);
+    transport
+        .send_blocking(&turn)
+        .context("Failed to hand the connection over to the server")?;
+
+    let mut collector = LatencyCollector::new(LatencyType::OneWay)?;
+    let mut received = 0usize;
+    let mut first_arrival = None;
+    while received < total {
+        let (message, receive_time_ns) = transport
+            .receive_blocking_timed()
+            .context("Server-to-client stream ended early")?;
+        if message.message_type != MessageType::OneWay {
+            continue;
+        }
+        received += 1;
+        if discard_first && received == 1 {
+            continue;
+        }
+        first_arrival.get_or_insert_with(Instant::now);
+        collector.record(Duration::from_nanos(
+            receive_time_ns.saturating_sub(message.timestamp),
+        ))?;
+    }
+    let elapsed = first_arrival.map_or(Duration::ZERO, |start| start.elapsed());
+    Ok(PairReport {
+        server_to_client: collector.get_metrics(percentiles),
+        messages_per_second: if elapsed.is_zero() {
+            0.0
+        } else {
+            count as f64 / elapsed.as_secs_f64()
+        },
+        asymmetry: None,
+    })
+}
+
+/// Answer a client's turn message by streaming `turn.id` messages of
+/// `message_size` bytes back, each stamped as it is sent.
+pub fn stream_reverse_blocking(
+    transport: &mut dyn BlockingTransport,
+    turn: &Message,
+    message_size: usize,
+) -> Result<()> {
+    let payload = vec![0u8; message_size];
+    for id in 0..turn.id {
+        let message = Message::new(id, payload.clone(), MessageType::OneWay);
+        transport
+            .send_blocking(&message)
+            .context("Failed to stream to the client")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latency(samples_us: &[u64]) -> LatencyMetrics {
+        let mut collector = LatencyCollector::new(LatencyType::OneWay).unwrap();
+        for &us in samples_us {
+            collector.record(Duration::from_micros(us)).unwrap();
+        }
+        collector.get_metrics(&[50.0, 99.0])
+    }
+
+    #[test]
+    fn test_asymmetry_ratios() {
+        let forward = latency(&[10, 10, 10, 20]);
+        let reverse = latency(&[20, 20, 20, 60]);
+        let asymmetry = Asymmetry::between(&forward, &reverse).unwrap();
+        assert!((asymmetry.p50_ratio - 2.0).abs() < 0.01);
+        assert!((asymmetry.p99_ratio.unwrap() - 3.0).abs() < 0.01);
+        assert_eq!(asymmetry.to_string(), "2.00x at P50, 3.00x at P99");
+
+        assert!(Asymmetry::between(&forward, &latency(&[])).is_none());
+    }
+
+    #[test]
+    fn test_supports_mechanisms_with_a_way_back() {
+        assert!(supports(&IpcMechanism::TcpSocket));
+        assert!(!supports(&IpcMechanism::SharedMemory));
+        assert!(!supports(&IpcMechanism::Http));
+        assert!(!supports(&IpcMechanism::Grpc));
+    }
+}
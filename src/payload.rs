@@ -0,0 +1,260 @@
+//! Payloads of the messages the benchmark client sends.
+//!
+//! By default every message carries `--message-size` zero bytes. Library
+//! users benchmarking domain-specific traffic, such as CAN frames or
+//! protobuf telemetry, can implement [`PayloadGenerator`] to choose each
+//! message's size, content and metadata, and hand it to a runner with
+//! `with_payload_generator`. The messages then go through the standard
+//! pipeline: same transports, same server, same metrics.
+//!
+//! The transports' buffers are sized from `--message-size`, so a
+//! generator's payloads must not be larger; a larger one fails the test.
+//! Byte throughput is still counted as `--message-size` per message.
+//!
+//! Payload buffers come from a [`BufferPool`]; send loops hand each
+//! message back with [`MessageSource::recycle`] once it is sent.
+//!
+//! ```rust
+//! use ipc_benchmark::payload::{MessageMetadata, PayloadGenerator};
+//!
+//! /// 8-byte CAN frames with the message ID as the frame counter
+//! struct CanFrames;
+//!
+//! impl PayloadGenerator for CanFrames {
+//!     fn size(&self, _id: u64) -> usize {
+//!         8
+//!     }
+//!
+//!     fn fill(&self, id: u64, payload: &mut [u8]) {
+//!         payload.copy_from_slice(&id.to_le_bytes());
+//!     }
+//!
+//!     fn metadata(&self, id: u64) -> MessageMetadata {
+//!         // Every tenth frame is a high-priority diagnostic frame
+//!         MessageMetadata {
+//!             priority: (id % 10 == 0).then_some(7),
+//!             ..MessageMetadata::default()
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::buffer_pool::BufferPool;
+use crate::ipc::{Message, MessageType};
+use crate::sequence::IdSequence;
+use anyhow::{bail, Result};
+use std::{sync::Arc, time::Duration};
+
+/// Per-message settings a [`PayloadGenerator`] can choose
+///
+/// Fields left at `None` take the value the command line gives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageMetadata {
+    /// Delivery priority, in place of `--priorities`
+    pub priority: Option<u8>,
+
+    /// Time to live after the send, in place of `--deadline`
+    pub ttl: Option<Duration>,
+}
+
+/// Chooses the size, content and metadata of each benchmark message.
+///
+/// Called from the client's send loop, once per message, so
+/// implementations should be cheap. They are shared between workers and
+/// must be `Send + Sync`; keep any state behind atomics or a lock.
+pub trait PayloadGenerator: Send + Sync {
+    /// Payload size of message `id`, in bytes.
+    fn size(&self, id: u64) -> usize;
+
+    /// Write the content of message `id` into `payload`, which is
+    /// [`size`](Self::size) bytes long. Its buffer is reused between
+    /// messages and still holds an earlier message's bytes; a generator
+    /// whose payloads vary must write all of them.
+    fn fill(&self, id: u64, payload: &mut [u8]);
+
+    /// Priority and deadline of message `id`; by default, those of the
+    /// command line.
+    fn metadata(&self, _id: u64) -> MessageMetadata {
+        MessageMetadata::default()
+    }
+}
+
+/// The default payload: a fixed number of zero bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct ZeroPayload {
+    size: usize,
+}
+
+impl ZeroPayload {
+    /// Payloads of `size` zero bytes.
+    pub fn new(size: usize) -> Self {
+        Self { size }
+    }
+}
+
+impl PayloadGenerator for ZeroPayload {
+    fn size(&self, _id: u64) -> usize {
+        self.size
+    }
+
+    fn fill(&self, _id: u64, _payload: &mut [u8]) {}
+}
+
+/// Builds the messages of a test from a [`PayloadGenerator`].
+#[derive(Clone)]
+pub struct MessageSource {
+    generator: Arc<dyn PayloadGenerator>,
+    max_size: usize,
+    ids: Option<IdSequence>,
+    pool: Arc<BufferPool>,
+}
+
+impl std::fmt::Debug for MessageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageSource")
+            .field("max_size", &self.max_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MessageSource {
+    /// Messages from `generator`, whose payloads may be at most
+    /// `max_size` bytes.
+    pub fn new(generator: Arc<dyn PayloadGenerator>, max_size: usize) -> Self {
+        Self {
+            generator,
+            max_size,
+            ids: None,
+            pool: Arc::new(BufferPool::new(max_size)),
+        }
+    }
+
+    /// Send the IDs of `ids` (`--id-width`) in place of the message numbers
+    /// given to [`message`](Self::message); the generator still sees the
+    /// numbers.
+    pub fn with_id_sequence(mut self, ids: Option<IdSequence>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// The IDs sent, when they follow an [`IdSequence`]
+    pub fn id_sequence(&self) -> Option<&IdSequence> {
+        self.ids.as_ref()
+    }
+
+    /// Start the ID sequence again for a new test.
+    pub fn restart_ids(&self) {
+        if let Some(ids) = &self.ids {
+            ids.restart();
+        }
+    }
+
+    /// Message `id` of `message_type`. `ttl` and `priority` are the
+    /// command line's, used where the generator leaves them unset.
+    pub fn message(
+        &self,
+        id: u64,
+        message_type: MessageType,
+        ttl: Option<Duration>,
+        priority: u8,
+    ) -> Result<Message> {
+        let size = self.generator.size(id);
+        if size > self.max_size {
+            bail!(
+                "Payload generator made a {}-byte payload for message {}, larger than the \
+                 message size of {} bytes the transports are sized for",
+                size,
+                id,
+                self.max_size
+            );
+        }
+        let mut payload = self.pool.take(size);
+        self.generator.fill(id, &mut payload);
+        let metadata = self.generator.metadata(id);
+        let wire_id = self.ids.as_ref().map_or(id, IdSequence::next_id);
+        Ok(Message::new(wire_id, payload, message_type)
+            .with_ttl(metadata.ttl.or(ttl))
+            .with_priority(metadata.priority.unwrap_or(priority)))
+    }
+
+    /// Return a sent message's payload buffer for the next message.
+    pub fn recycle(&self, message: Message) {
+        self.pool.give(message.payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter;
+
+    impl PayloadGenerator for Counter {
+        fn size(&self, id: u64) -> usize {
+            id as usize
+        }
+
+        fn fill(&self, id: u64, payload: &mut [u8]) {
+            payload.fill(id as u8);
+        }
+
+        fn metadata(&self, id: u64) -> MessageMetadata {
+            MessageMetadata {
+                priority: (id == 3).then_some(9),
+                ttl: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_messages_follow_the_generator() {
+        let source = MessageSource::new(Arc::new(Counter), 4);
+        let ttl = Some(Duration::from_micros(5));
+        let message = source.message(3, MessageType::Request, ttl, 1).unwrap();
+        assert_eq!(message.id, 3);
+        assert_eq!(message.payload, [3, 3, 3]);
+        assert_eq!(message.priority, 9);
+        assert_eq!(message.ttl_ns, Some(5_000));
+        assert_eq!(
+            source
+                .message(2, MessageType::OneWay, None, 1)
+                .unwrap()
+                .priority,
+            1
+        );
+
+        let error = source.message(5, MessageType::OneWay, None, 0).unwrap_err();
+        assert!(error.to_string().contains("5-byte payload for message 5"));
+    }
+
+    #[test]
+    fn test_id_sequence_replaces_message_ids() {
+        let space = crate::sequence::IdSpace::new(8, 255).unwrap();
+        let source =
+            MessageSource::new(Arc::new(Counter), 4).with_id_sequence(Some(IdSequence::new(space)));
+        let first = source.message(1, MessageType::OneWay, None, 0).unwrap();
+        let second = source.message(2, MessageType::OneWay, None, 0).unwrap();
+        assert_eq!((first.id, second.id), (255, 0));
+        assert_eq!(second.payload, [2, 2]);
+        source.restart_ids();
+        assert_eq!(
+            source.message(3, MessageType::OneWay, None, 0).unwrap().id,
+            255
+        );
+    }
+
+    #[test]
+    fn test_zero_payload() {
+        let source = MessageSource::new(Arc::new(ZeroPayload::new(16)), 16);
+        let message = source.message(0, MessageType::OneWay, None, 0).unwrap();
+        assert_eq!(message.payload, vec![0u8; 16]);
+
+        // A recycled payload is reused as it was sent, without a second
+        // pass to zero it
+        let address = message.payload.as_ptr();
+        source.recycle(message);
+        let message = source.message(1, MessageType::OneWay, None, 0).unwrap();
+        assert_eq!(message.payload.as_ptr(), address);
+        assert_eq!(message.payload, vec![0u8; 16]);
+    }
+}
@@ -0,0 +1,236 @@
+//! Priority inversion scenario for PMQ and SHM.
+//!
+//! Mixed-criticality systems share a channel between a latency-critical
+//! sender and bulk traffic, and rely on message priorities to keep the bulk
+//! from delaying the critical path. `--priority-inversion N` measures how
+//! well that holds for each mechanism. The one-way test runs twice against
+//! the same server, constrained with `--server-rate`:
+//!
+//! 1. Baseline: only critical messages, at [`CRITICAL_PRIORITY`].
+//! 2. Contended: each critical message followed by N bulk messages at
+//!    [`BULK_PRIORITY`], through PMQ message priorities or the SHM ring's
+//!    priority lanes.
+//!
+//! The baseline keeps the critical messages' count and, with
+//! `--send-delay`, their spacing, so the critical stream is the same in
+//! both runs and only the bulk traffic differs. The contended run is the
+//! test's one-way result, split by priority as with `--priorities`, and the
+//! critical latency of both runs is compared under `priority_inversion`.
+
+use crate::metrics::utils::format_latency;
+use crate::metrics::{LatencyMetrics, PerformanceMetrics, Priority};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Priority of the latency-critical messages
+pub const CRITICAL_PRIORITY: u8 = 7;
+
+/// Priority of the bulk messages
+pub const BULK_PRIORITY: u8 = 0;
+
+/// Priorities of the contended run, sent in turn: one critical message,
+/// then `bulk_per_critical` bulk ones.
+pub fn priorities(bulk_per_critical: u32) -> Vec<u8> {
+    std::iter::once(CRITICAL_PRIORITY)
+        .chain(std::iter::repeat(BULK_PRIORITY).take(bulk_per_critical as usize))
+        .collect()
+}
+
+/// Distribution of the critical messages' latency in one run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CriticalLatency {
+    /// Median latency in nanoseconds
+    pub p50_ns: u64,
+
+    /// 99th percentile in nanoseconds, when it was calculated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99_ns: Option<u64>,
+
+    /// Maximum latency in nanoseconds
+    pub max_ns: u64,
+
+    /// Number of critical messages measured
+    pub samples: usize,
+}
+
+impl CriticalLatency {
+    fn from_latency(latency: &LatencyMetrics) -> Self {
+        Self {
+            p50_ns: latency.median_ns as u64,
+            p99_ns: latency
+                .percentiles
+                .iter()
+                .find(|p| (p.percentile - 99.0).abs() < f64::EPSILON)
+                .map(|p| p.value_ns),
+            max_ns: latency.max_ns,
+            samples: latency.total_samples,
+        }
+    }
+
+    /// Latency of the critical priority in `metrics`.
+    fn of(metrics: &PerformanceMetrics) -> Option<Self> {
+        metrics
+            .priority_latency
+            .iter()
+            .find(|entry| entry.key == Priority(CRITICAL_PRIORITY))
+            .map(|entry| Self::from_latency(&entry.latency))
+    }
+}
+
+/// How much bulk traffic inflated the critical path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InversionReport {
+    /// Bulk messages sent after each critical message
+    pub bulk_per_critical: u32,
+
+    /// Consumer rate limit (`--server-rate`), messages per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_rate: Option<u64>,
+
+    /// Critical messages sent alone
+    pub baseline: CriticalLatency,
+
+    /// Critical messages sent among the bulk messages
+    pub contended: CriticalLatency,
+
+    /// Contended over baseline median
+    pub p50_inflation: f64,
+
+    /// Contended over baseline P99, when both were calculated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99_inflation: Option<f64>,
+
+    /// Contended over baseline maximum
+    pub max_inflation: f64,
+}
+
+impl InversionReport {
+    /// Compare the critical messages of the `baseline` and `contended`
+    /// one-way runs; `None` when either has no critical latency.
+    pub fn new(
+        bulk_per_critical: u32,
+        server_rate: Option<u64>,
+        baseline: &PerformanceMetrics,
+        contended: &PerformanceMetrics,
+    ) -> Option<Self> {
+        let baseline = CriticalLatency::of(baseline)?;
+        let contended = CriticalLatency::of(contended)?;
+        Some(Self {
+            bulk_per_critical,
+            server_rate,
+            p50_inflation: ratio(contended.p50_ns, baseline.p50_ns),
+            p99_inflation: baseline
+                .p99_ns
+                .zip(contended.p99_ns)
+                .map(|(alone, mixed)| ratio(mixed, alone)),
+            max_inflation: ratio(contended.max_ns, baseline.max_ns),
+            baseline,
+            contended,
+        })
+    }
+}
+
+fn ratio(contended: u64, baseline: u64) -> f64 {
+    contended as f64 / baseline.max(1) as f64
+}
+
+impl fmt::Display for InversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "critical P50 {} alone, {} with {} bulk per critical ({:.2}x)",
+            format_latency(self.baseline.p50_ns),
+            format_latency(self.contended.p50_ns),
+            self.bulk_per_critical,
+            self.p50_inflation
+        )?;
+        if let (Some(alone), Some(mixed), Some(inflation)) = (
+            self.baseline.p99_ns,
+            self.contended.p99_ns,
+            self.p99_inflation,
+        ) {
+            write!(
+                f,
+                ", P99 {} -> {} ({:.2}x)",
+                format_latency(alone),
+                format_latency(mixed),
+                inflation
+            )?;
+        }
+        write!(
+            f,
+            ", max {} -> {} ({:.2}x)",
+            format_latency(self.baseline.max_ns),
+            format_latency(self.contended.max_ns),
+            self.max_inflation
+        )?;
+        if let Some(rate) = self.server_rate {
+            write!(f, ", consumer limited to {} msgs/s", rate)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::MetricsCollector;
+    use std::time::Duration;
+
+    fn run(latencies_us: &[(u8, u64)]) -> PerformanceMetrics {
+        let mut collector =
+            MetricsCollector::new(Some(crate::metrics::LatencyType::OneWay), vec![50.0, 99.0])
+                .unwrap();
+        for &(priority, us) in latencies_us {
+            let latency = Duration::from_micros(us);
+            collector.record_message(64, Some(latency)).unwrap();
+            collector
+                .record_priority_latency(priority, latency)
+                .unwrap();
+        }
+        collector.get_metrics()
+    }
+
+    #[test]
+    fn test_priorities() {
+        assert_eq!(priorities(0), [CRITICAL_PRIORITY]);
+        assert_eq!(
+            priorities(3),
+            [
+                CRITICAL_PRIORITY,
+                BULK_PRIORITY,
+                BULK_PRIORITY,
+                BULK_PRIORITY
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_compares_critical_latency() {
+        let baseline = run(&[(CRITICAL_PRIORITY, 10), (CRITICAL_PRIORITY, 10)]);
+        let contended = run(&[
+            (CRITICAL_PRIORITY, 30),
+            (BULK_PRIORITY, 500),
+            (CRITICAL_PRIORITY, 40),
+            (BULK_PRIORITY, 900),
+        ]);
+        let report = InversionReport::new(1, Some(1000), &baseline, &contended).unwrap();
+        assert_eq!(report.contended.samples, 2);
+        assert!((report.max_inflation - 4.0).abs() < 0.1);
+        assert!(report.p99_inflation.unwrap() > 3.0);
+        let line = report.to_string();
+        assert!(line.starts_with("critical P50 10."), "{}", line);
+        assert!(
+            line.ends_with("consumer limited to 1000 msgs/s"),
+            "{}",
+            line
+        );
+
+        // Without prioritized messages there is nothing to compare
+        let unprioritized =
+            MetricsCollector::new(Some(crate::metrics::LatencyType::OneWay), vec![])
+                .unwrap()
+                .get_metrics();
+        assert!(InversionReport::new(1, None, &unprioritized, &contended).is_none());
+    }
+}
@@ -0,0 +1,413 @@
+//! Several producers sending into one POSIX message queue.
+//!
+//! With `--producers N`, a one-way PMQ test has N clients sending into the
+//! server's request queue at the same time: the benchmark's own client, as
+//! producer 0, and N-1 child processes of this binary started with
+//! `--internal-run-as-producer`. Each child opens the queue, reports that it
+//! is ready on stdout and waits for a start byte on stdin, so all producers
+//! begin together. With `--priorities`, each producer sends all of its
+//! messages at one priority, so high-priority producers compete with
+//! low-priority ones.
+//!
+//! Every message ID carries its producer number in the top bits. The server
+//! lists the producer of each message it records, and the runner splits
+//! delivery by producer into a [`ProducerReport`]: messages, throughput and
+//! latency per producer, and Jain's fairness index over their throughput.
+
+use crate::metrics::{LatencyCollector, LatencyMetrics, LatencyType};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Bits of a message ID below the producer number
+const PRODUCER_SHIFT: u32 = 56;
+
+/// ID of the `sequence`-th message of `producer`
+pub fn producer_message_id(producer: u8, sequence: u64) -> u64 {
+    (u64::from(producer) << PRODUCER_SHIFT) | (sequence & ((1 << PRODUCER_SHIFT) - 1))
+}
+
+/// Producer that sent the message with `id`
+pub fn message_producer(id: u64) -> u8 {
+    (id >> PRODUCER_SHIFT) as u8
+}
+
+/// Priority every message of `producer` is sent at: the `--priorities` in
+/// turn by producer, or 0.
+pub fn producer_priority(priorities: &[u8], producer: u8) -> u8 {
+    if priorities.is_empty() {
+        0
+    } else {
+        priorities[usize::from(producer) % priorities.len()]
+    }
+}
+
+/// Jain's fairness index of `rates`: 1 when all are equal, down to 1/n
+/// when one producer got everything.
+pub fn jain_fairness_index(rates: &[f64]) -> f64 {
+    let sum: f64 = rates.iter().sum();
+    let sum_of_squares: f64 = rates.iter().map(|rate| rate * rate).sum();
+    if sum_of_squares == 0.0 {
+        return 1.0;
+    }
+    sum * sum / (rates.len() as f64 * sum_of_squares)
+}
+
+/// Delivery of one producer's messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProducerMetrics {
+    /// Producer number; 0 is the benchmark's own client
+    pub producer: u8,
+
+    /// Priority the producer sent at
+    pub priority: u8,
+
+    /// Messages of this producer the server received
+    pub messages: usize,
+
+    /// Messages per second, from the producer's first send to the server's
+    /// receipt of its last message
+    pub messages_per_second: f64,
+
+    /// Latency of this producer's messages
+    pub latency: LatencyMetrics,
+}
+
+/// Per-producer delivery of a `--producers` test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProducerReport {
+    /// Each producer, in producer order
+    pub producers: Vec<ProducerMetrics>,
+
+    /// Jain's fairness index over the producers' throughput
+    pub fairness_index: f64,
+}
+
+/// Accumulates the server-measured latencies of each producer.
+pub struct ProducerTally {
+    percentiles: Vec<f64>,
+    producers: BTreeMap<u8, Tally>,
+}
+
+struct Tally {
+    messages: usize,
+    first_send_ns: u64,
+    last_receive_ns: u64,
+    latency: LatencyCollector,
+}
+
+impl ProducerTally {
+    /// Empty tally reporting `percentiles`.
+    pub fn new(percentiles: Vec<f64>) -> Self {
+        Self {
+            percentiles,
+            producers: BTreeMap::new(),
+        }
+    }
+
+    /// Record a message of `producer` sent at wall-clock `wall_send_ns`
+    /// that took `latency` to arrive.
+    pub fn record(&mut self, producer: u8, wall_send_ns: u64, latency: Duration) -> Result<()> {
+        let receive_ns = wall_send_ns + latency.as_nanos() as u64;
+        let tally = match self.producers.entry(producer) {
+            std::collections::btree_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::btree_map::Entry::Vacant(entry) => entry.insert(Tally {
+                messages: 0,
+                first_send_ns: wall_send_ns,
+                last_receive_ns: receive_ns,
+                latency: LatencyCollector::new(LatencyType::OneWay)?,
+            }),
+        };
+        tally.messages += 1;
+        tally.first_send_ns = tally.first_send_ns.min(wall_send_ns);
+        tally.last_receive_ns = tally.last_receive_ns.max(receive_ns);
+        tally.latency.record(latency)
+    }
+
+    /// Report of the producers seen, sent at `priorities` in turn; `None`
+    /// if no messages were recorded.
+    pub fn finish(self, priorities: &[u8]) -> Option<ProducerReport> {
+        if self.producers.is_empty() {
+            return None;
+        }
+        let producers: Vec<ProducerMetrics> = self
+            .producers
+            .into_iter()
+            .map(|(producer, tally)| {
+                let span_ns = tally.last_receive_ns.saturating_sub(tally.first_send_ns);
+                ProducerMetrics {
+                    producer,
+                    priority: producer_priority(priorities, producer),
+                    messages: tally.messages,
+                    messages_per_second: tally.messages as f64 / (span_ns.max(1) as f64 / 1e9),
+                    latency: tally.latency.get_metrics(&self.percentiles),
+                }
+            })
+            .collect();
+        let rates: Vec<f64> = producers.iter().map(|p| p.messages_per_second).collect();
+        Some(ProducerReport {
+            fairness_index: jain_fairness_index(&rates),
+            producers,
+        })
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "cli"))]
+pub use self::processes::{run_producer, ProducerProcesses};
+
+#[cfg(all(target_os = "linux", feature = "cli"))]
+mod processes {
+    use super::{producer_message_id, producer_priority};
+    use crate::benchmark::BenchmarkConfig;
+    use crate::benchmark_blocking::BlockingBenchmarkRunner;
+    use crate::cli::{Args, IpcMechanism};
+    use crate::ipc::{BlockingTransportFactory, Message, MessageType, TransportConfig};
+    use anyhow::{Context, Result};
+    use std::io::{Read, Write};
+    use std::process::{Child, Command, Stdio};
+    use std::time::Instant;
+    use tracing::{debug, warn};
+
+    /// Byte a producer writes once its queue is open, and the parent writes
+    /// back to start it
+    const READY: u8 = b'R';
+    const START: u8 = b'S';
+
+    /// The child producer processes of a `--producers` test.
+    ///
+    /// Dropping the handle kills producers that are still running, so a
+    /// failed test does not leave them sending into a stale queue.
+    pub struct ProducerProcesses {
+        children: Vec<Child>,
+    }
+
+    impl ProducerProcesses {
+        /// Start producers 1 to `args.producers - 1` against the server
+        /// queue in `transport_config`, and wait until all have opened it.
+        pub fn spawn(
+            args: &Args,
+            config: &BenchmarkConfig,
+            transport_config: &TransportConfig,
+        ) -> Result<Self> {
+            let mut producers = Self {
+                children: Vec::new(),
+            };
+            for producer in 1..args.producers {
+                let cmd = producer_command(producer, args, config, transport_config)?;
+                producers.children.push(spawn_child(cmd, producer)?);
+            }
+            for (producer, child) in (1..).zip(&mut producers.children) {
+                let mut signal = [0u8; 1];
+                child
+                    .stdout
+                    .as_mut()
+                    .context("Producer stdout is not piped")?
+                    .read_exact(&mut signal)
+                    .with_context(|| format!("Producer {} failed to start", producer))?;
+            }
+            debug!("{} extra producers ready", producers.children.len());
+            Ok(producers)
+        }
+
+        /// Let every producer start sending.
+        pub fn start(&mut self) -> Result<()> {
+            for child in &mut self.children {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin
+                        .write_all(&[START])
+                        .context("Failed to start producer")?;
+                }
+            }
+            Ok(())
+        }
+
+        /// Wait for every producer to finish sending.
+        pub fn finish(mut self) -> Result<()> {
+            for (producer, mut child) in (1..).zip(std::mem::take(&mut self.children)) {
+                let status = child.wait()?;
+                if !status.success() {
+                    anyhow::bail!("Producer {} exited with {}", producer, status);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for ProducerProcesses {
+        fn drop(&mut self) {
+            for child in &mut self.children {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+
+    fn spawn_child(mut cmd: Command, producer: u8) -> Result<Child> {
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+        // Own process group, like the server: a Ctrl+C reaches the client,
+        // which stops the test and then the producers
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+        debug!("Spawning producer {} with command: {:?}", producer, cmd);
+        cmd.spawn()
+            .with_context(|| format!("Failed to spawn producer {}", producer))
+    }
+
+    /// Command line of producer `producer`.
+    fn producer_command(
+        producer: u8,
+        args: &Args,
+        config: &BenchmarkConfig,
+        transport_config: &TransportConfig,
+    ) -> Result<Command> {
+        let mut cmd = Command::new(crate::server_handle::resolve_server_binary()?);
+        cmd.arg("--internal-run-as-producer")
+            .arg(producer.to_string())
+            .arg("--blocking")
+            .args(["-m", "pmq"]);
+        cmd.arg("--message-size")
+            .arg(config.message_size.to_string());
+        match config.duration {
+            Some(duration) => cmd
+                .arg("--duration")
+                .arg(format!("{}ms", duration.as_secs_f64() * 1000.0)),
+            None => cmd
+                .arg("--msg-count")
+                .arg(config.msg_count.unwrap_or_default().to_string()),
+        };
+        cmd.arg("--message-queue-name")
+            .arg(&transport_config.message_queue_name);
+        cmd.arg("--buffer-size")
+            .arg(transport_config.buffer_size.to_string());
+        if !args.priorities.is_empty() {
+            let priorities: Vec<String> = args.priorities.iter().map(u8::to_string).collect();
+            cmd.arg("--priorities").arg(priorities.join(","));
+        }
+        if let Some(delay) = config.send_delay {
+            cmd.arg("--send-delay")
+                .arg(format!("{}us", delay.as_micros()));
+        }
+        cmd.arg("--run-id").arg(crate::run_id::get());
+        for _ in 0..args.verbose {
+            cmd.arg("-v");
+        }
+        Ok(cmd)
+    }
+
+    /// Body of a producer child process: open the queue, signal readiness,
+    /// wait for the start byte and send this producer's messages.
+    pub fn run_producer(producer: u8, args: &Args) -> Result<()> {
+        let config = BenchmarkConfig::from_args(args)?;
+        let mechanism = IpcMechanism::PosixMessageQueue;
+        let runner = BlockingBenchmarkRunner::new(config.clone(), mechanism, args.clone());
+        let mut transport_config = runner.create_transport_config_internal(args)?;
+        if let Some(name) = &args.message_queue_name {
+            transport_config.message_queue_name = name.clone();
+        }
+
+        let mut transport = BlockingTransportFactory::create(&mechanism, false, None)?;
+        transport
+            .start_client_blocking(&transport_config)
+            .with_context(|| format!("Producer {} failed to open the queue", producer))?;
+
+        let mut stdout = std::io::stdout();
+        stdout.write_all(&[READY])?;
+        stdout.flush()?;
+        let mut start = [0u8; 1];
+        if std::io::stdin().read_exact(&mut start).is_err() || start[0] != START {
+            // The test ended before it started
+            return transport.close_blocking();
+        }
+
+        let priority = producer_priority(&args.priorities, producer);
+        let payload = vec![0u8; config.message_size];
+        let start_time = Instant::now();
+        let mut sequence = 0u64;
+        loop {
+            let more = match (config.duration, config.msg_count) {
+                (Some(duration), _) => start_time.elapsed() < duration,
+                (None, count) => sequence < count.unwrap_or_default() as u64,
+            };
+            if !more {
+                break;
+            }
+            let message = Message::new(
+                producer_message_id(producer, sequence),
+                payload.clone(),
+                MessageType::OneWay,
+            )
+            .with_priority(priority);
+            if let Err(e) = transport.send_blocking(&message) {
+                // In duration mode the server may stop draining first
+                if config.duration.is_none() {
+                    return Err(e);
+                }
+                warn!("Producer {} stopped sending: {}", producer, e);
+                break;
+            }
+            sequence += 1;
+            if let Some(delay) = config.send_delay {
+                std::thread::sleep(delay);
+            }
+        }
+        debug!("Producer {} sent {} messages", producer, sequence);
+        transport.close_blocking()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_ids_carry_the_producer() {
+        assert_eq!(producer_message_id(0, 42), 42);
+        let id = producer_message_id(3, 42);
+        assert_eq!(message_producer(id), 3);
+        assert_eq!(id & 0xffff, 42);
+        assert_eq!(message_producer(7), 0);
+    }
+
+    #[test]
+    fn test_producer_priority_cycles_by_producer() {
+        assert_eq!(producer_priority(&[], 2), 0);
+        let priorities = [1, 5];
+        let assigned: Vec<u8> = (0..4).map(|p| producer_priority(&priorities, p)).collect();
+        assert_eq!(assigned, [1, 5, 1, 5]);
+    }
+
+    #[test]
+    fn test_jain_fairness_index() {
+        assert_eq!(jain_fairness_index(&[100.0, 100.0, 100.0]), 1.0);
+        // One producer getting everything scores 1/n
+        assert!((jain_fairness_index(&[300.0, 0.0, 0.0]) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(jain_fairness_index(&[0.0, 0.0]), 1.0);
+    }
+
+    #[test]
+    fn test_tally_reports_each_producer() {
+        let mut tally = ProducerTally::new(vec![50.0]);
+        assert!(ProducerTally::new(Vec::new()).finish(&[]).is_none());
+        // Producer 0 gets 4 messages through in 1ms, producer 1 one in 1ms
+        for i in 0..4u64 {
+            tally
+                .record(0, 1_000_000 + i * 250_000, Duration::from_micros(10))
+                .unwrap();
+        }
+        tally
+            .record(1, 1_000_000, Duration::from_millis(1))
+            .unwrap();
+
+        let report = tally.finish(&[6, 0]).unwrap();
+        assert_eq!(report.producers.len(), 2);
+        let (high, low) = (&report.producers[0], &report.producers[1]);
+        assert_eq!((high.priority, high.messages), (6, 4));
+        assert_eq!((low.priority, low.messages), (0, 1));
+        assert!(high.messages_per_second > low.messages_per_second);
+        assert!(report.fairness_index < 1.0);
+        assert_eq!(low.latency.total_samples, 1);
+    }
+}
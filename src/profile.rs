@@ -0,0 +1,242 @@
+//! Benchmark profiles.
+//!
+//! The defaults favor neither latency nor throughput, so a new user's first
+//! numbers often answer a question they did not ask. `--profile` tunes each
+//! mechanism for one goal:
+//!
+//! | Setting | `latency` | `throughput` | `balanced` |
+//! |---------|-----------|--------------|------------|
+//! | Warmup iterations | 10000 | 2000 | 5000 |
+//! | Receiver batch (`--server-batch`) | 1 | 64 | 8 |
+//! | Reply wait (`--spin-wait`) | busy, with `--blocking` | blocking | blocking |
+//! | Buffer size (`--buffer-size`) | automatic | 1 MB | automatic |
+//!
+//! A setting only applies to mechanisms that support it: receiver batching
+//! to the SHM ring, TCP, UDS and blocking PMQ; busy-polling to blocking
+//! TCP, UDS and PMQ; the larger buffer to the SHM ring, TCP and UDS, as PMQ
+//! buffers are bounded by `msgsize_max`. Options given on the command line
+//! are never overridden. What a profile changed is recorded with each
+//! result as `test_config.profile`.
+
+#[cfg(feature = "cli")]
+use crate::benchmark::BenchmarkConfig;
+#[cfg(feature = "cli")]
+use crate::cli::{Args, IpcMechanism};
+use crate::options::{Profile, SpinWait};
+#[cfg(feature = "cli")]
+use crate::warmup;
+use serde::{Deserialize, Serialize};
+
+/// Buffer size of the throughput profile
+#[cfg(feature = "cli")]
+const THROUGHPUT_BUFFER_SIZE: usize = 1 << 20;
+
+/// What a profile set for one mechanism
+///
+/// Each setting is absent when the profile left it alone: because the
+/// mechanism does not support it, or because it was given on the command
+/// line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileSettings {
+    /// The profile applied
+    pub profile: Profile,
+
+    /// Warmup iterations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup_iterations: Option<usize>,
+
+    /// Messages the server drains per wakeup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_batch: Option<usize>,
+
+    /// How the client waits for replies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spin_wait: Option<SpinWait>,
+
+    /// Transport buffer size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_size: Option<usize>,
+}
+
+impl std::fmt::Display for ProfileSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut settings = Vec::new();
+        if let Some(warmup) = self.warmup_iterations {
+            settings.push(format!("warmup {}", warmup));
+        }
+        if let Some(batch) = self.server_batch {
+            settings.push(format!("server batch {}", batch));
+        }
+        if let Some(spin) = self.spin_wait {
+            settings.push(format!("spin-wait {}", spin));
+        }
+        if let Some(size) = self.buffer_size {
+            settings.push(format!("buffer {} bytes", size));
+        }
+        if settings.is_empty() {
+            write!(f, "{} (no changes)", self.profile)
+        } else {
+            write!(f, "{} ({})", self.profile, settings.join(", "))
+        }
+    }
+}
+
+/// Apply `args.profile`, if any, to the arguments and configuration of a
+/// `mechanism` test, returning what it changed.
+#[cfg(feature = "cli")]
+pub fn apply(
+    args: &mut Args,
+    config: &mut BenchmarkConfig,
+    mechanism: IpcMechanism,
+) -> Option<ProfileSettings> {
+    let profile = args.profile?;
+    let preset = preset(profile, mechanism, args);
+    let mut applied = ProfileSettings {
+        profile,
+        warmup_iterations: None,
+        server_batch: None,
+        spin_wait: None,
+        buffer_size: None,
+    };
+
+    // A timed or per-mechanism warmup replaces the count altogether
+    if !args.is_explicit("warmup_iterations")
+        && args.warmup.is_none()
+        && warmup::override_for(args, mechanism).is_none()
+    {
+        args.warmup_iterations = preset.warmup_iterations;
+        config.warmup_iterations = preset.warmup_iterations;
+        applied.warmup_iterations = Some(preset.warmup_iterations);
+    }
+    if let Some(batch) = preset
+        .server_batch
+        .filter(|_| !args.is_explicit("server_batch"))
+    {
+        args.server_batch = batch;
+        applied.server_batch = Some(batch);
+    }
+    if let Some(spin) = preset.spin_wait.filter(|_| !args.is_explicit("spin_wait")) {
+        args.spin_wait = Some(spin);
+        applied.spin_wait = Some(spin);
+    }
+    if let Some(size) = preset
+        .buffer_size
+        .filter(|_| !args.is_explicit("buffer_size"))
+    {
+        args.buffer_size = Some(size);
+        config.buffer_size = Some(size);
+        applied.buffer_size = Some(size);
+    }
+    Some(applied)
+}
+
+/// The settings of `profile` that `mechanism` supports
+#[cfg(feature = "cli")]
+struct Preset {
+    warmup_iterations: usize,
+    server_batch: Option<usize>,
+    spin_wait: Option<SpinWait>,
+    buffer_size: Option<usize>,
+}
+
+#[cfg(feature = "cli")]
+fn preset(profile: Profile, mechanism: IpcMechanism, args: &Args) -> Preset {
+    let shm_ring = mechanism == IpcMechanism::SharedMemory && !args.shm_direct;
+    let (socket, pmq) = match mechanism {
+        #[cfg(unix)]
+        IpcMechanism::UnixDomainSocket => (true, false),
+        IpcMechanism::TcpSocket => (true, false),
+        #[cfg(target_os = "linux")]
+        IpcMechanism::PosixMessageQueue => (false, true),
+        _ => (false, false),
+    };
+    let batches = shm_ring || socket || (pmq && args.blocking);
+    let spins = args.blocking && (socket || pmq);
+
+    let batch = |depth: usize| batches.then_some(depth);
+    match profile {
+        Profile::Latency => Preset {
+            warmup_iterations: 10_000,
+            server_batch: batch(1),
+            spin_wait: spins.then_some(SpinWait::Busy),
+            buffer_size: None,
+        },
+        Profile::Throughput => Preset {
+            warmup_iterations: 2_000,
+            server_batch: batch(64),
+            spin_wait: None,
+            buffer_size: (shm_ring || socket).then_some(THROUGHPUT_BUFFER_SIZE),
+        },
+        Profile::Balanced => Preset {
+            warmup_iterations: 5_000,
+            server_batch: batch(8),
+            spin_wait: None,
+            buffer_size: None,
+        },
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> (Args, BenchmarkConfig) {
+        let args = Args::try_parse_explicit_from(
+            std::iter::once("ipc-benchmark").chain(args.iter().copied()),
+        )
+        .unwrap();
+        let config = BenchmarkConfig::from_args(&args).unwrap();
+        (args, config)
+    }
+
+    #[test]
+    fn test_no_profile_changes_nothing() {
+        let (mut args, mut config) = parse(&[]);
+        assert!(apply(&mut args, &mut config, IpcMechanism::TcpSocket).is_none());
+        assert_eq!(args.server_batch, 1);
+    }
+
+    #[test]
+    fn test_throughput_profile() {
+        let (mut args, mut config) = parse(&["--profile", "throughput"]);
+        let applied = apply(&mut args, &mut config, IpcMechanism::TcpSocket).unwrap();
+        assert_eq!(applied.server_batch, Some(64));
+        assert_eq!(applied.buffer_size, Some(THROUGHPUT_BUFFER_SIZE));
+        assert_eq!(config.buffer_size, Some(THROUGHPUT_BUFFER_SIZE));
+        assert_eq!(config.warmup_iterations, 2_000);
+        assert_eq!(args.server_batch, 64);
+        assert_eq!(
+            applied.to_string(),
+            "throughput (warmup 2000, server batch 64, buffer 1048576 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_command_line_wins() {
+        let (mut args, mut config) =
+            parse(&["--profile", "throughput", "-w", "10", "--server-batch", "4"]);
+        let applied = apply(&mut args, &mut config, IpcMechanism::TcpSocket).unwrap();
+        assert_eq!(applied.warmup_iterations, None);
+        assert_eq!(applied.server_batch, None);
+        assert_eq!(config.warmup_iterations, 10);
+        assert_eq!(args.server_batch, 4);
+    }
+
+    #[test]
+    fn test_latency_profile_spins_only_in_blocking_mode() {
+        let (mut args, mut config) = parse(&["--profile", "latency"]);
+        let applied = apply(&mut args, &mut config, IpcMechanism::TcpSocket).unwrap();
+        assert_eq!(applied.spin_wait, None);
+
+        let (mut args, mut config) = parse(&["--profile", "latency", "--blocking"]);
+        let applied = apply(&mut args, &mut config, IpcMechanism::TcpSocket).unwrap();
+        assert_eq!(applied.spin_wait, Some(SpinWait::Busy));
+        assert_eq!(args.spin_wait, Some(SpinWait::Busy));
+
+        // Shared memory has no busy-polled reply path
+        let (mut args, mut config) = parse(&["--profile", "latency", "--blocking"]);
+        let applied = apply(&mut args, &mut config, IpcMechanism::SharedMemory).unwrap();
+        assert_eq!(applied.spin_wait, None);
+        assert_eq!(applied.warmup_iterations, Some(10_000));
+    }
+}
@@ -0,0 +1,367 @@
+//! Queue depth sampling during measurement.
+//!
+//! Latency alone cannot tell a slow transport from a queue that was full
+//! the whole time. With `--queue-depth-interval`, a [`QueueDepthSampler`]
+//! polls the client transport's queue from a background thread while a test
+//! runs: the current message count of a POSIX message queue (`mq_getattr`)
+//! or the occupancy of a shared memory ring. The [`QueueDepthReport`]
+//! summarizes the samples in the results, and the series itself is written
+//! to the streaming output.
+//!
+//! Transports without a queue to inspect (sockets, direct-memory SHM)
+//! provide no probe and produce no report.
+
+use crate::ipc::get_monotonic_time_ns;
+use crate::utils::current_timestamp_ns;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How much a queue holds, at one moment or at most.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepth {
+    /// Messages
+    pub messages: usize,
+
+    /// Bytes, for queues that track them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+}
+
+/// Limits of a queue; either may be absent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueCapacity {
+    /// Most messages the queue holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<usize>,
+
+    /// Most bytes the queue holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+}
+
+impl QueueCapacity {
+    /// Whether a queue at `depth` is full: at its message limit, or without
+    /// room for another message of the average queued size.
+    pub fn is_full(&self, depth: QueueDepth) -> bool {
+        if self.messages.is_some_and(|max| depth.messages >= max) {
+            return true;
+        }
+        match (self.bytes, depth.bytes) {
+            (Some(capacity), Some(used)) if depth.messages > 0 => {
+                capacity.saturating_sub(used) < used / depth.messages
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reads the depth of a transport's queue independently of the transport,
+/// so it can be polled from another thread while the test uses it.
+pub trait QueueDepthProbe: Send {
+    /// Current depth, or `None` if the queue can no longer be read.
+    fn depth(&mut self) -> Option<QueueDepth>;
+
+    /// Limits of the queue.
+    fn capacity(&self) -> QueueCapacity;
+}
+
+/// One reading of the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepthSample {
+    /// Wall-clock time of the reading, in nanoseconds since the Unix epoch
+    pub timestamp_ns: u64,
+
+    /// Queue depth at that time
+    pub depth: QueueDepth,
+}
+
+/// Queue depth over one test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueDepthReport {
+    /// Number of samples taken
+    pub samples: usize,
+
+    /// Mean number of queued messages
+    pub mean_messages: f64,
+
+    /// Largest depth seen
+    pub max: QueueDepth,
+
+    /// Limits of the queue
+    pub capacity: QueueCapacity,
+
+    /// Percentage of samples that found the queue full
+    pub full_percent: f64,
+
+    /// The samples, in order; written to the streaming output rather than
+    /// the final results
+    #[serde(skip)]
+    pub series: Vec<QueueDepthSample>,
+}
+
+impl QueueDepthReport {
+    /// Summarize `series` for a queue with `capacity`; `None` without
+    /// samples.
+    pub fn from_series(series: Vec<QueueDepthSample>, capacity: QueueCapacity) -> Option<Self> {
+        if series.is_empty() {
+            return None;
+        }
+        let samples = series.len();
+        let total: usize = series.iter().map(|s| s.depth.messages).sum();
+        let full = series.iter().filter(|s| capacity.is_full(s.depth)).count();
+        let max = QueueDepth {
+            messages: series.iter().map(|s| s.depth.messages).max().unwrap_or(0),
+            bytes: series.iter().filter_map(|s| s.depth.bytes).max(),
+        };
+        Some(Self {
+            samples,
+            mean_messages: total as f64 / samples as f64,
+            max,
+            capacity,
+            full_percent: full as f64 * 100.0 / samples as f64,
+            series,
+        })
+    }
+}
+
+impl std::fmt::Display for QueueDepthReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mean {:.1}, max {}",
+            self.mean_messages, self.max.messages
+        )?;
+        if let Some(capacity) = self.capacity.messages {
+            write!(f, " of {}", capacity)?;
+        }
+        write!(
+            f,
+            " messages, full in {:.1}% of {} samples",
+            self.full_percent, self.samples
+        )
+    }
+}
+
+/// Polls a probe at a fixed interval on a background thread.
+///
+/// Dropping the sampler without [`finish`](Self::finish), as when a test
+/// fails, stops the thread and discards the samples.
+pub struct QueueDepthSampler {
+    stop: Arc<AtomicBool>,
+    capacity: QueueCapacity,
+    handle: Option<JoinHandle<Vec<QueueDepthSample>>>,
+}
+
+impl QueueDepthSampler {
+    /// Start sampling `probe` every `interval`, if both are given.
+    pub fn start(
+        probe: Option<Box<dyn QueueDepthProbe>>,
+        interval: Option<Duration>,
+    ) -> Option<Self> {
+        let (mut probe, interval) = (probe?, interval?);
+        let stop = Arc::new(AtomicBool::new(false));
+        let capacity = probe.capacity();
+        let stop_flag = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            let mut series = Vec::new();
+            let start_ns = get_monotonic_time_ns();
+            while !stop_flag.load(Ordering::Relaxed) {
+                let Some(depth) = probe.depth() else {
+                    break;
+                };
+                series.push(QueueDepthSample {
+                    timestamp_ns: current_timestamp_ns(),
+                    depth,
+                });
+                // Keep to the schedule rather than drifting by the read time
+                let next_ns = start_ns + interval.as_nanos() as u64 * series.len() as u64;
+                let now_ns = get_monotonic_time_ns();
+                std::thread::park_timeout(Duration::from_nanos(next_ns.saturating_sub(now_ns)));
+            }
+            series
+        });
+        Some(Self {
+            stop,
+            capacity,
+            handle: Some(handle),
+        })
+    }
+
+    /// Stop sampling and summarize the samples taken.
+    pub fn finish(mut self) -> Option<QueueDepthReport> {
+        let handle = self.handle.take()?;
+        self.stop.store(true, Ordering::Relaxed);
+        handle.thread().unpark();
+        let series = handle.join().unwrap_or_default();
+        QueueDepthReport::from_series(series, self.capacity)
+    }
+}
+
+impl Drop for QueueDepthSampler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = &self.handle {
+            handle.thread().unpark();
+        }
+    }
+}
+
+/// Probe of a POSIX message queue through a descriptor of its own.
+#[cfg(all(target_os = "linux", feature = "pmq"))]
+pub(crate) struct MessageQueueProbe {
+    fd: Option<nix::mqueue::MqdT>,
+}
+
+#[cfg(all(target_os = "linux", feature = "pmq"))]
+impl MessageQueueProbe {
+    /// Open the queue `name` for reading its attributes only.
+    pub(crate) fn open(name: &str) -> Option<Self> {
+        use nix::mqueue::{mq_open, MQ_OFlag};
+        use nix::sys::stat::Mode;
+        let flags = MQ_OFlag::O_RDONLY | MQ_OFlag::O_NONBLOCK;
+        match mq_open(name, flags, Mode::empty(), None) {
+            Ok(fd) => Some(Self { fd: Some(fd) }),
+            Err(e) => {
+                tracing::debug!("Cannot sample depth of message queue {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    fn attr(&self) -> Option<nix::mqueue::MqAttr> {
+        nix::mqueue::mq_getattr(self.fd.as_ref()?).ok()
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "pmq"))]
+impl QueueDepthProbe for MessageQueueProbe {
+    fn depth(&mut self) -> Option<QueueDepth> {
+        Some(QueueDepth {
+            messages: self.attr()?.curmsgs() as usize,
+            bytes: None,
+        })
+    }
+
+    fn capacity(&self) -> QueueCapacity {
+        QueueCapacity {
+            messages: self.attr().map(|attr| attr.maxmsg() as usize),
+            bytes: None,
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "pmq"))]
+impl Drop for MessageQueueProbe {
+    fn drop(&mut self) {
+        if let Some(fd) = self.fd.take() {
+            let _ = nix::mqueue::mq_close(fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(messages: usize, bytes: Option<usize>) -> QueueDepthSample {
+        QueueDepthSample {
+            timestamp_ns: 0,
+            depth: QueueDepth { messages, bytes },
+        }
+    }
+
+    #[test]
+    fn test_capacity_is_full() {
+        let by_count = QueueCapacity {
+            messages: Some(10),
+            bytes: None,
+        };
+        assert!(by_count.is_full(QueueDepth {
+            messages: 10,
+            bytes: None
+        }));
+        assert!(!by_count.is_full(QueueDepth {
+            messages: 9,
+            bytes: None
+        }));
+
+        // A byte-bounded ring is full when another record would not fit
+        let by_bytes = QueueCapacity {
+            messages: None,
+            bytes: Some(1000),
+        };
+        assert!(by_bytes.is_full(QueueDepth {
+            messages: 9,
+            bytes: Some(950)
+        }));
+        assert!(!by_bytes.is_full(QueueDepth {
+            messages: 5,
+            bytes: Some(500)
+        }));
+        assert!(!by_bytes.is_full(QueueDepth::default()));
+    }
+
+    #[test]
+    fn test_report_from_series() {
+        let capacity = QueueCapacity {
+            messages: Some(4),
+            bytes: None,
+        };
+        assert!(QueueDepthReport::from_series(Vec::new(), capacity).is_none());
+
+        let series = vec![
+            sample(0, None),
+            sample(4, None),
+            sample(4, None),
+            sample(2, None),
+        ];
+        let report = QueueDepthReport::from_series(series, capacity).unwrap();
+        assert_eq!(report.samples, 4);
+        assert_eq!(report.mean_messages, 2.5);
+        assert_eq!(report.max.messages, 4);
+        assert_eq!(report.full_percent, 50.0);
+        assert_eq!(
+            report.to_string(),
+            "mean 2.5, max 4 of 4 messages, full in 50.0% of 4 samples"
+        );
+        // The series is left out of the final results
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json.get("series").is_none());
+    }
+
+    struct CountingProbe(usize);
+
+    impl QueueDepthProbe for CountingProbe {
+        fn depth(&mut self) -> Option<QueueDepth> {
+            self.0 += 1;
+            Some(QueueDepth {
+                messages: self.0,
+                bytes: None,
+            })
+        }
+
+        fn capacity(&self) -> QueueCapacity {
+            QueueCapacity::default()
+        }
+    }
+
+    #[test]
+    fn test_sampler_polls_until_finished() {
+        assert!(QueueDepthSampler::start(None, Some(Duration::from_millis(1))).is_none());
+        assert!(QueueDepthSampler::start(Some(Box::new(CountingProbe(0))), None).is_none());
+
+        let sampler = QueueDepthSampler::start(
+            Some(Box::new(CountingProbe(0))),
+            Some(Duration::from_millis(1)),
+        )
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+        let report = sampler.finish().unwrap();
+        assert!(report.samples >= 2);
+        assert_eq!(report.series.len(), report.samples);
+        assert_eq!(report.max.messages, report.samples);
+    }
+}
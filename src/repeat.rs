@@ -0,0 +1,302 @@
+//! Periodic re-runs of the configured suite.
+//!
+//! `--repeat-every 1h --until 24h` turns one invocation into a schedule: the
+//! suite is run as a child process at the start of each interval, and the
+//! final results of every repetition are appended, with its start and end
+//! times, as one JSON line to the `--output-file`. Comparing the lines shows
+//! time-of-day and thermal effects on a lab machine without cron plumbing.
+//!
+//! Slots are aligned to the start of the schedule. A repetition that runs
+//! past its interval delays the next one to the following slot rather than
+//! starting it straight away. The first SIGINT/SIGTERM is passed on to the
+//! running repetition, which finishes with partial results, and ends the
+//! schedule.
+
+use crate::cli::Args;
+use crate::shutdown;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+/// One line of the repetition history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Repetition {
+    /// Repetition number, starting at 1
+    pub repetition: usize,
+
+    /// When the repetition started
+    pub started: DateTime<Utc>,
+
+    /// When the repetition finished
+    pub finished: DateTime<Utc>,
+
+    /// Exit code of the repetition, if it exited normally
+    pub exit_code: Option<i32>,
+
+    /// Final results document the repetition wrote, if any
+    pub results: Option<serde_json::Value>,
+}
+
+/// Run the suite every `--repeat-every` until `--until` has elapsed or the
+/// run is interrupted.
+pub fn run(args: &Args) -> Result<()> {
+    let interval = args
+        .repeat_every
+        .context("repeated runs require --repeat-every")?;
+    if interval.is_zero() {
+        anyhow::bail!("--repeat-every must be greater than zero");
+    }
+    let exe = std::env::current_exe().context("Failed to locate the benchmark executable")?;
+    let suite = suite_args(args);
+
+    shutdown::install_handler("Interrupted, finishing the current repetition");
+
+    let schedule_start = Instant::now();
+    let mut repetitions = 0;
+    let mut failed = 0;
+    loop {
+        repetitions += 1;
+        eprintln!("Repetition {} starting", repetitions);
+        let record = run_once(&exe, &suite, repetitions, args.output_file.as_deref())?;
+        if record.exit_code != Some(0) {
+            failed += 1;
+        }
+        if let Some(path) = &args.output_file {
+            append(path, &record)?;
+        }
+        if shutdown::requested() {
+            break;
+        }
+
+        let next = next_slot(interval, schedule_start.elapsed());
+        if args.until.is_some_and(|until| next >= until) {
+            break;
+        }
+        eprintln!(
+            "Repetition {} finished; next in {:.0?}",
+            repetitions,
+            next.saturating_sub(schedule_start.elapsed())
+        );
+        if !sleep_until(schedule_start + next) {
+            break;
+        }
+    }
+
+    if shutdown::requested() {
+        anyhow::bail!("Interrupted after {} repetitions", repetitions);
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} repetitions failed", failed, repetitions);
+    }
+    Ok(())
+}
+
+/// Options the schedule owns, by field name: left out of each repetition
+/// along with `--config`, whose options are given again individually.
+const SCHEDULE_OPTIONS: [&str; 7] = [
+    "repeat_every",
+    "until",
+    "upload",
+    "upload_retries",
+    "upload_headers_file",
+    "output_file",
+    "config",
+];
+
+/// Command-line arguments for one repetition: the options given to `args`,
+/// from the command line or its `--config` file, without the scheduling,
+/// upload and output file options, which the schedule owns.
+pub(crate) fn suite_args(args: &Args) -> Vec<OsString> {
+    args.explicit_tokens
+        .iter()
+        .filter(|(id, _)| !SCHEDULE_OPTIONS.contains(&id.as_str()))
+        .flat_map(|(_, tokens)| tokens.iter().map(OsString::from))
+        .collect()
+}
+
+/// Offset from the start of the schedule of the first slot after `elapsed`.
+fn next_slot(interval: Duration, elapsed: Duration) -> Duration {
+    let slots = elapsed.as_nanos() / interval.as_nanos() + 1;
+    interval * slots as u32
+}
+
+/// Sleep until `deadline`, returning `false` if interrupted first.
+fn sleep_until(deadline: Instant) -> bool {
+    while !shutdown::requested() {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+    false
+}
+
+/// Run the suite once as a child process and collect its results.
+pub(crate) fn run_once(
+    exe: &Path,
+    suite: &[OsString],
+    repetition: usize,
+    output_file: Option<&Path>,
+) -> Result<Repetition> {
+    let partial: Option<PathBuf> = output_file.map(|path| path.with_extension("repetition.json"));
+    let mut cmd = Command::new(exe);
+    cmd.args(suite);
+    if let Some(partial) = &partial {
+        cmd.arg("--output-file").arg(partial);
+    }
+    // Own process group, so a terminal Ctrl+C reaches the repetition only
+    // once, through the forwarded signal
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let started = Utc::now();
+    let mut child = cmd
+        .spawn()
+        .context("Failed to start benchmark repetition")?;
+    let status = wait_forwarding_shutdown(&mut child)?;
+    let finished = Utc::now();
+
+    let results = match &partial {
+        Some(partial) if partial.exists() => {
+            let content = std::fs::read_to_string(partial)?;
+            std::fs::remove_file(partial)?;
+            Some(
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Repetition {} wrote invalid results", repetition))?,
+            )
+        }
+        _ => None,
+    };
+    Ok(Repetition {
+        repetition,
+        started,
+        finished,
+        exit_code: status.code(),
+        results,
+    })
+}
+
+/// Wait for a repetition, passing the first shutdown request on to it.
+pub(crate) fn wait_forwarding_shutdown(child: &mut std::process::Child) -> Result<ExitStatus> {
+    let mut forwarded = false;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if shutdown::requested() && !forwarded {
+            forwarded = true;
+            #[cfg(unix)]
+            unsafe {
+                libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+            }
+            #[cfg(not(unix))]
+            child.kill()?;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Append `record` as one JSON line to the history file.
+fn append(path: &Path, record: &Repetition) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_explicit_from(std::iter::once("ipc-benchmark").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    fn os(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_suite_args_drop_scheduling_options() {
+        let suite = suite_args(&parse(&[
+            "-m",
+            "uds",
+            "--repeat-every",
+            "1h",
+            "-i",
+            "100",
+            "--until=24h",
+            "-o",
+            "history.jsonl",
+            "--upload",
+            "s3://bucket/ci",
+        ]));
+        assert_eq!(suite, os(&["-m", "uds", "--msg-count", "100"]));
+
+        // The output file name is optional
+        let suite = suite_args(&parse(&["-o", "--blocking", "-w", "0"]));
+        assert_eq!(suite, os(&["--warmup-iterations", "0", "--blocking"]));
+    }
+
+    #[test]
+    fn test_suite_args_expand_config_without_scheduling_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("nightly.json");
+        std::fs::write(
+            &config,
+            r#"{"repeat-every": "1h", "until": "24h", "output-file": "history.jsonl", "msg-count": 100}"#,
+        )
+        .unwrap();
+
+        let args = parse(&["-m", "uds", "--config", config.to_str().unwrap()]);
+        assert_eq!(args.repeat_every, Some(Duration::from_secs(3600)));
+        let suite = suite_args(&args);
+        assert_eq!(suite, os(&["-m", "uds", "--msg-count", "100"]));
+    }
+
+    #[test]
+    fn test_next_slot() {
+        let hour = Duration::from_secs(3600);
+        assert_eq!(next_slot(hour, Duration::from_secs(600)), hour);
+        assert_eq!(next_slot(hour, Duration::ZERO), hour);
+        // An overrunning repetition skips to the following slot
+        assert_eq!(next_slot(hour, Duration::from_secs(3700)), hour * 2);
+        assert_eq!(next_slot(hour, hour), hour * 2);
+    }
+
+    #[test]
+    fn test_append_writes_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        for repetition in 1..=2 {
+            let record = Repetition {
+                repetition,
+                started: Utc::now(),
+                finished: Utc::now(),
+                exit_code: Some(0),
+                results: Some(serde_json::json!({"results": []})),
+            };
+            append(&path, &record).unwrap();
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Repetition> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].repetition, 2);
+    }
+}
@@ -0,0 +1,232 @@
+//! Command line that re-runs one test of a larger run.
+//!
+//! A run over `-m all` or a campaign matrix produces many tests, and a
+//! reader who finds one interesting cell in the results should not have to
+//! work out which of the run's flags applied to it. Each test's results
+//! carry a [`Reproduction`]: the options given on the original command
+//! line, narrowed to that test's mechanism and the CPUs it was pinned to,
+//! both as a command line and as a `--config` file.
+//!
+//! Options that own files or schedule the run (output files, streaming
+//! output, history, manifests, uploads, repetition and run IDs) are left
+//! out, so the reproduction does not overwrite the original run's files.
+//! `--parallel-mechanisms` is left out too: the test re-runs alone, pinned
+//! to the cores its group assigned it.
+
+#[cfg(feature = "cli")]
+use crate::cli::{self, Args};
+#[cfg(feature = "cli")]
+use crate::mechanism::IpcMechanism;
+#[cfg(feature = "cli")]
+use crate::utils::shell_quote;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Options that belong to the whole run rather than to any one test
+#[cfg(feature = "cli")]
+const RUN_OPTIONS: [&str; 21] = [
+    "mechanisms",
+    "config",
+    "repeat_every",
+    "until",
+    "parallel_mechanisms",
+    "client_affinity",
+    "server_affinity",
+    "output_file",
+    "dump_cli_schema",
+    "log_file",
+    "streaming_output_json",
+    "streaming_output_csv",
+    "run_id",
+    "machine_output",
+    "percentile_distribution",
+    "append_summary",
+    "manifest",
+    "resume",
+    "upload",
+    "upload_retries",
+    "upload_headers_file",
+];
+
+/// How to run one test again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reproduction {
+    /// Shell command line, ready to paste
+    pub command: String,
+
+    /// The same arguments, unquoted, as the daemon's `submit` method and
+    /// the conductor take them
+    pub args: Vec<String>,
+
+    /// The same options as a `--config` file, keyed by long option name
+    #[serde(default)]
+    pub config: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "cli")]
+impl Reproduction {
+    /// The command line that runs only `mechanism`, with the client and
+    /// server pinned as the test was, and otherwise the options of `args`.
+    pub fn of_test(
+        args: &Args,
+        mechanism: IpcMechanism,
+        client_affinity: Option<usize>,
+        server_affinity: Option<usize>,
+    ) -> Self {
+        let mut options = vec![(
+            "mechanisms".to_string(),
+            vec!["-m".to_string(), mechanism_name(mechanism)],
+        )];
+        if let Some(core) = client_affinity {
+            options.push((
+                "client_affinity".to_string(),
+                vec!["--client-affinity".to_string(), core.to_string()],
+            ));
+        }
+        if let Some(core) = server_affinity {
+            options.push((
+                "server_affinity".to_string(),
+                vec!["--server-affinity".to_string(), core.to_string()],
+            ));
+        }
+        options.extend(
+            args.explicit_tokens
+                .iter()
+                .filter(|(id, _)| !RUN_OPTIONS.contains(&id.as_str()))
+                .cloned(),
+        );
+        let tokens: Vec<String> = options
+            .iter()
+            .flat_map(|(_, option)| option.iter().cloned())
+            .collect();
+        let command = std::iter::once("ipc-benchmark".to_string())
+            .chain(tokens.iter().map(|token| shell_quote(token)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            command,
+            args: tokens,
+            config: cli::config_snippet(&options),
+        }
+    }
+}
+
+/// The name `-m` takes for `mechanism`.
+#[cfg(feature = "cli")]
+fn mechanism_name(mechanism: IpcMechanism) -> String {
+    mechanism.to_possible_value().map_or_else(
+        || mechanism.to_string(),
+        |value| value.get_name().to_string(),
+    )
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_explicit_from(std::iter::once("ipc-benchmark").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_narrows_to_one_test() {
+        let args = parse(&[
+            "-m",
+            "shm",
+            "tcp",
+            "-s",
+            "1024",
+            "--sla-thresholds",
+            "100us,1ms",
+            "-vv",
+            "--blocking",
+            "-o",
+            "run.json",
+            "--parallel-mechanisms",
+            "2",
+        ]);
+        let reproduction = Reproduction::of_test(&args, IpcMechanism::TcpSocket, Some(2), Some(3));
+        assert_eq!(
+            reproduction.command,
+            "ipc-benchmark -m tcp --client-affinity 2 --server-affinity 3 \
+             --message-size 1024 --verbose --verbose --sla-thresholds 100us,1ms --blocking"
+        );
+        assert_eq!(reproduction.args[..2], ["-m", "tcp"]);
+
+        // The arguments parse back to the same test
+        let again = parse(
+            &reproduction
+                .args
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(again.mechanisms, vec![IpcMechanism::TcpSocket]);
+        assert_eq!(again.message_size, 1024);
+        assert_eq!(again.sla_thresholds, args.sla_thresholds);
+        assert_eq!(again.verbose, 2);
+        assert!(again.blocking);
+        assert_eq!(again.output_file, None);
+
+        assert_eq!(
+            serde_json::Value::Object(reproduction.config.clone()),
+            serde_json::json!({
+                "mechanisms": "tcp",
+                "client-affinity": "2",
+                "server-affinity": "3",
+                "message-size": "1024",
+                "verbose": 2,
+                "sla-thresholds": "100us,1ms",
+                "blocking": true,
+            })
+        );
+
+        // The config file gives the same test, and the command line still wins
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        std::fs::write(&path, serde_json::to_string(&reproduction.config).unwrap()).unwrap();
+        let path = path.to_str().unwrap();
+        let from_config = parse(&["--config", path, "-s", "64"]);
+        assert_eq!(from_config.mechanisms, vec![IpcMechanism::TcpSocket]);
+        assert_eq!(from_config.client_affinity, Some(2));
+        assert_eq!(from_config.message_size, 64);
+        assert_eq!(from_config.sla_thresholds, args.sla_thresholds);
+        assert_eq!(from_config.verbose, 2);
+        assert!(from_config.blocking);
+        assert_eq!(
+            Reproduction::of_test(
+                &from_config,
+                IpcMechanism::TcpSocket,
+                from_config.client_affinity,
+                from_config.server_affinity
+            )
+            .args,
+            [
+                "-m",
+                "tcp",
+                "--client-affinity",
+                "2",
+                "--server-affinity",
+                "3",
+                "--message-size",
+                "64",
+                "--verbose",
+                "--verbose",
+                "--sla-thresholds",
+                "100us,1ms",
+                "--blocking"
+            ]
+        );
+
+        std::fs::write(dir.path().join("bad.json"), r#"{"no-such-option": 1}"#).unwrap();
+        let bad = dir.path().join("bad.json");
+        assert!(Args::try_parse_explicit_from([
+            "ipc-benchmark",
+            "--config",
+            bad.to_str().unwrap()
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Uniform samples of raw latencies kept in the results.
+//!
+//! The histogram behind each latency distribution answers percentile
+//! queries, but statistical tests that compare two runs, such as a
+//! Kolmogorov-Smirnov test or a bootstrap of the median, need raw
+//! samples. The streaming outputs carry every one of them, at a size that
+//! is awkward to keep for long campaigns. With `--latency-reservoir N`,
+//! each test keeps at most N latencies chosen uniformly at random from all
+//! it measured (reservoir sampling), and records them as
+//! `latency_reservoir` in its JSON metrics.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Raw latencies sampled uniformly from a test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyReservoir {
+    /// Most samples kept
+    pub capacity: usize,
+
+    /// Latencies measured, of which `samples_ns` is a uniform sample
+    pub seen: u64,
+
+    /// The sampled latencies in nanoseconds, in no particular order
+    pub samples_ns: Vec<u64>,
+}
+
+impl LatencyReservoir {
+    /// Combine the reservoirs of several workers into one of `capacity`
+    /// samples, drawing from each in proportion to the latencies it saw.
+    ///
+    /// `None` when there is nothing to combine.
+    pub fn merge(reservoirs: &[&LatencyReservoir], capacity: usize) -> Option<Self> {
+        let seen: u64 = reservoirs.iter().map(|r| r.seen).sum();
+        if reservoirs.is_empty() || seen == 0 {
+            return None;
+        }
+        let mut rng = StdRng::from_entropy();
+        let mut samples_ns = Vec::with_capacity(capacity);
+        for reservoir in reservoirs {
+            let share = (capacity as f64 * reservoir.seen as f64 / seen as f64).round() as usize;
+            let picked = rand::seq::index::sample(
+                &mut rng,
+                reservoir.samples_ns.len(),
+                share.min(reservoir.samples_ns.len()),
+            );
+            samples_ns.extend(picked.into_iter().map(|i| reservoir.samples_ns[i]));
+        }
+        samples_ns.truncate(capacity);
+        Some(Self {
+            capacity,
+            seen,
+            samples_ns,
+        })
+    }
+}
+
+/// Keeps a [`LatencyReservoir`] of the latencies recorded so far.
+#[derive(Debug, Clone)]
+pub struct ReservoirSampler {
+    reservoir: LatencyReservoir,
+    rng: StdRng,
+}
+
+impl ReservoirSampler {
+    /// Sampler keeping at most `capacity` latencies.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            reservoir: LatencyReservoir {
+                capacity,
+                seen: 0,
+                samples_ns: Vec::with_capacity(capacity),
+            },
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Offer one latency to the reservoir.
+    ///
+    /// The first `capacity` are kept; after that the n-th replaces a random
+    /// sample with probability `capacity / n`, which keeps every latency
+    /// seen equally likely to be in the reservoir.
+    pub fn record(&mut self, latency_ns: u64) {
+        let reservoir = &mut self.reservoir;
+        reservoir.seen += 1;
+        if reservoir.samples_ns.len() < reservoir.capacity {
+            reservoir.samples_ns.push(latency_ns);
+            return;
+        }
+        let slot = self.rng.gen_range(0..reservoir.seen);
+        if let Some(sample) = reservoir.samples_ns.get_mut(slot as usize) {
+            *sample = latency_ns;
+        }
+    }
+
+    /// The samples kept so far.
+    pub fn reservoir(&self) -> &LatencyReservoir {
+        &self.reservoir
+    }
+
+    /// Drop every sample, keeping the capacity.
+    pub fn reset(&mut self) {
+        self.reservoir.seen = 0;
+        self.reservoir.samples_ns.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampler_is_bounded_and_uniform() {
+        let mut sampler = ReservoirSampler::new(1000);
+        for latency in 0..100_000 {
+            sampler.record(latency);
+        }
+        let reservoir = sampler.reservoir();
+        assert_eq!(reservoir.seen, 100_000);
+        assert_eq!(reservoir.samples_ns.len(), 1000);
+        // A uniform sample of 0..100000 has its mean near 50000
+        let mean = reservoir.samples_ns.iter().sum::<u64>() as f64 / 1000.0;
+        assert!((40_000.0..60_000.0).contains(&mean), "mean {}", mean);
+
+        sampler.reset();
+        sampler.record(7);
+        assert_eq!(sampler.reservoir().samples_ns, [7]);
+        assert_eq!(sampler.reservoir().seen, 1);
+    }
+
+    #[test]
+    fn test_merge_draws_in_proportion() {
+        let busy = LatencyReservoir {
+            capacity: 100,
+            seen: 3000,
+            samples_ns: vec![1; 100],
+        };
+        let idle = LatencyReservoir {
+            capacity: 100,
+            seen: 1000,
+            samples_ns: vec![2; 100],
+        };
+        let merged = LatencyReservoir::merge(&[&busy, &idle], 100).unwrap();
+        assert_eq!(merged.seen, 4000);
+        assert_eq!(merged.samples_ns.len(), 100);
+        assert_eq!(merged.samples_ns.iter().filter(|&&s| s == 1).count(), 75);
+
+        // A worker with fewer samples than its share gives all it has
+        let small = LatencyReservoir {
+            capacity: 100,
+            seen: 10,
+            samples_ns: vec![3; 10],
+        };
+        let merged = LatencyReservoir::merge(&[&small], 100).unwrap();
+        assert_eq!(merged.samples_ns.len(), 10);
+        assert_eq!(LatencyReservoir::merge(&[], 100), None);
+    }
+}
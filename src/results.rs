@@ -24,14 +24,36 @@
 //! individual message latency measurements as they occur, while final output
 //! provides aggregated statistics and cross-mechanism comparisons.
 
-use crate::metrics::{LatencyMetrics, LatencyType, PerformanceMetrics};
-use crate::IpcMechanism;
+use crate::bootstrap::{self, PercentileInterval};
+use crate::cpu_frequency::FrequencyReport;
+use crate::criteria::{CriteriaPlan, CriteriaReport};
+use crate::csv_schema::{CsvColumn, CsvLayout};
+use crate::goodput::Goodput;
+use crate::history::SummaryHistory;
+use crate::host_info::{self, IpcLimits, Virtualization};
+use crate::ipc::TransportStats;
+use crate::json_output::JsonOutput;
+use crate::mechanism::{IpcMechanism, MechanismSupport};
+use crate::memory_info::MemoryInfo;
+use crate::metrics::utils::{format_bytes, format_latency, format_throughput, units};
+use crate::metrics::{
+    write_percentile_distribution, LatencyBreakdown, LatencyMetrics, LatencyType,
+    PerformanceMetrics,
+};
+use crate::options::{MachineOutput, ResponseMode};
+use crate::priority_inversion::InversionReport;
+use crate::reproduce::Reproduction;
+use crate::scheduler_noise::SchedulerNoise;
+use crate::serialization::SerializationCost;
+use crate::sla::SlaQuantile;
+use crate::system_load::SystemLoad;
+use crate::warmup::WarmupReport;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::{self, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::{debug, info};
 
@@ -46,12 +68,20 @@ use tracing::{debug, info};
 /// - **Success**: The benchmark completed without any critical errors.
 /// - **Failure**: The benchmark terminated due to an error. The associated
 ///   `String` contains a descriptive error message.
+/// - **Aborted**: The benchmark was interrupted by SIGINT or SIGTERM; its
+///   metrics cover only the messages exchanged before it stopped.
+/// - **Skipped**: The mechanism was part of `-m all` but cannot run on this
+///   host, so no test was attempted. The `String` says why.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BenchmarkStatus {
     /// The benchmark completed successfully.
     Success,
     /// The benchmark failed with the specified error message.
     Failure(String),
+    /// The benchmark was interrupted before it finished.
+    Aborted,
+    /// The mechanism cannot run on this host, for the specified reason.
+    Skipped(String),
 }
 
 /// Per-message latency record for streaming output
@@ -98,6 +128,9 @@ pub struct MessageLatencyRecord {
 
 impl MessageLatencyRecord {
     /// Column headings for columnar streaming output
+    ///
+    /// `run_id` is not a field of the record; the writers append this
+    /// process's [`crate::run_id`] to every row.
     pub const HEADINGS: &'static [&'static str] = &[
         "timestamp_ns",
         "message_id",
@@ -105,6 +138,7 @@ impl MessageLatencyRecord {
         "message_size",
         "one_way_latency_ns",
         "round_trip_latency_ns",
+        "run_id",
     ];
 
     /// Convert the record to a `serde_json::Value` array for columnar output
@@ -116,6 +150,7 @@ impl MessageLatencyRecord {
             serde_json::json!(self.message_size),
             serde_json::json!(self.one_way_latency_ns),
             serde_json::json!(self.round_trip_latency_ns),
+            serde_json::json!(crate::run_id::get()),
         ]
     }
 
@@ -142,6 +177,8 @@ impl MessageLatencyRecord {
         if let Some(latency) = self.round_trip_latency_ns {
             write!(&mut s, "{}", latency).unwrap();
         }
+        s.push(',');
+        s.push_str(crate::run_id::get());
         s
     }
 
@@ -169,6 +206,7 @@ impl MessageLatencyRecord {
         let (one_way_latency_ns, round_trip_latency_ns) = match latency_type {
             LatencyType::OneWay => (Some(latency_ns), None),
             LatencyType::RoundTrip => (None, Some(latency_ns)),
+            LatencyType::InterArrival => (None, None),
         };
 
         Self {
@@ -251,6 +289,128 @@ impl MessageLatencyRecord {
     }
 }
 
+/// One queue depth sample in the streaming output (`--queue-depth-interval`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueDepthRecord {
+    /// Unix timestamp (nanoseconds) of the sample
+    pub timestamp_ns: u64,
+
+    /// IPC mechanism being tested
+    pub mechanism: IpcMechanism,
+
+    /// Test the sample was taken in
+    pub latency_type: LatencyType,
+
+    /// Messages queued
+    pub queued_messages: usize,
+
+    /// Bytes queued, for queues that track them
+    pub queued_bytes: Option<usize>,
+}
+
+impl QueueDepthRecord {
+    /// Column headings of the `queue_depth` table in streaming output
+    ///
+    /// As for [`MessageLatencyRecord::HEADINGS`], the writers append the
+    /// run ID to every row.
+    pub const HEADINGS: &'static [&'static str] = &[
+        "timestamp_ns",
+        "mechanism",
+        "test",
+        "queued_messages",
+        "queued_bytes",
+        "run_id",
+    ];
+
+    /// Every sample in `results`, in test order.
+    pub fn from_results(results: &[BenchmarkResults]) -> Vec<Self> {
+        let mut records = Vec::new();
+        for result in results {
+            let tests = [
+                (LatencyType::OneWay, &result.one_way_results),
+                (LatencyType::RoundTrip, &result.round_trip_results),
+            ];
+            for (latency_type, metrics) in tests {
+                let Some(report) = metrics.as_ref().and_then(|m| m.queue_depth.as_ref()) else {
+                    continue;
+                };
+                records.extend(report.series.iter().map(|sample| Self {
+                    timestamp_ns: sample.timestamp_ns,
+                    mechanism: result.mechanism,
+                    latency_type,
+                    queued_messages: sample.depth.messages,
+                    queued_bytes: sample.depth.bytes,
+                }));
+            }
+        }
+        records
+    }
+
+    /// Convert the record to a `serde_json::Value` array for columnar output
+    pub fn to_value_array(&self) -> Vec<serde_json::Value> {
+        vec![
+            serde_json::json!(self.timestamp_ns),
+            serde_json::json!(self.mechanism),
+            serde_json::json!(self.latency_type),
+            serde_json::json!(self.queued_messages),
+            serde_json::json!(self.queued_bytes),
+            serde_json::json!(crate::run_id::get()),
+        ]
+    }
+
+    /// Convert the record to a CSV record string
+    pub fn to_csv_record(&self) -> String {
+        let bytes = self.queued_bytes.map(|b| b.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{:?},{},{},{}",
+            self.timestamp_ns,
+            self.mechanism,
+            self.latency_type,
+            self.queued_messages,
+            bytes,
+            crate::run_id::get()
+        )
+    }
+}
+
+/// The `"queue_depth"` table that closes a per-message streaming JSON
+/// file, as a member to follow `"data"`; empty without samples.
+pub(crate) fn queue_depth_json_member(results: &[BenchmarkResults]) -> Result<String> {
+    let records = QueueDepthRecord::from_results(results);
+    if records.is_empty() {
+        return Ok(String::new());
+    }
+    let rows = records
+        .iter()
+        .map(|record| serde_json::to_string(&record.to_value_array()))
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    Ok(format!(
+        ",\n  \"queue_depth\": {{\n    \"headings\": {},\n    \"data\": [\n      {}\n    ]\n  }}",
+        serde_json::to_string(QueueDepthRecord::HEADINGS)?,
+        rows.join(",\n      ")
+    ))
+}
+
+/// Write the queue depth samples in `results` to a CSV file beside the
+/// streaming CSV file `csv_path`, returning its path if there were any.
+pub(crate) fn write_queue_depth_csv(
+    csv_path: &Path,
+    results: &[BenchmarkResults],
+) -> Result<Option<PathBuf>> {
+    let records = QueueDepthRecord::from_results(results);
+    if records.is_empty() {
+        return Ok(None);
+    }
+    let path = csv_path.with_extension("queue_depth.csv");
+    let mut file = BufWriter::new(File::create(&path)?);
+    writeln!(file, "{}", QueueDepthRecord::HEADINGS.join(","))?;
+    for record in &records {
+        writeln!(file, "{}", record.to_csv_record())?;
+    }
+    file.flush()?;
+    Ok(Some(path))
+}
+
 /// Complete benchmark results for a specific IPC mechanism
 ///
 /// This structure encapsulates all performance data collected for a single
@@ -298,6 +458,561 @@ pub struct BenchmarkResults {
 
     /// System information for reproducibility
     pub system_info: SystemInfo,
+
+    /// Cold-cache controls applied to this test, if any were requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControlReport>,
+
+    /// CPU frequency before, during and after the test, where cpufreq is
+    /// available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_frequency: Option<FrequencyReport>,
+
+    /// How late a probe thread's timed sleeps woke up during the test,
+    /// with `--scheduler-noise-interval`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduler_noise: Option<SchedulerNoise>,
+
+    /// Per-message cost of bincode next to the fixed-layout records the
+    /// SHM ring carried, with `--zero-copy`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serialization: Option<SerializationCost>,
+
+    /// Command line that runs this test alone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reproduction: Option<Reproduction>,
+
+    /// CPU time the client thread spent in the round-trip loop compared with
+    /// the loop's wall time (blocking round-trip tests, where the platform
+    /// has a thread CPU clock)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cpu: Option<CpuUsage>,
+
+    /// Receiver-side latency to the first bytes of each one-way message
+    /// (present with `--first-byte-latency`); the one-way results measure
+    /// to the last byte
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_byte_latency: Option<LatencyMetrics>,
+
+    /// Round trips of the warmup messages, kept apart from the measured
+    /// results (present with `--capture-warmup`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup: Option<WarmupReport>,
+
+    /// Critical-path latency with and without low-priority bulk traffic
+    /// (present with `--priority-inversion`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_inversion: Option<InversionReport>,
+
+    /// Transient transport setup failures retried by the client and its
+    /// servers (`--setup-retries`)
+    #[serde(default)]
+    pub setup_retries: u32,
+
+    /// Transport-specific counters of the client transports, summed over
+    /// the measured tests; the server's are in its report
+    #[serde(default, skip_serializing_if = "TransportStats::is_empty")]
+    pub transport_stats: TransportStats,
+}
+
+/// Record of cold-cache controls applied during a benchmark
+///
+/// Cold-path results are only meaningful if it is known whether the page
+/// cache was actually dropped, so every attempt and its outcome is kept
+/// alongside the results rather than just the requested flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheControlReport {
+    /// Whether `--drop-caches` was requested
+    pub drop_caches: bool,
+
+    /// Whether transport resources were recreated for every test phase
+    pub fresh_resources: bool,
+
+    /// Number of attempts to drop the page cache
+    pub drop_caches_attempts: usize,
+
+    /// Number of attempts that succeeded
+    pub drop_caches_succeeded: usize,
+
+    /// Error from the most recent failed attempt
+    pub last_error: Option<String>,
+}
+
+impl CacheControlReport {
+    /// Record the outcome of one attempt to drop the page cache
+    pub fn record_drop_attempt(&mut self, outcome: &Result<()>) {
+        self.drop_caches_attempts += 1;
+        match outcome {
+            Ok(()) => self.drop_caches_succeeded += 1,
+            Err(e) => self.last_error = Some(e.to_string()),
+        }
+    }
+
+    /// True when every drop attempt succeeded (vacuously true if none were made)
+    pub fn all_drops_succeeded(&self) -> bool {
+        self.drop_caches_succeeded == self.drop_caches_attempts
+    }
+
+    /// Combine the reports of several results into one for run metadata
+    pub fn aggregate(results: &[BenchmarkResults]) -> Option<Self> {
+        results
+            .iter()
+            .filter_map(|r| r.cache_control.as_ref())
+            .fold(None, |acc: Option<Self>, report| {
+                let mut total = acc.unwrap_or_default();
+                total.drop_caches |= report.drop_caches;
+                total.fresh_resources |= report.fresh_resources;
+                total.drop_caches_attempts += report.drop_caches_attempts;
+                total.drop_caches_succeeded += report.drop_caches_succeeded;
+                if report.last_error.is_some() {
+                    total.last_error = report.last_error.clone();
+                }
+                Some(total)
+            })
+    }
+}
+
+/// CPU time consumed by one thread over a measured loop
+///
+/// A thread that blocks in the kernel while waiting for replies uses little
+/// CPU; one that spins on non-blocking receives uses a whole core. Reporting
+/// both times next to the latency makes that trade-off visible.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CpuUsage {
+    /// CPU time the thread was running, in nanoseconds
+    pub cpu_time_ns: u64,
+
+    /// Wall time of the loop, in nanoseconds
+    pub wall_time_ns: u64,
+
+    /// CPU time as a percentage of wall time (100% is one busy core)
+    pub utilization_percent: f64,
+}
+
+impl CpuUsage {
+    /// Usage from the thread CPU time and wall time of a loop
+    pub fn new(cpu_time_ns: u64, wall_time_ns: u64) -> Self {
+        let utilization_percent = if wall_time_ns > 0 {
+            cpu_time_ns as f64 / wall_time_ns as f64 * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            cpu_time_ns,
+            wall_time_ns,
+            utilization_percent,
+        }
+    }
+}
+
+/// Console summary line with the deadline-miss rate of each test, when the
+/// run used `--deadline`
+pub(crate) fn deadline_miss_summary(result: &BenchmarkResults) -> Option<String> {
+    let deadline = result.test_config.deadline?;
+    let tests: Vec<String> = [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(name, metrics)| {
+        let metrics = metrics.as_ref()?;
+        Some(format!(
+            "{} {} of {} ({:.2}%)",
+            name,
+            metrics.expired_count,
+            metrics.throughput.total_messages + metrics.expired_count,
+            metrics.deadline_miss_percent()
+        ))
+    })
+    .collect();
+    Some(format!(
+        "{}: {}",
+        format_latency(deadline.as_nanos() as u64),
+        tests.join(", ")
+    ))
+}
+
+/// Console summary of what the server sent back for each request, unless
+/// it was the default empty reply
+pub(crate) fn response_mode_line(result: &BenchmarkResults) -> Option<&'static str> {
+    match result.test_config.response_mode? {
+        ResponseMode::Echo => Some("echo (replies carry the request payload back)"),
+        ResponseMode::Ack => None,
+        ResponseMode::None => Some("none (the server sent no replies)"),
+    }
+}
+
+/// Console summary lines with the goodput of each test (`--goodput-budget`)
+pub(crate) fn goodput_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let goodput = metrics.as_ref()?.goodput.as_ref()?;
+        Some(format!("{}: {}", test, goodput))
+    })
+    .collect()
+}
+
+/// Console summary lines with the percentile of each latency threshold
+/// (`--sla-thresholds`)
+pub(crate) fn sla_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let sla = &metrics.as_ref()?.sla;
+        (!sla.is_empty()).then(|| {
+            let quantiles: Vec<String> = sla.iter().map(ToString::to_string).collect();
+            format!("{}: {}", test, quantiles.join(", "))
+        })
+    })
+    .collect()
+}
+
+/// Short distribution of a latency breakdown: P50, P99 when calculated,
+/// max and sample count
+fn latency_breakdown(latency: &LatencyMetrics) -> String {
+    let p99 = latency
+        .percentiles
+        .iter()
+        .find(|p| (p.percentile - 99.0).abs() < f64::EPSILON)
+        .map_or(String::new(), |p| {
+            format!(", P99 {}", format_latency(p.value_ns))
+        });
+    format!(
+        "P50 {}{}, max {} ({} messages)",
+        format_latency(latency.median_ns as u64),
+        p99,
+        format_latency(latency.max_ns),
+        latency.total_samples
+    )
+}
+
+/// Console summary lines of each latency breakdown the run recorded, as
+/// `(heading, line)` pairs: by message priority (`--priorities`), by
+/// round-trip phase when replies carried server timestamps, by warm or cold
+/// connection (`--compare-pooling`), by processing stage with a client hook,
+/// and by on-CPU and off-CPU time (`--cpu-split`)
+///
+/// CPU splits of one-way tests cover the client's send calls; round trips
+/// split the whole request and reply.
+pub(crate) fn latency_breakdown_lines(result: &BenchmarkResults) -> Vec<(&'static str, String)> {
+    let one_way = result.one_way_results.as_ref();
+    let round_trip = result.round_trip_results.as_ref();
+    let mut lines = Vec::new();
+    breakdown_lines(
+        &mut lines,
+        "Priority Latency",
+        [("one-way", one_way), ("round-trip", round_trip)],
+        |metrics| &metrics.priority_latency,
+    );
+    breakdown_lines(
+        &mut lines,
+        "Round-Trip Phase",
+        [("", round_trip)],
+        |metrics| &metrics.phase_latency,
+    );
+    breakdown_lines(&mut lines, "Connection", [("", round_trip)], |metrics| {
+        &metrics.connection_latency
+    });
+    breakdown_lines(
+        &mut lines,
+        "Processing",
+        [("one-way", one_way), ("round-trip", round_trip)],
+        |metrics| &metrics.processing_latency,
+    );
+    breakdown_lines(
+        &mut lines,
+        "CPU Split",
+        [("one-way send", one_way), ("round-trip", round_trip)],
+        |metrics| &metrics.cpu_split,
+    );
+    lines
+}
+
+/// Append a line under `heading` for each entry of one breakdown of each
+/// test, prefixed with the test's name unless it is empty
+fn breakdown_lines<'a, K: std::fmt::Display, const N: usize>(
+    lines: &mut Vec<(&'static str, String)>,
+    heading: &'static str,
+    tests: [(&str, Option<&'a PerformanceMetrics>); N],
+    breakdown: fn(&PerformanceMetrics) -> &Vec<LatencyBreakdown<K>>,
+) {
+    for (name, metrics) in tests {
+        let Some(metrics) = metrics else {
+            continue;
+        };
+        for entry in breakdown(metrics) {
+            let prefix = if name.is_empty() {
+                String::new()
+            } else {
+                format!("{} ", name)
+            };
+            let line = format!(
+                "{}{}: {}",
+                prefix,
+                entry.key,
+                latency_breakdown(&entry.latency)
+            );
+            lines.push((heading, line));
+        }
+    }
+}
+
+/// Console summary lines of the warmup round trips and the median of each
+/// segment of the warmup, when the run used `--capture-warmup`
+pub(crate) fn warmup_lines(result: &BenchmarkResults) -> Vec<String> {
+    let Some(warmup) = &result.warmup else {
+        return Vec::new();
+    };
+    vec![
+        format!("round-trip: {}", latency_breakdown(&warmup.latency)),
+        format!(
+            "median by segment: {}",
+            warmup
+                .segments
+                .iter()
+                .map(|segment| format_latency(segment.median_ns))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        ),
+    ]
+}
+
+/// Console summary lines with the latency of each direction and their
+/// asymmetry, when the run used `--pair`
+pub(crate) fn pair_lines(result: &BenchmarkResults) -> Vec<String> {
+    let Some(metrics) = &result.one_way_results else {
+        return Vec::new();
+    };
+    let Some(report) = &metrics.pair else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    if let Some(latency) = &metrics.latency {
+        lines.push(format!("client->server: {}", latency_breakdown(latency)));
+    }
+    lines.push(format!(
+        "server->client: {}",
+        latency_breakdown(&report.server_to_client)
+    ));
+    if let Some(asymmetry) = &report.asymmetry {
+        lines.push(format!("server->client vs client->server: {}", asymmetry));
+    }
+    lines
+}
+
+/// Console summary lines describing queue depth, when the run used
+/// `--queue-depth-interval`
+pub(crate) fn queue_depth_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let report = metrics.as_ref()?.queue_depth.as_ref()?;
+        Some(format!("{}: {}", test, report))
+    })
+    .collect()
+}
+
+/// Console summary lines comparing requested and achieved send intervals,
+/// when the run paced its sends with `--send-delay`
+pub(crate) fn pacing_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let report = metrics.as_ref()?.pacing.as_ref()?;
+        Some(format!("{}: {}", test, report))
+    })
+    .collect()
+}
+
+/// Console summary lines saying whether pinned sides stayed on their
+/// cores, when the run used `--client-affinity` or `--server-affinity`
+pub(crate) fn affinity_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .flat_map(|(test, metrics)| {
+        metrics
+            .iter()
+            .flat_map(|metrics| &metrics.affinity)
+            .map(move |check| format!("{}: {}", test, check))
+    })
+    .collect()
+}
+
+/// Console summary lines with each producer's delivery and their fairness,
+/// when the run used `--producers`
+pub(crate) fn producer_lines(result: &BenchmarkResults) -> Vec<String> {
+    let Some(report) = result
+        .one_way_results
+        .as_ref()
+        .and_then(|metrics| metrics.producers.as_ref())
+    else {
+        return Vec::new();
+    };
+    report
+        .producers
+        .iter()
+        .map(|producer| {
+            format!(
+                "{} at priority {}: {:.0} msg/s, {}",
+                producer.producer,
+                producer.priority,
+                producer.messages_per_second,
+                latency_breakdown(&producer.latency)
+            )
+        })
+        .chain(std::iter::once(format!(
+            "fairness {:.3} (Jain's index over {} producers)",
+            report.fairness_index,
+            report.producers.len()
+        )))
+        .collect()
+}
+
+/// Console summary lines with each multicast subscriber's delivery and
+/// loss, when the run used `-m udp-multicast`
+pub(crate) fn subscriber_lines(result: &BenchmarkResults) -> Vec<String> {
+    let Some(report) = result
+        .one_way_results
+        .as_ref()
+        .and_then(|metrics| metrics.subscribers.as_ref())
+    else {
+        return Vec::new();
+    };
+    report
+        .subscribers
+        .iter()
+        .map(|subscriber| {
+            format!(
+                "{}: {} of {} received, {} lost ({:.2}%), {}",
+                subscriber.subscriber,
+                subscriber.received,
+                report.sent,
+                subscriber.lost,
+                subscriber.loss_percent,
+                latency_breakdown(&subscriber.latency)
+            )
+        })
+        .collect()
+}
+
+/// Console summary lines with what the spawned server reported about each
+/// test
+pub(crate) fn server_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let report = metrics.as_ref()?.server.as_ref()?;
+        let mut line = format!(
+            "{}: {} received, {} send errors",
+            test, report.received, report.send_errors
+        );
+        if let Some(cpu) = &report.cpu {
+            line.push_str(&format!(", CPU {}", cpu));
+        }
+        if let Some(latency) = &report.receive_latency {
+            line.push_str(&format!(", receive {}", latency_breakdown(latency)));
+        }
+        if let Some(gaps) = &report.inter_arrival {
+            // Coefficient of variation: 0 for evenly spaced arrivals, about
+            // 1 for Poisson arrivals, higher for bursts
+            let cv = if gaps.mean_ns > 0.0 {
+                gaps.std_dev_ns / gaps.mean_ns
+            } else {
+                0.0
+            };
+            line.push_str(&format!(
+                ", inter-arrival {} (CV {:.2})",
+                latency_breakdown(gaps),
+                cv
+            ));
+        }
+        if let Some(processing) = &report.processing {
+            line.push_str(&format!(", processing {}", latency_breakdown(processing)));
+        }
+        if report.torn_frames > 0 {
+            line.push_str(&format!(", {} torn frames dropped", report.torn_frames));
+        }
+        if let Some(sequence) = &report.sequence {
+            line.push_str(&format!(", {}", sequence));
+        }
+        if let Some(stats) = transport_stats_line(&report.transport_stats) {
+            line.push_str(&format!(", transport {}", stats));
+        }
+        Some(line)
+    })
+    .collect()
+}
+
+/// The nonzero counters of `stats`, or `None` when all are zero
+pub(crate) fn transport_stats_line(stats: &TransportStats) -> Option<String> {
+    let counters: Vec<String> = stats
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(name, count)| format!("{} {}", name, count))
+        .collect();
+    (!counters.is_empty()).then(|| counters.join(", "))
+}
+
+/// Console summary lines setting each test's first message against the
+/// rest (`--first-message both`)
+pub(crate) fn first_message_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let penalty = metrics.as_ref()?.first_message.as_ref()?;
+        Some(format!("{}: {}", test, penalty))
+    })
+    .collect()
+}
+
+/// Console summary lines with what makes each test's latency
+/// untrustworthy, if anything does
+pub(crate) fn confidence_lines(result: &BenchmarkResults) -> Vec<String> {
+    [
+        ("one-way", &result.one_way_results),
+        ("round-trip", &result.round_trip_results),
+    ]
+    .into_iter()
+    .filter_map(|(test, metrics)| {
+        let confidence = metrics.as_ref()?.latency.as_ref()?.confidence.as_ref()?;
+        let concerns = confidence.concerns();
+        (!concerns.is_empty()).then(|| format!("{}: {}", test, concerns.join("; ")))
+    })
+    .collect()
+}
+
+impl std::fmt::Display for CpuUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}% of one core ({:.2?} CPU over {:.2?})",
+            self.utilization_percent,
+            Duration::from_nanos(self.cpu_time_ns),
+            Duration::from_nanos(self.wall_time_ns)
+        )
+    }
 }
 
 /// Test configuration used for the benchmark
@@ -338,8 +1053,143 @@ pub struct TestConfiguration {
     /// Number of warmup iterations executed
     pub warmup_iterations: usize,
 
+    /// Time the warmup was run for, when timed rather than counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup_duration: Option<Duration>,
+
     /// Percentiles calculated for latency analysis
     pub percentiles: Vec<f64>,
+
+    /// Maximum messages the server drained per wakeup (None when not batching)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_batch: Option<usize>,
+
+    /// What `--profile` set for this test, if a profile was chosen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<crate::profile::ProfileSettings>,
+
+    /// Rate limit on the server's consumption in messages/sec (None when unpaced)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_rate: Option<u64>,
+
+    /// Simulated client processing per message (None: no client work)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_work: Option<Duration>,
+
+    /// Simulated server processing per message (None: no server work)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_work: Option<Duration>,
+
+    /// Compute kernel iterations the server ran per message (None: no
+    /// spin work)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_spin: Option<u64>,
+
+    /// Bits in each message ID (`--id-width`; None: 64-bit IDs that never
+    /// wrap)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_width: Option<u8>,
+
+    /// UDS peer credential checks performed (None when off or not UDS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uds_credentials: Option<crate::options::UdsCredentialMode>,
+
+    /// Idle period before each measured message (None for back-to-back sends)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_gap: Option<Duration>,
+
+    /// Deadline each message carried after its send (None: no deadline)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<Duration>,
+
+    /// Latency budget of the goodput reported for each test
+    /// (`--goodput-budget`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goodput_budget: Option<Duration>,
+
+    /// Latency thresholds whose percentile is reported for each test
+    /// (`--sla-thresholds`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sla_thresholds: Vec<Duration>,
+
+    /// Priorities measured messages were sent at, in turn (empty: all at 0)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priorities: Vec<u8>,
+
+    /// Client processes that sent into the queue (None for a single client)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producers: Option<u8>,
+
+    /// Multicast subscriber processes (None for a single subscriber)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscribers: Option<u16>,
+
+    /// Fixed delay between measured sends (None for back-to-back sends)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_delay: Option<Duration>,
+
+    /// Mechanisms measured at the same time as this one
+    /// (`--parallel-mechanisms`); their load may be reflected in the results
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub concurrent_with: Vec<IpcMechanism>,
+
+    /// The physical core the pinned client and server shared as SMT
+    /// siblings, if they did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smt_siblings: Option<crate::smt::SharedCore>,
+
+    /// TCP keepalive idle time (None when keepalive is off or not TCP)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive: Option<Duration>,
+
+    /// Network interface TCP sockets were bound to (None when not set or not TCP)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_device: Option<String>,
+
+    /// Local address the TCP client connected from (None when not set or not TCP)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_addr: Option<std::net::IpAddr>,
+
+    /// SSH target the server was launched on (None when it ran locally)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_remote: Option<String>,
+
+    /// Payload bytes per segment (None when messages were sent whole)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_size: Option<usize>,
+
+    /// Segments each message was split into (None when messages were sent whole)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segments_per_message: Option<usize>,
+
+    /// System limits discovered for the mechanism (None when none could be read)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport_limits: Option<crate::ipc::limits::TransportLimits>,
+
+    /// Tokio runtime the test ran on (None in blocking mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<crate::runtime::RuntimeConfig>,
+
+    /// How the client waited for round-trip replies (None: blocking receive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spin_wait: Option<crate::options::SpinWait>,
+
+    /// How the SHM ring woke blocked peers (None: condition variables or not SHM)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_wait: Option<crate::options::ShmWait>,
+
+    /// hugetlbfs mount that backed the SHM ring (None: POSIX shared memory
+    /// or not SHM)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_hugetlbfs: Option<std::path::PathBuf>,
+
+    /// HTTP version of an HTTP transport test (None: not HTTP)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<crate::options::HttpVersion>,
+
+    /// What the server sent back for each request (None: not recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_mode: Option<crate::options::ResponseMode>,
 }
 
 /// Summary of benchmark results
@@ -374,6 +1224,14 @@ pub struct BenchmarkSummary {
     /// Peak throughput observed in any single test in megabytes per second (MB/s)
     pub peak_throughput_megabytes_per_sec: f64,
 
+    /// Average throughput across all tests in bytes per second
+    #[serde(default)]
+    pub average_throughput_bytes_per_sec: f64,
+
+    /// Peak throughput observed in any single test in bytes per second
+    #[serde(default)]
+    pub peak_throughput_bytes_per_sec: f64,
+
     /// Average latency across all latency measurements (if any)
     pub average_latency_ns: Option<f64>,
 
@@ -391,6 +1249,106 @@ pub struct BenchmarkSummary {
 
     /// Number of errors encountered during testing
     pub error_count: usize,
+
+    /// Little's Law self-consistency check of each test with latency
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub littles_law: Vec<LittlesLawCheck>,
+}
+
+/// Relative difference between the average number of messages in flight
+/// and the configured depth past which a test is flagged as inconsistent
+pub const LITTLES_LAW_TOLERANCE_PERCENT: f64 = 25.0;
+
+/// Little's Law (L = λ·W) applied to one test's measurements
+///
+/// Throughput times mean latency gives the average number of messages that
+/// were in flight. A closed-loop test keeps exactly its configured depth in
+/// flight, so a large difference means the throughput window or the latency
+/// timestamps did not cover the same work: setup time counted as test time,
+/// latency stamped at the wrong point, or replies matched to the wrong
+/// request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LittlesLawCheck {
+    /// Test the check applies to
+    pub latency_type: LatencyType,
+
+    /// Measured throughput (λ) in messages per second
+    pub throughput_msgs_per_sec: f64,
+
+    /// Mean latency (W) in nanoseconds
+    pub mean_latency_ns: f64,
+
+    /// Average messages in flight, L = λ·W
+    pub average_in_flight: f64,
+
+    /// In-flight depth the test was configured for (None when the sender
+    /// does not wait for each message, so the depth is not fixed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_in_flight: Option<usize>,
+
+    /// Difference between L and the expected depth, in percent of the
+    /// expected depth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discrepancy_percent: Option<f64>,
+
+    /// Whether the discrepancy is within [`LITTLES_LAW_TOLERANCE_PERCENT`]
+    pub consistent: bool,
+}
+
+impl LittlesLawCheck {
+    /// Check a test's metrics against the depth it was configured for.
+    /// Returns `None` when the test measured no latency or no throughput.
+    pub fn evaluate(
+        metrics: &PerformanceMetrics,
+        expected_in_flight: Option<usize>,
+    ) -> Option<Self> {
+        let latency = metrics.latency.as_ref()?;
+        let throughput = metrics.throughput.messages_per_second;
+        if throughput <= 0.0 || latency.total_samples == 0 {
+            return None;
+        }
+        let average_in_flight = throughput * latency.mean_ns / 1_000_000_000.0;
+        let discrepancy_percent = expected_in_flight
+            .filter(|&depth| depth > 0)
+            .map(|depth| (average_in_flight - depth as f64) / depth as f64 * 100.0);
+
+        Some(Self {
+            latency_type: latency.latency_type,
+            throughput_msgs_per_sec: throughput,
+            mean_latency_ns: latency.mean_ns,
+            average_in_flight,
+            expected_in_flight,
+            discrepancy_percent,
+            consistent: discrepancy_percent.map_or(true, |percent| {
+                percent.abs() <= LITTLES_LAW_TOLERANCE_PERCENT
+            }),
+        })
+    }
+}
+
+impl std::fmt::Display for LittlesLawCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.latency_type {
+            LatencyType::OneWay => "one-way",
+            LatencyType::RoundTrip => "round-trip",
+            LatencyType::InterArrival => "inter-arrival",
+        };
+        write!(
+            f,
+            "{} L = {:.2} in flight ({:.0} msg/s x {:.2?})",
+            name,
+            self.average_in_flight,
+            self.throughput_msgs_per_sec,
+            Duration::from_nanos(self.mean_latency_ns as u64)
+        )?;
+        if let (Some(depth), Some(percent)) = (self.expected_in_flight, self.discrepancy_percent) {
+            write!(f, ", expected {} ({:+.1}%)", depth, percent)?;
+        }
+        if !self.consistent {
+            write!(f, " [INCONSISTENT]")?;
+        }
+        Ok(())
+    }
 }
 
 /// System information for reproducibility
@@ -421,10 +1379,21 @@ pub struct SystemInfo {
     /// System architecture (e.g., "x86_64", "aarch64")
     pub architecture: String,
 
+    /// Kernel release (e.g., "6.8.0-45-generic")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel_version: Option<String>,
+
+    /// Distribution name from os-release (e.g., "Fedora Linux 40")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<String>,
+
     /// Number of CPU cores available
     pub cpu_cores: usize,
 
-    /// Available system memory in gigabytes
+    /// Memory available to the benchmark in gigabytes
+    ///
+    /// The host's physical memory, capped by the cgroup memory limit when
+    /// running in a container. 0 if it could not be detected.
     pub memory_gb: f64,
 
     /// Rust compiler version used to build the benchmark
@@ -432,6 +1401,38 @@ pub struct SystemInfo {
 
     /// Benchmark suite version
     pub benchmark_version: String,
+
+    /// Clock synchronization quality of this host
+    ///
+    /// Recorded by the standalone client, where server and client may run
+    /// on different hosts and their timestamps only compare as well as their
+    /// clocks agree. `None` for single-host runs or when no time daemon answered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_sync: Option<crate::clock_sync::ClockSync>,
+
+    /// Detailed memory figures: available memory, swap, huge pages and
+    /// cgroup limit. `None` on platforms where they can't be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryInfo>,
+
+    /// Kernel limits on message queues, shared memory and socket buffers
+    /// in effect for the run
+    #[serde(default)]
+    pub ipc_limits: IpcLimits,
+
+    /// Container runtime and hypervisor the benchmark ran under
+    #[serde(default)]
+    pub virtualization: Virtualization,
+
+    /// Load average, core utilization and competing processes sampled
+    /// before the run; `None` when not sampled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<SystemLoad>,
+
+    /// Mechanisms `-m all` runs on this build, and those it leaves out
+    /// with the reason
+    #[serde(default)]
+    pub mechanisms: MechanismSupport,
 }
 
 /// Results manager for handling output and streaming
@@ -484,6 +1485,9 @@ pub struct ResultsManager {
     /// Whether CSV streaming is enabled
     csv_streaming_enabled: bool,
 
+    /// Columns of the streaming CSV and the context of its rows
+    csv_layout: CsvLayout,
+
     /// Whether to stream per-message latency records instead of final results
     per_message_streaming: bool,
 
@@ -495,6 +1499,25 @@ pub struct ResultsManager {
 
     /// Buffer for collecting records when both tests are running (keyed by message ID)
     pending_records: HashMap<u64, MessageLatencyRecord>,
+
+    /// Format of results printed to stdout, if any
+    machine_output: Option<MachineOutput>,
+
+    /// Formatting and trimming of the final JSON results
+    json_output: JsonOutput,
+
+    /// Directory receiving per-test percentile distribution files, if any
+    percentile_distribution_dir: Option<std::path::PathBuf>,
+
+    /// Scenario bounds checked once all tests have run, if any
+    criteria: Option<CriteriaPlan>,
+
+    /// Summary CSV appended to once all tests have run, if any
+    summary_history: Option<SummaryHistory>,
+
+    /// How busy the machine was before the run, recorded in the system
+    /// information
+    system_load: Option<SystemLoad>,
 }
 
 impl ResultsManager {
@@ -526,13 +1549,68 @@ impl ResultsManager {
             results: Vec::new(),
             streaming_enabled: false,
             csv_streaming_enabled: false,
+            csv_layout: CsvLayout::default(),
             per_message_streaming: false,
             first_record_streamed: true,
             both_tests_enabled: false,
             pending_records: HashMap::new(),
+            machine_output: None,
+            json_output: JsonOutput::default(),
+            percentile_distribution_dir: None,
+            criteria: None,
+            summary_history: None,
+            system_load: None,
         })
     }
 
+    /// Print results to stdout as JSON (`--machine-output`).
+    ///
+    /// `Json` prints the final results document from `finalize()`; `Jsonl`
+    /// prints each mechanism's results on one line from `add_results()`.
+    pub fn set_machine_output(&mut self, format: Option<MachineOutput>) {
+        self.machine_output = format;
+    }
+
+    /// Format and trim the final JSON results (`--output-compact`,
+    /// `--output-fields`).
+    pub fn set_json_output(&mut self, output: JsonOutput) {
+        self.json_output = output;
+    }
+
+    /// Export each test's latency percentile distribution to `dir`
+    /// (`--percentile-distribution`), creating the directory if needed.
+    pub fn set_percentile_distribution_dir(&mut self, dir: Option<&Path>) -> Result<()> {
+        if let Some(dir) = dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        self.percentile_distribution_dir = dir.map(Path::to_path_buf);
+        Ok(())
+    }
+
+    /// Check the results against scenario bounds (`--criteria`) when the
+    /// run finishes.
+    pub fn set_criteria(&mut self, criteria: Option<CriteriaPlan>) {
+        self.criteria = criteria;
+    }
+
+    /// Append a summary row per mechanism to a long-lived CSV
+    /// (`--append-summary`) when the run finishes.
+    pub fn set_summary_history(&mut self, history: Option<SummaryHistory>) {
+        self.summary_history = history;
+    }
+
+    /// Record the pre-run system load check in the system information.
+    pub fn set_system_load(&mut self, load: Option<SystemLoad>) {
+        self.system_load = load;
+    }
+
+    /// Outcome of the `--criteria` checks over the results so far.
+    pub fn criteria_report(&self) -> Option<CriteriaReport> {
+        self.criteria
+            .as_ref()
+            .map(|criteria| criteria.evaluate(&self.results))
+    }
+
     /// Enable streaming results to a file
     ///
     /// Configures real-time result streaming to monitor benchmark progress
@@ -651,6 +1729,12 @@ impl ResultsManager {
         Ok(())
     }
 
+    /// Select the columns of the streaming CSV; call before
+    /// [`Self::enable_csv_streaming`].
+    pub fn set_csv_columns(&mut self, columns: Vec<CsvColumn>) {
+        self.csv_layout = CsvLayout::new(columns);
+    }
+
     /// Enable CSV latency streaming to a file
     ///
     /// Configures real-time per-message latency streaming in CSV format.
@@ -686,10 +1770,11 @@ impl ResultsManager {
                 .write(true)
                 .truncate(true)
                 .open(self.streaming_csv_file.as_ref().unwrap())?;
-            let header = MessageLatencyRecord::HEADINGS.join(",");
-            writeln!(file, "{}", header)?;
+            writeln!(file, "{}", self.csv_layout.header())?;
             file.flush()?;
         }
+        self.csv_layout
+            .write_schema(self.streaming_csv_file.as_ref().unwrap())?;
 
         let append_file = OpenOptions::new()
             .create(true)
@@ -793,7 +1878,7 @@ impl ResultsManager {
         // Stream to CSV if enabled
         if self.csv_streaming_enabled {
             if let Some(ref mut csv_writer) = self.streaming_csv_handle {
-                let csv_record = record.to_csv_record();
+                let csv_record = self.csv_layout.row(record);
                 csv_writer.write_all(csv_record.as_bytes())?;
                 csv_writer.write_all(b"\n")?;
                 csv_writer.flush()?;
@@ -803,7 +1888,7 @@ impl ResultsManager {
                     .append(true)
                     .open(streaming_csv_file)?;
                 let mut buf = std::io::BufWriter::new(f);
-                let csv_record = record.to_csv_record();
+                let csv_record = self.csv_layout.row(record);
                 buf.write_all(csv_record.as_bytes())?;
                 buf.write_all(b"\n")?;
                 buf.flush()?;
@@ -880,6 +1965,18 @@ impl ResultsManager {
             self.stream_results(&results).await?;
         }
 
+        if let Some(dir) = &self.percentile_distribution_dir {
+            for path in write_percentile_distributions(dir, &results)? {
+                info!("Wrote percentile distribution to: {:?}", path);
+            }
+        }
+
+        if self.machine_output == Some(MachineOutput::Jsonl) {
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", self.json_output.render_line(&results)?)?;
+            stdout.flush()?;
+        }
+
         self.results.push(results);
         Ok(())
     }
@@ -961,6 +2058,18 @@ impl ResultsManager {
             self.write_final_results(output_file)?;
         }
 
+        if let Some(history) = &self.summary_history {
+            let rows = history.append(&self.results)?;
+            info!("Appended {} summary rows to the summary history", rows);
+        }
+
+        if self.machine_output == Some(MachineOutput::Json) {
+            let json = self.json_output.render(&self.final_results())?;
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", json)?;
+            stdout.flush()?;
+        }
+
         Ok(())
     }
 
@@ -972,14 +2081,16 @@ impl ResultsManager {
     async fn close_streaming_json(&mut self) -> Result<()> {
         if self.per_message_streaming {
             info!("Closing streaming JSON file.");
+            // Queue depth samples follow the data as a table of their own
+            let closing = format!("\n  ]{}\n}}\n", queue_depth_json_member(&self.results)?);
             // Prefer buffered handle if present
             if let Some(mut writer) = self.streaming_file_handle.take() {
-                writer.write_all(b"\n  ]\n}\n")?;
+                writer.write_all(closing.as_bytes())?;
                 writer.flush()?;
             } else if let Some(streaming_file) = &self.streaming_file {
                 let mut file = OpenOptions::new().append(true).open(streaming_file)?;
                 // Write the closing brackets for the JSON data array and the root object.
-                write!(file, "\n  ]\n}}\n")?;
+                file.write_all(closing.as_bytes())?;
                 file.flush()?;
             }
         } else if self.streaming_enabled {
@@ -1012,6 +2123,9 @@ impl ResultsManager {
                 if let Some(mut csv_writer) = self.streaming_csv_handle.take() {
                     csv_writer.flush()?;
                 }
+                if let Some(queue_path) = write_queue_depth_csv(path, &self.results)? {
+                    info!("Wrote queue depth samples to {}", queue_path.display());
+                }
             }
         }
         Ok(())
@@ -1048,6 +2162,23 @@ impl ResultsManager {
         Ok(())
     }
 
+    /// Assemble the final results document with metadata and summary.
+    fn final_results(&self) -> FinalBenchmarkResults {
+        FinalBenchmarkResults {
+            metadata: BenchmarkMetadata {
+                version: crate::VERSION.to_string(),
+                run_id: crate::run_id::get().to_string(),
+                timestamp: chrono::Utc::now(),
+                total_tests: self.results.len(),
+                system_info: self.get_system_info(),
+                cache_control: CacheControlReport::aggregate(&self.results),
+            },
+            results: self.results.clone(),
+            summary: self.calculate_overall_summary(),
+            criteria: self.criteria_report(),
+        }
+    }
+
     /// Write final consolidated results
     ///
     /// Generates the complete final output including all individual results,
@@ -1071,19 +2202,8 @@ impl ResultsManager {
     fn write_final_results(&self, output_file: &Path) -> Result<()> {
         info!("Writing final results to: {:?}", output_file);
 
-        let final_results = FinalBenchmarkResults {
-            metadata: BenchmarkMetadata {
-                version: crate::VERSION.to_string(),
-                timestamp: chrono::Utc::now(),
-                total_tests: self.results.len(),
-                system_info: self.get_system_info(),
-            },
-            results: self.results.clone(),
-            summary: self.calculate_overall_summary(),
-        };
-
-        // Serialize pretty JSON
-        let json = serde_json::to_string_pretty(&final_results)?;
+        // Serialize as --output-compact and --output-fields ask
+        let json = self.json_output.render(&self.final_results())?;
 
         // Atomic write: write to a temp file next to the target, then rename.
         // Use a ".partial" extension to avoid clobbering the destination on failure.
@@ -1136,6 +2256,9 @@ impl ResultsManager {
                     average_throughput_megabytes_per_sec: result
                         .summary
                         .average_throughput_megabytes_per_sec,
+                    average_throughput_bytes_per_sec: result
+                        .summary
+                        .average_throughput_bytes_per_sec,
                     p95_latency_ns: result.summary.p95_latency_ns,
                     p99_latency_ns: result.summary.p99_latency_ns,
                     total_messages: result.summary.total_messages_sent,
@@ -1221,38 +2344,27 @@ impl ResultsManager {
     /// - **Platform Info**: From standard library constants
     /// - **Hardware Info**: From system APIs and detection functions
     /// - **Software Info**: From build-time and runtime version detection
-    /// - **Memory Info**: From system information utilities
+    /// - **Memory Info**: From `/proc/meminfo` and cgroup limits (see [`MemoryInfo`])
     fn get_system_info(&self) -> SystemInfo {
+        let memory = MemoryInfo::query();
         SystemInfo {
             os: std::env::consts::OS.to_string(),
             architecture: std::env::consts::ARCH.to_string(),
+            kernel_version: host_info::kernel_version(),
+            distribution: host_info::distribution(),
             cpu_cores: num_cpus::get(),
-            memory_gb: Self::get_memory_gb(),
+            memory_gb: memory.as_ref().map_or(0.0, MemoryInfo::effective_gb),
             rust_version: Self::get_rust_version(),
             benchmark_version: crate::VERSION.to_string(),
+            clock_sync: None,
+            memory,
+            ipc_limits: IpcLimits::query(),
+            virtualization: Virtualization::detect(),
+            load: self.system_load.clone(),
+            mechanisms: MechanismSupport::of_this_build(),
         }
     }
 
-    /// Get available memory in GB
-    ///
-    /// Estimates available system memory for resource correlation.
-    /// This is a simplified implementation that provides reasonable
-    /// estimates for most systems.
-    ///
-    /// ## Returns
-    /// Estimated available memory in gigabytes
-    ///
-    /// ## Implementation Note
-    ///
-    /// This is a placeholder implementation that returns reasonable
-    /// defaults. A production implementation would use platform-specific
-    /// system information APIs to get accurate memory information.
-    fn get_memory_gb() -> f64 {
-        // This is a simplified implementation
-        // In a real implementation, you'd want to use a system info crate
-        16.0 // Default assumption
-    }
-
     /// Get Rust version
     ///
     /// Retrieves the Rust compiler version used to build the benchmark.
@@ -1296,6 +2408,9 @@ impl ResultsManager {
         if let Some(path) = &self.streaming_csv_file {
             println!("    Streaming CSV:        {}", path.display());
         }
+        if let Some(path) = &self.percentile_distribution_dir {
+            println!("    Percentiles (hgrm):   {}", path.display());
+        }
         if let Some(path) = &self.log_file {
             println!("    Log File:             {}", path);
         }
@@ -1308,6 +2423,121 @@ impl ResultsManager {
                 println!("Mechanism: {}", result.mechanism);
                 println!("  Message Size: {} bytes", result.test_config.message_size);
                 println!("  Buffer Size:  {} bytes", result.test_config.buffer_size);
+                if let Some(mode) = response_mode_line(result) {
+                    println!("  Responses:    {}", mode);
+                }
+                if let Some(profile) = &result.test_config.profile {
+                    println!("  Profile:      {}", profile);
+                }
+                if !result.test_config.concurrent_with.is_empty() {
+                    let others: Vec<String> = result
+                        .test_config
+                        .concurrent_with
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect();
+                    println!(
+                        "  Warning: measured concurrently with {}",
+                        others.join(", ")
+                    );
+                }
+                if let Some(shared) = &result.test_config.smt_siblings {
+                    println!("  SMT Siblings: {}", shared);
+                }
+                if let (Some(count), Some(size)) = (
+                    result.test_config.segments_per_message,
+                    result.test_config.segment_size,
+                ) {
+                    println!(
+                        "  Segments:     {} per message ({} bytes each)",
+                        count, size
+                    );
+                }
+                if let Some(report) = &result.cpu_frequency {
+                    println!("  CPU Frequency: {}", report);
+                }
+                if let Some(noise) = &result.scheduler_noise {
+                    println!("  Scheduler Noise: {}", noise);
+                }
+                if let Some(cost) = &result.serialization {
+                    println!("  Serialization: {}", cost);
+                }
+                if let Some(usage) = &result.client_cpu {
+                    let wait = result
+                        .test_config
+                        .spin_wait
+                        .map_or("blocking receive".to_string(), |mode| {
+                            format!("spin-wait: {}", mode)
+                        });
+                    println!("  Client CPU: {} ({})", usage, wait);
+                }
+                if let Some(misses) = deadline_miss_summary(result) {
+                    println!("  Deadline Misses: {}", misses);
+                }
+                for line in goodput_lines(result) {
+                    println!("  Goodput: {}", line);
+                }
+                for line in sla_lines(result) {
+                    println!("  SLA: {}", line);
+                }
+                for (heading, line) in latency_breakdown_lines(result) {
+                    println!("  {}: {}", heading, line);
+                }
+                for line in pair_lines(result) {
+                    println!("  Direction: {}", line);
+                }
+                for line in warmup_lines(result) {
+                    println!("  Warmup: {}", line);
+                }
+                for line in queue_depth_lines(result) {
+                    println!("  Queue Depth: {}", line);
+                }
+                for line in pacing_lines(result) {
+                    println!("  Pacing: {}", line);
+                }
+                if let Some(count) = result
+                    .one_way_results
+                    .as_ref()
+                    .and_then(|metrics| metrics.send_allocations)
+                {
+                    println!("  Send Allocations: {} after the first message", count);
+                }
+                for line in first_message_lines(result) {
+                    println!("  First-Message Penalty: {}", line);
+                }
+                for line in affinity_lines(result) {
+                    println!("  Affinity: {}", line);
+                }
+                if let Some(report) = &result.priority_inversion {
+                    println!("  Priority Inversion: {}", report);
+                }
+                for line in producer_lines(result) {
+                    println!("  Producer: {}", line);
+                }
+                for line in subscriber_lines(result) {
+                    println!("  Subscriber: {}", line);
+                }
+                for line in server_lines(result) {
+                    println!("  Server: {}", line);
+                }
+                for line in confidence_lines(result) {
+                    println!("  Confidence: {}", line);
+                }
+                if result.setup_retries > 0 {
+                    println!("  Setup Retries: {}", result.setup_retries);
+                }
+                if let Some(line) = transport_stats_line(&result.transport_stats) {
+                    println!("  Transport: {}", line);
+                }
+                for check in &result.summary.littles_law {
+                    println!("  Little's Law: {}", check);
+                }
+                if result.summary.error_count > 0 {
+                    println!(
+                        "  Errors: {} (replies that did not match their request)",
+                        result.summary.error_count
+                    );
+                }
 
                 match &result.status {
                     BenchmarkStatus::Success => {
@@ -1317,9 +2547,29 @@ impl ResultsManager {
                         println!("  Status: FAILED");
                         println!("    Error: {}", error_msg);
                     }
+                    BenchmarkStatus::Aborted => {
+                        println!("  Status: ABORTED (interrupted, partial results)");
+                        Self::print_summary_details(result, "  ");
+                    }
+                    BenchmarkStatus::Skipped(reason) => {
+                        println!("  Status: SKIPPED");
+                        println!("    Reason: {}", reason);
+                    }
                 }
                 println!("-----------------------------------------------------------------");
             }
+
+            if let Some(table) = format_comparison_table(&self.results) {
+                println!("Comparison:");
+                print!("{}", table);
+                println!("-----------------------------------------------------------------");
+            }
+
+            if let Some(report) = self.criteria_report() {
+                println!("Pass Criteria:");
+                print!("{}", report);
+                println!("-----------------------------------------------------------------");
+            }
         }
 
         io::stdout().flush()?;
@@ -1359,6 +2609,12 @@ impl ResultsManager {
             format_latency(latency.min_ns),
             format_latency(latency.max_ns)
         );
+        if let Some(line) = interval_line(latency) {
+            println!("{}{:<8} {}", indent, "  ", line);
+        }
+        for line in format_latency_histogram(latency, &format!("{}    ", indent)) {
+            println!("{}", line);
+        }
     }
 
     /// Helper function to format and print the details from a BenchmarkResults struct.
@@ -1373,6 +2629,10 @@ impl ResultsManager {
             }
         }
 
+        if let Some(latency) = &result.first_byte_latency {
+            Self::print_latency_details(latency, indent, "First-Byte Latency");
+        }
+
         if let Some(round_trip) = &result.round_trip_results {
             if let Some(latency) = &round_trip.latency {
                 Self::print_latency_details(latency, indent, "Round-Trip Latency");
@@ -1383,34 +2643,281 @@ impl ResultsManager {
 
         println!("{}Throughput:", indent);
         println!(
-            "{}{:<8} Average: {:.2} MB/s, Peak: {:.2} MB/s",
+            "{}{:<8} Average: {}, Peak: {}",
             indent,
             "  ",
-            summary.average_throughput_megabytes_per_sec,
-            summary.peak_throughput_megabytes_per_sec
+            format_throughput(summary.average_throughput_bytes_per_sec),
+            format_throughput(summary.peak_throughput_bytes_per_sec)
         );
 
         println!("{}Totals:", indent);
         println!(
-            "{}{:<8} Messages: {}, Data: {:.2} MB",
+            "{}{:<8} Messages: {}, Data: {}",
             indent,
             "  ",
             summary.total_messages_sent,
-            summary.total_bytes_transferred as f64 / (1024.0 * 1024.0)
+            format_bytes(summary.total_bytes_transferred as f64)
         );
     }
 }
 
-/// Helper function to format latency values (in nanoseconds) into a
-/// human-readable string (us, ms).
-fn format_latency(ns: u64) -> String {
-    if ns >= 1_000_000 {
-        format!("{:.2} ms", ns as f64 / 1_000_000.0)
-    } else if ns >= 1_000 {
-        format!("{:.2} us", ns as f64 / 1_000.0)
-    } else {
-        format!("{} ns", ns)
+/// Write the percentile distribution of each latency measurement in
+/// `result` to `dir`, returning the files written.
+///
+/// Files are named `<mechanism>_<size>B_<one_way|round_trip|first_byte>.hgrm`
+/// and hold values in microseconds.
+pub(crate) fn write_percentile_distributions(
+    dir: &Path,
+    result: &BenchmarkResults,
+) -> Result<Vec<PathBuf>> {
+    let mechanism = result.mechanism.name();
+    let latencies = [
+        (
+            "one_way",
+            result
+                .one_way_results
+                .as_ref()
+                .and_then(|r| r.latency.as_ref()),
+        ),
+        (
+            "round_trip",
+            result
+                .round_trip_results
+                .as_ref()
+                .and_then(|r| r.latency.as_ref()),
+        ),
+        ("first_byte", result.first_byte_latency.as_ref()),
+    ];
+
+    let mut written = Vec::new();
+    for (kind, latency) in latencies {
+        let Some(histogram) = latency
+            .and_then(|l| l.histogram.as_ref())
+            .filter(|h| !h.is_empty())
+        else {
+            continue;
+        };
+        let path = dir.join(format!(
+            "{}_{}B_{}.hgrm",
+            mechanism, result.test_config.message_size, kind
+        ));
+        let mut file = BufWriter::new(File::create(&path)?);
+        write_percentile_distribution(histogram, 1000.0, &mut file)?;
+        file.flush()?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Width of the longest bar in the terminal latency histogram, in characters
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Latency at the given percentile, if it was calculated
+pub(crate) fn percentile_ns(latency: &LatencyMetrics, percentile: f64) -> Option<u64> {
+    latency
+        .percentiles
+        .iter()
+        .find(|p| (p.percentile - percentile).abs() < 0.1)
+        .map(|p| p.value_ns)
+}
+
+/// Bootstrap confidence interval of the given percentile, if one was
+/// computed
+pub(crate) fn percentile_interval(
+    latency: &LatencyMetrics,
+    percentile: f64,
+) -> Option<&PercentileInterval> {
+    latency
+        .percentile_intervals
+        .iter()
+        .find(|i| (i.percentile - percentile).abs() < 0.1)
+}
+
+/// Console summary line with the confidence interval of each percentile,
+/// where raw latencies were kept to compute them
+pub(crate) fn interval_line(latency: &LatencyMetrics) -> Option<String> {
+    if latency.percentile_intervals.is_empty() {
+        return None;
+    }
+    let intervals: Vec<String> = latency
+        .percentile_intervals
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    Some(format!(
+        "{}% CI: {}",
+        bootstrap::CONFIDENCE_LEVEL,
+        intervals.join(", ")
+    ))
+}
+
+/// Render a side-by-side comparison of the successful results.
+///
+/// There is one row per mechanism and test type. "P50 vs best" is the
+/// row's median latency relative to the lowest median of the same test
+/// type, marked with `*` when the two medians' confidence intervals
+/// overlap, so the difference may be noise. Returns `None` when there are
+/// fewer than two rows to compare.
+pub(crate) fn format_comparison_table(results: &[BenchmarkResults]) -> Option<String> {
+    let mut rows: Vec<(&str, String, &PerformanceMetrics)> = Vec::new();
+    for result in results
+        .iter()
+        .filter(|r| r.status == BenchmarkStatus::Success)
+    {
+        for (test, metrics) in [
+            ("One-Way", &result.one_way_results),
+            ("Round-Trip", &result.round_trip_results),
+        ] {
+            if let Some(metrics) = metrics {
+                rows.push((test, result.mechanism.to_string(), metrics));
+            }
+        }
+    }
+    if rows.len() < 2 {
+        return None;
+    }
+
+    let mut best: HashMap<&str, &LatencyMetrics> = HashMap::new();
+    for (test, _, metrics) in &rows {
+        if let Some(latency) = &metrics.latency {
+            let best = best.entry(test).or_insert(latency);
+            if latency.median_ns < best.median_ns {
+                *best = latency;
+            }
+        }
+    }
+    let mut within_noise = false;
+
+    let units = units();
+    let optional = |ns: Option<u64>| ns.map(format_latency).unwrap_or_else(|| "N/A".to_string());
+    let mut table: Vec<[String; 8]> = vec![[
+        "Mechanism".to_string(),
+        "Test".to_string(),
+        "Msg/s".to_string(),
+        units.megabytes_per_second_label().to_string(),
+        "P50".to_string(),
+        "P95".to_string(),
+        "P99".to_string(),
+        "P50 vs best".to_string(),
+    ]];
+    for (test, mechanism, metrics) in &rows {
+        let latency = metrics.latency.as_ref();
+        let relative = latency
+            .and_then(|l| {
+                let best = best[test];
+                let overlaps = !std::ptr::eq(l, best)
+                    && percentile_interval(l, 50.0)
+                        .zip(percentile_interval(best, 50.0))
+                        .is_some_and(|(row, best)| row.overlaps(best));
+                within_noise |= overlaps;
+                (best.median_ns > 0.0).then(|| {
+                    let marker = if overlaps { "*" } else { "" };
+                    format!("{:.2}x{}", l.median_ns / best.median_ns, marker)
+                })
+            })
+            .unwrap_or_else(|| "N/A".to_string());
+        table.push([
+            mechanism.clone(),
+            test.to_string(),
+            format!("{:.0}", metrics.throughput.messages_per_second),
+            format!(
+                "{:.*}",
+                units.precision,
+                units.megabytes_per_second(metrics.throughput.bytes_per_second)
+            ),
+            optional(latency.map(|l| l.median_ns as u64)),
+            optional(latency.and_then(|l| percentile_ns(l, 95.0))),
+            optional(latency.and_then(|l| percentile_ns(l, 99.0))),
+            relative,
+        ]);
+    }
+
+    let mut widths = [0usize; 8];
+    for row in &table {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
     }
+
+    let mut out = String::new();
+    for (i, row) in table.iter().enumerate() {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                // Names are left-aligned, numbers right-aligned
+                if column < 2 {
+                    format!("{:<width$}", cell)
+                } else {
+                    format!("{:>width$}", cell)
+                }
+            })
+            .collect();
+        out.push_str("  ");
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+        if i == 0 {
+            let rule: usize = widths.iter().sum::<usize>() + 2 * (widths.len() - 1);
+            out.push_str(&format!("  {}\n", "-".repeat(rule)));
+        }
+    }
+    if within_noise {
+        out.push_str(&format!(
+            "  * P50 within the best's {}% confidence interval; the difference may be noise\n",
+            bootstrap::CONFIDENCE_LEVEL
+        ));
+    }
+    Some(out)
+}
+
+/// Render the latency distribution as one horizontal bar per bucket.
+///
+/// Bars are drawn with Unicode block characters, scaled so the fullest
+/// bucket is [`HISTOGRAM_WIDTH`] characters wide; any non-empty bucket gets
+/// at least a sliver. Returns no lines when the metrics carry no buckets.
+pub(crate) fn format_latency_histogram(latency: &LatencyMetrics, indent: &str) -> Vec<String> {
+    const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    let end = latency
+        .buckets
+        .iter()
+        .rposition(|b| b.count > 0)
+        .map_or(0, |i| i + 1);
+    let buckets = &latency.buckets[..end];
+    let Some(max) = buckets.iter().map(|b| b.count).max() else {
+        return Vec::new();
+    };
+    let total: u64 = buckets.iter().map(|b| b.count).sum();
+
+    let labels: Vec<String> = buckets
+        .iter()
+        .map(|b| format!("<= {}", format_latency(b.upper_bound_ns)))
+        .collect();
+    let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0);
+
+    buckets
+        .iter()
+        .zip(&labels)
+        .map(|(bucket, label)| {
+            let eighths = (bucket.count * HISTOGRAM_WIDTH as u64 * 8)
+                .div_euclid(max)
+                .max(u64::from(bucket.count > 0)) as usize;
+            let mut bar = "█".repeat(eighths / 8);
+            if eighths % 8 > 0 {
+                bar.push(PARTIAL[eighths % 8]);
+            }
+            format!(
+                "{}{:>label_width$} |{:<bar_width$} {} ({:.1}%)",
+                indent,
+                label,
+                bar,
+                bucket.count,
+                bucket.count as f64 * 100.0 / total as f64,
+                bar_width = HISTOGRAM_WIDTH,
+            )
+        })
+        .collect()
 }
 
 /// Final benchmark results
@@ -1439,6 +2946,10 @@ pub struct FinalBenchmarkResults {
 
     /// Cross-mechanism summary and analysis
     pub summary: OverallSummary,
+
+    /// Pass/fail outcome of the `--criteria` scenario bounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criteria: Option<CriteriaReport>,
 }
 
 /// Benchmark metadata
@@ -1457,6 +2968,10 @@ pub struct BenchmarkMetadata {
     /// Benchmark suite version
     pub version: String,
 
+    /// Identifier of the run, shared with its logs and streaming output
+    #[serde(default)]
+    pub run_id: String,
+
     /// When the benchmark suite was executed
     pub timestamp: chrono::DateTime<chrono::Utc>,
 
@@ -1465,6 +2980,10 @@ pub struct BenchmarkMetadata {
 
     /// System information for reproducibility
     pub system_info: SystemInfo,
+
+    /// Aggregated cold-cache controls across all tests, if any were requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControlReport>,
 }
 
 /// Overall summary across all mechanisms
@@ -1531,6 +3050,10 @@ pub struct MechanismSummary {
     /// Average throughput performance in megabytes per second (MB/s)
     pub average_throughput_megabytes_per_sec: f64,
 
+    /// Average throughput performance in bytes per second
+    #[serde(default)]
+    pub average_throughput_bytes_per_sec: f64,
+
     /// 95th percentile latency (if latency was measured)
     pub p95_latency_ns: Option<u64>,
 
@@ -1590,7 +3113,39 @@ impl BenchmarkResults {
             one_way_enabled: one_way,
             round_trip_enabled: round_trip,
             warmup_iterations,
+            warmup_duration: None,
             percentiles: vec![50.0, 95.0, 99.0, 99.9],
+            server_batch: None,
+            profile: None,
+            server_rate: None,
+            client_work: None,
+            server_work: None,
+            server_spin: None,
+            id_width: None,
+            uds_credentials: None,
+            idle_gap: None,
+            deadline: None,
+            goodput_budget: None,
+            sla_thresholds: Vec::new(),
+            priorities: Vec::new(),
+            producers: None,
+            subscribers: None,
+            send_delay: None,
+            concurrent_with: Vec::new(),
+            smt_siblings: None,
+            tcp_keepalive: None,
+            bind_device: None,
+            source_addr: None,
+            server_remote: None,
+            segment_size: None,
+            segments_per_message: None,
+            transport_limits: None,
+            runtime: None,
+            spin_wait: None,
+            shm_wait: None,
+            shm_hugetlbfs: None,
+            http_version: None,
+            response_mode: None,
         };
 
         Self {
@@ -1603,6 +3158,17 @@ impl BenchmarkResults {
             timestamp: chrono::Utc::now(),
             test_duration: Duration::ZERO,
             system_info: SystemInfo::default(),
+            cache_control: None,
+            cpu_frequency: None,
+            scheduler_noise: None,
+            serialization: None,
+            reproduction: None,
+            client_cpu: None,
+            first_byte_latency: None,
+            warmup: None,
+            priority_inversion: None,
+            setup_retries: 0,
+            transport_stats: TransportStats::new(),
         }
     }
 
@@ -1611,6 +3177,66 @@ impl BenchmarkResults {
         self.status = BenchmarkStatus::Failure(error_message);
     }
 
+    /// Mark the benchmark result as interrupted, holding partial metrics
+    pub fn set_aborted(&mut self) {
+        self.status = BenchmarkStatus::Aborted;
+    }
+
+    /// Mark the benchmark as not run because the mechanism is unavailable
+    pub fn set_skipped(&mut self, reason: String) {
+        self.status = BenchmarkStatus::Skipped(reason);
+    }
+
+    /// Report the goodput of each test under `budget`, if one is set.
+    pub fn annotate_goodput(&mut self, budget: Option<Duration>) {
+        let Some(budget) = budget else {
+            return;
+        };
+        self.test_config.goodput_budget = Some(budget);
+        for metrics in [&mut self.one_way_results, &mut self.round_trip_results]
+            .into_iter()
+            .flatten()
+        {
+            metrics.goodput = Goodput::measure(metrics, budget);
+        }
+    }
+
+    /// Report the percentile of each of `thresholds` in each test's
+    /// latency.
+    pub fn annotate_sla(&mut self, thresholds: &[Duration]) {
+        if thresholds.is_empty() {
+            return;
+        }
+        self.test_config.sla_thresholds = thresholds.to_vec();
+        for metrics in [&mut self.one_way_results, &mut self.round_trip_results]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(latency) = &metrics.latency {
+                metrics.sla = SlaQuantile::measure(latency, thresholds);
+            }
+        }
+    }
+
+    /// Record the host conditions of the test in the confidence of its
+    /// latency results: whether the CPU throttled, and the noise floor.
+    ///
+    /// Call after `cpu_frequency` is set.
+    pub fn annotate_confidence(&mut self, noise_floor_ns: u64) {
+        let throttled = self.cpu_frequency.as_ref().map(|report| report.throttled);
+        for metrics in [&mut self.one_way_results, &mut self.round_trip_results]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(latency) = metrics.latency.as_mut() {
+                let median_ns = latency.median_ns;
+                if let Some(confidence) = latency.confidence.as_mut() {
+                    confidence.annotate_host(throttled, noise_floor_ns, median_ns);
+                }
+            }
+        }
+    }
+
     /// Add one-way test results
     ///
     /// Incorporates performance metrics from one-way latency testing
@@ -1670,10 +3296,12 @@ impl BenchmarkResults {
         let mut total_bytes = 0;
         let mut throughput_values = Vec::new();
         let mut latency_values = Vec::new();
+        let mut error_count = 0;
 
         // Process one-way results if available
         if let Some(ref results) = self.one_way_results {
             total_messages += results.throughput.total_messages;
+            error_count += results.error_count;
             total_bytes += results.throughput.total_bytes;
             throughput_values.push(results.throughput.bytes_per_second);
 
@@ -1686,6 +3314,7 @@ impl BenchmarkResults {
         if let Some(ref results) = self.round_trip_results {
             total_messages += results.throughput.total_messages;
             total_bytes += results.throughput.total_bytes;
+            error_count += results.error_count;
             throughput_values.push(results.throughput.bytes_per_second);
 
             if let Some(ref latency) = results.latency {
@@ -1694,10 +3323,9 @@ impl BenchmarkResults {
         }
 
         // Calculate summary metrics
-        let average_throughput_megabytes_per_sec =
-            throughput_values.iter().sum::<f64>() / throughput_values.len() as f64 / 1_000_000.0;
-        let peak_throughput_megabytes_per_sec =
-            throughput_values.iter().cloned().fold(0.0, f64::max) / 1_000_000.0;
+        let average_throughput_bytes_per_sec =
+            throughput_values.iter().sum::<f64>() / throughput_values.len() as f64;
+        let peak_throughput_bytes_per_sec = throughput_values.iter().cloned().fold(0.0, f64::max);
 
         // Calculate properly weighted average latency across all test types
         let average_latency_ns = self.calculate_weighted_average_latency();
@@ -1708,17 +3336,39 @@ impl BenchmarkResults {
         self.summary = BenchmarkSummary {
             total_messages_sent: total_messages,
             total_bytes_transferred: total_bytes,
-            average_throughput_megabytes_per_sec,
-            peak_throughput_megabytes_per_sec,
+            average_throughput_megabytes_per_sec: average_throughput_bytes_per_sec / 1_000_000.0,
+            peak_throughput_megabytes_per_sec: peak_throughput_bytes_per_sec / 1_000_000.0,
+            average_throughput_bytes_per_sec,
+            peak_throughput_bytes_per_sec,
             average_latency_ns,
             min_latency_ns,
             max_latency_ns,
             p95_latency_ns,
             p99_latency_ns,
-            error_count: 0, // TODO: Track errors
+            error_count,
+            littles_law: self.littles_law_checks(),
         };
     }
 
+    /// Little's Law checks of the one-way and round-trip tests
+    ///
+    /// Round-trip clients wait for each reply, so each keeps one message in
+    /// flight and the expected depth is the concurrency. One-way senders
+    /// don't wait, and an idle gap or send delay leaves the client empty
+    /// part of the time, so those tests report L without an expected depth.
+    fn littles_law_checks(&self) -> Vec<LittlesLawCheck> {
+        let paced = self.test_config.idle_gap.is_some() || self.test_config.send_delay.is_some();
+        let round_trip_depth = (!paced).then_some(self.test_config.concurrency);
+
+        [
+            (&self.one_way_results, None),
+            (&self.round_trip_results, round_trip_depth),
+        ]
+        .into_iter()
+        .filter_map(|(metrics, depth)| LittlesLawCheck::evaluate(metrics.as_ref()?, depth))
+        .collect()
+    }
+
     /// Calculate properly weighted average latency across all test types
     ///
     /// Computes the weighted average latency by considering the sample count
@@ -1856,12 +3506,15 @@ impl Default for BenchmarkSummary {
             total_bytes_transferred: 0,
             average_throughput_megabytes_per_sec: 0.0,
             peak_throughput_megabytes_per_sec: 0.0,
+            average_throughput_bytes_per_sec: 0.0,
+            peak_throughput_bytes_per_sec: 0.0,
             average_latency_ns: None,
             min_latency_ns: None,
             max_latency_ns: None,
             p95_latency_ns: None,
             p99_latency_ns: None,
             error_count: 0,
+            littles_law: Vec::new(),
         }
     }
 }
@@ -1878,13 +3531,22 @@ impl Default for SystemInfo {
     /// - **Hardware**: Detected from system APIs or reasonable defaults
     /// - **Software**: Detected from build-time metadata or defaults
     fn default() -> Self {
+        let memory = MemoryInfo::query();
         Self {
             os: std::env::consts::OS.to_string(),
             architecture: std::env::consts::ARCH.to_string(),
+            kernel_version: host_info::kernel_version(),
+            distribution: host_info::distribution(),
             cpu_cores: num_cpus::get(),
-            memory_gb: 16.0,
+            memory_gb: memory.as_ref().map_or(0.0, MemoryInfo::effective_gb),
             rust_version: "1.75.0".to_string(),
             benchmark_version: crate::VERSION.to_string(),
+            clock_sync: None,
+            memory,
+            ipc_limits: IpcLimits::query(),
+            virtualization: Virtualization::detect(),
+            load: None,
+            mechanisms: MechanismSupport::of_this_build(),
         }
     }
 }
@@ -1892,7 +3554,7 @@ impl Default for SystemInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::IpcMechanism;
+    use crate::mechanism::IpcMechanism;
     use tempfile::NamedTempFile;
     // use a local tokio runtime for running async helpers in tests
     use std::fs;
@@ -1903,7 +3565,7 @@ mod tests {
     #[cfg(unix)]
     fn test_benchmark_results_creation() {
         let results = BenchmarkResults::new(
-            IpcMechanism::UnixDomainSocket,
+            IpcMechanism::Http,
             1024,
             8192,
             1,
@@ -1914,7 +3576,7 @@ mod tests {
             true,
         );
 
-        assert_eq!(results.mechanism, IpcMechanism::UnixDomainSocket);
+        assert_eq!(results.mechanism, IpcMechanism::Http);
         assert_eq!(results.test_config.message_size, 1024);
         assert_eq!(results.test_config.concurrency, 1);
         assert_eq!(results.test_config.msg_count, Some(1000));
@@ -1958,7 +3620,7 @@ mod tests {
         #[cfg(unix)]
         let r1 = MessageLatencyRecord::new(
             1,
-            IpcMechanism::UnixDomainSocket,
+            IpcMechanism::Http,
             128,
             LatencyType::OneWay,
             Duration::from_micros(10),
@@ -1976,7 +3638,7 @@ mod tests {
         #[cfg(unix)]
         let r2 = MessageLatencyRecord::new(
             2,
-            IpcMechanism::UnixDomainSocket,
+            IpcMechanism::Http,
             128,
             LatencyType::RoundTrip,
             Duration::from_micros(20),
@@ -2023,7 +3685,7 @@ mod tests {
         #[cfg(unix)]
         let r_high = MessageLatencyRecord::new(
             5,
-            IpcMechanism::UnixDomainSocket,
+            IpcMechanism::Http,
             128,
             LatencyType::OneWay,
             Duration::from_micros(50),
@@ -2041,7 +3703,7 @@ mod tests {
         #[cfg(unix)]
         let r_low = MessageLatencyRecord::new(
             2,
-            IpcMechanism::UnixDomainSocket,
+            IpcMechanism::Http,
             128,
             LatencyType::OneWay,
             Duration::from_micros(20),
@@ -2097,7 +3759,7 @@ mod tests {
         #[cfg(unix)]
         let r1 = MessageLatencyRecord::new(
             1,
-            IpcMechanism::UnixDomainSocket,
+            IpcMechanism::Http,
             64,
             LatencyType::OneWay,
             Duration::from_micros(5),
@@ -2148,8 +3810,8 @@ mod tests {
         // Mechanism is serialized as "TcpSocket" or similar
         assert!(csv.contains("1024"));
         assert!(csv.contains("5000"));
-        // Round-trip should be empty
-        assert!(csv.ends_with(','));
+        // Round-trip should be empty, followed by the run ID
+        assert!(csv.ends_with(&format!(",,{}", crate::run_id::get())));
     }
 
     #[test]
@@ -2197,12 +3859,13 @@ mod tests {
         };
 
         let values = record.to_value_array();
-        assert_eq!(values.len(), 6);
+        assert_eq!(values.len(), 7);
         assert_eq!(values[0], serde_json::json!(9999u64));
         assert_eq!(values[1], serde_json::json!(1u64));
         assert_eq!(values[3], serde_json::json!(512));
         assert_eq!(values[4], serde_json::json!(Some(1000u64)));
         assert_eq!(values[5], serde_json::json!(Option::<u64>::None));
+        assert_eq!(values[6], serde_json::json!(crate::run_id::get()));
     }
 
     #[test]
@@ -2318,13 +3981,14 @@ mod tests {
 
     #[test]
     fn test_message_latency_record_headings() {
-        assert_eq!(MessageLatencyRecord::HEADINGS.len(), 6);
+        assert_eq!(MessageLatencyRecord::HEADINGS.len(), 7);
         assert_eq!(MessageLatencyRecord::HEADINGS[0], "timestamp_ns");
         assert_eq!(MessageLatencyRecord::HEADINGS[1], "message_id");
         assert_eq!(MessageLatencyRecord::HEADINGS[2], "mechanism");
         assert_eq!(MessageLatencyRecord::HEADINGS[3], "message_size");
         assert_eq!(MessageLatencyRecord::HEADINGS[4], "one_way_latency_ns");
         assert_eq!(MessageLatencyRecord::HEADINGS[5], "round_trip_latency_ns");
+        assert_eq!(MessageLatencyRecord::HEADINGS[6], "run_id");
     }
 
     #[test]
@@ -2516,4 +4180,315 @@ mod tests {
             before, after, ts
         );
     }
+
+    #[test]
+    fn test_cache_control_report_records_and_aggregates() {
+        let mut report = CacheControlReport {
+            drop_caches: true,
+            ..Default::default()
+        };
+        report.record_drop_attempt(&Ok(()));
+        report.record_drop_attempt(&Err(anyhow::anyhow!("permission denied")));
+        assert_eq!(report.drop_caches_attempts, 2);
+        assert_eq!(report.drop_caches_succeeded, 1);
+        assert!(!report.all_drops_succeeded());
+        assert_eq!(report.last_error.as_deref(), Some("permission denied"));
+
+        let mut first = BenchmarkResults::new(
+            IpcMechanism::TcpSocket,
+            64,
+            1024,
+            1,
+            Some(10),
+            None,
+            0,
+            true,
+            false,
+        );
+        let second = first.clone();
+        assert!(CacheControlReport::aggregate(&[first.clone()]).is_none());
+
+        first.cache_control = Some(report);
+        let total = CacheControlReport::aggregate(&[first, second]).unwrap();
+        assert!(total.drop_caches);
+        assert_eq!(total.drop_caches_attempts, 2);
+        assert_eq!(total.drop_caches_succeeded, 1);
+    }
+
+    #[test]
+    fn test_cpu_usage() {
+        let usage = CpuUsage::new(750_000_000, 1_000_000_000);
+        assert_eq!(usage.utilization_percent, 75.0);
+        assert_eq!(
+            usage.to_string(),
+            "75.0% of one core (750.00ms CPU over 1.00s)"
+        );
+        assert_eq!(CpuUsage::new(5, 0).utilization_percent, 0.0);
+    }
+
+    /// Round-trip results whose latencies are all `latency_ns`
+    fn round_trip_result(mechanism: IpcMechanism, latency_ns: u64) -> BenchmarkResults {
+        let mut collector = crate::metrics::LatencyCollector::new(LatencyType::RoundTrip).unwrap();
+        for _ in 0..10 {
+            collector.record(Duration::from_nanos(latency_ns)).unwrap();
+        }
+        let mut result =
+            BenchmarkResults::new(mechanism, 64, 1024, 1, Some(10), None, 0, false, true);
+        result.add_round_trip_results(PerformanceMetrics {
+            latency: Some(collector.get_metrics(&[50.0, 95.0, 99.0])),
+            throughput: crate::metrics::ThroughputMetrics {
+                messages_per_second: 1000.0,
+                bytes_per_second: 64000.0,
+                total_messages: 10,
+                total_bytes: 640,
+                duration_ns: 10_000_000,
+            },
+            error_count: 0,
+            expired_count: 0,
+            priority_latency: Vec::new(),
+            phase_latency: Vec::new(),
+            connection_latency: Vec::new(),
+            processing_latency: Vec::new(),
+            cpu_split: Vec::new(),
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_message: None,
+            first_latency_ns: None,
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            latency_reservoir: None,
+            goodput: None,
+            sla: Vec::new(),
+            timestamp: chrono::Utc::now(),
+        });
+        result
+    }
+
+    #[test]
+    fn test_deadline_miss_summary() {
+        let mut result = round_trip_result(IpcMechanism::Http, 1000);
+        assert_eq!(deadline_miss_summary(&result), None);
+
+        result.test_config.deadline = Some(Duration::from_micros(50));
+        result.round_trip_results.as_mut().unwrap().expired_count = 10;
+        assert_eq!(
+            deadline_miss_summary(&result).unwrap(),
+            "50.00μs: round-trip 10 of 20 (50.00%)"
+        );
+    }
+
+    #[test]
+    fn test_latency_breakdown_lines() {
+        let mut result = round_trip_result(IpcMechanism::TcpSocket, 2000);
+        assert!(latency_breakdown_lines(&result).is_empty());
+
+        let metrics = result.round_trip_results.as_mut().unwrap();
+        let latency = metrics.latency.clone().unwrap();
+        metrics.priority_latency = vec![LatencyBreakdown {
+            key: crate::metrics::Priority(3),
+            latency: latency.clone(),
+        }];
+        metrics.connection_latency = vec![LatencyBreakdown {
+            key: crate::metrics::ConnectionReuse::Cold,
+            latency: latency.clone(),
+        }];
+        metrics.cpu_split = vec![LatencyBreakdown {
+            key: crate::metrics::CpuSplit::OffCpu,
+            latency,
+        }];
+        result.one_way_results = Some(metrics.clone());
+        result.one_way_results.as_mut().unwrap().connection_latency = Vec::new();
+
+        let summary = "P50 2.00μs, P99 2.00μs, max 2.00μs (10 messages)";
+        let expected = [
+            (
+                "Priority Latency",
+                format!("one-way priority 3: {}", summary),
+            ),
+            (
+                "Priority Latency",
+                format!("round-trip priority 3: {}", summary),
+            ),
+            ("Connection", format!("cold: {}", summary)),
+            ("CPU Split", format!("one-way send off-CPU: {}", summary)),
+            ("CPU Split", format!("round-trip off-CPU: {}", summary)),
+        ];
+        assert_eq!(latency_breakdown_lines(&result), expected);
+    }
+
+    #[test]
+    fn test_warmup_lines() {
+        let mut result = round_trip_result(IpcMechanism::TcpSocket, 2000);
+        assert!(warmup_lines(&result).is_empty());
+
+        let latency = result
+            .round_trip_results
+            .as_ref()
+            .and_then(|m| m.latency.clone())
+            .unwrap();
+        result.warmup = Some(WarmupReport {
+            latency,
+            segments: [4000, 2000]
+                .iter()
+                .enumerate()
+                .map(|(i, &median_ns)| crate::warmup::WarmupSegment {
+                    first_message: i * 5,
+                    messages: 5,
+                    median_ns,
+                    p99_ns: median_ns,
+                })
+                .collect(),
+        });
+        assert_eq!(
+            warmup_lines(&result),
+            [
+                "round-trip: P50 2.00μs, P99 2.00μs, max 2.00μs (10 messages)",
+                "median by segment: 4.00μs -> 2.00μs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pair_lines() {
+        let mut result = round_trip_result(IpcMechanism::TcpSocket, 2000);
+        result.one_way_results = result.round_trip_results.take();
+        assert!(pair_lines(&result).is_empty());
+
+        let slower = round_trip_result(IpcMechanism::TcpSocket, 4000);
+        let metrics = result.one_way_results.as_mut().unwrap();
+        let report = crate::pair::PairReport {
+            server_to_client: slower.round_trip_results.unwrap().latency.unwrap(),
+            messages_per_second: 1000.0,
+            asymmetry: None,
+        };
+        metrics.pair = Some(report.compared_with(metrics.latency.as_ref()));
+        assert_eq!(
+            pair_lines(&result),
+            [
+                "client->server: P50 2.00μs, P99 2.00μs, max 2.00μs (10 messages)",
+                "server->client: P50 4.00μs, P99 4.00μs, max 4.00μs (10 messages)",
+                "server->client vs client->server: 2.00x at P50, 2.00x at P99",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_confidence_lines() {
+        let mut result = round_trip_result(IpcMechanism::Http, 2000);
+        result.annotate_confidence(20);
+        let latency = result.round_trip_results.as_ref().unwrap().latency.as_ref();
+        let confidence = latency.unwrap().confidence.as_ref().unwrap();
+        assert_eq!(confidence.throttled, None);
+        assert_eq!(confidence.noise_floor_ratio, Some(0.01));
+
+        // Ten samples support none of P50, P95 and P99
+        let lines = confidence_lines(&result);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("round-trip: P50 has 5 samples beyond it"));
+        assert_eq!(lines[0].matches("samples beyond it").count(), 3);
+    }
+
+    #[test]
+    fn test_littles_law_check() {
+        // 1000 msg/s with 1ms each keeps one request in flight
+        let result = round_trip_result(IpcMechanism::Http, 1_000_000);
+        let check = &result.summary.littles_law[0];
+        assert_eq!(check.latency_type, LatencyType::RoundTrip);
+        assert!((check.average_in_flight - 1.0).abs() < 0.01);
+        assert_eq!(check.expected_in_flight, Some(1));
+        assert!(check.consistent);
+        assert_eq!(
+            check.to_string(),
+            "round-trip L = 1.00 in flight (1000 msg/s x 1.00ms), expected 1 (+0.0%)"
+        );
+
+        // Half the latency at the same throughput: the windows disagree
+        let result = round_trip_result(IpcMechanism::Http, 500_000);
+        let check = &result.summary.littles_law[0];
+        assert!((check.discrepancy_percent.unwrap() + 50.0).abs() < 0.1);
+        assert!(!check.consistent);
+        assert!(check.to_string().ends_with("(-50.0%) [INCONSISTENT]"));
+
+        // A paced sender has no fixed depth to compare against
+        let mut result = round_trip_result(IpcMechanism::Http, 500_000);
+        result.test_config.send_delay = Some(Duration::from_millis(1));
+        result.update_summary();
+        let check = &result.summary.littles_law[0];
+        assert_eq!(check.expected_in_flight, None);
+        assert!(check.consistent);
+    }
+
+    #[test]
+    fn test_format_comparison_table() {
+        let fast = round_trip_result(IpcMechanism::SharedMemory, 1000);
+        let slow = round_trip_result(IpcMechanism::TcpSocket, 2000);
+        assert!(format_comparison_table(std::slice::from_ref(&fast)).is_none());
+
+        let table = format_comparison_table(&[fast, slow]).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(
+            lines[0].contains("Mechanism")
+                && lines[0].contains("MiB/s")
+                && lines[0].contains("P50 vs best")
+        );
+        assert!(lines[2].contains("Shared Memory") && lines[2].ends_with("1.00x"));
+        assert!(lines[3].contains("TCP Socket") && lines[3].ends_with("2.00x"));
+        assert!(lines[3].contains("2.00μs"));
+    }
+
+    #[test]
+    fn test_comparison_marks_overlapping_medians() {
+        let with_p50_interval = |mechanism, latency_ns, lower_ns, upper_ns| {
+            let mut result = round_trip_result(mechanism, latency_ns);
+            let metrics = result.round_trip_results.as_mut().unwrap();
+            metrics.latency.as_mut().unwrap().percentile_intervals = vec![PercentileInterval {
+                percentile: 50.0,
+                lower_ns,
+                upper_ns,
+            }];
+            result
+        };
+        let best = with_p50_interval(IpcMechanism::SharedMemory, 1000, 900, 1100);
+        let close = with_p50_interval(IpcMechanism::Http, 1050, 1000, 1150);
+        let far = with_p50_interval(IpcMechanism::TcpSocket, 2000, 1900, 2100);
+
+        let latency = best.round_trip_results.as_ref().unwrap().latency.as_ref();
+        assert_eq!(
+            interval_line(latency.unwrap()).unwrap(),
+            "95% CI: P50 900ns-1.10μs"
+        );
+
+        let table = format_comparison_table(&[best, close, far]).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[2].ends_with("1.00x"));
+        assert!(lines[3].ends_with("1.05x*"));
+        assert!(lines[4].ends_with("2.00x"));
+        assert!(lines[5].starts_with("  * P50 within the best's 95% confidence interval"));
+    }
+
+    #[test]
+    fn test_format_latency_histogram() {
+        let mut collector = crate::metrics::LatencyCollector::new(LatencyType::OneWay).unwrap();
+        for ns in [1000, 1000, 1000, 1000, 9000] {
+            collector.record(Duration::from_nanos(ns)).unwrap();
+        }
+        let latency = collector.get_metrics(&[50.0]);
+        let lines = format_latency_histogram(&latency, "  ");
+        assert_eq!(lines.len(), latency.buckets.len());
+        assert!(lines[0].contains(&"█".repeat(HISTOGRAM_WIDTH)));
+        assert!(lines[0].ends_with("4 (80.0%)"));
+        assert!(lines.last().unwrap().ends_with("1 (20.0%)"));
+
+        let empty = LatencyMetrics {
+            buckets: Vec::new(),
+            ..latency
+        };
+        assert!(format_latency_histogram(&empty, "").is_empty());
+    }
 }
@@ -31,14 +31,26 @@
 //! - File flushes block until buffers are synchronized
 //! - No async/await or Tokio runtime required
 
+use crate::clock_sync::ClockSync;
+use crate::criteria::{CriteriaPlan, CriteriaReport};
+use crate::csv_schema::{CsvColumn, CsvLayout};
+use crate::history::SummaryHistory;
+use crate::host_info::{self, IpcLimits, Virtualization};
+use crate::json_output::JsonOutput;
+use crate::memory_info::MemoryInfo;
+use crate::metrics::utils::{format_bytes, format_latency, format_throughput};
+use crate::options::MachineOutput;
 use crate::results::{
-    BenchmarkMetadata, BenchmarkResults, FinalBenchmarkResults, MechanismSummary,
-    MessageLatencyRecord, OverallSummary, SystemInfo,
+    format_comparison_table, format_latency_histogram, interval_line, queue_depth_json_member,
+    write_percentile_distributions, write_queue_depth_csv, BenchmarkMetadata, BenchmarkResults,
+    CacheControlReport, FinalBenchmarkResults, MechanismSummary, MessageLatencyRecord,
+    OverallSummary, SystemInfo,
 };
+use crate::system_load::SystemLoad;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
 use tracing::{debug, info};
 
@@ -121,6 +133,9 @@ pub struct BlockingResultsManager {
     /// Whether CSV streaming is enabled
     csv_streaming_enabled: bool,
 
+    /// Columns of the streaming CSV and the context of its rows
+    csv_layout: CsvLayout,
+
     /// Whether to stream per-message latency records instead of final results
     per_message_streaming: bool,
 
@@ -134,6 +149,28 @@ pub struct BlockingResultsManager {
     /// Buffer for collecting records when both tests are running (keyed by
     /// message ID)
     pending_records: HashMap<u64, MessageLatencyRecord>,
+
+    /// Clock synchronization quality recorded in the system information
+    clock_sync: Option<ClockSync>,
+
+    /// Format of results printed to stdout, if any
+    machine_output: Option<MachineOutput>,
+
+    /// Formatting and trimming of the final JSON results
+    json_output: JsonOutput,
+
+    /// Directory receiving per-test percentile distribution files, if any
+    percentile_distribution_dir: Option<std::path::PathBuf>,
+
+    /// Scenario bounds checked once all tests have run, if any
+    criteria: Option<CriteriaPlan>,
+
+    /// Summary CSV appended to once all tests have run, if any
+    summary_history: Option<SummaryHistory>,
+
+    /// How busy the machine was before the run, recorded in the system
+    /// information
+    system_load: Option<SystemLoad>,
 }
 
 impl BlockingResultsManager {
@@ -180,10 +217,18 @@ impl BlockingResultsManager {
             results: Vec::new(),
             streaming_enabled: false,
             csv_streaming_enabled: false,
+            csv_layout: CsvLayout::default(),
             per_message_streaming: false,
             first_record_streamed: true,
             both_tests_enabled: false,
             pending_records: HashMap::new(),
+            clock_sync: None,
+            machine_output: None,
+            json_output: JsonOutput::default(),
+            percentile_distribution_dir: None,
+            criteria: None,
+            summary_history: None,
+            system_load: None,
         })
     }
 
@@ -245,6 +290,62 @@ impl BlockingResultsManager {
         Ok(())
     }
 
+    /// Record this host's clock synchronization quality in the system information.
+    ///
+    /// Used by split client/server runs, where timestamps from two hosts are
+    /// only as comparable as the agreement between their clocks.
+    pub fn set_clock_sync(&mut self, clock_sync: Option<ClockSync>) {
+        self.clock_sync = clock_sync;
+    }
+
+    /// Print results to stdout as JSON (`--machine-output`).
+    ///
+    /// `Json` prints the final results document from `finalize()`; `Jsonl`
+    /// prints each mechanism's results on one line from `add_results()`.
+    pub fn set_machine_output(&mut self, format: Option<MachineOutput>) {
+        self.machine_output = format;
+    }
+
+    /// Format and trim the final JSON results (`--output-compact`,
+    /// `--output-fields`).
+    pub fn set_json_output(&mut self, output: JsonOutput) {
+        self.json_output = output;
+    }
+
+    /// Export each test's latency percentile distribution to `dir`
+    /// (`--percentile-distribution`), creating the directory if needed.
+    pub fn set_percentile_distribution_dir(&mut self, dir: Option<&Path>) -> Result<()> {
+        if let Some(dir) = dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        self.percentile_distribution_dir = dir.map(Path::to_path_buf);
+        Ok(())
+    }
+
+    /// Check the results against scenario bounds (`--criteria`) when the
+    /// run finishes.
+    pub fn set_criteria(&mut self, criteria: Option<CriteriaPlan>) {
+        self.criteria = criteria;
+    }
+
+    /// Append a summary row per mechanism to a long-lived CSV
+    /// (`--append-summary`) when the run finishes.
+    pub fn set_summary_history(&mut self, history: Option<SummaryHistory>) {
+        self.summary_history = history;
+    }
+
+    /// Record the pre-run system load check in the system information.
+    pub fn set_system_load(&mut self, load: Option<SystemLoad>) {
+        self.system_load = load;
+    }
+
+    /// Outcome of the `--criteria` checks over the results so far.
+    pub fn criteria_report(&self) -> Option<CriteriaReport> {
+        self.criteria
+            .as_ref()
+            .map(|criteria| criteria.evaluate(&self.results))
+    }
+
     /// Enable per-message latency streaming
     ///
     /// Configures real-time per-message latency streaming for detailed
@@ -379,6 +480,12 @@ impl BlockingResultsManager {
         Ok(())
     }
 
+    /// Select the columns of the streaming CSV; call before
+    /// [`Self::enable_csv_streaming`].
+    pub fn set_csv_columns(&mut self, columns: Vec<CsvColumn>) {
+        self.csv_layout = CsvLayout::new(columns);
+    }
+
     /// Enable CSV latency streaming to a file
     ///
     /// Configures real-time per-message latency streaming in CSV format.
@@ -434,10 +541,11 @@ impl BlockingResultsManager {
                 .write(true)
                 .truncate(true)
                 .open(self.streaming_csv_file.as_ref().unwrap())?;
-            let header = MessageLatencyRecord::HEADINGS.join(",");
-            writeln!(file, "{}", header)?;
+            writeln!(file, "{}", self.csv_layout.header())?;
             file.flush()?;
         }
+        self.csv_layout
+            .write_schema(self.streaming_csv_file.as_ref().unwrap())?;
 
         let append_file = OpenOptions::new()
             .create(true)
@@ -561,7 +669,7 @@ impl BlockingResultsManager {
         // Stream to CSV if enabled
         if self.csv_streaming_enabled {
             if let Some(ref mut csv_writer) = self.streaming_csv_handle {
-                let csv_record = record.to_csv_record();
+                let csv_record = self.csv_layout.row(record);
                 csv_writer.write_all(csv_record.as_bytes())?;
                 csv_writer.write_all(b"\n")?;
                 csv_writer.flush()?;
@@ -571,7 +679,7 @@ impl BlockingResultsManager {
                     .append(true)
                     .open(streaming_csv_file)?;
                 let mut buf = std::io::BufWriter::new(f);
-                let csv_record = record.to_csv_record();
+                let csv_record = self.csv_layout.row(record);
                 buf.write_all(csv_record.as_bytes())?;
                 buf.write_all(b"\n")?;
                 buf.flush()?;
@@ -678,6 +786,18 @@ impl BlockingResultsManager {
             self.stream_results(&results)?;
         }
 
+        if let Some(dir) = &self.percentile_distribution_dir {
+            for path in write_percentile_distributions(dir, &results)? {
+                info!("Wrote percentile distribution to: {:?}", path);
+            }
+        }
+
+        if self.machine_output == Some(MachineOutput::Jsonl) {
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", self.json_output.render_line(&results)?)?;
+            stdout.flush()?;
+        }
+
         self.results.push(results);
         Ok(())
     }
@@ -786,6 +906,18 @@ impl BlockingResultsManager {
             self.write_final_results(output_file)?;
         }
 
+        if let Some(history) = &self.summary_history {
+            let rows = history.append(&self.results)?;
+            info!("Appended {} summary rows to the summary history", rows);
+        }
+
+        if self.machine_output == Some(MachineOutput::Json) {
+            let json = self.json_output.render(&self.final_results())?;
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", json)?;
+            stdout.flush()?;
+        }
+
         Ok(())
     }
 
@@ -807,15 +939,17 @@ impl BlockingResultsManager {
     fn close_streaming_json(&mut self) -> Result<()> {
         if self.per_message_streaming {
             info!("Closing streaming JSON file.");
+            // Queue depth samples follow the data as a table of their own
+            let closing = format!("\n  ]{}\n}}\n", queue_depth_json_member(&self.results)?);
             // Prefer buffered handle if present
             if let Some(mut writer) = self.streaming_file_handle.take() {
-                writer.write_all(b"\n  ]\n}\n")?;
+                writer.write_all(closing.as_bytes())?;
                 writer.flush()?;
             } else if let Some(streaming_file) = &self.streaming_file {
                 let mut file = OpenOptions::new().append(true).open(streaming_file)?;
                 // Write the closing brackets for the JSON data array and the
                 // root object.
-                write!(file, "\n  ]\n}}\n")?;
+                file.write_all(closing.as_bytes())?;
                 file.flush()?;
             }
         } else if self.streaming_enabled {
@@ -858,6 +992,9 @@ impl BlockingResultsManager {
                 if let Some(mut csv_writer) = self.streaming_csv_handle.take() {
                     csv_writer.flush()?;
                 }
+                if let Some(queue_path) = write_queue_depth_csv(path, &self.results)? {
+                    info!("Wrote queue depth samples to {}", queue_path.display());
+                }
             }
         }
         Ok(())
@@ -905,6 +1042,23 @@ impl BlockingResultsManager {
         Ok(())
     }
 
+    /// Assemble the final results document with metadata and summary.
+    fn final_results(&self) -> FinalBenchmarkResults {
+        FinalBenchmarkResults {
+            metadata: BenchmarkMetadata {
+                version: crate::VERSION.to_string(),
+                run_id: crate::run_id::get().to_string(),
+                timestamp: chrono::Utc::now(),
+                total_tests: self.results.len(),
+                system_info: self.get_system_info(),
+                cache_control: CacheControlReport::aggregate(&self.results),
+            },
+            results: self.results.clone(),
+            summary: self.calculate_overall_summary(),
+            criteria: self.criteria_report(),
+        }
+    }
+
     /// Write final consolidated results (blocking)
     ///
     /// Generates the complete final output including all individual results,
@@ -942,19 +1096,8 @@ impl BlockingResultsManager {
     fn write_final_results(&self, output_file: &Path) -> Result<()> {
         info!("Writing final results to: {:?}", output_file);
 
-        let final_results = FinalBenchmarkResults {
-            metadata: BenchmarkMetadata {
-                version: crate::VERSION.to_string(),
-                timestamp: chrono::Utc::now(),
-                total_tests: self.results.len(),
-                system_info: self.get_system_info(),
-            },
-            results: self.results.clone(),
-            summary: self.calculate_overall_summary(),
-        };
-
-        // Serialize pretty JSON
-        let json = serde_json::to_string_pretty(&final_results)?;
+        // Serialize as --output-compact and --output-fields ask
+        let json = self.json_output.render(&self.final_results())?;
 
         // Atomic write: write to a temp file next to the target, then rename.
         // Use a ".partial" extension to avoid clobbering the destination on
@@ -1008,6 +1151,9 @@ impl BlockingResultsManager {
                     average_throughput_megabytes_per_sec: result
                         .summary
                         .average_throughput_megabytes_per_sec,
+                    average_throughput_bytes_per_sec: result
+                        .summary
+                        .average_throughput_bytes_per_sec,
                     p95_latency_ns: result.summary.p95_latency_ns,
                     p99_latency_ns: result.summary.p99_latency_ns,
                     total_messages: result.summary.total_messages_sent,
@@ -1093,38 +1239,27 @@ impl BlockingResultsManager {
     /// - **Platform Info**: From standard library constants
     /// - **Hardware Info**: From system APIs and detection functions
     /// - **Software Info**: From build-time and runtime version detection
-    /// - **Memory Info**: From system information utilities
+    /// - **Memory Info**: From `/proc/meminfo` and cgroup limits (see [`MemoryInfo`])
     fn get_system_info(&self) -> SystemInfo {
+        let memory = MemoryInfo::query();
         SystemInfo {
             os: std::env::consts::OS.to_string(),
             architecture: std::env::consts::ARCH.to_string(),
+            kernel_version: host_info::kernel_version(),
+            distribution: host_info::distribution(),
             cpu_cores: num_cpus::get(),
-            memory_gb: Self::get_memory_gb(),
+            memory_gb: memory.as_ref().map_or(0.0, MemoryInfo::effective_gb),
             rust_version: Self::get_rust_version(),
             benchmark_version: crate::VERSION.to_string(),
+            clock_sync: self.clock_sync.clone(),
+            memory,
+            ipc_limits: IpcLimits::query(),
+            virtualization: Virtualization::detect(),
+            load: self.system_load.clone(),
+            mechanisms: crate::mechanism::MechanismSupport::of_this_build(),
         }
     }
 
-    /// Get available memory in GB
-    ///
-    /// Estimates available system memory for resource correlation.
-    /// This is a simplified implementation that provides reasonable
-    /// estimates for most systems.
-    ///
-    /// ## Returns
-    /// Estimated available memory in gigabytes
-    ///
-    /// ## Implementation Note
-    ///
-    /// This is a placeholder implementation that returns reasonable
-    /// defaults. A production implementation would use platform-specific
-    /// system information APIs to get accurate memory information.
-    fn get_memory_gb() -> f64 {
-        // This is a simplified implementation
-        // In a real implementation, you'd want to use a system info crate
-        16.0 // Default assumption
-    }
-
     /// Get Rust version
     ///
     /// Retrieves the Rust compiler version used to build the benchmark.
@@ -1177,6 +1312,9 @@ impl BlockingResultsManager {
         if let Some(path) = &self.streaming_csv_file {
             println!("    Streaming CSV:        {}", path.display());
         }
+        if let Some(path) = &self.percentile_distribution_dir {
+            println!("    Percentiles (hgrm):   {}", path.display());
+        }
         if let Some(path) = &self.log_file {
             println!("    Log File:             {}", path);
         }
@@ -1189,6 +1327,121 @@ impl BlockingResultsManager {
                 println!("Mechanism: {}", result.mechanism);
                 println!("  Message Size: {} bytes", result.test_config.message_size);
                 println!("  Buffer Size:  {} bytes", result.test_config.buffer_size);
+                if let Some(mode) = crate::results::response_mode_line(result) {
+                    println!("  Responses:    {}", mode);
+                }
+                if let Some(profile) = &result.test_config.profile {
+                    println!("  Profile:      {}", profile);
+                }
+                if !result.test_config.concurrent_with.is_empty() {
+                    let others: Vec<String> = result
+                        .test_config
+                        .concurrent_with
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect();
+                    println!(
+                        "  Warning: measured concurrently with {}",
+                        others.join(", ")
+                    );
+                }
+                if let Some(shared) = &result.test_config.smt_siblings {
+                    println!("  SMT Siblings: {}", shared);
+                }
+                if let (Some(count), Some(size)) = (
+                    result.test_config.segments_per_message,
+                    result.test_config.segment_size,
+                ) {
+                    println!(
+                        "  Segments:     {} per message ({} bytes each)",
+                        count, size
+                    );
+                }
+                if let Some(report) = &result.cpu_frequency {
+                    println!("  CPU Frequency: {}", report);
+                }
+                if let Some(noise) = &result.scheduler_noise {
+                    println!("  Scheduler Noise: {}", noise);
+                }
+                if let Some(cost) = &result.serialization {
+                    println!("  Serialization: {}", cost);
+                }
+                if let Some(usage) = &result.client_cpu {
+                    let wait = result
+                        .test_config
+                        .spin_wait
+                        .map_or("blocking receive".to_string(), |mode| {
+                            format!("spin-wait: {}", mode)
+                        });
+                    println!("  Client CPU: {} ({})", usage, wait);
+                }
+                if let Some(misses) = crate::results::deadline_miss_summary(result) {
+                    println!("  Deadline Misses: {}", misses);
+                }
+                for line in crate::results::goodput_lines(result) {
+                    println!("  Goodput: {}", line);
+                }
+                for line in crate::results::sla_lines(result) {
+                    println!("  SLA: {}", line);
+                }
+                for (heading, line) in crate::results::latency_breakdown_lines(result) {
+                    println!("  {}: {}", heading, line);
+                }
+                for line in crate::results::pair_lines(result) {
+                    println!("  Direction: {}", line);
+                }
+                for line in crate::results::warmup_lines(result) {
+                    println!("  Warmup: {}", line);
+                }
+                for line in crate::results::queue_depth_lines(result) {
+                    println!("  Queue Depth: {}", line);
+                }
+                for line in crate::results::pacing_lines(result) {
+                    println!("  Pacing: {}", line);
+                }
+                if let Some(count) = result
+                    .one_way_results
+                    .as_ref()
+                    .and_then(|metrics| metrics.send_allocations)
+                {
+                    println!("  Send Allocations: {} after the first message", count);
+                }
+                for line in crate::results::first_message_lines(result) {
+                    println!("  First-Message Penalty: {}", line);
+                }
+                for line in crate::results::affinity_lines(result) {
+                    println!("  Affinity: {}", line);
+                }
+                if let Some(report) = &result.priority_inversion {
+                    println!("  Priority Inversion: {}", report);
+                }
+                for line in crate::results::producer_lines(result) {
+                    println!("  Producer: {}", line);
+                }
+                for line in crate::results::subscriber_lines(result) {
+                    println!("  Subscriber: {}", line);
+                }
+                for line in crate::results::server_lines(result) {
+                    println!("  Server: {}", line);
+                }
+                for line in crate::results::confidence_lines(result) {
+                    println!("  Confidence: {}", line);
+                }
+                if result.setup_retries > 0 {
+                    println!("  Setup Retries: {}", result.setup_retries);
+                }
+                if let Some(line) = crate::results::transport_stats_line(&result.transport_stats) {
+                    println!("  Transport: {}", line);
+                }
+                for check in &result.summary.littles_law {
+                    println!("  Little's Law: {}", check);
+                }
+                if result.summary.error_count > 0 {
+                    println!(
+                        "  Errors: {} (replies that did not match their request)",
+                        result.summary.error_count
+                    );
+                }
 
                 match &result.status {
                     crate::results::BenchmarkStatus::Success => {
@@ -1198,9 +1451,29 @@ impl BlockingResultsManager {
                         println!("  Status: FAILED");
                         println!("    Error: {}", error_msg);
                     }
+                    crate::results::BenchmarkStatus::Aborted => {
+                        println!("  Status: ABORTED (interrupted, partial results)");
+                        Self::print_summary_details(result, "  ");
+                    }
+                    crate::results::BenchmarkStatus::Skipped(reason) => {
+                        println!("  Status: SKIPPED");
+                        println!("    Reason: {}", reason);
+                    }
                 }
                 println!("-----------------------------------------------------------------");
             }
+
+            if let Some(table) = format_comparison_table(&self.results) {
+                println!("Comparison:");
+                print!("{}", table);
+                println!("-----------------------------------------------------------------");
+            }
+
+            if let Some(report) = self.criteria_report() {
+                println!("Pass Criteria:");
+                print!("{}", report);
+                println!("-----------------------------------------------------------------");
+            }
         }
 
         std::io::Write::flush(&mut std::io::stdout())?;
@@ -1240,6 +1513,12 @@ impl BlockingResultsManager {
             format_latency(latency.min_ns),
             format_latency(latency.max_ns)
         );
+        if let Some(line) = interval_line(latency) {
+            println!("{}{:<8} {}", indent, "  ", line);
+        }
+        for line in format_latency_histogram(latency, &format!("{}    ", indent)) {
+            println!("{}", line);
+        }
     }
 
     /// Helper function to format and print the details from a BenchmarkResults struct.
@@ -1254,6 +1533,10 @@ impl BlockingResultsManager {
             }
         }
 
+        if let Some(latency) = &result.first_byte_latency {
+            Self::print_latency_details(latency, indent, "First-Byte Latency");
+        }
+
         if let Some(round_trip) = &result.round_trip_results {
             if let Some(latency) = &round_trip.latency {
                 Self::print_latency_details(latency, indent, "Round-Trip Latency");
@@ -1264,40 +1547,28 @@ impl BlockingResultsManager {
 
         println!("{}Throughput:", indent);
         println!(
-            "{}{:<8} Average: {:.2} MB/s, Peak: {:.2} MB/s",
+            "{}{:<8} Average: {}, Peak: {}",
             indent,
             "  ",
-            summary.average_throughput_megabytes_per_sec,
-            summary.peak_throughput_megabytes_per_sec
+            format_throughput(summary.average_throughput_bytes_per_sec),
+            format_throughput(summary.peak_throughput_bytes_per_sec)
         );
 
         println!("{}Totals:", indent);
         println!(
-            "{}{:<8} Messages: {}, Data: {:.2} MB",
+            "{}{:<8} Messages: {}, Data: {}",
             indent,
             "  ",
             summary.total_messages_sent,
-            summary.total_bytes_transferred as f64 / (1024.0 * 1024.0)
+            format_bytes(summary.total_bytes_transferred as f64)
         );
     }
 }
 
-/// Helper function to format latency values (in nanoseconds) into a
-/// human-readable string (us, ms).
-fn format_latency(ns: u64) -> String {
-    if ns >= 1_000_000 {
-        format!("{:.2} ms", ns as f64 / 1_000_000.0)
-    } else if ns >= 1_000 {
-        format!("{:.2} us", ns as f64 / 1_000.0)
-    } else {
-        format!("{} ns", ns)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::IpcMechanism;
+    use crate::mechanism::IpcMechanism;
     use crate::results::BenchmarkResults;
     use tempfile::TempDir;
 
@@ -1645,13 +1916,14 @@ mod tests {
 
         let values = record.to_value_array();
 
-        assert_eq!(values.len(), 6);
+        assert_eq!(values.len(), 7);
         assert_eq!(values[0], serde_json::json!(123456789)); // timestamp
         assert_eq!(values[1], serde_json::json!(42)); // message_id
         assert_eq!(values[2], serde_json::json!("SharedMemory")); // mechanism
         assert_eq!(values[3], serde_json::json!(256)); // message_size
         assert_eq!(values[4], serde_json::json!(7500)); // one_way
         assert_eq!(values[5], serde_json::json!(14000)); // round_trip
+        assert_eq!(values[6], serde_json::json!(crate::run_id::get())); // run_id
     }
 
     #[test]
@@ -2681,6 +2953,10 @@ mod tests {
             ],
             total_samples: 100,
             histogram_data: vec![],
+            buckets: vec![],
+            histogram: None,
+            confidence: None,
+            percentile_intervals: Vec::new(),
         };
 
         let throughput = ThroughputMetrics {
@@ -2694,12 +2970,52 @@ mod tests {
         results.one_way_results = Some(PerformanceMetrics {
             latency: Some(latency.clone()),
             throughput: throughput.clone(),
+            error_count: 0,
+            expired_count: 0,
+            priority_latency: Vec::new(),
+            phase_latency: Vec::new(),
+            connection_latency: Vec::new(),
+            processing_latency: Vec::new(),
+            cpu_split: Vec::new(),
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_message: None,
+            first_latency_ns: None,
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            latency_reservoir: None,
+            goodput: None,
+            sla: Vec::new(),
             timestamp: chrono::Utc::now(),
         });
 
         results.round_trip_results = Some(PerformanceMetrics {
             latency: Some(latency),
             throughput,
+            error_count: 0,
+            expired_count: 0,
+            priority_latency: Vec::new(),
+            phase_latency: Vec::new(),
+            connection_latency: Vec::new(),
+            processing_latency: Vec::new(),
+            cpu_split: Vec::new(),
+            queue_depth: None,
+            pacing: None,
+            pair: None,
+            send_allocations: None,
+            first_message: None,
+            first_latency_ns: None,
+            affinity: Vec::new(),
+            producers: None,
+            subscribers: None,
+            server: None,
+            latency_reservoir: None,
+            goodput: None,
+            sla: Vec::new(),
             timestamp: chrono::Utc::now(),
         });
 
@@ -2830,9 +3146,9 @@ mod tests {
 
     #[test]
     fn test_format_latency_microseconds() {
-        // >= 1,000 ns but < 1,000,000 ns should format as us
+        // >= 1,000 ns but < 1,000,000 ns should format as μs
         let result = super::format_latency(5_000);
-        assert!(result.contains("us"));
+        assert!(result.contains("μs"));
         assert!(result.contains("5.00"));
     }
 
@@ -2854,15 +3170,15 @@ mod tests {
 
     #[test]
     fn test_format_latency_boundary_microseconds() {
-        // Exactly 1,000 ns = 1 us
+        // Exactly 1,000 ns = 1 μs
         let result = super::format_latency(1_000);
-        assert!(result.contains("us"));
+        assert!(result.contains("μs"));
         assert!(result.contains("1.00"));
     }
 
     #[test]
     fn test_format_latency_zero() {
         let result = super::format_latency(0);
-        assert!(result.contains("0 ns"));
+        assert_eq!(result, "0ns");
     }
 }
@@ -0,0 +1,69 @@
+//! Run identifier for correlating artifacts.
+//!
+//! Every invocation gets one ID, a random UUID unless `--run-id` supplies
+//! one. It is attached to log lines through the root [`span`], written into
+//! the final results metadata and appended to every per-message streaming
+//! record, so logs, streams and results from concurrent or repeated runs can
+//! be matched up. A spawned server process is handed the parent's ID, and
+//! split client/server runs can share one by passing the same `--run-id`.
+//! IDs become file and URL path components, so they are limited to ASCII
+//! letters, digits, `.`, `_` and `-`.
+
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+static RUN_ID: OnceLock<String> = OnceLock::new();
+
+/// Set this process's run ID, generating one if `id` is `None`.
+///
+/// Only the first call has any effect; later calls return the ID already in
+/// use.
+pub fn init(id: Option<&str>) -> &'static str {
+    RUN_ID.get_or_init(|| id.map_or_else(|| Uuid::new_v4().to_string(), str::to_string))
+}
+
+/// Parse `--run-id`, rejecting IDs that are not safe as a path component.
+pub fn parse(id: &str) -> Result<String, String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+    if id.is_empty() || id == "." || id == ".." || !id.chars().all(allowed) {
+        return Err(format!(
+            "invalid run ID '{}': use ASCII letters, digits, '.', '_' and '-'",
+            id
+        ));
+    }
+    Ok(id.to_string())
+}
+
+/// This process's run ID, generated on first use if [`init`] was not called.
+pub fn get() -> &'static str {
+    init(None)
+}
+
+/// The root tracing span carrying the run ID as its `run_id` field.
+///
+/// Enter it once logging is initialized; threads and tasks that should log
+/// under it need the span carried over (see [`crate::utils::spawn_with_affinity`]).
+pub fn span() -> tracing::Span {
+    tracing::info_span!("run", run_id = %get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_id_is_stable() {
+        let id = get();
+        assert!(!id.is_empty());
+        assert_eq!(init(Some("other")), id);
+        assert_eq!(get(), id);
+    }
+
+    #[test]
+    fn test_parse_run_id() {
+        assert_eq!(parse("nightly-42_v1.2").unwrap(), "nightly-42_v1.2");
+        for bad in ["", "..", "../etc", "a/b", "run 1", "run;id", "ünï"] {
+            assert!(parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+}
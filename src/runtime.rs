@@ -0,0 +1,173 @@
+//! Tokio runtime configuration for async mode.
+//!
+//! Async-mode latency includes the runtime's scheduling: how tasks are woken,
+//! which worker runs them and how often the scheduler stops to poll for I/O.
+//! [`RuntimeConfig`] collects the `--runtime`, `--worker-threads` and
+//! `--event-interval` settings, builds the runtime from them and is recorded
+//! with each async result so the scheduler that produced it is known.
+//!
+//! Library users who bring their own runtime can drive a
+//! [`BenchmarkRunner`](crate::BenchmarkRunner) on it with
+//! [`run_on`](crate::BenchmarkRunner::run_on) and describe it with
+//! [`with_runtime_config`](crate::BenchmarkRunner::with_runtime_config).
+
+#[cfg(feature = "cli")]
+use crate::cli::Args;
+use crate::options::RuntimeFlavor;
+use serde::{Deserialize, Serialize};
+use std::io;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// Settings of the Tokio runtime an async benchmark ran on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeConfig {
+    /// Scheduler flavor
+    pub flavor: RuntimeFlavor,
+
+    /// Worker threads of the multi-thread runtime (`None`: Tokio's default
+    /// of one per CPU core, or not known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_threads: Option<usize>,
+
+    /// Scheduler ticks between I/O and timer polls (`None`: Tokio's default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_interval: Option<u32>,
+}
+
+impl RuntimeConfig {
+    /// The runtime requested on the command line.
+    #[cfg(feature = "cli")]
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            flavor: args.runtime,
+            worker_threads: args.worker_threads,
+            event_interval: args.event_interval,
+        }
+    }
+
+    /// The flavor of the runtime behind `handle`; its other settings can't
+    /// be read back and are left unset.
+    pub fn of_handle(handle: &Handle) -> Self {
+        let flavor = match handle.runtime_flavor() {
+            tokio::runtime::RuntimeFlavor::CurrentThread => RuntimeFlavor::CurrentThread,
+            _ => RuntimeFlavor::MultiThread,
+        };
+        Self {
+            flavor,
+            ..Default::default()
+        }
+    }
+
+    /// Build a runtime with these settings and all drivers enabled.
+    pub fn build(&self) -> io::Result<Runtime> {
+        let mut builder = match self.flavor {
+            RuntimeFlavor::CurrentThread => {
+                if self.worker_threads.is_some() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "--worker-threads requires --runtime multi-thread",
+                    ));
+                }
+                Builder::new_current_thread()
+            }
+            RuntimeFlavor::MultiThread => {
+                let mut builder = Builder::new_multi_thread();
+                if let Some(threads) = self.worker_threads {
+                    if threads == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "--worker-threads must be at least 1",
+                        ));
+                    }
+                    builder.worker_threads(threads);
+                }
+                builder
+            }
+        };
+        if let Some(interval) = self.event_interval {
+            builder.event_interval(interval);
+        }
+        builder.enable_all().build()
+    }
+
+    /// Command-line arguments that reproduce these settings, for passing
+    /// to a spawned server process.
+    pub fn to_cli_args(&self) -> Vec<String> {
+        let mut args = vec!["--runtime".to_string(), self.flavor.to_string()];
+        if let Some(threads) = self.worker_threads {
+            args.extend(["--worker-threads".to_string(), threads.to_string()]);
+        }
+        if let Some(interval) = self.event_interval {
+            args.extend(["--event-interval".to_string(), interval.to_string()]);
+        }
+        args
+    }
+}
+
+impl std::fmt::Display for RuntimeConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.flavor)?;
+        if let Some(threads) = self.worker_threads {
+            write!(f, ", {} workers", threads)?;
+        }
+        if let Some(interval) = self.event_interval {
+            write!(f, ", event interval {}", interval)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_runtime_config_from_args() {
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--runtime",
+            "current-thread",
+            "--event-interval",
+            "7",
+        ]);
+        let config = RuntimeConfig::from_args(&args);
+        assert_eq!(config.flavor, RuntimeFlavor::CurrentThread);
+        assert_eq!(config.to_string(), "current-thread, event interval 7");
+        assert_eq!(
+            config.to_cli_args(),
+            ["--runtime", "current-thread", "--event-interval", "7"]
+        );
+
+        let runtime = config.build().unwrap();
+        assert_eq!(
+            RuntimeConfig::of_handle(runtime.handle()).flavor,
+            RuntimeFlavor::CurrentThread
+        );
+    }
+
+    #[test]
+    fn test_runtime_config_build() {
+        let config = RuntimeConfig {
+            flavor: RuntimeFlavor::MultiThread,
+            worker_threads: Some(2),
+            event_interval: None,
+        };
+        let runtime = config.build().unwrap();
+        assert_eq!(
+            RuntimeConfig::of_handle(runtime.handle()).flavor,
+            RuntimeFlavor::MultiThread
+        );
+
+        let invalid = RuntimeConfig {
+            flavor: RuntimeFlavor::CurrentThread,
+            ..config
+        };
+        assert!(invalid.build().is_err());
+        let no_workers = RuntimeConfig {
+            worker_threads: Some(0),
+            ..config
+        };
+        assert!(no_workers.build().is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Scheduler wakeup latency measured alongside each test.
+//!
+//! A P99.9 of 2ms says little on its own: the transport may have stalled,
+//! or the OS may have left a runnable thread waiting for a CPU. With
+//! `--scheduler-noise-interval`, a [`NoiseProbe`] thread sleeps for the
+//! interval over and over while a test runs and records how late each
+//! wakeup came. The [`SchedulerNoise`] report puts the test's tail latencies
+//! next to the tail of the OS's own wakeup delay.
+//!
+//! The probe is not pinned, so it sees the noise any unpinned thread would.
+//! Each wakeup costs a little CPU; short intervals on a loaded host add to
+//! the noise being measured.
+
+use crate::metrics::utils::format_latency;
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How late the probe's wakeups came over one test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerNoise {
+    /// Interval the probe slept for, in nanoseconds
+    pub interval_ns: u64,
+
+    /// Wakeups recorded
+    pub wakeups: u64,
+
+    /// Mean oversleep, in nanoseconds
+    pub mean_ns: f64,
+
+    /// Median oversleep, in nanoseconds
+    pub p50_ns: u64,
+
+    /// 99th percentile oversleep, in nanoseconds
+    pub p99_ns: u64,
+
+    /// 99.9th percentile oversleep, in nanoseconds
+    pub p99_9_ns: u64,
+
+    /// Largest oversleep, in nanoseconds
+    pub max_ns: u64,
+}
+
+impl SchedulerNoise {
+    /// Summarize the oversleeps in `histogram`; `None` without samples.
+    fn from_histogram(interval: Duration, histogram: &Histogram<u64>) -> Option<Self> {
+        if histogram.is_empty() {
+            return None;
+        }
+        Some(Self {
+            interval_ns: interval.as_nanos() as u64,
+            wakeups: histogram.len(),
+            mean_ns: histogram.mean(),
+            p50_ns: histogram.value_at_quantile(0.50),
+            p99_ns: histogram.value_at_quantile(0.99),
+            p99_9_ns: histogram.value_at_quantile(0.999),
+            max_ns: histogram.max(),
+        })
+    }
+}
+
+impl std::fmt::Display for SchedulerNoise {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "wakeups {} late at P50, {} at P99, {} at P99.9, {} max ({} sleeps of {})",
+            format_latency(self.p50_ns),
+            format_latency(self.p99_ns),
+            format_latency(self.p99_9_ns),
+            format_latency(self.max_ns),
+            self.wakeups,
+            format_latency(self.interval_ns)
+        )
+    }
+}
+
+/// Sleeps repeatedly on a background thread from the start of a test until
+/// [`finish`](Self::finish), recording how late each wakeup came.
+///
+/// Dropping the probe without `finish`, as when a test fails, stops the
+/// thread and discards the samples.
+pub struct NoiseProbe {
+    interval: Duration,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Histogram<u64>>>,
+}
+
+impl NoiseProbe {
+    /// Start probing with sleeps of `interval`, if one is given.
+    pub fn start(interval: Option<Duration>) -> Option<Self> {
+        let interval = interval.filter(|interval| !interval.is_zero())?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
+        let handle = std::thread::Builder::new()
+            .name("scheduler-noise".to_string())
+            .spawn(move || {
+                let mut histogram = Histogram::<u64>::new(3).expect("valid histogram precision");
+                loop {
+                    let asleep = Instant::now();
+                    std::thread::park_timeout(interval);
+                    let slept = asleep.elapsed();
+                    if stop_flag.load(Ordering::Acquire) {
+                        break;
+                    }
+                    // park_timeout may return early; only full sleeps count
+                    if let Some(late) = slept.checked_sub(interval) {
+                        // Recording resizes the histogram as needed; it fails
+                        // only when memory does
+                        let _ = histogram.record(late.as_nanos() as u64);
+                    }
+                }
+                histogram
+            })
+            .map_err(|e| tracing::debug!("Cannot start the scheduler noise probe: {}", e))
+            .ok()?;
+        Some(Self {
+            interval,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Stop probing and summarize the wakeups recorded.
+    pub fn finish(mut self) -> Option<SchedulerNoise> {
+        let handle = self.handle.take()?;
+        self.stop.store(true, Ordering::Release);
+        handle.thread().unpark();
+        let histogram = handle.join().ok()?;
+        SchedulerNoise::from_histogram(self.interval, &histogram)
+    }
+}
+
+impl Drop for NoiseProbe {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = &self.handle {
+            handle.thread().unpark();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_histogram() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        assert!(SchedulerNoise::from_histogram(Duration::from_millis(1), &histogram).is_none());
+
+        for late_ns in [10_000, 20_000, 30_000, 1_000_000] {
+            histogram.record(late_ns).unwrap();
+        }
+        let noise = SchedulerNoise::from_histogram(Duration::from_millis(1), &histogram).unwrap();
+        assert_eq!(noise.interval_ns, 1_000_000);
+        assert_eq!(noise.wakeups, 4);
+        assert!((noise.mean_ns - 265_000.0).abs() < 1_000.0);
+        // Within the histogram's three significant digits
+        assert_eq!(noise.p50_ns / 100, 200);
+        assert_eq!(noise.max_ns / 1000, 1000);
+    }
+
+    #[test]
+    fn test_display() {
+        let noise = SchedulerNoise {
+            interval_ns: 1_000_000,
+            wakeups: 4,
+            mean_ns: 265_000.0,
+            p50_ns: 20_000,
+            p99_ns: 1_000_000,
+            p99_9_ns: 1_000_000,
+            max_ns: 1_000_000,
+        };
+        assert_eq!(
+            noise.to_string(),
+            "wakeups 20.00μs late at P50, 1.00ms at P99, 1.00ms at P99.9, 1.00ms max \
+             (4 sleeps of 1.00ms)"
+        );
+    }
+
+    #[test]
+    fn test_probe_records_until_finished() {
+        assert!(NoiseProbe::start(None).is_none());
+        assert!(NoiseProbe::start(Some(Duration::ZERO)).is_none());
+
+        let probe = NoiseProbe::start(Some(Duration::from_millis(1))).unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+        let noise = probe.finish().unwrap();
+        assert!(noise.wakeups >= 2);
+        assert!(noise.max_ns >= noise.p50_ns);
+    }
+}
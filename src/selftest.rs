@@ -0,0 +1,171 @@
+//! Loopback self-test of the transports.
+//!
+//! `ipc-benchmark selftest` checks that each mechanism works on this host
+//! before a long campaign is started against it. For every mechanism a
+//! server process is spawned as in a blocking benchmark and a handful of
+//! messages is exchanged in round trips, each reply matched against the ID
+//! of its request. A mechanism passes when every message came back without
+//! an error; its round-trip latency is reported as a sanity check of the
+//! host, not as a measurement. The blocking SHM transports and UDP
+//! multicast have no way to reply, so they are checked one way, passing
+//! when the server received every message.
+
+use crate::benchmark::BenchmarkConfig;
+use crate::benchmark_blocking::BlockingBenchmarkRunner;
+use crate::cli::{Args, SelftestArgs};
+use crate::mechanism::IpcMechanism;
+use crate::metrics::utils::format_latency;
+use crate::metrics::PerformanceMetrics;
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// How one mechanism fared
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Every message came back; median and maximum latency in nanoseconds
+    Passed { median_ns: u64, max_ns: u64 },
+
+    /// The mechanism could not be set up or lost messages
+    Failed(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Passed { median_ns, max_ns } => write!(
+                f,
+                "PASS  P50 {}, max {}",
+                format_latency(*median_ns),
+                format_latency(*max_ns)
+            ),
+            Outcome::Failed(reason) => write!(f, "FAIL  {}", reason),
+        }
+    }
+}
+
+/// Whether the mechanism can only be tested one way: the blocking SHM
+/// transports have no reply channel and multicast subscribers cannot reply.
+fn one_way_only(mechanism: IpcMechanism) -> bool {
+    #[cfg(target_os = "linux")]
+    if mechanism == IpcMechanism::UdpMulticast {
+        return true;
+    }
+    mechanism == IpcMechanism::SharedMemory
+}
+
+/// Benchmark arguments that exchange `selftest`'s messages over `mechanism`,
+/// keeping the transport options given before the subcommand.
+fn test_args(base: &Args, selftest: &SelftestArgs, mechanism: IpcMechanism) -> Args {
+    let one_way = one_way_only(mechanism);
+    Args {
+        mechanisms: vec![mechanism],
+        command: None,
+        blocking: true,
+        one_way,
+        round_trip: !one_way,
+        msg_count: selftest.msg_count,
+        duration: None,
+        message_size: selftest.message_size,
+        warmup_iterations: 0,
+        ..base.clone()
+    }
+}
+
+/// Judge the metrics of a test that should have carried `expected` messages.
+fn judge(metrics: Option<&PerformanceMetrics>, expected: usize) -> Outcome {
+    let Some(metrics) = metrics else {
+        return Outcome::Failed("no results".to_string());
+    };
+    let Some(latency) = &metrics.latency else {
+        return Outcome::Failed("no message came back".to_string());
+    };
+    if latency.total_samples != expected {
+        return Outcome::Failed(format!(
+            "{} of {} messages came back",
+            latency.total_samples, expected
+        ));
+    }
+    if metrics.error_count > 0 {
+        return Outcome::Failed(format!(
+            "{} mismatched or failed messages",
+            metrics.error_count
+        ));
+    }
+    Outcome::Passed {
+        median_ns: latency.median_ns as u64,
+        max_ns: latency.max_ns,
+    }
+}
+
+/// Exchange the self-test messages over `mechanism`.
+pub fn test_mechanism(base: &Args, selftest: &SelftestArgs, mechanism: IpcMechanism) -> Outcome {
+    let args = test_args(base, selftest, mechanism);
+    let run = BenchmarkConfig::from_args(&args)
+        .and_then(|config| BlockingBenchmarkRunner::new(config, mechanism, args.clone()).run(None));
+    match run {
+        Ok(results) => {
+            let metrics = if args.one_way {
+                results.one_way_results.as_ref()
+            } else {
+                results.round_trip_results.as_ref()
+            };
+            judge(metrics, selftest.msg_count)
+        }
+        Err(e) => Outcome::Failed(format!("{:#}", e)),
+    }
+}
+
+/// Run the `selftest` subcommand, failing when any mechanism failed.
+pub fn run_selftest(base: &Args, selftest: &SelftestArgs) -> Result<()> {
+    let mechanisms = IpcMechanism::expand_all(selftest.mechanisms.clone());
+    println!(
+        "Self-test: {} messages of {} bytes per mechanism",
+        selftest.msg_count, selftest.message_size
+    );
+    let mut failed = 0;
+    for mechanism in &mechanisms {
+        let outcome = test_mechanism(base, selftest, *mechanism);
+        if matches!(outcome, Outcome::Failed(_)) {
+            failed += 1;
+        }
+        println!("  {:<14} {}", mechanism.name(), outcome);
+    }
+    if failed > 0 {
+        bail!(
+            "{} of {} mechanisms failed the self-test",
+            failed,
+            mechanisms.len()
+        );
+    }
+    println!("All {} mechanisms passed", mechanisms.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{LatencyType, MetricsCollector};
+    use std::time::Duration;
+
+    #[test]
+    fn test_judge_requires_every_message() {
+        let mut collector =
+            MetricsCollector::new(Some(LatencyType::RoundTrip), vec![50.0]).unwrap();
+        for us in [10, 20, 30] {
+            collector
+                .record_message(64, Some(Duration::from_micros(us)))
+                .unwrap();
+        }
+        let metrics = collector.get_metrics();
+
+        assert!(matches!(
+            judge(Some(&metrics), 3),
+            Outcome::Passed { max_ns, .. } if max_ns >= 29_000
+        ));
+        assert_eq!(
+            judge(Some(&metrics), 4),
+            Outcome::Failed("3 of 4 messages came back".to_string())
+        );
+        assert!(matches!(judge(None, 3), Outcome::Failed(_)));
+    }
+}
@@ -0,0 +1,340 @@
+//! Message ID width, wraparound, and gap and reorder accounting.
+//!
+//! Message IDs normally count up from zero in 64 bits and never wrap, so
+//! tooling built on them may quietly assume they never will. Protocols with
+//! small sequence spaces, and long soak runs against 32-bit consumers, do
+//! wrap. `--id-width BITS` sends IDs in a space of that many bits, starting
+//! at `--id-start` and wrapping to zero after the largest, so a test can
+//! cross the wrap point as often as it likes.
+//!
+//! With a narrow ID space, the server tracks the IDs it receives with
+//! serial number arithmetic (RFC 1982): an ID less than half the space
+//! ahead of the next expected one is a gap, one behind it fills an earlier
+//! gap or repeats an ID already received. A jump of half the space or more
+//! cannot be told apart from an ID arriving late and is taken as one. The
+//! counts are reported under `sequence` in the server's report.
+
+#[cfg(feature = "cli")]
+use crate::cli::Args;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Narrowest ID space `--id-width` accepts
+pub const MIN_WIDTH: u8 = 8;
+
+/// Widest ID space `--id-width` accepts
+pub const MAX_WIDTH: u8 = 32;
+
+/// Missing IDs remembered for late arrivals; an ID that arrives after more
+/// than this many newer ones went missing counts as a duplicate
+pub const MAX_TRACKED_MISSING: usize = 1 << 16;
+
+/// The ID space of this process, once `--id-width` set one
+static ID_SPACE: OnceLock<IdSpace> = OnceLock::new();
+
+/// IDs of `width` bits, counting from `start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdSpace {
+    /// Bits in an ID
+    pub width: u8,
+
+    /// ID of the first message of each test
+    pub start: u64,
+}
+
+impl IdSpace {
+    /// IDs of `width` bits from `start`, which must fit in the width.
+    pub fn new(width: u8, start: u64) -> Result<Self> {
+        if !(MIN_WIDTH..=MAX_WIDTH).contains(&width) {
+            bail!(
+                "ID width must be {} to {} bits, not {}",
+                MIN_WIDTH,
+                MAX_WIDTH,
+                width
+            );
+        }
+        let space = Self { width, start };
+        if start > space.max_id() {
+            bail!(
+                "--id-start {} does not fit in {}-bit IDs (largest {})",
+                start,
+                width,
+                space.max_id()
+            );
+        }
+        Ok(space)
+    }
+
+    /// The ID space of `--id-width` and `--id-start`, if a width was given.
+    #[cfg(feature = "cli")]
+    pub fn from_args(args: &Args) -> Result<Option<Self>> {
+        args.id_width
+            .map(|width| Self::new(width, args.id_start))
+            .transpose()
+    }
+
+    /// The largest ID, after which IDs wrap to zero
+    pub fn max_id(&self) -> u64 {
+        (1 << self.width) - 1
+    }
+
+    /// ID of the `sequence`-th message of a test.
+    pub fn wire_id(&self, sequence: u64) -> u64 {
+        self.start.wrapping_add(sequence) & self.max_id()
+    }
+
+    /// How far `to` is ahead of `from`, the shorter way around the space;
+    /// negative when it is behind.
+    pub fn distance(&self, from: u64, to: u64) -> i64 {
+        let ahead = to.wrapping_sub(from) & self.max_id();
+        if ahead >> (self.width - 1) == 0 {
+            ahead as i64
+        } else {
+            ahead as i64 - (1i64 << self.width)
+        }
+    }
+}
+
+/// Use `space` for the rest of this process; later calls are ignored.
+pub fn set_id_space(space: IdSpace) {
+    let _ = ID_SPACE.set(space);
+}
+
+/// Whether ID `earlier` was sent before ID `later`, within the ID space
+/// set for this process.
+pub fn precedes(earlier: u64, later: u64) -> bool {
+    match ID_SPACE.get() {
+        Some(space) => space.distance(earlier, later) > 0,
+        None => earlier < later,
+    }
+}
+
+/// Hands out the IDs of a test's messages in order.
+///
+/// Clones share the count, so every client of a test draws from one
+/// sequence.
+#[derive(Debug, Clone)]
+pub struct IdSequence {
+    space: IdSpace,
+    sent: Arc<AtomicU64>,
+}
+
+impl IdSequence {
+    /// A sequence over `space`, starting at its start.
+    pub fn new(space: IdSpace) -> Self {
+        Self {
+            space,
+            sent: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// ID of the next message.
+    pub fn next_id(&self) -> u64 {
+        self.space
+            .wire_id(self.sent.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Start again from the space's start, for a new test.
+    pub fn restart(&self) {
+        self.sent.store(0, Ordering::Relaxed);
+    }
+}
+
+/// How the IDs of the messages a server received lined up
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceReport {
+    /// Bits in an ID
+    pub id_width: u8,
+
+    /// Messages tracked
+    pub received: u64,
+
+    /// IDs skipped and never received
+    pub lost: u64,
+
+    /// Jumps ahead past at least one ID
+    pub gaps: u64,
+
+    /// IDs skipped by the largest jump
+    pub max_gap: u64,
+
+    /// Skipped IDs that arrived later
+    pub reordered: u64,
+
+    /// IDs received again, or too late to be told from a repeat
+    pub duplicates: u64,
+
+    /// Times the IDs wrapped past the largest back to zero
+    pub wraps: u64,
+}
+
+impl fmt::Display for SequenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-bit IDs: {} lost in {} gaps (largest {}), {} reordered, {} duplicates, {} wraps",
+            self.id_width,
+            self.lost,
+            self.gaps,
+            self.max_gap,
+            self.reordered,
+            self.duplicates,
+            self.wraps
+        )
+    }
+}
+
+/// Follows received IDs across wraparound, counting gaps, late arrivals and
+/// repeats.
+///
+/// IDs are extended to an unwrapped count, so a gap that spans the wrap
+/// point is one gap, and an ID that fills it is matched after the wrap.
+#[derive(Debug, Clone)]
+pub struct SequenceTracker {
+    space: IdSpace,
+    first: Option<u64>,
+    next: u64,
+    missing: BTreeSet<u64>,
+    report: SequenceReport,
+}
+
+impl SequenceTracker {
+    /// Track IDs of `space`.
+    pub fn new(space: IdSpace) -> Self {
+        Self {
+            space,
+            first: None,
+            next: 0,
+            missing: BTreeSet::new(),
+            report: SequenceReport {
+                id_width: space.width,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Record a received message ID.
+    pub fn record(&mut self, id: u64) {
+        self.report.received += 1;
+        if self.first.is_none() {
+            // Extended IDs start one lap in, so late arrivals of IDs before
+            // the first stay positive
+            let extended = (1 << self.space.width) + (id & self.space.max_id());
+            self.first = Some(extended);
+            self.next = extended + 1;
+            return;
+        }
+        let distance = self.space.distance(self.next & self.space.max_id(), id);
+        let extended = (self.next as i64 + distance) as u64;
+        match distance {
+            0 => self.next += 1,
+            skipped if skipped > 0 => {
+                let skipped = skipped as u64;
+                self.report.gaps += 1;
+                self.report.lost += skipped;
+                self.report.max_gap = self.report.max_gap.max(skipped);
+                let tracked_from = extended.saturating_sub(MAX_TRACKED_MISSING as u64);
+                for missing in self.next.max(tracked_from)..extended {
+                    self.missing.insert(missing);
+                    if self.missing.len() > MAX_TRACKED_MISSING {
+                        self.missing.pop_first();
+                    }
+                }
+                self.next = extended + 1;
+            }
+            _ => {
+                if self.missing.remove(&extended) {
+                    self.report.reordered += 1;
+                    self.report.lost -= 1;
+                } else {
+                    self.report.duplicates += 1;
+                }
+            }
+        }
+    }
+
+    /// The counts so far.
+    pub fn report(&self) -> SequenceReport {
+        let laps = |extended: u64| extended >> self.space.width;
+        SequenceReport {
+            wraps: self
+                .first
+                .map_or(0, |first| laps(self.next - 1) - laps(first)),
+            ..self.report.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(space: IdSpace, ids: &[u64]) -> SequenceReport {
+        let mut tracker = SequenceTracker::new(space);
+        for &id in ids {
+            tracker.record(id);
+        }
+        tracker.report()
+    }
+
+    #[test]
+    fn test_id_space() {
+        let space = IdSpace::new(8, 250).unwrap();
+        assert_eq!(space.max_id(), 255);
+        assert_eq!(space.wire_id(0), 250);
+        assert_eq!(space.wire_id(5), 255);
+        assert_eq!(space.wire_id(6), 0);
+        assert_eq!(space.distance(254, 2), 4);
+        assert_eq!(space.distance(2, 254), -4);
+        assert_eq!(space.distance(7, 7), 0);
+
+        assert!(IdSpace::new(8, 256).is_err());
+        assert!(IdSpace::new(4, 0).is_err());
+        assert!(IdSpace::new(64, 0).is_err());
+        assert_eq!(IdSpace::new(32, u32::MAX as u64).unwrap().wire_id(1), 0);
+    }
+
+    #[test]
+    fn test_sequence_restarts_and_is_shared() {
+        let sequence = IdSequence::new(IdSpace::new(8, 255).unwrap());
+        let clone = sequence.clone();
+        assert_eq!(sequence.next_id(), 255);
+        assert_eq!(clone.next_id(), 0);
+        sequence.restart();
+        assert_eq!(clone.next_id(), 255);
+    }
+
+    #[test]
+    fn test_in_order_ids_across_wraps() {
+        let space = IdSpace::new(8, 200).unwrap();
+        let ids: Vec<u64> = (0..1000).map(|i| space.wire_id(i)).collect();
+        let report = track(space, &ids);
+        assert_eq!(report.received, 1000);
+        assert_eq!((report.lost, report.gaps, report.reordered), (0, 0, 0));
+        assert_eq!(report.duplicates, 0);
+        // 200 + 999 = 1199 ends in lap 4 of 256 IDs
+        assert_eq!(report.wraps, 4);
+    }
+
+    #[test]
+    fn test_gap_and_late_arrival_spanning_the_wrap() {
+        let space = IdSpace::new(8, 0).unwrap();
+        // 255 and 0 are skipped at the wrap point, then 0 arrives late and
+        // 2 is repeated
+        let report = track(space, &[253, 254, 1, 2, 0, 2, 3]);
+        assert_eq!(report.gaps, 1);
+        assert_eq!(report.max_gap, 2);
+        assert_eq!(report.reordered, 1);
+        assert_eq!(report.lost, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.wraps, 1);
+        assert_eq!(
+            report.to_string(),
+            "8-bit IDs: 1 lost in 1 gaps (largest 2), 1 reordered, 1 duplicates, 1 wraps"
+        );
+    }
+}
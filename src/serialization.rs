@@ -0,0 +1,130 @@
+//! What serde costs the SHM ring per message.
+//!
+//! With `--zero-copy` the ring carries fixed-layout records (see
+//! [`crate::ipc::fixed_layout`]) instead of bincode, so the measured
+//! latencies no longer include serialization. [`SerializationCost::measure`]
+//! puts a number on the difference: it runs messages of the test's size
+//! through both record formats against an in-memory ring, doing the same
+//! copies the transport does, and times each path on its own.
+
+use crate::ipc::{fixed_layout, Message, MessageType};
+use crate::metrics::utils::format_latency;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Bytes pushed through each path, spread over the iterations
+const MEASURED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Per-message cost of the two record formats for one message size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializationCost {
+    /// Payload size the formats were measured with, in bytes
+    pub message_size: usize,
+
+    /// Mean time to send and receive a bincode record, in nanoseconds
+    pub bincode_ns: f64,
+
+    /// Mean time to send and receive a fixed-layout record, in nanoseconds
+    pub fixed_layout_ns: f64,
+}
+
+impl SerializationCost {
+    /// Time both record formats for messages of `message_size` bytes.
+    pub fn measure(message_size: usize) -> Result<Self> {
+        let iterations = (MEASURED_BYTES / message_size.max(1)).clamp(16, 10_000);
+        let message = Message::new(0, vec![0xA5; message_size], MessageType::OneWay);
+        let mut ring = vec![0u8; message_size + fixed_layout::HEADER_SIZE + 64];
+        let mut buffer = Vec::new();
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            message.serialize_unstamped_into(&mut buffer)?;
+            ring[..buffer.len()].copy_from_slice(&buffer);
+            let record = ring[..buffer.len()].to_vec();
+            let received: Message =
+                bincode::deserialize(&record).context("Failed to deserialize message")?;
+            black_box(received);
+        }
+        let bincode_ns = start.elapsed().as_nanos() as f64 / iterations as f64;
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let header = fixed_layout::header(&message);
+            let len = header.len() + message.payload.len();
+            ring[..header.len()].copy_from_slice(&header);
+            ring[header.len()..len].copy_from_slice(&message.payload);
+            let mut head = [0u8; fixed_layout::HEADER_SIZE];
+            head.copy_from_slice(&ring[..header.len()]);
+            let payload = ring[header.len()..len].to_vec();
+            black_box(fixed_layout::decode(&head, payload));
+        }
+        let fixed_layout_ns = start.elapsed().as_nanos() as f64 / iterations as f64;
+
+        Ok(Self {
+            message_size,
+            bincode_ns,
+            fixed_layout_ns,
+        })
+    }
+
+    /// How much less a fixed-layout record costs than a bincode one, in
+    /// nanoseconds; negative when it costs more
+    pub fn saved_ns(&self) -> f64 {
+        self.bincode_ns - self.fixed_layout_ns
+    }
+}
+
+impl std::fmt::Display for SerializationCost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let saved_ns = self.saved_ns();
+        write!(
+            f,
+            "bincode {} per message, fixed layout {} ({} {})",
+            format_latency(self.bincode_ns as u64),
+            format_latency(self.fixed_layout_ns as u64),
+            format_latency(saved_ns.abs() as u64),
+            if saved_ns < 0.0 { "lost" } else { "saved" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let cost = SerializationCost::measure(64).unwrap();
+        assert_eq!(cost.message_size, 64);
+        assert!(cost.bincode_ns > 0.0);
+        assert!(cost.fixed_layout_ns > 0.0);
+        assert!((cost.saved_ns() - (cost.bincode_ns - cost.fixed_layout_ns)).abs() < 1e-9);
+
+        // Empty payloads still carry a header
+        assert!(SerializationCost::measure(0).is_ok());
+    }
+
+    #[test]
+    fn test_display() {
+        let cost = SerializationCost {
+            message_size: 1024,
+            bincode_ns: 1_500.0,
+            fixed_layout_ns: 400.0,
+        };
+        assert_eq!(
+            cost.to_string(),
+            "bincode 1.50μs per message, fixed layout 400ns (1.10μs saved)"
+        );
+
+        let slower = SerializationCost {
+            fixed_layout_ns: 2_500.0,
+            ..cost
+        };
+        assert_eq!(
+            slower.to_string(),
+            "bincode 1.50μs per message, fixed layout 2.50μs (1.00μs lost)"
+        );
+    }
+}
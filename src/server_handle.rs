@@ -0,0 +1,1016 @@
+//! Lifecycle management for spawned benchmark servers.
+//!
+//! Both runners start their server as a child process running this binary
+//! with `--internal-run-as-server`. The child's stdout is a pipe back to the
+//! parent, carrying a small protocol of length-prefixed frames: a one-byte
+//! kind, the payload length as a little-endian `u32`, then the payload.
+//!
+//! 1. Once its transport is listening, the server sends a ready frame with
+//!    the port it bound.
+//! 2. From then on, a heartbeat frame every [`HEARTBEAT_INTERVAL`], with
+//!    the number of messages it has received so far.
+//! 3. When its receive loop ends, it sends a report frame: a JSON
+//!    [`ServerReport`] of what it received, the errors it hit, its CPU time,
+//!    the latency it measured on the receive side and the gaps between
+//!    arrivals.
+//!
+//! [`ServerHandle`] owns that child and pipe so callers do not have to
+//! repeat the signaling protocol. Frames of kinds it does not know are
+//! skipped, and a server that exits without a report is not an error; the
+//! parent just has no server-side data for that test.
+//!
+//! ## Liveness
+//!
+//! Once the server is ready, a thread in the parent reads its frames. With
+//! a heartbeat timeout set, a server that goes that long without a
+//! heartbeat (stopped, hung, or stuck writing its stdout) is killed as
+//! wedged, which makes the client's transport fail instead of waiting out
+//! the test. [`ServerLiveness::check`] lets clients that poll with
+//! timeouts notice sooner, and [`ServerHandle::shutdown`] then fails with
+//! what the server last reported.
+//!
+//! ## Typical Lifecycle
+//!
+//! ```rust,no_run
+//! use ipc_benchmark::ipc::TransportConfig;
+//! use ipc_benchmark::server_handle::ServerHandle;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut cmd = ServerHandle::command()?;
+//! cmd.args(["-m", "tcp", "-i", "1000", "--port", "0"]);
+//! let mut server = ServerHandle::spawn(cmd)?;
+//! let endpoint = server.wait_ready(&TransportConfig::default())?;
+//! println!("server listening on port {}", endpoint.port);
+//! // ... run a client against `endpoint` ...
+//! server.shutdown()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Orchestration layers that run the server elsewhere (in a container, over
+//! SSH) can pass any [`Command`] to [`ServerHandle::spawn`], as long as the
+//! remote server's stdout is relayed back unchanged.
+//! [`ServerHandle::remote_command`] builds such a command for SSH, which is
+//! what `--server-remote` uses.
+
+use crate::ipc::{framing, get_process_cpu_time_ns, TransportConfig, TransportStats};
+use crate::metrics::{LatencyCollector, LatencyMetrics, LatencyType};
+use crate::results::CpuUsage;
+use crate::sequence::{IdSpace, SequenceReport, SequenceTracker};
+use anyhow::{bail, Context, Result};
+use os_pipe::PipeReader;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::os::unix::io::FromRawFd;
+#[cfg(windows)]
+use std::os::windows::io::{FromRawHandle, IntoRawHandle};
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, warn};
+
+/// Length of a frame header: the kind byte and the payload length.
+pub const FRAME_HEADER_LEN: usize = 5;
+
+/// Frame kind of the readiness signal
+const FRAME_READY: u8 = 1;
+
+/// Frame kind of the server's [`ServerReport`]
+const FRAME_REPORT: u8 = 2;
+
+/// Frame kind of a heartbeat
+const FRAME_HEARTBEAT: u8 = 3;
+
+/// How often a ready server sends a heartbeat.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Largest payload accepted; a longer frame means stdout is not carrying
+/// the protocol at all.
+const MAX_FRAME_LEN: usize = 16 << 20;
+
+/// What a spawned server observed during one test.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerReport {
+    /// Messages the server received, not counting canaries and shutdowns
+    pub received: u64,
+
+    /// Replies the server failed to send
+    pub send_errors: u64,
+
+    /// Receive error that ended the server loop, if one did; with stream
+    /// transports this is usually just the client disconnecting
+    pub end_error: Option<String>,
+
+    /// CPU time of the server process over its receive loop
+    pub cpu: Option<CpuUsage>,
+
+    /// Time from each message's send timestamp to the server receiving it;
+    /// for round trips, the request leg
+    pub receive_latency: Option<LatencyMetrics>,
+
+    /// Time between consecutive messages arriving at the server, which
+    /// shows how bursty delivery is regardless of the sender's pacing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inter_arrival: Option<LatencyMetrics>,
+
+    /// Time the server's per-message work took (`--server-work`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing: Option<LatencyMetrics>,
+
+    /// Transient failures the server retried while starting its transport
+    #[serde(default)]
+    pub setup_retries: u32,
+
+    /// Stream frames the server dropped for failing their checksum
+    #[serde(default)]
+    pub torn_frames: u64,
+
+    /// Lost, reordered and duplicate message IDs (`--id-width`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<SequenceReport>,
+
+    /// Transport-specific counters of the server's transport
+    #[serde(default, skip_serializing_if = "TransportStats::is_empty")]
+    pub transport_stats: TransportStats,
+}
+
+/// Accumulates a server's [`ServerReport`] while it serves.
+pub struct ServerTally {
+    report: ServerReport,
+    progress: Arc<AtomicU64>,
+    latency: LatencyCollector,
+    inter_arrival: LatencyCollector,
+    last_arrival_ns: Option<u64>,
+    processing: Option<LatencyCollector>,
+    percentiles: Vec<f64>,
+    started: Instant,
+    cpu_start: Option<u64>,
+    torn_start: u64,
+    sequence: Option<SequenceTracker>,
+}
+
+impl ServerTally {
+    /// Start tallying, reporting latency at `percentiles`.
+    pub fn new(percentiles: Vec<f64>) -> Result<Self> {
+        Ok(Self {
+            report: ServerReport::default(),
+            progress: Arc::new(AtomicU64::new(0)),
+            latency: LatencyCollector::new(LatencyType::OneWay)?,
+            inter_arrival: LatencyCollector::new(LatencyType::InterArrival)?,
+            last_arrival_ns: None,
+            processing: None,
+            percentiles,
+            started: Instant::now(),
+            cpu_start: get_process_cpu_time_ns(),
+            torn_start: framing::torn_frames(),
+            sequence: None,
+        })
+    }
+
+    /// Track the IDs of received messages in `space` (`--id-width`).
+    pub fn track_sequence(&mut self, space: IdSpace) {
+        self.sequence = Some(SequenceTracker::new(space));
+    }
+
+    /// Record message `message_id`, received at monotonic time
+    /// `receive_time_ns`, `latency_ns` after it was sent.
+    ///
+    /// Canaries and shutdowns (ID `u64::MAX`) are not test traffic and are
+    /// left out.
+    pub fn record(&mut self, message_id: u64, receive_time_ns: u64, latency_ns: u64) -> Result<()> {
+        if message_id == u64::MAX {
+            return Ok(());
+        }
+        self.report.received += 1;
+        self.progress.store(self.report.received, Ordering::Relaxed);
+        if let Some(sequence) = self.sequence.as_mut() {
+            sequence.record(message_id);
+        }
+        if let Some(last) = self.last_arrival_ns.replace(receive_time_ns) {
+            self.inter_arrival
+                .record(Duration::from_nanos(receive_time_ns.saturating_sub(last)))?;
+        }
+        self.latency
+            .record(std::time::Duration::from_nanos(latency_ns))
+    }
+
+    /// Record the time the server's work on one message took.
+    pub fn processed(&mut self, work: Duration) -> Result<()> {
+        let collector = match self.processing.as_mut() {
+            Some(collector) => collector,
+            None => self
+                .processing
+                .insert(LatencyCollector::new(LatencyType::OneWay)?),
+        };
+        collector.record(work)
+    }
+
+    /// Messages received so far, for [`start_heartbeats`].
+    pub fn progress(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.progress)
+    }
+
+    /// Count a reply the server could not send.
+    pub fn send_failed(&mut self) {
+        self.report.send_errors += 1;
+    }
+
+    /// Note the receive error that ended the server loop.
+    pub fn ended_by(&mut self, error: &anyhow::Error) {
+        self.report.end_error = Some(format!("{:#}", error));
+    }
+
+    /// The report, with CPU time measured up to now.
+    pub fn finish(mut self) -> ServerReport {
+        let wall_time_ns = self.started.elapsed().as_nanos() as u64;
+        self.report.cpu = self
+            .cpu_start
+            .zip(get_process_cpu_time_ns())
+            .map(|(start, end)| CpuUsage::new(end.saturating_sub(start), wall_time_ns));
+        if self.report.received > 0 {
+            self.report.receive_latency = Some(self.latency.get_metrics(&self.percentiles));
+        }
+        if self.report.received > 1 {
+            self.report.inter_arrival = Some(self.inter_arrival.get_metrics(&self.percentiles));
+        }
+        self.report.processing = self
+            .processing
+            .map(|collector| collector.get_metrics(&self.percentiles));
+        self.report.torn_frames = framing::torn_frames().saturating_sub(self.torn_start);
+        self.report.sequence = self.sequence.as_ref().map(SequenceTracker::report);
+        self.report
+    }
+}
+
+/// A frame of `kind` carrying `payload`.
+fn frame(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.push(kind);
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Read the next frame, or `None` once the server has closed its stdout.
+fn read_frame(reader: &mut impl Read) -> Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > MAX_FRAME_LEN {
+        bail!("Server sent a {}-byte frame; its stdout is corrupt", len);
+    }
+    let mut payload = vec![0u8; len];
+    reader
+        .read_exact(&mut payload)
+        .context("Server output ended in the middle of a frame")?;
+    Ok(Some((header[0], payload)))
+}
+
+/// Build the readiness signal for a server listening on `local_port`.
+///
+/// The payload is the TCP port the server bound, as a little-endian `u16`
+/// (0 for mechanisms without ports). Servers bind port 0, so this is how
+/// the client learns the ephemeral port.
+pub fn server_ready_signal(local_port: Option<u16>) -> Vec<u8> {
+    frame(FRAME_READY, &local_port.unwrap_or(0).to_le_bytes())
+}
+
+/// Build the frame carrying a server's `report`.
+pub fn server_report_frame(report: &ServerReport) -> Result<Vec<u8>> {
+    Ok(frame(FRAME_REPORT, &serde_json::to_vec(report)?))
+}
+
+/// Send a heartbeat with `progress`, the messages received so far, every
+/// [`HEARTBEAT_INTERVAL`] until the process exits or the parent goes away.
+///
+/// Each frame is written under the stdout lock, so heartbeats never split
+/// the report frame.
+pub fn start_heartbeats(progress: Arc<AtomicU64>) {
+    let beat = move || loop {
+        std::thread::sleep(HEARTBEAT_INTERVAL);
+        let frame = frame(
+            FRAME_HEARTBEAT,
+            &progress.load(Ordering::Relaxed).to_le_bytes(),
+        );
+        let mut stdout = io::stdout().lock();
+        if stdout
+            .write_all(&frame)
+            .and_then(|()| stdout.flush())
+            .is_err()
+        {
+            return;
+        }
+    };
+    if let Err(e) = std::thread::Builder::new()
+        .name("heartbeat".to_string())
+        .spawn(beat)
+    {
+        warn!("Cannot send heartbeats to the parent: {}", e);
+    }
+}
+
+/// Wait for a spawned server's readiness signal.
+///
+/// Returns `transport_config` with the port the server reported filled in,
+/// so the client connects where the server is actually listening.
+pub fn read_server_ready(
+    reader: &mut impl Read,
+    transport_config: &TransportConfig,
+) -> Result<TransportConfig> {
+    let port = match read_frame(reader)? {
+        Some((FRAME_READY, payload)) if payload.len() == 2 => {
+            u16::from_le_bytes([payload[0], payload[1]])
+        }
+        Some((kind, _)) => bail!(
+            "Expected the server's ready signal, got frame kind {}",
+            kind
+        ),
+        None => bail!("Server exited before signaling readiness"),
+    };
+    let mut config = transport_config.clone();
+    if port != 0 {
+        config.port = port;
+    }
+    Ok(config)
+}
+
+/// Read frames until the server closes its stdout, returning its last
+/// report, if it sent one.
+pub fn read_server_report(reader: &mut impl Read) -> Result<Option<ServerReport>> {
+    read_frames(reader, |_| {})
+}
+
+/// Read frames until the server closes its stdout, passing the progress in
+/// each heartbeat to `on_heartbeat`.
+fn read_frames(
+    reader: &mut impl Read,
+    mut on_heartbeat: impl FnMut(u64),
+) -> Result<Option<ServerReport>> {
+    let mut report = None;
+    while let Some((kind, payload)) = read_frame(reader)? {
+        match kind {
+            FRAME_REPORT => {
+                report = Some(serde_json::from_slice(&payload).context("Malformed server report")?);
+            }
+            FRAME_HEARTBEAT if payload.len() == 8 => {
+                let mut progress = [0u8; 8];
+                progress.copy_from_slice(&payload);
+                on_heartbeat(u64::from_le_bytes(progress));
+            }
+            _ => debug!("Skipping server frame of kind {}", kind),
+        }
+    }
+    Ok(report)
+}
+
+/// Liveness of a ready server, as its heartbeats show it.
+///
+/// Clones share the state, so a client can check it from its own task.
+#[derive(Clone)]
+pub struct ServerLiveness {
+    shared: Arc<Liveness>,
+}
+
+struct Liveness {
+    state: Mutex<LivenessState>,
+    changed: Condvar,
+    pid: u32,
+}
+
+struct LivenessState {
+    /// When the last heartbeat arrived, or monitoring started
+    last_beat: Instant,
+    /// Messages received, as of the last heartbeat
+    received: u64,
+    /// The server closed its stdout, so it has exited or is exiting
+    closed: bool,
+    /// The handle is done with the server; nothing may be killed any more
+    released: bool,
+    /// Why the server was killed as wedged
+    wedged: Option<String>,
+}
+
+impl ServerLiveness {
+    fn new(pid: u32) -> Self {
+        Self {
+            shared: Arc::new(Liveness {
+                state: Mutex::new(LivenessState {
+                    last_beat: Instant::now(),
+                    received: 0,
+                    closed: false,
+                    released: false,
+                    wedged: None,
+                }),
+                changed: Condvar::new(),
+                pid,
+            }),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut LivenessState)) {
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state);
+        self.shared.changed.notify_all();
+    }
+
+    /// Why the server was killed as wedged, if it was.
+    fn wedged(&self) -> Option<String> {
+        let state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.wedged.clone()
+    }
+
+    /// Fail, with what the server last reported, once it has been killed
+    /// as wedged or has exited while the test still needs it.
+    pub fn check(&self) -> Result<()> {
+        let state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(wedged) = &state.wedged {
+            bail!("{}", wedged);
+        }
+        if state.closed {
+            bail!(
+                "Server {} exited during the test, after receiving {} messages",
+                self.shared.pid,
+                state.received
+            );
+        }
+        Ok(())
+    }
+
+    /// Kill the server once `timeout` passes without a heartbeat, unless it
+    /// exits or is released first.
+    fn watch(&self, timeout: Duration) {
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if state.closed || state.released {
+                return;
+            }
+            let silent = state.last_beat.elapsed();
+            if silent >= timeout {
+                let reason = format!(
+                    "Server {} sent no heartbeat for {:.1?}, after receiving {} messages; \
+                     killed it as wedged",
+                    self.shared.pid, silent, state.received
+                );
+                error!("{}", reason);
+                // The lock is held, so the handle cannot reap the server,
+                // and its PID cannot be reused, before this. The server
+                // leads its own process group; killing the group also
+                // closes the pipe in anything it started.
+                #[cfg(unix)]
+                unsafe {
+                    libc::kill(-(self.shared.pid as libc::pid_t), libc::SIGKILL);
+                }
+                state.wedged = Some(reason);
+                return;
+            }
+            state = self
+                .shared
+                .changed
+                .wait_timeout(state, timeout - silent)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+}
+
+/// Threads following a ready server's frames.
+struct Monitor {
+    liveness: ServerLiveness,
+    reader: JoinHandle<Result<Option<ServerReport>>>,
+}
+
+impl Monitor {
+    /// Read the frames of server `pid` from `ready`, killing the server if
+    /// its heartbeats stop for `heartbeat_timeout`.
+    fn start(mut ready: PipeReader, pid: u32, heartbeat_timeout: Option<Duration>) -> Result<Self> {
+        let liveness = ServerLiveness::new(pid);
+        let state = liveness.clone();
+        let reader = std::thread::Builder::new()
+            .name(format!("server-{}", pid))
+            .spawn(move || {
+                let report = read_frames(&mut ready, |received| {
+                    state.update(|s| {
+                        s.last_beat = Instant::now();
+                        s.received = received;
+                    })
+                });
+                state.update(|s| s.closed = true);
+                report
+            })
+            .context("Failed to start the server monitor")?;
+        if let Some(timeout) = heartbeat_timeout {
+            let watchdog = liveness.clone();
+            std::thread::Builder::new()
+                .name(format!("server-{}-watchdog", pid))
+                .spawn(move || watchdog.watch(timeout))
+                .context("Failed to start the server watchdog")?;
+        }
+        Ok(Self { liveness, reader })
+    }
+}
+
+/// Address a server launched with `--server-remote` listens on.
+///
+/// The remote server cannot know which of its addresses the client will
+/// use, so it accepts on all of them.
+pub const REMOTE_LISTEN_HOST: &str = "0.0.0.0";
+
+/// Check that `target` is a plain `[user@]host` for `--server-remote`.
+///
+/// ssh reads an argument starting with `-` as an option wherever it sits,
+/// so a target such as `-oProxyCommand=...` would run a local command.
+/// Empty targets and ones with whitespace or control characters are
+/// refused as well.
+pub fn check_remote_target(target: &str) -> Result<()> {
+    if target.is_empty() || target.starts_with('-') {
+        bail!(
+            "Invalid --server-remote target {:?}: expected [USER@]HOST",
+            target
+        );
+    }
+    if target.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        bail!(
+            "Invalid --server-remote target {:?}: whitespace is not allowed",
+            target
+        );
+    }
+    Ok(())
+}
+
+/// Host clients connect to for a server launched on `target` (`[user@]host`).
+///
+/// This is the SSH host name, unless `host` names some other address, such
+/// as a particular NIC on the server. The loopback default of `--host`
+/// never makes sense for a remote server and is replaced.
+pub fn remote_connect_host(target: &str, host: &str) -> String {
+    if host == "127.0.0.1" {
+        target
+            .rsplit_once('@')
+            .map_or(target, |(_, h)| h)
+            .to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+/// A benchmark server running as a child process.
+///
+/// Dropping a handle whose server is still running kills it, so a client
+/// that fails part-way through a test does not leave an orphaned server
+/// holding sockets or shared memory.
+pub struct ServerHandle {
+    child: Child,
+    ready: PipeReader,
+    endpoint: Option<TransportConfig>,
+    report: Option<ServerReport>,
+    heartbeat_timeout: Option<Duration>,
+    retry_counter: Option<Arc<AtomicU32>>,
+    monitor: Option<Monitor>,
+    exited: bool,
+    /// The client's end of a socketpair shared with the server, handed to
+    /// the caller with the endpoint.
+    #[cfg(unix)]
+    peer: Option<std::os::unix::io::OwnedFd>,
+}
+
+impl ServerHandle {
+    /// A command that runs this benchmark's binary in server mode.
+    ///
+    /// Add the mechanism, message size, count and transport arguments the
+    /// server needs, then pass the command to [`ServerHandle::spawn`].
+    pub fn command() -> Result<Command> {
+        let exe_path = resolve_server_binary()?;
+        debug!("Spawning server binary: {}", exe_path.display());
+        Ok(Self::command_at(exe_path))
+    }
+
+    /// Like [`ServerHandle::command`], running `binary` instead of this
+    /// benchmark's own; for a server on another host, see
+    /// [`ServerHandle::remote_command`].
+    pub fn command_at(binary: impl AsRef<std::ffi::OsStr>) -> Command {
+        let mut cmd = Command::new(binary);
+        cmd.arg("--internal-run-as-server");
+        cmd
+    }
+
+    /// A command that runs the server command line `server`, built with
+    /// [`ServerHandle::command_at`], on `target` over SSH.
+    ///
+    /// SSH runs in batch mode without a terminal: it must not prompt, since
+    /// stdin is closed, and a pty would mangle the binary readiness signal on
+    /// stdout. Key-based authentication has to be set up beforehand. The
+    /// target is checked with [`check_remote_target`] and passed after
+    /// `--`, so ssh never reads it as an option. ssh joins the rest into one
+    /// line for the remote shell, so each word is quoted for it.
+    pub fn remote_command(target: &str, server: &Command) -> Result<Command> {
+        check_remote_target(target)?;
+        let mut cmd = Command::new("ssh");
+        cmd.args(["-o", "BatchMode=yes", "-T", "--", target]);
+        for word in std::iter::once(server.get_program()).chain(server.get_args()) {
+            cmd.arg(crate::utils::shell_quote(&word.to_string_lossy()));
+        }
+        Ok(cmd)
+    }
+
+    /// Spawn `cmd` with its stdout connected to the readiness pipe.
+    ///
+    /// Stdin is closed and stderr is inherited, so server logs appear
+    /// alongside the client's. On Unix the server gets its own process
+    /// group, so a Ctrl+C at the terminal reaches only the client, which
+    /// finishes its test and then shuts the server down itself.
+    pub fn spawn(mut cmd: Command) -> Result<Self> {
+        let (reader, writer) =
+            os_pipe::pipe().context("Failed to create OS pipe for server signaling")?;
+
+        // Connect the writer end of the pipe to the child's stdout so the child
+        // can write its ready signal which the parent will read from the pipe.
+        cmd.stdin(Stdio::null());
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::IntoRawFd;
+            cmd.stdout(unsafe { Stdio::from_raw_fd(writer.into_raw_fd()) });
+        }
+        #[cfg(windows)]
+        {
+            cmd.stdout(unsafe { Stdio::from_raw_handle(writer.into_raw_handle()) });
+        }
+        cmd.stderr(Stdio::inherit());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        debug!("Spawning server process with command: {:?}", cmd);
+        let child = cmd.spawn().context("Failed to spawn server process")?;
+        // `cmd` holds the parent's copy of the write end; drop it so a server
+        // that dies before signaling shows up as EOF instead of a hang.
+        drop(cmd);
+
+        Ok(Self {
+            child,
+            ready: reader,
+            endpoint: None,
+            report: None,
+            heartbeat_timeout: None,
+            retry_counter: None,
+            monitor: None,
+            exited: false,
+            #[cfg(unix)]
+            peer: None,
+        })
+    }
+
+    /// Spawn `cmd` connected to the caller through a socketpair.
+    ///
+    /// The server inherits one end across exec and is told its descriptor
+    /// with `--internal-socketpair-fd`; [`ServerHandle::wait_ready`] hands
+    /// the other end to the client as [`TransportConfig::inherited_fd`].
+    /// Only the server process inherits the socket: close-on-exec is
+    /// cleared in the child after fork, and the parent's copy of the
+    /// server's end is closed as soon as the server has been spawned.
+    #[cfg(unix)]
+    pub fn spawn_with_socketpair(mut cmd: Command) -> Result<Self> {
+        use std::os::unix::{io::AsRawFd, net::UnixStream, process::CommandExt};
+
+        let (client, server) = UnixStream::pair().context("Failed to create socketpair")?;
+        let server_fd = server.as_raw_fd();
+        cmd.arg("--internal-socketpair-fd")
+            .arg(server_fd.to_string());
+        // SAFETY: fcntl is async-signal-safe, and the closure touches no
+        // other state. It owns `server`, which closes when `cmd` is dropped.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::fcntl(server.as_raw_fd(), libc::F_SETFD, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let mut handle = Self::spawn(cmd)?;
+        handle.peer = Some(client.into());
+        Ok(handle)
+    }
+
+    /// Kill the server if it goes `timeout` without a heartbeat once it is
+    /// ready; zero never kills it.
+    pub fn with_heartbeat_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.heartbeat_timeout = timeout.filter(|t| !t.is_zero());
+        self
+    }
+
+    /// Add the setup retries the server reports to `counter` once it has
+    /// been shut down.
+    pub fn with_retry_counter(mut self, counter: Arc<AtomicU32>) -> Self {
+        self.retry_counter = Some(counter);
+        self
+    }
+
+    /// Block until the server reports it is ready to accept clients.
+    ///
+    /// Returns `transport_config` updated with the server's actual endpoint:
+    /// the ephemeral TCP port, or the client's end of the socketpair for a
+    /// server started with [`ServerHandle::spawn_with_socketpair`]. The
+    /// result is also available from [`ServerHandle::endpoint`] afterwards.
+    pub fn wait_ready(&mut self, transport_config: &TransportConfig) -> Result<TransportConfig> {
+        #[allow(unused_mut)]
+        let mut config = read_server_ready(&mut self.ready, transport_config)?;
+        #[cfg(unix)]
+        if let Some(peer) = self.peer.take() {
+            use std::os::unix::io::IntoRawFd;
+            config.inherited_fd = Some(peer.into_raw_fd());
+        }
+        debug!("Server {} is ready", self.child.id());
+        let reader = self
+            .ready
+            .try_clone()
+            .context("Failed to share the server pipe")?;
+        self.monitor = Some(Monitor::start(
+            reader,
+            self.child.id(),
+            self.heartbeat_timeout,
+        )?);
+        self.endpoint = Some(config.clone());
+        Ok(config)
+    }
+
+    /// Liveness of the server, once it is ready.
+    pub fn liveness(&self) -> Option<ServerLiveness> {
+        self.monitor.as_ref().map(|m| m.liveness.clone())
+    }
+
+    /// The configuration clients should connect with, once the server is ready.
+    pub fn endpoint(&self) -> Option<&TransportConfig> {
+        self.endpoint.as_ref()
+    }
+
+    /// OS process ID of the server.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Wait for the server to exit on its own.
+    ///
+    /// Servers exit once their client disconnects or sends a shutdown
+    /// message, so call this after closing the client transport. The
+    /// server's report is read on the way, and is then available from
+    /// [`ServerHandle::take_report`]. Fails if the server had to be killed
+    /// as wedged.
+    pub fn shutdown(&mut self) -> Result<ExitStatus> {
+        // Read to the end before waiting: a report larger than the pipe
+        // buffer would otherwise keep the server from exiting
+        let mut wedged = None;
+        let report = match self.monitor.take() {
+            Some(monitor) => {
+                let report = monitor
+                    .reader
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Server monitor panicked")));
+                monitor.liveness.update(|s| s.released = true);
+                wedged = monitor.liveness.wedged();
+                report
+            }
+            None => read_server_report(&mut self.ready),
+        };
+        match report {
+            Ok(report) => self.report = report,
+            Err(e) => warn!("Ignoring server {}'s report: {:#}", self.child.id(), e),
+        }
+        if let (Some(counter), Some(report)) = (&self.retry_counter, &self.report) {
+            counter.fetch_add(report.setup_retries, Ordering::Relaxed);
+        }
+        let status = self.child.wait()?;
+        self.exited = true;
+        if let Some(reason) = wedged {
+            bail!("{}", reason);
+        }
+        Ok(status)
+    }
+
+    /// The report the server sent before exiting, once it has been shut down.
+    pub fn take_report(&mut self) -> Option<ServerReport> {
+        self.report.take()
+    }
+
+    /// Kill the server immediately and reap it.
+    pub fn kill(&mut self) -> Result<()> {
+        if !self.exited {
+            // The server may already have exited; reaping below is what matters.
+            let _ = self.child.kill();
+            self.child.wait()?;
+            self.exited = true;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        if !self.exited && matches!(self.child.try_wait(), Ok(None)) {
+            debug!("Killing server {} left running", self.child.id());
+        }
+        let _ = self.kill();
+    }
+}
+
+/// Locate the `ipc-benchmark` binary to run as the server.
+///
+/// When running tests, `current_exe()` points to the test runner binary,
+/// not the main application binary. Integration tests compile the library
+/// without cfg(test), so we cannot rely on cfg!(test). Strategy:
+/// 1) If current_exe filename matches our binary name, use it.
+/// 2) If CARGO_BIN_EXE_* env var is set, use it.
+/// 3) Fallback to target/debug/<name>[.exe] from manifest dir.
+pub(crate) fn resolve_server_binary() -> Result<PathBuf> {
+    let current_exe = std::env::current_exe().context("Failed to get current executable path")?;
+
+    let exe_name = "ipc-benchmark";
+    #[cfg(windows)]
+    let exe_name_win = "ipc-benchmark.exe";
+
+    {
+        let current_name = current_exe.file_name().and_then(|n| n.to_str());
+        let matches_unix = current_name == Some(exe_name);
+        #[cfg(windows)]
+        let matches_win = current_name == Some(exe_name_win);
+        #[cfg(not(windows))]
+        let matches_win = false;
+        if matches_unix || matches_win {
+            return Ok(current_exe);
+        }
+    }
+
+    if let Ok(p) = std::env::var("CARGO_BIN_EXE_ipc-benchmark") {
+        let pbuf = PathBuf::from(p);
+        if pbuf.exists() && pbuf.file_name().and_then(|n| n.to_str()) == Some(exe_name) {
+            return Ok(pbuf);
+        }
+    } else if let Ok(p) = std::env::var("CARGO_BIN_EXE_ipc_benchmark") {
+        // Some Cargo setups may use underscores in the env var name
+        let pbuf = PathBuf::from(p);
+        if pbuf.exists() {
+            return Ok(pbuf);
+        }
+    }
+
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    #[cfg(windows)]
+    let p = root
+        .join("target")
+        .join("debug")
+        .join(format!("{}{}", exe_name, ".exe"));
+    #[cfg(not(windows))]
+    let p = root.join("target").join("debug").join(exe_name);
+    if p.exists() {
+        return Ok(p);
+    }
+
+    Err(anyhow::anyhow!(
+        "Could not resolve '{}' binary for server mode. Build it with \
+         `cargo build --bin {}` or run full `cargo test` first.",
+        "ipc-benchmark",
+        "ipc-benchmark"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_ready_signal_carries_port() {
+        let config = TransportConfig::default();
+
+        let signal = server_ready_signal(Some(40123));
+        let ready = read_server_ready(&mut &signal[..], &config).unwrap();
+        assert_eq!(ready.port, 40123);
+
+        // Mechanisms without ports leave the configured port alone.
+        let signal = server_ready_signal(None);
+        assert_eq!(signal.len(), FRAME_HEADER_LEN + 2);
+        let ready = read_server_ready(&mut &signal[..], &config).unwrap();
+        assert_eq!(ready.port, config.port);
+
+        // A server that dies before signalling is an error, not a hang.
+        assert!(read_server_ready(&mut &[][..], &config).is_err());
+        let report = server_report_frame(&ServerReport::default()).unwrap();
+        assert!(read_server_ready(&mut &report[..], &config).is_err());
+    }
+
+    #[test]
+    fn test_report_follows_ready_signal() {
+        let mut tally = ServerTally::new(vec![50.0]).unwrap();
+        tally.record(0, 1_000_000, 10_000).unwrap();
+        tally.record(1, 1_004_000, 30_000).unwrap();
+        // A canary is not counted
+        tally.record(u64::MAX, 1_005_000, 1).unwrap();
+        tally.processed(Duration::from_micros(5)).unwrap();
+        tally.send_failed();
+        tally.ended_by(&anyhow::anyhow!("client disconnected"));
+        let report = tally.finish();
+        assert_eq!(report.received, 2);
+        assert_eq!(report.send_errors, 1);
+        assert_eq!(report.end_error.as_deref(), Some("client disconnected"));
+        assert_eq!(report.torn_frames, 0);
+        if cfg!(unix) {
+            assert!(report.cpu.is_some());
+        }
+
+        let mut stream = server_ready_signal(Some(7));
+        stream.extend(frame(FRAME_HEARTBEAT, &1u64.to_le_bytes()));
+        stream.extend(frame(99, b"from a newer server"));
+        stream.extend(server_report_frame(&report).unwrap());
+        let mut reader = &stream[..];
+        read_server_ready(&mut reader, &TransportConfig::default()).unwrap();
+        let received = read_server_report(&mut reader).unwrap().unwrap();
+        assert_eq!(received.received, 2);
+        let latency = received.receive_latency.unwrap();
+        assert_eq!(latency.total_samples, 2);
+        let inter_arrival = received.inter_arrival.unwrap();
+        assert_eq!(inter_arrival.total_samples, 1);
+        assert_eq!(inter_arrival.max_ns / 1000, 4);
+        assert_eq!(inter_arrival.latency_type, LatencyType::InterArrival);
+        assert_eq!(received.processing.unwrap().total_samples, 1);
+
+        // No report at all, or one cut short
+        assert!(read_server_report(&mut &[][..]).unwrap().is_none());
+        let cut = &stream[..stream.len() - 1];
+        assert!(read_server_report(&mut &cut[FRAME_HEADER_LEN + 2..]).is_err());
+    }
+
+    #[test]
+    fn test_remote_command_and_connect_host() {
+        let mut server = ServerHandle::command_at("/opt/ipc-benchmark");
+        server.args(["--run-id", "nightly 42; touch /tmp/x"]);
+        let cmd = ServerHandle::remote_command("bench@lab-2", &server).unwrap();
+        assert_eq!(cmd.get_program(), "ssh");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-o",
+                "BatchMode=yes",
+                "-T",
+                "--",
+                "bench@lab-2",
+                "/opt/ipc-benchmark",
+                "--internal-run-as-server",
+                "--run-id",
+                "'nightly 42; touch /tmp/x'"
+            ]
+        );
+
+        // A target ssh would parse as an option is refused
+        for target in ["-oProxyCommand=touch /tmp/x", "", "lab 2"] {
+            assert!(ServerHandle::remote_command(target, &server).is_err());
+        }
+
+        assert_eq!(remote_connect_host("bench@lab-2", "127.0.0.1"), "lab-2");
+        assert_eq!(remote_connect_host("lab-2", "127.0.0.1"), "lab-2");
+        assert_eq!(remote_connect_host("bench@lab-2", "10.1.0.5"), "10.1.0.5");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_handle_reports_early_exit_and_kills_on_drop() {
+        // A "server" that exits without signaling
+        let mut server = ServerHandle::spawn(Command::new("true")).unwrap();
+        assert!(server.wait_ready(&TransportConfig::default()).is_err());
+        assert!(server.endpoint().is_none());
+        assert!(server.shutdown().unwrap().success());
+
+        // One that stops sending heartbeats is killed as wedged
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", r"printf '\001\002\000\000\000\000\000'; sleep 30"]);
+        let mut server = ServerHandle::spawn(cmd)
+            .unwrap()
+            .with_heartbeat_timeout(Some(Duration::from_millis(300)));
+        server.wait_ready(&TransportConfig::default()).unwrap();
+        let liveness = server.liveness().unwrap();
+        liveness.check().unwrap();
+        let error = server.shutdown().unwrap_err().to_string();
+        assert!(error.contains("killed it as wedged"), "{}", error);
+        assert!(liveness.check().is_err());
+
+        // One that never signals is killed when the handle is dropped
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let server = ServerHandle::spawn(cmd).unwrap();
+        let pid = server.id() as libc::pid_t;
+        drop(server);
+        assert_ne!(unsafe { libc::kill(pid, 0) }, 0);
+    }
+}
@@ -0,0 +1,257 @@
+//! Retrying transport setup after transient failures.
+//!
+//! Starting a transport fails now and then for reasons that are gone a
+//! moment later: a TCP port still held by the previous test's socket, a
+//! Unix socket path or shared memory segment that the other side has not
+//! created yet, a message queue that is momentarily unavailable. On busy CI
+//! hosts these races cause most failed runs.
+//!
+//! [`SetupRetry`] starts a transport, and after a failure that
+//! [`is_transient`] recognizes, waits and tries again: up to
+//! `--setup-retries` times, with a backoff that starts at `--setup-backoff`
+//! and doubles each time. Any other failure is returned at once. Each retry
+//! is counted, and the count is recorded in the results, so a run that only
+//! passed thanks to retries can be told from a clean one.
+
+use crate::cli::Args;
+use crate::ipc::{BlockingTransport, IpcTransport, TransportConfig};
+use anyhow::Result;
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tracing::warn;
+
+/// Retry policy for transport setup, with the count of retries made.
+///
+/// Clones share the count.
+#[derive(Debug, Clone, Default)]
+pub struct SetupRetry {
+    retries: u32,
+    backoff: Duration,
+    count: Arc<AtomicU32>,
+}
+
+impl SetupRetry {
+    /// Retry up to `retries` times, waiting `backoff` before the first retry
+    /// and twice as long before each one after it.
+    pub fn new(retries: u32, backoff: Duration) -> Self {
+        Self {
+            retries,
+            backoff,
+            count: Arc::default(),
+        }
+    }
+
+    /// The policy set by `--setup-retries` and `--setup-backoff`.
+    pub fn from_args(args: &Args) -> Self {
+        Self::new(args.setup_retries, args.setup_backoff)
+    }
+
+    /// Retries made so far.
+    pub fn count(&self) -> u32 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// The shared retry count, for retries made elsewhere, such as by a
+    /// spawned server.
+    pub fn counter(&self) -> Arc<AtomicU32> {
+        Arc::clone(&self.count)
+    }
+
+    /// How long to wait before retrying `attempt` (0 for the first
+    /// attempt) of `what` after `error`, or `None` to give up.
+    fn retry_after(&self, what: &str, attempt: u32, error: &anyhow::Error) -> Option<Duration> {
+        if attempt >= self.retries || !is_transient(error) {
+            return None;
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let delay = self.backoff.saturating_mul(1 << attempt.min(16));
+        warn!(
+            "{} setup failed ({:#}); retry {} of {} in {:?}",
+            what,
+            error,
+            attempt + 1,
+            self.retries,
+            delay
+        );
+        Some(delay)
+    }
+
+    /// Start a blocking client, retrying transient failures.
+    pub fn start_client_blocking(
+        &self,
+        transport: &mut dyn BlockingTransport,
+        config: &TransportConfig,
+    ) -> Result<()> {
+        self.blocking("Client", || transport.start_client_blocking(config))
+    }
+
+    /// Start a blocking server, retrying transient failures.
+    pub fn start_server_blocking(
+        &self,
+        transport: &mut dyn BlockingTransport,
+        config: &TransportConfig,
+    ) -> Result<()> {
+        self.blocking("Server", || transport.start_server_blocking(config))
+    }
+
+    fn blocking(&self, what: &str, mut start: impl FnMut() -> Result<()>) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match start() {
+                Ok(()) => return Ok(()),
+                Err(e) => match self.retry_after(what, attempt, &e) {
+                    Some(delay) => std::thread::sleep(delay),
+                    None => return Err(e),
+                },
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Start an async client, retrying transient failures.
+    pub async fn start_client(
+        &self,
+        transport: &mut dyn IpcTransport,
+        config: &TransportConfig,
+    ) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match transport.start_client(config).await {
+                Ok(()) => return Ok(()),
+                Err(e) => match self.retry_after("Client", attempt, &e) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(e),
+                },
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Start an async server, retrying transient failures.
+    pub async fn start_server(
+        &self,
+        transport: &mut dyn IpcTransport,
+        config: &TransportConfig,
+    ) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match transport.start_server(config).await {
+                Ok(()) => return Ok(()),
+                Err(e) => match self.retry_after("Server", attempt, &e) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(e),
+                },
+            }
+            attempt += 1;
+        }
+    }
+}
+
+/// Whether `error` comes from a race that may be gone on a second try.
+///
+/// Looks through the whole error chain for an I/O error or errno meaning
+/// the address is in use, the path or object does not exist yet, the
+/// connection was refused, or the resource is temporarily unavailable.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                e.kind(),
+                io::ErrorKind::AddrInUse
+                    | io::ErrorKind::NotFound
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::Interrupted
+            ) || e.raw_os_error().is_some_and(transient_errno);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(errno) = cause.downcast_ref::<nix::errno::Errno>() {
+            return transient_errno(*errno as i32);
+        }
+        false
+    })
+}
+
+fn transient_errno(errno: i32) -> bool {
+    #[cfg(unix)]
+    {
+        [
+            libc::EADDRINUSE,
+            libc::ENOENT,
+            libc::ECONNREFUSED,
+            libc::EAGAIN,
+            libc::EINTR,
+        ]
+        .contains(&errno)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = errno;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_transient_errors_are_recognized_through_context() {
+        let in_use: anyhow::Error = io::Error::from(io::ErrorKind::AddrInUse).into();
+        assert!(is_transient(&in_use.context("Failed to bind")));
+        let missing = Err::<(), _>(io::Error::from(io::ErrorKind::NotFound))
+            .context("Failed to open shared memory")
+            .unwrap_err();
+        assert!(is_transient(&missing));
+        #[cfg(target_os = "linux")]
+        assert!(is_transient(&anyhow::Error::from(
+            nix::errno::Errno::EAGAIN
+        )));
+
+        let denied: anyhow::Error = io::Error::from(io::ErrorKind::PermissionDenied).into();
+        assert!(!is_transient(&denied));
+        assert!(!is_transient(&anyhow!("Invalid configuration")));
+    }
+
+    #[test]
+    fn test_retries_transient_failures_with_backoff() {
+        let retry = SetupRetry::new(3, Duration::from_millis(1));
+        let mut calls = 0;
+        retry
+            .blocking("Client", || {
+                calls += 1;
+                if calls < 3 {
+                    Err(io::Error::from(io::ErrorKind::ConnectionRefused).into())
+                } else {
+                    Ok(())
+                }
+            })
+            .unwrap();
+        assert_eq!(calls, 3);
+        assert_eq!(retry.count(), 2);
+
+        // Permanent failures, and transient ones past the limit, are returned
+        let mut calls = 0;
+        assert!(retry
+            .blocking("Server", || {
+                calls += 1;
+                Err(anyhow!("Invalid configuration"))
+            })
+            .is_err());
+        assert_eq!(calls, 1);
+        assert!(retry
+            .blocking("Server", || Err(
+                io::Error::from(io::ErrorKind::AddrInUse).into()
+            ))
+            .is_err());
+        assert_eq!(retry.count(), 5);
+        assert_eq!(retry.clone().counter().load(Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,30 @@
+//! Graceful shutdown on SIGINT and SIGTERM.
+//!
+//! The first signal only sets a flag. Benchmark loops poll it with
+//! [`requested`] and stop early, so an interrupted suite still records the
+//! test in progress (marked aborted), finalizes its results and streaming
+//! files, and shuts spawned servers down through the normal paths. A second
+//! signal exits immediately.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Install the SIGINT/SIGTERM handler, printing `message` on the first
+/// signal.
+///
+/// Fails silently if a handler is already installed (e.g. in tests).
+pub fn install_handler(message: &'static str) {
+    let _ = ctrlc::set_handler(move || {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            eprintln!("\nForced shutdown.");
+            std::process::exit(1);
+        }
+        eprintln!("\n{} (Ctrl+C again to force)...", message);
+    });
+}
+
+/// Whether a shutdown signal has been received.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,93 @@
+//! Share of messages within latency thresholds.
+//!
+//! Percentiles answer "how slow is the 99th percentile message"; SLAs are
+//! usually phrased the other way round, as "how many messages took under
+//! 1 ms". With `--sla-thresholds 100us,1ms`, each latency measurement
+//! reports, for every threshold, the messages at or under it and the
+//! percentile that makes them. The counts come from the test's latency
+//! histogram, so a threshold is matched to the histogram's three
+//! significant figures.
+
+use crate::metrics::utils::format_latency;
+use crate::metrics::LatencyMetrics;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// The percentile of one latency threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaQuantile {
+    /// Latency threshold in nanoseconds
+    pub threshold_ns: u64,
+
+    /// Latency samples at or under the threshold
+    pub messages: u64,
+
+    /// Share of the latency samples at or under the threshold, in percent
+    pub percentile: f64,
+}
+
+impl SlaQuantile {
+    /// The percentile of each of `thresholds` in `latency`, in the order
+    /// given, or none when its histogram is gone, as after reading JSON
+    /// back.
+    pub fn measure(latency: &LatencyMetrics, thresholds: &[Duration]) -> Vec<Self> {
+        let Some(histogram) = latency.histogram.as_ref() else {
+            return Vec::new();
+        };
+        if latency.total_samples == 0 {
+            return Vec::new();
+        }
+        thresholds
+            .iter()
+            .map(|threshold| {
+                let threshold_ns = threshold.as_nanos() as u64;
+                let messages = histogram.count_between(0, threshold_ns);
+                Self {
+                    threshold_ns,
+                    messages,
+                    percentile: messages as f64 / latency.total_samples as f64 * 100.0,
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SlaQuantile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3}% within {}",
+            self.percentile,
+            format_latency(self.threshold_ns)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{LatencyCollector, LatencyType};
+
+    #[test]
+    fn test_sla_quantiles_of_each_threshold() {
+        let mut collector = LatencyCollector::new(LatencyType::OneWay).unwrap();
+        for us in [10, 20, 30, 40, 50, 60, 70, 80, 90, 2000] {
+            collector.record(Duration::from_micros(us)).unwrap();
+        }
+        let mut latency = collector.get_metrics(&[50.0]);
+
+        let quantiles = SlaQuantile::measure(
+            &latency,
+            &[Duration::from_micros(50), Duration::from_millis(1)],
+        );
+        assert_eq!(quantiles.len(), 2);
+        assert_eq!(quantiles[0].messages, 5);
+        assert_eq!(quantiles[0].percentile, 50.0);
+        assert_eq!(quantiles[1].messages, 9);
+        assert_eq!(quantiles[1].to_string(), "90.000% within 1.00ms");
+
+        latency.histogram = None;
+        assert!(SlaQuantile::measure(&latency, &[Duration::from_millis(1)]).is_empty());
+    }
+}
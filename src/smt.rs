@@ -0,0 +1,201 @@
+//! SMT siblings among the pinned client and server CPUs.
+//!
+//! Hardware threads of one physical core share its execution units, its
+//! L1 and L2 caches and its store buffers. A client and server pinned to
+//! two such siblings look like two cores to `--client-affinity` and
+//! `--server-affinity`, but contend for one: shared memory results in
+//! particular come out very different from two real cores. Whether CPUs 0
+//! and 1 are siblings depends on the machine's enumeration, so it is easy
+//! to pin them by accident.
+//!
+//! The topology comes from
+//! `/sys/devices/system/cpu/cpuN/topology/thread_siblings_list`. When the
+//! client and server CPUs are siblings, `--smt-siblings` decides what
+//! happens:
+//!
+//! - `warn` (default): run as pinned, with a warning.
+//! - `separate`: move the server to the lowest CPU on another physical
+//!   core before the run.
+//! - `allow`: run as pinned, silently, for runs that mean to measure
+//!   siblings.
+//!
+//! Whatever the policy, a test whose client and server shared a core
+//! records it under `test_config.smt_siblings`. Linux only.
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{info, warn};
+
+/// What to do when the client and server are pinned to SMT siblings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum SmtPolicy {
+    /// Run as pinned, with a warning
+    #[default]
+    Warn,
+
+    /// Move the server to a CPU on another physical core
+    Separate,
+
+    /// Run as pinned without a warning
+    Allow,
+}
+
+impl fmt::Display for SmtPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmtPolicy::Warn => write!(f, "warn"),
+            SmtPolicy::Separate => write!(f, "separate"),
+            SmtPolicy::Allow => write!(f, "allow"),
+        }
+    }
+}
+
+/// A client and server pinned to hardware threads of one physical core
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedCore {
+    /// CPU the client was pinned to
+    pub client_cpu: usize,
+
+    /// CPU the server was pinned to
+    pub server_cpu: usize,
+
+    /// Every hardware thread of the core, as in `thread_siblings_list`
+    pub siblings: String,
+}
+
+impl SharedCore {
+    /// The core the client and server affinities share, if they are
+    /// distinct SMT siblings. Affinities are indexes into the CPUs this
+    /// process may use, as for `--client-affinity`.
+    pub fn between(client_affinity: usize, server_affinity: usize) -> Option<Self> {
+        let cpus = core_affinity::get_core_ids()?;
+        let client_cpu = cpus.get(client_affinity)?.id;
+        let server_cpu = cpus.get(server_affinity)?.id;
+        if client_cpu == server_cpu {
+            return None;
+        }
+        let siblings = read_siblings(client_cpu)?;
+        parse_cpu_list(&siblings)?
+            .contains(&server_cpu)
+            .then_some(Self {
+                client_cpu,
+                server_cpu,
+                siblings,
+            })
+    }
+}
+
+impl fmt::Display for SharedCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "client CPU {} and server CPU {} are SMT siblings of one physical core (CPUs {})",
+            self.client_cpu, self.server_cpu, self.siblings
+        )
+    }
+}
+
+/// Apply `policy` to the pinned `client` and `server` affinities before a
+/// run: warn about siblings, or move the server off the client's core.
+///
+/// Returns the server affinity to use.
+pub fn check(policy: SmtPolicy, client: Option<usize>, server: Option<usize>) -> Option<usize> {
+    let (Some(client), Some(server)) = (client, server) else {
+        return server;
+    };
+    let Some(shared) = SharedCore::between(client, server) else {
+        return Some(server);
+    };
+    match policy {
+        SmtPolicy::Warn => {
+            warn!(
+                "{}; they contend for it. Pin them to separate cores, or pass \
+                 --smt-siblings separate",
+                shared
+            );
+            Some(server)
+        }
+        SmtPolicy::Separate => {
+            let cpus = core_affinity::get_core_ids().unwrap_or_default();
+            let moved = (0..cpus.len())
+                .find(|&index| index != client && SharedCore::between(client, index).is_none());
+            match moved {
+                Some(index) => {
+                    info!(
+                        "{}; moving the server to CPU {} (affinity {})",
+                        shared, cpus[index].id, index
+                    );
+                    Some(index)
+                }
+                None => {
+                    warn!("{}, and no other physical core is available", shared);
+                    Some(server)
+                }
+            }
+        }
+        SmtPolicy::Allow => Some(server),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_siblings(cpu: usize) -> Option<String> {
+    let path = format!("/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list");
+    Some(std::fs::read_to_string(path).ok()?.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_siblings(_cpu: usize) -> Option<String> {
+    None
+}
+
+/// CPUs of a kernel CPU list such as "0-3,8,10-11".
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (first.parse().ok()?, last.parse::<usize>().ok()?);
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0,4\n"), Some(vec![0, 4]));
+        assert_eq!(parse_cpu_list("0-1,8-9"), Some(vec![0, 1, 8, 9]));
+        assert_eq!(parse_cpu_list("3"), Some(vec![3]));
+        assert_eq!(parse_cpu_list("0-x"), None);
+    }
+
+    #[test]
+    fn test_same_cpu_is_not_a_sibling() {
+        assert!(SharedCore::between(0, 0).is_none());
+        assert_eq!(check(SmtPolicy::Separate, Some(0), Some(0)), Some(0));
+        assert_eq!(check(SmtPolicy::Warn, None, Some(3)), Some(3));
+    }
+
+    #[test]
+    fn test_shared_core_display() {
+        let shared = SharedCore {
+            client_cpu: 0,
+            server_cpu: 1,
+            siblings: "0-1".to_string(),
+        };
+        assert_eq!(
+            shared.to_string(),
+            "client CPU 0 and server CPU 1 are SMT siblings of one physical core (CPUs 0-1)"
+        );
+    }
+}
@@ -16,14 +16,19 @@ use tracing_subscriber::filter::LevelFilter;
 
 use crate::benchmark::BenchmarkConfig;
 use crate::cli::{Args, IpcMechanism};
+use crate::clock_sync::ClockSync;
+use crate::host_info::Virtualization;
 use crate::ipc::{
-    get_monotonic_time_ns, BlockingTransportFactory, Message, MessageType, TransportConfig,
-    TransportFactory,
+    get_monotonic_time_ns, get_thread_cpu_time_ns,
+    handshake::{self, Handshake},
+    receive_blocking_with, receive_response, receive_response_blocking, BlockingTransportFactory,
+    Message, MessageType, TransportConfig, TransportFactory,
 };
 use crate::logging::ColorizedFormatter;
 use crate::metrics::{LatencyType, MetricsCollector};
-use crate::results::{BenchmarkResults, MessageLatencyRecord};
+use crate::results::{BenchmarkResults, CpuUsage, MessageLatencyRecord};
 use crate::results_blocking::BlockingResultsManager;
+use crate::runtime::RuntimeConfig;
 use crate::standalone_server::{
     build_standalone_transport_config, effective_concurrency, CONNECT_RETRY_INTERVAL,
     CONNECT_RETRY_TIMEOUT,
@@ -79,6 +84,7 @@ pub fn run_standalone_client(args: Args) -> Result<()> {
             eprintln!("Note: tracing subscriber already initialized, using existing configuration");
         }
     }
+    let _run_span = crate::run_id::span().entered();
 
     if let Some(core) = args.client_affinity {
         if let Err(e) = crate::utils::set_affinity(core) {
@@ -103,6 +109,19 @@ pub fn run_standalone_client(args: Args) -> Result<()> {
 
     let mut results_manager =
         BlockingResultsManager::new(args.output_file.as_deref(), log_file_for_manager.as_deref())?;
+    let machine_output = args.machine_output;
+    results_manager.set_machine_output(machine_output);
+    results_manager.set_percentile_distribution_dir(args.percentile_distribution.as_deref())?;
+    results_manager.set_summary_history(crate::history::SummaryHistory::from_args(&args));
+
+    // Across hosts, timestamps are only as comparable as the clocks' agreement
+    let clock_sync = ClockSync::query();
+    match &clock_sync {
+        Some(sync) => info!("Clock sync: {}", sync),
+        None => debug!("Clock sync status unavailable (no chrony, PTP or adjtimex)"),
+    }
+    results_manager.set_clock_sync(clock_sync);
+    results_manager.set_system_load(crate::system_load::check(args.require_idle)?);
 
     // Enable streaming if requested
     if let Some(ref streaming_file) = args.streaming_output_json {
@@ -114,6 +133,9 @@ pub fn run_standalone_client(args: Args) -> Result<()> {
         }
     }
     if let Some(ref streaming_file) = args.streaming_output_csv {
+        if let Some(columns) = &args.csv_columns {
+            results_manager.set_csv_columns(columns.clone());
+        }
         results_manager.enable_csv_streaming(streaming_file)?;
     }
 
@@ -121,16 +143,34 @@ pub fn run_standalone_client(args: Args) -> Result<()> {
         "Starting standalone client: mechanism={}, blocking={}",
         mechanism, args.blocking
     );
+    info!("Run ID: {}", crate::run_id::get());
+
+    // A split run's peer may sit in another container or VM
+    let virtualization = Virtualization::detect();
+    info!("Environment: {}", virtualization);
+    for warning in virtualization.boundary_warnings(mechanism, &args.host) {
+        warn!("{}", warning);
+    }
 
     if args.blocking {
         run_standalone_client_blocking(args, mechanism, transport_config, &mut results_manager)?;
     } else {
-        run_standalone_client_async(args, mechanism, transport_config, &mut results_manager)?;
+        let runtime = RuntimeConfig::from_args(&args)
+            .build()
+            .context("Failed to build the Tokio runtime")?;
+        runtime.block_on(run_standalone_client_async(
+            args,
+            mechanism,
+            transport_config,
+            &mut results_manager,
+        ))?;
     }
 
     results_manager.finalize()?;
-    if let Err(e) = results_manager.print_summary() {
-        error!("Failed to print results summary: {}", e);
+    if machine_output.is_none() {
+        if let Err(e) = results_manager.print_summary() {
+            error!("Failed to print results summary: {}", e);
+        }
     }
 
     Ok(())
@@ -163,6 +203,22 @@ pub fn connect_blocking_with_retry(
     }
 }
 
+/// Check protocol compatibility with the server on a fresh connection,
+/// unless the mechanism has no reply path, proposing checked frames if
+/// `verify_frames`.
+fn handshake_blocking(
+    transport: &mut dyn crate::ipc::BlockingTransport,
+    mechanism: &IpcMechanism,
+    message_size: usize,
+    verify_frames: bool,
+) -> Result<()> {
+    if handshake::has_reply_path(mechanism) {
+        let terms = Handshake::new(message_size as u64, false).with_frame_checksums(verify_frames);
+        handshake::client_blocking(transport, &terms)?;
+    }
+    Ok(())
+}
+
 /// Blocking standalone client implementation.
 ///
 /// When `concurrency > 1` (and mechanism supports it), spawns N worker
@@ -211,6 +267,12 @@ pub fn run_standalone_client_blocking_single(
 
     info!("Connecting to server...");
     connect_blocking_with_retry(&mut transport, &transport_config)?;
+    handshake_blocking(
+        transport.as_mut(),
+        &mechanism,
+        config.message_size,
+        transport_config.verify_frames,
+    )?;
     info!("Connected to server.");
 
     let msg_count = config.msg_count.unwrap_or(crate::defaults::MSG_COUNT);
@@ -258,7 +320,7 @@ pub fn run_standalone_client_blocking_single(
         }
 
         // Create message once, reuse across iterations to avoid per-message heap allocation
-        let mut msg = Message::new(0, payload.clone(), MessageType::OneWay);
+        let mut msg = Message::new(0, payload.clone(), MessageType::OneWay).with_ttl(args.deadline);
 
         let start = std::time::Instant::now();
         let count = if let Some(test_duration) = config.duration {
@@ -311,12 +373,20 @@ pub fn run_standalone_client_blocking_single(
         let mut metrics =
             MetricsCollector::new(Some(LatencyType::RoundTrip), config.percentiles.clone())?;
 
+        let spin = args.spin_wait;
+        if spin.is_some() && !transport.supports_try_receive() {
+            warn!(
+                "{} does not support non-blocking receives; --spin-wait ignored",
+                mechanism
+            );
+        }
+
         // Send canary to warm up the connection if first message excluded
         if !config.include_first_message {
             let canary = Message::new(u64::MAX, payload.clone(), MessageType::Request);
             match transport.send_blocking(&canary) {
                 Ok(()) => {
-                    if let Err(e) = transport.receive_blocking() {
+                    if let Err(e) = receive_blocking_with(transport.as_mut(), spin) {
                         warn!("Canary receive failed, connection may be broken: {}", e);
                     }
                 }
@@ -327,7 +397,9 @@ pub fn run_standalone_client_blocking_single(
         }
 
         // Create message once, reuse across iterations to avoid per-message heap allocation
-        let mut msg = Message::new(0, payload, MessageType::Request);
+        let mut msg = Message::new(0, payload, MessageType::Request).with_ttl(args.deadline);
+        let loop_start = std::time::Instant::now();
+        let cpu_start = get_thread_cpu_time_ns();
 
         if let Some(test_duration) = config.duration {
             info!(
@@ -345,20 +417,27 @@ pub fn run_standalone_client_blocking_single(
                     .as_nanos() as u64;
                 let send_time = std::time::Instant::now();
                 transport.send_blocking(&msg)?;
-                let _response = transport.receive_blocking()?;
+                let matched = receive_response_blocking(
+                    transport.as_mut(),
+                    msg.id,
+                    spin,
+                    &mut metrics.error_count,
+                )?;
                 let latency = send_time.elapsed();
 
-                metrics.record_message(config.message_size, Some(latency))?;
-                let record = MessageLatencyRecord::new(
-                    i,
-                    mechanism,
-                    config.message_size,
-                    LatencyType::RoundTrip,
-                    latency,
-                    send_wall_ns,
-                );
-                if let Err(e) = results_manager.stream_latency_record(&record) {
-                    debug!("Streaming latency record failed: {}", e);
+                if matched {
+                    metrics.record_message(config.message_size, Some(latency))?;
+                    let record = MessageLatencyRecord::new(
+                        i,
+                        mechanism,
+                        config.message_size,
+                        LatencyType::RoundTrip,
+                        latency,
+                        send_wall_ns,
+                    );
+                    if let Err(e) = results_manager.stream_latency_record(&record) {
+                        debug!("Streaming latency record failed: {}", e);
+                    }
                 }
 
                 if let Some(delay) = config.send_delay {
@@ -380,20 +459,27 @@ pub fn run_standalone_client_blocking_single(
                     .as_nanos() as u64;
                 let send_time = std::time::Instant::now();
                 transport.send_blocking(&msg)?;
-                let _response = transport.receive_blocking()?;
+                let matched = receive_response_blocking(
+                    transport.as_mut(),
+                    msg.id,
+                    spin,
+                    &mut metrics.error_count,
+                )?;
                 let latency = send_time.elapsed();
 
-                metrics.record_message(config.message_size, Some(latency))?;
-                let record = MessageLatencyRecord::new(
-                    i as u64,
-                    mechanism,
-                    config.message_size,
-                    LatencyType::RoundTrip,
-                    latency,
-                    send_wall_ns,
-                );
-                if let Err(e) = results_manager.stream_latency_record(&record) {
-                    debug!("Streaming latency record failed: {}", e);
+                if matched {
+                    metrics.record_message(config.message_size, Some(latency))?;
+                    let record = MessageLatencyRecord::new(
+                        i as u64,
+                        mechanism,
+                        config.message_size,
+                        LatencyType::RoundTrip,
+                        latency,
+                        send_wall_ns,
+                    );
+                    if let Err(e) = results_manager.stream_latency_record(&record) {
+                        debug!("Streaming latency record failed: {}", e);
+                    }
                 }
 
                 if let Some(delay) = config.send_delay {
@@ -402,6 +488,11 @@ pub fn run_standalone_client_blocking_single(
             }
         }
 
+        let wall_time_ns = loop_start.elapsed().as_nanos() as u64;
+        results.client_cpu = cpu_start
+            .zip(get_thread_cpu_time_ns())
+            .map(|(start, end)| CpuUsage::new(end.saturating_sub(start), wall_time_ns));
+        results.test_config.spin_wait = spin;
         results.add_round_trip_results(metrics.get_metrics());
     }
 
@@ -483,6 +574,7 @@ pub fn run_standalone_client_blocking_concurrent(
                 let include_first = config.include_first_message;
                 let warmup_iters = config.warmup_iterations;
                 let shm_direct = args.shm_direct;
+                let deadline = args.deadline;
                 let mech = mechanism;
                 // Last worker gets any remainder messages
                 let worker_msg_count = base_messages_per_worker
@@ -496,6 +588,7 @@ pub fn run_standalone_client_blocking_concurrent(
                     let mut transport =
                         BlockingTransportFactory::create(&mech, shm_direct, send_delay)?;
                     connect_blocking_with_retry(&mut transport, &tc)?;
+                    handshake_blocking(transport.as_mut(), &mech, message_size, tc.verify_frames)?;
                     debug!("Worker {} connected (one-way)", worker_id);
 
                     let payload = vec![0u8; message_size];
@@ -517,7 +610,7 @@ pub fn run_standalone_client_blocking_concurrent(
                         }
                     }
 
-                    let mut msg = Message::new(0, payload, MessageType::OneWay);
+                    let mut msg = Message::new(0, payload, MessageType::OneWay).with_ttl(deadline);
 
                     if let Some(test_duration) = duration {
                         let start = std::time::Instant::now();
@@ -581,6 +674,7 @@ pub fn run_standalone_client_blocking_concurrent(
                 let include_first = config.include_first_message;
                 let warmup_iters = config.warmup_iterations;
                 let shm_direct = args.shm_direct;
+                let deadline = args.deadline;
                 let mech = mechanism;
                 let worker_msg_count = base_messages_per_worker
                     + if worker_id == concurrency - 1 {
@@ -592,6 +686,7 @@ pub fn run_standalone_client_blocking_concurrent(
                 std::thread::spawn(move || -> Result<(PerformanceMetrics, Vec<MessageLatencyRecord>)> {
                     let mut transport = BlockingTransportFactory::create(&mech, shm_direct, send_delay)?;
                     connect_blocking_with_retry(&mut transport, &tc)?;
+                    handshake_blocking(transport.as_mut(), &mech, message_size, tc.verify_frames)?;
                     debug!("Worker {} connected (round-trip)", worker_id);
 
                     let payload = vec![0u8; message_size];
@@ -620,7 +715,7 @@ pub fn run_standalone_client_blocking_concurrent(
                         }
                     }
 
-                    let mut msg = Message::new(0, payload, MessageType::Request);
+                    let mut msg = Message::new(0, payload, MessageType::Request).with_ttl(deadline);
 
                     if let Some(test_duration) = duration {
                         let start = std::time::Instant::now();
@@ -634,17 +729,19 @@ pub fn run_standalone_client_blocking_concurrent(
                                 .as_nanos() as u64;
                             let send_time = std::time::Instant::now();
                             transport.send_blocking(&msg)?;
-                            let _response = transport.receive_blocking()?;
+                            let matched = receive_response_blocking(transport.as_mut(), msg.id, None, &mut metrics.error_count)?;
                             let latency = send_time.elapsed();
-                            metrics.record_message(message_size, Some(latency))?;
-                            records.push(MessageLatencyRecord::new(
-                                msg.id,
-                                mech,
-                                message_size,
-                                LatencyType::RoundTrip,
-                                latency,
-                                send_wall_ns,
-                            ));
+                            if matched {
+                                metrics.record_message(message_size, Some(latency))?;
+                                records.push(MessageLatencyRecord::new(
+                                    msg.id,
+                                    mech,
+                                    message_size,
+                                    LatencyType::RoundTrip,
+                                    latency,
+                                    send_wall_ns,
+                                ));
+                            }
                             if let Some(delay) = send_delay {
                                 std::thread::sleep(delay);
                             }
@@ -660,17 +757,19 @@ pub fn run_standalone_client_blocking_concurrent(
                                 .as_nanos() as u64;
                             let send_time = std::time::Instant::now();
                             transport.send_blocking(&msg)?;
-                            let _response = transport.receive_blocking()?;
+                            let matched = receive_response_blocking(transport.as_mut(), msg.id, None, &mut metrics.error_count)?;
                             let latency = send_time.elapsed();
-                            metrics.record_message(message_size, Some(latency))?;
-                            records.push(MessageLatencyRecord::new(
-                                msg.id,
-                                mech,
-                                message_size,
-                                LatencyType::RoundTrip,
-                                latency,
-                                send_wall_ns,
-                            ));
+                            if matched {
+                                metrics.record_message(message_size, Some(latency))?;
+                                records.push(MessageLatencyRecord::new(
+                                    msg.id,
+                                    mech,
+                                    message_size,
+                                    LatencyType::RoundTrip,
+                                    latency,
+                                    send_wall_ns,
+                                ));
+                            }
                             if let Some(delay) = send_delay {
                                 std::thread::sleep(delay);
                             }
@@ -772,10 +871,23 @@ pub async fn connect_async_with_retry(
     }
 }
 
+/// Async version of [`handshake_blocking`].
+async fn handshake_async(
+    transport: &mut dyn crate::ipc::IpcTransport,
+    mechanism: &IpcMechanism,
+    message_size: usize,
+    verify_frames: bool,
+) -> Result<()> {
+    if handshake::has_reply_path(mechanism) {
+        let terms = Handshake::new(message_size as u64, false).with_frame_checksums(verify_frames);
+        handshake::client(transport, &terms).await?;
+    }
+    Ok(())
+}
+
 /// Async standalone client implementation.
 ///
 /// Dispatches to single or concurrent mode based on concurrency setting.
-#[tokio::main]
 pub async fn run_standalone_client_async(
     args: Args,
     mechanism: IpcMechanism,
@@ -812,6 +924,13 @@ pub async fn run_standalone_client_async_single(
 
     info!("Connecting to server...");
     connect_async_with_retry(&mut transport, &transport_config).await?;
+    handshake_async(
+        transport.as_mut(),
+        &mechanism,
+        config.message_size,
+        transport_config.verify_frames,
+    )
+    .await?;
     info!("Connected to server.");
 
     let msg_count = config.msg_count.unwrap_or(crate::defaults::MSG_COUNT);
@@ -946,20 +1065,23 @@ pub async fn run_standalone_client_async_single(
                     .as_nanos() as u64;
                 let send_time = std::time::Instant::now();
                 transport.send(&msg).await?;
-                let _response = transport.receive().await?;
+                let matched =
+                    receive_response(transport.as_mut(), msg.id, &mut metrics.error_count).await?;
                 let latency = send_time.elapsed();
 
-                metrics.record_message(config.message_size, Some(latency))?;
-                let record = MessageLatencyRecord::new(
-                    i,
-                    mechanism,
-                    config.message_size,
-                    LatencyType::RoundTrip,
-                    latency,
-                    send_wall_ns,
-                );
-                if let Err(e) = results_manager.stream_latency_record(&record) {
-                    debug!("Streaming latency record failed: {}", e);
+                if matched {
+                    metrics.record_message(config.message_size, Some(latency))?;
+                    let record = MessageLatencyRecord::new(
+                        i,
+                        mechanism,
+                        config.message_size,
+                        LatencyType::RoundTrip,
+                        latency,
+                        send_wall_ns,
+                    );
+                    if let Err(e) = results_manager.stream_latency_record(&record) {
+                        debug!("Streaming latency record failed: {}", e);
+                    }
                 }
 
                 if let Some(delay) = config.send_delay {
@@ -981,20 +1103,23 @@ pub async fn run_standalone_client_async_single(
                     .as_nanos() as u64;
                 let send_time = std::time::Instant::now();
                 transport.send(&msg).await?;
-                let _response = transport.receive().await?;
+                let matched =
+                    receive_response(transport.as_mut(), msg.id, &mut metrics.error_count).await?;
                 let latency = send_time.elapsed();
 
-                metrics.record_message(config.message_size, Some(latency))?;
-                let record = MessageLatencyRecord::new(
-                    i as u64,
-                    mechanism,
-                    config.message_size,
-                    LatencyType::RoundTrip,
-                    latency,
-                    send_wall_ns,
-                );
-                if let Err(e) = results_manager.stream_latency_record(&record) {
-                    debug!("Streaming latency record failed: {}", e);
+                if matched {
+                    metrics.record_message(config.message_size, Some(latency))?;
+                    let record = MessageLatencyRecord::new(
+                        i as u64,
+                        mechanism,
+                        config.message_size,
+                        LatencyType::RoundTrip,
+                        latency,
+                        send_wall_ns,
+                    );
+                    if let Err(e) = results_manager.stream_latency_record(&record) {
+                        debug!("Streaming latency record failed: {}", e);
+                    }
                 }
 
                 if let Some(delay) = config.send_delay {
@@ -1088,6 +1213,7 @@ pub async fn run_standalone_client_async_concurrent(
             join_set.spawn(async move {
                 let mut transport = TransportFactory::create(&mech)?;
                 connect_async_with_retry(&mut transport, &tc).await?;
+                handshake_async(transport.as_mut(), &mech, message_size, tc.verify_frames).await?;
                 debug!("Async worker {} connected (one-way)", worker_id);
 
                 let payload = vec![0u8; message_size];
@@ -1180,6 +1306,7 @@ pub async fn run_standalone_client_async_concurrent(
             join_set.spawn(async move {
                 let mut transport = TransportFactory::create(&mech)?;
                 connect_async_with_retry(&mut transport, &tc).await?;
+                handshake_async(transport.as_mut(), &mech, message_size, tc.verify_frames).await?;
                 debug!("Async worker {} connected (round-trip)", worker_id);
 
                 let payload = vec![0u8; message_size];
@@ -1221,17 +1348,19 @@ pub async fn run_standalone_client_async_concurrent(
                             .as_nanos() as u64;
                         let send_time = std::time::Instant::now();
                         transport.send(&msg).await?;
-                        let _response = transport.receive().await?;
+                        let matched = receive_response(transport.as_mut(), msg.id, &mut metrics.error_count).await?;
                         let latency = send_time.elapsed();
-                        metrics.record_message(message_size, Some(latency))?;
-                        records.push(MessageLatencyRecord::new(
-                            msg.id,
-                            mech,
-                            message_size,
-                            LatencyType::RoundTrip,
-                            latency,
-                            send_wall_ns,
-                        ));
+                        if matched {
+                            metrics.record_message(message_size, Some(latency))?;
+                            records.push(MessageLatencyRecord::new(
+                                msg.id,
+                                mech,
+                                message_size,
+                                LatencyType::RoundTrip,
+                                latency,
+                                send_wall_ns,
+                            ));
+                        }
                         if let Some(delay) = send_delay {
                             tokio::time::sleep(delay).await;
                         }
@@ -1247,17 +1376,19 @@ pub async fn run_standalone_client_async_concurrent(
                             .as_nanos() as u64;
                         let send_time = std::time::Instant::now();
                         transport.send(&msg).await?;
-                        let _response = transport.receive().await?;
+                        let matched = receive_response(transport.as_mut(), msg.id, &mut metrics.error_count).await?;
                         let latency = send_time.elapsed();
-                        metrics.record_message(message_size, Some(latency))?;
-                        records.push(MessageLatencyRecord::new(
-                            msg.id,
-                            mech,
-                            message_size,
-                            LatencyType::RoundTrip,
-                            latency,
-                            send_wall_ns,
-                        ));
+                        if matched {
+                            metrics.record_message(message_size, Some(latency))?;
+                            records.push(MessageLatencyRecord::new(
+                                msg.id,
+                                mech,
+                                message_size,
+                                LatencyType::RoundTrip,
+                                latency,
+                                send_wall_ns,
+                            ));
+                        }
                         if let Some(delay) = send_delay {
                             tokio::time::sleep(delay).await;
                         }
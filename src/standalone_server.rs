@@ -10,27 +10,82 @@
 //! up logging, CPU affinity, and dispatches to the appropriate blocking
 //! or async implementation based on CLI flags.
 
-use std::sync::atomic::{AtomicBool, Ordering};
-
 use anyhow::{Context, Result};
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::filter::LevelFilter;
 
 use crate::benchmark::BenchmarkConfig;
 use crate::cli::{Args, IpcMechanism};
+use crate::clock_sync::ClockSync;
+use crate::host_info::Virtualization;
 use crate::ipc::{
-    get_monotonic_time_ns, BlockingTransport, BlockingTransportFactory, Message, MessageType,
-    TransportConfig, TransportFactory,
+    get_monotonic_time_ns,
+    handshake::{self, Handshake},
+    BlockingTransport, BlockingTransportFactory, Message, MessageType, TransportConfig,
+    TransportFactory,
 };
 use crate::logging::ColorizedFormatter;
-use crate::metrics::{LatencyType, MetricsCollector};
+use crate::metrics::utils::format_latency;
+use crate::metrics::{LatencyMetrics, LatencyType, MetricsCollector};
+use crate::results::percentile_ns;
+use crate::runtime::RuntimeConfig;
+use crate::shutdown;
+
+// --- Client host ---
+
+/// Where a client runs relative to the server.
+///
+/// One-way latency is the server's monotonic receive time minus the
+/// client's monotonic send time. Both read the same clock on one host, but
+/// monotonic clocks on different hosts have unrelated origins, so a
+/// cross-host difference means nothing however well the hosts are synced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientHost {
+    /// The client shares this host's clock
+    Same,
+
+    /// The client is on another host; its one-way latency is invalid
+    Other,
+
+    /// The client may be on another host
+    Unknown,
+}
 
-// --- Shutdown flag ---
+impl ClientHost {
+    /// Host of a client connected from `peer`.
+    pub fn of_peer(peer: std::net::IpAddr) -> Self {
+        use std::net::IpAddr;
 
-static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+        let peer = match peer {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(peer, IpAddr::V4),
+            v4 => v4,
+        };
+        // Only addresses of this host's interfaces can be bound
+        if peer.is_loopback() || std::net::UdpSocket::bind((peer, 0)).is_ok() {
+            Self::Same
+        } else {
+            Self::Other
+        }
+    }
 
-fn is_shutdown_requested() -> bool {
-    SHUTDOWN_REQUESTED.load(Ordering::Relaxed)
+    /// Host of the clients of a single-connection `mechanism` server
+    /// listening on `host`.
+    pub fn of_listener(mechanism: IpcMechanism, host: &str) -> Self {
+        let network = match mechanism {
+            IpcMechanism::TcpSocket | IpcMechanism::Http | IpcMechanism::Grpc => true,
+            #[cfg(target_os = "linux")]
+            IpcMechanism::UdpMulticast => true,
+            _ => false,
+        };
+        let loopback = host
+            .parse::<std::net::IpAddr>()
+            .map_or(host == "localhost", |ip| ip.is_loopback());
+        if network && !loopback {
+            Self::Unknown
+        } else {
+            Self::Same
+        }
+    }
 }
 
 // --- Standalone constants ---
@@ -59,11 +114,13 @@ pub const SERVER_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_s
 /// Only TCP and UDS support concurrent connections (socket-based accept
 /// loop). SHM and PMQ are forced to concurrency=1 with a warning.
 pub fn effective_concurrency(mechanism: IpcMechanism, requested: usize) -> usize {
-    let mut supports_concurrency = mechanism == IpcMechanism::TcpSocket;
     #[cfg(unix)]
-    {
-        supports_concurrency = supports_concurrency || mechanism == IpcMechanism::UnixDomainSocket;
-    }
+    let supports_concurrency = matches!(
+        mechanism,
+        IpcMechanism::TcpSocket | IpcMechanism::UnixDomainSocket
+    );
+    #[cfg(not(unix))]
+    let supports_concurrency = mechanism == IpcMechanism::TcpSocket;
     if !supports_concurrency && requested > 1 {
         warn!(
             "{} does not support concurrency > 1. Forcing concurrency = 1.",
@@ -77,9 +134,15 @@ pub fn effective_concurrency(mechanism: IpcMechanism, requested: usize) -> usize
 
 /// Determine the server's response to an incoming message.
 ///
-/// Returns `Some(response)` for Request (-> Response) and Ping (-> Pong).
-/// Returns `None` for all other message types (OneWay, Shutdown, etc.),
-/// which the caller handles directly for control flow.
+/// Returns `Some(response)` for Request (-> Response), Ping (-> Pong) and
+/// Handshake (-> the agreed terms or a refusal). Returns `None` for all
+/// other message types (OneWay, Shutdown, etc.), which the caller handles
+/// directly for control flow.
+///
+/// A standalone server accepts any message size and no segmentation, so
+/// it only refuses clients of another protocol version and grants checked
+/// frames to clients that propose them; a refused client disconnects and
+/// the server keeps serving others.
 ///
 /// Response payloads are intentionally empty: the server echoes back only
 /// the message ID for correlation. This matches the existing benchmark
@@ -89,6 +152,13 @@ pub fn dispatch_server_message(msg: &Message) -> Option<Message> {
     match msg.message_type {
         MessageType::Request => Some(Message::new(msg.id, Vec::new(), MessageType::Response)),
         MessageType::Ping => Some(Message::new(msg.id, Vec::new(), MessageType::Pong)),
+        MessageType::Handshake => {
+            let (reply, agreed) = handshake::answer(&Handshake::new(u64::MAX, false), msg);
+            if let Err(e) = agreed {
+                warn!("{}", e);
+            }
+            Some(reply)
+        }
         _ => None,
     }
 }
@@ -99,7 +169,13 @@ pub fn dispatch_server_message(msg: &Message) -> Option<Message> {
 /// to defaults from TransportConfig::default(). This allows the
 /// simple case (no extra flags) to work out of the box.
 pub fn build_standalone_transport_config(args: &Args) -> TransportConfig {
-    let defaults = TransportConfig::default();
+    #[allow(unused_mut)]
+    let mut defaults = TransportConfig::default();
+    // Named pipes live in their own namespace rather than the temp directory
+    #[cfg(windows)]
+    if args.mechanisms.first() == Some(&IpcMechanism::NamedPipe) {
+        defaults.socket_path = crate::ipc::named_pipe_blocking::pipe_path("ipc_benchmark");
+    }
 
     TransportConfig {
         host: args.host.clone(),
@@ -115,6 +191,24 @@ pub fn build_standalone_transport_config(args: &Args) -> TransportConfig {
             .clone()
             .unwrap_or(defaults.message_queue_name),
         buffer_size: args.buffer_size.unwrap_or(defaults.buffer_size),
+        shm_slot_align: args.shm_align.unwrap_or(defaults.shm_slot_align),
+        shm_pad_indices: args.shm_pad_indices,
+        shm_capacity_messages: args.shm_capacity_messages,
+        shm_priority_lanes: !args.priorities.is_empty(),
+        shm_wait: args.shm_wait,
+        shm_hugetlbfs: args.shm_hugetlbfs.clone(),
+        http_version: args.http_version,
+        uds_credentials: args.uds_credentials,
+        tcp_keepalive: args.tcp_keepalive,
+        bind_device: args.bind_device.clone(),
+        source_addr: args.source_addr,
+        multicast_group: args
+            .multicast_group
+            .unwrap_or(crate::defaults::MULTICAST_GROUP),
+        first_byte_timestamps: false,
+        fragment_writes: args.fragment_writes,
+        verify_frames: args.verify_frames,
+        segment_size: None,
         ..defaults
     }
 }
@@ -198,6 +292,7 @@ pub fn run_standalone_server(args: Args) -> Result<()> {
             eprintln!("Note: tracing subscriber already initialized, using existing configuration");
         }
     }
+    let _run_span = crate::run_id::span().entered();
 
     // Set CPU affinity if specified
     if let Some(core) = args.server_affinity {
@@ -208,28 +303,40 @@ pub fn run_standalone_server(args: Args) -> Result<()> {
         }
     }
 
+    if let Some(sync) = ClockSync::query() {
+        info!("Clock sync: {}", sync);
+    }
+
     let transport_config = build_standalone_transport_config(&args);
     let config = BenchmarkConfig::from_args(&args)?;
 
     // Install signal handler for graceful shutdown.
-    // set_handler fails if already installed (e.g., in tests), which is safe to ignore.
-    let _ = ctrlc::set_handler(move || {
-        if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
-            eprintln!("\nForced shutdown.");
-            std::process::exit(1);
-        }
-        eprintln!("\nShutdown signal received, exiting gracefully (Ctrl+C again to force)...");
-    });
+    shutdown::install_handler("Shutdown signal received, exiting gracefully");
 
     info!(
         "Starting standalone server: mechanism={}, blocking={}",
         mechanism, args.blocking
     );
+    info!("Run ID: {}", crate::run_id::get());
+
+    // A split run's peer may sit in another container or VM
+    let virtualization = Virtualization::detect();
+    info!("Environment: {}", virtualization);
+    for warning in virtualization.boundary_warnings(mechanism, &args.host) {
+        warn!("{}", warning);
+    }
 
     if args.blocking {
         run_standalone_server_blocking(&args, mechanism, &transport_config, &config)
     } else {
-        run_standalone_server_async(mechanism, transport_config, &config)
+        let runtime = RuntimeConfig::from_args(&args)
+            .build()
+            .context("Failed to build the Tokio runtime")?;
+        runtime.block_on(run_standalone_server_async(
+            mechanism,
+            transport_config,
+            &config,
+        ))
     }
 }
 
@@ -281,7 +388,7 @@ pub fn run_standalone_server_blocking_single(
     let mut one_way_count = 0u64;
 
     loop {
-        if is_shutdown_requested() {
+        if shutdown::requested() {
             info!("Shutdown signal received, exiting");
             break;
         }
@@ -293,6 +400,10 @@ pub fn run_standalone_server_blocking_single(
                 }
 
                 if message.message_type == MessageType::OneWay && message.id != u64::MAX {
+                    if message.is_expired(receive_time_ns) {
+                        one_way_metrics.expired_count += 1;
+                        continue;
+                    }
                     let latency_ns = receive_time_ns.saturating_sub(message.timestamp);
                     let latency = std::time::Duration::from_nanos(latency_ns);
                     one_way_metrics.record_message(config.message_size, Some(latency))?;
@@ -304,6 +415,10 @@ pub fn run_standalone_server_blocking_single(
                         warn!("Server failed to send response: {}", e);
                         break;
                     }
+                    if response.message_type == MessageType::Handshake {
+                        transport
+                            .set_frame_checksums(handshake::agrees_on_frame_checksums(&response));
+                    }
                 }
             }
             Err(e) => {
@@ -313,7 +428,11 @@ pub fn run_standalone_server_blocking_single(
         }
     }
 
-    print_server_one_way_latency(one_way_count, &one_way_metrics);
+    print_server_one_way_latency(
+        one_way_count,
+        &one_way_metrics,
+        ClientHost::of_listener(mechanism, &transport_config.host),
+    );
     transport.close_blocking()?;
     info!("Standalone server exiting cleanly.");
     Ok(())
@@ -331,7 +450,7 @@ pub fn handle_client_connection(
         MetricsCollector::new(Some(LatencyType::OneWay), config.percentiles.clone())?;
 
     loop {
-        if is_shutdown_requested() {
+        if shutdown::requested() {
             debug!("Handler exiting due to shutdown signal");
             break;
         }
@@ -343,6 +462,10 @@ pub fn handle_client_connection(
                 }
 
                 if message.message_type == MessageType::OneWay && message.id != u64::MAX {
+                    if message.is_expired(receive_time_ns) {
+                        one_way_metrics.expired_count += 1;
+                        continue;
+                    }
                     let latency_ns = receive_time_ns.saturating_sub(message.timestamp);
                     let latency = std::time::Duration::from_nanos(latency_ns);
                     one_way_metrics.record_message(config.message_size, Some(latency))?;
@@ -353,6 +476,10 @@ pub fn handle_client_connection(
                         warn!("Handler failed to send response: {}", e);
                         break;
                     }
+                    if response.message_type == MessageType::Handshake {
+                        transport
+                            .set_frame_checksums(handshake::agrees_on_frame_checksums(&response));
+                    }
                 }
             }
             Err(e) => {
@@ -433,16 +560,21 @@ pub fn run_standalone_server_blocking_multi_accept_tcp(
 
                 let metrics_clone = worker_metrics.clone();
                 let handler_config = config.clone();
+                let client = ClientHost::of_peer(peer_addr.ip());
+                if client == ClientHost::Other {
+                    warn!("{} ({})", CROSS_HOST_ONE_WAY, peer_addr);
+                }
 
                 let handle = std::thread::spawn(move || {
                     let mut transport = BlockingTcpSocket::from_stream(stream);
                     match handle_client_connection(&mut transport, &handler_config) {
-                        Ok(collector) => {
+                        Ok(collector) if client == ClientHost::Same => {
                             metrics_clone
                                 .lock()
                                 .unwrap_or_else(|e| e.into_inner())
                                 .push(collector);
                         }
+                        Ok(_) => {}
                         Err(e) => {
                             warn!("Handler error: {}", e);
                         }
@@ -453,7 +585,7 @@ pub fn run_standalone_server_blocking_multi_accept_tcp(
                 handles.push(handle);
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                if is_shutdown_requested() {
+                if shutdown::requested() {
                     info!("Shutdown signal received, stopping accept loop");
                     break;
                 }
@@ -576,7 +708,7 @@ pub fn run_standalone_server_blocking_multi_accept_uds(
                 handles.push(handle);
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                if is_shutdown_requested() {
+                if shutdown::requested() {
                     info!("Shutdown signal received, stopping accept loop");
                     break;
                 }
@@ -628,6 +760,24 @@ pub fn run_standalone_server_blocking_multi_accept_uds(
     Ok(())
 }
 
+/// Mean, P50, P95, P99, min and max of a server-side latency summary.
+fn latency_summary(latency: &LatencyMetrics) -> String {
+    let percentile = |p| format_latency(percentile_ns(latency, p).unwrap_or(0));
+    format!(
+        "mean={}, P50={}, P95={}, P99={}, min={}, max={}",
+        format_latency(latency.mean_ns as u64),
+        format_latency(latency.median_ns as u64),
+        percentile(95.0),
+        percentile(99.0),
+        format_latency(latency.min_ns),
+        format_latency(latency.max_ns)
+    )
+}
+
+/// Why one-way latency from a client on another host is not reported
+const CROSS_HOST_ONE_WAY: &str = "Server one-way latency not reported: the client is on another \
+                                  host, whose monotonic clock cannot be compared with this one";
+
 /// Aggregate and print server-side one-way latency from multiple handler threads.
 pub fn aggregate_and_print_server_metrics(collectors: &[MetricsCollector], percentiles: &[f64]) {
     let total_one_way: u64 = collectors
@@ -641,27 +791,10 @@ pub fn aggregate_and_print_server_metrics(collectors: &[MetricsCollector], perce
             Ok(aggregated) => {
                 if let Some(ref latency) = aggregated.latency {
                     info!(
-                        "Server one-way latency ({} messages, {} clients): \
-                         mean={:.2}us, P50={:.2}us, P95={:.2}us, P99={:.2}us, \
-                         min={:.2}us, max={:.2}us",
+                        "Server one-way latency ({} messages, {} clients): {}",
                         total_one_way,
                         collectors.len(),
-                        latency.mean_ns / 1000.0,
-                        latency.median_ns / 1000.0,
-                        latency
-                            .percentiles
-                            .iter()
-                            .find(|p| (p.percentile - 95.0).abs() < 0.1)
-                            .map_or(0.0, |p| p.value_ns as f64)
-                            / 1000.0,
-                        latency
-                            .percentiles
-                            .iter()
-                            .find(|p| (p.percentile - 99.0).abs() < 0.1)
-                            .map_or(0.0, |p| p.value_ns as f64)
-                            / 1000.0,
-                        latency.min_ns as f64 / 1000.0,
-                        latency.max_ns as f64 / 1000.0,
+                        latency_summary(latency),
                     );
                 }
             }
@@ -670,34 +803,35 @@ pub fn aggregate_and_print_server_metrics(collectors: &[MetricsCollector], perce
     }
 }
 
-/// Print server-side one-way latency summary.
-pub fn print_server_one_way_latency(one_way_count: u64, one_way_metrics: &MetricsCollector) {
+/// Print server-side one-way latency summary, unless `client` is on
+/// another host.
+pub fn print_server_one_way_latency(
+    one_way_count: u64,
+    one_way_metrics: &MetricsCollector,
+    client: ClientHost,
+) {
     if one_way_count > 0 {
         let metrics = one_way_metrics.get_metrics();
-        if let Some(ref latency) = metrics.latency {
-            info!(
-                "Server one-way latency ({} messages): \
-                 mean={:.2}us, P50={:.2}us, P95={:.2}us, P99={:.2}us, \
-                 min={:.2}us, max={:.2}us",
+        match (client, &metrics.latency) {
+            (ClientHost::Other, _) => warn!("{}", CROSS_HOST_ONE_WAY),
+            (_, Some(latency)) => info!(
+                "Server one-way latency ({} messages): {}{}",
                 one_way_count,
-                latency.mean_ns / 1000.0,
-                latency.median_ns / 1000.0,
-                latency
-                    .percentiles
-                    .iter()
-                    .find(|p| (p.percentile - 95.0).abs() < 0.1)
-                    .map_or(0.0, |p| p.value_ns as f64)
-                    / 1000.0,
-                latency
-                    .percentiles
-                    .iter()
-                    .find(|p| (p.percentile - 99.0).abs() < 0.1)
-                    .map_or(0.0, |p| p.value_ns as f64)
-                    / 1000.0,
-                latency.min_ns as f64 / 1000.0,
-                latency.max_ns as f64 / 1000.0,
-            );
-        }
+                latency_summary(latency),
+                if client == ClientHost::Unknown {
+                    " [invalid if the client is on another host]"
+                } else {
+                    ""
+                },
+            ),
+            (_, None) => {}
+        }
+    }
+    if one_way_metrics.expired_count > 0 {
+        info!(
+            "Server dropped {} one-way messages that arrived past their deadline",
+            one_way_metrics.expired_count
+        );
     }
 }
 
@@ -706,7 +840,6 @@ pub fn print_server_one_way_latency(one_way_count: u64, one_way_metrics: &Metric
 /// For TCP and UDS, the server accepts multiple concurrent connections,
 /// spawning a tokio task per client. For SHM and PMQ, only a single
 /// connection is supported.
-#[tokio::main]
 pub async fn run_standalone_server_async(
     mechanism: IpcMechanism,
     transport_config: TransportConfig,
@@ -743,7 +876,7 @@ pub async fn run_standalone_server_async_single(
     let mut one_way_count = 0u64;
 
     loop {
-        if is_shutdown_requested() {
+        if shutdown::requested() {
             info!("Shutdown signal received, exiting");
             break;
         }
@@ -767,6 +900,10 @@ pub async fn run_standalone_server_async_single(
                         info!("Client disconnected during send, exiting.");
                         break;
                     }
+                    if response.message_type == MessageType::Handshake {
+                        transport
+                            .set_frame_checksums(handshake::agrees_on_frame_checksums(&response));
+                    }
                 }
             }
             Err(e) => {
@@ -776,7 +913,11 @@ pub async fn run_standalone_server_async_single(
         }
     }
 
-    print_server_one_way_latency(one_way_count, &one_way_metrics);
+    print_server_one_way_latency(
+        one_way_count,
+        &one_way_metrics,
+        ClientHost::of_listener(mechanism, &transport_config.host),
+    );
     let _ = transport.close().await;
     info!("Standalone server exiting cleanly.");
     Ok(())
@@ -841,13 +982,18 @@ pub async fn run_standalone_server_async_multi_accept_tcp(
 
                         let metrics_clone = worker_metrics.clone();
                         let handler_config = config.clone();
+                        let client = ClientHost::of_peer(peer_addr.ip());
+                        if client == ClientHost::Other {
+                            warn!("{} ({})", CROSS_HOST_ONE_WAY, peer_addr);
+                        }
 
                         handles.spawn_blocking(move || {
                             let mut transport = BlockingTcpSocket::from_stream(std_stream);
                             match handle_client_connection(&mut transport, &handler_config) {
-                                Ok(collector) => {
+                                Ok(collector) if client == ClientHost::Same => {
                                     metrics_clone.lock().unwrap_or_else(|e| e.into_inner()).push(collector);
                                 }
+                                Ok(_) => {}
                                 Err(e) => {
                                     warn!("Handler error: {}", e);
                                 }
@@ -872,7 +1018,7 @@ pub async fn run_standalone_server_async_multi_accept_tcp(
             }
         }
 
-        if is_shutdown_requested() {
+        if shutdown::requested() {
             info!("Shutdown signal received, stopping accept loop");
             break;
         }
@@ -996,7 +1142,7 @@ pub async fn run_standalone_server_async_multi_accept_uds(
             }
         }
 
-        if is_shutdown_requested() {
+        if shutdown::requested() {
             info!("Shutdown signal received, stopping accept loop");
             break;
         }
@@ -1152,6 +1298,14 @@ mod tests {
         assert!(dispatch_server_message(&msg).is_none());
     }
 
+    #[test]
+    fn test_dispatch_server_message_answers_handshake() {
+        let hello = Handshake::new(1024, false).hello();
+        let resp = dispatch_server_message(&hello).unwrap();
+        assert_eq!(resp.message_type, MessageType::Handshake);
+        assert_eq!(resp.payload, Handshake::new(1024, false).hello().payload);
+    }
+
     /// Integration test: blocking TCP round-trip with duration mode.
     #[test]
     fn test_standalone_blocking_tcp_duration_round_trip() {
@@ -2332,7 +2486,46 @@ mod tests {
                 .unwrap();
         }
         // Should print without panic
-        print_server_one_way_latency(5, &metrics);
+        print_server_one_way_latency(5, &metrics, ClientHost::Same);
+        print_server_one_way_latency(5, &metrics, ClientHost::Unknown);
+        print_server_one_way_latency(5, &metrics, ClientHost::Other);
+    }
+
+    /// Test: clients are on this host only when their address is local.
+    #[test]
+    fn test_client_host() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        assert_eq!(
+            ClientHost::of_peer(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            ClientHost::Same
+        );
+        assert_eq!(
+            ClientHost::of_peer(IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped())),
+            ClientHost::Same
+        );
+        assert_eq!(
+            ClientHost::of_peer(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+            ClientHost::Same
+        );
+        // TEST-NET-3, never assigned to an interface
+        assert_eq!(
+            ClientHost::of_peer(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))),
+            ClientHost::Other
+        );
+
+        assert_eq!(
+            ClientHost::of_listener(IpcMechanism::SharedMemory, "0.0.0.0"),
+            ClientHost::Same
+        );
+        assert_eq!(
+            ClientHost::of_listener(IpcMechanism::Http, "127.0.0.1"),
+            ClientHost::Same
+        );
+        assert_eq!(
+            ClientHost::of_listener(IpcMechanism::Grpc, "0.0.0.0"),
+            ClientHost::Unknown
+        );
     }
 
     /// Test: print_server_one_way_latency with zero count.
@@ -2344,7 +2537,7 @@ mod tests {
         )
         .unwrap();
         // Should not panic with zero count
-        print_server_one_way_latency(0, &metrics);
+        print_server_one_way_latency(0, &metrics, ClientHost::Same);
     }
 
     /// Test: async multi-accept TCP server handles clients via
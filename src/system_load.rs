@@ -0,0 +1,351 @@
+//! Busy-system detection before a run.
+//!
+//! Latency measured next to a compiler, a backup or a stuck process says
+//! more about the neighbour than about the mechanism. Before the first test
+//! the machine is sampled for [`SAMPLE_WINDOW`]: the load average from
+//! `/proc/loadavg`, the utilization of each core from `/proc/stat`, and the
+//! processes that used a noticeable share of a core from `/proc/<pid>/stat`.
+//! A machine over any of the limits below is busy, with a finding naming
+//! the cause.
+//!
+//! The sample is recorded with the results under `system_info.load` and a
+//! busy machine is warned about. With `--require-idle` the run is refused
+//! instead. Only Linux is sampled; elsewhere the check is skipped.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How long the machine is observed before the run
+pub const SAMPLE_WINDOW: Duration = Duration::from_millis(250);
+
+/// One-minute load average per core above which the machine is busy
+pub const LOAD_PER_CORE_LIMIT: f64 = 0.5;
+
+/// Utilization of a single core, in percent, above which it is busy
+pub const CORE_BUSY_LIMIT: f64 = 25.0;
+
+/// Share of one core, in percent, above which another process competes
+pub const PROCESS_BUSY_LIMIT: f64 = 20.0;
+
+/// A process that used a noticeable share of a core during the sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusyProcess {
+    /// Process ID
+    pub pid: u32,
+
+    /// Command name from `/proc/<pid>/stat`
+    pub command: String,
+
+    /// CPU used over the sample, in percent of one core
+    pub cpu_percent: f64,
+}
+
+/// How busy the machine was just before the run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemLoad {
+    /// Load average over 1, 5 and 15 minutes
+    pub load_average: [f64; 3],
+
+    /// Utilization of each core over the sample, in percent, by core index
+    pub core_busy_percent: Vec<f64>,
+
+    /// Other processes over [`PROCESS_BUSY_LIMIT`], busiest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub busy_processes: Vec<BusyProcess>,
+
+    /// Why the machine counts as busy; empty when it was idle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<String>,
+}
+
+impl SystemLoad {
+    /// Whether any limit was exceeded
+    pub fn is_busy(&self) -> bool {
+        !self.findings.is_empty()
+    }
+
+    /// Observe the machine for `window`; `None` where `/proc` is unavailable.
+    #[cfg(target_os = "linux")]
+    pub fn sample(window: Duration) -> Option<Self> {
+        let load_average = parse_loadavg(&std::fs::read_to_string("/proc/loadavg").ok()?)?;
+        let cores_before = parse_core_times(&std::fs::read_to_string("/proc/stat").ok()?);
+        let processes_before = process_ticks();
+        std::thread::sleep(window);
+        let cores_after = parse_core_times(&std::fs::read_to_string("/proc/stat").ok()?);
+        let processes_after = process_ticks();
+
+        let core_busy_percent = cores_before
+            .iter()
+            .zip(&cores_after)
+            .map(|(before, after)| after.busy_percent_since(before))
+            .collect();
+
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+        let own_pid = std::process::id();
+        let mut busy_processes: Vec<BusyProcess> = processes_after
+            .into_iter()
+            .filter(|(pid, _, _)| *pid != own_pid)
+            .filter_map(|(pid, command, ticks)| {
+                let (_, _, before) = processes_before.iter().find(|(p, _, _)| *p == pid)?;
+                let cpu_percent =
+                    ticks.saturating_sub(*before) as f64 / ticks_per_second / window.as_secs_f64()
+                        * 100.0;
+                (cpu_percent > PROCESS_BUSY_LIMIT).then_some(BusyProcess {
+                    pid,
+                    command,
+                    cpu_percent,
+                })
+            })
+            .collect();
+        busy_processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+
+        let mut load = Self {
+            load_average,
+            core_busy_percent,
+            busy_processes,
+            findings: Vec::new(),
+        };
+        // The load average counts every CPU of the host, not only those
+        // this process may use
+        load.findings = load.find_busy(load.core_busy_percent.len());
+        Some(load)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn sample(_window: Duration) -> Option<Self> {
+        None
+    }
+
+    /// Findings for each limit exceeded on a machine with `cpus` CPUs.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn find_busy(&self, cpus: usize) -> Vec<String> {
+        let mut findings = Vec::new();
+        let load_per_core = self.load_average[0] / cpus.max(1) as f64;
+        if load_per_core > LOAD_PER_CORE_LIMIT {
+            findings.push(format!(
+                "load average {:.2} on {} CPUs",
+                self.load_average[0], cpus
+            ));
+        }
+        for (core, busy) in self.core_busy_percent.iter().enumerate() {
+            if *busy > CORE_BUSY_LIMIT {
+                findings.push(format!("core {} {:.0}% busy", core, busy));
+            }
+        }
+        for process in &self.busy_processes {
+            findings.push(format!(
+                "process {} ({}) using {:.0}% of a core",
+                process.pid, process.command, process.cpu_percent
+            ));
+        }
+        findings
+    }
+}
+
+impl std::fmt::Display for SystemLoad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [one, five, fifteen] = self.load_average;
+        if self.is_busy() {
+            write!(f, "busy: {}", self.findings.join(", "))
+        } else {
+            let busiest = self.core_busy_percent.iter().copied().fold(0.0, f64::max);
+            write!(
+                f,
+                "idle (load average {:.2} {:.2} {:.2}, busiest core {:.0}%)",
+                one, five, fifteen, busiest
+            )
+        }
+    }
+}
+
+/// Sample the machine before a run, refusing to run on a busy one when
+/// `require_idle` is set.
+pub fn check(require_idle: bool) -> Result<Option<SystemLoad>> {
+    let Some(load) = SystemLoad::sample(SAMPLE_WINDOW) else {
+        if require_idle {
+            warn!("--require-idle: system load cannot be read on this platform; running anyway");
+        }
+        return Ok(None);
+    };
+    if !load.is_busy() {
+        info!("System load: {}", load);
+    } else if require_idle {
+        anyhow::bail!(
+            "--require-idle: the system is {}. Rerun once it is idle, or drop --require-idle",
+            load
+        );
+    } else {
+        warn!("System load: {}. Results may be contaminated", load);
+    }
+    Ok(Some(load))
+}
+
+/// Cumulative time of one core from `/proc/stat`, in clock ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct CoreTimes {
+    busy: u64,
+    total: u64,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl CoreTimes {
+    fn busy_percent_since(&self, earlier: &CoreTimes) -> f64 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy.saturating_sub(earlier.busy) as f64 / total as f64 * 100.0
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_loadavg(contents: &str) -> Option<[f64; 3]> {
+    let mut fields = contents.split_whitespace().map(|f| f.parse::<f64>().ok());
+    Some([fields.next()??, fields.next()??, fields.next()??])
+}
+
+/// Per-core times from `/proc/stat`, in core order. Idle and I/O wait
+/// count as idle.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_core_times(contents: &str) -> Vec<CoreTimes> {
+    contents
+        .lines()
+        .filter(|line| {
+            line.strip_prefix("cpu")
+                .and_then(|rest| rest.chars().next())
+                .is_some_and(|c| c.is_ascii_digit())
+        })
+        .map(|line| {
+            let ticks: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|f| f.parse().ok())
+                .collect();
+            let total: u64 = ticks.iter().take(8).sum();
+            let idle = ticks.get(3).copied().unwrap_or(0) + ticks.get(4).copied().unwrap_or(0);
+            CoreTimes {
+                busy: total - idle,
+                total,
+            }
+        })
+        .collect()
+}
+
+/// Command name and user plus system ticks from a `/proc/<pid>/stat` line.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_process_stat(contents: &str) -> Option<(String, u64)> {
+    // The command is in parentheses and may itself contain spaces or ')'
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let command = contents.get(open + 1..close)?.to_string();
+    let mut fields = contents.get(close + 1..)?.split_whitespace();
+    // utime and stime are fields 14 and 15; the state, field 3, comes first
+    let utime: u64 = fields.nth(11)?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some((command, utime + stime))
+}
+
+/// PID, command and CPU ticks of every process
+#[cfg(target_os = "linux")]
+fn process_ticks() -> Vec<(u32, String, u64)> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let pid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            let (command, ticks) = parse_process_stat(&stat)?;
+            Some((pid, command, ticks))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_files() {
+        assert_eq!(
+            parse_loadavg("0.52 0.58 0.59 2/1067 12345\n"),
+            Some([0.52, 0.58, 0.59])
+        );
+        assert_eq!(parse_loadavg("garbage"), None);
+
+        let stat = "cpu  100 0 100 800 0 0 0 0 0 0\n\
+                    cpu0 50 0 50 400 0 0 0 0 0 0\n\
+                    cpu1 50 0 50 300 100 0 0 0 0 0\n\
+                    intr 12345\n";
+        let cores = parse_core_times(stat);
+        assert_eq!(
+            cores,
+            vec![
+                CoreTimes {
+                    busy: 100,
+                    total: 500
+                },
+                CoreTimes {
+                    busy: 100,
+                    total: 500
+                }
+            ]
+        );
+        let later = CoreTimes {
+            busy: 175,
+            total: 600,
+        };
+        assert_eq!(later.busy_percent_since(&cores[0]), 75.0);
+
+        let (command, ticks) = parse_process_stat(
+            "4242 (my (odd) cmd) R 1 4242 4242 0 -1 4194304 100 0 0 0 700 55 0 0 20 0 1 0",
+        )
+        .unwrap();
+        assert_eq!(command, "my (odd) cmd");
+        assert_eq!(ticks, 755);
+    }
+
+    #[test]
+    fn test_findings() {
+        let mut load = SystemLoad {
+            load_average: [0.3, 0.2, 0.1],
+            core_busy_percent: vec![2.0, 4.0],
+            busy_processes: Vec::new(),
+            findings: Vec::new(),
+        };
+        assert!(load.find_busy(2).is_empty());
+        assert_eq!(
+            load.to_string(),
+            "idle (load average 0.30 0.20 0.10, busiest core 4%)"
+        );
+
+        load.load_average[0] = 3.0;
+        load.core_busy_percent[1] = 98.0;
+        load.busy_processes.push(BusyProcess {
+            pid: 99,
+            command: "stress".to_string(),
+            cpu_percent: 97.0,
+        });
+        load.findings = load.find_busy(2);
+        assert!(load.is_busy());
+        assert_eq!(
+            load.to_string(),
+            "busy: load average 3.00 on 2 CPUs, core 1 98% busy, \
+             process 99 (stress) using 97% of a core"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sample_reads_this_machine() {
+        let load = SystemLoad::sample(Duration::from_millis(20)).unwrap();
+        assert!(!load.core_busy_percent.is_empty());
+        assert!(load
+            .busy_processes
+            .iter()
+            .all(|p| p.pid != std::process::id()));
+    }
+}
@@ -0,0 +1,345 @@
+//! Upload of results to object storage.
+//!
+//! `--upload DEST` pushes the files a run wrote (final JSON, streaming
+//! output, summary history) once the results are finalized, so ephemeral CI
+//! runners and containers do not take them along when they are destroyed.
+//! Files go to `DEST/<run ID>/<file name>`, followed by a `manifest.json`
+//! listing each file with its size and CRC-32. The manifest is uploaded
+//! last, so its presence marks a complete upload.
+//!
+//! ## Destinations
+//!
+//! - `s3://bucket/prefix` is copied with `aws s3 cp`, using the AWS CLI's
+//!   usual credentials and endpoint configuration.
+//! - `http://` and `https://` URLs receive an HTTP PUT from `curl`. Extra
+//!   headers, such as authorization, are read by curl from the
+//!   `--upload-headers-file`, so they never appear on a command line.
+//!
+//! Each file is tried `--upload-retries` more times after a failure, with
+//! the wait doubling from one second. Local files are never removed.
+
+use crate::cli::Args;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Name of the manifest uploaded after the results
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Where results are uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// `s3://bucket/prefix`, via the AWS CLI
+    S3(String),
+
+    /// HTTP(S) base URL, via `curl -T`
+    Http(String),
+}
+
+impl Destination {
+    /// Parse `--upload`, rejecting schemes that are not supported.
+    pub fn parse(dest: &str) -> Result<Self, String> {
+        let dest = dest.trim_end_matches('/');
+        if let Some(path) = dest.strip_prefix("s3://") {
+            if path.is_empty() {
+                return Err("an S3 destination needs a bucket: s3://bucket/prefix".to_string());
+            }
+            Ok(Self::S3(dest.to_string()))
+        } else if dest.starts_with("http://") || dest.starts_with("https://") {
+            Ok(Self::Http(dest.to_string()))
+        } else {
+            Err(format!(
+                "unsupported upload destination '{}': use s3://bucket/prefix or an http(s):// URL",
+                dest
+            ))
+        }
+    }
+
+    /// URL of `name` under the destination
+    fn url(&self, run_id: &str, name: &str) -> String {
+        let base = match self {
+            Self::S3(base) | Self::Http(base) => base,
+        };
+        format!("{}/{}/{}", base, run_id, name)
+    }
+
+    fn command(&self, file: &Path, url: &str, headers: Option<&Path>) -> Command {
+        match self {
+            Self::S3(_) => {
+                let mut cmd = Command::new("aws");
+                cmd.args(["s3", "cp", "--only-show-errors"])
+                    .arg(file)
+                    .arg(url);
+                cmd
+            }
+            Self::Http(_) => {
+                let mut cmd = Command::new("curl");
+                cmd.args(["--silent", "--show-error", "--fail", "-T"])
+                    .arg(file);
+                if let Some(headers) = headers {
+                    let mut from_file = std::ffi::OsString::from("@");
+                    from_file.push(headers);
+                    cmd.arg("-H").arg(from_file);
+                }
+                cmd.arg(url);
+                cmd
+            }
+        }
+    }
+}
+
+/// One uploaded file, in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadedFile {
+    /// File name under the run's prefix
+    pub name: String,
+
+    /// Size in bytes
+    pub bytes: u64,
+
+    /// CRC-32 of the contents
+    pub crc32: u32,
+
+    /// Where the file was uploaded
+    pub url: String,
+}
+
+/// The manifest uploaded after the results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadManifest {
+    /// Run ID the files belong to
+    pub run_id: String,
+
+    /// When the upload finished
+    pub uploaded: DateTime<Utc>,
+
+    /// Every uploaded file
+    pub files: Vec<UploadedFile>,
+}
+
+/// Upload settings of a run
+#[derive(Debug, Clone)]
+pub struct Upload {
+    destination: Destination,
+    retries: u32,
+    headers: Option<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl Upload {
+    /// The upload `args` ask for, if any, with the files the run may write.
+    pub fn from_args(args: &Args) -> Option<Self> {
+        let destination = args.upload.clone()?;
+        let mut files: Vec<PathBuf> = [
+            args.output_file.clone(),
+            args.streaming_output_json.clone(),
+            args.streaming_output_csv.clone(),
+            args.streaming_output_csv
+                .as_ref()
+                .map(|csv| csv.with_extension("queue_depth.csv")),
+            args.streaming_output_csv
+                .as_ref()
+                .map(|csv| crate::csv_schema::schema_path(csv)),
+            args.append_summary.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        files.dedup();
+        Some(Self {
+            destination,
+            retries: args.upload_retries,
+            headers: args.upload_headers_file.clone(),
+            files,
+        })
+    }
+
+    /// Upload the files that exist, then the manifest.
+    pub fn push(&self, run_id: &str) -> Result<UploadManifest> {
+        let mut uploaded = Vec::new();
+        for file in self.files.iter().filter(|file| file.is_file()) {
+            let name = file_name(file)?;
+            let (bytes, crc32) =
+                checksum(file).with_context(|| format!("Failed to read {}", file.display()))?;
+            let url = self.destination.url(run_id, &name);
+            self.put(file, &url)?;
+            uploaded.push(UploadedFile {
+                name,
+                bytes,
+                crc32,
+                url,
+            });
+        }
+        if uploaded.is_empty() {
+            anyhow::bail!("--upload found no result files; write some with --output-file");
+        }
+
+        let manifest = UploadManifest {
+            run_id: run_id.to_string(),
+            uploaded: Utc::now(),
+            files: uploaded,
+        };
+        // Created exclusively under a random name and removed when dropped
+        let mut local = tempfile::Builder::new()
+            .prefix("ipc-benchmark-manifest-")
+            .suffix(".json")
+            .tempfile()
+            .context("Failed to create the upload manifest")?;
+        local.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+        local.flush()?;
+        self.put(local.path(), &self.destination.url(run_id, MANIFEST_NAME))?;
+        Ok(manifest)
+    }
+
+    /// Upload one file, retrying with exponential backoff.
+    fn put(&self, file: &Path, url: &str) -> Result<()> {
+        let mut delay = Duration::from_secs(1);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match self
+                .destination
+                .command(file, url, self.headers.as_deref())
+                .output()
+            {
+                Ok(output) if output.status.success() => {
+                    tracing::info!("Uploaded {} to {}", file.display(), url);
+                    return Ok(());
+                }
+                Ok(output) => format!(
+                    "{}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(e) => e.to_string(),
+            };
+            if attempt > self.retries {
+                anyhow::bail!(
+                    "Failed to upload {} to {} after {} attempts: {}",
+                    file.display(),
+                    url,
+                    attempt,
+                    error
+                );
+            }
+            tracing::warn!(
+                "Upload of {} failed ({}), retrying in {:?}",
+                file.display(),
+                error,
+                delay
+            );
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+    }
+}
+
+/// Size and CRC-32 of the file at `path`, read in chunks.
+fn checksum(path: &Path) -> std::io::Result<(u64, u32)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut bytes = 0;
+    loop {
+        match file.read(&mut buffer)? {
+            0 => return Ok((bytes, hasher.finalize())),
+            n => {
+                hasher.update(&buffer[..n]);
+                bytes += n as u64;
+            }
+        }
+    }
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .with_context(|| format!("{} has no file name", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_destination() {
+        assert_eq!(
+            Destination::parse("s3://bucket/ci/").unwrap(),
+            Destination::S3("s3://bucket/ci".to_string())
+        );
+        assert_eq!(
+            Destination::parse("https://store.example/results").unwrap(),
+            Destination::Http("https://store.example/results".to_string())
+        );
+        assert!(Destination::parse("s3://").is_err());
+        assert!(Destination::parse("ftp://host/x").is_err());
+        assert_eq!(
+            Destination::parse("s3://bucket/ci")
+                .unwrap()
+                .url("run-1", "results.json"),
+            "s3://bucket/ci/run-1/results.json"
+        );
+    }
+
+    #[test]
+    fn test_headers_stay_off_the_command_line() {
+        let destination = Destination::Http("https://store.example/results".to_string());
+        let command = destination.command(
+            Path::new("results.json"),
+            "https://store.example/results/run-1/results.json",
+            Some(Path::new("/run/secrets/headers")),
+        );
+        let args: Vec<_> = command.get_args().collect();
+        assert!(args
+            .windows(2)
+            .any(|pair| pair[0] == "-H" && pair[1] == "@/run/secrets/headers"));
+    }
+
+    #[test]
+    fn test_checksum_matches_whole_file_hash() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let contents: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        std::fs::write(file.path(), &contents).unwrap();
+        assert_eq!(
+            checksum(file.path()).unwrap(),
+            (contents.len() as u64, crc32fast::hash(&contents))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_push_to_file_url_with_curl() {
+        // curl also uploads to file:// URLs, standing in for an HTTP server
+        if Command::new("curl").arg("--version").output().is_err() {
+            return;
+        }
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let results = source.path().join("results.json");
+        std::fs::write(&results, "{}").unwrap();
+        std::fs::create_dir(target.path().join("run-1")).unwrap();
+        let headers = source.path().join("headers");
+        std::fs::write(&headers, "Authorization: Bearer secret\n").unwrap();
+
+        let upload = Upload {
+            destination: Destination::Http(format!("file://{}", target.path().display())),
+            retries: 0,
+            headers: Some(headers),
+            files: vec![results, source.path().join("missing.csv")],
+        };
+        let manifest = upload.push("run-1").unwrap();
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].bytes, 2);
+        assert_eq!(manifest.files[0].crc32, crc32fast::hash(b"{}"));
+        assert!(target.path().join("run-1/results.json").is_file());
+        let uploaded: UploadManifest = serde_json::from_str(
+            &std::fs::read_to_string(target.path().join("run-1").join(MANIFEST_NAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(uploaded.files, manifest.files);
+    }
+}
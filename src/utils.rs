@@ -113,6 +113,9 @@ pub fn get_temp_socket_path(filename: &str) -> String {
 /// If `core_id` is `Some(n)`, the spawned thread will attempt to pin itself
 /// to CPU core `n` using the `core_affinity` crate. If affinity cannot be set,
 /// the future will still run normally.
+///
+/// The thread runs inside the caller's current tracing span, so its log
+/// lines keep fields such as the run ID.
 pub async fn spawn_with_affinity<F, T>(future: F, core_id: Option<usize>) -> anyhow::Result<T>
 where
     F: std::future::Future<Output = anyhow::Result<T>> + Send + 'static,
@@ -120,8 +123,11 @@ where
 {
     // Use a dedicated OS thread instead of Tokio's thread pool to maintain CPU affinity
     let (sender, receiver) = tokio::sync::oneshot::channel();
+    let span = tracing::Span::current();
 
     std::thread::spawn(move || {
+        let _span = span.entered();
+
         // Run the async future on this dedicated thread using a local runtime
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -202,6 +208,34 @@ pub fn current_timestamp_ns() -> u64 {
         .as_nanos() as u64
 }
 
+/// Flushes dirty pages and drops the kernel page, dentry and inode caches.
+///
+/// Used by `--drop-caches` to produce cold-cache runs on purpose. Writing
+/// to `/proc/sys/vm/drop_caches` requires root, so callers should treat an
+/// error as "the run was not cold" rather than as fatal.
+///
+/// ## Returns
+///
+/// - `Ok(())` if the caches were dropped.
+/// - `Err(anyhow::Error)` if the write failed or the platform is not Linux.
+pub fn drop_page_caches() -> anyhow::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use anyhow::Context;
+
+        // Dirty pages are not dropped, so flush them first.
+        unsafe { libc::sync() };
+        std::fs::write("/proc/sys/vm/drop_caches", "3\n")
+            .context("Failed to write /proc/sys/vm/drop_caches (root required)")
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(anyhow::anyhow!(
+            "Dropping page caches is only supported on Linux"
+        ))
+    }
+}
+
 /// Sets the CPU affinity for the current thread to the specified core.
 ///
 /// This function takes a core ID as input and attempts to pin the current
@@ -244,9 +278,30 @@ pub fn set_affinity(core_id: usize) -> anyhow::Result<()> {
     }
 }
 
+/// `token` as a POSIX shell word, quoted only when it has to be.
+pub fn shell_quote(token: &str) -> String {
+    let plain = !token.is_empty()
+        && token
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"_-+=.,/:@%".contains(&b));
+    if plain {
+        token.to_string()
+    } else {
+        format!("'{}'", token.replace('\'', r"'\''"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{get_temp_dir, get_temp_socket_path, spawn_with_affinity};
+    use super::{get_temp_dir, get_temp_socket_path, shell_quote, spawn_with_affinity};
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("--buffer-size"), "--buffer-size");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
 
     /// Smoke test for spawn_with_affinity: ensures the future runs and returns a value.
     #[tokio::test]
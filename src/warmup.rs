@@ -0,0 +1,315 @@
+//! Warmup-phase latency capture.
+//!
+//! Warmup messages are normally sent and forgotten, so how long a mechanism
+//! takes to reach steady state (cold caches, page faults on new buffers,
+//! lazily grown socket queues) is never seen. With `--capture-warmup` each
+//! warmup message is sent as a request and its reply awaited, and the round
+//! trips are kept in a distribution of their own, reported under `warmup`
+//! and never mixed into the measured results.
+//!
+//! Besides the overall distribution, the warmup is cut into
+//! [`SEGMENTS`] consecutive segments with the median and P99 of each, which
+//! traces the warmup curve: comparing it between mechanisms shows which
+//! settle quickly and which keep improving for thousands of messages.
+//!
+//! How long the warmup lasts is a [`WarmupLength`]: a number of messages
+//! (`-w`), a time (`--warmup 2s`), or either one for a single mechanism
+//! (`--warmup-for pmq=5s`), since PMQ and TCP take far longer to settle
+//! than shared memory and one global count has to fit the slowest.
+
+#[cfg(feature = "cli")]
+use crate::cli::{parse_duration_micros, Args};
+use crate::mechanism::IpcMechanism;
+use crate::metrics::{LatencyCollector, LatencyMetrics, LatencyType};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Number of segments the warmup curve is cut into
+pub const SEGMENTS: usize = 10;
+
+/// Latency of one stretch of consecutive warmup messages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmupSegment {
+    /// Index of the segment's first warmup message
+    pub first_message: usize,
+
+    /// Messages in the segment
+    pub messages: usize,
+
+    /// Median round trip of the segment, in nanoseconds
+    pub median_ns: u64,
+
+    /// P99 round trip of the segment, in nanoseconds
+    pub p99_ns: u64,
+}
+
+/// Latencies of the warmup phase of one test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupReport {
+    /// Distribution of every warmup round trip
+    pub latency: LatencyMetrics,
+
+    /// The warmup curve, in message order
+    pub segments: Vec<WarmupSegment>,
+}
+
+/// Warmup round trips recorded by the client
+#[derive(Debug, Default)]
+pub struct WarmupCapture {
+    latencies_ns: Vec<u64>,
+}
+
+impl WarmupCapture {
+    pub fn with_capacity(iterations: usize) -> Self {
+        Self {
+            latencies_ns: Vec::with_capacity(iterations),
+        }
+    }
+
+    /// Record the round trip of the next warmup message.
+    pub fn record(&mut self, latency: Duration) {
+        self.latencies_ns.push(latency.as_nanos() as u64);
+    }
+
+    /// The report of the recorded round trips, or `None` if there were none.
+    pub fn finish(self, percentiles: &[f64]) -> Result<Option<WarmupReport>> {
+        if self.latencies_ns.is_empty() {
+            return Ok(None);
+        }
+        let mut collector = LatencyCollector::new(LatencyType::RoundTrip)?;
+        for &latency in &self.latencies_ns {
+            collector.record(Duration::from_nanos(latency))?;
+        }
+        Ok(Some(WarmupReport {
+            latency: collector.get_metrics(percentiles),
+            segments: segments(&self.latencies_ns),
+        }))
+    }
+}
+
+/// How long a mechanism warms up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupLength {
+    /// Send this many messages
+    Iterations(usize),
+
+    /// Send messages until this much time has passed
+    Duration(Duration),
+}
+
+impl WarmupLength {
+    /// Parse a message count ("5000") or a duration ("2s", "500ms").
+    #[cfg(feature = "cli")]
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.parse::<usize>() {
+            Ok(iterations) => Ok(Self::Iterations(iterations)),
+            Err(_) => parse_duration_micros(s).map(Self::Duration),
+        }
+    }
+
+    /// Whether no warmup messages are sent at all.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Iterations(iterations) => *iterations == 0,
+            Self::Duration(duration) => duration.is_zero(),
+        }
+    }
+
+    /// Whether another warmup message is due after `sent` messages were
+    /// sent since `started`.
+    pub fn continues(&self, sent: usize, started: Instant) -> bool {
+        match self {
+            Self::Iterations(iterations) => sent < *iterations,
+            Self::Duration(duration) => started.elapsed() < *duration,
+        }
+    }
+
+    /// Messages to reserve room for when capturing the warmup.
+    pub fn capacity(&self) -> usize {
+        match self {
+            Self::Iterations(iterations) => *iterations,
+            Self::Duration(_) => 0,
+        }
+    }
+
+    /// The time of a timed warmup.
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            Self::Iterations(_) => None,
+            Self::Duration(duration) => Some(*duration),
+        }
+    }
+}
+
+impl fmt::Display for WarmupLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Iterations(iterations) => write!(f, "{} iterations", iterations),
+            Self::Duration(duration) => write!(f, "{:?}", duration),
+        }
+    }
+}
+
+/// Warmup of one mechanism (`--warmup-for MECHANISM=LENGTH`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupOverride {
+    /// Mechanism the length applies to
+    pub mechanism: IpcMechanism,
+
+    /// Its warmup
+    pub length: WarmupLength,
+}
+
+/// Parse `MECHANISM=LENGTH`, where the length is a message count or a
+/// duration.
+#[cfg(feature = "cli")]
+pub fn parse_override(s: &str) -> Result<WarmupOverride, String> {
+    let (name, length) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected MECHANISM=LENGTH, got '{}'", s))?;
+    let mechanism = IpcMechanism::from_name(name.trim())
+        .ok_or_else(|| format!("unknown mechanism '{}'", name))?;
+    if mechanism == IpcMechanism::All {
+        return Err("name a single mechanism, not 'all'".to_string());
+    }
+    Ok(WarmupOverride {
+        mechanism,
+        length: WarmupLength::parse(length.trim())?,
+    })
+}
+
+/// The `--warmup-for` override of `mechanism`, the last one if repeated.
+#[cfg(feature = "cli")]
+pub fn override_for(args: &Args, mechanism: IpcMechanism) -> Option<WarmupLength> {
+    args.warmup_for
+        .iter()
+        .rev()
+        .find(|o| o.mechanism == mechanism)
+        .map(|o| o.length)
+}
+
+/// The warmup of `mechanism`: its `--warmup-for` override, else the
+/// `--warmup` duration, else `iterations` messages.
+#[cfg(feature = "cli")]
+pub fn length_for(args: &Args, mechanism: IpcMechanism, iterations: usize) -> WarmupLength {
+    override_for(args, mechanism)
+        .or(args.warmup.map(WarmupLength::Duration))
+        .unwrap_or(WarmupLength::Iterations(iterations))
+}
+
+/// Cut `latencies_ns` into up to [`SEGMENTS`] segments of nearly equal
+/// length, the earlier ones taking the remainder.
+fn segments(latencies_ns: &[u64]) -> Vec<WarmupSegment> {
+    let count = SEGMENTS.min(latencies_ns.len());
+    let mut segments = Vec::with_capacity(count);
+    let mut first_message = 0;
+    for i in 0..count {
+        let messages = latencies_ns.len() / count + usize::from(i < latencies_ns.len() % count);
+        let mut sorted = latencies_ns[first_message..first_message + messages].to_vec();
+        sorted.sort_unstable();
+        let rank = |percentile: f64| {
+            let index = ((percentile / 100.0) * messages as f64).ceil() as usize;
+            sorted[index.clamp(1, messages) - 1]
+        };
+        segments.push(WarmupSegment {
+            first_message,
+            messages,
+            median_ns: rank(50.0),
+            p99_ns: rank(99.0),
+        });
+        first_message += messages;
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments_trace_the_curve() {
+        // Latency falls from 2000ns to 1000ns over the warmup
+        let latencies: Vec<u64> = (0..95).map(|i| 2000 - (i as u64 / 10) * 100).collect();
+        let segments = segments(&latencies);
+        assert_eq!(segments.len(), SEGMENTS);
+        assert_eq!(segments.iter().map(|s| s.messages).sum::<usize>(), 95);
+        // The remainder goes to the earlier segments
+        assert_eq!(segments[0].messages, 10);
+        assert_eq!(segments[9].messages, 9);
+        assert_eq!(segments[9].first_message, 86);
+        assert!(segments[0].median_ns > segments[9].median_ns);
+    }
+
+    #[test]
+    fn test_short_warmup_has_fewer_segments() {
+        let segments = segments(&[300, 100, 200]);
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[1].median_ns, 100);
+        assert_eq!(segments[1].p99_ns, 100);
+    }
+
+    #[test]
+    fn test_capture_report() {
+        assert!(WarmupCapture::default().finish(&[50.0]).unwrap().is_none());
+
+        let mut capture = WarmupCapture::with_capacity(20);
+        for i in 0..20 {
+            capture.record(Duration::from_micros(if i < 10 { 20 } else { 10 }));
+        }
+        let report = capture.finish(&[50.0, 99.0]).unwrap().unwrap();
+        assert_eq!(report.latency.total_samples, 20);
+        assert_eq!(report.segments.len(), 10);
+        assert_eq!(report.segments[0].median_ns, 20_000);
+        assert_eq!(report.segments[9].median_ns, 10_000);
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_warmup_length_for_each_mechanism() {
+        use clap::Parser;
+
+        assert_eq!(
+            WarmupLength::parse("5000"),
+            Ok(WarmupLength::Iterations(5000))
+        );
+        let timed = WarmupLength::parse("2s").unwrap();
+        assert_eq!(timed.duration(), Some(Duration::from_secs(2)));
+        assert!(timed.continues(1_000_000, Instant::now()));
+        assert!(!WarmupLength::Iterations(3).continues(3, Instant::now()));
+        assert!(WarmupLength::Duration(Duration::ZERO).is_empty());
+
+        assert!(parse_override("pmq").is_err());
+        assert!(parse_override("all=1s").is_err());
+        assert!(parse_override("nope=1s").is_err());
+
+        let args = Args::parse_from([
+            "ipc-benchmark",
+            "--warmup",
+            "500ms",
+            "--warmup-for",
+            "tcp=3s",
+            "--warmup-for",
+            "http=200",
+        ]);
+        assert_eq!(
+            length_for(&args, IpcMechanism::TcpSocket, 1000),
+            WarmupLength::Duration(Duration::from_secs(3))
+        );
+        assert_eq!(
+            length_for(&args, IpcMechanism::Http, 1000),
+            WarmupLength::Iterations(200)
+        );
+        assert_eq!(
+            length_for(&args, IpcMechanism::SharedMemory, 1000),
+            WarmupLength::Duration(Duration::from_millis(500))
+        );
+        let args = Args::parse_from(["ipc-benchmark"]);
+        assert_eq!(
+            length_for(&args, IpcMechanism::SharedMemory, 1000),
+            WarmupLength::Iterations(1000)
+        );
+    }
+}
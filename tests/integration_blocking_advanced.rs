@@ -3,8 +3,15 @@
 //! These tests verify advanced features like CPU affinity, send delays,
 //! and consistency checks between async and blocking modes.
 
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use anyhow::Result;
-use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
+#[cfg(unix)]
+use ipc_benchmark::metrics::{ConnectionReuse, RoundTripPhase};
+use ipc_benchmark::{
+    cli::{Args, SpinWait},
+    BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism,
+};
 
 /// Test blocking mode with CPU affinity for client
 #[test]
@@ -197,3 +204,294 @@ fn blocking_duration_based() -> Result<()> {
 
     Ok(())
 }
+
+/// Test that a busy-polled round trip completes and reports client CPU use
+#[test]
+fn blocking_spin_wait_round_trip() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::TcpSocket],
+        one_way: false,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        spin_wait: Some(SpinWait::Pause),
+        msg_count: 32,
+        message_size: 64,
+        host: "127.0.0.1".to_string(),
+        port: 22500,
+        percentiles: vec![50.0, 99.0],
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::TcpSocket, args.clone());
+
+    let results = runner.run(None)?;
+
+    let latency = results.round_trip_results.unwrap().latency.unwrap();
+    assert_eq!(latency.total_samples, 32);
+    assert_eq!(results.test_config.spin_wait, Some(SpinWait::Pause));
+    let usage = results
+        .client_cpu
+        .expect("client CPU usage should be measured");
+    assert!(usage.cpu_time_ns > 0);
+    assert!(usage.wall_time_ns >= usage.cpu_time_ns / 2);
+
+    Ok(())
+}
+
+/// Test that prioritized messages report latency for each priority
+#[cfg(unix)]
+#[test]
+fn blocking_priorities_report_per_priority_latency() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::UnixDomainSocket],
+        one_way: true,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        priorities: vec![0, 5],
+        msg_count: 32,
+        message_size: 64,
+        percentiles: vec![50.0, 99.0],
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
+
+    let results = runner.run(None)?;
+
+    assert_eq!(results.test_config.priorities, [0, 5]);
+    for metrics in [results.one_way_results, results.round_trip_results] {
+        let per_priority: Vec<(u8, usize)> = metrics
+            .unwrap()
+            .priority_latency
+            .iter()
+            .map(|p| (p.key.0, p.latency.total_samples))
+            .collect();
+        assert_eq!(per_priority, [(0, 16), (5, 16)]);
+    }
+
+    Ok(())
+}
+
+/// Test that round trips are split into phases using the server's timestamps
+#[cfg(unix)]
+#[test]
+fn blocking_round_trip_reports_phase_latency() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::UnixDomainSocket],
+        one_way: false,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        msg_count: 32,
+        message_size: 64,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
+
+    let results = runner.run(None)?;
+
+    let metrics = results.round_trip_results.unwrap();
+    let phases: Vec<(RoundTripPhase, usize)> = metrics
+        .phase_latency
+        .iter()
+        .map(|p| (p.key, p.latency.total_samples))
+        .collect();
+    assert_eq!(
+        phases,
+        [
+            (RoundTripPhase::RequestTransit, 32),
+            (RoundTripPhase::ServerProcessing, 32),
+            (RoundTripPhase::ReplyTransit, 32)
+        ]
+    );
+    // Each phase is part of the whole round trip
+    let round_trip_max = metrics.latency.unwrap().max_ns;
+    assert!(metrics
+        .phase_latency
+        .iter()
+        .all(|p| p.latency.max_ns <= round_trip_max));
+
+    Ok(())
+}
+
+/// Test that --compare-pooling alternates warm and cold requests and reports
+/// both distributions
+#[cfg(unix)]
+#[test]
+fn blocking_compare_pooling_reports_warm_and_cold() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::UnixDomainSocket],
+        one_way: false,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        msg_count: 32,
+        message_size: 64,
+        compare_pooling: true,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
+
+    let results = runner.run(None)?;
+
+    let metrics = results.round_trip_results.unwrap();
+    let connections: Vec<(ConnectionReuse, usize)> = metrics
+        .connection_latency
+        .iter()
+        .map(|c| (c.key, c.latency.total_samples))
+        .collect();
+    assert_eq!(
+        connections,
+        [(ConnectionReuse::Warm, 16), (ConnectionReuse::Cold, 16)]
+    );
+    assert_eq!(metrics.latency.unwrap().total_samples, 32);
+
+    Ok(())
+}
+
+/// Test that --server-rate holds round trips to the server's pace
+#[cfg(unix)]
+#[test]
+fn blocking_server_rate_paces_round_trips() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::UnixDomainSocket],
+        one_way: false,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        msg_count: 20,
+        message_size: 64,
+        server_rate: Some(200),
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
+
+    let results = runner.run(None)?;
+
+    assert_eq!(results.test_config.server_rate, Some(200));
+    // Every round trip after the first waits for a 5ms consumption slot
+    let latency = results.round_trip_results.unwrap().latency.unwrap();
+    assert!(
+        latency.median_ns >= 4_000_000.0,
+        "median round trip {}ns is faster than the server's pace",
+        latency.median_ns
+    );
+
+    Ok(())
+}
+
+/// Test that --queue-depth-interval reports a queue the server drains too
+/// slowly as full
+#[cfg(target_os = "linux")]
+#[test]
+fn blocking_queue_depth_sampling_sees_a_full_queue() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::PosixMessageQueue],
+        one_way: true,
+        round_trip: false,
+        warmup_iterations: 0,
+        blocking: true,
+        msg_count: 100,
+        message_size: 64,
+        server_rate: Some(200),
+        queue_depth_interval: Some(std::time::Duration::from_millis(1)),
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner =
+        BlockingBenchmarkRunner::new(config, IpcMechanism::PosixMessageQueue, args.clone());
+
+    let results = runner.run(None)?;
+
+    let report = results.one_way_results.unwrap().queue_depth.unwrap();
+    assert!(report.samples > 0);
+    assert_eq!(Some(report.max.messages), report.capacity.messages);
+    assert!(report.full_percent > 0.0);
+
+    Ok(())
+}
+
+/// Test that --parallel-mechanisms runs a group together and marks each
+/// result with the mechanisms it ran alongside
+#[cfg(target_os = "linux")]
+#[test]
+fn parallel_mechanisms_mark_concurrent_results() -> Result<()> {
+    // Each mechanism in the group needs a server and a client core
+    if core_affinity::get_core_ids().map_or(0, |ids| ids.len()) < 4 {
+        return Ok(());
+    }
+
+    let dir = tempfile::tempdir()?;
+    let output = dir.path().join("results.json");
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_ipc-benchmark"))
+        .args(["-m", "uds", "pmq", "-i", "200", "-w", "0", "--round-trip"])
+        .args(["--parallel-mechanisms", "2", "--quiet", "-o"])
+        .arg(&output)
+        .arg("--log-file")
+        .arg(dir.path().join("run.log"))
+        .status()?;
+    assert!(status.success());
+
+    let results: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output)?)?;
+    let results = results["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[0]["test_config"]["concurrent_with"],
+        serde_json::json!(["PosixMessageQueue"])
+    );
+    assert_eq!(
+        results[1]["test_config"]["concurrent_with"],
+        serde_json::json!(["UnixDomainSocket"])
+    );
+
+    Ok(())
+}
+
+/// Test that --idle-gap pauses before every measured message in blocking
+/// mode, whether or not an unmeasured canary goes first
+#[cfg(unix)]
+#[test]
+fn blocking_idle_gap_is_applied() -> Result<()> {
+    let msg_count = 4;
+    let idle_gap = std::time::Duration::from_millis(25);
+
+    for include_first_message in [true, false] {
+        let args = Args {
+            mechanisms: vec![IpcMechanism::UnixDomainSocket],
+            one_way: false,
+            round_trip: true,
+            warmup_iterations: 0,
+            blocking: true,
+            msg_count,
+            message_size: 64,
+            idle_gap: Some(idle_gap),
+            include_first_message,
+            ..Default::default()
+        };
+
+        let config = BenchmarkConfig::from_args(&args)?;
+        let runner =
+            BlockingBenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
+
+        let start_time = std::time::Instant::now();
+        let results = runner.run(None)?;
+
+        assert!(start_time.elapsed() >= idle_gap * msg_count as u32);
+        let latency = results.round_trip_results.unwrap().latency.unwrap();
+        assert_eq!(latency.total_samples, msg_count);
+        assert!(latency.max_ns < idle_gap.as_nanos() as u64);
+    }
+
+    Ok(())
+}
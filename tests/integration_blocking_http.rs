@@ -0,0 +1,61 @@
+//! Integration tests for the HTTP loopback transport in blocking mode
+//!
+//! These tests verify that HTTP/1.1 and HTTP/2 requests and gRPC calls
+//! carry one-way and round-trip messages end-to-end with a spawned server
+//! process.
+
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use anyhow::Result;
+use ipc_benchmark::{
+    cli::{Args, HttpVersion},
+    BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism,
+};
+
+fn run(mechanism: IpcMechanism, http_version: HttpVersion, port: u16) -> Result<()> {
+    let args = Args {
+        mechanisms: vec![mechanism],
+        one_way: true,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        concurrency: 1,
+        msg_count: 64,
+        message_size: 256,
+        host: "127.0.0.1".to_string(),
+        port,
+        http_version,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, mechanism, args.clone());
+
+    let results = runner.run(None)?;
+    if mechanism == IpcMechanism::Http {
+        assert_eq!(results.test_config.http_version, Some(http_version));
+    }
+    for metrics in [results.one_way_results, results.round_trip_results] {
+        let latency = metrics.expect("results").latency.expect("latency");
+        assert_eq!(latency.total_samples, 64);
+    }
+    Ok(())
+}
+
+/// Verify HTTP/1.1 one-way and round-trip tests deliver every message
+#[test]
+fn http1_blocking_one_way_and_round_trip() -> Result<()> {
+    run(IpcMechanism::Http, HttpVersion::H1, 21200)
+}
+
+/// Verify HTTP/2 one-way and round-trip tests deliver every message
+#[test]
+fn http2_blocking_one_way_and_round_trip() -> Result<()> {
+    run(IpcMechanism::Http, HttpVersion::H2, 21201)
+}
+
+/// Verify gRPC one-way and round-trip tests deliver every message
+#[test]
+fn grpc_blocking_one_way_and_round_trip() -> Result<()> {
+    run(IpcMechanism::Grpc, HttpVersion::default(), 21202)
+}
@@ -0,0 +1,37 @@
+//! Integration tests for the file + inotify transport in blocking mode
+//!
+//! These tests verify that the file + inotify spool transport works end-to-end
+//! with a spawned server process. They are Linux-specific.
+
+#![cfg(target_os = "linux")] // inotify is Linux-only
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use anyhow::Result;
+use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
+
+/// Verify inotify one-way and round-trip tests deliver every message
+#[test]
+fn inotify_blocking_one_way_and_round_trip() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::Inotify],
+        one_way: true,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        concurrency: 1,
+        msg_count: 64,
+        message_size: 256,
+        shared_memory_name: Some("ipc_test_blocking_inotify".to_string()),
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::Inotify, args.clone());
+
+    let results = runner.run(None)?;
+    for metrics in [results.one_way_results, results.round_trip_results] {
+        let latency = metrics.expect("results").latency.expect("latency");
+        assert_eq!(latency.total_samples, 64);
+    }
+    Ok(())
+}
@@ -4,6 +4,7 @@
 //! blocking mode with spawned server processes. They are Linux-specific.
 
 #![cfg(target_os = "linux")] // POSIX message queues are Linux-only
+#![cfg(feature = "cli")] // drives the runners or the binary
 
 use anyhow::Result;
 use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
@@ -115,6 +116,44 @@ fn pmq_blocking_with_priority() -> Result<()> {
     Ok(())
 }
 
+/// Test that --producers sends from several processes into one queue and
+/// reports each producer's delivery
+#[test]
+fn pmq_blocking_multiple_producers() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::PosixMessageQueue],
+        one_way: true,
+        round_trip: false,
+        warmup_iterations: 0,
+        blocking: true,
+        msg_count: 50,
+        message_size: 64,
+        buffer_size: Some(1024),
+        producers: 3,
+        priorities: vec![0, 7],
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner =
+        BlockingBenchmarkRunner::new(config, IpcMechanism::PosixMessageQueue, args.clone());
+
+    let results = runner.run(None)?;
+
+    assert_eq!(results.test_config.producers, Some(3));
+    let one_way = results.one_way_results.unwrap();
+    assert_eq!(one_way.throughput.total_messages, 150);
+    let report = one_way.producers.unwrap();
+    let delivered: Vec<(u8, u8, usize)> = report
+        .producers
+        .iter()
+        .map(|p| (p.producer, p.priority, p.messages))
+        .collect();
+    assert_eq!(delivered, [(0, 0, 50), (1, 7, 50), (2, 0, 50)]);
+    assert!(report.fairness_index > 0.0 && report.fairness_index <= 1.0);
+    Ok(())
+}
+
 /// Minimal PMQ blocking server spawn smoke test
 #[test]
 fn pmq_blocking_server_ready_smoke() -> Result<()> {
@@ -137,11 +176,11 @@ fn pmq_blocking_server_ready_smoke() -> Result<()> {
         BlockingBenchmarkRunner::new(config, IpcMechanism::PosixMessageQueue, args.clone());
 
     let transport_config = runner.create_transport_config_internal(&args)?;
-    let (mut child, _reader) = runner.spawn_server_process(&transport_config)?;
+    let mut server = runner.spawn_server_process(&transport_config)?;
 
     // Give server a moment to start (blocking sleep)
     std::thread::sleep(std::time::Duration::from_millis(100));
 
-    let _ = child.kill();
+    let _ = server.kill();
     Ok(())
 }
@@ -3,6 +3,8 @@
 //! These tests verify that shared memory transport works correctly in blocking
 //! mode with spawned server processes.
 
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use anyhow::Result;
 use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
 
@@ -103,11 +105,44 @@ fn shm_blocking_server_ready_smoke() -> Result<()> {
     let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::SharedMemory, args.clone());
 
     let transport_config = runner.create_transport_config_internal(&args)?;
-    let (mut child, _reader) = runner.spawn_server_process(&transport_config)?;
+    let mut server = runner.spawn_server_process(&transport_config)?;
 
     // Give server a moment to start (blocking sleep)
     std::thread::sleep(std::time::Duration::from_millis(100));
 
-    let _ = child.kill();
+    let _ = server.kill();
+    Ok(())
+}
+
+/// The named semaphore variant delivers every message and is recorded in
+/// the results
+#[cfg(target_os = "linux")]
+#[test]
+fn shm_blocking_semaphore_wait() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::SharedMemory],
+        one_way: true,
+        round_trip: false,
+        warmup_iterations: 0,
+        blocking: true,
+        msg_count: 200,
+        message_size: 128,
+        shared_memory_name: Some("ipc_test_blocking_shm_sem".to_string()),
+        shm_wait: ipc_benchmark::cli::ShmWait::Semaphore,
+        // A small ring so the sender also waits on the space semaphore
+        shm_capacity_messages: Some(4),
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::SharedMemory, args.clone());
+
+    let results = runner.run(None)?;
+    assert_eq!(
+        results.test_config.shm_wait,
+        Some(ipc_benchmark::cli::ShmWait::Semaphore)
+    );
+    let one_way = results.one_way_results.expect("one-way results");
+    assert_eq!(one_way.latency.unwrap().total_samples, 200);
     Ok(())
 }
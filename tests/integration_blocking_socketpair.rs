@@ -0,0 +1,37 @@
+//! Integration tests for the socketpair transport in blocking mode
+//!
+//! These tests verify that a server spawned with an inherited socketpair
+//! end exchanges messages with the client without any socket file. They are
+//! Unix-specific.
+
+#![cfg(unix)] // socketpair() and fd inheritance are Unix-only
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use anyhow::Result;
+use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
+
+/// Verify socketpair one-way and round-trip tests deliver every message
+#[test]
+fn socketpair_blocking_one_way_and_round_trip() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::Socketpair],
+        one_way: true,
+        round_trip: true,
+        warmup_iterations: 10,
+        blocking: true,
+        concurrency: 1,
+        msg_count: 64,
+        message_size: 256,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::Socketpair, args.clone());
+
+    let results = runner.run(None)?;
+    for metrics in [results.one_way_results, results.round_trip_results] {
+        let latency = metrics.expect("results").latency.expect("latency");
+        assert_eq!(latency.total_samples, 64);
+    }
+    Ok(())
+}
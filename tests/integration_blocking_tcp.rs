@@ -4,8 +4,13 @@
 //! mode with spawned server processes. They mirror the async TCP tests but
 //! use pure blocking I/O operations.
 
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use anyhow::Result;
-use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
+use ipc_benchmark::{
+    cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism, PayloadGenerator,
+};
+use std::sync::Arc;
 
 /// Verify TCP round-trip works end-to-end in blocking mode with a spawned
 /// server process.
@@ -35,7 +40,16 @@ fn tcp_round_trip_blocking_smoke() -> Result<()> {
     let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::TcpSocket, args.clone());
 
     // Run blocking benchmark (blocks until complete - no .await)
-    let _results = runner.run(None)?;
+    let results = runner.run(None)?;
+
+    // The server reports its side of the test back over its stdout pipe
+    let server = results
+        .round_trip_results
+        .and_then(|metrics| metrics.server)
+        .expect("server report");
+    assert_eq!(server.received, 32);
+    assert_eq!(server.send_errors, 0);
+    assert_eq!(server.receive_latency.expect("latency").total_samples, 32);
 
     Ok(())
 }
@@ -110,11 +124,64 @@ fn tcp_blocking_server_ready_smoke() -> Result<()> {
     let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::TcpSocket, args.clone());
 
     let transport_config = runner.create_transport_config_internal(&args)?;
-    let (mut child, _reader) = runner.spawn_server_process(&transport_config)?;
+    let mut server = runner.spawn_server_process(&transport_config)?;
+
+    // The server binds an ephemeral port and reports it once listening
+    let endpoint = server.wait_ready(&transport_config)?;
+    assert_ne!(endpoint.port, 0);
+    assert_eq!(server.endpoint().map(|c| c.port), Some(endpoint.port));
 
-    // Give server a moment to start (blocking sleep)
-    std::thread::sleep(std::time::Duration::from_millis(100));
+    server.kill()?;
+    Ok(())
+}
 
-    let _ = child.kill();
+/// Payloads of 16 to 128 bytes, growing with the message ID
+struct GrowingPayload;
+
+impl PayloadGenerator for GrowingPayload {
+    fn size(&self, id: u64) -> usize {
+        16 + (id as usize % 8) * 16
+    }
+
+    fn fill(&self, id: u64, payload: &mut [u8]) {
+        payload.fill(id as u8);
+    }
+}
+
+/// Verify that a library-supplied payload generator drives the messages
+/// sent, and that payloads larger than the message size are rejected
+#[test]
+fn tcp_blocking_payload_generator() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::TcpSocket],
+        one_way: false,
+        round_trip: true,
+        warmup_iterations: 0,
+        blocking: true,
+        host: "127.0.0.1".to_string(),
+        port: 21030,
+        msg_count: 32,
+        message_size: 128,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::TcpSocket, args.clone())
+        .with_payload_generator(Arc::new(GrowingPayload));
+    let results = runner.run(None)?;
+    let metrics = results.round_trip_results.expect("round-trip results");
+    assert_eq!(metrics.latency.expect("latency").total_samples, 32);
+
+    let small = Args {
+        message_size: 64,
+        ..args
+    };
+    let config = BenchmarkConfig::from_args(&small)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::TcpSocket, small.clone())
+        .with_payload_generator(Arc::new(GrowingPayload));
+    let error = runner
+        .run(None)
+        .expect_err("payload larger than the message size");
+    assert!(format!("{:#}", error).contains("80-byte payload for message 4"));
     Ok(())
 }
@@ -0,0 +1,47 @@
+//! Integration tests for the UDP multicast transport in blocking mode
+//!
+//! These tests verify that every subscriber process receives the published
+//! messages and that delivery is reported per subscriber. They are
+//! Linux-specific.
+
+#![cfg(target_os = "linux")] // the multicast transport is Linux-only
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use anyhow::Result;
+use ipc_benchmark::{cli::Args, BenchmarkConfig, BlockingBenchmarkRunner, IpcMechanism};
+
+/// Verify that two subscribers each report their own delivery
+#[test]
+fn udp_multicast_blocking_two_subscribers() -> Result<()> {
+    let args = Args {
+        mechanisms: vec![IpcMechanism::UdpMulticast],
+        one_way: true,
+        warmup_iterations: 0,
+        blocking: true,
+        concurrency: 1,
+        msg_count: 64,
+        message_size: 256,
+        host: "127.0.0.1".to_string(),
+        subscribers: 2,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::UdpMulticast, args.clone());
+
+    let results = runner.run(None)?;
+    assert_eq!(results.test_config.subscribers, Some(2));
+    let metrics = results.one_way_results.expect("one-way results");
+    let report = metrics.subscribers.expect("subscriber report");
+    assert_eq!(report.sent, 64);
+    assert_eq!(report.subscribers.len(), 2);
+    // Loopback multicast at this rate should lose next to nothing
+    for subscriber in &report.subscribers {
+        assert_eq!(subscriber.received + subscriber.lost, 64);
+        assert!(subscriber.received > 0);
+    }
+    let latency = metrics.latency.expect("latency");
+    let received: usize = report.subscribers.iter().map(|s| s.received).sum();
+    assert_eq!(latency.total_samples, received);
+    Ok(())
+}
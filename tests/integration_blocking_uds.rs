@@ -5,6 +5,7 @@
 //! on Windows.
 
 #![cfg(unix)] // Unix Domain Sockets are Unix-only
+#![cfg(feature = "cli")] // drives the runners or the binary
 
 use anyhow::Result;
 use ipc_benchmark::{
@@ -107,11 +108,11 @@ fn uds_blocking_server_ready_smoke() -> Result<()> {
     let runner = BlockingBenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
 
     let transport_config = runner.create_transport_config_internal(&args)?;
-    let (mut child, _reader) = runner.spawn_server_process(&transport_config)?;
+    let mut server = runner.spawn_server_process(&transport_config)?;
 
     // Give server a moment to start (blocking sleep)
     std::thread::sleep(std::time::Duration::from_millis(100));
 
-    let _ = child.kill();
+    let _ = server.kill();
     Ok(())
 }
@@ -0,0 +1,60 @@
+//! Integration tests for the HTTP loopback transport in async mode
+//!
+//! These tests verify that HTTP/1.1 and HTTP/2 requests and gRPC calls
+//! carry one-way and round-trip messages end-to-end with a spawned server
+//! process.
+
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use anyhow::Result;
+use ipc_benchmark::{
+    cli::{Args, HttpVersion},
+    BenchmarkConfig, BenchmarkRunner, IpcMechanism,
+};
+
+async fn run(mechanism: IpcMechanism, http_version: HttpVersion, port: u16) -> Result<()> {
+    let args = Args {
+        mechanisms: vec![mechanism],
+        one_way: true,
+        round_trip: true,
+        warmup_iterations: 0,
+        concurrency: 1,
+        msg_count: 64,
+        message_size: 256,
+        host: "127.0.0.1".to_string(),
+        port,
+        http_version,
+        ..Default::default()
+    };
+
+    let config = BenchmarkConfig::from_args(&args)?;
+    let runner = BenchmarkRunner::new(config, mechanism, args.clone());
+
+    let results = runner.run(None).await?;
+    if mechanism == IpcMechanism::Http {
+        assert_eq!(results.test_config.http_version, Some(http_version));
+    }
+    for metrics in [results.one_way_results, results.round_trip_results] {
+        let latency = metrics.expect("results").latency.expect("latency");
+        assert_eq!(latency.total_samples, 64);
+    }
+    Ok(())
+}
+
+/// Verify HTTP/1.1 one-way and round-trip tests deliver every message
+#[tokio::test]
+async fn http1_one_way_and_round_trip() -> Result<()> {
+    run(IpcMechanism::Http, HttpVersion::H1, 21210).await
+}
+
+/// Verify HTTP/2 one-way and round-trip tests deliver every message
+#[tokio::test]
+async fn http2_one_way_and_round_trip() -> Result<()> {
+    run(IpcMechanism::Http, HttpVersion::H2, 21211).await
+}
+
+/// Verify gRPC one-way and round-trip tests deliver every message
+#[tokio::test]
+async fn grpc_one_way_and_round_trip() -> Result<()> {
+    run(IpcMechanism::Grpc, HttpVersion::default(), 21212).await
+}
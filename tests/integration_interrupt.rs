@@ -0,0 +1,154 @@
+//! Integration tests for interrupted runs.
+//!
+//! These tests start a long benchmark with the built binary, send it SIGTERM
+//! part way through and check that it still writes valid, partial results
+//! and shuts its server down, and that a campaign manifest lets a later run
+//! pick up where an earlier one stopped.
+
+#![cfg(unix)]
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+fn binary_path() -> std::path::PathBuf {
+    let mut path = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    path.push("ipc-benchmark");
+    path
+}
+
+/// Send SIGTERM to a 30 second run once it is measuring, and return the
+/// final results document it wrote.
+fn interrupt_run(extra_args: &[&str]) -> serde_json::Value {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("results.json");
+    let streaming = dir.path().join("stream.json");
+
+    let mut child = Command::new(binary_path())
+        .args([
+            "-m",
+            "uds",
+            "-d",
+            "30s",
+            "--round-trip",
+            "-w",
+            "0",
+            "--quiet",
+        ])
+        .args(extra_args)
+        .arg("-o")
+        .arg(&output)
+        .arg("--streaming-output-json")
+        .arg(&streaming)
+        .arg("--log-file")
+        .arg(dir.path().join("run.log"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn benchmark");
+
+    std::thread::sleep(Duration::from_secs(3));
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            panic!("benchmark did not stop after SIGTERM");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(
+        status.code(),
+        Some(1),
+        "interrupted runs exit with an error"
+    );
+
+    // The streaming file is closed as valid JSON
+    let stream: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&streaming).unwrap()).unwrap();
+    assert!(stream.is_object());
+
+    serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap()
+}
+
+#[test]
+fn sigterm_writes_partial_results_async() {
+    let results = interrupt_run(&[]);
+    let result = &results["results"][0];
+    assert_eq!(result["status"], "Aborted");
+    assert!(result["summary"]["total_messages_sent"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn sigterm_writes_partial_results_blocking() {
+    let results = interrupt_run(&["--blocking"]);
+    let result = &results["results"][0];
+    assert_eq!(result["status"], "Aborted");
+    assert!(result["summary"]["total_messages_sent"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn resume_skips_completed_cells() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("campaign.json");
+    let output = dir.path().join("results.json");
+    let run = |args: &[&str]| {
+        let status = Command::new(binary_path())
+            .args([
+                "-i",
+                "100",
+                "-w",
+                "0",
+                "--round-trip",
+                "--blocking",
+                "--quiet",
+            ])
+            .args(args)
+            .arg("--log-file")
+            .arg(dir.path().join("run.log"))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    run(&["-m", "uds", "--manifest", manifest.to_str().unwrap()]);
+    let first: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&manifest).unwrap()).unwrap();
+    let saved = &first["completed"][0]["results"];
+
+    run(&[
+        "-m",
+        "uds",
+        "tcp",
+        "--resume",
+        manifest.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+    ]);
+    let results: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    let results = results["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    // The completed cell is carried over rather than run again
+    assert_eq!(results[0]["timestamp"], saved["timestamp"]);
+    assert_eq!(results[1]["mechanism"], "TcpSocket");
+
+    let resumed: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&manifest).unwrap()).unwrap();
+    assert_eq!(resumed["run_id"], first["run_id"]);
+    assert_eq!(resumed["completed"].as_array().unwrap().len(), 2);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 #[cfg(target_os = "linux")]
 use anyhow::Result;
 #[cfg(target_os = "linux")]
@@ -0,0 +1,62 @@
+//! Integration test for scheduled repetitions of a suite.
+//!
+//! Runs a short blocking suite twice with `--repeat-every` and checks the
+//! JSON Lines history it appends to the output file.
+
+#![cfg(unix)]
+#![cfg(feature = "cli")] // drives the runners or the binary
+
+use std::process::{Command, Stdio};
+
+#[test]
+fn repeat_every_appends_each_repetition() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = dir.path().join("history.jsonl");
+    let status = Command::new(env!("CARGO_BIN_EXE_ipc-benchmark"))
+        .args([
+            "-m",
+            "uds",
+            "-i",
+            "100",
+            "-w",
+            "0",
+            "--round-trip",
+            "--blocking",
+            "--quiet",
+            "--repeat-every",
+            "1s",
+            "--until",
+            "2s",
+        ])
+        .arg("-o")
+        .arg(&history)
+        .arg("--log-file")
+        .arg(dir.path().join("run.log"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let content = std::fs::read_to_string(&history).unwrap();
+    let lines: Vec<serde_json::Value> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(line["repetition"], i + 1);
+        assert_eq!(line["exit_code"], 0);
+        assert_eq!(
+            line["results"]["results"][0]["mechanism"],
+            "UnixDomainSocket"
+        );
+    }
+    assert!(line_started(&lines[1]) > line_started(&lines[0]));
+    // Only the history is left behind
+    assert!(!dir.path().join("history.repetition.json").exists());
+}
+
+fn line_started(line: &serde_json::Value) -> String {
+    line["started"].as_str().unwrap().to_string()
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use os_pipe::pipe;
 use std::io::Read;
 #[cfg(unix)]
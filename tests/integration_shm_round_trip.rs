@@ -1,7 +1,11 @@
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use anyhow::Result;
 use ipc_benchmark::{cli::Args, BenchmarkConfig, BenchmarkRunner, IpcMechanism};
 
-/// Verify SHM round-trip works end-to-end with a spawned server process.
+/// Verify a SHM round-trip run completes against a spawned server process,
+/// skipping the round trips the one-way ring cannot carry instead of
+/// measuring the client reading back its own requests.
 #[tokio::test]
 async fn shm_round_trip_process_smoke() -> Result<()> {
     // Ensure a deterministic shared memory name
@@ -19,6 +23,7 @@ async fn shm_round_trip_process_smoke() -> Result<()> {
     let config = BenchmarkConfig::from_args(&args)?;
     let runner = BenchmarkRunner::new(config, IpcMechanism::SharedMemory, args.clone());
 
-    let _results = runner.run(None).await?;
+    let results = runner.run(None).await?;
+    assert!(results.round_trip_results.is_none());
     Ok(())
 }
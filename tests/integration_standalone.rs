@@ -4,6 +4,8 @@
 //! built binary, verifying end-to-end standalone mode across TCP and UDS
 //! mechanisms in both blocking and async modes.
 
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use std::process::{Command, Stdio};
 use std::time::Duration;
 
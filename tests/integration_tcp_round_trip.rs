@@ -1,3 +1,5 @@
+#![cfg(feature = "cli")] // drives the runners or the binary
+
 use anyhow::Result;
 use ipc_benchmark::{cli::Args, BenchmarkConfig, BenchmarkRunner, IpcMechanism};
 
@@ -47,8 +49,8 @@ async fn uds_server_ready_smoke() -> Result<()> {
     let config = BenchmarkConfig::from_args(&args)?;
     let runner = BenchmarkRunner::new(config, IpcMechanism::UnixDomainSocket, args.clone());
     let transport_config = runner.create_transport_config_internal(&args)?;
-    let (mut child, _reader) = runner.spawn_server_process(&transport_config)?;
+    let mut server = runner.spawn_server_process(&transport_config)?;
     tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-    let _ = child.kill();
+    let _ = server.kill();
     Ok(())
 }